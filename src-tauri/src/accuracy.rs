@@ -0,0 +1,214 @@
+//! 抽出したシーケンスと正解のシーケンスの精度比較
+//!
+//! 両方をフレーム単位の入力状態に展開し、最初の入力が揃うようにずらしてから1フレームずつ比較する。
+//! ボタンごとの適合率・再現率と方向の正解率を求め、モデルの改善を数値で確かめられるようにする。
+//! 比較するのは正解側のフレーム範囲のみ（録画前後の無入力区間は数えない）。
+
+use crate::types::InputFrame;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 最初の入力の位置から、さらにずれを探す範囲（フレーム）
+const MAX_ALIGN_OFFSET: i64 = 30;
+
+/// ずれを探すときに比較するフレーム数（最初の入力から）
+const ALIGN_WINDOW: usize = 600;
+
+/// ボタンごとの精度
+#[derive(Debug, Clone, Serialize)]
+pub struct ButtonAccuracy {
+    pub button: String,
+    pub true_positive: u32,
+    pub false_positive: u32,  // 抽出側だけ押されていたフレーム数
+    pub false_negative: u32,  // 正解側だけ押されていたフレーム数
+    pub precision: Option<f32>, // 抽出側で一度も押されていなければ None
+    pub recall: Option<f32>,    // 正解側で一度も押されていなければ None
+}
+
+/// 入力が食い違っている区間（正解側のフレーム番号、end_frame は含まない）
+#[derive(Debug, Clone, Serialize)]
+pub struct MismatchRange {
+    pub start_frame: u32,
+    pub end_frame: u32,
+    pub expected: String, // 区間の先頭での正解側の入力（例: "6A+B"）
+    pub actual: String,   // 区間の先頭での抽出側の入力
+}
+
+/// 精度比較の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct AccuracyReport {
+    pub compared_frames: u32,
+    pub offset: i64, // 抽出側のフレーム番号 = 正解側のフレーム番号 + offset
+    pub direction_accuracy: f32,
+    pub frame_accuracy: f32, // 方向・ボタンがすべて一致したフレームの割合
+    pub buttons: Vec<ButtonAccuracy>,
+    pub mismatches: Vec<MismatchRange>,
+}
+
+// 1フレームの入力状態（方向, 押されているボタン名）
+type FrameState<'a> = (u8, BTreeSet<&'a str>);
+
+static NEUTRAL: FrameState<'static> = (5, BTreeSet::new());
+
+// ステップ列をフレームごとの入力状態に展開する
+fn expand(frames: &[InputFrame]) -> Vec<FrameState<'_>> {
+    let mut states = Vec::with_capacity(frames.iter().map(|f| f.duration as usize).sum());
+    for frame in frames {
+        let pressed: BTreeSet<&str> = frame
+            .buttons
+            .iter()
+            .filter(|(_, &v)| v == 1)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        for _ in 0..frame.duration {
+            states.push((frame.direction, pressed.clone()));
+        }
+    }
+    states
+}
+
+fn first_input(states: &[FrameState]) -> Option<usize> {
+    states.iter().position(|state| *state != NEUTRAL)
+}
+
+// 範囲外は無入力として扱う
+fn state_at<'a, 'b>(states: &'b [FrameState<'a>], index: i64) -> &'b FrameState<'a> {
+    usize::try_from(index).ok().and_then(|i| states.get(i)).unwrap_or(&NEUTRAL)
+}
+
+fn label(state: &FrameState) -> String {
+    let buttons: Vec<&str> = state.1.iter().copied().collect();
+    format!("{}{}", state.0, buttons.join("+"))
+}
+
+// 最初の入力を揃えたずれの前後を探し、一致するフレームが最も多いずれを返す
+fn find_offset(reference: &[FrameState], extracted: &[FrameState]) -> i64 {
+    let (Some(ref_start), Some(ext_start)) = (first_input(reference), first_input(extracted)) else {
+        return 0;
+    };
+    let base = ext_start as i64 - ref_start as i64;
+    let window = ref_start..(ref_start + ALIGN_WINDOW).min(reference.len());
+    (base - MAX_ALIGN_OFFSET..=base + MAX_ALIGN_OFFSET)
+        // 一致数が同じなら base に近い方を選ぶ
+        .max_by_key(|&offset| {
+            let matched = window
+                .clone()
+                .filter(|&i| reference[i] == *state_at(extracted, i as i64 + offset))
+                .count();
+            (matched, -(offset - base).abs())
+        })
+        .unwrap_or(base)
+}
+
+fn ratio(numerator: u32, denominator: u32) -> Option<f32> {
+    (denominator > 0).then(|| numerator as f32 / denominator as f32)
+}
+
+/// 抽出したシーケンスを正解のシーケンスとフレーム単位で比較する
+pub fn compare_sequences_accuracy(extracted: &[InputFrame], reference: &[InputFrame]) -> AccuracyReport {
+    let extracted = expand(extracted);
+    let reference = expand(reference);
+    let offset = find_offset(&reference, &extracted);
+
+    let mut direction_matched = 0u32;
+    let mut frame_matched = 0u32;
+    // ボタン名 -> (TP, FP, FN)
+    let mut counts: BTreeMap<&str, (u32, u32, u32)> = BTreeMap::new();
+    let mut mismatches: Vec<MismatchRange> = Vec::new();
+
+    for (i, expected) in reference.iter().enumerate() {
+        let actual = state_at(&extracted, i as i64 + offset);
+        if expected.0 == actual.0 {
+            direction_matched += 1;
+        }
+        for button in expected.1.union(&actual.1) {
+            let entry = counts.entry(*button).or_default();
+            match (expected.1.contains(button), actual.1.contains(button)) {
+                (true, true) => entry.0 += 1,
+                (false, true) => entry.1 += 1,
+                _ => entry.2 += 1,
+            }
+        }
+
+        if expected == actual {
+            frame_matched += 1;
+            continue;
+        }
+        let frame = i as u32;
+        match mismatches.last_mut() {
+            Some(range) if range.end_frame == frame => range.end_frame += 1,
+            _ => mismatches.push(MismatchRange {
+                start_frame: frame,
+                end_frame: frame + 1,
+                expected: label(expected),
+                actual: label(actual),
+            }),
+        }
+    }
+
+    let compared_frames = reference.len() as u32;
+    let buttons = counts
+        .into_iter()
+        .map(|(button, (tp, fp, fn_))| ButtonAccuracy {
+            button: button.to_string(),
+            true_positive: tp,
+            false_positive: fp,
+            false_negative: fn_,
+            precision: ratio(tp, tp + fp),
+            recall: ratio(tp, tp + fn_),
+        })
+        .collect();
+
+    AccuracyReport {
+        compared_frames,
+        offset,
+        direction_accuracy: ratio(direction_matched, compared_frames).unwrap_or(1.0),
+        frame_accuracy: ratio(frame_matched, compared_frames).unwrap_or(1.0),
+        buttons,
+        mismatches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_after_leading_idle() {
+        let reference = vec![InputFrame::with_buttons(3, 6, &["A"]), InputFrame::with_buttons(5, 2, &[])];
+        let extracted = vec![
+            InputFrame::with_buttons(40, 5, &[]),
+            InputFrame::with_buttons(3, 6, &["A"]),
+            InputFrame::with_buttons(5, 2, &[]),
+            InputFrame::with_buttons(100, 5, &[]),
+        ];
+        let report = compare_sequences_accuracy(&extracted, &reference);
+        assert_eq!(report.offset, 40);
+        assert_eq!(report.compared_frames, 8);
+        assert_eq!(report.frame_accuracy, 1.0);
+        assert!(report.mismatches.is_empty());
+        assert_eq!(report.buttons[0].recall, Some(1.0));
+    }
+
+    #[test]
+    fn test_reports_button_errors_and_ranges() {
+        let reference = vec![InputFrame::with_buttons(4, 6, &["A"]), InputFrame::with_buttons(4, 5, &["B"])];
+        // B の最初の2フレームを取りこぼし、A が2フレーム長く残っている
+        let extracted = vec![InputFrame::with_buttons(6, 6, &["A"]), InputFrame::with_buttons(2, 5, &["B"])];
+        let report = compare_sequences_accuracy(&extracted, &reference);
+        assert_eq!(report.offset, 0);
+        assert_eq!(report.direction_accuracy, 6.0 / 8.0);
+
+        let a = report.buttons.iter().find(|b| b.button == "A").unwrap();
+        assert_eq!((a.true_positive, a.false_positive, a.false_negative), (4, 2, 0));
+        assert_eq!(a.recall, Some(1.0));
+        let b = report.buttons.iter().find(|b| b.button == "B").unwrap();
+        assert_eq!((b.true_positive, b.false_positive, b.false_negative), (2, 0, 2));
+        assert_eq!(b.precision, Some(1.0));
+
+        assert_eq!(report.mismatches.len(), 1);
+        let range = &report.mismatches[0];
+        assert_eq!((range.start_frame, range.end_frame), (4, 6));
+        assert_eq!((range.expected.as_str(), range.actual.as_str()), ("5B", "6A"));
+    }
+}
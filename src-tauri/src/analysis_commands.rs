@@ -3,8 +3,8 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-use crate::video::{FrameExtractor, FrameExtractorConfig};
-use crate::model::AppConfig;
+use crate::video::{FrameExtractor, FrameExtractorConfig, PreviewSession, TimeRange};
+use crate::model::{AppConfig, ModelRegistryEntry, RegionPreset, VideoBackend, VideoTransform};
 #[cfg(feature = "ml")]
 use crate::model::{load_metadata, ModelMetadata};
 
@@ -37,11 +37,6 @@ fn save_as_uncompressed_png<P: AsRef<std::path::Path>>(
     Ok(())
 }
 
-// GStreamer用のインポート
-use gstreamer as gst;
-use gstreamer::prelude::*;
-use gstreamer_app as gst_app;
-
 /// 解析範囲設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisRegion {
@@ -61,19 +56,68 @@ pub struct AnalysisRegion {
     pub video_width: u32,
     /// 動画の高さ
     pub video_height: u32,
+    /// 動画の回転・反転の補正
+    #[serde(default)]
+    pub video_transform: VideoTransform,
+    /// 複数の領域を同時に解析する場合の名前（"P1" / "P2" など。出力CSVのファイル名に付ける）
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl AnalysisRegion {
+    /// 抽出に使う領域（最下行のみ）
+    #[cfg(feature = "ml")]
+    pub fn to_indicator_region(&self) -> crate::analyzer::InputIndicatorRegion {
+        crate::analyzer::InputIndicatorRegion {
+            x: self.x,
+            y: self.y,
+            width: self.tile_width * self.columns,
+            height: self.tile_height,
+            rows: 1,
+            cols: self.columns,
+        }
+    }
+
+    /// 設定時と解像度が異なる動画に合わせて、座標とタイルの大きさを比率で補正する
+    ///
+    /// video_width / video_height は補正後（回転・反転を適用した後）の動画の解像度。
+    /// 設定時の解像度が記録されていない場合はそのまま返す。
+    #[cfg(feature = "ml")]
+    pub fn scaled_to(&self, video_width: u32, video_height: u32) -> Self {
+        if self.video_width == 0 || self.video_height == 0 {
+            return self.clone();
+        }
+        let scale_x = video_width as f64 / self.video_width as f64;
+        let scale_y = video_height as f64 / self.video_height as f64;
+        let scale = |value: u32, ratio: f64| (value as f64 * ratio).round() as u32;
+        Self {
+            x: scale(self.x, scale_x),
+            y: scale(self.y, scale_y),
+            tile_width: scale(self.tile_width, scale_x).max(1),
+            tile_height: scale(self.tile_height, scale_y).max(1),
+            video_width,
+            video_height,
+            ..self.clone()
+        }
+    }
+}
+
+// 保存済みの解析範囲の回転・反転の補正（解析範囲を受け取らないコマンド用）
+fn saved_transform() -> VideoTransform {
+    AppConfig::load_or_default().button_tile.video_transform
 }
 
-/// GStreamerが利用可能かチェック
+/// 選択中の動画バックエンド（GStreamer / ffmpeg）が利用可能かチェック
 #[tauri::command]
 pub fn check_gstreamer_available() -> Result<(), String> {
-    gst::init().map_err(|e| format!("GStreamerが利用できません: {}", e))?;
-    Ok(())
+    crate::video::check_backend()
+        .map_err(|e| format!("{:?}が利用できません: {}", crate::video::backend(), e))
 }
 
 /// 動画情報取得
 #[tauri::command]
 pub fn get_video_info(video_path: String) -> Result<VideoInfoResponse, String> {
-    let info = FrameExtractor::get_video_info(&video_path)
+    let info = FrameExtractor::get_video_info(&video_path, saved_transform())
         .map_err(|e| format!("動画情報の取得に失敗: {}", e))?;
     
     Ok(VideoInfoResponse {
@@ -99,12 +143,15 @@ pub fn save_analysis_region(region: AnalysisRegion) -> Result<String, String> {
     
     config.button_tile.x = region.x;
     config.button_tile.y = region.y;
-    config.button_tile.tile_size = region.tile_width; // 正方形を想定
+    config.button_tile.tile_size = region.tile_width;
+    config.button_tile.tile_height = region.tile_height;
     config.button_tile.columns_per_row = region.columns;
     
     // 動画解像度も保存
     config.button_tile.source_video_width = region.video_width;
     config.button_tile.source_video_height = region.video_height;
+
+    config.button_tile.video_transform = region.video_transform;
     
     config.save_default()
         .map_err(|e| format!("設定の保存に失敗: {}", e))?;
@@ -116,33 +163,111 @@ pub fn save_analysis_region(region: AnalysisRegion) -> Result<String, String> {
 #[tauri::command]
 pub fn load_analysis_region() -> Result<AnalysisRegion, String> {
     let config = AppConfig::load_or_default();
+    let (tile_width, tile_height) = config.button_tile.tile_dims();
     
     Ok(AnalysisRegion {
         x: config.button_tile.x,
         y: config.button_tile.y,
-        tile_width: config.button_tile.tile_size,
-        tile_height: config.button_tile.tile_size,
+        tile_width,
+        tile_height,
         columns: config.button_tile.columns_per_row,
         rows: 1, // 最下行のみ解析
         video_width: config.button_tile.source_video_width,
         video_height: config.button_tile.source_video_height,
+        video_transform: config.button_tile.video_transform,
+        label: None,
     })
 }
 
+/// 現在の解析範囲設定をプリセットファイルとして書き出す
+#[tauri::command]
+pub fn export_region_preset(
+    path: String,
+    name: String,
+    game: Option<String>,
+    notes: Option<String>,
+    button_labels: Vec<String>,
+    model_file: Option<String>,
+) -> Result<RegionPreset, String> {
+    let config = AppConfig::load_or_default();
+
+    let mut preset = RegionPreset::new(name, config.button_tile.clone(), button_labels);
+    preset.game = game;
+    preset.notes = notes;
+    preset.model_file = model_file;
+
+    preset.save(&path)
+        .map_err(|e| format!("プリセットの保存に失敗: {}", e))?;
+
+    Ok(preset)
+}
+
+/// プリセットファイルを読み込み、解析範囲設定に反映する
+///
+/// apply が false の場合は内容の確認のみで設定は変更しない
+#[tauri::command]
+pub fn import_region_preset(path: String, apply: bool) -> Result<RegionPreset, String> {
+    let preset = RegionPreset::load(&path)
+        .map_err(|e| format!("プリセットの読み込みに失敗: {}", e))?;
+
+    if apply {
+        let mut config = AppConfig::load_or_default();
+        config.button_tile = preset.button_tile.clone();
+        config.save_default()
+            .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+    }
+
+    Ok(preset)
+}
+
+/// フレーム抽出に使うバックエンドを切り替え、設定ファイルに保存
+#[tauri::command]
+pub fn set_video_backend(backend: VideoBackend) -> Result<(), String> {
+    crate::video::set_backend(backend)
+        .map_err(|e| format!("バックエンドの切り替えに失敗: {}", e))?;
+
+    let mut config = AppConfig::load_or_default();
+    config.video_backend = backend;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_video_backend() -> VideoBackend {
+    crate::video::backend()
+}
+
+/// 接続されているキャプチャデバイス（ライブ解析の入力）の一覧
+#[tauri::command]
+pub fn list_capture_devices() -> Result<Vec<crate::video::CaptureDeviceInfo>, String> {
+    crate::video::list_capture_devices()
+        .map_err(|e| format!("キャプチャデバイスの取得に失敗: {}", e))
+}
+
 /// 動画から特定フレームを抽出してプレビュー用に返す
 #[tauri::command]
 pub fn extract_preview_frame(
     video_path: String,
     frame_number: u32,
 ) -> Result<String, String> {
-    use image::ImageEncoder;
-    
-    // メモリ上でフレームを抽出（ファイル保存なし）
-    let config = FrameExtractorConfig::default();
+    // キーフレームへシークしてメモリ上でフレームを抽出（ファイル保存なし）
+    let config = FrameExtractorConfig {
+        transform: saved_transform(),
+        ..FrameExtractorConfig::default()
+    };
     let extractor = FrameExtractor::new(config);
     let rgb_image = extractor.extract_frame_to_memory(&video_path, frame_number)
         .map_err(|e| format!("フレーム抽出に失敗: {}", e))?;
     
+    to_png_data_url(&rgb_image)
+}
+
+/// フレーム画像をPNGのdata URLに変換
+fn to_png_data_url(rgb_image: &image::RgbImage) -> Result<String, String> {
+    use image::ImageEncoder;
+
     // PNG形式でメモリ上にエンコード
     let mut png_data = Vec::new();
     let encoder = image::codecs::png::PngEncoder::new(&mut png_data);
@@ -159,24 +284,240 @@ pub fn extract_preview_frame(
     Ok(format!("data:image/png;base64,{}", base64_data))
 }
 
+/// プレビューセッションの情報（open_preview の戻り値）
+#[derive(Debug, Serialize)]
+pub struct PreviewInfo {
+    pub session_id: String,
+    pub width: i32,
+    pub height: i32,
+    pub fps: f64,
+    pub total_frames: u32,
+}
+
+static NEXT_PREVIEW_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// 開いているプレビュー（開いたウィンドウが閉じられると解放する）
+pub struct OpenPreview {
+    window: String,
+    session: std::sync::Arc<std::sync::Mutex<PreviewSession>>,
+}
+
+/// タイムラインのスクラブ用に動画を開く（close_preview か、開いたウィンドウを閉じるまでパイプラインを保持する）
+#[tauri::command]
+pub async fn open_preview(window: tauri::Window, video_path: String) -> Result<PreviewInfo, String> {
+    use tauri::Manager;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let session = PreviewSession::open(&video_path, saved_transform())
+            .map_err(|e| format!("プレビューの作成に失敗: {}", e))?;
+        let session_id = format!(
+            "preview-{}",
+            NEXT_PREVIEW_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+        );
+        let info = PreviewInfo {
+            session_id: session_id.clone(),
+            width: session.info().width,
+            height: session.info().height,
+            fps: session.info().fps,
+            total_frames: session.total_frames(),
+        };
+        println!("[プレビュー] 開始: {} ({}, {})", session_id, video_path, window.label());
+        let preview = OpenPreview {
+            window: window.label().to_string(),
+            session: std::sync::Arc::new(std::sync::Mutex::new(session)),
+        };
+        window.state::<crate::AppState>().preview_sessions.lock().unwrap().insert(session_id, preview);
+        Ok(info)
+    })
+    .await
+    .map_err(|e| format!("プレビューの作成スレッドの実行に失敗: {}", e))?
+}
+
+/// サムネイル1枚（generate_thumbnails の戻り値）
+#[derive(Debug, Serialize)]
+pub struct ThumbnailResponse {
+    pub frame_number: u32,
+    /// PNGのdata URL
+    pub data_url: String,
+}
+
+/// 解析範囲の設定画面用に、動画全体から等間隔に count 枚のサムネイルを作成する
+///
+/// 1本のパイプラインでシークしながら縮小したフレームを取り出す（width の既定値は 160）
+#[tauri::command]
+pub fn generate_thumbnails(
+    video_path: String,
+    count: u32,
+    width: Option<u32>,
+) -> Result<Vec<ThumbnailResponse>, String> {
+    let thumbnails = crate::video::generate_thumbnails(&video_path, saved_transform(), count, width.unwrap_or(160))
+        .map_err(|e| format!("サムネイルの作成に失敗: {}", e))?;
+    thumbnails
+        .iter()
+        .map(|thumbnail| {
+            Ok(ThumbnailResponse {
+                frame_number: thumbnail.frame_number,
+                data_url: to_png_data_url(&thumbnail.image)?,
+            })
+        })
+        .collect()
+}
+
+/// プレビューセッション内でシークしてフレームを取得（PNGのdata URLで返す）
+#[tauri::command]
+pub async fn preview_frame(
+    state: tauri::State<'_, crate::AppState>,
+    session_id: String,
+    frame_number: u32,
+) -> Result<String, String> {
+    // デコード中は他のセッションを待たせないよう、全体のロックはすぐに離す
+    let session = state.preview_sessions.lock().unwrap()
+        .get(&session_id)
+        .map(|preview| preview.session.clone())
+        .ok_or_else(|| format!("プレビューが開かれていません: {}", session_id))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let rgb_image = session.lock().unwrap().frame(frame_number)
+            .map_err(|e| format!("フレーム抽出に失敗: {}", e))?;
+        to_png_data_url(&rgb_image)
+    })
+    .await
+    .map_err(|e| format!("フレーム抽出スレッドの実行に失敗: {}", e))?
+}
+
+/// プレビューセッションを閉じてパイプラインを解放
+#[tauri::command]
+pub fn close_preview(state: tauri::State<'_, crate::AppState>, session_id: String) {
+    if state.preview_sessions.lock().unwrap().remove(&session_id).is_some() {
+        println!("[プレビュー] 終了: {}", session_id);
+    }
+}
+
+/// 閉じられたウィンドウが開いていたプレビューセッションをすべて解放
+pub fn close_window_previews(state: &crate::AppState, window_label: &str) {
+    state.preview_sessions.lock().unwrap().retain(|session_id, preview| {
+        let keep = preview.window != window_label;
+        if !keep {
+            println!("[プレビュー] ウィンドウが閉じられたため終了: {}", session_id);
+        }
+        keep
+    });
+}
+
+/// 解析範囲のグリッドを動画フレーム上に描画してPNGとして保存
+///
+/// ドキュメント用途や、同じゲームのユーザー間で領域設定を共有するために使用する
+#[tauri::command]
+pub fn export_region_overlay(
+    video_path: String,
+    frame_number: u32,
+    region: AnalysisRegion,
+    output_path: String,
+) -> Result<String, String> {
+    let config = FrameExtractorConfig {
+        transform: region.video_transform,
+        ..FrameExtractorConfig::default()
+    };
+    let extractor = FrameExtractor::new(config);
+    let mut frame = extractor.extract_frame_to_memory(&video_path, frame_number)
+        .map_err(|e| format!("フレーム抽出に失敗: {}", e))?;
+
+    // 領域全体の外枠（赤）と各タイルの枠（緑）を描画
+    let total_width = region.tile_width * region.columns;
+    let total_height = region.tile_height * region.rows;
+    for row in 0..region.rows {
+        for col in 0..region.columns {
+            draw_rect_outline(
+                &mut frame,
+                region.x + col * region.tile_width,
+                region.y + row * region.tile_height,
+                region.tile_width,
+                region.tile_height,
+                image::Rgb([0, 255, 0]),
+                1,
+            );
+        }
+    }
+    draw_rect_outline(
+        &mut frame,
+        region.x,
+        region.y,
+        total_width,
+        total_height,
+        image::Rgb([255, 0, 0]),
+        2,
+    );
+
+    let output = PathBuf::from(&output_path);
+    if let Some(parent) = output.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("出力ディレクトリの作成に失敗: {}", e))?;
+        }
+    }
+
+    frame.save_with_format(&output, image::ImageFormat::Png)
+        .map_err(|e| format!("画像保存に失敗: {}", e))?;
+
+    Ok(output_path)
+}
+
+/// 矩形の枠線を描画（画像範囲外のピクセルは無視）
+fn draw_rect_outline(
+    img: &mut image::RgbImage,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    color: image::Rgb<u8>,
+    thickness: u32,
+) {
+    let (img_w, img_h) = img.dimensions();
+    if width == 0 || height == 0 || img_w == 0 || img_h == 0 {
+        return;
+    }
+    let mut put = |px: u32, py: u32| {
+        if px < img_w && py < img_h {
+            img.put_pixel(px, py, color);
+        }
+    };
+
+    // 座標は u32 の上限付近でも桁あふれしないよう飽和演算で求め、走査は画像内に限る
+    for t in 0..thickness.min(width).min(height) {
+        let left = x.saturating_add(t);
+        let top = y.saturating_add(t);
+        let right = x.saturating_add(width - 1).saturating_sub(t);
+        let bottom = y.saturating_add(height - 1).saturating_sub(t);
+        for px in left..=right.min(img_w - 1) {
+            put(px, top);
+            put(px, bottom);
+        }
+        for py in top..=bottom.min(img_h - 1) {
+            put(left, py);
+            put(right, py);
+        }
+    }
+}
+
 /// タイル抽出（学習データ生成用）
-/// AppSinkを使ってフレームから直接タイルを抽出（学習データ収集用）
+/// 領域全体をクロップしたフレームから直接タイルを抽出（学習データ収集用）
+/// start_sec / end_sec を指定するとその区間だけを処理する
+/// video_path には連番画像のフォルダも指定できる（image_sequence_fps で撮影されたものとして扱う。省略時は 60fps）
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn collect_training_data(
     video_path: String,
     output_dir: String,
     frame_interval: u32,
     region: AnalysisRegion,
+    start_sec: Option<f64>,
+    end_sec: Option<f64>,
+    image_sequence_fps: Option<f64>,
 ) -> Result<ExtractTilesResponse, String> {
     // validate frame_interval
     if frame_interval == 0 {
         return Err("frame_interval must be >= 1".to_string());
     }
-    use gstreamer_video as gst_video;
-    use image::{ImageBuffer, Rgb};
-    
-    // GStreamerの初期化
-    gst::init().map_err(|e| format!("GStreamer初期化失敗: {}", e))?;
+    let range = TimeRange::new(start_sec, end_sec).map_err(|e| e.to_string())?;
     
     // 出力ディレクトリを作成
     let output_path = PathBuf::from(&output_dir);
@@ -190,111 +531,48 @@ pub fn collect_training_data(
         .unwrap_or("video")
         .to_string();
     
-    // 動画情報を取得して videocrop のパラメータを計算
-    let info = FrameExtractor::get_video_info(&video_path)
-        .map_err(|e| format!("動画情報取得に失敗: {}", e))?;
-
-    let left = region.x as i32;
-    let top = region.y as i32;
-    let crop_w = region.tile_width * region.columns;
-    let crop_h = region.tile_height * region.rows;
-    let right = (info.width as i32) - (left + crop_w as i32);
-    let bottom = (info.height as i32) - (top + crop_h as i32);
-    let right = if right < 0 { 0 } else { right };
-    let bottom = if bottom < 0 { 0 } else { bottom };
-
-    // パイプラインを構築（事前に領域全体を videocrop で切り出す）
-    let pipeline = format!(
-        "filesrc location=\"{}\" ! decodebin ! videoconvert ! videocrop left={} right={} top={} bottom={} ! video/x-raw,format=RGB ! appsink name=sink",
-        video_path.replace("\\", "/"), left, right, top, bottom
-    );
+    // 事前に領域全体を切り出す
+    let crop_region = crate::analyzer::InputIndicatorRegion {
+        x: region.x,
+        y: region.y,
+        width: region.tile_width * region.columns,
+        height: region.tile_height * region.rows,
+        rows: region.rows,
+        cols: region.columns,
+    };
+
+    let extractor = FrameExtractor::new(FrameExtractorConfig {
+        frame_interval,
+        transform: region.video_transform,
+        image_sequence_fps: image_sequence_fps.unwrap_or(crate::video::DEFAULT_IMAGE_SEQUENCE_FPS),
+        ..FrameExtractorConfig::default()
+    });
 
-    let pipeline = gst::parse::launch(&pipeline)
-        .map_err(|e| format!("パイプライン構築失敗: {}", e))?;
-    let pipeline = pipeline
-        .dynamic_cast::<gst::Pipeline>()
-        .map_err(|_| "Pipeline型への変換失敗")?;
-    
-    // AppSinkを取得
-    let appsink = pipeline
-        .by_name("sink")
-        .ok_or("AppSinkが見つかりません")?
-        .dynamic_cast::<gst_app::AppSink>()
-        .map_err(|_| "AppSink型への変換失敗")?;
-    
-    // パイプラインを開始
-    pipeline
-        .set_state(gst::State::Playing)
-        .map_err(|e| format!("パイプライン開始失敗: {:?}", e))?;
-    
-    let mut frame_count = 0u32;
     let mut tile_count = 0usize;
     let mut extracted_frame_count = 0u32;
     
     // フレームを処理
-    loop {
-        let sample = match appsink.pull_sample() {
-            Ok(sample) => sample,
-            Err(_) => break, // EOSまたはエラーで終了
-        };
-        
-        // frame_intervalごとに処理
-        if frame_count % frame_interval == 0 {
-            let buffer = sample.buffer().ok_or("バッファ取得失敗")?;
-            let caps = sample.caps().ok_or("Caps取得失敗")?;
-            
-            let video_info = gst_video::VideoInfo::from_caps(caps)
-                .map_err(|e| format!("VideoInfo取得失敗: {:?}", e))?;
-            
-            let width = video_info.width() as u32;
-            let height = video_info.height() as u32;
-            
-            // バッファをマップ
-            let map = buffer.map_readable()
-                .map_err(|_| "バッファマップ失敗")?;
-            
-            let data = map.as_slice();
-            // タイルを切り出して保存
-            // 行のバイト幅（stride）を考慮
-            let stride_vals = video_info.stride();
-            let stride = if let Some(&s) = stride_vals.get(0) {
-                let s = if s < 0 { -s } else { s };
-                s as usize
-            } else {
-                (width as usize) * 3
-            };
+    extractor
+        .process_frames_sync_in_range(&video_path, Some(crop_region), range, |frame_img, _frame_num| {
+            let data = frame_img.as_raw();
+            let stride = frame_img.width() as usize * 3;
             for row in 0..region.rows {
                 for col in 0..region.columns {
-                    // videocrop により既に領域全体が切り出されているので origin は 0,0
+                    // 既に領域全体が切り出されているので origin は 0,0
                     let tile_x = col * region.tile_width;
                     let tile_y = row * region.tile_height;
                     
                     // 範囲チェック
-                    if tile_x + region.tile_width > width || tile_y + region.tile_height > height {
+                    if tile_x + region.tile_width > frame_img.width() || tile_y + region.tile_height > frame_img.height() {
                         continue;
                     }
                     
-                    // タイル画像を作成
-                    let mut tile_img = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(
-                        region.tile_width,
-                        region.tile_height
-                    );
-                    
-                    for ty in 0..region.tile_height {
-                        for tx in 0..region.tile_width {
-                            let src_x = tile_x + tx;
-                            let src_y = tile_y + ty;
-                            let src_idx = (src_y as usize * stride) + (src_x as usize * 3);
-
-                            if src_idx + 2 < data.len() {
-                                tile_img.put_pixel(tx, ty, Rgb([
-                                    data[src_idx],
-                                    data[src_idx + 1],
-                                    data[src_idx + 2],
-                                ]));
-                            }
-                        }
-                    }
+                    // タイル画像を作成（行単位でコピー）
+                    let Some(tile_img) = crate::video::crop_rgb_rows(
+                        data, stride, tile_x, tile_y, region.tile_width, region.tile_height,
+                    ) else {
+                        continue;
+                    };
                     
                     // ファイル名形式: {動画名}_frame={フレーム}_tile={タイルid}.png
                     let tile_id = row * region.columns + col;
@@ -306,22 +584,16 @@ pub fn collect_training_data(
                     
                     let dynamic_img = image::DynamicImage::ImageRgb8(tile_img);
                     save_as_uncompressed_png(&dynamic_img, &tile_path)
-                        .map_err(|e| format!("タイル保存失敗: {}", e))?;
+                        .map_err(|e| anyhow::anyhow!("タイル保存失敗: {}", e))?;
                     
                     tile_count += 1;
                 }
             }
             
             extracted_frame_count += 1;
-        }
-        
-        frame_count += 1;
-    }
-    
-    // パイプラインを停止
-    pipeline
-        .set_state(gst::State::Null)
-        .map_err(|e| format!("パイプライン停止失敗: {:?}", e))?;
+            Ok(())
+        })
+        .map_err(|e| format!("フレーム処理に失敗: {:#}", e))?;
     
     Ok(ExtractTilesResponse {
         tile_count,
@@ -360,6 +632,10 @@ pub fn extract_tiles_from_video(
         output_dir: PathBuf::from("."), // 使用しない
         image_format: "png".to_string(),
         jpeg_quality: 95,
+        scale_width: None,
+        scale_height: None,
+        transform: region.video_transform,
+        image_sequence_fps: crate::video::DEFAULT_IMAGE_SEQUENCE_FPS,
     };
 
     let extractor = FrameExtractor::new(frame_config);
@@ -547,9 +823,12 @@ pub fn create_training_directory(
 }
 
 /// モデルメタデータを取得
+///
+/// model_path にはモデルレジストリの登録名も指定可能
 #[cfg(feature = "ml")]
 #[tauri::command]
 pub fn get_model_metadata(model_path: String) -> Result<ModelMetadata, String> {
+    let model_path = AppConfig::load_or_default().resolve_model_path(&model_path);
     load_metadata(&PathBuf::from(model_path))
         .map_err(|e| format!("メタデータ読み込みエラー: {}", e))
 }
@@ -559,3 +838,42 @@ pub fn get_model_metadata(model_path: String) -> Result<ModelMetadata, String> {
 pub fn get_model_metadata(_model_path: String) -> Result<String, String> {
     Err("機械学習機能が有効化されていません".to_string())
 }
+
+/// モデルレジストリの一覧を取得
+#[tauri::command]
+pub fn list_registered_models() -> Result<Vec<ModelRegistryEntry>, String> {
+    let config = AppConfig::load_or_default();
+    Ok(config.model_registry)
+}
+
+/// モデルをレジストリに登録（同名のエントリは上書き）
+#[tauri::command]
+pub fn register_model(entry: ModelRegistryEntry) -> Result<String, String> {
+    if entry.name.trim().is_empty() {
+        return Err("登録名が空です".to_string());
+    }
+    if !PathBuf::from(&entry.path).exists() {
+        return Err(format!("モデルファイルが見つかりません: {}", entry.path));
+    }
+
+    let mut config = AppConfig::load_or_default();
+    let name = entry.name.clone();
+    config.register_model(entry);
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(format!("モデル '{}' を登録しました", name))
+}
+
+/// モデルをレジストリから削除
+#[tauri::command]
+pub fn unregister_model(name: String) -> Result<String, String> {
+    let mut config = AppConfig::load_or_default();
+    if !config.unregister_model(&name) {
+        return Err(format!("登録されていないモデルです: {}", name));
+    }
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(format!("モデル '{}' を削除しました", name))
+}
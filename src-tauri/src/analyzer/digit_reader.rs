@@ -0,0 +1,213 @@
+//! 継続フレーム数の数字の読み取り
+//!
+//! 入力表示の左端の列には、その入力が続いているフレーム数が数字で表示される。
+//! 動画から切り出した 0〜9 の数字画像をテンプレートとして、列のセルを1文字ずつ照合して読み取る。
+//! 録画でフレームが落ちていても、ゲームが表示した正しいフレーム数が得られる。
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// 照合前に文字を揃える大きさ
+const GLYPH_WIDTH: u32 = 10;
+const GLYPH_HEIGHT: u32 = 16;
+
+/// 文字と背景の明るさの差がこれ未満のセルは数字が無いものとして扱う
+const MIN_CONTRAST: u8 = 48;
+
+/// テンプレートとの一致率（一致した画素の割合）がこれ未満の文字は読み取り失敗とする
+const MIN_MATCH_SCORE: f32 = 0.8;
+
+// 2値化した文字（GLYPH_WIDTH x GLYPH_HEIGHT）
+type Glyph = Vec<bool>;
+
+/// 数字のテンプレート照合による読み取り
+#[derive(Debug, Clone)]
+pub struct DigitReader {
+    /// 0〜9 の順
+    templates: Vec<Glyph>,
+}
+
+impl DigitReader {
+    /// フォルダ内の 0.png〜9.png をテンプレートとして読み込む
+    ///
+    /// 各画像は数字1文字を含む切り抜き（余白があってもよい）。
+    pub fn load(dir: &Path) -> Result<Self> {
+        let images = (0..10)
+            .map(|digit| {
+                let path = dir.join(format!("{}.png", digit));
+                image::open(&path)
+                    .with_context(|| format!("数字のテンプレートを読み込めません: {:?}", path))
+                    .map(|img| img.to_luma8())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_templates(&images)
+    }
+
+    /// 0〜9 の順に並べた数字画像から作成
+    pub fn from_templates(images: &[image::GrayImage]) -> Result<Self> {
+        if images.len() != 10 {
+            anyhow::bail!("数字のテンプレートは 0〜9 の10枚が必要です（{}枚）", images.len());
+        }
+        let templates = images
+            .iter()
+            .enumerate()
+            .map(|(digit, img)| {
+                let mask = binarize(img).ok_or_else(|| anyhow::anyhow!("数字 {} のテンプレートに文字がありません", digit))?;
+                let (width, height) = img.dimensions();
+                let has_ink = |x: &u32| column_has_ink(&mask, width, height, *x);
+                let (Some(x0), Some(last)) = ((0..width).find(has_ink), (0..width).rev().find(has_ink)) else {
+                    anyhow::bail!("数字 {} のテンプレートに文字がありません", digit);
+                };
+                let x1 = last + 1;
+                Ok(normalize_glyph(&mask, width, height, x0, x1))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { templates })
+    }
+
+    /// セルの画像から数字を読み取る（数字が無い・照合できない文字がある場合は None）
+    pub fn read(&self, cell: &image::RgbImage) -> Option<u32> {
+        let gray = image::imageops::grayscale(cell);
+        let (width, height) = gray.dimensions();
+        let mask = binarize(&gray)?;
+
+        // 文字を含む列の連続した範囲を1文字とする
+        let mut value: Option<u32> = None;
+        let mut x = 0;
+        while x < width {
+            if !column_has_ink(&mask, width, height, x) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < width && column_has_ink(&mask, width, height, x) {
+                x += 1;
+            }
+
+            let glyph = normalize_glyph(&mask, width, height, start, x);
+            let (digit, score) = self
+                .templates
+                .iter()
+                .enumerate()
+                .map(|(digit, template)| (digit as u32, match_score(&glyph, template)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            if score < MIN_MATCH_SCORE {
+                return None;
+            }
+            value = Some(value.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+        }
+        value
+    }
+}
+
+// 明るさの最小値と最大値の中間で2値化する（文字が背景より暗い場合は反転して文字側を true にする）
+fn binarize(gray: &image::GrayImage) -> Option<Vec<bool>> {
+    let min = gray.pixels().map(|p| p[0]).min()?;
+    let max = gray.pixels().map(|p| p[0]).max()?;
+    if max - min < MIN_CONTRAST {
+        return None;
+    }
+    let threshold = min + (max - min) / 2;
+    let (width, height) = gray.dimensions();
+    let mut mask: Vec<bool> = gray.pixels().map(|p| p[0] > threshold).collect();
+
+    // 外周の画素の多数派を背景とみなす
+    let border: Vec<bool> = (0..width)
+        .flat_map(|x| [mask[x as usize], mask[((height - 1) * width + x) as usize]])
+        .chain((0..height).flat_map(|y| [mask[(y * width) as usize], mask[(y * width + width - 1) as usize]]))
+        .collect();
+    if border.iter().filter(|&&ink| ink).count() * 2 > border.len() {
+        mask.iter_mut().for_each(|ink| *ink = !*ink);
+    }
+    Some(mask)
+}
+
+fn column_has_ink(mask: &[bool], width: u32, height: u32, x: u32) -> bool {
+    (0..height).any(|y| mask[(y * width + x) as usize])
+}
+
+// 列 [x0, x1) の文字を上下の余白を除いて切り出し、GLYPH_WIDTH x GLYPH_HEIGHT に揃える
+fn normalize_glyph(mask: &[bool], width: u32, height: u32, x0: u32, x1: u32) -> Glyph {
+    let row_has_ink = |y: u32| (x0..x1).any(|x| mask[(y * width + x) as usize]);
+    let y0 = (0..height).find(|&y| row_has_ink(y)).unwrap_or(0);
+    let y1 = (0..height).rev().find(|&y| row_has_ink(y)).map_or(height, |y| y + 1);
+
+    let (glyph_width, glyph_height) = (x1 - x0, y1 - y0);
+    let mut glyph = Vec::with_capacity((GLYPH_WIDTH * GLYPH_HEIGHT) as usize);
+    for gy in 0..GLYPH_HEIGHT {
+        for gx in 0..GLYPH_WIDTH {
+            // 最近傍で拡大・縮小
+            let x = x0 + gx * glyph_width / GLYPH_WIDTH;
+            let y = y0 + gy * glyph_height / GLYPH_HEIGHT;
+            glyph.push(mask[(y * width + x) as usize]);
+        }
+    }
+    glyph
+}
+
+fn match_score(a: &Glyph, b: &Glyph) -> f32 {
+    let same = a.iter().zip(b.iter()).filter(|(a, b)| a == b).count();
+    same as f32 / a.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3x5 のドット絵の数字を scale 倍して描く
+    const FONT: [[&str; 5]; 10] = [
+        ["###", "#.#", "#.#", "#.#", "###"],
+        [".#.", "##.", ".#.", ".#.", "###"],
+        ["###", "..#", "###", "#..", "###"],
+        ["###", "..#", "###", "..#", "###"],
+        ["#.#", "#.#", "###", "..#", "..#"],
+        ["###", "#..", "###", "..#", "###"],
+        ["###", "#..", "###", "#.#", "###"],
+        ["###", "..#", ".#.", ".#.", ".#."],
+        ["###", "#.#", "###", "#.#", "###"],
+        ["###", "#.#", "###", "..#", "###"],
+    ];
+
+    fn draw(img: &mut image::GrayImage, digit: usize, left: u32, top: u32, scale: u32) {
+        for (row, line) in FONT[digit].iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                if c != '#' {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        img.put_pixel(left + col as u32 * scale + dx, top + row as u32 * scale + dy, image::Luma([230]));
+                    }
+                }
+            }
+        }
+    }
+
+    fn reader() -> DigitReader {
+        let templates: Vec<image::GrayImage> = (0..10)
+            .map(|digit| {
+                let mut img = image::GrayImage::from_pixel(16, 24, image::Luma([20]));
+                draw(&mut img, digit, 2, 2, 4);
+                img
+            })
+            .collect();
+        DigitReader::from_templates(&templates).unwrap()
+    }
+
+    #[test]
+    fn test_reads_multi_digit_numbers() {
+        let reader = reader();
+        let mut cell = image::GrayImage::from_pixel(48, 48, image::Luma([30]));
+        draw(&mut cell, 4, 10, 12, 4);
+        draw(&mut cell, 7, 26, 12, 4);
+        let cell = image::DynamicImage::ImageLuma8(cell).to_rgb8();
+        assert_eq!(reader.read(&cell), Some(47));
+    }
+
+    #[test]
+    fn test_blank_cell_is_unreadable() {
+        let reader = reader();
+        let cell = image::RgbImage::from_pixel(48, 48, image::Rgb([30, 30, 30]));
+        assert_eq!(reader.read(&cell), None);
+    }
+}
@@ -0,0 +1,82 @@
+//! 重複フレームの検出
+//!
+//! 30fps のゲームを 60fps でキャプチャした動画や VFR の録画では、同じ画像のフレームが
+//! 連続する。切り出した入力表示領域の画素を直前のフレームと比べ、完全に同じフレームは
+//! 分類を省略して直前のステップの持続フレーム数に加える。
+
+use crate::model::AppConfig;
+
+/// 直前のフレームと同じ画像のフレームを判定する
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateFrameFilter {
+    previous: Option<image::RgbImage>,
+    duplicates: u32,
+}
+
+impl DuplicateFrameFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 設定ファイルで重複フレームの検出が有効な場合に作成
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        config.generation.skip_duplicate_frames.then(Self::new)
+    }
+
+    /// 直前のフレームと画素が完全に一致すれば true（色だけの変化も別の画像として扱う）
+    pub fn is_duplicate(&mut self, img: &image::RgbImage) -> bool {
+        if let Some(previous) = &self.previous {
+            if previous.dimensions() == img.dimensions() && previous.as_raw() == img.as_raw() {
+                self.duplicates += 1;
+                return true;
+            }
+        }
+        match self.previous.as_mut() {
+            Some(previous) => previous.clone_from(img),
+            None => self.previous = Some(img.clone()),
+        }
+        false
+    }
+
+    /// 重複として分類を省略したフレーム数
+    pub fn duplicates(&self) -> u32 {
+        self.duplicates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 左から1タイル分ずつ明るさの違う帯（tile 番目だけを指定の色にする）
+    fn strip(tile: u32, color: [u8; 3]) -> image::RgbImage {
+        image::RgbImage::from_fn(320, 32, |x, _| {
+            if x / 32 == tile {
+                image::Rgb(color)
+            } else {
+                let value = (x % 32) as u8 * 4;
+                image::Rgb([value, value, value])
+            }
+        })
+    }
+
+    #[test]
+    fn test_skips_repeated_frames() {
+        let mut filter = DuplicateFrameFilter::new();
+        assert!(!filter.is_duplicate(&strip(0, [255, 255, 255])));
+        assert!(filter.is_duplicate(&strip(0, [255, 255, 255])));
+        assert!(!filter.is_duplicate(&strip(3, [255, 255, 255])));
+        assert!(filter.is_duplicate(&strip(3, [255, 255, 255])));
+        assert!(!filter.is_duplicate(&strip(0, [255, 255, 255])));
+        assert_eq!(filter.duplicates(), 2);
+    }
+
+    #[test]
+    fn test_color_change_is_not_duplicate() {
+        // 押下中のアイコンは形が同じで色だけが変わる
+        let mut filter = DuplicateFrameFilter::new();
+        assert!(!filter.is_duplicate(&strip(2, [200, 200, 200])));
+        assert!(!filter.is_duplicate(&strip(2, [255, 80, 80])));
+        assert_eq!(filter.duplicates(), 0);
+    }
+}
@@ -17,6 +17,28 @@ pub struct InputIndicatorRegion {
     pub cols: u32,
 }
 
+#[cfg(feature = "ml")]
+impl InputIndicatorRegion {
+    /// 全ての領域を囲む最小の領域（1回のデコードで複数の領域を切り出すため。rows・cols は 1）
+    pub fn bounding(regions: &[InputIndicatorRegion]) -> Option<InputIndicatorRegion> {
+        let left = regions.iter().map(|r| r.x).min()?;
+        let top = regions.iter().map(|r| r.y).min()?;
+        let right = regions.iter().map(|r| r.x + r.width).max()?;
+        let bottom = regions.iter().map(|r| r.y + r.height).max()?;
+        Some(InputIndicatorRegion { x: left, y: top, width: right - left, height: bottom - top, rows: 1, cols: 1 })
+    }
+}
+
+/// ラベル付きの解析領域（対戦動画の P1 / P2 側など）
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone)]
+pub struct LabeledRegion {
+    pub label: String,
+    /// transform の補正後の画像上の座標
+    pub region: InputIndicatorRegion,
+    pub transform: crate::model::VideoTransform,
+}
+
 /// 入力状態（各ボタンの状態）
 #[cfg(feature = "ml")]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -33,15 +55,6 @@ impl InputState {
             buttons: std::collections::HashMap::new(),
         }
     }
-
-    pub fn to_csv_line(&self, duration: u32, button_labels: &[String]) -> String {
-        let mut parts = vec![duration.to_string(), self.direction.to_string()];
-        for label in button_labels {
-            let state = self.buttons.get(label).copied().unwrap_or(0);
-            parts.push(state.to_string());
-        }
-        parts.join(",")
-    }
 }
 
 /// クラス名から入力状態を更新
@@ -95,14 +108,9 @@ pub fn extract_tiles_from_image(img: &image::RgbImage, region: &InputIndicatorRe
                 cell_x, cell_y, cell_width, cell_height, img.width(), img.height());
         }
 
-        // サブイメージを作成
-        let mut tile = image::RgbImage::new(cell_width, cell_height);
-        for y in 0..cell_height {
-            for x in 0..cell_width {
-                let pixel = img.get_pixel(cell_x + x, cell_y + y);
-                tile.put_pixel(x, y, *pixel);
-            }
-        }
+        // サブイメージを作成（行単位でコピー）
+        let tile = crate::video::crop_rgb_rows(img.as_raw(), img.width() as usize * 3, cell_x, cell_y, cell_width, cell_height)
+            .ok_or_else(|| anyhow::anyhow!("タイルの切り出しに失敗しました"))?;
         icons.push(tile);
     }
 
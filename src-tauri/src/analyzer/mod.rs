@@ -1,3 +1,9 @@
+pub mod digit_reader;
+pub mod duplicate_frame;
 pub mod input_history_extractor;
+pub mod postprocess;
 
-pub use input_history_extractor::{InputIndicatorRegion, InputState, update_input_state, extract_bottom_row_icons, extract_tiles_from_image};
+pub use input_history_extractor::{InputIndicatorRegion, InputState, LabeledRegion, update_input_state, extract_bottom_row_icons, extract_tiles_from_image};
+pub use digit_reader::DigitReader;
+pub use duplicate_frame::DuplicateFrameFilter;
+pub use postprocess::{StepPostProcess, OutputColumn, output_columns, output_columns_for, to_csv_record, write_steps_csv, trim_dead_time, insert_release_gaps};
@@ -0,0 +1,377 @@
+//! 生成したシーケンスの後処理
+//!
+//! 動画から抽出したステップ列を、そのまま再生しやすい形に整えます。
+
+#[cfg(feature = "ml")]
+use crate::analyzer::InputState;
+#[cfg(feature = "ml")]
+use crate::model::{AppConfig, GenerationSettings};
+#[cfg(feature = "ml")]
+use anyhow::{Context, Result};
+#[cfg(feature = "ml")]
+use std::collections::HashMap;
+#[cfg(feature = "ml")]
+use std::path::Path;
+
+/// 後処理の設定
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StepPostProcess {
+    /// 同じボタンが連続するステップ間に挿入するリリースのフレーム数（0で無効）
+    pub release_gap_frames: u32,
+    /// 先頭・末尾の無入力ステップを取り除く
+    pub trim_dead_time: bool,
+    /// 途中の無入力ステップの最大フレーム数（None で制限なし）
+    pub max_neutral_gap_frames: Option<u32>,
+    /// 多数決で平滑化する窓のフレーム数（1以下で無効）
+    pub smoothing_window_frames: u32,
+    /// これより短いステップを前後のステップに統合する（0で無効）
+    pub min_step_frames: u32,
+}
+
+#[cfg(feature = "ml")]
+impl StepPostProcess {
+    /// 設定ファイルの値から作成
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            release_gap_frames: config.playback.release_gap_frames,
+            trim_dead_time: config.generation.trim_dead_time,
+            max_neutral_gap_frames: match config.generation.max_neutral_gap_frames {
+                0 => None,
+                frames => Some(frames),
+            },
+            smoothing_window_frames: config.generation.smoothing_window_frames,
+            min_step_frames: config.generation.min_step_frames,
+        }
+    }
+
+    /// ステップ列に後処理を適用
+    ///
+    /// 誤認識の平滑化を先に行い、リリースの挿入は最後に行う（挿入した短いリリースを平滑化で消さないため）。
+    pub fn apply(&self, steps: Vec<(InputState, u32)>) -> Vec<(InputState, u32)> {
+        let steps = majority_smooth(steps, self.smoothing_window_frames);
+        let steps = merge_short_steps(steps, self.min_step_frames);
+        let steps = trim_dead_time(steps, self.trim_dead_time, self.max_neutral_gap_frames);
+        insert_release_gaps(steps, self.release_gap_frames)
+    }
+}
+
+/// 出力CSVのボタン列
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputColumn {
+    /// ヘッダーに書き出す列名
+    pub name: String,
+    /// 値を取り出すモデルのボタンラベル（None の場合は常に0）
+    pub source: Option<String>,
+}
+
+/// 出力CSVのボタン列の並びを決定
+///
+/// モデルのボタンラベルに renames を適用した列名を、mapping_order の順に並べる。
+/// mapping_order に無い列はモデルの順序のまま末尾に置き、
+/// mapping_order にあってモデルに無いボタンは常に0の列として追加する。
+/// mapping_order が空の場合はモデルの順序をそのまま使う。
+#[cfg(feature = "ml")]
+pub fn output_columns(
+    model_labels: &[String],
+    mapping_order: &[String],
+    renames: &HashMap<String, String>,
+) -> Vec<OutputColumn> {
+    let mut columns: Vec<OutputColumn> = model_labels
+        .iter()
+        .map(|label| OutputColumn {
+            name: renames.get(label).cloned().unwrap_or_else(|| label.clone()),
+            source: Some(label.clone()),
+        })
+        .collect();
+    if mapping_order.is_empty() {
+        return columns;
+    }
+
+    for button in mapping_order {
+        if !columns.iter().any(|c| &c.name == button) {
+            columns.push(OutputColumn { name: button.clone(), source: None });
+        }
+    }
+    // 安定ソートなのでマッピングに無い列はモデルの順序を保つ
+    columns.sort_by_key(|c| {
+        mapping_order
+            .iter()
+            .position(|b| b == &c.name)
+            .unwrap_or(mapping_order.len())
+    });
+    columns
+}
+
+/// 生成設定に従って出力CSVのボタン列を決定
+///
+/// use_mapping_column_order が有効な場合は button_order（読み込み中のボタンマッピングの順序）に合わせる。
+/// 列名の変更（column_renames）は常に適用する。動画から生成するCSVはすべてこの列で書き出す。
+#[cfg(feature = "ml")]
+pub fn output_columns_for(
+    model_labels: &[String],
+    generation: &GenerationSettings,
+    button_order: &[String],
+) -> Vec<OutputColumn> {
+    let mapping_order = if generation.use_mapping_column_order {
+        if button_order.is_empty() {
+            println!("[CSV出力] 警告: ボタンマッピングが読み込まれていないため、モデルの列順で出力します");
+        }
+        button_order
+    } else {
+        &[]
+    };
+    output_columns(model_labels, mapping_order, &generation.column_renames)
+}
+
+/// ステップ列をCSVに書き出す（ヘッダー含む）
+#[cfg(feature = "ml")]
+pub fn write_steps_csv(path: &Path, columns: &[OutputColumn], steps: &[(InputState, u32)]) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path).context("CSV作成エラー")?;
+    let mut header = vec!["duration".to_string(), "direction".to_string()];
+    header.extend(columns.iter().map(|c| c.name.clone()));
+    writer.write_record(&header).context("CSVヘッダー書き込みエラー")?;
+    for (state, duration) in steps {
+        writer.write_record(to_csv_record(state, *duration, columns)).context("CSV書き込みエラー")?;
+    }
+    writer.flush().context("CSVフラッシュエラー")?;
+    Ok(())
+}
+
+/// ステップをCSVの1行に変換（columns の順にボタン値を並べる）
+#[cfg(feature = "ml")]
+pub fn to_csv_record(state: &InputState, duration: u32, columns: &[OutputColumn]) -> Vec<String> {
+    let mut record = vec![duration.to_string(), state.direction.to_string()];
+    record.extend(columns.iter().map(|column| {
+        column
+            .source
+            .as_ref()
+            .and_then(|label| state.buttons.get(label))
+            .copied()
+            .unwrap_or(0)
+            .to_string()
+    }));
+    record
+}
+
+#[cfg(feature = "ml")]
+fn is_neutral(state: &InputState) -> bool {
+    state.direction == 5 && state.buttons.values().all(|&v| v == 0)
+}
+
+/// 各フレームの入力状態を前後の窓の多数決で置き換える
+///
+/// 窓は対象のフレームを中心とした window_frames フレーム（偶数の場合は1つ増やす）。
+/// 数フレームだけの誤認識を取り除くためのもので、シーケンス全体の長さは変わらない。
+/// 同数の場合は元の状態を残す。window_frames が 1 以下の場合は何もしない。
+#[cfg(feature = "ml")]
+pub fn majority_smooth(steps: Vec<(InputState, u32)>, window_frames: u32) -> Vec<(InputState, u32)> {
+    if window_frames <= 1 || steps.len() <= 1 {
+        return steps;
+    }
+    let half = (window_frames / 2) as usize;
+
+    // 状態ごとに番号を振り、フレームごとの状態番号の列に展開する
+    let mut states: Vec<InputState> = Vec::new();
+    let mut frames: Vec<usize> = Vec::new();
+    for (state, duration) in steps {
+        let id = match states.iter().position(|s| s == &state) {
+            Some(id) => id,
+            None => {
+                states.push(state);
+                states.len() - 1
+            }
+        };
+        frames.extend(std::iter::repeat_n(id, duration as usize));
+    }
+
+    let mut counts = vec![0u32; states.len()];
+    for &id in frames.iter().take(half + 1) {
+        counts[id] += 1;
+    }
+
+    let mut result: Vec<(usize, u32)> = Vec::new();
+    for i in 0..frames.len() {
+        // 窓を [i - half, i + half] に合わせる
+        if i > 0 {
+            if let Some(&id) = frames.get(i + half) {
+                counts[id] += 1;
+            }
+            if i > half {
+                counts[frames[i - half - 1]] -= 1;
+            }
+        }
+
+        let current = frames[i];
+        let max = counts.iter().copied().max().unwrap_or(0);
+        let id = if counts[current] == max {
+            current
+        } else {
+            counts.iter().position(|&c| c == max).unwrap_or(current)
+        };
+        match result.last_mut() {
+            Some((last, duration)) if *last == id => *duration += 1,
+            _ => result.push((id, 1)),
+        }
+    }
+
+    result.into_iter().map(|(id, duration)| (states[id].clone(), duration)).collect()
+}
+
+/// min_frames より短いステップを直前のステップに統合する（先頭の場合は次のステップ）
+///
+/// 統合した結果、同じ状態が続く場合は1つのステップにまとめる。シーケンス全体の長さは変わらない。
+/// 全てのステップが短い場合や min_frames が 0 の場合は何もしない。
+#[cfg(feature = "ml")]
+pub fn merge_short_steps(steps: Vec<(InputState, u32)>, min_frames: u32) -> Vec<(InputState, u32)> {
+    if min_frames == 0 || steps.iter().all(|(_, duration)| *duration < min_frames) {
+        return steps;
+    }
+
+    let mut result: Vec<(InputState, u32)> = Vec::with_capacity(steps.len());
+    // 先頭の短いステップの長さ（次の十分な長さのステップに加える）
+    let mut carry = 0u32;
+    for (state, duration) in steps {
+        if duration < min_frames {
+            match result.last_mut() {
+                Some((_, last)) => *last += duration,
+                None => carry += duration,
+            }
+            continue;
+        }
+        match result.last_mut() {
+            Some((last, last_duration)) if *last == state => *last_duration += duration,
+            _ => result.push((state, duration + std::mem::take(&mut carry))),
+        }
+    }
+    result
+}
+
+/// 無入力区間を詰める
+///
+/// trim_edges が true の場合は先頭・末尾の無入力ステップを取り除き、
+/// max_neutral_frames を指定した場合は途中の無入力ステップをそのフレーム数までに短縮する。
+#[cfg(feature = "ml")]
+pub fn trim_dead_time(
+    steps: Vec<(InputState, u32)>,
+    trim_edges: bool,
+    max_neutral_frames: Option<u32>,
+) -> Vec<(InputState, u32)> {
+    let (start, end) = if trim_edges {
+        let start = steps.iter().position(|(s, _)| !is_neutral(s)).unwrap_or(steps.len());
+        let end = steps.iter().rposition(|(s, _)| !is_neutral(s)).map_or(start, |i| i + 1);
+        (start, end)
+    } else {
+        (0, steps.len())
+    };
+
+    steps
+        .into_iter()
+        .skip(start)
+        .take(end - start)
+        .map(|(state, duration)| match max_neutral_frames {
+            Some(max) if is_neutral(&state) => (state, duration.min(max.max(1))),
+            _ => (state, duration),
+        })
+        .collect()
+}
+
+/// 同じボタンが連続するステップ間にリリースを挿入
+///
+/// 押し直しをリリース無しでは認識しないゲーム向け。再生時のリリース挿入（player::release_buttons）と
+/// 同じ規則で、押され続けるボタンを離した gap_frames のステップを間に追加する（以降はその分後ろにずれる）。
+/// 挿入済みの箇所には押され続けるボタンが無いため、生成したCSVを再生時にもう一度挿入することはない。
+#[cfg(feature = "ml")]
+pub fn insert_release_gaps(steps: Vec<(InputState, u32)>, gap_frames: u32) -> Vec<(InputState, u32)> {
+    if gap_frames == 0 {
+        return steps;
+    }
+
+    let mut result: Vec<(InputState, u32)> = Vec::with_capacity(steps.len());
+    let mut iter = steps.into_iter().peekable();
+    while let Some((state, duration)) = iter.next() {
+        let release = iter
+            .peek()
+            .and_then(|(next, _)| crate::player::release_buttons(&state.buttons, &next.buttons))
+            .map(|buttons| InputState { direction: state.direction, buttons });
+        result.push((state, duration));
+        if let Some(release) = release {
+            result.push((release, gap_frames));
+        }
+    }
+    result
+}
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+
+    fn state(direction: u8, pressed: &[&str]) -> InputState {
+        InputState {
+            direction,
+            buttons: pressed.iter().map(|b| (b.to_string(), 1)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_trim_dead_time() {
+        let steps = vec![
+            (state(5, &[]), 200),
+            (state(6, &["A"]), 3),
+            (state(5, &[]), 90),
+            (state(2, &[]), 4),
+            (state(5, &[]), 500),
+        ];
+        let trimmed = trim_dead_time(steps, true, Some(10));
+        let durations: Vec<u32> = trimmed.iter().map(|(_, d)| *d).collect();
+        assert_eq!(durations, vec![3, 10, 4]);
+    }
+
+    #[test]
+    fn test_insert_release_gaps_like_playback() {
+        // 1フレームだけのステップの後にも、再生時と同じくリリースを追加する
+        let steps = vec![(state(5, &["A"]), 4), (state(6, &["A"]), 1), (state(6, &["A", "B"]), 3), (state(2, &["B"]), 2)];
+        let result = insert_release_gaps(steps, 1);
+        let durations: Vec<u32> = result.iter().map(|(_, d)| *d).collect();
+        assert_eq!(durations, vec![4, 1, 1, 1, 3, 1, 2]);
+        assert_eq!((result[1].0.direction, result[1].0.buttons.get("A")), (5, Some(&0)));
+        assert_eq!((result[5].0.buttons.get("A"), result[5].0.buttons.get("B")), (Some(&1), Some(&0)));
+
+        // 挿入済みのステップ列にもう一度適用しても変わらない
+        assert_eq!(insert_release_gaps(result.clone(), 1), result);
+    }
+
+    #[test]
+    fn test_smoothing_removes_single_frame_glitches() {
+        // 6 の入力中に 1 フレームだけ 3 と誤認識した
+        let steps = vec![(state(6, &[]), 5), (state(3, &[]), 1), (state(6, &[]), 4), (state(5, &["A"]), 6)];
+
+        let smoothed = majority_smooth(steps.clone(), 3);
+        let durations: Vec<(u8, u32)> = smoothed.iter().map(|(s, d)| (s.direction, *d)).collect();
+        assert_eq!(durations, vec![(6, 10), (5, 6)]);
+
+        let merged = merge_short_steps(steps, 2);
+        let durations: Vec<(u8, u32)> = merged.iter().map(|(s, d)| (s.direction, *d)).collect();
+        assert_eq!(durations, vec![(6, 10), (5, 6)]);
+
+        let leading = merge_short_steps(vec![(state(2, &[]), 1), (state(6, &[]), 4)], 2);
+        assert_eq!(leading, vec![(state(6, &[]), 5)]);
+    }
+
+    #[test]
+    fn test_output_columns_follow_mapping_order() {
+        let labels: Vec<String> = ["btn_b", "A", "X"].iter().map(|s| s.to_string()).collect();
+        let order: Vec<String> = ["A", "B", "C"].iter().map(|s| s.to_string()).collect();
+        let renames = HashMap::from([("btn_b".to_string(), "B".to_string())]);
+
+        let columns = output_columns(&labels, &order, &renames);
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["A", "B", "C", "X"]);
+        assert_eq!(columns[1].source.as_deref(), Some("btn_b"));
+        assert_eq!(columns[2].source, None);
+
+        let record = to_csv_record(&state(6, &["btn_b"]), 3, &columns);
+        assert_eq!(record, vec!["3", "6", "0", "1", "0", "0"]);
+    }
+}
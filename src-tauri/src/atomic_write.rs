@@ -0,0 +1,77 @@
+//! ファイルの安全な上書き保存
+//!
+//! 同じディレクトリの一時ファイルに書き込んでからリネームするため、
+//! 書き込み途中でクラッシュしても元のファイルは壊れない。
+//! 保存前の内容は `<ファイル名>.1.bak`（最新）〜 `<ファイル名>.N.bak` にローテーションして残せる。
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// n 番目（1が最新）のバックアップのパス
+pub fn backup_path(path: &Path, n: u32) -> PathBuf {
+    sibling_path(path, &format!(".{}.bak", n))
+}
+
+// 既存のバックアップを1つずつずらし、現在のファイルを .1.bak にコピーする
+fn rotate_backups(path: &Path, backup_count: u32) -> io::Result<()> {
+    if backup_count == 0 || !path.exists() {
+        return Ok(());
+    }
+    let oldest = backup_path(path, backup_count);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for n in (1..backup_count).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// 一時ファイル経由でアトミックに上書き保存（backup_count > 0 なら保存前の内容をバックアップ）
+pub fn write_atomic(path: &Path, contents: &[u8], backup_count: u32) -> io::Result<()> {
+    let temp_path = sibling_path(path, ".tmp");
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        rotate_backups(path, backup_count)?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_rotates_backups() {
+        let dir = crate::test_support::TempDir::new("atomic_write_test");
+        let path = dir.join("sequence.csv");
+
+        for content in ["v1", "v2", "v3", "v4"] {
+            write_atomic(&path, content.as_bytes(), 2).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v4");
+        assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "v3");
+        assert_eq!(fs::read_to_string(backup_path(&path, 2)).unwrap(), "v2");
+        assert!(!backup_path(&path, 3).exists());
+        assert!(!sibling_path(&path, ".tmp").exists());
+    }
+}
@@ -0,0 +1,152 @@
+//! GUI を使わずに動画から入力履歴のシーケンスCSVを作成するコマンドラインツール
+//!
+//! 複数の動画を指定すると順番に変換する。後処理の設定は config.json の値を使用する。
+
+#[cfg(feature = "ml")]
+const USAGE: &str = "Usage: cli <video_path>... --model <model_path|登録名> [--backend cpu|wgpu] [--output <csv_path>] [--config <config_path>]";
+
+#[cfg(feature = "ml")]
+struct CliArgs {
+    videos: Vec<String>,
+    model: String,
+    backend: String,
+    output: Option<String>,
+    config: Option<String>,
+}
+
+#[cfg(feature = "ml")]
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut videos = Vec::new();
+    let mut model = None;
+    let mut backend = "cpu".to_string();
+    let mut output = None;
+    let mut config = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let arg = arg.as_str();
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} の値が指定されていません", name))
+        };
+        match arg {
+            "--model" | "-m" => model = Some(value(arg)?),
+            "--backend" | "-b" => backend = value(arg)?,
+            "--output" | "-o" => output = Some(value(arg)?),
+            "--config" | "-c" => config = Some(value(arg)?),
+            other if other.starts_with('-') => return Err(format!("不明なオプションです: {}", other)),
+            other => videos.push(other.to_string()),
+        }
+    }
+
+    if videos.is_empty() {
+        return Err("動画ファイルが指定されていません".to_string());
+    }
+    let model = model.ok_or_else(|| "--model が指定されていません".to_string())?;
+    if backend != "cpu" && backend != "wgpu" {
+        return Err(format!("バックエンドは cpu か wgpu を指定してください: {}", backend));
+    }
+    if output.is_some() && videos.len() > 1 {
+        return Err("--output は動画を1つだけ指定した場合のみ使用できます".to_string());
+    }
+    Ok(CliArgs { videos, model, backend, output, config })
+}
+
+// 出力先を省略した場合は動画と同じ場所に <動画名>_input_history.csv を作成
+#[cfg(feature = "ml")]
+fn default_output_path(video_path: &std::path::Path) -> std::path::PathBuf {
+    let stem = video_path.file_stem().and_then(|s| s.to_str()).unwrap_or("video");
+    video_path.with_file_name(format!("{}_input_history.csv", stem))
+}
+
+#[cfg(feature = "ml")]
+fn main() {
+    use input_player_lib::analyzer::StepPostProcess;
+    use input_player_lib::ml::{extract_input_history_to_csv, InferenceEngine};
+    use input_player_lib::model::{AppConfig, VideoTransform};
+    use input_player_lib::video::{FrameExtractor, TimeRange};
+    use std::path::{Path, PathBuf};
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let config = match &args.config {
+        Some(path) => match AppConfig::load(path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("設定ファイルの読み込みに失敗: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => AppConfig::load_or_default(),
+    };
+    let model_path = config.resolve_model_path(&args.model);
+    let post_process = StepPostProcess::from_config(&config);
+
+    println!("[CLI] モデル: {}", model_path);
+    println!("[CLI] バックエンド: {}", args.backend);
+    let engine = match InferenceEngine::load_with_backend(&PathBuf::from(&model_path), args.backend == "wgpu") {
+        Ok(engine) => engine,
+        Err(e) => {
+            eprintln!("推論エンジンの初期化エラー: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut failed = 0;
+    for (index, video) in args.videos.iter().enumerate() {
+        let video_path = Path::new(video);
+        let output_path = match &args.output {
+            Some(output) => PathBuf::from(output),
+            None => default_output_path(video_path),
+        };
+        println!("[CLI] ({}/{}) {} → {}", index + 1, args.videos.len(), video, output_path.display());
+
+        // 進捗表示用の総フレーム数（取得できなければ不明として扱う）
+        let total_frames = FrameExtractor::get_video_info(video_path, VideoTransform::None)
+            .map(|info| (info.fps * info.duration_sec).round() as u32)
+            .unwrap_or(0);
+        let started = std::time::Instant::now();
+
+        let result = extract_input_history_to_csv(&engine, video_path, &output_path, &post_process, TimeRange::default(), |frame_num| {
+            if frame_num % 300 == 0 {
+                if total_frames > 0 {
+                    let percent = (frame_num as f64 / total_frames as f64 * 100.0).min(100.0);
+                    println!("[CLI] {}/{} フレーム ({:.1}%)", frame_num, total_frames, percent);
+                } else {
+                    println!("[CLI] {} フレーム", frame_num);
+                }
+            }
+            Ok(())
+        }, |_, _| {});
+        match result {
+            Ok(frames) => println!(
+                "[CLI] 完了: {} フレーム ({:.1}秒)",
+                frames,
+                started.elapsed().as_secs_f64()
+            ),
+            Err(e) => {
+                eprintln!("[CLI] 変換に失敗: {}: {}", video, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("[CLI] {}/{} 件の変換に失敗しました", failed, args.videos.len());
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "ml"))]
+fn main() {
+    println!("ML機能が有効化されていません");
+}
@@ -4,7 +4,7 @@
 fn main() {
     use std::path::PathBuf;
     use input_player_lib::video::{FrameExtractor, FrameExtractorConfig};
-    use input_player_lib::model::load_metadata;
+    use input_player_lib::model::{load_metadata, VideoTransform};
     use input_player_lib::ml::InferenceEngine;
     use input_player_lib::analyzer::InputIndicatorRegion;
 
@@ -47,7 +47,7 @@ fn main() {
         x: metadata.tile_x,
         y: metadata.tile_y,
         width: total_width,
-        height: tile_size,
+        height: metadata.image_height,
         rows: 1,
         cols: metadata.columns_per_row,
     };
@@ -59,6 +59,10 @@ fn main() {
         output_dir: PathBuf::from(&out_dir_name),
         image_format: "png".to_string(),
         jpeg_quality: 95,
+        scale_width: None,
+        scale_height: None,
+        transform: VideoTransform::None,
+        image_sequence_fps: input_player_lib::video::DEFAULT_IMAGE_SEQUENCE_FPS,
     };
 
     let extractor = FrameExtractor::new(frame_config.clone());
@@ -102,28 +106,14 @@ fn main() {
             }
         } else {
             for (chunk_idx, chunk) in all_tiles.chunks(batch_size).enumerate() {
-                match &engine {
-                    InferenceEngine::Wgpu { .. } => {
-                        match engine.classify_batch_from_images(chunk) {
-                            Ok(labels) => {
-                                for (j, class_name) in labels.into_iter().enumerate() {
-                                    let tile_index = chunk_idx * batch_size + j;
-                                    println!(" frame {} tile {} => {}", frame_num, tile_index, class_name);
-                                }
-                            }
-                            Err(err) => println!(" batch classification error: {}", err),
-                        }
-                    }
-                    InferenceEngine::NdArray { .. } => {
-                        // CPUバックエンドでは既存の個別推論を使う（チャンク単位で処理）
-                        for (j, tile) in chunk.iter().enumerate() {
+                match engine.classify_batch_from_images(chunk) {
+                    Ok(labels) => {
+                        for (j, class_name) in labels.into_iter().enumerate() {
                             let tile_index = chunk_idx * batch_size + j;
-                            match engine.classify_image_direct(tile) {
-                                Ok(class_name) => println!(" frame {} tile {} => {}", frame_num, tile_index, class_name),
-                                Err(err) => println!(" classification error: {}", err),
-                            }
+                            println!(" frame {} tile {} => {}", frame_num, tile_index, class_name);
                         }
                     }
+                    Err(err) => println!(" batch classification error: {}", err),
                 }
             }
         }
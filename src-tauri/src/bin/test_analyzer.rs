@@ -1,7 +1,7 @@
 //! 動画解析機能のテスト用バイナリ
 
 use input_player_lib::video::FrameExtractor;
-use input_player_lib::model::{load_metadata, print_metadata_info};
+use input_player_lib::model::{load_metadata, print_metadata_info, VideoTransform};
 use std::path::PathBuf;
 
 fn main() {
@@ -35,7 +35,7 @@ fn main() {
 fn test_video_info() {
     let video_path = r"E:\workspace\input_analyzer\sample_data\input_sample_01.mp4";
     
-    match FrameExtractor::get_video_info(video_path) {
+    match FrameExtractor::get_video_info(video_path, VideoTransform::None) {
         Ok(info) => {
             println!("✓ 動画情報取得成功:");
             println!("  解像度: {}x{}", info.width, info.height);
@@ -80,6 +80,10 @@ fn test_frame_extraction() {
         output_dir: output_dir.clone(),
         image_format: "png".to_string(),
         jpeg_quality: 95,
+        scale_width: None,
+        scale_height: None,
+        transform: VideoTransform::None,
+        image_sequence_fps: input_player_lib::video::DEFAULT_IMAGE_SEQUENCE_FPS,
     };
     
     let extractor = FrameExtractor::new(config);
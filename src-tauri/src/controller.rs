@@ -1,23 +1,73 @@
+use crate::instance::{NamedMutex, CONTROLLER_TARGET_NAME};
+use crate::output_backend::{create_backend, OutputBackend, DEFAULT_BACKEND};
 use crate::types::{ControllerType, InputFrame};
 use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::collections::VecDeque;
 use vigem_client::{Client, TargetId, Xbox360Wired, XGamepad, XButtons};
 
-pub struct Controller {
+// 送信ログの保持件数（デフォルト、60FPSで約1分）
+const DEFAULT_LOG_CAPACITY: usize = 3600;
+
+/// マッピングで指定できるコントローラーのボタン名（update_input で割り当てを持つもの）
+pub const CONTROLLER_BUTTONS: &[&str] = &[
+    "button1", "button2", "button3", "button4", "button5", "button6",
+    "button7", "button8", "button9", "button10", "button11", "button12",
+];
+
+// コントローラーへの送信ログ（1回分）
+#[derive(Debug, Clone, Serialize)]
+pub struct ControllerLogEntry {
+    pub unix_ms: u64,              // 送信時刻（UNIXエポックからのミリ秒）
+    pub direction: u8,             // 方向（テンキー表記、左右反転適用前）
+    pub invert_horizontal: bool,
+    pub buttons: Vec<String>,      // 押されているボタン名
+    pub buttons_raw: u16,          // 実際に送信したXInputボタンのビット列
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+    pub sent: bool,                // ドライバへの送信に成功したか
+}
+
+/// 現在コントローラーに送信している状態（入力オーバーレイ表示用）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ControllerOutput {
+    pub direction: u8,             // 方向（テンキー表記、左右反転適用後。未送信は5）
+    pub buttons: Vec<String>,      // 押されているボタン名（マッピング適用後の button1〜button12）
+    pub thumb_lx: i16,
+    pub thumb_ly: i16,
+    pub thumb_rx: i16,
+    pub thumb_ry: i16,
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+    pub invert_horizontal: bool,
+}
+
+impl ControllerOutput {
+    fn neutral() -> Self {
+        Self { direction: 5, ..Default::default() }
+    }
+}
+
+/// 標準の出力先: ViGEm の仮想 Xbox360 コントローラー
+pub struct ViGEmBackend {
     target: Option<Xbox360Wired<Client>>,
-    controller_type: Option<ControllerType>,
-    gamepad: XGamepad,  // READMEサンプルと同様に状態を保持
+    target_lock: Option<NamedMutex>,  // 接続中であることを他のプロセスに示す
 }
 
-impl Controller {
+impl Default for ViGEmBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViGEmBackend {
     pub fn new() -> Self {
-        Self {
-            target: None,
-            controller_type: None,
-            gamepad: XGamepad::default(),
-        }
+        Self { target: None, target_lock: None }
     }
+}
 
-    pub fn connect(&mut self, controller_type: ControllerType) -> Result<()> {
+impl OutputBackend for ViGEmBackend {
+    fn connect(&mut self, controller_type: ControllerType) -> Result<()> {
         // 既に接続されている場合は何もしない
         if self.is_connected() {
             return Ok(());
@@ -28,6 +78,11 @@ impl Controller {
             return Err(anyhow!("DualShock4 is not yet supported"));
         }
 
+        // 別のインスタンスが仮想コントローラーを接続中なら、入力を送り合わないよう接続しない
+        let target_lock = NamedMutex::acquire(CONTROLLER_TARGET_NAME).map_err(|_| {
+            anyhow!("別の input_player が仮想コントローラーを使用中です。もう一方を終了するか切断してください")
+        })?;
+
         // ViGEmクライアントを初期化
         let client = Client::connect().map_err(|e| {
             anyhow!("Failed to connect to ViGEm: {:?}", e)
@@ -47,111 +102,284 @@ impl Controller {
         })?;
 
         self.target = Some(target);
-        self.controller_type = Some(controller_type);
-        
+        self.target_lock = Some(target_lock);
         Ok(())
     }
 
-    pub fn disconnect(&mut self) -> Result<()> {
+    fn disconnect(&mut self) -> Result<()> {
         if let Some(mut target) = self.target.take() {
             target.unplug().map_err(|e| anyhow!("Failed to unplug controller: {:?}", e))?;
         }
-
-        self.controller_type = None;
-
+        self.target_lock = None;
         Ok(())
     }
 
-    pub fn update_input(&mut self, frame: &InputFrame, invert_horizontal: bool) -> Result<()> {
-        // 方向入力を処理
-        let (up, down, left, right) = Self::parse_direction(frame.direction, invert_horizontal);
-        
+    fn is_connected(&self) -> bool {
+        self.target.is_some()
+    }
+
+    fn update_input(&mut self, frame: &InputFrame, invert_horizontal: bool) -> Result<()> {
         let target = self.target.as_mut().ok_or_else(|| anyhow!("Controller not connected"))?;
+        target.update(&to_gamepad(frame, invert_horizontal))
+            .map_err(|e| anyhow!("Failed to update controller: {:?}", e))
+    }
+}
 
-        // 保持しているgamepadを更新（READMEサンプルと同じパターン）
-        let mut buttons_raw = 0u16;
-        
-        // D-Padの設定
-        // XButtons定数: UP=0x0001, DOWN=0x0002, LEFT=0x0004, RIGHT=0x0008
-        if up { buttons_raw |= XButtons::UP; }
-        if down { buttons_raw |= XButtons::DOWN; }
-        if left { buttons_raw |= XButtons::LEFT; }
-        if right { buttons_raw |= XButtons::RIGHT; }
-
-        // ボタンの設定 (button1-10にマッピング)
-        // XButtons定数:
-        // A=0x1000, B=0x2000, X=0x4000, Y=0x8000
-        // LB=0x0100, RB=0x0200
-        // LTHUMB=0x0040, RTHUMB=0x0080
-        // START=0x0010, BACK=0x0020
-        let mut left_trigger_value = 0u8;
-        let mut right_trigger_value = 0u8;
-        
-        for (button_name, &value) in &frame.buttons {
-            if value == 1 {
-                match button_name.as_str() {
-                    "button1" => buttons_raw |= XButtons::A,
-                    "button2" => buttons_raw |= XButtons::B,
-                    "button3" => buttons_raw |= XButtons::X,
-                    "button4" => buttons_raw |= XButtons::Y,
-                    "button5" => buttons_raw |= XButtons::LB,
-                    "button6" => buttons_raw |= XButtons::RB,
-                    "button7" => {
-                        // OR結合: 既に押されている場合はそのまま
-                        left_trigger_value = left_trigger_value.max(255);
-                    },
-                    "button8" => {
-                        // OR結合: 既に押されている場合はそのまま
-                        right_trigger_value = right_trigger_value.max(255);
-                    },
-                    "button9" => buttons_raw |= XButtons::BACK,
-                    "button10" => buttons_raw |= XButtons::START,
-                    "button11" => buttons_raw |= XButtons::LTHUMB,
-                    "button12" => buttons_raw |= XButtons::RTHUMB,
-                    _ => {}
-                }
+// 上下左右の組み合わせをテンキー表記に戻す
+fn to_numpad(up: bool, down: bool, left: bool, right: bool) -> u8 {
+    let row = if up { 6 } else if down { 0 } else { 3 };
+    let column = if left { 1 } else if right { 3 } else { 2 };
+    row + column
+}
+
+// 入力フレームを XInput のゲームパッド状態に変換
+fn to_gamepad(frame: &InputFrame, invert_horizontal: bool) -> XGamepad {
+    // 方向入力を処理
+    let (up, down, left, right) = parse_direction(frame.direction, invert_horizontal);
+
+    let mut buttons_raw = 0u16;
+    
+    // D-Padの設定
+    // XButtons定数: UP=0x0001, DOWN=0x0002, LEFT=0x0004, RIGHT=0x0008
+    if up { buttons_raw |= XButtons::UP; }
+    if down { buttons_raw |= XButtons::DOWN; }
+    if left { buttons_raw |= XButtons::LEFT; }
+    if right { buttons_raw |= XButtons::RIGHT; }
+
+    // ボタンの設定 (button1-10にマッピング)
+    // XButtons定数:
+    // A=0x1000, B=0x2000, X=0x4000, Y=0x8000
+    // LB=0x0100, RB=0x0200
+    // LTHUMB=0x0040, RTHUMB=0x0080
+    // START=0x0010, BACK=0x0020
+    let mut left_trigger_value = 0u8;
+    let mut right_trigger_value = 0u8;
+    
+    for (button_name, &value) in &frame.buttons {
+        if value == 1 {
+            match button_name.as_str() {
+                "button1" => buttons_raw |= XButtons::A,
+                "button2" => buttons_raw |= XButtons::B,
+                "button3" => buttons_raw |= XButtons::X,
+                "button4" => buttons_raw |= XButtons::Y,
+                "button5" => buttons_raw |= XButtons::LB,
+                "button6" => buttons_raw |= XButtons::RB,
+                "button7" => {
+                    // OR結合: 既に押されている場合はそのまま
+                    left_trigger_value = left_trigger_value.max(255);
+                },
+                "button8" => {
+                    // OR結合: 既に押されている場合はそのまま
+                    right_trigger_value = right_trigger_value.max(255);
+                },
+                "button9" => buttons_raw |= XButtons::BACK,
+                "button10" => buttons_raw |= XButtons::START,
+                "button11" => buttons_raw |= XButtons::LTHUMB,
+                "button12" => buttons_raw |= XButtons::RTHUMB,
+                _ => {}
             }
         }
+    }
+
+    // アナログスティック（左右反転時はX軸も反転）
+    let mirror_x = |x: i16| if invert_horizontal { x.checked_neg().unwrap_or(i16::MAX) } else { x };
+    let mut gamepad = XGamepad::default();
+    gamepad.buttons = XButtons { raw: buttons_raw };
+    gamepad.left_trigger = left_trigger_value.max(frame.left_trigger);
+    gamepad.right_trigger = right_trigger_value.max(frame.right_trigger);
+    gamepad.thumb_lx = mirror_x(frame.thumb_lx);
+    gamepad.thumb_ly = frame.thumb_ly;
+    gamepad.thumb_rx = mirror_x(frame.thumb_rx);
+    gamepad.thumb_ry = frame.thumb_ry;
+    gamepad
+}
+
+fn parse_direction(direction: u8, invert_horizontal: bool) -> (bool, bool, bool, bool) {
+    let mut up = false;
+    let mut down = false;
+    let mut left = false;
+    let mut right = false;
+
+    match direction {
+        8 => up = true,        // 上
+        2 => down = true,      // 下
+        4 => left = true,      // 左
+        6 => right = true,     // 右
+        7 => { up = true; left = true; }   // 左上
+        9 => { up = true; right = true; }  // 右上
+        1 => { down = true; left = true; } // 左下
+        3 => { down = true; right = true; } // 右下
+        _ => {} // 5 or その他: 中立
+    }
+
+    // 左右反転
+    if invert_horizontal {
+        std::mem::swap(&mut left, &mut right);
+    }
+
+    (up, down, left, right)
+}
+
+pub struct Controller {
+    backend: Box<dyn OutputBackend>,  // 実際の送信先（output_backend で登録されたもの）
+    backend_name: String,
+    controller_type: Option<ControllerType>,
+    gamepad: XGamepad,  // 最後に送信した状態（ログ用、XInput換算）
+    output: ControllerOutput,  // 最後に送信した状態（オーバーレイ用）
+    log: VecDeque<ControllerLogEntry>,  // 直近の送信ログ（モードに関係なく記録）
+    log_capacity: usize,
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Controller {
+    pub fn new() -> Self {
+        let backend = create_backend(DEFAULT_BACKEND).expect("標準の出力先は登録済み");
+        Self::with_backend(DEFAULT_BACKEND, backend)
+    }
+
+    /// 出力先を指定して作成（テストで記録を読むためにバックエンドを直接渡す場合など）
+    pub fn with_backend(name: &str, backend: Box<dyn OutputBackend>) -> Self {
+        Self {
+            backend,
+            backend_name: name.to_string(),
+            controller_type: None,
+            gamepad: XGamepad::default(),
+            output: ControllerOutput::neutral(),
+            log: VecDeque::with_capacity(DEFAULT_LOG_CAPACITY),
+            log_capacity: DEFAULT_LOG_CAPACITY,
+        }
+    }
 
-        // 保持しているgamepadインスタンスを更新（READMEサンプルと同様）
-        self.gamepad.buttons = XButtons { raw: buttons_raw };
-        self.gamepad.left_trigger = left_trigger_value;
-        self.gamepad.right_trigger = right_trigger_value;
-        // thumb_lx, thumb_ly, thumb_rx, thumb_ry は 0 のまま
+    /// 出力先を切り替える（接続中の場合は切断する）
+    pub fn set_backend(&mut self, name: &str) -> Result<()> {
+        if name == self.backend_name {
+            return Ok(());
+        }
+        let backend = create_backend(name)?;
+        self.disconnect()?;
+        self.backend = backend;
+        self.backend_name = name.to_string();
+        Ok(())
+    }
 
-        target.update(&self.gamepad).map_err(|e| anyhow!("Failed to update controller: {:?}", e))?;
+    pub fn backend_name(&self) -> &str {
+        &self.backend_name
+    }
 
+    pub fn connect(&mut self, controller_type: ControllerType) -> Result<()> {
+        // 既に接続されている場合は何もしない
+        if self.is_connected() {
+            return Ok(());
+        }
+        self.backend.connect(controller_type.clone())?;
+        self.controller_type = Some(controller_type);
         Ok(())
     }
 
-    fn parse_direction(direction: u8, invert_horizontal: bool) -> (bool, bool, bool, bool) {
-        let mut up = false;
-        let mut down = false;
-        let mut left = false;
-        let mut right = false;
-
-        match direction {
-            8 => up = true,        // 上
-            2 => down = true,      // 下
-            4 => left = true,      // 左
-            6 => right = true,     // 右
-            7 => { up = true; left = true; }   // 左上
-            9 => { up = true; right = true; }  // 右上
-            1 => { down = true; left = true; } // 左下
-            3 => { down = true; right = true; } // 右下
-            _ => {} // 5 or その他: 中立
+    pub fn disconnect(&mut self) -> Result<()> {
+        self.backend.disconnect()?;
+        self.controller_type = None;
+        self.output = ControllerOutput::neutral();
+        Ok(())
+    }
+
+    pub fn update_input(&mut self, frame: &InputFrame, invert_horizontal: bool) -> Result<()> {
+        if !self.backend.is_connected() {
+            return Err(anyhow!("Controller not connected"));
         }
+        self.gamepad = to_gamepad(frame, invert_horizontal);
+        self.output = self.to_output(frame, invert_horizontal);
+        let result = self.backend.update_input(frame, invert_horizontal);
+        self.push_log(frame, invert_horizontal, result.is_ok());
+        result
+    }
 
-        // 左右反転
-        if invert_horizontal {
-            std::mem::swap(&mut left, &mut right);
+    fn to_output(&self, frame: &InputFrame, invert_horizontal: bool) -> ControllerOutput {
+        let (up, down, left, right) = parse_direction(frame.direction, invert_horizontal);
+        let mut buttons: Vec<String> = frame
+            .buttons
+            .iter()
+            .filter(|(_, &value)| value == 1)
+            .map(|(name, _)| name.clone())
+            .collect();
+        buttons.sort();
+        ControllerOutput {
+            direction: to_numpad(up, down, left, right),
+            buttons,
+            thumb_lx: self.gamepad.thumb_lx,
+            thumb_ly: self.gamepad.thumb_ly,
+            thumb_rx: self.gamepad.thumb_rx,
+            thumb_ry: self.gamepad.thumb_ry,
+            left_trigger: self.gamepad.left_trigger,
+            right_trigger: self.gamepad.right_trigger,
+            invert_horizontal,
         }
+    }
 
-        (up, down, left, right)
+    fn push_log(&mut self, frame: &InputFrame, invert_horizontal: bool, sent: bool) {
+        if self.log_capacity == 0 {
+            return;
+        }
+        while self.log.len() >= self.log_capacity {
+            self.log.pop_front();
+        }
+        let unix_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut buttons: Vec<String> = frame
+            .buttons
+            .iter()
+            .filter(|(_, &value)| value == 1)
+            .map(|(name, _)| name.clone())
+            .collect();
+        buttons.sort();
+        self.log.push_back(ControllerLogEntry {
+            unix_ms,
+            direction: frame.direction,
+            invert_horizontal,
+            buttons,
+            buttons_raw: self.gamepad.buttons.raw,
+            left_trigger: self.gamepad.left_trigger,
+            right_trigger: self.gamepad.right_trigger,
+            sent,
+        });
+    }
+
+    // 直近の送信ログ（古い順）
+    pub fn log_entries(&self) -> Vec<ControllerLogEntry> {
+        self.log.iter().cloned().collect()
+    }
+
+    // 送信ログの保持件数を変更（0で記録しない）
+    pub fn set_log_capacity(&mut self, capacity: usize) {
+        self.log_capacity = capacity;
+        while self.log.len() > capacity {
+            self.log.pop_front();
+        }
+    }
+
+    pub fn clear_log(&mut self) {
+        self.log.clear();
     }
 
     pub fn is_connected(&self) -> bool {
-        self.target.is_some()
+        self.backend.is_connected()
+    }
+
+    /// 最後に送信した状態（未接続・未送信の場合は中立）
+    pub fn current_output(&self) -> ControllerOutput {
+        self.output.clone()
+    }
+
+    // 接続中のコントローラーの種類
+    pub fn get_controller_type(&self) -> Option<ControllerType> {
+        self.controller_type.clone()
     }
 }
 
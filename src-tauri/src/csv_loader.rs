@@ -1,31 +1,169 @@
-use crate::types::InputFrame;
+use crate::types::{InputFrame, SequenceLabel};
 use anyhow::{Result, Context};
 use csv::ReaderBuilder;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::path::Path;
 
-pub fn load_csv(path: &Path) -> Result<Vec<InputFrame>> {
-    // ファイルの存在チェック
-    if !path.exists() {
-        anyhow::bail!("ファイルが見つかりません: {:?}", path);
+/// アナログ軸の予約列名（LX, LY, RX, RY: スティック, LT, RT: トリガー）
+///
+/// これらの列はボタンとしては扱わず、InputFrame のアナログフィールドに読み込む。
+pub const ANALOG_COLUMNS: [&str; 6] = ["LX", "LY", "RX", "RY", "LT", "RT"];
+
+pub fn is_analog_column(header: &str) -> bool {
+    ANALOG_COLUMNS.contains(&header)
+}
+
+// アナログ列の値をフレームに設定（範囲外は飽和させる）
+fn set_analog_value(frame: &mut InputFrame, column: &str, value: i64) {
+    let stick = value.clamp(i16::MIN as i64, i16::MAX as i64) as i16;
+    let trigger = value.clamp(0, u8::MAX as i64) as u8;
+    match column {
+        "LX" => frame.thumb_lx = stick,
+        "LY" => frame.thumb_ly = stick,
+        "RX" => frame.thumb_rx = stick,
+        "RY" => frame.thumb_ry = stick,
+        "LT" => frame.left_trigger = trigger,
+        "RT" => frame.right_trigger = trigger,
+        _ => {}
     }
-    
-    // ファイルが読み取り可能かチェック
-    if let Err(e) = std::fs::metadata(path) {
-        anyhow::bail!("ファイルにアクセスできません: {:?} ({})", path, e);
+}
+
+/// フレームのアナログ値を ANALOG_COLUMNS と同じ順序で返す
+pub fn analog_values(frame: &InputFrame) -> [i32; 6] {
+    [
+        frame.thumb_lx as i32,
+        frame.thumb_ly as i32,
+        frame.thumb_rx as i32,
+        frame.thumb_ry as i32,
+        frame.left_trigger as i32,
+        frame.right_trigger as i32,
+    ]
+}
+
+/// アナログ値を持つフレームが含まれるか（保存時にアナログ列を出力するかの判定）
+pub fn has_analog_values(frames: &[InputFrame]) -> bool {
+    frames.iter().any(|frame| analog_values(frame).iter().any(|&v| v != 0))
+}
+
+/// 保存時の列（duration, direction とアナログ列を除く）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveColumn {
+    pub name: String,
+    pub text: bool,  // 数値以外の列（extra_columns から値を取る。無い行は空欄）
+}
+
+/// 保存する列を決める
+///
+/// button_order のボタン列に続けて、button_order に無いボタン列と数値以外の列を
+/// フレーム中の出現順に並べる。読み込んだ時の列を落とさずに書き戻すため。
+pub fn save_columns(frames: &[InputFrame], button_order: &[String]) -> Vec<SaveColumn> {
+    let mut columns: Vec<SaveColumn> = button_order
+        .iter()
+        .filter(|name| !is_analog_column(name))
+        .map(|name| SaveColumn { name: name.clone(), text: false })
+        .collect();
+    for frame in frames {
+        let mut buttons: Vec<&String> = frame.buttons.keys().collect();
+        buttons.sort();
+        for name in buttons {
+            if !is_analog_column(name) && !columns.iter().any(|c| &c.name == name) {
+                columns.push(SaveColumn { name: name.clone(), text: false });
+            }
+        }
+        for (name, _) in &frame.extra_columns {
+            if !columns.iter().any(|c| &c.name == name) {
+                columns.push(SaveColumn { name: name.clone(), text: true });
+            }
+        }
     }
-    
-    let mut reader = ReaderBuilder::new()
+    columns
+}
+
+/// フレームを columns の順の値にする（ボタン列は数値、数値以外の列は extra_columns の値）
+pub fn column_values(frame: &InputFrame, columns: &[SaveColumn]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|column| {
+            if let Some(value) = frame.buttons.get(&column.name) {
+                return value.to_string();
+            }
+            let extra = frame.extra_columns.iter().find(|(name, _)| name == &column.name);
+            match (extra, column.text) {
+                (Some((_, value)), _) => value.clone(),
+                (None, true) => String::new(),
+                (None, false) => "0".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// ラベル行の接頭辞（`label:名前` の行は次のステップに名前を付ける）
+pub const LABEL_PREFIX: &str = "label:";
+
+// ラベル行ならラベル名を返す
+fn parse_label(record: &csv::StringRecord) -> Option<String> {
+    let name = record.get(0)?.trim().strip_prefix(LABEL_PREFIX)?;
+    Some(name.trim().to_string())
+}
+
+// `#` で始まる行はコメントとして読み飛ばす（空行は csv クレートが読み飛ばす）
+fn sequence_reader(path: &Path) -> Result<csv::Reader<std::fs::File>> {
+    ReaderBuilder::new()
         .has_headers(true)
+        .comment(Some(b'#'))
+        .flexible(true)
         .from_path(path)
-        .context(format!("CSVファイルを開けませんでした: {:?}", path))?;
+        .context(format!("CSVファイルを開けませんでした: {:?}", path))
+}
 
-    let headers = reader.headers()?.clone();
-    let mut frames = Vec::new();
+// 読み込み進捗を通知する間隔（行数）
+const PROGRESS_INTERVAL_ROWS: usize = 4096;
 
-    for result in reader.records() {
-        let record = result?;
-        
+/// シーケンスCSVを1行ずつ読み込むイテレーター
+///
+/// 巨大なファイルでも全体を文字列として保持せず、行バッファを使い回してステップに変換する。
+/// `label:` 行は読み進めながら labels に溜め、take_labels で取り出す。
+pub struct CsvFrameReader {
+    reader: csv::Reader<std::fs::File>,
+    // (列番号, 列名, アナログ列か)
+    columns: Vec<(usize, String, bool)>,
+    record: csv::StringRecord,
+    labels: Vec<SequenceLabel>,
+    steps: usize,
+}
+
+impl CsvFrameReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut reader = sequence_reader(path)?;
+        let columns = reader
+            .headers()?
+            .iter()
+            .enumerate()
+            .skip(2)
+            .map(|(i, header)| (i, header.to_string(), is_analog_column(header)))
+            .collect();
+        Ok(Self {
+            reader,
+            columns,
+            record: csv::StringRecord::new(),
+            labels: Vec::new(),
+            steps: 0,
+        })
+    }
+
+    /// ここまでに読んだバイト数（進捗表示用）
+    pub fn bytes_read(&self) -> u64 {
+        self.reader.position().byte()
+    }
+
+    /// ここまでに読んだ `label:` 行のラベル
+    pub fn take_labels(&mut self) -> Vec<SequenceLabel> {
+        std::mem::take(&mut self.labels)
+    }
+
+    fn parse_frame(&self) -> Result<InputFrame> {
+        let record = &self.record;
         let duration: u32 = record.get(0)
             .ok_or_else(|| anyhow::anyhow!("Missing duration"))?
             .parse()?;
@@ -34,34 +172,65 @@ pub fn load_csv(path: &Path) -> Result<Vec<InputFrame>> {
             .ok_or_else(|| anyhow::anyhow!("Missing direction"))?
             .parse()?;
 
-        let mut buttons = HashMap::new();
+        let mut frame = InputFrame {
+            direction,
+            buttons: HashMap::with_capacity(self.columns.len()),
+            ..InputFrame::neutral(duration)
+        };
         
-        // duration, direction以外のカラムをボタンとして処理
-        for (i, header) in headers.iter().enumerate().skip(2) {
-            if let Some(value_str) = record.get(i) {
-                if let Ok(value) = value_str.parse::<u8>() {
-                    buttons.insert(header.to_string(), value);
+        // duration, direction以外のカラムをボタン（予約列はアナログ軸、数値以外は extra_columns）として処理
+        for (i, header, analog) in &self.columns {
+            let Some(value_str) = record.get(*i) else { continue };
+            if *analog {
+                if let Ok(value) = value_str.trim().parse::<i64>() {
+                    set_analog_value(&mut frame, header, value);
                 }
+            } else if let Ok(value) = value_str.parse::<u8>() {
+                frame.buttons.insert(header.clone(), value);
+            } else if !value_str.is_empty() {
+                frame.extra_columns.push((header.clone(), value_str.to_string()));
             }
         }
+        Ok(frame)
+    }
+}
 
-        frames.push(InputFrame {
-            duration,
-            direction,
-            buttons,
-            thumb_lx: 0,
-            thumb_ly: 0,
-            thumb_rx: 0,
-            thumb_ry: 0,
-            left_trigger: 0,
-            right_trigger: 0,
-        });
+impl Iterator for CsvFrameReader {
+    type Item = Result<InputFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_record(&mut self.record) {
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e.into())),
+                Ok(true) => {}
+            }
+            if let Some(name) = parse_label(&self.record) {
+                self.labels.push(SequenceLabel { name, step: self.steps });
+                continue;
+            }
+            self.steps += 1;
+            return Some(self.parse_frame());
+        }
     }
+}
 
-    Ok(frames)
+pub fn load_csv(path: &Path) -> Result<Vec<InputFrame>> {
+    load_csv_with_labels(path).map(|(frames, _)| frames)
 }
 
-pub fn get_csv_button_names(path: &Path) -> Result<Vec<String>> {
+/// CSVを読み込み、フレームと `label:` 行のラベルを返す
+///
+/// `#` で始まるコメント行と空行は読み飛ばす。
+pub fn load_csv_with_labels(path: &Path) -> Result<(Vec<InputFrame>, Vec<SequenceLabel>)> {
+    load_csv_with_progress(path, |_| {})
+}
+
+/// 進捗を通知しながらCSVを読み込む（on_progress は一定行数ごとに読み込んだバイト数で呼ばれる）
+pub fn load_csv_with_progress<F>(path: &Path, mut on_progress: F) -> Result<(Vec<InputFrame>, Vec<SequenceLabel>)>
+where
+    F: FnMut(u64),
+{
     // ファイルの存在チェック
     if !path.exists() {
         anyhow::bail!("ファイルが見つかりません: {:?}", path);
@@ -72,18 +241,374 @@ pub fn get_csv_button_names(path: &Path) -> Result<Vec<String>> {
         anyhow::bail!("ファイルにアクセスできません: {:?} ({})", path, e);
     }
     
+    let mut reader = CsvFrameReader::open(path)?;
+    let mut frames = Vec::new();
+    while let Some(frame) = reader.next() {
+        frames.push(frame?);
+        if frames.len() % PROGRESS_INTERVAL_ROWS == 0 {
+            on_progress(reader.bytes_read());
+        }
+    }
+    let mut labels = reader.take_labels();
+
+    // 後ろにステップが無いラベルは無視する
+    labels.retain(|label| {
+        let valid = label.step < frames.len();
+        if !valid {
+            eprintln!("[CSV] 警告: ラベル '{}' の後にステップがありません", label.name);
+        }
+        valid
+    });
+
+    Ok((frames, labels))
+}
+
+/// `#` で始まるコメント行（text は行全体）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceComment {
+    pub step: Option<usize>,  // 直後のステップ（行番号、0始まり）。ヘッダー行より前なら None
+    pub text: String,
+}
+
+/// ステップ以外の行（`label:` 行と `#` コメント行）。保存し直すときに書き戻すために読む
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SequenceAnnotations {
+    pub labels: Vec<SequenceLabel>,
+    pub comments: Vec<SequenceComment>,
+}
+
+impl SequenceAnnotations {
+    /// ステップの増減に合わせて、ラベル・コメントの直後のステップを付け替える
+    pub fn remap_steps(&mut self, remap: impl Fn(usize) -> usize) {
+        for label in &mut self.labels {
+            label.step = remap(label.step);
+        }
+        for comment in &mut self.comments {
+            comment.step = comment.step.map(&remap);
+        }
+    }
+}
+
+/// CSVのラベル行とコメント行を読み込む（コメント行は元の文字列のまま）
+pub fn read_annotations(path: &Path) -> Result<SequenceAnnotations> {
+    let content = std::fs::read(path).context(format!("CSVファイルを開けませんでした: {:?}", path))?;
+    // コメント行の元の文字列を取り出すため、コメントも1行のレコードとして読む
     let mut reader = ReaderBuilder::new()
-        .has_headers(true)
-        .from_path(path)
-        .context(format!("CSVファイルを開けませんでした: {:?}", path))?;
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_slice());
+    let mut record = csv::StringRecord::new();
+    let mut annotations = SequenceAnnotations::default();
+    let mut header_read = false;
+    let mut steps = 0;
+    loop {
+        let start = reader.position().byte() as usize;
+        if !reader.read_record(&mut record)? {
+            break;
+        }
+        let end = reader.position().byte() as usize;
+        if record.get(0).is_some_and(|field| field.starts_with('#')) {
+            let text = String::from_utf8_lossy(&content[start..end]);
+            annotations.comments.push(SequenceComment {
+                step: header_read.then_some(steps),
+                text: text.trim_matches(['\r', '\n']).to_string(),
+            });
+        } else if !header_read {
+            header_read = true;
+        } else if let Some(name) = parse_label(&record) {
+            annotations.labels.push(SequenceLabel { name, step: steps });
+        } else {
+            steps += 1;
+        }
+    }
+    Ok(annotations)
+}
+
+/// シーケンスCSVの内容を組み立てる
+///
+/// 列は save_columns で決め、アナログ値を持つフレームがある場合のみアナログ列を出力する。
+/// ラベル行とコメント行は元の位置（直後のステップの前）に書き戻す。
+pub fn sequence_csv_bytes(
+    frames: &[InputFrame],
+    button_order: &[String],
+    annotations: &SequenceAnnotations,
+) -> Result<Vec<u8>> {
+    let columns = save_columns(frames, button_order);
+    let write_analog = has_analog_values(frames);
+
+    let mut header = vec!["duration".to_string(), "direction".to_string()];
+    header.extend(columns.iter().map(|c| c.name.clone()));
+    if write_analog {
+        header.extend(ANALOG_COLUMNS.iter().map(|c| c.to_string()));
+    }
+
+    let mut out = Vec::new();
+    let mut comments = annotations.comments.iter().peekable();
+    let mut labels = annotations.labels.iter().peekable();
+    // ヘッダー行より前のコメント
+    while let Some(comment) = comments.next_if(|c| c.step.is_none()) {
+        out.extend_from_slice(comment.text.as_bytes());
+        out.push(b'\n');
+    }
+    {
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(&mut out);
+        writer.write_record(&header)?;
+        writer.flush()?;
+    }
+
+    // コメント行はCSVのレコードとして書くと引用符が付くため、次のコメントの位置までずつ書き出す
+    // （末尾より後ろを指すラベル・コメントは最後にまとめて書く）
+    let mut step = 0;
+    loop {
+        while let Some(comment) = comments.next_if(|c| c.step.is_some_and(|s| s.min(frames.len()) <= step)) {
+            out.extend_from_slice(comment.text.as_bytes());
+            out.push(b'\n');
+        }
+        let until = comments
+            .peek()
+            .and_then(|c| c.step)
+            .unwrap_or(frames.len())
+            .min(frames.len());
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(&mut out);
+        loop {
+            while let Some(label) = labels.next_if(|label| label.step.min(frames.len()) <= step) {
+                writer.write_record([format!("{}{}", LABEL_PREFIX, label.name)])?;
+            }
+            if step >= until {
+                break;
+            }
+            let frame = &frames[step];
+            let mut values = vec![frame.duration.to_string(), frame.direction.to_string()];
+            values.extend(column_values(frame, &columns));
+            if write_analog {
+                values.extend(analog_values(frame).iter().map(|v| v.to_string()));
+            }
+            writer.write_record(&values)?;
+            step += 1;
+        }
+        writer.flush()?;
+        drop(writer);
+        if step >= frames.len() && comments.peek().is_none() {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// シーケンスファイルのボタン名（CSVはヘッダー行、バイナリ形式はヘッダーのボタン名）
+pub fn get_csv_button_names(path: &Path) -> Result<Vec<String>> {
+    // ファイルの存在チェック
+    if !path.exists() {
+        anyhow::bail!("ファイルが見つかりません: {:?}", path);
+    }
+    
+    // ファイルが読み取り可能かチェック
+    if let Err(e) = std::fs::metadata(path) {
+        anyhow::bail!("ファイルにアクセスできません: {:?} ({})", path, e);
+    }
+
+    if crate::sequence_binary::is_binary_sequence(path) {
+        let reader = crate::sequence_binary::BinarySequenceReader::open(path)?;
+        return Ok(reader.button_names().to_vec());
+    }
+    
+    let mut reader = sequence_reader(path)?;
 
     let headers = reader.headers()?;
     
-    // 3列目以降（インデックス2以降）がボタン名
+    // 3列目以降（インデックス2以降）がボタン名（アナログ軸の予約列は除く）
     let button_names: Vec<String> = headers.iter()
         .skip(2)
+        .filter(|s| !is_analog_column(s))
         .map(|s| s.to_string())
         .collect();
 
     Ok(button_names)
 }
+
+/// フレーム数の列を倍率で変換する（誤差拡散）
+///
+/// 端数は次のステップへ持ち越し、累積フレーム数が常に 倍率 × 元の累積 に近くなるようにする。
+/// 各ステップは最低1フレーム。
+pub fn scale_durations(durations: &[u32], scale: f64) -> Vec<u32> {
+    let mut original_total = 0u64;
+    let mut scaled_total = 0u64;
+    durations
+        .iter()
+        .map(|&duration| {
+            original_total += duration as u64;
+            let target = (original_total as f64 * scale).round() as u64;
+            let scaled = target.saturating_sub(scaled_total).max(1);
+            scaled_total += scaled;
+            scaled as u32
+        })
+        .collect()
+}
+
+/// FPS変換のステップごとの結果
+#[derive(Debug, Clone, Serialize)]
+pub struct FpsConversionStep {
+    pub step: usize,
+    pub original_frames: u32,
+    pub converted_frames: u32,
+    pub exact_frames: f64,       // 丸める前のフレーム数（元のフレーム数 × 変換後FPS / 変換前FPS）
+    pub rounding_error: f64,     // このステップの丸め誤差（フレーム）
+    pub cumulative_error: f64,   // このステップ終了時点の累積誤差（フレーム）
+}
+
+/// FPS変換の結果
+#[derive(Debug, Clone, Serialize)]
+pub struct FpsConversionReport {
+    pub from_fps: u32,
+    pub to_fps: u32,
+    pub original_total_frames: u32,
+    pub converted_total_frames: u32,
+    pub max_abs_cumulative_error: f64,
+    pub steps: Vec<FpsConversionStep>,
+}
+
+/// シーケンスCSVのフレーム数を別のFPSに変換して保存
+///
+/// duration列以外はそのまま書き出す。
+pub fn convert_csv_fps(path: &Path, from_fps: u32, to_fps: u32, out_path: &Path) -> Result<FpsConversionReport> {
+    if from_fps == 0 || to_fps == 0 {
+        anyhow::bail!("FPSは1以上を指定してください");
+    }
+    let scale = to_fps as f64 / from_fps as f64;
+
+    // コメント行は書き出されない。ラベル行はそのまま残す
+    let mut reader = sequence_reader(path)?;
+    let headers = reader.headers()?.clone();
+    let rows = reader.records().collect::<std::result::Result<Vec<_>, _>>()?;
+    let records: Vec<&csv::StringRecord> = rows.iter().filter(|r| parse_label(r).is_none()).collect();
+
+    let durations = records
+        .iter()
+        .map(|record| -> Result<u32> {
+            Ok(record.get(0).ok_or_else(|| anyhow::anyhow!("Missing duration"))?.parse()?)
+        })
+        .collect::<Result<Vec<u32>>>()?;
+    let converted = scale_durations(&durations, scale);
+
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_path(out_path)
+        .context(format!("CSVファイルを作成できませんでした: {:?}", out_path))?;
+    writer.write_record(&headers)?;
+    let mut converted_iter = converted.iter();
+    for row in &rows {
+        if parse_label(row).is_some() {
+            writer.write_record(row)?;
+            continue;
+        }
+        let frames = converted_iter.next().copied().unwrap_or_default().to_string();
+        let row = std::iter::once(frames.as_str()).chain(row.iter().skip(1));
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+
+    let mut steps = Vec::with_capacity(durations.len());
+    let mut exact_total = 0.0;
+    let mut converted_total = 0u32;
+    for (step, (&original, &frames)) in durations.iter().zip(&converted).enumerate() {
+        let exact = original as f64 * scale;
+        exact_total += exact;
+        converted_total += frames;
+        steps.push(FpsConversionStep {
+            step,
+            original_frames: original,
+            converted_frames: frames,
+            exact_frames: exact,
+            rounding_error: frames as f64 - exact,
+            cumulative_error: converted_total as f64 - exact_total,
+        });
+    }
+
+    Ok(FpsConversionReport {
+        from_fps,
+        to_fps,
+        original_total_frames: durations.iter().sum(),
+        converted_total_frames: converted_total,
+        max_abs_cumulative_error: steps.iter().map(|s| s.cumulative_error.abs()).fold(0.0, f64::max),
+        steps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_mappings_avoid_analog_columns() {
+        for controller_type in [crate::types::ControllerType::Xbox, crate::types::ControllerType::DualShock4] {
+            let mapping = crate::types::ButtonMapping::default_for(controller_type);
+            for button in &mapping.mapping {
+                assert!(!is_analog_column(&button.user_button), "{}", button.user_button);
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_csv_reads_analog_columns() {
+        let dir = crate::test_support::TempDir::new("csv_analog_columns_test");
+        let path = dir.join("sequence.csv");
+        std::fs::write(
+            &path,
+            "duration,direction,button1,LX,LY,RX,RY,LT,RT,note\n3,6,1,32767,-40000,0,100,255,300,hit\n",
+        )
+        .unwrap();
+        let frames = load_csv(&path).unwrap();
+
+        assert_eq!(frames.len(), 1);
+        let frame = &frames[0];
+        assert_eq!(frame.buttons.len(), 1);
+        assert_eq!(frame.extra_columns, vec![("note".to_string(), "hit".to_string())]);
+        assert_eq!(frame.buttons.get("button1"), Some(&1));
+        assert_eq!(analog_values(frame), [32767, -32768, 0, 100, 255, 255]);
+    }
+
+    #[test]
+    fn test_save_columns_keep_unknown_columns() {
+        let dir = crate::test_support::TempDir::new("csv_unknown_columns_test");
+        let path = dir.join("sequence.csv");
+        std::fs::write(&path, "duration,direction,A,turbo,memo\n3,6,1,1,start\n2,5,0,0,\n").unwrap();
+        let frames = load_csv(&path).unwrap();
+
+        let columns = save_columns(&frames, &["A".to_string(), "B".to_string()]);
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["A", "B", "turbo", "memo"]);
+        assert_eq!(column_values(&frames[0], &columns), vec!["1", "0", "1", "start"]);
+        assert_eq!(column_values(&frames[1], &columns), vec!["0", "0", "0", ""]);
+    }
+
+    #[test]
+    fn test_load_csv_skips_comments_and_reads_labels() {
+        let dir = crate::test_support::TempDir::new("csv_labels_test");
+        let path = dir.join("sequence.csv");
+        std::fs::write(
+            &path,
+            "# コンボ練習\nduration,direction,button1\n3,5,0\n\nlabel: combo\n# 236A\n2,2,0\n2,3,0\n4,6,1\n",
+        )
+        .unwrap();
+        let (frames, labels) = load_csv_with_labels(&path).unwrap();
+
+        assert_eq!(frames.len(), 4);
+        assert_eq!(labels, vec![SequenceLabel { name: "combo".to_string(), step: 1 }]);
+    }
+
+    #[test]
+    fn test_labels_and_comments_round_trip() {
+        let dir = crate::test_support::TempDir::new("csv_annotations_test");
+        let path = dir.join("sequence.csv");
+        let csv = "# コンボ練習\nduration,direction,button1\n3,5,0\nlabel:combo\n# 236A, 早めに\n2,2,0\n4,6,1\n# 終わり\n";
+        std::fs::write(&path, csv).unwrap();
+        let frames = load_csv(&path).unwrap();
+        let annotations = read_annotations(&path).unwrap();
+
+        assert_eq!(annotations.comments[0].step, None);
+        assert_eq!(annotations.comments[1].step, Some(1));
+        assert_eq!(annotations.comments[2].step, Some(3));
+        let written = sequence_csv_bytes(&frames, &["button1".to_string()], &annotations).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), csv);
+    }
+}
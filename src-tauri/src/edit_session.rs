@@ -0,0 +1,257 @@
+//! エディタの編集セッション
+//!
+//! 編集中のシーケンスをバックエンドで保持し、行の挿入・削除・複製などの操作と
+//! 元に戻す/やり直しの履歴を管理する。同じファイルを開いた複数のエディタウィンドウは
+//! 同じセッションを操作するため、ウィンドウごとに内容が食い違わない。
+//!
+//! 操作はすべて「index から何行か取り除き、何行か挿入する」置き換え（FrameSplice）として扱う。
+//! 履歴には変更した行だけを残し、ウィンドウへの通知も置き換えた行だけを送る。
+
+use crate::types::InputFrame;
+use anyhow::Result;
+use serde::Serialize;
+
+/// 元に戻せる操作の最大数
+pub const MAX_HISTORY: usize = 100;
+
+/// 行の置き換え（index から removed 行を取り除き、その位置に inserted を挿入する）
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameSplice {
+    pub index: usize,
+    pub removed: usize,
+    pub inserted: Vec<InputFrame>,
+}
+
+/// セッションを開いたときの状態（open_edit_session の戻り値）
+#[derive(Debug, Clone, Serialize)]
+pub struct EditSessionState {
+    pub path: String,
+    pub frames: Vec<InputFrame>,
+    pub revision: u64,    // 変更のたびに増える（ウィンドウ間で最新か判定する）
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub dirty: bool,      // 保存した内容から変更があるか
+}
+
+/// 操作による変更（各操作の戻り値、"edit-session-changed" イベントのペイロード）
+#[derive(Debug, Clone, Serialize)]
+pub struct EditSessionChange {
+    pub path: String,
+    pub splice: Option<FrameSplice>, // 内容が変わらなかった操作（保存・戻せる操作が無い undo など）では None
+    pub revision: u64,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub dirty: bool,
+}
+
+// 履歴の1操作（置き換えの前後の行）
+struct EditOp {
+    id: u64,               // この操作を適用した後の内容の ID
+    index: usize,
+    before: Vec<InputFrame>,
+    after: Vec<InputFrame>,
+}
+
+pub struct EditSession {
+    frames: Vec<InputFrame>,
+    undo: Vec<EditOp>,
+    redo: Vec<EditOp>,
+    revision: u64,
+    next_op_id: u64,
+    base_id: u64,          // 元に戻せる最古の状態の内容の ID（履歴が溢れると進む）
+    saved_id: u64,         // 最後に保存した内容の ID
+    handles: usize,        // セッションを開いているウィンドウの数
+}
+
+impl EditSession {
+    pub fn new(frames: Vec<InputFrame>) -> Self {
+        Self {
+            frames,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            revision: 0,
+            next_op_id: 1,
+            base_id: 0,
+            saved_id: 0,
+            handles: 1,
+        }
+    }
+
+    pub fn frames(&self) -> &[InputFrame] {
+        &self.frames
+    }
+
+    /// 別のウィンドウが同じセッションを開いた
+    pub fn acquire(&mut self) {
+        self.handles += 1;
+    }
+
+    /// ウィンドウがセッションを閉じた（どのウィンドウも開いていなくなれば true）
+    pub fn release(&mut self) -> bool {
+        self.handles = self.handles.saturating_sub(1);
+        self.handles == 0
+    }
+
+    // 現在の内容の ID（同じ内容に戻れば同じ ID になる）
+    fn content_id(&self) -> u64 {
+        self.undo.last().map_or(self.base_id, |op| op.id)
+    }
+
+    pub fn state(&self, path: &str) -> EditSessionState {
+        EditSessionState {
+            path: path.to_string(),
+            frames: self.frames.clone(),
+            revision: self.revision,
+            can_undo: !self.undo.is_empty(),
+            can_redo: !self.redo.is_empty(),
+            dirty: self.content_id() != self.saved_id,
+        }
+    }
+
+    pub fn change(&self, path: &str, splice: Option<FrameSplice>) -> EditSessionChange {
+        EditSessionChange {
+            path: path.to_string(),
+            splice,
+            revision: self.revision,
+            can_undo: !self.undo.is_empty(),
+            can_redo: !self.redo.is_empty(),
+            dirty: self.content_id() != self.saved_id,
+        }
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_id = self.content_id();
+    }
+
+    // 行を置き換え、元に戻せるよう置き換えた行を履歴に積む
+    fn apply(&mut self, index: usize, removed: usize, inserted: Vec<InputFrame>) -> FrameSplice {
+        let before: Vec<InputFrame> = self.frames.splice(index..index + removed, inserted.iter().cloned()).collect();
+        if self.undo.len() >= MAX_HISTORY {
+            let oldest = self.undo.remove(0);
+            self.base_id = oldest.id;
+        }
+        self.undo.push(EditOp { id: self.next_op_id, index, before, after: inserted.clone() });
+        self.next_op_id += 1;
+        self.redo.clear();
+        self.revision += 1;
+        FrameSplice { index, removed, inserted }
+    }
+
+    fn check_range(&self, start: usize, end: usize) -> Result<()> {
+        if start >= end || end > self.frames.len() {
+            anyhow::bail!("範囲が不正です: {}..{} (行数: {})", start, end, self.frames.len());
+        }
+        Ok(())
+    }
+
+    /// index の位置に行を挿入（index が行数と同じなら末尾に追加）
+    pub fn insert(&mut self, index: usize, frames: Vec<InputFrame>) -> Result<Option<FrameSplice>> {
+        if index > self.frames.len() {
+            anyhow::bail!("挿入位置が不正です: {} (行数: {})", index, self.frames.len());
+        }
+        if frames.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(self.apply(index, 0, frames)))
+    }
+
+    /// start..end の行を削除
+    pub fn delete(&mut self, start: usize, end: usize) -> Result<Option<FrameSplice>> {
+        self.check_range(start, end)?;
+        Ok(Some(self.apply(start, end - start, Vec::new())))
+    }
+
+    /// start..end の行を複製し、範囲の直後に挿入
+    pub fn duplicate(&mut self, start: usize, end: usize) -> Result<Option<FrameSplice>> {
+        self.check_range(start, end)?;
+        let copies = self.frames[start..end].to_vec();
+        Ok(Some(self.apply(end, 0, copies)))
+    }
+
+    /// index の行を置き換える
+    pub fn update(&mut self, index: usize, frame: InputFrame) -> Result<Option<FrameSplice>> {
+        self.check_range(index, index + 1)?;
+        Ok(Some(self.apply(index, 1, vec![frame])))
+    }
+
+    /// 直前の操作を元に戻す（戻せる操作が無ければ None）
+    pub fn undo(&mut self) -> Option<FrameSplice> {
+        let op = self.undo.pop()?;
+        let splice = FrameSplice { index: op.index, removed: op.after.len(), inserted: op.before.clone() };
+        self.frames.splice(op.index..op.index + op.after.len(), op.before.iter().cloned());
+        self.redo.push(op);
+        self.revision += 1;
+        Some(splice)
+    }
+
+    /// 元に戻した操作をやり直す（やり直せる操作が無ければ None）
+    pub fn redo(&mut self) -> Option<FrameSplice> {
+        let op = self.redo.pop()?;
+        let splice = FrameSplice { index: op.index, removed: op.before.len(), inserted: op.after.clone() };
+        self.frames.splice(op.index..op.index + op.before.len(), op.after.iter().cloned());
+        self.undo.push(op);
+        self.revision += 1;
+        Some(splice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(duration: u32) -> InputFrame {
+        InputFrame::neutral(duration)
+    }
+
+    fn durations(session: &EditSession) -> Vec<u32> {
+        session.frames().iter().map(|f| f.duration).collect()
+    }
+
+    #[test]
+    fn test_edit_operations_with_undo_redo() {
+        let mut session = EditSession::new(vec![frame(1), frame(2), frame(3)]);
+        session.duplicate(0, 2).unwrap();
+        assert_eq!(durations(&session), vec![1, 2, 1, 2, 3]);
+        session.delete(1, 3).unwrap();
+        assert_eq!(durations(&session), vec![1, 2, 3]);
+        session.insert(3, vec![frame(9)]).unwrap();
+        assert_eq!(durations(&session), vec![1, 2, 3, 9]);
+
+        assert!(session.undo().is_some());
+        let splice = session.undo().unwrap();
+        assert_eq!((splice.index, splice.removed, splice.inserted.len()), (1, 0, 2));
+        assert_eq!(durations(&session), vec![1, 2, 1, 2, 3]);
+        assert!(session.redo().is_some());
+        assert_eq!(durations(&session), vec![1, 2, 3]);
+        assert!(session.state("a.csv").dirty);
+
+        // 新しい操作でやり直し履歴は消える
+        session.update(0, frame(7)).unwrap();
+        assert!(session.redo().is_none());
+        assert!(session.delete(2, 5).is_err());
+    }
+
+    #[test]
+    fn test_undo_to_saved_content_is_not_dirty() {
+        let mut session = EditSession::new(vec![frame(1), frame(2)]);
+        session.update(0, frame(5)).unwrap();
+        session.mark_saved();
+        session.delete(0, 1).unwrap();
+        assert!(session.state("a.csv").dirty);
+
+        session.undo();
+        assert!(!session.state("a.csv").dirty);
+        session.undo();
+        assert!(session.state("a.csv").dirty);
+        session.redo();
+        assert!(!session.state("a.csv").dirty);
+    }
+
+    #[test]
+    fn test_session_is_released_by_last_window() {
+        let mut session = EditSession::new(Vec::new());
+        session.acquire();
+        assert!(!session.release());
+        assert!(session.release());
+    }
+}
@@ -0,0 +1,168 @@
+//! 読み込み中のファイルの変更監視
+//!
+//! 使用中のボタンマッピングJSONと再生用シーケンスを監視し、外部のエディタで保存されたら
+//! コールバックで再読み込みさせる（lib.rs で "file-reloaded" イベントを発行する）。
+//! 一時ファイルからのリネームで保存するエディタにも対応するため、ファイルではなく
+//! 親ディレクトリを監視してパスで絞り込む。保存1回で複数の通知が来るため短時間の通知はまとめる。
+//! アプリ自身が保存した場合は mark_saved で更新日時とサイズを記録し、それと変わらない通知は無視する。
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// 連続した通知をまとめる待ち時間
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// 監視対象の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchKind {
+    Mapping,
+    Sequence,
+}
+
+/// "file-reloaded" イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReloadedEvent {
+    pub kind: WatchKind,
+    pub path: String,           // 読み込み時に指定されたパス
+    pub error: Option<String>,  // 再読み込みに失敗した場合のエラー
+}
+
+struct WatchTarget {
+    file: PathBuf,
+    key: String,
+    saved: Option<FileStamp>, // アプリ自身が最後に保存したときのファイルの状態
+}
+
+/// 変更の判定に使うファイルの更新日時とサイズ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self { modified: metadata.modified().ok()?, len: metadata.len() })
+    }
+}
+
+pub struct FileWatcher {
+    watcher: Option<RecommendedWatcher>,  // 作成に失敗した場合は監視しない
+    targets: Arc<Mutex<HashMap<WatchKind, WatchTarget>>>,
+    watched_dirs: HashSet<PathBuf>,
+}
+
+impl FileWatcher {
+    /// on_change は変更された対象の種類と読み込み時のパスで呼ばれる（監視用スレッドから）
+    pub fn new<F>(on_change: F) -> Self
+    where
+        F: Fn(WatchKind, String) + Send + 'static,
+    {
+        let targets: Arc<Mutex<HashMap<WatchKind, WatchTarget>>> = Arc::new(Mutex::new(HashMap::new()));
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            let Ok(event) = result else { return };
+            if event.kind.is_modify() || event.kind.is_create() {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        });
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                println!("[ファイル監視] 監視を開始できませんでした: {}", e);
+                None
+            }
+        };
+
+        let thread_targets = targets.clone();
+        std::thread::spawn(move || {
+            while let Ok(first) = rx.recv() {
+                let mut changed = HashSet::from([first]);
+                while let Ok(path) = rx.recv_timeout(DEBOUNCE) {
+                    changed.insert(path);
+                }
+                let matched: Vec<(WatchKind, String)> = thread_targets
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|(_, target)| changed.contains(&target.file))
+                    // アプリ自身の保存から変わっていなければ再読み込みしない
+                    .filter(|(_, target)| target.saved.is_none() || target.saved != FileStamp::of(&target.file))
+                    .map(|(&kind, target)| (kind, target.key.clone()))
+                    .collect();
+                for (kind, key) in matched {
+                    on_change(kind, key);
+                }
+            }
+        });
+
+        Self {
+            watcher,
+            targets,
+            watched_dirs: HashSet::new(),
+        }
+    }
+
+    /// 種類ごとに1つのファイルを監視する（同じ種類の以前の対象は置き換える）
+    pub fn watch(&mut self, kind: WatchKind, path: &Path, key: String) {
+        // 通知のパスと比較できるよう正規化しておく
+        let file = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.targets.lock().unwrap().insert(kind, WatchTarget { file, key, saved: None });
+        self.update_dirs();
+    }
+
+    /// アプリ自身がファイルを保存した直後に呼ぶ（監視中のファイルなら、この保存による通知を無視する）
+    pub fn mark_saved(&self, path: &Path) {
+        let file = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let stamp = FileStamp::of(&file);
+        for target in self.targets.lock().unwrap().values_mut() {
+            if target.file == file {
+                target.saved = stamp;
+            }
+        }
+    }
+
+    pub fn unwatch(&mut self, kind: WatchKind) {
+        self.targets.lock().unwrap().remove(&kind);
+        self.update_dirs();
+    }
+
+    // 監視対象の親ディレクトリに合わせて監視を付け外しする
+    fn update_dirs(&mut self) {
+        let Some(watcher) = self.watcher.as_mut() else { return };
+        let needed: HashSet<PathBuf> = self
+            .targets
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|target| target.file.parent().map(Path::to_path_buf))
+            .collect();
+
+        for dir in self.watched_dirs.difference(&needed) {
+            let _ = watcher.unwatch(dir);
+        }
+        let mut watched = HashSet::new();
+        for dir in needed {
+            if self.watched_dirs.contains(&dir) {
+                watched.insert(dir);
+                continue;
+            }
+            match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    watched.insert(dir);
+                }
+                Err(e) => println!("[ファイル監視] {:?} を監視できませんでした: {}", dir, e),
+            }
+        }
+        self.watched_dirs = watched;
+    }
+}
@@ -0,0 +1,216 @@
+//! 読み込んだシーケンスのキャッシュ
+//!
+//! 同じCSVを繰り返し読み込む場合に解析を省略する。キーは正規化したパスで、
+//! 読み込み時の更新日時と異なる場合は古い内容とみなして読み込み直させる。
+//! 推定メモリ使用量が上限を超えたら、最後に使われてから最も時間が経ったものから破棄する。
+
+use crate::types::{InputFrame, SequenceLabel};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// キャッシュ上限のデフォルト（MB）
+pub const DEFAULT_MAX_MEGABYTES: u32 = 256;
+
+struct CacheEntry {
+    modified: Option<SystemTime>,
+    frames: Arc<Vec<InputFrame>>,
+    labels: Vec<SequenceLabel>,
+    bytes: usize,
+    last_used: u64,
+}
+
+/// キャッシュの使用状況
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub total_bytes: usize,  // 推定メモリ使用量
+    pub max_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,      // 上限を超えたため破棄した数
+}
+
+pub struct FrameCache {
+    entries: HashMap<PathBuf, CacheEntry>,
+    max_bytes: usize,
+    tick: u64,  // 最後に使われた順序の基準
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+// 同じファイルを別の書き方のパスで開いても同じキーになるよう正規化する
+fn cache_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// フレーム列のおおよそのメモリ使用量（バイト）
+pub fn estimate_bytes(frames: &[InputFrame], labels: &[SequenceLabel]) -> usize {
+    let frame_bytes: usize = frames
+        .iter()
+        .map(|frame| {
+            let buttons: usize = frame
+                .buttons
+                .keys()
+                .map(|name| std::mem::size_of::<(String, u8)>() + name.len())
+                .sum();
+            let extra: usize = frame
+                .extra_columns
+                .iter()
+                .map(|(name, value)| std::mem::size_of::<(String, String)>() + name.len() + value.len())
+                .sum();
+            std::mem::size_of::<InputFrame>() + buttons + extra
+        })
+        .sum();
+    let label_bytes: usize = labels
+        .iter()
+        .map(|label| std::mem::size_of::<SequenceLabel>() + label.name.len())
+        .sum();
+    frame_bytes + label_bytes
+}
+
+impl FrameCache {
+    pub fn new(max_megabytes: u32) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_bytes: max_megabytes as usize * 1024 * 1024,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// キャッシュから取得（ファイルが更新されていれば破棄して None）
+    pub fn get(&mut self, path: &Path) -> Option<(Arc<Vec<InputFrame>>, Vec<SequenceLabel>)> {
+        let key = cache_key(path);
+        let modified = modified_time(path);
+        let fresh = match self.entries.get(&key) {
+            Some(entry) => entry.modified == modified,
+            None => {
+                self.misses += 1;
+                return None;
+            }
+        };
+        if !fresh {
+            self.entries.remove(&key);
+            self.misses += 1;
+            return None;
+        }
+
+        self.tick += 1;
+        self.hits += 1;
+        let entry = self.entries.get_mut(&key)?;
+        entry.last_used = self.tick;
+        Some((entry.frames.clone(), entry.labels.clone()))
+    }
+
+    /// キャッシュに追加（上限より大きいものは保存しない）
+    pub fn insert(&mut self, path: &Path, frames: Arc<Vec<InputFrame>>, labels: Vec<SequenceLabel>) {
+        let key = cache_key(path);
+        let bytes = estimate_bytes(&frames, &labels);
+        self.entries.remove(&key);
+        if bytes > self.max_bytes {
+            return;
+        }
+        self.tick += 1;
+        self.entries.insert(key, CacheEntry {
+            modified: modified_time(path),
+            frames,
+            labels,
+            bytes,
+            last_used: self.tick,
+        });
+        self.evict();
+    }
+
+    /// キャッシュから削除（キャッシュにあった場合は true）
+    pub fn remove(&mut self, path: &Path) -> bool {
+        self.entries.remove(&cache_key(path)).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// 上限を変更（超えた分はすぐに破棄する）
+    pub fn set_max_megabytes(&mut self, max_megabytes: u32) {
+        self.max_bytes = max_megabytes as usize * 1024 * 1024;
+        self.evict();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            total_bytes: self.total_bytes(),
+            max_bytes: self.max_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+
+    fn total_bytes(&self) -> usize {
+        self.entries.values().map(|e| e.bytes).sum()
+    }
+
+    // 上限に収まるまで最後に使われたのが最も古いものから破棄
+    fn evict(&mut self) {
+        let mut total = self.total_bytes();
+        while total > self.max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                total -= entry.bytes;
+                self.evictions += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(count: usize) -> Arc<Vec<InputFrame>> {
+        Arc::new(vec![InputFrame::neutral(1); count])
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = crate::test_support::TempDir::new("frame_cache_test");
+        let paths: Vec<PathBuf> = (0..3).map(|i| dir.join(format!("sequence_{}.csv", i))).collect();
+        for path in &paths {
+            std::fs::write(path, "duration,direction\n1,5\n").unwrap();
+        }
+
+        // 2件分だけ入る上限
+        let size = estimate_bytes(&frames(1000), &[]);
+        let mut cache = FrameCache::new(0);
+        cache.max_bytes = size * 2;
+
+        cache.insert(&paths[0], frames(1000), Vec::new());
+        cache.insert(&paths[1], frames(1000), Vec::new());
+        assert!(cache.get(&paths[0]).is_some());
+        cache.insert(&paths[2], frames(1000), Vec::new());
+
+        assert!(cache.get(&paths[1]).is_none());
+        assert!(cache.get(&paths[0]).is_some());
+        assert!(cache.get(&paths[2]).is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions, stats.hits, stats.misses), (2, 1, 3, 1));
+    }
+}
@@ -0,0 +1,167 @@
+//! よく使うコマンド入力の生成
+//!
+//! 波動（qcf）・昇龍（dp）・一回転（360）・溜め技などのコマンドを、
+//! 指定したFPSでゲーム側に受け付けられる最小フレーム数を満たすステップ列として生成する。
+//! フレーム数は60fps基準の値をFPSに合わせて換算する（最低1フレーム）。
+//! 途中の方向と最後のステップのフレーム数はテンキー表記と同じ NotationSettings の値を使う。
+//!
+//! アナログ角度を読むゲーム向けに、左スティック（thumb_lx / thumb_ly）で
+//! 円を描く軌道や左右の往復も生成できる。
+
+use crate::model::NotationSettings;
+use crate::types::InputFrame;
+use anyhow::Result;
+use serde::Deserialize;
+
+/// 溜め技の溜め時間（60fps基準）
+pub const CHARGE_FRAMES: u32 = 45;
+
+/// 生成できるコマンド名
+pub const MOTION_NAMES: &[&str] = &[
+    "qcf", "qcb", "dp", "rdp", "hcf", "hcb", "360", "charge_back", "charge_down",
+];
+
+// コマンドごとの方向（最後の方向でボタンを押す）
+fn motion_directions(motion: &str) -> Option<&'static [u8]> {
+    let directions: &[u8] = match motion {
+        "qcf" => &[2, 3, 6],
+        "qcb" => &[2, 1, 4],
+        "dp" => &[6, 2, 3],
+        "rdp" => &[4, 2, 1],
+        "hcf" => &[4, 1, 2, 3, 6],
+        "hcb" => &[6, 3, 2, 1, 4],
+        // 上方向はジャンプの移行フレーム中にボタンを押して成立させる
+        "360" => &[6, 3, 2, 1, 4, 7, 8],
+        "charge_back" => &[4, 6],
+        "charge_down" => &[2, 8],
+        _ => return None,
+    };
+    Some(directions)
+}
+
+// 60fps基準のフレーム数を指定FPSに換算
+fn frames_at(base_frames: u32, fps: u32) -> u32 {
+    ((base_frames as f64 * fps as f64 / 60.0).round() as u32).max(1)
+}
+
+fn step(duration: u32, direction: u8, button: Option<&str>) -> InputFrame {
+    InputFrame {
+        direction,
+        buttons: button.map(|b| (b.to_string(), 1)).into_iter().collect(),
+        ..InputFrame::neutral(duration)
+    }
+}
+
+/// コマンド入力を生成（button が None なら方向入力のみ）
+pub fn generate_motion(
+    motion: &str,
+    button: Option<&str>,
+    fps: u32,
+    settings: &NotationSettings,
+) -> Result<Vec<InputFrame>> {
+    if fps == 0 {
+        anyhow::bail!("FPSは1以上を指定してください");
+    }
+    let motion = motion.trim().to_ascii_lowercase();
+    let directions = motion_directions(&motion).ok_or_else(|| {
+        anyhow::anyhow!("未対応のコマンドです: {} (対応: {})", motion, MOTION_NAMES.join(", "))
+    })?;
+    let (&last, path) = directions.split_last().expect("コマンドの方向は1つ以上");
+
+    let is_charge = motion.starts_with("charge_");
+    let mut frames: Vec<InputFrame> = path
+        .iter()
+        .map(|&direction| {
+            let base = if is_charge { CHARGE_FRAMES } else { settings.motion_frames };
+            step(frames_at(base, fps), direction, None)
+        })
+        .collect();
+    frames.push(step(frames_at(settings.press_frames, fps), last, button));
+    Ok(frames)
+}
+
+/// 左スティックの軌道（角度は右が0°、反時計回りが正）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StickMotion {
+    /// start_angle から degrees だけ回転（1フレームずつ、両端を含む）
+    Rotation { start_angle: f64, degrees: f64, frames: u32 },
+    /// angle とその反対方向を count 回交互に倒す（各 hold_frames フレーム）
+    Wiggle { angle: f64, count: u32, hold_frames: u32 },
+}
+
+fn stick_step(duration: u32, angle_degrees: f64, radius: f64) -> InputFrame {
+    let (sin, cos) = angle_degrees.to_radians().sin_cos();
+    let axis = |v: f64| (v * radius * i16::MAX as f64).round() as i16;
+    let mut frame = step(duration, 5, None);
+    frame.thumb_lx = axis(cos);
+    frame.thumb_ly = axis(sin);
+    frame
+}
+
+/// 左スティックの軌道を生成（radius は倒す量 0.0〜1.0）
+pub fn generate_stick_motion(motion: &StickMotion, radius: f64) -> Result<Vec<InputFrame>> {
+    if !(0.0..=1.0).contains(&radius) {
+        anyhow::bail!("倒す量は0.0〜1.0で指定してください: {}", radius);
+    }
+    let frames = match *motion {
+        StickMotion::Rotation { start_angle, degrees, frames } => {
+            if frames == 0 {
+                anyhow::bail!("フレーム数は1以上を指定してください");
+            }
+            let last = (frames - 1).max(1) as f64;
+            (0..frames)
+                .map(|i| stick_step(1, start_angle + degrees * i as f64 / last, radius))
+                .collect()
+        }
+        StickMotion::Wiggle { angle, count, hold_frames } => {
+            if count == 0 || hold_frames == 0 {
+                anyhow::bail!("回数とフレーム数は1以上を指定してください");
+            }
+            (0..count)
+                .map(|i| stick_step(hold_frames, if i % 2 == 0 { angle } else { angle + 180.0 }, radius))
+                .collect()
+        }
+    };
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(frames: &[InputFrame]) -> Vec<(u32, u8, bool)> {
+        frames.iter().map(|f| (f.duration, f.direction, f.buttons.contains_key("LP"))).collect()
+    }
+
+    #[test]
+    fn test_generate_motion() {
+        let settings = NotationSettings::default();
+        let frames = generate_motion("QCF", Some("LP"), 60, &settings).unwrap();
+        assert_eq!(summary(&frames), vec![(2, 2, false), (2, 3, false), (4, 6, true)]);
+
+        // FPSに合わせて換算（最低1フレーム）
+        let frames = generate_motion("charge_back", None, 30, &settings).unwrap();
+        assert_eq!(summary(&frames), vec![(23, 4, false), (2, 6, false)]);
+        let frames = generate_motion("dp", None, 20, &settings).unwrap();
+        assert_eq!(frames[0].duration, 1);
+
+        assert!(generate_motion("tiger_knee", None, 60, &settings).is_err());
+        assert!(generate_motion("qcf", None, 0, &settings).is_err());
+    }
+
+    #[test]
+    fn test_generate_stick_motion() {
+        let motion = StickMotion::Rotation { start_angle: 0.0, degrees: 360.0, frames: 5 };
+        let frames = generate_stick_motion(&motion, 1.0).unwrap();
+        let axes: Vec<(i16, i16)> = frames.iter().map(|f| (f.thumb_lx, f.thumb_ly)).collect();
+        assert_eq!(axes, vec![(32767, 0), (0, 32767), (-32767, 0), (0, -32767), (32767, 0)]);
+        assert!(frames.iter().all(|f| f.duration == 1 && f.direction == 5));
+
+        let motion = StickMotion::Wiggle { angle: 0.0, count: 3, hold_frames: 2 };
+        let frames = generate_stick_motion(&motion, 0.5).unwrap();
+        let xs: Vec<i16> = frames.iter().map(|f| f.thumb_lx).collect();
+        assert_eq!(xs, vec![16384, -16384, 16384]);
+        assert!(generate_stick_motion(&motion, 1.5).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! GPUジョブスケジューラ
+//!
+//! 学習とGPU推論が同時に走るとVRAMを奪い合い確保に失敗するため、
+//! GPUを使うジョブを1つずつ実行させる。
+//! 待機中のジョブは優先度順（推論 > 学習）、同じ優先度なら到着順に実行される。
+//! 待機はブロックするため、コマンドのスレッド（spawn_blocking）から呼ぶこと。
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::jobs::CancellationToken;
+
+/// 待機中にキャンセルを確認する間隔
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// GPUジョブの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuJobKind {
+    /// モデル学習（長時間）
+    Training,
+    /// 推論（動画解析など）
+    Inference,
+}
+
+impl GpuJobKind {
+    /// 優先度（大きいほど先に実行）
+    /// 推論は対話的に使われるため、長時間かかる学習より優先する
+    fn priority(self) -> u8 {
+        match self {
+            GpuJobKind::Training => 0,
+            GpuJobKind::Inference => 1,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            GpuJobKind::Training => "学習",
+            GpuJobKind::Inference => "推論",
+        }
+    }
+}
+
+struct WaitingJob {
+    ticket: u64,
+    kind: GpuJobKind,
+}
+
+impl WaitingJob {
+    /// other より先に実行されるべきか
+    fn runs_before(&self, other: &WaitingJob) -> bool {
+        (self.kind.priority(), std::cmp::Reverse(self.ticket))
+            > (other.kind.priority(), std::cmp::Reverse(other.ticket))
+    }
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    next_ticket: u64,
+    running: Option<GpuJobKind>,
+    waiting: Vec<WaitingJob>,
+}
+
+/// GPUジョブスケジューラ
+#[derive(Default)]
+pub struct GpuScheduler {
+    state: Mutex<SchedulerState>,
+    available: Condvar,
+}
+
+impl GpuScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// GPUの使用権を取得するまで待機する
+    ///
+    /// 待機が必要な場合、待ち順が変わるたびに on_wait へ進捗メッセージを渡す。
+    /// 待機中に token がキャンセルされた場合は待ち行列から外れてキャンセルのエラーを返す。
+    /// 返されたガードをドロップすると使用権が解放され、次のジョブが開始される。
+    pub fn acquire<F>(
+        self: &Arc<Self>,
+        kind: GpuJobKind,
+        token: &CancellationToken,
+        mut on_wait: F,
+    ) -> anyhow::Result<GpuJobGuard>
+    where
+        F: FnMut(String),
+    {
+        let mut state = self.state.lock().unwrap();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push(WaitingJob { ticket, kind });
+
+        let mut last_position = None;
+        loop {
+            let me = state.waiting.iter().find(|job| job.ticket == ticket).unwrap();
+            let ahead = state.waiting.iter().filter(|job| job.runs_before(me)).count();
+
+            if state.running.is_none() && ahead == 0 {
+                state.waiting.retain(|job| job.ticket != ticket);
+                state.running = Some(kind);
+                if last_position.is_some() {
+                    println!("[GPU] {}ジョブの待機が解除されました", kind.label());
+                }
+                return Ok(GpuJobGuard {
+                    scheduler: self.clone(),
+                });
+            }
+
+            if token.is_cancelled() {
+                state.waiting.retain(|job| job.ticket != ticket);
+                drop(state);
+                // 後ろで待っているジョブの待ち順が変わる
+                self.available.notify_all();
+                println!("[GPU] {}ジョブの待機をキャンセルしました", kind.label());
+                return Err(anyhow::anyhow!(crate::jobs::CANCELLED_MESSAGE));
+            }
+
+            if last_position != Some(ahead) {
+                let running = state.running.map(|k| k.label()).unwrap_or("他のジョブ");
+                let message = format!(
+                    "GPU待機中: {}がGPUを使用中のため待機しています（待ち順: {}番目）",
+                    running,
+                    ahead + 1
+                );
+                println!("[GPU] {}", message);
+                on_wait(message);
+                last_position = Some(ahead);
+            }
+
+            state = self.available.wait_timeout(state, CANCEL_CHECK_INTERVAL).unwrap().0;
+        }
+    }
+}
+
+/// GPU使用権のガード（ドロップ時に解放）
+pub struct GpuJobGuard {
+    scheduler: Arc<GpuScheduler>,
+}
+
+impl Drop for GpuJobGuard {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock().unwrap();
+        state.running = None;
+        drop(state);
+        self.scheduler.available.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_while_waiting() {
+        let scheduler = Arc::new(GpuScheduler::new());
+        let running = scheduler.acquire(GpuJobKind::Training, &CancellationToken::new(), |_| {}).unwrap();
+
+        let token = CancellationToken::new();
+        let waiter = {
+            let scheduler = scheduler.clone();
+            let token = token.clone();
+            std::thread::spawn(move || scheduler.acquire(GpuJobKind::Inference, &token, |_| {}).map(|_| ()))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        token.cancel();
+        let result = waiter.join().unwrap();
+        assert_eq!(result.unwrap_err().to_string(), crate::jobs::CANCELLED_MESSAGE);
+        assert!(scheduler.state.lock().unwrap().waiting.is_empty());
+
+        drop(running);
+        assert!(scheduler.acquire(GpuJobKind::Inference, &CancellationToken::new(), |_| {}).is_ok());
+    }
+}
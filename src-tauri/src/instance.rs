@@ -0,0 +1,96 @@
+//! 多重起動・仮想コントローラーの取り合いの検出
+//!
+//! 2つのインスタンスが同時に動くと、それぞれが60Hzで仮想パッドに入力を送り合って
+//! 再生が壊れる。Windows の名前付きミューテックスで「アプリが起動中」「ViGEm の
+//! 仮想コントローラーを接続中」をプロセス間で共有し、取り合いを接続前にエラーにする。
+//! ミューテックスはハンドルを閉じる（プロセスが終了する）と自動的に消える。
+//! Windows 以外では ViGEm が使えないため常に取得に成功する。
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// アプリの起動を示すミューテックス名
+pub const APP_INSTANCE_NAME: &str = "Local\\input_player_instance";
+/// ViGEm の仮想コントローラーの接続を示すミューテックス名
+pub const CONTROLLER_TARGET_NAME: &str = "Local\\input_player_vigem_target";
+
+/// 他のプロセスとの競合状態（check_instance_conflict の戻り値）
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceConflict {
+    pub another_instance: bool,  // このアプリが他にも起動している
+    pub target_in_use: bool,     // 他のプロセスが仮想コントローラーを接続中
+}
+
+#[cfg(windows)]
+mod ffi {
+    use std::ffi::c_void;
+
+    pub const ERROR_ALREADY_EXISTS: u32 = 183;
+    pub const SYNCHRONIZE: u32 = 0x0010_0000;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn CreateMutexW(attributes: *mut c_void, initial_owner: i32, name: *const u16) -> *mut c_void;
+        pub fn OpenMutexW(desired_access: u32, inherit_handle: i32, name: *const u16) -> *mut c_void;
+        pub fn CloseHandle(handle: *mut c_void) -> i32;
+        pub fn GetLastError() -> u32;
+    }
+
+    pub fn wide(name: &str) -> Vec<u16> {
+        name.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+}
+
+/// 名前付きミューテックス（保持している間だけ存在する）
+pub struct NamedMutex {
+    #[cfg(windows)]
+    handle: isize,  // HANDLE（Send にするため整数で保持）
+}
+
+impl NamedMutex {
+    /// 作成して保持する（他のプロセスが既に保持していればエラー）
+    #[cfg(windows)]
+    pub fn acquire(name: &str) -> Result<Self> {
+        let wide = ffi::wide(name);
+        let handle = unsafe { ffi::CreateMutexW(std::ptr::null_mut(), 0, wide.as_ptr()) };
+        if handle.is_null() {
+            anyhow::bail!("ミューテックスの作成に失敗: {} (error {})", name, unsafe { ffi::GetLastError() });
+        }
+        if unsafe { ffi::GetLastError() } == ffi::ERROR_ALREADY_EXISTS {
+            unsafe { ffi::CloseHandle(handle) };
+            anyhow::bail!("{} は他のプロセスが使用中です", name);
+        }
+        Ok(Self { handle: handle as isize })
+    }
+
+    #[cfg(not(windows))]
+    pub fn acquire(_name: &str) -> Result<Self> {
+        Ok(Self {})
+    }
+
+    /// いずれかのプロセス（自分を含む）が保持しているか
+    #[cfg(windows)]
+    pub fn exists(name: &str) -> bool {
+        let wide = ffi::wide(name);
+        let handle = unsafe { ffi::OpenMutexW(ffi::SYNCHRONIZE, 0, wide.as_ptr()) };
+        if handle.is_null() {
+            return false;
+        }
+        unsafe { ffi::CloseHandle(handle) };
+        true
+    }
+
+    #[cfg(not(windows))]
+    pub fn exists(_name: &str) -> bool {
+        false
+    }
+}
+
+impl Drop for NamedMutex {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        unsafe {
+            ffi::CloseHandle(self.handle as *mut std::ffi::c_void);
+        }
+    }
+}
@@ -0,0 +1,368 @@
+//! 長時間かかる処理（学習・動画からの抽出・タイル分類・ライブ解析）のジョブ管理
+//!
+//! 各処理は開始時に [`JobRegistry::register`] でジョブとして登録され、ID が割り当てられる。
+//! フロントエンドは `list_jobs` / `get_job_status` で状態を問い合わせられるため、
+//! ウィンドウを再読み込みしても実行中・終了済みのジョブを追跡できる。
+//!
+//! キャンセルは `cancel_job(job_id)` で要求する。処理側はフレームごとに
+//! [`CancellationToken::check`] を呼び、キャンセルされていればエラーで抜けて、
+//! パイプラインの停止と途中まで書いた出力の削除を行う。
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// キャンセル時のエラーメッセージ（フロントエンドはこの文字列でキャンセルを判別する）
+pub const CANCELLED_MESSAGE: &str = "キャンセルされました";
+
+/// ジョブ開始時に送るイベント名（ペイロードは [`JobInfo`]）
+pub const JOB_STARTED_EVENT: &str = "job-started";
+/// ジョブ終了時（完了・失敗・キャンセル）に送るイベント名（ペイロードは [`JobInfo`]）
+pub const JOB_FINISHED_EVENT: &str = "job-finished";
+
+/// 終了したジョブを保持する件数（古いものから削除する）
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// キャンセル要求のフラグ（複製しても同じフラグを共有する）
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// キャンセルされていればエラーを返す
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            anyhow::bail!(CANCELLED_MESSAGE);
+        }
+        Ok(())
+    }
+
+    /// AtomicBool のキャンセルフラグを受け取る処理（学習など）に渡すためのフラグ
+    pub fn as_flag(&self) -> Arc<AtomicBool> {
+        self.0.clone()
+    }
+}
+
+/// ジョブの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Training,
+    Extraction,
+    Classification,
+    LiveAnalysis,
+}
+
+impl JobKind {
+    fn id_prefix(self) -> &'static str {
+        match self {
+            JobKind::Training => "training",
+            JobKind::Extraction => "extraction",
+            JobKind::Classification => "classification",
+            JobKind::LiveAnalysis => "live",
+        }
+    }
+}
+
+/// ジョブの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// ジョブの情報（`list_jobs` / `get_job_status` / ジョブイベントで返す）
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: JobKind,
+    /// 処理対象（動画パスなど）
+    pub label: String,
+    pub status: JobStatus,
+    /// 開始日時（RFC3339）
+    pub started_at: String,
+    /// 終了日時（RFC3339、実行中は None）
+    pub finished_at: Option<String>,
+    /// 進捗（フレーム数・エポック数など。total が不明な場合は 0）
+    pub current: u64,
+    pub total: u64,
+    /// 最新の進捗メッセージ
+    pub message: String,
+    /// 失敗時のエラーメッセージ
+    pub error: Option<String>,
+}
+
+type JobListener = Box<dyn Fn(&'static str, &JobInfo) + Send + Sync>;
+
+struct JobEntry {
+    info: JobInfo,
+    token: CancellationToken,
+}
+
+/// 実行中・終了済みのジョブ
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<Vec<JobEntry>>,
+    next_id: AtomicU64,
+    listener: Mutex<Option<JobListener>>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ジョブの開始・終了を通知するコールバックを設定する
+    pub fn set_listener(&self, listener: impl Fn(&'static str, &JobInfo) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Box::new(listener));
+    }
+
+    fn notify(&self, event: &'static str, info: &JobInfo) {
+        if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+            listener(event, info);
+        }
+    }
+
+    /// ジョブを登録する（job_id が None の場合は連番で ID を割り当てる）
+    ///
+    /// 同じ ID のジョブが実行中の場合はエラー（終了済みのジョブは置き換える）。
+    pub fn register(self: &Arc<Self>, kind: JobKind, job_id: Option<String>, label: impl Into<String>) -> Result<JobGuard, String> {
+        let token = CancellationToken::new();
+        let info = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let id = match job_id {
+                Some(id) => {
+                    if jobs.iter().any(|entry| entry.info.id == id && entry.info.status == JobStatus::Running) {
+                        return Err(format!("同じIDのジョブが実行中です: {}", id));
+                    }
+                    id
+                }
+                // 呼び出し側が指定した ID と重ならない番号まで進める
+                None => loop {
+                    let id = format!("{}-{}", kind.id_prefix(), self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+                    if !jobs.iter().any(|entry| entry.info.id == id) {
+                        break id;
+                    }
+                },
+            };
+            let info = JobInfo {
+                id: id.clone(),
+                kind,
+                label: label.into(),
+                status: JobStatus::Running,
+                started_at: chrono::Local::now().to_rfc3339(),
+                finished_at: None,
+                current: 0,
+                total: 0,
+                message: String::new(),
+                error: None,
+            };
+            // 同じ ID の終了済みジョブは置き換える
+            jobs.retain(|entry| entry.info.id != id);
+            jobs.push(JobEntry { info: info.clone(), token: token.clone() });
+            info
+        };
+        println!("[Job] 開始: {} ({})", info.id, info.label);
+        self.notify(JOB_STARTED_EVENT, &info);
+        Ok(JobGuard { registry: self.clone(), id: info.id, token, finished: false })
+    }
+
+    /// キャンセルを要求する（実行中のジョブが無ければ false）
+    pub fn cancel(&self, job_id: &str) -> bool {
+        let jobs = self.jobs.lock().unwrap();
+        match jobs.iter().find(|entry| entry.info.id == job_id && entry.info.status == JobStatus::Running) {
+            Some(entry) => {
+                entry.token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// ジョブの一覧（新しい順）
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.jobs.lock().unwrap().iter().rev().map(|entry| entry.info.clone()).collect()
+    }
+
+    /// ジョブの状態を取得する
+    pub fn get(&self, job_id: &str) -> Option<JobInfo> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|entry| entry.info.id == job_id)
+            .map(|entry| entry.info.clone())
+    }
+
+    /// 進捗を更新する（別スレッドから更新する場合は JobGuard ではなくこちらを使う）
+    pub fn set_progress(&self, job_id: &str, current: u64, total: u64, message: &str) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(entry) = jobs.iter_mut().find(|entry| entry.info.id == job_id) {
+            entry.info.current = current;
+            entry.info.total = total;
+            entry.info.message = message.to_string();
+        }
+    }
+
+    fn complete(&self, job_id: &str, status: JobStatus, error: Option<String>) {
+        let info = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(entry) = jobs.iter_mut().find(|entry| entry.info.id == job_id) else {
+                return;
+            };
+            entry.info.status = status;
+            entry.info.finished_at = Some(chrono::Local::now().to_rfc3339());
+            entry.info.error = error;
+            let info = entry.info.clone();
+
+            // 終了済みのジョブが多すぎる場合は古いものから削除
+            let finished = jobs.iter().filter(|entry| entry.info.status != JobStatus::Running).count();
+            let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+            jobs.retain(|entry| {
+                if excess > 0 && entry.info.status != JobStatus::Running {
+                    excess -= 1;
+                    return false;
+                }
+                true
+            });
+            info
+        };
+        println!("[Job] 終了: {} ({:?})", info.id, info.status);
+        self.notify(JOB_FINISHED_EVENT, &info);
+    }
+}
+
+/// 実行中のジョブ（`finish` で結果を記録する。記録せずにドロップした場合は失敗として扱う）
+pub struct JobGuard {
+    registry: Arc<JobRegistry>,
+    id: String,
+    token: CancellationToken,
+    finished: bool,
+}
+
+impl JobGuard {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub fn registry(&self) -> &Arc<JobRegistry> {
+        &self.registry
+    }
+
+    pub fn set_progress(&self, current: u64, total: u64, message: &str) {
+        self.registry.set_progress(&self.id, current, total, message);
+    }
+
+    /// 処理結果を記録してそのまま返す（キャンセルされていた場合はキャンセルとして記録）
+    pub fn finish<T>(mut self, result: Result<T, String>) -> Result<T, String> {
+        let (status, error) = match &result {
+            Ok(_) => (JobStatus::Completed, None),
+            Err(_) if self.token.is_cancelled() => (JobStatus::Cancelled, None),
+            Err(e) => (JobStatus::Failed, Some(e.clone())),
+        };
+        self.finished = true;
+        self.registry.complete(&self.id, status, error);
+        result
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.registry.complete(&self.id, JobStatus::Failed, Some("処理が異常終了しました".to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_registered_job() {
+        let registry = Arc::new(JobRegistry::new());
+        let guard = registry.register(JobKind::Extraction, Some("extract-1".to_string()), "video.mp4").unwrap();
+        assert!(guard.token().check().is_ok());
+
+        assert!(registry.cancel("extract-1"));
+        assert!(guard.token().is_cancelled());
+        assert_eq!(guard.token().check().unwrap_err().to_string(), CANCELLED_MESSAGE);
+
+        // 終了したジョブはキャンセルできない
+        let result: Result<(), String> = guard.finish(Err(CANCELLED_MESSAGE.to_string()));
+        assert!(result.is_err());
+        assert!(!registry.cancel("extract-1"));
+        assert_eq!(registry.get("extract-1").unwrap().status, JobStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_job_status_is_kept_after_finish() {
+        let registry = Arc::new(JobRegistry::new());
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let finished_clone = finished.clone();
+        registry.set_listener(move |event, info| {
+            if event == JOB_FINISHED_EVENT {
+                finished_clone.lock().unwrap().push(info.id.clone());
+            }
+        });
+
+        let training = registry.register(JobKind::Training, None, "data").unwrap();
+        let extraction = registry.register(JobKind::Extraction, None, "video.mp4").unwrap();
+        assert_eq!(training.id(), "training-1");
+        assert_eq!(extraction.id(), "extraction-2");
+
+        extraction.set_progress(10, 100, "10フレーム処理中...");
+        assert_eq!(registry.get("extraction-2").unwrap().current, 10);
+
+        let _ = extraction.finish(Err::<(), _>("読み込みエラー".to_string()));
+        let info = registry.get("extraction-2").unwrap();
+        assert_eq!(info.status, JobStatus::Failed);
+        assert_eq!(info.error.as_deref(), Some("読み込みエラー"));
+
+        // 結果を記録せずにドロップしたジョブは失敗扱い
+        drop(training);
+        assert_eq!(registry.get("training-1").unwrap().status, JobStatus::Failed);
+
+        assert_eq!(*finished.lock().unwrap(), vec!["extraction-2", "training-1"]);
+        let ids: Vec<String> = registry.list().into_iter().map(|job| job.id).collect();
+        assert_eq!(ids, vec!["extraction-2", "training-1"]);
+    }
+
+    #[test]
+    fn test_register_rejects_running_duplicate() {
+        let registry = Arc::new(JobRegistry::new());
+        let guard = registry.register(JobKind::Extraction, Some("extraction-1".to_string()), "a.mp4").unwrap();
+        assert!(registry.register(JobKind::Extraction, Some("extraction-1".to_string()), "b.mp4").is_err());
+
+        // 自動の ID は指定済みの ID を避ける
+        let auto = registry.register(JobKind::Extraction, None, "c.mp4").unwrap();
+        assert_eq!(auto.id(), "extraction-2");
+
+        // 終了したジョブの ID は再利用できる
+        let _ = guard.finish(Ok::<(), String>(()));
+        let again = registry.register(JobKind::Extraction, Some("extraction-1".to_string()), "d.mp4").unwrap();
+        assert_eq!(registry.get("extraction-1").unwrap().label, "d.mp4");
+        assert!(!again.token().is_cancelled());
+        let _ = auto.finish(Ok::<(), String>(()));
+        let _ = again.finish(Ok::<(), String>(()));
+    }
+}
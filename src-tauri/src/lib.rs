@@ -1,9 +1,63 @@
-mod types;
-mod controller;
-mod csv_loader;
-mod player;
+//! 入力シーケンス再生・動画解析ライブラリ
+//!
+//! Tauriアプリ本体（[`run`]）に加え、以下の機能をTauriに依存せずに利用できます。
+//!
+//! - [`Player`] / [`Controller`]: CSVシーケンスの再生と仮想コントローラーへの入力送信
+//! - [`output_backend::OutputBackend`]: 入力の出力先の追加（ネットワーク送信・シリアル接続のパッドなど）
+//! - [`video::FrameExtractor`]: 動画からのフレーム抽出
+//! - `ml::InferenceEngine` / `ml::extract_input_history_to_csv`: 入力表示の分類と入力履歴の抽出（`ml` feature）
+//!
+//! ```no_run
+//! use input_player_lib::{load_csv, Controller, ControllerType, Player, SequenceState};
+//! use std::path::Path;
+//! use std::time::Instant;
+//!
+//! let frames = load_csv(Path::new("combo.csv"))?;
+//! let mut controller = Controller::new();
+//! controller.connect(ControllerType::Xbox)?;
+//!
+//! let mut player = Player::new();
+//! player.load_frames(frames);
+//! player.start();
+//! while player.get_state() == SequenceState::Playing {
+//!     if let Some(deadline) = player.next_step_deadline() {
+//!         std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+//!     }
+//!     player.update(Some(&mut controller))?;
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod types;
+pub mod controller;
+pub mod output_backend;
+pub mod csv_loader;
+pub mod player;
 mod analysis_commands;
 mod ml_commands;
+mod timer;
+mod gpu_scheduler;
+mod jobs;
+mod atomic_write;
+mod file_watcher;
+mod frame_cache;
+mod obs;
+pub mod instance;
+#[cfg(feature = "remote")]
+mod remote;
+pub mod roundtrip;
+pub mod accuracy;
+pub mod playback_history;
+pub mod notation;
+pub mod sequence_binary;
+pub mod edit_session;
+pub mod transform;
+pub mod generator;
+pub mod mapping_validation;
+pub mod script;
+pub mod selftest;
+#[cfg(test)]
+mod test_support;
 
 // 入力解析機能のモジュール
 pub mod video;
@@ -12,31 +66,59 @@ pub mod model;
 #[cfg(feature = "ml")]
 pub mod ml;
 
-use controller::Controller;
-use csv_loader::load_csv;
-use player::Player;
-use types::{ButtonMapping, ControllerType, InputFrame, SequenceState};
+pub use controller::Controller;
+pub use csv_loader::{load_csv, load_csv_with_labels};
+pub use player::Player;
+pub use types::{
+    BreakpointHitEvent, ControllerType, InputFrame, PlaybackStepEvent, PlaybackTimingReport,
+    SequenceLabel, SequenceState,
+};
+use types::{AppSnapshot, ButtonMapping, ScheduledPlaybackFiredEvent, SequenceLoadProgressEvent};
+use edit_session::{EditSession, EditSessionChange, EditSessionState, FrameSplice};
+use file_watcher::{FileReloadedEvent, FileWatcher, WatchKind};
+use frame_cache::{CacheStats, FrameCache};
+use model::{AppConfig, EditorSettings, ObsSettings, RemoteSettings, SessionSettings, GenerationSettings, HotkeySettings, NotationSettings, SyncMarkerSettings};
 
 use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
 use std::collections::HashMap;
 use tauri::{Emitter, State, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
 pub struct AppState {
     controller: Arc<Mutex<Controller>>,
     player: Arc<Mutex<Player>>,
     fps: Arc<Mutex<u32>>,
-    frame_cache: Arc<Mutex<std::collections::HashMap<String, Vec<InputFrame>>>>, // パス -> フレームデータのキャッシュ
+    frame_cache: Arc<Mutex<FrameCache>>, // 読み込んだフレームデータとラベルのキャッシュ（プレイヤーと共有、LRUで上限あり）
     manual_input: Arc<Mutex<InputFrame>>, // 手動入力の現在状態
     app_handle: Arc<Mutex<Option<tauri::AppHandle>>>, // イベント発行用
     button_order: Arc<Mutex<Vec<String>>>, // ボタンマッピングの順序
     is_training: Arc<Mutex<bool>>, // 学習中フラグ
+    gpu_scheduler: Arc<gpu_scheduler::GpuScheduler>, // GPUジョブの排他制御
+    jobs: Arc<jobs::JobRegistry>, // 学習・抽出・分類のジョブ（実行中と終了済み）
+    #[cfg(feature = "ml")]
+    engine_cache: Arc<ml::EngineCache>, // 読み込み済みの推論エンジン（モデルパス・バックエンドごと）
+    hotkey_bindings: Arc<Mutex<HashMap<u32, HotkeyAction>>>, // ショートカットID -> 動作
+    edit_sessions: Arc<Mutex<HashMap<String, EditSession>>>, // パス -> 編集セッション
+    preview_sessions: Arc<Mutex<HashMap<String, analysis_commands::OpenPreview>>>, // セッションID -> 動画プレビュー
+    file_watcher: Arc<Mutex<FileWatcher>>, // 使用中のマッピング・シーケンスの変更監視
+    pending_reloads: Arc<Mutex<Vec<(WatchKind, String)>>>, // 再生中に変更され、停止後に読み込み直すファイル
+    script: Arc<Mutex<Option<script::SequenceScript>>>, // 読み込み中のスクリプト（ループ時のフックを呼ぶ）
+    instance_guard: Option<instance::NamedMutex>, // 起動中を示すミューテックス（他のインスタンスが起動中なら None）
+}
+
+/// グローバルホットキーに割り当てる動作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HotkeyAction {
+    StartPlayback,
+    StopPlayback,
 }
 
 // Tauri commands
 #[tauri::command]
 fn connect_controller(
     controller_type: String,
+    backend: Option<String>,
     state: State<AppState>,
 ) -> Result<String, String> {
     let ctrl_type = match controller_type.as_str() {
@@ -46,12 +128,23 @@ fn connect_controller(
     };
 
     let mut controller = state.controller.lock().unwrap();
+    // 出力先の指定があれば切り替える（省略時は現在の出力先）
+    if let Some(backend) = backend {
+        controller.set_backend(&backend)
+            .map_err(|e| format!("出力先の切り替えに失敗: {}", e))?;
+    }
     controller.connect(ctrl_type)
         .map_err(|e| e.to_string())?;
 
     Ok(format!("Connected to {} controller", controller_type))
 }
 
+/// 登録されている出力先の名前（"vigem" など）
+#[tauri::command]
+fn list_output_backends() -> Vec<String> {
+    output_backend::backend_names()
+}
+
 #[tauri::command]
 fn disconnect_controller(state: State<AppState>) -> Result<String, String> {
     let mut controller = state.controller.lock().unwrap();
@@ -61,12 +154,99 @@ fn disconnect_controller(state: State<AppState>) -> Result<String, String> {
     Ok("Controller disconnected".to_string())
 }
 
+/// コントローラー・再生・学習の状態をまとめて取得（エディタなど後から開いたウィンドウの同期用）
+#[tauri::command]
+fn get_app_snapshot(state: State<AppState>) -> AppSnapshot {
+    app_snapshot(&state)
+}
+
+fn app_snapshot(state: &AppState) -> AppSnapshot {
+    let (controller_connected, controller_type) = {
+        let controller = state.controller.lock().unwrap();
+        (controller.is_connected(), controller.get_controller_type())
+    };
+    let fps = *state.fps.lock().unwrap();
+    let is_training = *state.is_training.lock().unwrap();
+    let player = state.player.lock().unwrap();
+    let (current_step, total_steps) = player.get_progress();
+    AppSnapshot {
+        controller_connected,
+        controller_type,
+        sequence_path: player.get_current_path(),
+        sequence_state: player.get_state(),
+        current_step,
+        total_steps,
+        fps,
+        loop_playback: player.get_loop_playback(),
+        invert_horizontal: player.get_invert_horizontal(),
+        reversed: player.is_reversed(),
+        is_training,
+    }
+}
+
+// 変更した状態を設定ファイルに保存（次回起動時に restore_last_session で復元する）
+fn save_session<F: FnOnce(&mut SessionSettings)>(update: F) {
+    let mut config = AppConfig::load_or_default();
+    update(&mut config.session);
+    if let Err(e) = config.save_default() {
+        eprintln!("[セッション] 状態の保存に失敗: {}", e);
+    }
+}
+
+// 前回終了時の設定（FPS・ループ・左右反転・マッピング）を反映
+fn restore_session_settings(state: &AppState, session: &SessionSettings) {
+    if (1..=240).contains(&session.fps) {
+        *state.fps.lock().unwrap() = session.fps;
+        state.player.lock().unwrap().set_fps(session.fps);
+    }
+    {
+        let mut player = state.player.lock().unwrap();
+        player.set_loop_playback(session.loop_playback);
+        player.set_invert_horizontal(session.invert_horizontal);
+    }
+    if let Some(path) = &session.mapping_path {
+        if let Err(e) = apply_button_mapping(state, path) {
+            eprintln!("[セッション] マッピングを復元できませんでした: {}", e);
+        }
+    }
+}
+
+// 前回読み込んでいたシーケンスを読み込み直す（ファイルが無くなっていれば何もしない）
+fn restore_session_sequence(state: &AppState, session: &SessionSettings) {
+    let Some(path) = &session.last_sequence_path else { return };
+    match load_sequence_file(state, path.clone()) {
+        Ok(total_frames) => println!("[セッション] シーケンスを復元: {} ({}F)", path, total_frames),
+        Err(e) => eprintln!("[セッション] シーケンスを復元できませんでした: {}", e),
+    }
+}
+
+/// 前回終了時の状態（シーケンス・マッピング・FPS・ループ・左右反転）を復元して現在の状態を返す
+/// 起動時にも自動で呼ばれる
+#[tauri::command]
+async fn restore_last_session(app: tauri::AppHandle) -> Result<AppSnapshot, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let session = AppConfig::load_or_default().session;
+        restore_session_settings(&state, &session);
+        restore_session_sequence(&state, &session);
+        app_snapshot(&state)
+    })
+    .await
+    .map_err(|e| format!("読み込みスレッドの実行に失敗: {}", e))
+}
+
 #[tauri::command]
 fn is_controller_connected(state: State<AppState>) -> bool {
     let controller = state.controller.lock().unwrap();
     controller.is_connected()
 }
 
+/// 設定ファイル（config.json）のパス
+#[tauri::command]
+fn get_config_path() -> String {
+    AppConfig::default_path().to_string_lossy().to_string()
+}
+
 #[tauri::command]
 fn get_app_dir() -> Result<String, String> {
     // 実行ファイルのディレクトリを取得
@@ -85,6 +265,7 @@ fn load_input_sequence(frames: Vec<types::InputFrame>, state: State<AppState>) -
 
     // 総フレーム数（durationの合計）を計算
     let total_frames: u32 = frames.iter().map(|f| f.duration).sum();
+    *state.script.lock().unwrap() = None;
     let mut player = state.player.lock().unwrap();
     player.load_frames(frames);
 
@@ -92,142 +273,925 @@ fn load_input_sequence(frames: Vec<types::InputFrame>, state: State<AppState>) -
     Ok(total_frames as usize)
 }
 
+// 相対パスをプロジェクトルート基準の絶対パスに変換
+fn resolve_project_path(normalized_path: &str) -> Result<PathBuf, String> {
+    if std::path::Path::new(normalized_path).is_absolute() {
+        return Ok(PathBuf::from(normalized_path));
+    }
+    let current = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    let project_root = if current.ends_with("src-tauri") {
+        current.parent().unwrap().to_path_buf()
+    } else {
+        current
+    };
+    Ok(project_root.join(normalized_path))
+}
+
+/// スクリプト（.rhai）を実行して生成したシーケンスをプレイヤーにロード（戻り値は総フレーム数）
+///
+/// スクリプトに on_loop を定義している場合は、ループ再生で先頭に戻るたびに呼ばれる。
 #[tauri::command]
-fn load_input_file(path: String, state: State<AppState>) -> Result<usize, String> {
+async fn load_script_sequence(path: String, app: tauri::AppHandle) -> Result<usize, String> {
+    if !script::is_script(std::path::Path::new(&path)) {
+        return Err(format!("スクリプトファイル（.rhai）を指定してください: {}", path));
+    }
+    tauri::async_runtime::spawn_blocking(move || load_sequence_file(&app.state::<AppState>(), path))
+        .await
+        .map_err(|e| format!("読み込みスレッドの実行に失敗: {}", e))?
+}
+
+/// シーケンスファイルを読み込んでプレイヤーにロード（戻り値は総フレーム数）
+///
+/// 巨大なファイルでもIPCスレッドを塞がないよう、読み込みはブロッキング用スレッドで行う。
+#[tauri::command]
+async fn load_input_file(path: String, app: tauri::AppHandle) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || load_sequence_file(&app.state::<AppState>(), path))
+        .await
+        .map_err(|e| format!("読み込みスレッドの実行に失敗: {}", e))?
+}
+
+fn load_sequence_file(state: &AppState, path: String) -> Result<usize, String> {
     println!("[load_input_file] 開始 - パス: {}", path);
 
     // パスの区切り文字を正規化
     let normalized_path = path.replace('\\', "/");
 
     // 相対パスを絶対パスに変換
-    let csv_path = if std::path::Path::new(&normalized_path).is_absolute() {
-        PathBuf::from(&normalized_path)
-    } else {
-        let current = std::env::current_dir()
-            .map_err(|e| format!("Failed to get current directory: {}", e))?;
-        let project_root = if current.ends_with("src-tauri") {
-            current.parent().unwrap().to_path_buf()
-        } else {
-            current
-        };
-        project_root.join(&normalized_path)
-    };
+    let csv_path = resolve_project_path(&normalized_path)?;
 
     if !csv_path.exists() {
         return Err(format!("File not found: {:?}", csv_path));
     }
 
-    // キャッシュをチェック
-    let mut cache = state.frame_cache.lock().unwrap();
-    let frames = if let Some(cached_frames) = cache.get(&normalized_path) {
-        // キャッシュから取得
-        println!("[load_input_file] キャッシュから取得 - {}フレーム", cached_frames.len());
-        cached_frames.clone()
+    // スクリプトは実行して生成したシーケンスを読み込む（ループ時のフック用に保持する）
+    let (frames, labels, script) = if script::is_script(&csv_path) {
+        let fps = *state.fps.lock().unwrap();
+        let mut script = script::SequenceScript::load(&csv_path, fps, AppConfig::load_or_default().notation)
+            .map_err(|e| e.to_string())?;
+        let frames = script.generate().map_err(|e| e.to_string())?;
+        println!("[スクリプト] 生成完了 - {}ステップ", frames.len());
+        (Arc::new(frames), Vec::new(), Some(script))
     } else {
-        // CSVを読み込んでキャッシュに保存
-        println!("[load_input_file] CSVから読み込み中...");
-        let loaded_frames = load_csv(&csv_path)
-            .map_err(|e| format!("CSV load error: {}", e))?;
-        println!("[load_input_file] CSV読み込み完了 - {}フレーム", loaded_frames.len());
-        cache.insert(normalized_path.clone(), loaded_frames.clone());
-        loaded_frames
+        let (frames, labels) = read_sequence_frames(state, &csv_path)?;
+        (frames, labels, None)
     };
+    *state.script.lock().unwrap() = script;
 
     // 総フレーム数（durationの合計）を計算
     let total_frames: u32 = frames.iter().map(|f| f.duration).sum();
     let mut player = state.player.lock().unwrap();
-    player.load_frames(frames);
-    player.set_current_path(normalized_path);
+    player.load_shared_frames(frames);
+    player.set_labels(labels);
+    player.set_current_path(normalized_path.clone());
+    drop(player);
+
+    // 外部のエディタで保存されたら読み込み直す
+    state.file_watcher.lock().unwrap().watch(WatchKind::Sequence, &csv_path, normalized_path.clone());
+    save_session(|session| session.last_sequence_path = Some(normalized_path));
 
     Ok(total_frames as usize)
 }
 
+// 進捗イベントを送るファイルサイズの下限
+const LOAD_PROGRESS_MIN_BYTES: u64 = 8 * 1024 * 1024;
+
+// 大きなファイルの読み込み進捗を "sequence-load-progress" イベントで通知する（1%刻み）
+struct LoadProgress {
+    app: Option<tauri::AppHandle>,
+    path: String,
+    total_bytes: u64,
+    last_percent: u64,
+}
+
+impl LoadProgress {
+    fn new(state: &AppState, path: &std::path::Path) -> Self {
+        let total_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let app = if total_bytes >= LOAD_PROGRESS_MIN_BYTES {
+            state.app_handle.lock().unwrap().clone()
+        } else {
+            None
+        };
+        Self {
+            app,
+            path: path.to_string_lossy().replace('\\', "/"),
+            total_bytes,
+            last_percent: 0,
+        }
+    }
+
+    fn report(&mut self, bytes_read: u64) {
+        let percent = bytes_read * 100 / self.total_bytes.max(1);
+        if percent > self.last_percent {
+            self.last_percent = percent;
+            self.emit(bytes_read);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.emit(self.total_bytes);
+    }
+
+    fn emit(&self, bytes_read: u64) {
+        if let Some(app) = &self.app {
+            let _ = app.emit("sequence-load-progress", SequenceLoadProgressEvent {
+                path: self.path.clone(),
+                bytes_read: bytes_read.min(self.total_bytes),
+                total_bytes: self.total_bytes,
+            });
+        }
+    }
+}
+
+// シーケンスファイル（CSV・バイナリ）を読み込む
+// CSVはキャッシュを使い、プレイヤー・エディタ・キャッシュで同じフレームデータを共有する
+fn read_sequence_frames(state: &AppState, csv_path: &std::path::Path) -> Result<(Arc<Vec<InputFrame>>, Vec<SequenceLabel>), String> {
+    // キャッシュをチェック（読み込み中はキャッシュのロックを保持しない）
+    // （ファイルが更新されていればキャッシュは使わない）
+    let is_binary = sequence_binary::is_binary_sequence(csv_path);
+    let cached = if is_binary { None } else { state.frame_cache.lock().unwrap().get(csv_path) };
+    let (frames, labels) = if is_binary {
+        // バイナリ形式はストリーム読み込みが速いため、メモリ節約のためキャッシュしない
+        println!("[シーケンス読み込み] バイナリ形式を読み込み中...");
+        let mut progress = LoadProgress::new(state, csv_path);
+        let (loaded_frames, loaded_labels) = sequence_binary::load_binary_with_progress(csv_path, |bytes| progress.report(bytes))
+            .map_err(|e| format!("Binary sequence load error: {}", e))?;
+        progress.finish();
+        println!("[シーケンス読み込み] バイナリ読み込み完了 - {}フレーム, {}ラベル", loaded_frames.len(), loaded_labels.len());
+        (Arc::new(loaded_frames), loaded_labels)
+    } else if let Some((cached_frames, cached_labels)) = cached {
+        // キャッシュから取得（フレームはコピーせず共有する）
+        println!("[シーケンス読み込み] キャッシュから取得 - {}フレーム", cached_frames.len());
+        (cached_frames, cached_labels)
+    } else {
+        // CSVを1行ずつ読み込んでキャッシュに保存（プレイヤーとキャッシュで同じデータを共有）
+        println!("[シーケンス読み込み] CSVから読み込み中...");
+        let mut progress = LoadProgress::new(state, csv_path);
+        let (loaded_frames, loaded_labels) = csv_loader::load_csv_with_progress(csv_path, |bytes| progress.report(bytes))
+            .map_err(|e| format!("CSV load error: {}", e))?;
+        progress.finish();
+        println!("[シーケンス読み込み] CSV読み込み完了 - {}フレーム, {}ラベル", loaded_frames.len(), loaded_labels.len());
+        let loaded_frames = Arc::new(loaded_frames);
+        state.frame_cache.lock().unwrap()
+            .insert(csv_path, loaded_frames.clone(), loaded_labels.clone());
+        (loaded_frames, loaded_labels)
+    };
+    Ok((frames, labels))
+}
+
+// 監視中のファイルが変更されたら読み込み直し、"file-reloaded" イベントを発行する
+//
+// 再生中・一時停止中は入力が残らないよう、停止するまで読み込み直しを保留する（apply_pending_reloads）。
+fn reload_watched_file(app: &tauri::AppHandle, kind: WatchKind, path: String) {
+    let state = app.state::<AppState>();
+    let playback_state = state.player.lock().unwrap().get_state();
+    if matches!(playback_state, SequenceState::Playing | SequenceState::Paused) {
+        let mut pending = state.pending_reloads.lock().unwrap();
+        pending.retain(|(pending_kind, _)| *pending_kind != kind);
+        pending.push((kind, path.clone()));
+        println!("[ファイル監視] 再生中のため停止後に再読み込みします: {}", path);
+        return;
+    }
+
+    let result = match kind {
+        WatchKind::Sequence => {
+            // 別のシーケンスに切り替わっていれば何もしない
+            if state.player.lock().unwrap().get_current_path().as_deref() != Some(path.as_str()) {
+                return;
+            }
+            resolve_project_path(&path).and_then(|csv_path| {
+                state.frame_cache.lock().unwrap().remove(&csv_path);
+                load_sequence_file(&state, path.clone()).map(|_| ())
+            })
+        }
+        WatchKind::Mapping => apply_button_mapping(&state, &path).map(|_| ()),
+    };
+    match &result {
+        Ok(()) => println!("[ファイル監視] 再読み込み: {}", path),
+        Err(e) => println!("[ファイル監視] 再読み込みに失敗: {} ({})", path, e),
+    }
+    let _ = app.emit("file-reloaded", FileReloadedEvent { kind, path, error: result.err() });
+}
+
+// 再生中に保留したファイルの再読み込みを行う（再生が止まったときに呼ぶ）
+fn apply_pending_reloads(app: &tauri::AppHandle) {
+    let pending = std::mem::take(&mut *app.state::<AppState>().pending_reloads.lock().unwrap());
+    for (kind, path) in pending {
+        reload_watched_file(app, kind, path);
+    }
+}
+
 #[tauri::command]
-fn start_playback(state: State<AppState>) -> Result<(), String> {
+fn start_playback(hold_last_input: Option<bool>, state: State<AppState>) -> Result<(), String> {
     let mut player = state.player.lock().unwrap();
     let frame_count = player.frames.len();
     println!("[start_playback] シーケンスモード開始 - フレーム数: {}", frame_count);
     player.start();
+    // 終了時の入力保持は指定があれば今回の再生のみ上書き
+    if let Some(hold) = hold_last_input {
+        player.set_hold_for_current_playback(hold);
+    }
     println!("[start_playback] 状態: Playing (マニュアルモード無効)");
+    drop(player);
+
+    // OBS連携・リモート操作のクライアントにも開始を通知する
+    if let Some(app) = state.app_handle.lock().unwrap().as_ref() {
+        let _ = app.emit("playback-state-changed", "playing");
+    }
     Ok(())
 }
 
+/// シーケンスを最終行から先頭行へ逆順に再生（各ステップのdurationはそのまま）
 #[tauri::command]
-fn stop_playback(state: State<AppState>) -> Result<(), String> {
+fn start_playback_reversed(hold_last_input: Option<bool>, state: State<AppState>) -> Result<(), String> {
     let mut player = state.player.lock().unwrap();
-    player.stop();
+    let frame_count = player.frames.len();
+    println!("[start_playback_reversed] 逆再生開始 - フレーム数: {}", frame_count);
+    player.start_reversed();
+    if let Some(hold) = hold_last_input {
+        player.set_hold_for_current_playback(hold);
+    }
+    drop(player);
+
+    if let Some(app) = state.app_handle.lock().unwrap().as_ref() {
+        let _ = app.emit("playback-state-changed", "playing");
+    }
+    Ok(())
+}
+
+/// 現在のOS時刻（UNIXエポックからのミリ秒）
+fn unix_time_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 予約再生の発火をフロントエンドに通知
+fn emit_scheduled_playback_fired(app: &tauri::AppHandle, scheduled_unix_ms: Option<u64>) {
+    let event = ScheduledPlaybackFiredEvent {
+        scheduled_unix_ms,
+        fired_unix_ms: unix_time_ms(),
+    };
+    println!("[Schedule] 予約再生を開始: {:?}", event);
+    let _ = app.emit("scheduled-playback-fired", event);
+    let _ = app.emit("playback-state-changed", "playing");
+}
+
+/// 指定したOS時刻に再生を開始するよう予約する
+///
+/// start_at_unix_ms を省略すると外部トリガー（trigger_armed_playback）待ちになる。
+/// 複数PCで同期させる場合は、各PCのOS時刻がNTP等で同期されている必要がある。
+#[tauri::command]
+fn schedule_playback(
+    start_at_unix_ms: Option<u64>,
+    reversed: Option<bool>,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let deadline = match start_at_unix_ms {
+        Some(target_ms) => {
+            let now_ms = unix_time_ms();
+            if target_ms <= now_ms {
+                return Err(format!("予約時刻が過去です ({}ms 前)", now_ms - target_ms));
+            }
+            // OS時刻をモノトニック時刻に変換して保持（以降の時刻補正の影響を受けない）
+            Some(std::time::Instant::now() + std::time::Duration::from_millis(target_ms - now_ms))
+        }
+        None => None,
+    };
+
+    let mut player = state.player.lock().unwrap();
+    if player.frames.is_empty() {
+        return Err("シーケンスがロードされていません".to_string());
+    }
+    if player.get_state() == SequenceState::Playing {
+        return Err("再生中は予約できません".to_string());
+    }
+    player.arm(player::ArmedStart {
+        deadline,
+        scheduled_unix_ms: start_at_unix_ms,
+        reversed: reversed.unwrap_or(false),
+    });
+    Ok(())
+}
+
+/// 予約中の再生を即座に開始する（外部トリガー）
+#[tauri::command]
+fn trigger_armed_playback(state: State<AppState>) -> Result<(), String> {
+    let armed = {
+        let mut player = state.player.lock().unwrap();
+        player.fire_armed()
+    };
+    let armed = armed.ok_or_else(|| "予約された再生がありません".to_string())?;
+
+    if let Some(app) = state.app_handle.lock().unwrap().as_ref() {
+        emit_scheduled_playback_fired(app, armed.scheduled_unix_ms);
+    }
+    Ok(())
+}
+
+/// 再生予約を取り消す
+#[tauri::command]
+fn cancel_scheduled_playback(state: State<AppState>) -> Result<(), String> {
+    let mut player = state.player.lock().unwrap();
+    player.disarm();
+    Ok(())
+}
+
+/// 予約再生の状態
+#[derive(Debug, Clone, serde::Serialize)]
+struct ScheduledPlaybackStatus {
+    start_at_unix_ms: Option<u64>, // 予約時刻（外部トリガー待ちの場合は None）
+    reversed: bool,
+}
+
+/// 予約中の再生を取得（予約なしの場合は None）
+#[tauri::command]
+fn get_scheduled_playback(state: State<AppState>) -> Option<ScheduledPlaybackStatus> {
+    let player = state.player.lock().unwrap();
+    player.get_armed().map(|armed| ScheduledPlaybackStatus {
+        start_at_unix_ms: armed.scheduled_unix_ms,
+        reversed: armed.reversed,
+    })
+}
+
+#[tauri::command]
+fn stop_playback(state: State<AppState>) -> Result<(), String> {
+    state.player.lock().unwrap().stop();
 
     // 停止時はコントローラーに中立入力を送信して、物理デバイス上で入力が残らないようにする
     {
         let mut controller = state.controller.lock().unwrap();
-        let neutral_frame = InputFrame {
-            duration: 1,
-            direction: 5,
-            buttons: HashMap::new(),
-            thumb_lx: 0,
-            thumb_ly: 0,
-            thumb_rx: 0,
-            thumb_ry: 0,
-            left_trigger: 0,
-            right_trigger: 0,
-        };
+        let neutral_frame = InputFrame::neutral(1);
 
         if let Err(e) = controller.update_input(&neutral_frame, false) {
             eprintln!("警告: 停止時の中立入力送信に失敗しました: {:?}", e);
         }
     }
 
-    // フロントエンドに即時に停止イベントを送出
-    if let Some(app) = state.app_handle.lock().unwrap().as_ref() {
-        let _ = app.emit("playback-state-changed", "stopped");
+    // フロントエンドに即時に停止イベントを送出
+    let app = state.app_handle.lock().unwrap().clone();
+    if let Some(app) = &app {
+        let _ = app.emit("playback-state-changed", "stopped");
+    }
+
+    println!("[stop_playback] シーケンスモード停止 (マニュアルモード有効)");
+
+    // 再生中に変更されたファイルを読み込み直す
+    if let Some(app) = &app {
+        apply_pending_reloads(app);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn pause_playback(state: State<AppState>) -> Result<(), String> {
+    let mut player = state.player.lock().unwrap();
+    player.pause();
+    Ok(())
+}
+
+#[tauri::command]
+fn resume_playback(state: State<AppState>) -> Result<(), String> {
+    let mut player = state.player.lock().unwrap();
+    player.resume();
+    let playing = player.get_state() == SequenceState::Playing;
+    drop(player);
+
+    // ブレークポイントからの再開は状態変化の監視では通知されないため、ここで通知する
+    if playing {
+        if let Some(app) = state.app_handle.lock().unwrap().as_ref() {
+            let _ = app.emit("playback-state-changed", "playing");
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn reload_current_sequence(app: tauri::AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<(), String> {
+        let state = app.state::<AppState>();
+        let player = state.player.lock().unwrap();
+        let current_path = player.get_current_path()
+            .ok_or_else(|| "再生中のシーケンスがありません".to_string())?;
+        drop(player); // unlock before reloading
+
+        // キャッシュをクリアして再ロード
+        state.frame_cache.lock().unwrap().remove(&resolve_project_path(&current_path)?);
+
+        // 再ロード（キャッシュなしで読み込み直す）
+        load_sequence_file(&state, current_path)?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("読み込みスレッドの実行に失敗: {}", e))?
+}
+
+/// シーケンスキャッシュの使用状況
+#[tauri::command]
+fn get_cache_stats(state: State<AppState>) -> CacheStats {
+    state.frame_cache.lock().unwrap().stats()
+}
+
+/// シーケンスキャッシュを空にする
+#[tauri::command]
+fn clear_cache(state: State<AppState>) {
+    state.frame_cache.lock().unwrap().clear();
+    println!("[キャッシュ] クリアしました");
+}
+
+/// シーケンスキャッシュの上限（MB）を設定し、設定ファイルに保存
+#[tauri::command]
+fn set_cache_max_megabytes(megabytes: u32, state: State<AppState>) -> Result<(), String> {
+    state.frame_cache.lock().unwrap().set_max_megabytes(megabytes);
+
+    let mut config = AppConfig::load_or_default();
+    config.cache.max_megabytes = megabytes;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn set_loop_playback(loop_enabled: bool, state: State<AppState>) -> Result<(), String> {
+    let mut player = state.player.lock().unwrap();
+    player.set_loop_playback(loop_enabled);
+    drop(player);
+    save_session(|session| session.loop_playback = loop_enabled);
+    Ok(())
+}
+
+/// 各ステップのフレーム数に掛ける倍率を設定（ファイルは変更しない）
+/// 例: 60FPS用のシーケンスを30FPSのゲームで再生する場合は 0.5
+#[tauri::command]
+fn set_duration_scale(scale: f32, state: State<AppState>) -> Result<(), String> {
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(format!("倍率は正の値を指定してください: {}", scale));
+    }
+    let mut player = state.player.lock().unwrap();
+    player.set_duration_scale(scale);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_duration_scale(state: State<AppState>) -> f32 {
+    let player = state.player.lock().unwrap();
+    player.get_duration_scale()
+}
+
+/// 同じボタンが連続するステップ間に挿入するリリースのフレーム数を設定し、設定ファイルに保存
+/// （再生と動画からのCSV生成の両方に適用）
+#[tauri::command]
+fn set_release_gap_frames(frames: u32, state: State<AppState>) -> Result<(), String> {
+    state.player.lock().unwrap().set_release_gap_frames(frames);
+
+    let mut config = AppConfig::load_or_default();
+    config.playback.release_gap_frames = frames;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_release_gap_frames(state: State<AppState>) -> u32 {
+    let player = state.player.lock().unwrap();
+    player.get_release_gap_frames()
+}
+
+/// 入力遅延の補正（符号付きフレーム数）を設定し、設定ファイルに保存
+///
+/// 負の値（早める補正）は時刻を指定した予約再生でのみ適用される。即時開始では開始時刻より前に送れないため補正しない。
+#[tauri::command]
+fn set_latency_offset_frames(frames: i32, state: State<AppState>) -> Result<(), String> {
+    state.player.lock().unwrap().set_latency_offset_frames(frames);
+
+    let mut config = AppConfig::load_or_default();
+    config.playback.latency_offset_frames = frames;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_latency_offset_frames(state: State<AppState>) -> i32 {
+    let player = state.player.lock().unwrap();
+    player.get_latency_offset_frames()
+}
+
+/// 送信の遅れを警告するしきい値（ミリ秒、0で無効）を設定し、設定ファイルに保存
+#[tauri::command]
+fn set_late_step_warning_ms(ms: u32, state: State<AppState>) -> Result<(), String> {
+    state.player.lock().unwrap().set_late_step_warning_ms(ms);
+
+    let mut config = AppConfig::load_or_default();
+    config.playback.late_step_warning_ms = ms;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_late_step_warning_ms(state: State<AppState>) -> u32 {
+    let player = state.player.lock().unwrap();
+    player.get_late_step_warning_ms()
+}
+
+/// 再生開始時の同期マーカー設定を変更し、設定ファイルに保存
+#[tauri::command]
+fn set_sync_marker_settings(settings: SyncMarkerSettings, state: State<AppState>) -> Result<(), String> {
+    state.player.lock().unwrap().set_sync_marker(settings.clone());
+
+    let mut config = AppConfig::load_or_default();
+    config.playback.sync_marker = settings;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_sync_marker_settings(state: State<AppState>) -> SyncMarkerSettings {
+    let player = state.player.lock().unwrap();
+    player.get_sync_marker()
+}
+
+/// オーバーレイモード（再生中の手動入力をシーケンスの入力に重ねる）を切り替える
+#[tauri::command]
+fn set_manual_overlay(enabled: bool, state: State<AppState>) -> Result<(), String> {
+    let mut player = state.player.lock().unwrap();
+    player.set_manual_overlay(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_manual_overlay(state: State<AppState>) -> bool {
+    let player = state.player.lock().unwrap();
+    player.get_manual_overlay()
+}
+
+/// ブレークポイント（到達時に一時停止するステップの行番号）を設定
+/// 一時停止後は resume_playback で続きから再生する
+#[tauri::command]
+fn set_step_breakpoints(steps: Vec<usize>, state: State<AppState>) -> Result<(), String> {
+    let mut player = state.player.lock().unwrap();
+    player.set_breakpoints(steps);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_step_breakpoints(state: State<AppState>) -> Vec<usize> {
+    let player = state.player.lock().unwrap();
+    player.get_breakpoints()
+}
+
+/// 編集セッションを開く（既に開いていればその内容を返す。ウィンドウごとに close_edit_session で閉じる）
+#[tauri::command]
+fn open_edit_session(path: String, state: State<AppState>) -> Result<EditSessionState, String> {
+    let key = path.replace('\\', "/");
+    let mut sessions = state.edit_sessions.lock().unwrap();
+    if let Some(session) = sessions.get_mut(&key) {
+        session.acquire();
+        return Ok(session.state(&key));
+    }
+    let (frames, _labels) = read_sequence_frames(&state, &resolve_project_path(&key)?)
+        .map_err(|e| format!("編集セッションの読み込みに失敗: {}", e))?;
+    println!("[編集セッション] 開始: {} ({}行)", key, frames.len());
+    // 編集で書き換えるため、共有しているフレームデータはここでコピーする
+    let session = EditSession::new(frames.as_ref().clone());
+    let session_state = session.state(&key);
+    sessions.insert(key, session);
+    Ok(session_state)
+}
+
+// 編集セッションに操作を適用し、全ウィンドウに変更した行を通知する
+fn apply_edit<F>(path: &str, state: &AppState, app: &tauri::AppHandle, edit: F) -> Result<EditSessionChange, String>
+where
+    F: FnOnce(&mut EditSession) -> anyhow::Result<Option<FrameSplice>>,
+{
+    let key = path.replace('\\', "/");
+    let mut sessions = state.edit_sessions.lock().unwrap();
+    let session = sessions.get_mut(&key)
+        .ok_or_else(|| format!("編集セッションが開かれていません: {}", key))?;
+    let splice = edit(session).map_err(|e| format!("編集に失敗: {}", e))?;
+    let change = session.change(&key, splice);
+    let _ = app.emit("edit-session-changed", &change);
+    Ok(change)
+}
+
+/// index の位置に行を挿入
+#[tauri::command]
+fn insert_frames(path: String, index: usize, frames: Vec<InputFrame>, state: State<AppState>, app: tauri::AppHandle) -> Result<EditSessionChange, String> {
+    apply_edit(&path, &state, &app, |session| session.insert(index, frames))
+}
+
+/// start..end の行を削除
+#[tauri::command]
+fn delete_frames(path: String, start: usize, end: usize, state: State<AppState>, app: tauri::AppHandle) -> Result<EditSessionChange, String> {
+    apply_edit(&path, &state, &app, |session| session.delete(start, end))
+}
+
+/// start..end の行を複製して直後に挿入
+#[tauri::command]
+fn duplicate_frames(path: String, start: usize, end: usize, state: State<AppState>, app: tauri::AppHandle) -> Result<EditSessionChange, String> {
+    apply_edit(&path, &state, &app, |session| session.duplicate(start, end))
+}
+
+/// index の行を置き換える
+#[tauri::command]
+fn update_frame(path: String, index: usize, frame: InputFrame, state: State<AppState>, app: tauri::AppHandle) -> Result<EditSessionChange, String> {
+    apply_edit(&path, &state, &app, |session| session.update(index, frame))
+}
+
+#[tauri::command]
+fn undo_edit(path: String, state: State<AppState>, app: tauri::AppHandle) -> Result<EditSessionChange, String> {
+    apply_edit(&path, &state, &app, |session| Ok(session.undo()))
+}
+
+#[tauri::command]
+fn redo_edit(path: String, state: State<AppState>, app: tauri::AppHandle) -> Result<EditSessionChange, String> {
+    apply_edit(&path, &state, &app, |session| Ok(session.redo()))
+}
+
+/// 編集セッションの内容をファイルに保存
+#[tauri::command]
+fn save_edit_session(path: String, state: State<AppState>, app: tauri::AppHandle) -> Result<EditSessionChange, String> {
+    let key = path.replace('\\', "/");
+    let frames = {
+        let sessions = state.edit_sessions.lock().unwrap();
+        let session = sessions.get(&key)
+            .ok_or_else(|| format!("編集セッションが開かれていません: {}", key))?;
+        session.frames().to_vec()
+    };
+    write_frames_csv(&state, &key, &frames, None)?;
+    apply_edit(&key, &state, &app, |session| {
+        session.mark_saved();
+        Ok(None)
+    })
+}
+
+/// 編集セッションを閉じる（最後のウィンドウが閉じたときに破棄し、未保存の変更も破棄する）
+#[tauri::command]
+fn close_edit_session(path: String, state: State<AppState>) {
+    let key = path.replace('\\', "/");
+    let mut sessions = state.edit_sessions.lock().unwrap();
+    if sessions.get_mut(&key).is_some_and(|session| session.release()) {
+        sessions.remove(&key);
+        println!("[編集セッション] 終了: {}", key);
+    }
+}
+
+/// 読み込み中のシーケンスのラベル一覧
+#[tauri::command]
+fn get_sequence_labels(state: State<AppState>) -> Vec<SequenceLabel> {
+    let player = state.player.lock().unwrap();
+    player.get_labels().to_vec()
+}
+
+/// ラベルを付けたステップへ移動（停止中はそのステップから再生開始）。戻り値は行番号
+#[tauri::command]
+fn jump_to_label(name: String, state: State<AppState>) -> Result<usize, String> {
+    let mut player = state.player.lock().unwrap();
+    player.jump_to_label(&name)
+        .map_err(|e| format!("ラベルへの移動に失敗: {}", e))
+}
+
+/// 指定した行のステップへ移動（停止中はそのステップから再生開始）
+#[tauri::command]
+fn seek_playback(step: usize, state: State<AppState>) -> Result<usize, String> {
+    let mut player = state.player.lock().unwrap();
+    player.seek(step)
+        .map_err(|e| format!("ステップへの移動に失敗: {}", e))
+}
+
+/// このセッションの再生履歴を取得
+#[tauri::command]
+fn get_playback_history(state: State<AppState>) -> Vec<playback_history::PlaybackRun> {
+    let player = state.player.lock().unwrap();
+    player.get_history().runs.clone()
+}
+
+#[tauri::command]
+fn clear_playback_history(state: State<AppState>) -> Result<(), String> {
+    let mut player = state.player.lock().unwrap();
+    player.clear_history();
+    Ok(())
+}
+
+/// 再生履歴をCSV形式で書き出す
+#[tauri::command]
+fn export_playback_history_csv(path: String, state: State<AppState>) -> Result<(), String> {
+    let history = state.player.lock().unwrap().get_history().clone();
+    history.save_csv(&path)
+        .map_err(|e| format!("再生履歴の書き出しに失敗: {}", e))
+}
+
+/// 再生履歴をJSON形式で書き出す
+#[tauri::command]
+fn export_playback_history_json(path: String, state: State<AppState>) -> Result<(), String> {
+    let history = state.player.lock().unwrap().get_history().clone();
+    history.save_json(&path)
+        .map_err(|e| format!("再生履歴の書き出しに失敗: {}", e))
+}
+
+/// ロード済みシーケンスの再生時間と各ステップの開始時刻を計算（コントローラーには送信しない）
+#[tauri::command]
+fn get_sequence_timeline(fps: Option<u32>, state: State<AppState>) -> Result<types::SequenceTimeline, String> {
+    let player = state.player.lock().unwrap();
+    if player.frames.is_empty() {
+        return Err("シーケンスがロードされていません".to_string());
+    }
+    Ok(player.timeline(fps))
+}
+
+/// 直近のコントローラー送信ログを取得（path を指定した場合はJSONファイルにも書き出す）
+#[tauri::command]
+fn dump_controller_log(
+    path: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<controller::ControllerLogEntry>, String> {
+    let entries = state.controller.lock().unwrap().log_entries();
+    if let Some(path) = path {
+        let content = serde_json::to_string_pretty(&entries)
+            .map_err(|e| format!("送信ログのシリアライズに失敗: {}", e))?;
+        std::fs::write(&path, content)
+            .map_err(|e| format!("送信ログの書き出しに失敗: {}", e))?;
+        println!("[ControllerLog] {}件を書き出しました: {}", entries.len(), path);
+    }
+    Ok(entries)
+}
+
+/// コントローラー送信ログの保持件数を設定（0で記録しない）
+#[tauri::command]
+fn set_controller_log_capacity(capacity: usize, state: State<AppState>) -> Result<(), String> {
+    let mut controller = state.controller.lock().unwrap();
+    controller.set_log_capacity(capacity);
+    Ok(())
+}
+
+#[tauri::command]
+fn clear_controller_log(state: State<AppState>) -> Result<(), String> {
+    let mut controller = state.controller.lock().unwrap();
+    controller.clear_log();
+    Ok(())
+}
+
+// セルフテストの各項目を押し続ける時間・項目間の中立時間の標準値
+const SELFTEST_DEFAULT_HOLD_MS: u64 = 300;
+const SELFTEST_GAP_MS: u64 = 150;
+
+/// 仮想コントローラーのセルフテスト
+/// マッピングされたボタン・8方向・トリガー・スティックの端を順に押し、
+/// "controller-selftest-progress" イベントで進捗を通知する。実行した項目数を返す
+#[tauri::command]
+async fn run_controller_selftest(hold_ms: Option<u64>, app: tauri::AppHandle) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<usize, String> {
+        let state = app.state::<AppState>();
+        let hold = std::time::Duration::from_millis(hold_ms.unwrap_or(SELFTEST_DEFAULT_HOLD_MS));
+        let gap = std::time::Duration::from_millis(SELFTEST_GAP_MS);
+
+        let steps = {
+            let player = state.player.lock().unwrap();
+            if player.get_state() == SequenceState::Playing {
+                return Err("再生中はセルフテストを実行できません".to_string());
+            }
+            selftest::selftest_steps(player.get_button_mapping())
+        };
+        if !state.controller.lock().unwrap().is_connected() {
+            return Err("Controller not connected".to_string());
+        }
+        println!("[SelfTest] {}項目のセルフテストを開始", steps.len());
+
+        let total = steps.len();
+        let send = |frame: &InputFrame| -> Result<(), String> {
+            state.controller.lock().unwrap().update_input(frame, false)
+                .map_err(|e| format!("セルフテストの送信に失敗: {}", e))
+        };
+        for (index, step) in steps.iter().enumerate() {
+            // 再生が始まったら送信が混ざらないよう中断する
+            if state.player.lock().unwrap().get_state() == SequenceState::Playing {
+                return Err("再生が開始されたためセルフテストを中断しました".to_string());
+            }
+            let _ = app.emit("controller-selftest-progress", selftest::SelfTestProgressEvent {
+                index,
+                total,
+                label: step.label.clone(),
+            });
+            send(&step.frame)?;
+            std::thread::sleep(hold);
+            send(&selftest::neutral_frame())?;
+            std::thread::sleep(gap);
+        }
+        let _ = app.emit("controller-selftest-progress", selftest::SelfTestProgressEvent {
+            index: total,
+            total,
+            label: "完了".to_string(),
+        });
+        println!("[SelfTest] セルフテストが完了しました");
+        Ok(total)
+    })
+    .await
+    .map_err(|e| format!("セルフテストに失敗: {}", e))?
+}
+
+/// 入力オーバーレイのウィンドウラベル
+const OVERLAY_WINDOW_LABEL: &str = "overlay";
+
+/// 実行中のジョブ（動画からの抽出など）のキャンセルを要求
+/// 該当するジョブが無い（既に終了した）場合は false を返す
+#[tauri::command]
+fn cancel_job(job_id: String, state: State<AppState>) -> bool {
+    let found = state.jobs.cancel(&job_id);
+    if found {
+        println!("[Job] キャンセルを要求: {}", job_id);
+    }
+    found
+}
+
+/// ジョブの一覧を取得（新しい順、終了済みのジョブも含む）
+#[tauri::command]
+fn list_jobs(state: State<AppState>) -> Vec<jobs::JobInfo> {
+    state.jobs.list()
+}
+
+/// ジョブの状態を取得
+#[tauri::command]
+fn get_job_status(job_id: String, state: State<AppState>) -> Result<jobs::JobInfo, String> {
+    state
+        .jobs
+        .get(&job_id)
+        .ok_or_else(|| format!("ジョブが見つかりません: {}", job_id))
+}
+
+/// 他の input_player との競合を確認（多重起動・仮想コントローラーの使用中）
+#[tauri::command]
+fn check_instance_conflict(state: State<AppState>) -> instance::InstanceConflict {
+    let controller = state.controller.lock().unwrap();
+    let own_target = controller.is_connected() && controller.backend_name() == output_backend::VIGEM_BACKEND;
+    instance::InstanceConflict {
+        another_instance: state.instance_guard.is_none(),
+        target_in_use: !own_target && instance::NamedMutex::exists(instance::CONTROLLER_TARGET_NAME),
     }
-
-    println!("[stop_playback] シーケンスモード停止 (マニュアルモード有効)");
-    Ok(())
 }
 
+/// 現在コントローラーに送信している状態を取得
 #[tauri::command]
-fn pause_playback(state: State<AppState>) -> Result<(), String> {
-    let mut player = state.player.lock().unwrap();
-    player.pause();
-    Ok(())
+fn get_controller_output(state: State<AppState>) -> Result<controller::ControllerOutput, String> {
+    Ok(state.controller.lock().unwrap().current_output())
 }
 
+/// 配信用の入力オーバーレイ（最前面・透過の小さなウィンドウ）を開く
+/// 送信中の状態は再生スレッドから "controller-output" イベントで毎フレーム送られる
 #[tauri::command]
-fn resume_playback(state: State<AppState>) -> Result<(), String> {
-    let mut player = state.player.lock().unwrap();
-    player.resume();
+fn open_overlay_window(app: tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(OVERLAY_WINDOW_LABEL) {
+        window.set_focus().map_err(|e| format!("オーバーレイの表示に失敗: {}", e))?;
+        return Ok(());
+    }
+    tauri::WebviewWindowBuilder::new(&app, OVERLAY_WINDOW_LABEL, tauri::WebviewUrl::App("overlay.html".into()))
+        .title("Input Overlay")
+        .inner_size(320.0, 140.0)
+        .resizable(true)
+        .transparent(true)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .shadow(false)
+        .build()
+        .map_err(|e| format!("オーバーレイの作成に失敗: {}", e))?;
+    println!("[Overlay] 入力オーバーレイを開きました");
     Ok(())
 }
 
+/// 直近の再生の送信タイミング（予定時刻とのずれ）を取得
 #[tauri::command]
-fn reload_current_sequence(state: State<AppState>) -> Result<(), String> {
+fn get_playback_timing_report(state: State<AppState>) -> PlaybackTimingReport {
     let player = state.player.lock().unwrap();
-    let current_path = player.get_current_path()
-        .ok_or_else(|| "再生中のシーケンスがありません".to_string())?;
-    drop(player); // unlock before reloading
-
-    // キャッシュをクリアして再ロード
-    let mut cache = state.frame_cache.lock().unwrap();
-    cache.remove(&current_path);
-    drop(cache);
-
-    // 再ロード（キャッシュなしで読み込み直す）
-    load_input_file(current_path, state)?;
-    Ok(())
+    player.timing_report()
 }
 
 #[tauri::command]
-fn set_loop_playback(loop_enabled: bool, state: State<AppState>) -> Result<(), String> {
+fn set_hold_last_input(hold: bool, state: State<AppState>) -> Result<(), String> {
     let mut player = state.player.lock().unwrap();
-    player.set_loop_playback(loop_enabled);
+    player.set_hold_last_input(hold);
     Ok(())
 }
 
+#[tauri::command]
+fn get_hold_last_input(state: State<AppState>) -> bool {
+    let player = state.player.lock().unwrap();
+    player.get_hold_last_input()
+}
+
 #[tauri::command]
 fn set_invert_horizontal(invert: bool, state: State<AppState>) -> Result<(), String> {
     let mut player = state.player.lock().unwrap();
     player.set_invert_horizontal(invert);
+    drop(player);
+    save_session(|session| session.invert_horizontal = invert);
     Ok(())
 }
 
@@ -249,22 +1213,16 @@ fn get_playback_progress(state: State<AppState>) -> (usize, usize) {
 
 #[tauri::command]
 fn load_button_mapping(path: String, state: State<AppState>) -> Result<ButtonMapping, String> {
+    apply_button_mapping(&state, &path)
+}
+
+// マッピングファイルを読み込んでプレイヤーに設定し、変更監視に登録する
+fn apply_button_mapping(state: &AppState, path: &str) -> Result<ButtonMapping, String> {
     // パスの区切り文字を正規化
     let normalized_path = path.replace('\\', "/");
 
     // 相対パスを絶対パスに変換
-    let mapping_path = if std::path::Path::new(&normalized_path).is_absolute() {
-        PathBuf::from(&normalized_path)
-    } else {
-        let current = std::env::current_dir()
-            .map_err(|e| format!("Failed to get current directory: {}", e))?;
-        let project_root = if current.ends_with("src-tauri") {
-            current.parent().unwrap().to_path_buf()
-        } else {
-            current
-        };
-        project_root.join(&normalized_path)
-    };
+    let mapping_path = resolve_project_path(&normalized_path)?;
 
     if !mapping_path.exists() {
         return Err(format!("ファイルが見つかりません: {:?}", mapping_path));
@@ -302,28 +1260,51 @@ fn load_button_mapping(path: String, state: State<AppState>) -> Result<ButtonMap
     // シーケンス用ボタンの順序を保存
     let mut button_order = state.button_order.lock().unwrap();
     *button_order = button_order_vec;
+    drop(button_order);
+    drop(player);
+
+    state.file_watcher.lock().unwrap().watch(WatchKind::Mapping, &mapping_path, path.to_string());
+    save_session(|session| session.mapping_path = Some(path.to_string()));
 
     Ok(mapping)
 }
 
+/// コントローラーの標準的なボタンマッピングを生成して保存（既存のファイルは overwrite 指定時のみ上書き）
+#[tauri::command]
+fn generate_default_mapping(
+    controller_type: ControllerType,
+    path: String,
+    overwrite: Option<bool>,
+    state: State<AppState>,
+) -> Result<ButtonMapping, String> {
+    let mapping_path = resolve_project_path(&path.replace('\\', "/"))?;
+    if mapping_path.exists() && !overwrite.unwrap_or(false) {
+        return Err(format!("ファイルが既に存在します: {:?}", mapping_path));
+    }
+    let mapping = ButtonMapping::default_for(controller_type);
+    save_button_mapping(path, mapping.clone(), state)?;
+    println!("[マッピング] {} の標準マッピングを生成: {:?}", mapping.controller_type, mapping_path);
+    Ok(mapping)
+}
+
+/// ボタンマッピングファイルを検証し、行ごとの診断結果を返す（問題が無ければ空）
+#[tauri::command]
+fn validate_button_mapping(path: String) -> Result<Vec<mapping_validation::MappingDiagnostic>, String> {
+    let mapping_path = resolve_project_path(&path.replace('\\', "/"))?;
+    let content = std::fs::read_to_string(&mapping_path)
+        .map_err(|e| format!("ファイルの読み込みエラー: {} (パス: {:?})", e, mapping_path))?;
+    let mapping: ButtonMapping = serde_json::from_str(&content)
+        .map_err(|e| format!("JSON解析エラー: {} (パス: {:?})", e, mapping_path))?;
+    Ok(mapping_validation::validate_mapping(&mapping))
+}
+
 #[tauri::command]
-fn save_button_mapping(path: String, mapping: ButtonMapping) -> Result<(), String> {
+fn save_button_mapping(path: String, mapping: ButtonMapping, state: State<AppState>) -> Result<(), String> {
     // パスの区切り文字を正規化
     let normalized_path = path.replace('\\', "/");
 
     // 相対パスを絶対パスに変換
-    let mapping_path = if std::path::Path::new(&normalized_path).is_absolute() {
-        PathBuf::from(&normalized_path)
-    } else {
-        let current = std::env::current_dir()
-            .map_err(|e| format!("Failed to get current directory: {}", e))?;
-        let project_root = if current.ends_with("src-tauri") {
-            current.parent().unwrap().to_path_buf()
-        } else {
-            current
-        };
-        project_root.join(&normalized_path)
-    };
+    let mapping_path = resolve_project_path(&normalized_path)?;
 
     // ディレクトリが存在しない場合は作成
     if let Some(parent) = mapping_path.parent() {
@@ -338,6 +1319,7 @@ fn save_button_mapping(path: String, mapping: ButtonMapping) -> Result<(), Strin
 
     std::fs::write(&mapping_path, content)
         .map_err(|e| format!("File write error: {}", e))?;
+    state.file_watcher.lock().unwrap().mark_saved(&mapping_path);
 
     Ok(())
 }
@@ -348,15 +1330,43 @@ fn update_manual_input(
     buttons: std::collections::HashMap<String, u8>,
     state: State<AppState>,
 ) -> Result<(), String> {
-    // 再生モード中はマニュアル入力を無視
-    let player = state.player.lock().unwrap();
+    let mut player = state.player.lock().unwrap();
     let is_playing = player.get_state() == SequenceState::Playing;
-    drop(player);
 
+    // オーバーレイモード: 手動入力をシーケンスの入力に重ねて送信
+    if player.get_manual_overlay() {
+        let mut manual_input = state.manual_input.lock().unwrap();
+        manual_input.direction = direction;
+        manual_input.buttons = buttons.clone();
+        let overlay = player.update_manual_overlay(manual_input.clone());
+        drop(manual_input);
+
+        if let Some((frame, invert_horizontal)) = overlay {
+            // 再生ループと同じく player → controller の順でロックし、ステップの送信と入れ替わらないようにする
+            let mut controller = state.controller.lock().unwrap();
+            if !controller.is_connected() {
+                return Err("Controller not connected".to_string());
+            }
+            return controller.update_input(&frame, invert_horizontal)
+                .map_err(|e| e.to_string());
+        }
+    }
+
+    // 再生モード中はマニュアル入力を無視
     if is_playing {
         return Ok(()); // 再生中は無視
     }
 
+    // 再生終了後の入力保持中は、何か入力されるまで最終ステップの状態を維持する
+    if player.is_holding_input() {
+        let is_neutral = direction == 5 && buttons.values().all(|&v| v == 0);
+        if is_neutral {
+            return Ok(());
+        }
+        player.release_held_input();
+    }
+    drop(player);
+
     let mut controller = state.controller.lock().unwrap();
 
     if !controller.is_connected() {
@@ -387,6 +1397,8 @@ fn set_fps(fps: u32, state: State<AppState>) -> Result<(), String> {
     // Playerにも新しいFPSを設定
     let mut player = state.player.lock().unwrap();
     player.set_fps(fps);
+    drop(player);
+    save_session(|session| session.fps = fps);
     
     Ok(())
 }
@@ -398,101 +1410,148 @@ fn get_fps(state: State<AppState>) -> u32 {
 }
 
 #[tauri::command]
-fn get_csv_button_names(path: String) -> Result<Vec<String>, String> {
-    // パスの区切り文字を正規化（\ を / に統一）
-    let normalized_path = path.replace('\\', "/");
-
-    // 相対パスを絶対パスに変換
-    let csv_path = if std::path::Path::new(&normalized_path).is_absolute() {
-        PathBuf::from(&normalized_path)
-    } else {
+async fn get_csv_button_names(path: String) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
         // 開発時は src-tauri がカレントディレクトリなので、親ディレクトリ（プロジェクトルート）からの相対パスとして解決
-        let current = std::env::current_dir()
-            .map_err(|e| format!("Failed to get current directory: {}", e))?;
-        let project_root = if current.ends_with("src-tauri") {
-            current.parent().unwrap().to_path_buf()
-        } else {
-            current
-        };
-        project_root.join(&normalized_path)
-    };
-
-    if !csv_path.exists() {
-        return Err(format!("File not found: {:?}", csv_path));
-    }
+        let csv_path = resolve_project_path(&path.replace('\\', "/"))?;
+        if !csv_path.exists() {
+            return Err(format!("File not found: {:?}", csv_path));
+        }
 
-    csv_loader::get_csv_button_names(&csv_path)
-        .map_err(|e| format!("CSV read error: {}", e))
+        csv_loader::get_csv_button_names(&csv_path)
+            .map_err(|e| format!("CSV read error: {}", e))
+    })
+    .await
+    .map_err(|e| format!("読み込みスレッドの実行に失敗: {}", e))?
 }
 
+/// シーケンスCSVをバイナリ形式に変換（戻り値はステップ数）
 #[tauri::command]
-fn load_frames_for_edit(path: String) -> Result<Vec<InputFrame>, String> {
-    println!("========== load_frames_for_edit ==========");
-    println!("Requested path: {}", path);
-
-    let normalized_path = path.replace('\\', "/");
-    println!("Normalized path: {}", normalized_path);
-
-    let csv_path = if std::path::Path::new(&normalized_path).is_absolute() {
-        PathBuf::from(&normalized_path)
-    } else {
-        let current = std::env::current_dir()
-            .map_err(|e| format!("Failed to get current directory: {}", e))?;
-        let project_root = if current.ends_with("src-tauri") {
-            current.parent().unwrap().to_path_buf()
-        } else {
-            current
-        };
-        project_root.join(&normalized_path)
-    };
-
-    println!("Final CSV path: {:?}", csv_path);
+fn convert_sequence_to_binary(path: String, out_path: String) -> Result<usize, String> {
+    let steps = sequence_binary::convert_csv_to_binary(
+        std::path::Path::new(&path),
+        std::path::Path::new(&out_path),
+    )
+    .map_err(|e| format!("バイナリ形式への変換に失敗: {}", e))?;
+    println!("[バイナリ変換] {} → {} ({}ステップ)", path, out_path, steps);
+    Ok(steps)
+}
 
-    if !csv_path.exists() {
-        eprintln!("✗ File not found: {:?}", csv_path);
-        return Err(format!("File not found: {:?}", csv_path));
-    }
+/// バイナリ形式のシーケンスをCSVに変換（戻り値はステップ数）
+#[tauri::command]
+fn convert_binary_to_csv(path: String, out_path: String) -> Result<usize, String> {
+    let steps = sequence_binary::convert_binary_to_csv(
+        std::path::Path::new(&path),
+        std::path::Path::new(&out_path),
+    )
+    .map_err(|e| format!("CSVへの変換に失敗: {}", e))?;
+    println!("[バイナリ変換] {} → {} ({}ステップ)", path, out_path, steps);
+    Ok(steps)
+}
 
-    println!("✓ File exists, loading CSV...");
-    let result = load_csv(&csv_path)
-        .map_err(|e| {
-            eprintln!("✗ CSV load error: {}", e);
-            format!("CSV load error: {}", e)
-        });
+/// シーケンスCSVのフレーム数を別のFPS用に変換して out_path に保存
+/// 丸め誤差は累積させず、ステップごとの誤差をレポートとして返す
+#[tauri::command]
+fn convert_sequence_fps(
+    path: String,
+    from_fps: u32,
+    to_fps: u32,
+    out_path: String,
+) -> Result<csv_loader::FpsConversionReport, String> {
+    let report = csv_loader::convert_csv_fps(
+        std::path::Path::new(&path),
+        from_fps,
+        to_fps,
+        std::path::Path::new(&out_path),
+    )
+    .map_err(|e| format!("FPS変換に失敗: {}", e))?;
+    println!(
+        "[FPS変換] {}fps → {}fps: {}F → {}F (最大累積誤差 {:.2}F)",
+        from_fps, to_fps, report.original_total_frames, report.converted_total_frames,
+        report.max_abs_cumulative_error
+    );
+    Ok(report)
+}
 
-    if let Ok(ref frames) = result {
-        println!("✓ Loaded {} frames", frames.len());
-    }
+/// シーケンスCSVに変換（ずらし・倍率・左右反転・ボタン列のクリア）を順に適用して保存
+/// out_path を省略した場合は元のファイルの隣に `元の名前_transformed.csv` として保存する。
+/// ラベル行・コメント行は変換後も同じステップの前に残す。戻り値は変換後のステップ数
+#[tauri::command]
+fn transform_sequence(
+    path: String,
+    operations: Vec<transform::SequenceTransform>,
+    out_path: Option<String>,
+    state: State<AppState>,
+) -> Result<usize, String> {
+    let source = resolve_project_path(&path.replace('\\', "/"))?;
+    let mut frames = load_csv(&source)
+        .map_err(|e| format!("シーケンスの読み込みに失敗: {}", e))?;
+    let mut annotations = csv_loader::read_annotations(&source)
+        .map_err(|e| format!("シーケンスの読み込みに失敗: {}", e))?;
+    let steps_before = frames.len();
+    transform::apply_transforms(&mut frames, &operations)
+        .map_err(|e| format!("シーケンスの変換に失敗: {}", e))?;
+    // ステップ数が変わるのは先頭の追加・削除（ずらし）だけなので、その分だけ位置をずらす
+    let added = frames.len() as isize - steps_before as isize;
+    annotations.remap_steps(|step| step.saturating_add_signed(added));
+    let out_path = out_path.unwrap_or_else(|| {
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        source.with_file_name(format!("{}_transformed.csv", stem)).to_string_lossy().replace('\\', "/")
+    });
+    write_frames_csv(&state, &out_path, &frames, Some(annotations))?;
+    println!("[一括変換] {} 件の操作を適用: {} ({} ステップ)", operations.len(), out_path, frames.len());
+    Ok(frames.len())
+}
 
-    result
+/// エディタ用にシーケンスを読み込む（キャッシュ済みならファイルを読み直さず共有する）
+///
+/// 読み込みはブロッキング用スレッドで行い、大きなファイルは "sequence-load-progress" で進捗を通知する。
+#[tauri::command]
+async fn load_frames_for_edit(path: String, app: tauri::AppHandle) -> Result<Arc<Vec<InputFrame>>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let csv_path = resolve_project_path(&path.replace('\\', "/"))?;
+        if !csv_path.exists() {
+            return Err(format!("File not found: {:?}", csv_path));
+        }
+        let (frames, _labels) = read_sequence_frames(&app.state::<AppState>(), &csv_path)?;
+        println!("[load_frames_for_edit] {} - {}フレーム", path, frames.len());
+        Ok(frames)
+    })
+    .await
+    .map_err(|e| format!("読み込みスレッドの実行に失敗: {}", e))?
 }
 
 #[tauri::command]
 fn save_frames_for_edit(path: String, frames: Vec<InputFrame>, state: State<AppState>) -> Result<(), String> {
-    use std::fs::File;
-    use std::io::Write;
+    write_frames_csv(&state, &path, &frames, None)
+}
 
+// フレームをCSVとして保存し、読み込みキャッシュを破棄する
+//
+// annotations を省略した場合は、保存先の既存ファイルのラベル行・コメント行を残す
+// （行の挿入・削除で位置がずれても、ステップ番号が範囲外になる分は末尾に寄せる）
+fn write_frames_csv(
+    state: &AppState,
+    path: &str,
+    frames: &[InputFrame],
+    annotations: Option<csv_loader::SequenceAnnotations>,
+) -> Result<(), String> {
     println!("[save_frames_for_edit] 開始 - パス: {}, フレーム数: {}", path, frames.len());
 
     let normalized_path = path.replace('\\', "/");
 
-    let csv_path = if std::path::Path::new(&normalized_path).is_absolute() {
-        PathBuf::from(&normalized_path)
-    } else {
-        let current = std::env::current_dir()
-            .map_err(|e| format!("Failed to get current directory: {}", e))?;
-        let project_root = if current.ends_with("src-tauri") {
-            current.parent().unwrap().to_path_buf()
-        } else {
-            current
-        };
-        project_root.join(&normalized_path)
-    };
+    let csv_path = resolve_project_path(&normalized_path)?;
 
     println!("[save_frames_for_edit] 保存先: {:?}", csv_path);
 
-    let mut file = File::create(&csv_path)
-        .map_err(|e| format!("ファイル作成エラー: {}", e))?;
+    let annotations = match annotations {
+        Some(annotations) => annotations,
+        None if csv_path.exists() && !sequence_binary::is_binary_sequence(&csv_path) => {
+            csv_loader::read_annotations(&csv_path)
+                .map_err(|e| format!("既存ファイルのラベル・コメントの読み込みに失敗: {}", e))?
+        }
+        None => csv_loader::SequenceAnnotations::default(),
+    };
 
     // ボタン名の順序をマッピング設定から取得
     let button_order = state.button_order.lock().unwrap();
@@ -509,35 +1568,21 @@ fn save_frames_for_edit(path: String, frames: Vec<InputFrame>, state: State<AppS
     } else {
         Vec::new()
     };
+    drop(button_order);
 
-    // ヘッダー行を書き込み
-    let mut header = vec!["duration".to_string(), "direction".to_string()];
-    header.extend(button_names.clone());
-    writeln!(file, "{}", header.join(","))
-        .map_err(|e| format!("書き込みエラー: {}", e))?;
-
-    // フレーム数を先に取得（ムーブ前）
     let frame_count = frames.len();
 
-    // データ行を書き込み
-    for frame in frames {
-        let mut values = vec![
-            frame.duration.to_string(),
-            frame.direction.to_string(),
-        ];
-
-        // ヘッダーと同じ順序でボタン値を出力
-        for button_name in &button_names {
-            values.push(frame.buttons.get(button_name).unwrap_or(&0).to_string());
-        }
+    // 内容をメモリ上に組み立ててから一時ファイル経由で書き込む（途中でクラッシュしても元のファイルを壊さない）
+    let content = csv_loader::sequence_csv_bytes(frames, &button_names, &annotations)
+        .map_err(|e| format!("書き込みエラー: {}", e))?;
 
-        writeln!(file, "{}", values.join(","))
-            .map_err(|e| format!("書き込みエラー: {}", e))?;
-    }
+    let backup_count = AppConfig::load_or_default().editor.backup_count;
+    atomic_write::write_atomic(&csv_path, &content, backup_count)
+        .map_err(|e| format!("ファイル保存エラー: {}", e))?;
+    state.file_watcher.lock().unwrap().mark_saved(&csv_path);
 
     // 保存後にキャッシュをクリア（次回読み込み時に最新のファイルを読む）
-    let mut cache = state.frame_cache.lock().unwrap();
-    let was_cached = cache.remove(&normalized_path).is_some();
+    let was_cached = state.frame_cache.lock().unwrap().remove(&csv_path);
     println!("[save_frames_for_edit] キャッシュクリア完了 - キャッシュにあった: {}", was_cached);
     println!("[save_frames_for_edit] 保存完了 - {}行を書き込み", frame_count);
 
@@ -550,6 +1595,230 @@ fn get_current_playing_frame(state: State<AppState>) -> usize {
     player.get_current_step()
 }
 
+/// 設定に従ってグローバルホットキーを登録し直す
+///
+/// キーの解析・重複の確認を全て済ませてから登録中のホットキーを入れ替える。
+/// 解析できないキーがある場合は登録中のホットキーをそのまま残す。
+fn register_hotkeys(app: &tauri::AppHandle, settings: &HotkeySettings) -> Result<(), String> {
+    let entries = [
+        (settings.start_playback.as_deref(), HotkeyAction::StartPlayback),
+        (settings.stop_playback.as_deref(), HotkeyAction::StopPlayback),
+    ];
+
+    let mut shortcuts: Vec<(&str, Shortcut, HotkeyAction)> = Vec::new();
+    if settings.enabled {
+        for (key, action) in entries {
+            let Some(key) = key.map(str::trim).filter(|k| !k.is_empty()) else {
+                continue;
+            };
+            let shortcut: Shortcut = key.parse()
+                .map_err(|e| format!("ホットキーの解析に失敗 ({}): {}", key, e))?;
+            if shortcuts.iter().any(|(_, other, _)| other.id() == shortcut.id()) {
+                return Err(format!("同じホットキーが複数の操作に割り当てられています: {}", key));
+            }
+            shortcuts.push((key, shortcut, action));
+        }
+    }
+
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all()
+        .map_err(|e| format!("ホットキーの解除に失敗: {}", e))?;
+
+    let state: State<AppState> = app.state();
+    let mut bindings = state.hotkey_bindings.lock().unwrap();
+    bindings.clear();
+
+    for (key, shortcut, action) in shortcuts {
+        global_shortcut.register(shortcut)
+            .map_err(|e| format!("ホットキーの登録に失敗 ({}): {}", key, e))?;
+        bindings.insert(shortcut.id(), action);
+        println!("[Hotkey] {} -> {:?}", key, action);
+    }
+
+    Ok(())
+}
+
+/// グローバルホットキー押下時の処理
+fn handle_hotkey(app: &tauri::AppHandle, shortcut: &Shortcut) {
+    let state: State<AppState> = app.state();
+    let action = state.hotkey_bindings.lock().unwrap().get(&shortcut.id()).copied();
+
+    match action {
+        Some(HotkeyAction::StartPlayback) => {
+            if let Err(e) = start_playback(None, state) {
+                eprintln!("[Hotkey] 再生開始に失敗: {}", e);
+            }
+        }
+        Some(HotkeyAction::StopPlayback) => {
+            if let Err(e) = stop_playback(state) {
+                eprintln!("[Hotkey] 再生停止に失敗: {}", e);
+            }
+        }
+        None => {}
+    }
+}
+
+#[tauri::command]
+fn get_hotkey_settings() -> HotkeySettings {
+    AppConfig::load_or_default().hotkeys
+}
+
+#[tauri::command]
+fn set_hotkey_settings(app: tauri::AppHandle, settings: HotkeySettings) -> Result<(), String> {
+    if let Err(e) = register_hotkeys(&app, &settings) {
+        // 他のアプリが使用中などで登録できなかった場合は保存済みの設定に戻す
+        if let Err(restore_error) = register_hotkeys(&app, &AppConfig::load_or_default().hotkeys) {
+            eprintln!("[Hotkey] 元の設定に戻せませんでした: {}", restore_error);
+        }
+        return Err(e);
+    }
+
+    let mut config = AppConfig::load_or_default();
+    config.hotkeys = settings;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_generation_settings() -> GenerationSettings {
+    AppConfig::load_or_default().generation
+}
+
+/// 動画から生成するシーケンスの後処理設定を保存
+#[tauri::command]
+fn set_generation_settings(settings: GenerationSettings) -> Result<(), String> {
+    let mut config = AppConfig::load_or_default();
+    config.generation = settings;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+/// エディタの保存設定を取得
+#[tauri::command]
+fn get_editor_settings() -> EditorSettings {
+    AppConfig::load_or_default().editor
+}
+
+/// エディタの保存設定を保存
+#[tauri::command]
+fn set_editor_settings(settings: EditorSettings) -> Result<(), String> {
+    let mut config = AppConfig::load_or_default();
+    config.editor = settings;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+/// リモート操作サーバーの設定を取得
+#[tauri::command]
+fn get_remote_settings() -> RemoteSettings {
+    AppConfig::load_or_default().remote
+}
+
+/// リモート操作サーバーの設定を保存（次回起動時に反映）
+///
+/// 有効にしてトークンが未設定の場合は生成して保存し、保存した設定を返す。
+#[tauri::command]
+fn set_remote_settings(mut settings: RemoteSettings) -> Result<RemoteSettings, String> {
+    settings.ensure_token();
+    let mut config = AppConfig::load_or_default();
+    config.remote = settings.clone();
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(settings)
+}
+
+/// OBS の録画連携の設定を取得
+#[tauri::command]
+fn get_obs_settings() -> ObsSettings {
+    AppConfig::load_or_default().obs
+}
+
+/// OBS の録画連携の設定を保存（次の再生から反映）
+#[tauri::command]
+fn set_obs_settings(settings: ObsSettings) -> Result<(), String> {
+    let mut config = AppConfig::load_or_default();
+    config.obs = settings;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
+/// OBS に接続できるか確認（OBS のバージョンを返す）
+#[tauri::command]
+async fn test_obs_connection(settings: ObsSettings) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || obs::test_connection(&settings))
+        .await
+        .map_err(|e| format!("接続スレッドの実行に失敗: {}", e))?
+        .map_err(|e| format!("OBS への接続に失敗: {}", e))
+}
+
+/// テンキー表記（"236A 5B 2C" など）をシーケンスに変換
+#[tauri::command]
+fn parse_notation(text: String) -> Result<Vec<InputFrame>, String> {
+    let config = AppConfig::load_or_default();
+    notation::parse_notation_with(&text, &config.notation)
+        .map_err(|e| format!("テンキー表記の解析に失敗: {}", e))
+}
+
+/// よく使うコマンド入力（qcf / dp / 360 / 溜め技など）のステップ列を生成
+#[tauri::command]
+fn generate_motion(motion: String, button: Option<String>, fps: u32) -> Result<Vec<InputFrame>, String> {
+    let config = AppConfig::load_or_default();
+    generator::generate_motion(&motion, button.as_deref(), fps, &config.notation)
+        .map_err(|e| format!("コマンドの生成に失敗: {}", e))
+}
+
+/// 左スティックの軌道（回転・左右の往復）のステップ列を生成（radius 省略時は最大まで倒す）
+#[tauri::command]
+fn generate_stick_motion(motion: generator::StickMotion, radius: Option<f64>) -> Result<Vec<InputFrame>, String> {
+    generator::generate_stick_motion(&motion, radius.unwrap_or(1.0))
+        .map_err(|e| format!("スティック軌道の生成に失敗: {}", e))
+}
+
+/// シーケンスCSVをテンキー表記に変換（共有用のテキスト）
+#[tauri::command]
+fn sequence_to_notation(path: String) -> Result<String, String> {
+    let config = AppConfig::load_or_default();
+    let csv_path = resolve_project_path(&path.replace('\\', "/"))?;
+    notation::sequence_to_notation(&csv_path, &config.notation)
+        .map_err(|e| format!("テンキー表記への変換に失敗: {}", e))
+}
+
+/// 抽出したシーケンスCSVを正解のシーケンスCSVとフレーム単位で比較（抽出精度の確認用）
+#[tauri::command]
+fn compare_sequences_accuracy(extracted_csv_path: String, reference_csv_path: String) -> Result<accuracy::AccuracyReport, String> {
+    let extracted = load_csv(&resolve_project_path(&extracted_csv_path.replace('\\', "/"))?)
+        .map_err(|e| format!("抽出したCSVの読み込みに失敗: {}", e))?;
+    let reference = load_csv(&resolve_project_path(&reference_csv_path.replace('\\', "/"))?)
+        .map_err(|e| format!("正解のCSVの読み込みに失敗: {}", e))?;
+    Ok(accuracy::compare_sequences_accuracy(&extracted, &reference))
+}
+
+/// テンキー表記の読み込み設定を取得
+#[tauri::command]
+fn get_notation_settings() -> NotationSettings {
+    AppConfig::load_or_default().notation
+}
+
+/// テンキー表記の読み込み設定を保存
+#[tauri::command]
+fn set_notation_settings(settings: NotationSettings) -> Result<(), String> {
+    let mut config = AppConfig::load_or_default();
+    config.notation = settings;
+    config.save_default()
+        .map_err(|e| format!("設定の保存に失敗: {}", e))?;
+
+    Ok(())
+}
+
 // `open_editor_test` (test helper) removed — unused in production code
 
 #[tauri::command]
@@ -661,25 +1930,48 @@ fn open_editor_window(app: tauri::AppHandle, csv_path: String) -> Result<(), Str
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 設定ファイルの内容はアプリ設定ディレクトリが決まる setup で反映する
+    let player = Player::new();
+
+    // ファイル監視のコールバックからイベントを発行するため、AppHandle は先に用意しておく
+    let app_handle: Arc<Mutex<Option<tauri::AppHandle>>> = Arc::new(Mutex::new(None));
+    let watcher_app_handle = app_handle.clone();
+    let file_watcher = FileWatcher::new(move |kind, path| {
+        let app = watcher_app_handle.lock().unwrap().clone();
+        if let Some(app) = app {
+            reload_watched_file(&app, kind, path);
+        }
+    });
+
+    // 多重起動の検出（起動は続け、フロントエンドが check_instance_conflict で警告する）
+    let instance_guard = match instance::NamedMutex::acquire(instance::APP_INSTANCE_NAME) {
+        Ok(guard) => Some(guard),
+        Err(_) => {
+            println!("[Instance] 別の input_player が起動中です。仮想コントローラーの接続は片方のみ行えます");
+            None
+        }
+    };
+
     let app_state = AppState {
         controller: Arc::new(Mutex::new(Controller::new())),
-        player: Arc::new(Mutex::new(Player::new())),
+        player: Arc::new(Mutex::new(player)),
         fps: Arc::new(Mutex::new(60)),
-        frame_cache: Arc::new(Mutex::new(std::collections::HashMap::new())),
-        manual_input: Arc::new(Mutex::new(InputFrame {
-            duration: 1,
-            direction: 5,
-            buttons: HashMap::new(),
-            thumb_lx: 0,
-            thumb_ly: 0,
-            thumb_rx: 0,
-            thumb_ry: 0,
-            left_trigger: 0,
-            right_trigger: 0,
-        })),
-        app_handle: Arc::new(Mutex::new(None)),
+        frame_cache: Arc::new(Mutex::new(FrameCache::new(frame_cache::DEFAULT_MAX_MEGABYTES))),
+        manual_input: Arc::new(Mutex::new(InputFrame::neutral(1))),
+        app_handle,
         button_order: Arc::new(Mutex::new(Vec::new())),
         is_training: Arc::new(Mutex::new(false)),
+        gpu_scheduler: Arc::new(gpu_scheduler::GpuScheduler::new()),
+        jobs: Arc::new(jobs::JobRegistry::new()),
+        #[cfg(feature = "ml")]
+        engine_cache: Arc::new(ml::EngineCache::new()),
+        hotkey_bindings: Arc::new(Mutex::new(HashMap::new())),
+        edit_sessions: Arc::new(Mutex::new(HashMap::new())),
+        preview_sessions: Arc::new(Mutex::new(HashMap::new())),
+        file_watcher: Arc::new(Mutex::new(file_watcher)),
+        pending_reloads: Arc::new(Mutex::new(Vec::new())),
+        script: Arc::new(Mutex::new(None)),
+        instance_guard,
     };
 
     // FPS設定に基づいて更新するタスクを起動
@@ -687,133 +1979,407 @@ pub fn run() {
     let player_clone = app_state.player.clone();
     let fps_clone = app_state.fps.clone();
     let app_handle_clone = app_state.app_handle.clone();
+    let script_clone = app_state.script.clone();
 
     std::thread::spawn(move || {
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        runtime.block_on(async {
-            loop {
-                // 現在のFPS設定を取得
-                let current_fps = {
-                    let fps = fps_clone.lock().unwrap();
-                    *fps
-                };
+        // Windowsのタイマー分解能を1msに引き上げ（スレッド終了まで維持）
+        let _timer_resolution = timer::TimerResolutionGuard::new();
+        // 最小化中も送信が遅れないようタイマーの間引きを避け、再生中だけ優先度を上げる
+        timer::disable_power_throttling();
+        let mut priority = timer::PlaybackPriority::new();
+
+        // 次のティック時刻（フレーム周期の基準）
+        let mut next_tick = std::time::Instant::now();
+        // オーバーレイに最後に送った状態
+        let mut last_overlay_output: Option<controller::ControllerOutput> = None;
+
+        loop {
+            // 現在のFPS設定を取得（整数ミリ秒に切り捨てない周期）
+            let current_fps = {
+                let fps = fps_clone.lock().unwrap();
+                *fps
+            };
+            let period = timer::frame_period(current_fps);
+
+            let now = std::time::Instant::now();
+            next_tick += period;
+            if next_tick < now {
+                // 大きく遅れた場合は基準を現在時刻に合わせ直す
+                next_tick = now + period;
+            }
+
+            // 再生中は次ステップの送信時刻ちょうどに起床する
+            // （ただし停止・FPS変更に追従できるよう最大1フレーム周期まで）
+            let (step_deadline, playing) = {
+                let player = player_clone.lock().unwrap();
+                (player.next_step_deadline(), player.get_state() == SequenceState::Playing)
+            };
+            priority.set_raised(playing);
+            let wake_at = match step_deadline {
+                Some(deadline) if deadline < next_tick => {
+                    next_tick = deadline.max(now);
+                    next_tick
+                }
+                _ => next_tick,
+            };
+            // スピン待機は送信時刻が決まっているとき（再生中・予約再生の待機中）だけ行う
+            if step_deadline.is_some() {
+                timer::sleep_until(wake_at);
+            } else {
+                timer::sleep_until_coarse(wake_at);
+            }
+
+            // 予約再生の時刻に達していれば開始（コントローラー未接続でも発火させる）
+            let fired = {
+                let mut player = player_clone.lock().unwrap();
+                player.fire_if_due()
+            };
+            if let Some(armed) = fired {
+                if let Some(app) = app_handle_clone.lock().unwrap().as_ref() {
+                    emit_scheduled_playback_fired(app, armed.scheduled_unix_ms);
+                }
+            }
 
-                let interval_ms = 1000 / current_fps;
-                let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(interval_ms as u64));
+            // 入力オーバーレイが開いていれば送信中の状態を送る（変化したときのみ）
+            if let Some(app) = app_handle_clone.lock().unwrap().as_ref() {
+                if app.get_webview_window(OVERLAY_WINDOW_LABEL).is_some() {
+                    let output = controller_clone.lock().unwrap().current_output();
+                    if last_overlay_output.as_ref() != Some(&output) {
+                        let _ = app.emit_to(OVERLAY_WINDOW_LABEL, "controller-output", &output);
+                        last_overlay_output = Some(output);
+                    }
+                } else {
+                    last_overlay_output = None;
+                }
+            }
 
-                // FPSが変更されるまでの間ループ
-                let last_fps = current_fps;
-                loop {
-                    interval.tick().await;
+            // コントローラーが接続されていない場合はスキップ
+            let controller = controller_clone.lock().unwrap();
+            if !controller.is_connected() {
+                drop(controller);
+                continue;
+            }
+            drop(controller);
+
+            // シーケンスモード専用のループ
+            // マニュアルモードの入力は update_manual_input で即座に送信されるため、ここでは処理しない
+            let player = player_clone.lock().unwrap();
+            let state = player.get_state();
+            drop(player);
+
+            if state == SequenceState::Playing {
+                // シーケンス再生モード: プレイヤーの update を呼ぶ
+                let mut player = player_clone.lock().unwrap();
+                // ループで先頭に戻ったときの周回数（スクリプトのフックはロックを外してから呼ぶ）
+                let mut looped = None;
+                // 変化後の状態（イベントはリスナーがプレイヤーをロックできるようロックを外してから送る）
+                let mut changed_state = None;
+
+                // コントローラが接続されているかチェックして、存在すれば渡す
+                let mut controller_guard = controller_clone.lock().unwrap();
+                let controller_connected = controller_guard.is_connected();
+
+                let step_before = player.get_current_step();
+                let update_result = if controller_connected {
+                    player.update(Some(&mut *controller_guard))
+                } else {
+                    // コントローラ未接続でも再生進行は行いたいので None を渡す
+                    player.update(None)
+                };
 
-                    // FPSが変更されたかチェック
-                    let current_fps = {
-                        let fps = fps_clone.lock().unwrap();
-                        *fps
-                    };
+                // 予定より大きく遅れて送信したステップを通知（ウォッチドッグ）
+                if let Some(late_event) = player.take_late_step() {
+                    println!(
+                        "[Watchdog] ステップ {} の送信が {:.1}ms 遅れました（しきい値 {}ms）",
+                        late_event.step, late_event.late_ms, late_event.threshold_ms
+                    );
+                    if let Some(app) = app_handle_clone.lock().unwrap().as_ref() {
+                        let _ = app.emit("playback-step-late", late_event);
+                    }
+                }
 
-                    if current_fps != last_fps {
-                        // FPSが変更されたので外側ループに戻ってintervalを再生成
-                        break;
+                if let Ok((_sent, state_changed)) = update_result {
+                    // ステップが進んだらフロントエンドに通知（ポーリング不要にするため）
+                    if player.get_current_step() != step_before {
+                        if let Some(step_event) = player.step_event() {
+                            if let Some(app) = app_handle_clone.lock().unwrap().as_ref() {
+                                let _ = app.emit("playback-step-changed", step_event);
+                            }
+                        }
                     }
 
-                    // コントローラーが接続されていない場合はスキップ
-                    let controller = controller_clone.lock().unwrap();
-                    if !controller.is_connected() {
-                        drop(controller);
-                        continue;
+                    if state_changed {
+                        let new_state = player.get_state();
+                        changed_state = Some((new_state, player.breakpoint_event()));
+
+                        // 再生中のまま状態が変わるのはループで先頭に戻ったとき
+                        if new_state == SequenceState::Playing {
+                            looped = Some(player.loops_completed());
+                        }
                     }
-                    drop(controller);
-
-                    // シーケンスモード専用のループ
-                    // マニュアルモードの入力は update_manual_input で即座に送信されるため、ここでは処理しない
-                    let player = player_clone.lock().unwrap();
-                    let state = player.get_state();
-                    drop(player);
-
-                    if state == SequenceState::Playing {
-                        // シーケンス再生モード: プレイヤーの update を呼ぶ
-                        let mut player = player_clone.lock().unwrap();
-
-                        // コントローラが接続されているかチェックして、存在すれば渡す
-                        let mut controller_guard = controller_clone.lock().unwrap();
-                        let controller_connected = controller_guard.is_connected();
-
-                        let update_result = if controller_connected {
-                            player.update(Some(&mut *controller_guard))
-                        } else {
-                            // コントローラ未接続でも再生進行は行いたいので None を渡す
-                            player.update(None)
+                }
+                drop(controller_guard);
+                drop(player);
+
+                // フロントエンドにイベント送信（ログは最小限に）
+                if let Some((new_state, breakpoint_event)) = changed_state {
+                    if let Some(app) = app_handle_clone.lock().unwrap().as_ref() {
+                        let state_str = match new_state {
+                            SequenceState::Playing => "playing",
+                            SequenceState::Stopped => "stopped",
+                            SequenceState::Paused => "paused",
+                            SequenceState::NoSequence => "no_sequence",
                         };
+                        let _ = app.emit("playback-state-changed", state_str);
+                        if let Some(breakpoint_event) = breakpoint_event {
+                            let _ = app.emit("playback-breakpoint-hit", breakpoint_event);
+                        }
+                        println!("[State] {:?}", new_state); // 状態変化のみ簡潔にログ
+
+                        // 再生が終わったら、再生中に変更されたファイルを読み込み直す
+                        if new_state == SequenceState::Stopped {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn_blocking(move || apply_pending_reloads(&app));
+                        }
+                    }
+                }
 
-                        if let Ok((_sent, state_changed)) = update_result {
-                            if state_changed {
-                                let new_state = player.get_state();
-
-                                // フロントエンドにイベント送信（ログは最小限に）
-                                if let Some(app) = app_handle_clone.lock().unwrap().as_ref() {
-                                    let state_str = match new_state {
-                                        SequenceState::Playing => "playing",
-                                        SequenceState::Stopped => "stopped",
-                                        SequenceState::NoSequence => "no_sequence",
-                                    };
-                                    let _ = app.emit("playback-state-changed", state_str);
-                                    println!("[State] {:?}", new_state); // 状態変化のみ簡潔にログ
-                                }
+                // スクリプトのフックはプレイヤーとコントローラーのロックの外で呼ぶ（実行時間はスクリプト側で制限する）
+                if let Some(count) = looped {
+                    if let Some(script) = script_clone.lock().unwrap().as_mut() {
+                        match script.on_loop(count) {
+                            Ok(frames) if !frames.is_empty() => {
+                                println!("[スクリプト] on_loop: {}ステップを追加", frames.len());
+                                player_clone.lock().unwrap().append_frames(frames);
                             }
+                            Ok(_) => {}
+                            Err(e) => eprintln!("[スクリプト] {}", e),
                         }
-                        drop(controller_guard);
                     }
-                    // マニュアルモード時はこのループでは何もしない（update_manual_inputで即座に送信）
                 }
             }
-        });
+            // マニュアルモード時はこのループでは何もしない（update_manual_inputで即座に送信）
+        }
     });
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app, shortcut, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        handle_hotkey(app, shortcut);
+                    }
+                })
+                .build(),
+        )
+        .on_window_event(|window, event| {
+            // ウィンドウが閉じられたら、そのウィンドウが開いていたプレビューのパイプラインを解放する
+            if let tauri::WindowEvent::Destroyed = event {
+                analysis_commands::close_window_previews(&window.state::<AppState>(), window.label());
+            }
+        })
         .setup(move |app| {
             // AppHandleを保存
             let handle = app.handle().clone();
             let state: tauri::State<AppState> = app.state();
             *state.app_handle.lock().unwrap() = Some(handle);
+
+            // ジョブの開始・終了をフロントエンドに通知
+            let job_handle = app.handle().clone();
+            state.jobs.set_listener(move |event, info| {
+                job_handle.emit(event, info).ok();
+            });
+
+            // 設定ファイルはプラットフォームのアプリ設定ディレクトリに置く（以前の場所から移行）
+            match app.path().app_config_dir() {
+                Ok(dir) => {
+                    if let Err(e) = AppConfig::init_config_dir(dir) {
+                        eprintln!("警告: 設定ディレクトリを使用できません: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("警告: 設定ディレクトリを取得できません: {}", e),
+            }
+
+            // 設定ファイルの再生設定とキャッシュ上限を反映
+            let config = AppConfig::load_or_default();
+            {
+                let mut player = state.player.lock().unwrap();
+                player.set_latency_offset_frames(config.playback.latency_offset_frames);
+                player.set_sync_marker(config.playback.sync_marker.clone());
+                player.set_release_gap_frames(config.playback.release_gap_frames);
+                player.set_late_step_warning_ms(config.playback.late_step_warning_ms);
+            }
+            state.frame_cache.lock().unwrap().set_max_megabytes(config.cache.max_megabytes);
+            if let Err(e) = video::set_backend(config.video_backend) {
+                eprintln!("警告: {}（{:?}を使用します）", e, video::backend());
+            }
+
+            // 前回終了時の状態を復元（シーケンスの読み込みは起動を遅らせないよう別スレッドで行う）
+            let session = config.session.clone();
+            restore_session_settings(&state, &session);
+            let restore_handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                restore_session_sequence(&restore_handle.state::<AppState>(), &session);
+            });
+
+            // 設定ファイルのグローバルホットキーを登録
+            let hotkeys = config.hotkeys;
+            if let Err(e) = register_hotkeys(app.handle(), &hotkeys) {
+                eprintln!("警告: ホットキーの登録に失敗しました: {}", e);
+            }
+
+            // 再生の開始・停止に合わせて OBS の録画を開始・停止する
+            obs::attach(app.handle());
+
+            // リモート操作サーバーを開始（設定ファイルを直接編集してトークンが無い場合はここで生成する）
+            if config.remote.enabled {
+                let mut remote_settings = config.remote.clone();
+                if remote_settings.ensure_token() {
+                    let mut saved = AppConfig::load_or_default();
+                    saved.remote = remote_settings.clone();
+                    if let Err(e) = saved.save_default() {
+                        eprintln!("警告: リモート操作のトークンを保存できません: {}", e);
+                    }
+                }
+                #[cfg(feature = "remote")]
+                if let Err(e) = remote::start(app.handle().clone(), &remote_settings) {
+                    eprintln!("警告: リモート操作サーバーを開始できません: {}", e);
+                }
+                #[cfg(not(feature = "remote"))]
+                eprintln!("警告: リモート操作は remote feature を有効にしてビルドした場合のみ使用できます");
+            }
             Ok(())
         })
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             connect_controller,
             disconnect_controller,
+            list_output_backends,
             is_controller_connected,
+            get_app_snapshot,
+            restore_last_session,
             get_app_dir,
+            get_config_path,
             load_input_file,
+            load_script_sequence,
             load_input_sequence,
             start_playback,
+            start_playback_reversed,
+            schedule_playback,
+            trigger_armed_playback,
+            cancel_scheduled_playback,
+            get_scheduled_playback,
             stop_playback,
             pause_playback,
             resume_playback,
             reload_current_sequence,
+            get_cache_stats,
+            clear_cache,
+            set_cache_max_megabytes,
             set_loop_playback,
+            set_release_gap_frames,
+            get_release_gap_frames,
+            set_duration_scale,
+            get_duration_scale,
+            set_latency_offset_frames,
+            get_latency_offset_frames,
+            set_late_step_warning_ms,
+            get_late_step_warning_ms,
+            set_sync_marker_settings,
+            get_sync_marker_settings,
+            set_manual_overlay,
+            get_manual_overlay,
+            set_step_breakpoints,
+            get_step_breakpoints,
+            get_sequence_labels,
+            open_edit_session,
+            insert_frames,
+            delete_frames,
+            duplicate_frames,
+            update_frame,
+            undo_edit,
+            redo_edit,
+            save_edit_session,
+            close_edit_session,
+            jump_to_label,
+            seek_playback,
+            get_sequence_timeline,
+            get_playback_timing_report,
+            dump_controller_log,
+            set_controller_log_capacity,
+            clear_controller_log,
+            get_controller_output,
+            check_instance_conflict,
+            cancel_job,
+            list_jobs,
+            get_job_status,
+            run_controller_selftest,
+            open_overlay_window,
+            get_playback_history,
+            clear_playback_history,
+            export_playback_history_csv,
+            export_playback_history_json,
+            set_hold_last_input,
+            get_hold_last_input,
             set_invert_horizontal,
             is_playing,
             get_playback_progress,
             load_button_mapping,
             save_button_mapping,
+            validate_button_mapping,
+            generate_default_mapping,
             update_manual_input,
             set_fps,
             get_fps,
             get_csv_button_names,
+            convert_sequence_fps,
+            transform_sequence,
+            convert_sequence_to_binary,
+            convert_binary_to_csv,
             load_frames_for_edit,
             save_frames_for_edit,
             get_current_playing_frame,
             open_editor_window,
+            get_hotkey_settings,
+            set_hotkey_settings,
+            get_generation_settings,
+            set_generation_settings,
+            get_editor_settings,
+            set_editor_settings,
+            get_remote_settings,
+            set_remote_settings,
+            get_obs_settings,
+            set_obs_settings,
+            test_obs_connection,
+            parse_notation,
+            generate_motion,
+            generate_stick_motion,
+            sequence_to_notation,
+            compare_sequences_accuracy,
+            get_notation_settings,
+            set_notation_settings,
             // 動画解析関連のコマンド
             analysis_commands::check_gstreamer_available,
             analysis_commands::get_video_info,
             analysis_commands::save_analysis_region,
             analysis_commands::load_analysis_region,
+            analysis_commands::export_region_preset,
+            analysis_commands::import_region_preset,
+            analysis_commands::list_registered_models,
+            analysis_commands::register_model,
+            analysis_commands::unregister_model,
+            analysis_commands::set_video_backend,
+            analysis_commands::get_video_backend,
+            analysis_commands::list_capture_devices,
             analysis_commands::extract_preview_frame,
+            analysis_commands::open_preview,
+            analysis_commands::preview_frame,
+            analysis_commands::close_preview,
+            analysis_commands::generate_thumbnails,
+            analysis_commands::export_region_overlay,
             analysis_commands::extract_tiles_from_video,
             analysis_commands::collect_training_data,
             analysis_commands::create_default_classification_folders,
@@ -821,13 +2387,21 @@ pub fn run() {
             analysis_commands::get_model_metadata,
             // 機械学習関連のコマンド
             ml_commands::extract_input_history,
+            ml_commands::extract_input_history_regions,
+            ml_commands::render_overlay_video,
+            ml_commands::compare_recording_with_sequence,
             ml_commands::train_classification_model,
+            ml_commands::list_training_runs,
             ml_commands::classify_video_tiles,
+            ml_commands::evaluate_model,
+            ml_commands::get_model_training_history,
             ml_commands::extract_and_classify_tiles,
             ml_commands::get_button_labels_from_data_dir,
             ml_commands::save_button_order_metadata,
             ml_commands::load_button_order_metadata,
             ml_commands::mp4_to_sequence,
+            ml_commands::start_live_analysis,
+            ml_commands::start_screen_analysis,
             ml_commands::validate_mapping_and_training_data,
         ])
         .run(tauri::generate_context!())
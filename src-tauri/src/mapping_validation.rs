@@ -0,0 +1,150 @@
+//! ボタンマッピングの検証
+//!
+//! マッピング設定の誤り（ユーザーボタン名の重複、存在しないコントローラーボタン、
+//! シーケンスで使うのに割り当てが空のボタンなど）を調べ、マッピングエディタが
+//! 該当する行に表示できるよう行番号付きの診断結果として返す。
+
+use crate::controller::CONTROLLER_BUTTONS;
+use crate::types::ButtonMapping;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticSeverity {
+    Error,   // 再生が正しく行えない
+    Warning, // 動作はするが意図と異なる可能性がある
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    EmptyUserButton,         // ユーザーボタン名が空
+    DuplicateUserButton,     // 同じユーザーボタン名が複数ある
+    UnknownControllerButton, // 存在しないコントローラーボタン名
+    EmptyMapping,            // シーケンスで使うのにコントローラーボタンが割り当てられていない
+    UnusedControllerButton,  // 2つ目以降のコントローラーボタン（再生では先頭のみ使用）
+    SharedControllerButton,  // 複数のユーザーボタンが同じコントローラーボタンに割り当てられている
+}
+
+/// 診断結果（1件）
+#[derive(Debug, Clone, Serialize)]
+pub struct MappingDiagnostic {
+    pub severity: DiagnosticSeverity,
+    pub kind: DiagnosticKind,
+    pub index: usize,  // mapping 内の行番号（0始まり）
+    pub user_button: String,
+    pub message: String,
+}
+
+/// ボタンマッピングを検証（問題が無ければ空）
+pub fn validate_mapping(mapping: &ButtonMapping) -> Vec<MappingDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut push = |severity, kind, index: usize, user_button: &str, message: String| {
+        diagnostics.push(MappingDiagnostic {
+            severity,
+            kind,
+            index,
+            user_button: user_button.to_string(),
+            message,
+        });
+    };
+
+    let mut first_index: HashMap<&str, usize> = HashMap::new();
+    // コントローラーボタン → 最初に割り当てたシーケンス用ユーザーボタン
+    let mut assigned: HashMap<&str, &str> = HashMap::new();
+
+    for (index, button) in mapping.mapping.iter().enumerate() {
+        let name = button.user_button.as_str();
+        if name.trim().is_empty() {
+            push(DiagnosticSeverity::Error, DiagnosticKind::EmptyUserButton, index, name,
+                "ユーザーボタン名が空です".to_string());
+        } else if let Some(&first) = first_index.get(name) {
+            push(DiagnosticSeverity::Error, DiagnosticKind::DuplicateUserButton, index, name,
+                format!("ユーザーボタン名 '{}' が {} 行目と重複しています", name, first + 1));
+        } else {
+            first_index.insert(name, index);
+        }
+
+        for controller_button in &button.controller_button {
+            if !CONTROLLER_BUTTONS.contains(&controller_button.as_str()) {
+                push(DiagnosticSeverity::Error, DiagnosticKind::UnknownControllerButton, index, name,
+                    format!("存在しないコントローラーボタンです: '{}'", controller_button));
+            }
+        }
+
+        let Some(first_button) = button.controller_button.first() else {
+            if button.use_in_sequence {
+                push(DiagnosticSeverity::Error, DiagnosticKind::EmptyMapping, index, name,
+                    format!("'{}' はシーケンスで使用しますがコントローラーボタンが割り当てられていません", name));
+            }
+            continue;
+        };
+        if button.controller_button.len() > 1 {
+            push(DiagnosticSeverity::Warning, DiagnosticKind::UnusedControllerButton, index, name,
+                format!("再生では先頭の '{}' のみ使用され、残りは無視されます", first_button));
+        }
+        if button.use_in_sequence {
+            match assigned.get(first_button.as_str()) {
+                Some(&other) => push(DiagnosticSeverity::Warning, DiagnosticKind::SharedControllerButton, index, name,
+                    format!("'{}' は '{}' と同じコントローラーボタン '{}' に割り当てられています", name, other, first_button)),
+                None => {
+                    assigned.insert(first_button.as_str(), name);
+                }
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ControllerType, UserButton};
+
+    fn button(name: &str, controller_buttons: &[&str], use_in_sequence: bool) -> UserButton {
+        UserButton {
+            user_button: name.to_string(),
+            controller_button: controller_buttons.iter().map(|b| b.to_string()).collect(),
+            use_in_sequence,
+        }
+    }
+
+    #[test]
+    fn test_validate_mapping() {
+        let mapping = ButtonMapping {
+            controller_type: ControllerType::Xbox,
+            mapping: vec![
+                button("A", &["button1"], true),
+                button("B", &["button2", "button3"], true),
+                button("A", &["button13"], true),
+                button("C", &[], true),
+                button("D", &["button1"], true),
+                button("Start", &[], false),
+            ],
+        };
+        let found: Vec<(usize, DiagnosticKind, DiagnosticSeverity)> = validate_mapping(&mapping)
+            .iter()
+            .map(|d| (d.index, d.kind, d.severity))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, DiagnosticKind::UnusedControllerButton, DiagnosticSeverity::Warning),
+                (2, DiagnosticKind::DuplicateUserButton, DiagnosticSeverity::Error),
+                (2, DiagnosticKind::UnknownControllerButton, DiagnosticSeverity::Error),
+                (3, DiagnosticKind::EmptyMapping, DiagnosticSeverity::Error),
+                (4, DiagnosticKind::SharedControllerButton, DiagnosticSeverity::Warning),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_mappings_are_valid() {
+        for controller_type in [ControllerType::Xbox, ControllerType::DualShock4] {
+            let mapping = ButtonMapping::default_for(controller_type);
+            assert_eq!(mapping.mapping.len(), 10);
+            assert!(validate_mapping(&mapping).is_empty());
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! 読み込み済み推論エンジンのキャッシュ
+//!
+//! モデルの読み込み（tar.gz の展開・一時 .mpk の書き出し・バックエンドの初期化）は数秒かかるため、
+//! 同じモデル・同じバックエンドで続けて抽出や分類を行う場合は前回のエンジンを使い回す。
+
+#[cfg(feature = "ml")]
+use anyhow::Result;
+#[cfg(feature = "ml")]
+use std::ops::Deref;
+#[cfg(feature = "ml")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "ml")]
+use std::sync::Mutex;
+#[cfg(feature = "ml")]
+use std::time::SystemTime;
+
+#[cfg(feature = "ml")]
+use crate::ml::InferenceEngine;
+
+/// キャッシュしておくエンジンの数（GPU のエンジンは VRAM を使うため少なめにする）
+#[cfg(feature = "ml")]
+const MAX_CACHED_ENGINES: usize = 2;
+
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EngineKey {
+    model_path: PathBuf,
+    use_gpu: bool,
+    /// モデルファイルの更新日時（同じパスに学習し直した場合は別のモデルとして扱う）
+    modified: Option<SystemTime>,
+}
+
+#[cfg(feature = "ml")]
+impl EngineKey {
+    fn new(model_path: &Path, use_gpu: bool) -> Self {
+        Self {
+            model_path: model_path.to_path_buf(),
+            use_gpu,
+            modified: std::fs::metadata(model_path).and_then(|m| m.modified()).ok(),
+        }
+    }
+}
+
+/// モデルパスとバックエンドごとの推論エンジンのキャッシュ
+///
+/// エンジンは使用中のジョブが借りている間キャッシュから取り出され、使い終わると戻される。
+/// 同じモデルを使うジョブが同時に実行された場合、後のジョブは新たに読み込む。
+#[cfg(feature = "ml")]
+#[derive(Default)]
+pub struct EngineCache {
+    // 古いものから順に並べる（上限を超えたら先頭から捨てる）
+    entries: Mutex<Vec<(EngineKey, InferenceEngine)>>,
+}
+
+#[cfg(feature = "ml")]
+impl EngineCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// キャッシュ済みのエンジンを借りる（無ければ読み込む）
+    pub fn checkout<P: AsRef<Path>>(&self, model_path: P, use_gpu: bool) -> Result<CachedEngine<'_>> {
+        let key = EngineKey::new(model_path.as_ref(), use_gpu);
+
+        let cached = {
+            let mut entries = self.entries.lock().unwrap();
+            entries
+                .iter()
+                .position(|(k, _)| k == &key)
+                .map(|index| entries.remove(index).1)
+        };
+        let engine = match cached {
+            Some(engine) => {
+                println!("[EngineCache] キャッシュ済みのモデルを使用: {:?} (GPU: {})", key.model_path, use_gpu);
+                engine
+            }
+            None => InferenceEngine::load_with_backend(&key.model_path, use_gpu)?,
+        };
+
+        Ok(CachedEngine { cache: self, key: Some(key), engine: Some(engine) })
+    }
+
+    /// キャッシュを空にする
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn put_back(&self, key: EngineKey, engine: InferenceEngine) {
+        let mut entries = self.entries.lock().unwrap();
+        // 同じパスの古いモデルや、同時実行で読み込んだ重複は捨てる
+        entries.retain(|(k, _)| !(k.model_path == key.model_path && k.use_gpu == key.use_gpu));
+        entries.push((key, engine));
+        if entries.len() > MAX_CACHED_ENGINES {
+            entries.remove(0);
+        }
+    }
+}
+
+/// キャッシュから借りた推論エンジン（drop でキャッシュに戻る）
+#[cfg(feature = "ml")]
+pub struct CachedEngine<'a> {
+    cache: &'a EngineCache,
+    key: Option<EngineKey>,
+    engine: Option<InferenceEngine>,
+}
+
+#[cfg(feature = "ml")]
+impl Deref for CachedEngine<'_> {
+    type Target = InferenceEngine;
+
+    fn deref(&self) -> &InferenceEngine {
+        self.engine.as_ref().expect("engine")
+    }
+}
+
+#[cfg(feature = "ml")]
+impl Drop for CachedEngine<'_> {
+    fn drop(&mut self) {
+        if let (Some(key), Some(engine)) = (self.key.take(), self.engine.take()) {
+            self.cache.put_back(key, engine);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! 学習済みモデルの評価
+//!
+//! クラス名のフォルダに分けた画像（学習データと同じ構成）をすべて分類し、正解率・クラスごとの指標・
+//! 誤分類した画像の一覧を求める。学習し直さずに複数のモデルを同じデータで比べるためのもの。
+
+#[cfg(feature = "ml")]
+use anyhow::{Context, Result};
+#[cfg(feature = "ml")]
+use serde::Serialize;
+#[cfg(feature = "ml")]
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "ml")]
+use crate::ml::InferenceEngine;
+
+/// 1回の推論で分類する画像数
+#[cfg(feature = "ml")]
+const EVALUATION_BATCH_SIZE: usize = 64;
+
+/// クラスごとの評価指標
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassMetrics {
+    pub class_name: String,
+    pub samples: usize,         // このクラスのフォルダにある画像数
+    pub correct: usize,         // 正しく分類された画像数
+    pub predicted: usize,       // このクラスと予測された画像数（他クラスの画像を含む）
+    pub precision: Option<f32>, // 一度も予測されなければ None
+    pub recall: Option<f32>,    // 画像が無ければ None
+}
+
+/// 誤分類した画像
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Serialize)]
+pub struct Misclassified {
+    pub path: String,
+    pub expected: String,
+    pub predicted: String,
+    pub confidence: f32,
+}
+
+/// 評価結果
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationReport {
+    pub total: usize,
+    pub correct: usize,
+    pub accuracy: f32,
+    pub classes: Vec<ClassMetrics>, // モデルのクラス順
+    pub misclassified: Vec<Misclassified>,
+    /// モデルに無いクラスのフォルダ（評価しない）
+    pub unknown_classes: Vec<String>,
+    /// 読み込めなかった画像数
+    pub unreadable: usize,
+}
+
+/// 1枚の分類結果（画像のパス, 正解のクラス, 予測したクラス, 確信度）
+#[cfg(feature = "ml")]
+type Prediction = (PathBuf, String, String, f32);
+
+#[cfg(feature = "ml")]
+impl EvaluationReport {
+    fn from_predictions(class_labels: &[String], predictions: &[Prediction]) -> Self {
+        let ratio = |numerator: usize, denominator: usize| (denominator > 0).then(|| numerator as f32 / denominator as f32);
+
+        let classes = class_labels
+            .iter()
+            .map(|class_name| {
+                let samples = predictions.iter().filter(|p| p.1 == *class_name).count();
+                let predicted = predictions.iter().filter(|p| p.2 == *class_name).count();
+                let correct = predictions.iter().filter(|p| p.1 == *class_name && p.2 == *class_name).count();
+                ClassMetrics {
+                    class_name: class_name.clone(),
+                    samples,
+                    correct,
+                    predicted,
+                    precision: ratio(correct, predicted),
+                    recall: ratio(correct, samples),
+                }
+            })
+            .collect();
+
+        let misclassified: Vec<Misclassified> = predictions
+            .iter()
+            .filter(|p| p.1 != p.2)
+            .map(|(path, expected, predicted, confidence)| Misclassified {
+                path: path.to_string_lossy().to_string(),
+                expected: expected.clone(),
+                predicted: predicted.clone(),
+                confidence: *confidence,
+            })
+            .collect();
+
+        let total = predictions.len();
+        let correct = total - misclassified.len();
+        Self {
+            total,
+            correct,
+            accuracy: ratio(correct, total).unwrap_or(0.0),
+            classes,
+            misclassified,
+            unknown_classes: Vec::new(),
+            unreadable: 0,
+        }
+    }
+}
+
+/// labeled_dir/クラス名/ の画像をすべて分類してモデルを評価する
+///
+/// on_progress には（処理済み枚数, 総枚数）を渡す。
+#[cfg(feature = "ml")]
+pub fn evaluate_model<F>(engine: &InferenceEngine, labeled_dir: &Path, mut on_progress: F) -> Result<EvaluationReport>
+where
+    F: FnMut(usize, usize) -> Result<()>,
+{
+    let config = engine.config();
+    let class_labels = if !config.all_class_labels.is_empty() {
+        &config.all_class_labels
+    } else {
+        &config.button_labels
+    };
+
+    // (画像のパス, 正解のクラス)
+    let mut samples: Vec<(PathBuf, String)> = Vec::new();
+    let mut unknown_classes = Vec::new();
+    let mut class_dirs: Vec<PathBuf> = std::fs::read_dir(labeled_dir)
+        .with_context(|| format!("評価データのフォルダを開けません: {:?}", labeled_dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    class_dirs.sort();
+    for class_dir in class_dirs {
+        let class_name = class_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if !class_labels.contains(&class_name) {
+            println!("[Evaluate] 警告: モデルに無いクラスのフォルダは評価しません: {}", class_name);
+            unknown_classes.push(class_name);
+            continue;
+        }
+        let mut images: Vec<PathBuf> = std::fs::read_dir(&class_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+            })
+            .collect();
+        images.sort();
+        samples.extend(images.into_iter().map(|path| (path, class_name.clone())));
+    }
+    if samples.is_empty() {
+        anyhow::bail!("評価する画像がありません（クラス名のフォルダに画像を置いてください）: {:?}", labeled_dir);
+    }
+    println!("[Evaluate] {}枚の画像で評価します", samples.len());
+
+    let mut predictions: Vec<Prediction> = Vec::with_capacity(samples.len());
+    let mut unreadable = 0usize;
+    for (chunk_index, chunk) in samples.chunks(EVALUATION_BATCH_SIZE).enumerate() {
+        let (loaded, images): (Vec<&(PathBuf, String)>, Vec<image::RgbImage>) = chunk
+            .iter()
+            .filter_map(|sample| match image::open(&sample.0) {
+                Ok(img) => Some((sample, img.to_rgb8())),
+                Err(e) => {
+                    println!("[Evaluate] 警告: 画像を読み込めません（スキップ）: {:?} ({})", sample.0, e);
+                    unreadable += 1;
+                    None
+                }
+            })
+            .unzip();
+        let results = engine.classify_batch_with_confidence(&images)
+            .context("バッチ分類エラー")?;
+        for ((path, expected), (predicted, confidence)) in loaded.into_iter().zip(results) {
+            predictions.push((path.clone(), expected.clone(), predicted, confidence));
+        }
+
+        on_progress(((chunk_index + 1) * EVALUATION_BATCH_SIZE).min(samples.len()), samples.len())?;
+    }
+
+    let mut report = EvaluationReport::from_predictions(class_labels, &predictions);
+    report.unknown_classes = unknown_classes;
+    report.unreadable = unreadable;
+    println!("[Evaluate] 正解率: {:.2}% ({}/{})", report.accuracy * 100.0, report.correct, report.total);
+    Ok(report)
+}
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_metrics() {
+        let labels: Vec<String> = ["dir_6", "A", "others"].iter().map(|s| s.to_string()).collect();
+        let prediction = |name: &str, expected: &str, predicted: &str| {
+            (PathBuf::from(name), expected.to_string(), predicted.to_string(), 0.9)
+        };
+        let predictions = vec![
+            prediction("a1.png", "A", "A"),
+            prediction("a2.png", "A", "others"),
+            prediction("d1.png", "dir_6", "dir_6"),
+            prediction("o1.png", "others", "A"),
+        ];
+        let report = EvaluationReport::from_predictions(&labels, &predictions);
+        assert_eq!((report.total, report.correct), (4, 2));
+        assert_eq!(report.accuracy, 0.5);
+
+        let a = &report.classes[1];
+        assert_eq!((a.samples, a.correct, a.predicted), (2, 1, 2));
+        assert_eq!((a.precision, a.recall), (Some(0.5), Some(0.5)));
+        assert_eq!(report.classes[0].precision, Some(1.0));
+
+        assert_eq!(report.misclassified.len(), 2);
+        assert_eq!(report.misclassified[0].path, "a2.png");
+        assert_eq!(report.misclassified[0].predicted, "others");
+    }
+}
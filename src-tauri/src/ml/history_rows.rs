@@ -0,0 +1,342 @@
+//! 入力履歴の複数行解析
+//!
+//! 入力表示には最新の入力（最下行）の上に過去の入力が最大16行並んでいる。
+//! 毎フレーム最下行を分類する代わりに、間引いたキーフレームで表示されている全行を分類し、
+//! 前のキーフレームの行と重なる位置を求めて新しく増えた行だけをステップとして追加する。
+//! 各行の持続フレーム数は継続フレーム数の列から読み取るため、数字のテンプレートの設定が必要。
+//! キーフレームの間隔が (行数 - 1) フレーム以下であれば、1フレームの入力も取りこぼさない。
+
+#[cfg(feature = "ml")]
+use anyhow::Result;
+#[cfg(feature = "ml")]
+use std::path::Path;
+
+#[cfg(feature = "ml")]
+use crate::analyzer::{InputIndicatorRegion, InputState};
+#[cfg(feature = "ml")]
+use crate::ml::input_history::{classify_frame_with_count, FrameClassification, FrameCountColumn};
+#[cfg(feature = "ml")]
+use crate::ml::InferenceEngine;
+#[cfg(feature = "ml")]
+use crate::model::{GenerationSettings, VideoTransform};
+#[cfg(feature = "ml")]
+use crate::video::{FrameExtractor, FrameExtractorConfig, TimeRange};
+
+/// 入力表示の1行
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone)]
+pub struct HistoryRow {
+    pub state: InputState,
+    /// 継続フレーム数の表示（読み取れなかった場合は None）
+    pub displayed_frames: Option<u32>,
+}
+
+#[cfg(feature = "ml")]
+impl From<FrameClassification> for HistoryRow {
+    fn from(classification: FrameClassification) -> Self {
+        Self { state: classification.state, displayed_frames: classification.displayed_frames }
+    }
+}
+
+/// 最下行の領域 region から上へ rows 行分広げた領域（画面の上端で打ち切る）
+#[cfg(feature = "ml")]
+pub fn history_region(region: &InputIndicatorRegion, rows: u32) -> InputIndicatorRegion {
+    let row_height = region.height / region.rows.max(1);
+    let rows = rows.clamp(1, region.y / row_height.max(1) + 1);
+    InputIndicatorRegion {
+        y: region.y - (rows - 1) * row_height,
+        height: rows * row_height,
+        rows,
+        ..region.clone()
+    }
+}
+
+/// history_region を frame_count.crop_region で切り出した画像の全行を上から順に分類する
+#[cfg(feature = "ml")]
+pub fn classify_history_rows(
+    engine: &InferenceEngine,
+    frame_img: &image::RgbImage,
+    row_region: &InputIndicatorRegion,
+    frame_count: &FrameCountColumn,
+) -> Result<Vec<FrameClassification>> {
+    let row_height = row_region.height;
+    let rows = frame_img.height() / row_height.max(1);
+    (0..rows)
+        .map(|row| {
+            let row_img = crate::video::crop_rgb_rows(
+                frame_img.as_raw(),
+                frame_img.width() as usize * 3,
+                0,
+                row * row_height,
+                frame_img.width(),
+                row_height,
+            )
+            .ok_or_else(|| anyhow::anyhow!("{}行目を切り出せません", row + 1))?;
+            classify_frame_with_count(engine, &row_img, row_region, Some(frame_count))
+        })
+        .collect()
+}
+
+/// キーフレームごとの表示行を突き合わせてステップ列を組み立てる
+#[cfg(feature = "ml")]
+#[derive(Debug, Default)]
+pub struct HistoryReconciler {
+    steps: Vec<(InputState, u32)>,
+    /// 直前のキーフレームの表示行（上から順、最後の行は継続中の入力）
+    previous: Vec<HistoryRow>,
+    previous_frame: u32,
+    /// 前のキーフレームと重なる行が見つからなかった回数（間に入力を取りこぼした可能性がある）
+    pub gaps: u32,
+    /// 継続フレーム数を読み取れず 1 フレームとして扱った行数
+    pub unreadable: u32,
+}
+
+#[cfg(feature = "ml")]
+impl HistoryReconciler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// frame_num のキーフレームの表示行（上から順）を追加
+    ///
+    /// 最初のキーフレームでは最下行（継続中の入力）だけをステップにし、それより上の行は解析範囲の前の入力として
+    /// 次のキーフレームとの突き合わせにだけ使う。
+    pub fn push(&mut self, frame_num: u32, rows: Vec<HistoryRow>) {
+        let elapsed = frame_num.saturating_sub(self.previous_frame);
+        self.previous_frame = frame_num;
+
+        // 上端の空行（まだ入力が表示されていない行）は数字が無いので読み飛ばす
+        let first_readable = rows.iter().position(|row| row.displayed_frames.is_some());
+        let rows: Vec<HistoryRow> = match first_readable {
+            Some(start) => rows.into_iter().skip(start).collect(),
+            None => rows.into_iter().last().into_iter().collect(),
+        };
+        if rows.is_empty() {
+            return;
+        }
+        if self.previous.is_empty() {
+            self.previous = rows;
+            return;
+        }
+
+        let new_rows = match new_row_count(&self.previous, &rows, elapsed) {
+            // 継続中だった行は、表示が残っている最新の継続フレーム数で確定する
+            Some(0) => 0,
+            Some(new_rows) => {
+                self.commit(rows[rows.len() - new_rows - 1].clone());
+                new_rows
+            }
+            None => {
+                self.gaps += 1;
+                println!(
+                    "[HistoryRows] 警告: フレーム {} で前のキーフレームと重なる行がありません（入力を取りこぼした可能性があります）",
+                    frame_num
+                );
+                let ongoing = self.previous.last().cloned().expect("previous");
+                self.commit(ongoing);
+                rows.len()
+            }
+        };
+        // 間に増えた行（最下行を除く）は確定済みの入力
+        if new_rows > 0 {
+            for row in &rows[rows.len() - new_rows..rows.len() - 1] {
+                self.commit(row.clone());
+            }
+        }
+        self.previous = rows;
+    }
+
+    /// 最後のキーフレームの最下行を確定してステップ列を返す
+    pub fn finish(mut self) -> Vec<(InputState, u32)> {
+        if let Some(last) = self.previous.pop() {
+            self.commit(last);
+        }
+        self.steps
+    }
+
+    fn commit(&mut self, row: HistoryRow) {
+        let duration = row.displayed_frames.unwrap_or_else(|| {
+            self.unreadable += 1;
+            1
+        });
+        self.steps.push((row.state, duration));
+    }
+}
+
+// 前のキーフレームから増えた行数（重なる行が無ければ None）
+//
+// previous の後ろの行が current の先頭に同じ順で並ぶずれを探す。継続フレーム数は確定済みの行では一致し、
+// 前回継続中だった行では増えているか同じであること。同じ入力の繰り返しで複数のずれが当てはまる場合は、
+// 増えたフレーム数の合計がキーフレームの間隔 elapsed に最も近いものを選ぶ。
+#[cfg(feature = "ml")]
+fn new_row_count(previous: &[HistoryRow], current: &[HistoryRow], elapsed: u32) -> Option<usize> {
+    (0..previous.len())
+        .filter_map(|shift| {
+            let overlap = &previous[shift..];
+            if overlap.len() > current.len() {
+                return None;
+            }
+            let matches = overlap.iter().zip(current).enumerate().all(|(i, (before, now))| {
+                let ongoing = i == overlap.len() - 1;
+                before.state == now.state
+                    && match (before.displayed_frames, now.displayed_frames) {
+                        (Some(before), Some(now)) if ongoing => now >= before,
+                        (Some(before), Some(now)) => now == before,
+                        _ => true,
+                    }
+            });
+            if !matches {
+                return None;
+            }
+
+            let ongoing_before = overlap.last()?.displayed_frames.unwrap_or(0);
+            let ongoing_now = current[overlap.len() - 1].displayed_frames.unwrap_or(ongoing_before);
+            let added: u32 = current[overlap.len()..].iter().map(|row| row.displayed_frames.unwrap_or(1)).sum();
+            let implied = ongoing_now.saturating_sub(ongoing_before) + added;
+            Some((implied.abs_diff(elapsed), current.len() - overlap.len()))
+        })
+        .min()
+        .map(|(_, new_rows)| new_rows)
+}
+
+/// 複数行解析の設定（行数が 1 以下なら None）
+#[cfg(feature = "ml")]
+pub struct HistoryRowsSettings {
+    pub rows: u32,
+    /// キーフレームの間隔（フレーム数）
+    pub keyframe_interval: u32,
+}
+
+#[cfg(feature = "ml")]
+impl HistoryRowsSettings {
+    pub fn from_config(settings: &GenerationSettings) -> Option<Self> {
+        if settings.history_rows <= 1 {
+            return None;
+        }
+        let keyframe_interval = match settings.history_keyframe_interval {
+            0 => settings.history_rows - 1,
+            interval => interval,
+        };
+        Some(Self { rows: settings.history_rows, keyframe_interval })
+    }
+}
+
+/// キーフレームの全行を解析してステップ列を求める
+///
+/// region は最下行の領域（transform の補正後の座標）。on_frame・on_classified は extract_input_history_to_csv と同じ
+/// （on_classified には最下行の分類結果を渡す）。戻り値は（ステップ列, 処理した最後のフレーム番号 + 1）。
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+pub fn extract_steps_from_history_rows<F, G>(
+    engine: &InferenceEngine,
+    video_path: &Path,
+    transform: VideoTransform,
+    region: &InputIndicatorRegion,
+    frame_count: Option<&FrameCountColumn>,
+    settings: &HistoryRowsSettings,
+    range: TimeRange,
+    mut on_frame: F,
+    mut on_classified: G,
+) -> Result<(Vec<(InputState, u32)>, u32)>
+where
+    F: FnMut(u32) -> Result<()>,
+    G: FnMut(u32, &FrameClassification),
+{
+    let Some(frame_count) = frame_count else {
+        anyhow::bail!("複数行解析には継続フレーム数の読み取り（数字のテンプレートのフォルダ）の設定が必要です");
+    };
+    let rows_region = history_region(region, settings.rows);
+    if rows_region.rows < settings.rows {
+        println!("[HistoryRows] 警告: 画面の上端までの {}行だけを解析します", rows_region.rows);
+    }
+    if settings.keyframe_interval >= rows_region.rows {
+        println!(
+            "[HistoryRows] 警告: キーフレームの間隔 {}F が表示行数 {} 以上のため、短い入力を取りこぼす可能性があります",
+            settings.keyframe_interval, rows_region.rows
+        );
+    }
+    println!("[HistoryRows] {}行をキーフレーム {}Fごとに解析します", rows_region.rows, settings.keyframe_interval);
+
+    let extractor = FrameExtractor::new(FrameExtractorConfig {
+        frame_interval: settings.keyframe_interval.max(1),
+        transform,
+        ..FrameExtractorConfig::default()
+    });
+    let mut reconciler = HistoryReconciler::new();
+    let mut total_frames = 0u32;
+
+    extractor.process_frames_sync_in_range(
+        video_path,
+        Some(frame_count.crop_region(&rows_region)),
+        range,
+        |frame_img, frame_num| {
+            total_frames = frame_num + 1;
+            on_frame(frame_num)?;
+
+            let rows = classify_history_rows(engine, frame_img, region, frame_count)?;
+            if let Some(newest) = rows.last() {
+                on_classified(frame_num, newest);
+            }
+            reconciler.push(frame_num, rows.into_iter().map(HistoryRow::from).collect());
+            Ok(())
+        },
+    )?;
+
+    println!(
+        "[HistoryRows] 重なりが見つからなかったキーフレーム {} / 継続フレーム数を読み取れなかった行 {}",
+        reconciler.gaps, reconciler.unreadable
+    );
+    Ok((reconciler.finish(), total_frames))
+}
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+
+    fn row(direction: u8, displayed: u32) -> HistoryRow {
+        HistoryRow { state: InputState { direction, ..InputState::new() }, displayed_frames: Some(displayed) }
+    }
+
+    fn durations(steps: &[(InputState, u32)]) -> Vec<(u8, u32)> {
+        steps.iter().map(|(s, d)| (s.direction, *d)).collect()
+    }
+
+    #[test]
+    fn test_reconciles_scrolled_rows() {
+        let mut reconciler = HistoryReconciler::new();
+        // 最初のキーフレームより前の行は使わない
+        reconciler.push(0, vec![row(2, 30), row(5, 12), row(6, 3)]);
+        // 継続中の入力が伸びただけ
+        reconciler.push(6, vec![row(2, 30), row(5, 12), row(6, 9)]);
+        // 6 が 10F で終わり、3 (2F) と 5 (継続中) が増えた
+        reconciler.push(13, vec![row(6, 10), row(3, 2), row(5, 4)]);
+        assert_eq!(reconciler.gaps, 0);
+        let steps = reconciler.finish();
+        assert_eq!(durations(&steps), vec![(6, 10), (3, 2), (5, 4)]);
+    }
+
+    #[test]
+    fn test_repeated_inputs_are_aligned_by_elapsed_frames() {
+        let previous = [row(5, 4), row(6, 2), row(5, 4), row(6, 1)];
+        let current = [row(5, 4), row(6, 2), row(5, 4), row(6, 3)];
+        // 行の並びだけでは、6 が伸びただけ（2F 経過）とも、6 (2F) が終わり 5 (4F) と 6 (3F) が増えた（8F 経過）とも読める
+        assert_eq!(new_row_count(&previous, &current, 2), Some(0));
+        assert_eq!(new_row_count(&previous, &current, 8), Some(2));
+
+        let mut reconciler = HistoryReconciler::new();
+        reconciler.push(0, previous.to_vec());
+        reconciler.push(8, current.to_vec());
+        let steps = reconciler.finish();
+        assert_eq!(durations(&steps), vec![(6, 2), (5, 4), (6, 3)]);
+    }
+
+    #[test]
+    fn test_missing_overlap_is_reported_as_gap() {
+        let mut reconciler = HistoryReconciler::new();
+        reconciler.push(0, vec![row(6, 1)]);
+        reconciler.push(20, vec![row(2, 3), row(5, 7)]);
+        assert_eq!(reconciler.gaps, 1);
+        let steps = reconciler.finish();
+        assert_eq!(durations(&steps), vec![(6, 1), (2, 3), (5, 7)]);
+    }
+}
@@ -5,23 +5,116 @@ use anyhow::Result;
 #[cfg(feature = "ml")]
 use std::path::Path;
 #[cfg(feature = "ml")]
+use std::sync::{Mutex, MutexGuard, OnceLock};
+#[cfg(feature = "ml")]
 use burn::{
     backend::Wgpu,
-    module::Module,
-    record::{DefaultFileRecorder, FullPrecisionSettings, Recorder},
-    tensor::Tensor,
+    tensor::{activation::softmax, backend::Backend, Tensor, TensorData},
 };
 #[cfg(feature = "ml")]
 use burn_wgpu::WgpuDevice;
 #[cfg(feature = "ml")]
 use burn_ndarray::{NdArray, NdArrayDevice};
-use std::io::Write;
 
 #[cfg(feature = "ml")]
-use crate::ml::{IconClassifier, ModelConfig, load_and_normalize_image_with_size};
+use crate::ml::{IconClassifier, ModelConfig, load_and_normalize_image_with_dims};
 #[cfg(feature = "ml")]
 use crate::model::{load_metadata, load_model_binary, InferenceConfig};
 
+/// 正規化に使う ImageNet の平均と標準偏差
+#[cfg(feature = "ml")]
+const NORMALIZE_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+#[cfg(feature = "ml")]
+const NORMALIZE_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+// 画素値（0〜255）から正規化後の値への変換表（チャンネルごと）
+#[cfg(feature = "ml")]
+fn normalize_table() -> &'static [[f32; 256]; 3] {
+    static TABLE: OnceLock<[[f32; 256]; 3]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [[0.0; 256]; 3];
+        for (channel, values) in table.iter_mut().enumerate() {
+            for (value, normalized) in values.iter_mut().enumerate() {
+                *normalized = (value as f32 / 255.0 - NORMALIZE_MEAN[channel]) / NORMALIZE_STD[channel];
+            }
+        }
+        table
+    })
+}
+
+/// 画像群を正規化して NCHW の順に buf へ詰める（buf の確保済み領域は再利用する）
+///
+/// (width, height) と異なるサイズの画像は縮小してから詰める。
+#[cfg(feature = "ml")]
+fn fill_nchw(buf: &mut Vec<f32>, images: &[image::RgbImage], (width, height): (usize, usize)) {
+    let table = normalize_table();
+    let plane = width * height;
+    buf.clear();
+    buf.resize(images.len() * 3 * plane, 0.0);
+
+    for (img, chw) in images.iter().zip(buf.chunks_exact_mut(3 * plane)) {
+        let resized;
+        let img = if img.dimensions() == (width as u32, height as u32) {
+            img
+        } else {
+            resized = image::imageops::resize(img, width as u32, height as u32, image::imageops::FilterType::Lanczos3);
+            &resized
+        };
+
+        for (i, pixel) in img.as_raw().chunks_exact(3).enumerate() {
+            for channel in 0..3 {
+                chw[channel * plane + i] = table[channel][pixel[channel] as usize];
+            }
+        }
+    }
+}
+
+// argmax の結果をクラスラベルに変換する
+// 整数の型はバックエンドによって異なる（wgpu は i32、NdArray は i64）ため、i64 に揃えて読む
+#[cfg(feature = "ml")]
+fn labels_from_indices(data: TensorData, config: &InferenceConfig) -> Result<Vec<String>> {
+    data.iter::<i64>()
+        .map(|idx| {
+            let class_idx = idx as usize;
+            config.class_index_to_label(class_idx)
+                .ok_or_else(|| anyhow::anyhow!("クラスインデックス {} は範囲外です", class_idx))
+        })
+        .collect()
+}
+
+// 作業用バッファを借りる（他のスレッドが推論中に panic してもバッファは使い続けられる）
+#[cfg(feature = "ml")]
+fn lock_scratch(scratch: &Mutex<Vec<f32>>) -> MutexGuard<'_, Vec<f32>> {
+    scratch.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// 画像群を1回の forward でまとめて推論し、出力（ロジット, [batch, クラス数]）を返す
+#[cfg(feature = "ml")]
+fn forward_images<B: Backend>(
+    model: &IconClassifier<B>,
+    device: &B::Device,
+    scratch: &Mutex<Vec<f32>>,
+    images: &[image::RgbImage],
+    (width, height): (usize, usize),
+) -> Tensor<B, 2> {
+    // バッファのロックはテンソルを作るまでにして、forward は他のスレッドと並行に実行する
+    let tensor = {
+        let mut normalized = lock_scratch(scratch);
+        fill_nchw(&mut normalized, images, (width, height));
+        Tensor::<B, 1>::from_floats(normalized.as_slice(), device)
+    };
+    model.forward(tensor.reshape([images.len(), 3, height, width]))
+}
+
+// 出力をソフトマックスで確率にし、最も確率の高いクラスのラベルとその確率を返す
+#[cfg(feature = "ml")]
+fn labels_with_confidence<B: Backend>(output: Tensor<B, 2>, config: &InferenceConfig) -> Result<Vec<(String, f32)>> {
+    let probabilities = softmax(output, 1);
+    let confidences = probabilities.clone().max_dim(1).into_data();
+    let labels = labels_from_indices(probabilities.argmax(1).into_data(), config)?;
+    Ok(labels.into_iter().zip(confidences.iter::<f32>()).collect())
+}
+
 /// 推論エンジン（enum dispatchパターンでバックエンドを切り替え）
 #[cfg(feature = "ml")]
 pub enum InferenceEngine {
@@ -29,10 +122,13 @@ pub enum InferenceEngine {
         model: IconClassifier<Wgpu>,
         config: InferenceConfig,
         device: WgpuDevice,
+        /// 正規化した入力を詰める作業用バッファ（推論ごとに確保し直さない）
+        scratch: Mutex<Vec<f32>>,
     },
     NdArray {
         model: IconClassifier<NdArray>,
         config: InferenceConfig,
+        scratch: Mutex<Vec<f32>>,
     },
 }
 
@@ -54,6 +150,8 @@ impl InferenceEngine {
             num_classes: config.num_total_classes(),
             dropout: 0.0, // 推論時はドロップアウトなし
             image_size: metadata.image_width as usize,  // メタデータから取得
+            image_height: metadata.image_height as usize,
+            architecture: metadata.architecture,
         };
 
         // モデルバイナリ読み込み（.mpk形式）
@@ -62,55 +160,27 @@ impl InferenceEngine {
         if use_gpu {
             // GPU (WGPU) バックエンド
             let device = WgpuDevice::DiscreteGpu(0);
-            let model = model_config.init::<Wgpu>(&device);
-
-            // 一時ファイルに書き出してDefaultFileRecorder(FullPrecision)で読み込む
-            let temp_dir = std::env::temp_dir();
-            let temp_model_path = temp_dir.join(format!("model_{}.mpk", std::process::id()));
-            
-            {
-                let mut temp_file = std::fs::File::create(&temp_model_path)?;
-                temp_file.write_all(&model_binary)?;
-            }
-
-            // モデルの重みを復元
-            let record = DefaultFileRecorder::<FullPrecisionSettings>::new()
-                .load(temp_model_path.clone(), &device)
-                .map_err(|e| anyhow::anyhow!("モデル重みの読み込みエラー: {:?}", e))?;
-
-            let _ = std::fs::remove_file(temp_model_path);
-            let model = model.load_record(record);
+            let model = model_config
+                .init::<Wgpu>(&device)
+                .load_weights(&model_binary, metadata.weight_precision, &device)?;
             
             Ok(Self::Wgpu {
                 model,
                 config,
                 device,
+                scratch: Mutex::default(),
             })
         } else {
             // CPU (NdArray) バックエンド
             let device = NdArrayDevice::Cpu;
-            let model = model_config.init::<NdArray>(&device);
-
-            // 一時ファイルに書き出してDefaultFileRecorder(FullPrecision)で読み込む
-            let temp_dir = std::env::temp_dir();
-            let temp_model_path = temp_dir.join(format!("model_{}.mpk", std::process::id()));
-            
-            {
-                let mut temp_file = std::fs::File::create(&temp_model_path)?;
-                temp_file.write_all(&model_binary)?;
-            }
-
-            // モデルの重みを復元
-            let record = DefaultFileRecorder::<FullPrecisionSettings>::new()
-                .load(temp_model_path.clone(), &device)
-                .map_err(|e| anyhow::anyhow!("モデル重みの読み込みエラー: {:?}", e))?;
-
-            let _ = std::fs::remove_file(temp_model_path);
-            let model = model.load_record(record);
+            let model = model_config
+                .init::<NdArray>(&device)
+                .load_weights(&model_binary, metadata.weight_precision, &device)?;
             
             Ok(Self::NdArray {
                 model,
                 config,
+                scratch: Mutex::default(),
             })
         }
     }
@@ -118,12 +188,12 @@ impl InferenceEngine {
     /// 単一画像を分類
     pub fn classify_image<P: AsRef<Path>>(&self, image_path: P) -> Result<String> {
         match self {
-            Self::Wgpu { model, config, device } => {
-                let img_size = config.model_input_size as usize;
-                let image_data = load_and_normalize_image_with_size(image_path.as_ref(), img_size)?;
+            Self::Wgpu { model, config, device, .. } => {
+                let (width, height) = config.input_dims();
+                let image_data = load_and_normalize_image_with_dims(image_path.as_ref(), width, height)?;
                 
                 let tensor = Tensor::<Wgpu, 1>::from_floats(image_data.as_slice(), device)
-                    .reshape([1, 3, img_size, img_size]);
+                    .reshape([1, 3, height, width]);
                 
                 let output = model.forward(tensor);
                 let predicted = output.argmax(1);
@@ -137,13 +207,13 @@ impl InferenceEngine {
                 
                 Ok(class_name)
             }
-            Self::NdArray { model, config } => {
-                let img_size = config.model_input_size as usize;
-                let image_data = load_and_normalize_image_with_size(image_path.as_ref(), img_size)?;
+            Self::NdArray { model, config, .. } => {
+                let (width, height) = config.input_dims();
+                let image_data = load_and_normalize_image_with_dims(image_path.as_ref(), width, height)?;
                 
                 let device = NdArrayDevice::Cpu;
                 let tensor = Tensor::<NdArray, 1>::from_floats(image_data.as_slice(), &device)
-                    .reshape([1, 3, img_size, img_size]);
+                    .reshape([1, 3, height, width]);
                 
                 let output = model.forward(tensor);
                 let predicted = output.argmax(1);
@@ -161,39 +231,19 @@ impl InferenceEngine {
 
     /// メモリ上の画像を直接分類（ファイルI/Oなし）
     pub fn classify_image_direct(&self, img: &image::RgbImage) -> Result<String> {
-        match self {
-            Self::Wgpu { model, config, device } => {
-                let img_size = config.model_input_size as usize;
-                let (width, height) = img.dimensions();
-                
-                if width != img_size as u32 || height != img_size as u32 {
-                    anyhow::bail!(
-                        "画像サイズが不正です: {}x{} (期待: {}x{})",
-                        width, height, img_size, img_size
-                    );
-                }
-
-                let mut data = Vec::with_capacity(3 * img_size * img_size);
-                let mean = [0.485, 0.456, 0.406];
-                let std = [0.229, 0.224, 0.225];
-
-                for channel in 0..3 {
-                    for y in 0..height {
-                        for x in 0..width {
-                            let pixel = img.get_pixel(x, y);
-                            let value = pixel[channel] as f32 / 255.0;
-                            let normalized = (value - mean[channel]) / std[channel];
-                            data.push(normalized);
-                        }
-                    }
-                }
-
-                let tensor = Tensor::<Wgpu, 1>::from_floats(data.as_slice(), device)
-                    .reshape([1, 3, img_size, img_size]);
+        let (width, height) = self.config().input_dims();
+        if img.dimensions() != (width as u32, height as u32) {
+            anyhow::bail!(
+                "画像サイズが不正です: {}x{} (期待: {}x{})",
+                img.width(), img.height(), width, height
+            );
+        }
 
-                let output = model.forward(tensor);
-                let predicted = output.argmax(1);
-                let class_idx = predicted
+        match self {
+            Self::Wgpu { model, config, device, scratch } => {
+                let output = forward_images(model, device, scratch, std::slice::from_ref(img), (width, height));
+                let class_idx = output
+                    .argmax(1)
                     .into_data()
                     .to_vec::<i32>()
                     .map_err(|e| anyhow::anyhow!("推論結果の取得エラー: {:?}", e))?[0] as usize;
@@ -203,60 +253,13 @@ impl InferenceEngine {
 
                 Ok(class_name)
             }
-            Self::NdArray { model, config } => {
-                println!("[NdArray推論] 開始");
-                let img_size = config.model_input_size as usize;
-                let (width, height) = img.dimensions();
-                println!("[NdArray推論] サイズ確認: {}x{} (期待: {}x{})", width, height, img_size, img_size);
-                
-                if width != img_size as u32 || height != img_size as u32 {
-                    anyhow::bail!(
-                        "画像サイズが不正です: {}x{} (期待: {}x{})",
-                        width, height, img_size, img_size
-                    );
-                }
-
-                println!("[NdArray推論] 正規化開始");
-                let mut data = Vec::with_capacity(3 * img_size * img_size);
-                let mean = [0.485, 0.456, 0.406];
-                let std = [0.229, 0.224, 0.225];
-
-                for channel in 0..3 {
-                    for y in 0..height {
-                        for x in 0..width {
-                            let pixel = img.get_pixel(x, y);
-                            let value = pixel[channel] as f32 / 255.0;
-                            let normalized = (value - mean[channel]) / std[channel];
-                            data.push(normalized);
-                        }
-                    }
-                }
-                println!("[NdArray推論] 正規化完了");
-
-                println!("[NdArray推論] Tensor作成開始");
-                let device = NdArrayDevice::Cpu;
-                let tensor = Tensor::<NdArray, 1>::from_floats(data.as_slice(), &device)
-                    .reshape([1, 3, img_size, img_size]);
-                println!("[NdArray推論] Tensor作成完了");
-
-                println!("[NdArray推論] forward開始");
-                let output = model.forward(tensor);
-                println!("[NdArray推論] forward完了");
-                
-                println!("[NdArray推論] argmax開始");
-                let predicted = output.argmax(1);
-                println!("[NdArray推論] argmax完了");
-                
-                println!("[NdArray推論] データ取得開始");
+            Self::NdArray { model, config, scratch } => {
+                let output = forward_images(model, &NdArrayDevice::Cpu, scratch, std::slice::from_ref(img), (width, height));
                 // NdArrayバックエンドではto_vecが遅いため、値を直接取得
-                let class_idx = predicted
-                    .clone()
-                    .into_scalar() as usize;
-                println!("[NdArray推論] データ取得完了: class_idx={}", class_idx);
+                let class_idx = output.argmax(1).into_scalar() as usize;
 
                 let class_name = config.class_index_to_label(class_idx)
                     .ok_or_else(|| anyhow::anyhow!("クラスインデックス {} は範囲外です", class_idx))?;
-                println!("[NdArray推論] 完了: {}", class_name);
 
                 Ok(class_name)
             }
@@ -278,111 +281,38 @@ impl InferenceEngine {
     /// バッチ画像（RGB画像群）をまとめて分類
     /// images の長さがバッチサイズになります。モデルのメタデータに基づく
     /// 列数などをバッチサイズとして使用してください。
+    /// どちらのバックエンドでも1回の forward でまとめて推論します。
     pub fn classify_batch_from_images(&self, images: &[image::RgbImage]) -> Result<Vec<String>> {
         if images.is_empty() {
             return Ok(Vec::new());
         }
 
         match self {
-            Self::Wgpu { model, config, device } => {
-                let img_size = config.model_input_size as usize;
-                let batch = images.len();
-                let mut normalized = Vec::with_capacity(batch * 3 * img_size * img_size);
-
-                for img in images {
-                    let resized = image::imageops::resize(img, img_size as u32, img_size as u32, image::imageops::FilterType::Lanczos3);
-                    for channel in 0..3 {
-                        for y in 0..img_size {
-                            for x in 0..img_size {
-                                let pixel = resized.get_pixel(x as u32, y as u32);
-                                let value = pixel[channel] as f32 / 255.0;
-                                let mean = [0.485f32, 0.456f32, 0.406f32];
-                                let std = [0.229f32, 0.224f32, 0.225f32];
-                                let normalized_value = (value - mean[channel]) / std[channel];
-                                normalized.push(normalized_value);
-                            }
-                        }
-                    }
-                }
-
-                let tensor = Tensor::<Wgpu, 1>::from_floats(normalized.as_slice(), device)
-                    .reshape([batch, 3, img_size, img_size]);
-
-                let output = model.forward(tensor);
-                let predicted = output.argmax(1);
-                // 出力の整数型はバックエンドや環境で異なることがあるため、
-                // まず i64 を試し、失敗したら i32 を試すフォールバックを行う。
-                let mut results = Vec::new();
-
-                // cloneしてi64を試す
-                let predicted_clone = predicted.clone();
-                match predicted_clone.into_data().to_vec::<i64>() {
-                    Ok(vec_i64) => {
-                        for idx in vec_i64 {
-                            let class_idx = idx as usize;
-                            let class_name = config.class_index_to_label(class_idx)
-                                .ok_or_else(|| anyhow::anyhow!("クラスインデックス {} は範囲外です", class_idx))?;
-                            results.push(class_name);
-                        }
-                        return Ok(results);
-                    }
-                    Err(_) => {
-                        // i32 を試す
-                        let vec_i32 = predicted
-                            .into_data()
-                            .to_vec::<i32>()
-                            .map_err(|e| anyhow::anyhow!("推論結果の取得エラー: {:?}", e))?;
-                        for idx in vec_i32 {
-                            let class_idx = idx as usize;
-                            let class_name = config.class_index_to_label(class_idx)
-                                .ok_or_else(|| anyhow::anyhow!("クラスインデックス {} は範囲外です", class_idx))?;
-                            results.push(class_name);
-                        }
-                        return Ok(results);
-                    }
-                }
+            Self::Wgpu { model, config, device, scratch } => {
+                let output = forward_images(model, device, scratch, images, config.input_dims());
+                labels_from_indices(output.argmax(1).into_data(), config)
             }
-            Self::NdArray { model, config } => {
-                let img_size = config.model_input_size as usize;
-                let batch = images.len();
-                let mut normalized = Vec::with_capacity(batch * 3 * img_size * img_size);
-
-                for img in images {
-                    let resized = image::imageops::resize(img, img_size as u32, img_size as u32, image::imageops::FilterType::Lanczos3);
-                    for channel in 0..3 {
-                        for y in 0..img_size {
-                            for x in 0..img_size {
-                                let pixel = resized.get_pixel(x as u32, y as u32);
-                                let value = pixel[channel] as f32 / 255.0;
-                                let mean = [0.485f32, 0.456f32, 0.406f32];
-                                let std = [0.229f32, 0.224f32, 0.225f32];
-                                let normalized_value = (value - mean[channel]) / std[channel];
-                                normalized.push(normalized_value);
-                            }
-                        }
-                    }
-                }
-
-                let device = NdArrayDevice::Cpu;
-                let tensor = Tensor::<NdArray, 1>::from_floats(normalized.as_slice(), &device)
-                    .reshape([batch, 3, img_size, img_size]);
-
-                let output = model.forward(tensor);
-                let predicted = output.argmax(1);
-                let class_idxs = predicted
-                    .into_data()
-                    .to_vec::<i32>()
-                    .map_err(|e| anyhow::anyhow!("推論結果の取得エラー: {:?}", e))?;
+            Self::NdArray { model, config, scratch } => {
+                let output = forward_images(model, &NdArrayDevice::Cpu, scratch, images, config.input_dims());
+                labels_from_indices(output.argmax(1).into_data(), config)
+            }
+        }
+    }
 
-                let mut results = Vec::with_capacity(class_idxs.len());
-                for idx in class_idxs {
-                    let class_idx = idx as usize;
-                    let class_name = config.class_index_to_label(class_idx)
-                        .ok_or_else(|| anyhow::anyhow!("クラスインデックス {} は範囲外です", class_idx))?;
-                    results.push(class_name);
-                }
+    /// classify_batch_from_images と同じくまとめて分類し、各画像のラベルと確信度（0〜1）を返す
+    pub fn classify_batch_with_confidence(&self, images: &[image::RgbImage]) -> Result<Vec<(String, f32)>> {
+        if images.is_empty() {
+            return Ok(Vec::new());
+        }
 
-                Ok(results)
+        match self {
+            Self::Wgpu { model, config, device, scratch } => {
+                let output = forward_images(model, device, scratch, images, config.input_dims());
+                labels_with_confidence(output, config)
+            }
+            Self::NdArray { model, config, scratch } => {
+                let output = forward_images(model, &NdArrayDevice::Cpu, scratch, images, config.input_dims());
+                labels_with_confidence(output, config)
             }
         }
     }
@@ -390,34 +320,13 @@ impl InferenceEngine {
     /// RGB画像から直接分類（クラスインデックスを返す）
     pub fn predict_from_rgb_image(&self, image: &image::ImageBuffer<image::Rgb<u8>, Vec<u8>>) -> Result<usize> {
         match self {
-            Self::Wgpu { model, config, device } => {
-                let img_size = config.model_input_size;
-                let resized = image::imageops::resize(
-                    image,
-                    img_size,
-                    img_size,
-                    image::imageops::FilterType::Lanczos3
-                );
-                
-                let mean = [0.485, 0.456, 0.406];
-                let std = [0.229, 0.224, 0.225];
-                
-                let img_size_usize = img_size as usize;
-                let mut normalized = Vec::with_capacity(3 * img_size_usize * img_size_usize);
-                
-                for channel in 0..3 {
-                    for y in 0..img_size_usize {
-                        for x in 0..img_size_usize {
-                            let pixel = resized.get_pixel(x as u32, y as u32);
-                            let value = pixel[channel] as f32 / 255.0;
-                            let normalized_value = (value - mean[channel]) / std[channel];
-                            normalized.push(normalized_value);
-                        }
-                    }
-                }
-                
+            Self::Wgpu { model, config, device, scratch } => {
+                let (width, height) = config.input_dims();
+                let mut normalized = lock_scratch(scratch);
+                fill_nchw(&mut normalized, std::slice::from_ref(image), (width, height));
+
                 let tensor = Tensor::<Wgpu, 1>::from_floats(normalized.as_slice(), device)
-                    .reshape([1, 3, img_size_usize, img_size_usize]);
+                    .reshape([1, 3, height, width]);
                 
                 let output = model.forward(tensor);
                 let predicted = output.argmax(1);
@@ -428,35 +337,14 @@ impl InferenceEngine {
                 
                 Ok(class_idx)
             }
-            Self::NdArray { model, config } => {
-                let img_size = config.model_input_size;
-                let resized = image::imageops::resize(
-                    image,
-                    img_size,
-                    img_size,
-                    image::imageops::FilterType::Lanczos3
-                );
-                
-                let mean = [0.485, 0.456, 0.406];
-                let std = [0.229, 0.224, 0.225];
-                
-                let img_size_usize = img_size as usize;
-                let mut normalized = Vec::with_capacity(3 * img_size_usize * img_size_usize);
-                
-                for channel in 0..3 {
-                    for y in 0..img_size_usize {
-                        for x in 0..img_size_usize {
-                            let pixel = resized.get_pixel(x as u32, y as u32);
-                            let value = pixel[channel] as f32 / 255.0;
-                            let normalized_value = (value - mean[channel]) / std[channel];
-                            normalized.push(normalized_value);
-                        }
-                    }
-                }
-                
+            Self::NdArray { model, config, scratch } => {
+                let (width, height) = config.input_dims();
+                let mut normalized = lock_scratch(scratch);
+                fill_nchw(&mut normalized, std::slice::from_ref(image), (width, height));
+
                 let device = NdArrayDevice::Cpu;
                 let tensor = Tensor::<NdArray, 1>::from_floats(normalized.as_slice(), &device)
-                    .reshape([1, 3, img_size_usize, img_size_usize]);
+                    .reshape([1, 3, height, width]);
                 
                 let output = model.forward(tensor);
                 let predicted = output.argmax(1);
@@ -469,6 +357,20 @@ impl InferenceEngine {
         }
     }
 
+    /// CPU (NdArray) バックエンドの場合、別スレッドで使う複製を作成する（GPUの場合は None）
+    ///
+    /// wgpu のデバイスは1スレッドから使う前提のため、並列化は CPU バックエンドに限る。
+    pub fn try_clone_cpu(&self) -> Option<Self> {
+        match self {
+            Self::Wgpu { .. } => None,
+            Self::NdArray { model, config, .. } => Some(Self::NdArray {
+                model: model.clone(),
+                config: config.clone(),
+                scratch: Mutex::default(),
+            }),
+        }
+    }
+
     /// InferenceConfigへの参照を取得
     pub fn config(&self) -> &InferenceConfig {
         match self {
@@ -477,3 +379,29 @@ impl InferenceEngine {
         }
     }
 }
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_nchw_layout_and_reuse() {
+        let red = image::RgbImage::from_pixel(2, 2, image::Rgb([255, 0, 0]));
+        let gray = image::RgbImage::from_pixel(4, 4, image::Rgb([128, 128, 128]));
+        let mut buf = Vec::new();
+
+        fill_nchw(&mut buf, &[red.clone(), gray], (2, 2));
+        assert_eq!(buf.len(), 2 * 3 * 4);
+        // 1枚目: R プレーン → G プレーン → B プレーンの順
+        assert!((buf[0] - (1.0 - NORMALIZE_MEAN[0]) / NORMALIZE_STD[0]).abs() < 1e-6);
+        assert!((buf[4] + NORMALIZE_MEAN[1] / NORMALIZE_STD[1]).abs() < 1e-6);
+        // 2枚目は縮小してから詰める
+        assert!((buf[12] - (128.0 / 255.0 - NORMALIZE_MEAN[0]) / NORMALIZE_STD[0]).abs() < 1e-6);
+
+        // 再利用時は前回の内容を残さない
+        let capacity = buf.capacity();
+        fill_nchw(&mut buf, &[red], (2, 2));
+        assert_eq!(buf.len(), 3 * 4);
+        assert_eq!(buf.capacity(), capacity);
+    }
+}
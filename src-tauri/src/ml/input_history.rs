@@ -0,0 +1,784 @@
+//! 動画からの入力履歴抽出
+//!
+//! 入力表示領域をフレームごとに分類し、入力状態が変化するたびに1ステップとしてCSVに書き出します。
+
+#[cfg(feature = "ml")]
+use anyhow::{Context, Result};
+#[cfg(feature = "ml")]
+use std::fs;
+#[cfg(feature = "ml")]
+use std::collections::BTreeMap;
+#[cfg(feature = "ml")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "ml")]
+use std::sync::{mpsc, Mutex};
+
+#[cfg(feature = "ml")]
+use crate::analyzer::{
+    extract_tiles_from_image, update_input_state, DigitReader, DuplicateFrameFilter, InputIndicatorRegion,
+    write_steps_csv, InputState, LabeledRegion, OutputColumn, StepPostProcess,
+};
+#[cfg(feature = "ml")]
+use crate::ml::history_rows::{extract_steps_from_history_rows, HistoryRowsSettings};
+#[cfg(feature = "ml")]
+use crate::ml::InferenceEngine;
+#[cfg(feature = "ml")]
+use crate::model::{FrameCountMode, GenerationSettings, InferenceConfig, VideoTransform};
+#[cfg(feature = "ml")]
+use crate::video::{FrameExtractor, FrameExtractorConfig, TimeRange};
+
+/// モデルのメタデータから入力表示の領域を求める（最下行のみを解析する）
+#[cfg(feature = "ml")]
+pub fn indicator_region(config: &InferenceConfig) -> InputIndicatorRegion {
+    // 領域全体のサイズを計算（個々のタイルサイズ × 列数）
+    // 注意: tile_widthは領域全体の幅、image_widthが個々のタイルサイズ
+    let tile_width = config.image_width; // 個々のタイルの幅（48x48 など）
+    let total_width = tile_width * config.columns_per_row;
+    let total_height = config.image_height; // 1行のみ
+
+    InputIndicatorRegion {
+        x: config.tile_x,
+        y: config.tile_y,
+        width: total_width,
+        height: total_height,
+        rows: 1, // 最下行のみ解析
+        cols: config.columns_per_row,
+    }
+}
+
+/// 1フレームの分類結果
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone)]
+pub struct FrameClassification {
+    pub state: InputState,
+    /// タイルごとの確信度（0〜1、左の列から順）
+    pub confidences: Vec<f32>,
+    /// 継続フレーム数の列から読み取った値（読み取らない・読み取れなかった場合は None）
+    pub displayed_frames: Option<u32>,
+}
+
+/// 継続フレーム数の列（解析する列のすぐ左にある、タイルと同じ幅の列）の読み取り
+#[cfg(feature = "ml")]
+pub struct FrameCountColumn {
+    reader: DigitReader,
+    cell_width: u32,
+    pub mode: FrameCountMode,
+    /// 表示の上限（この値以上は読み取った値を使わない。0で上限なし）
+    pub display_max: u32,
+}
+
+#[cfg(feature = "ml")]
+impl FrameCountColumn {
+    /// 設定で数字のテンプレートが指定されていれば作成する（読み込めない場合は警告して None）
+    pub fn from_config(settings: &GenerationSettings, region: &InputIndicatorRegion) -> Option<Self> {
+        let dir = settings.frame_count_digits_dir.as_deref().filter(|dir| !dir.is_empty())?;
+        let cell_width = region.width / region.cols.max(1);
+        if region.x < cell_width {
+            println!("[FrameCount] 警告: 解析する列の左に継続フレーム数の列がありません（x={}）", region.x);
+            return None;
+        }
+        match DigitReader::load(Path::new(dir)) {
+            Ok(reader) => Some(Self {
+                reader,
+                cell_width,
+                mode: settings.frame_count_mode,
+                display_max: settings.frame_count_display_max,
+            }),
+            Err(e) => {
+                println!("[FrameCount] 警告: 継続フレーム数を読み取りません: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// 継続フレーム数の列を含めて切り出す領域（region の左に1列分広げる）
+    pub fn crop_region(&self, region: &InputIndicatorRegion) -> InputIndicatorRegion {
+        InputIndicatorRegion {
+            x: region.x - self.cell_width,
+            width: region.width + self.cell_width,
+            ..region.clone()
+        }
+    }
+
+    /// crop_region で切り出した画像を、継続フレーム数のセルと入力表示の領域に分ける
+    fn split(&self, frame_img: &image::RgbImage) -> Result<(image::RgbImage, image::RgbImage)> {
+        let stride = frame_img.width() as usize * 3;
+        let height = frame_img.height();
+        let cell = crate::video::crop_rgb_rows(frame_img.as_raw(), stride, 0, 0, self.cell_width, height);
+        let indicator = crate::video::crop_rgb_rows(
+            frame_img.as_raw(),
+            stride,
+            self.cell_width,
+            0,
+            frame_img.width().saturating_sub(self.cell_width),
+            height,
+        );
+        cell.zip(indicator)
+            .ok_or_else(|| anyhow::anyhow!("継続フレーム数の列を切り出せません（{}x{}）", frame_img.width(), height))
+    }
+}
+
+/// 継続フレーム数の列があれば読み取ってから classify_frame で分類する
+///
+/// frame_count を指定した場合、frame_img は crop_region で切り出した画像であること。
+#[cfg(feature = "ml")]
+pub fn classify_frame_with_count(
+    engine: &InferenceEngine,
+    frame_img: &image::RgbImage,
+    region: &InputIndicatorRegion,
+    frame_count: Option<&FrameCountColumn>,
+) -> Result<FrameClassification> {
+    let Some(column) = frame_count else {
+        return classify_frame(engine, frame_img, region);
+    };
+    let (cell, indicator) = column.split(frame_img)?;
+    let mut classification = classify_frame(engine, &indicator, region)?;
+    classification.displayed_frames = column.reader.read(&cell);
+    Ok(classification)
+}
+
+/// 領域全体で切り出し済みの画像をタイルに分けて分類し、入力状態を求める
+#[cfg(feature = "ml")]
+pub fn classify_input_state(
+    engine: &InferenceEngine,
+    frame_img: &image::RgbImage,
+    region: &InputIndicatorRegion,
+) -> Result<InputState> {
+    classify_frame(engine, frame_img, region).map(|classification| classification.state)
+}
+
+/// classify_input_state と同じく分類し、タイルごとの確信度も返す
+#[cfg(feature = "ml")]
+pub fn classify_frame(
+    engine: &InferenceEngine,
+    frame_img: &image::RgbImage,
+    region: &InputIndicatorRegion,
+) -> Result<FrameClassification> {
+    let config = engine.config();
+    // 渡される画像は既に領域全体でクロップ済みなので、
+    // 切り出し後の画像上で列ごとにタイルを抽出する（x=0,y=0開始）
+    let cropped_region = InputIndicatorRegion {
+        x: 0,
+        y: 0,
+        width: region.width,
+        height: region.height,
+        rows: region.rows,
+        cols: region.cols,
+    };
+
+    let tiles = extract_tiles_from_image(frame_img, &cropped_region)
+        .map_err(|e| anyhow::anyhow!("タイル抽出エラー: {}", e))?;
+
+    // 入力状態を初期化
+    let mut current_state = InputState::new();
+    let mut confidences = Vec::with_capacity(tiles.len());
+
+    // バッチサイズはモデルメタデータの列数を使用（0 の場合は1タイルずつ分類する）
+    let batch_size = (config.columns_per_row as usize).max(1);
+
+    // チャンク毎にバッチ分類を行う
+    for chunk in tiles.chunks(batch_size) {
+        let results = engine.classify_batch_with_confidence(chunk)
+            .map_err(|e| anyhow::anyhow!("バッチ推論エラー: {}", e))?;
+
+        for (class_name, confidence) in results.into_iter() {
+            update_input_state(&mut current_state, &class_name);
+            confidences.push(confidence);
+        }
+    }
+
+    Ok(FrameClassification { state: current_state, confidences, displayed_frames: None })
+}
+
+/// 並列分類で各ワーカーに対して先読みしておくフレーム数
+#[cfg(feature = "ml")]
+const QUEUED_FRAMES_PER_WORKER: usize = 4;
+
+/// 継続フレーム数の不一致を個別にログ出力する件数の上限
+#[cfg(feature = "ml")]
+const MAX_MISMATCH_LOGS: u32 = 20;
+
+/// 数えたフレーム数と読み取った継続フレーム数の照合
+#[cfg(feature = "ml")]
+struct FrameCountCheck {
+    mode: FrameCountMode,
+    display_max: u32,
+    matched: u32,
+    mismatched: u32,
+    unreadable: u32,
+}
+
+#[cfg(feature = "ml")]
+impl FrameCountCheck {
+    // ステップの持続フレーム数を決める
+    fn resolve(&mut self, step_index: usize, counted: u32, displayed: Option<u32>) -> u32 {
+        let displayed = displayed.filter(|&d| d > 0 && (self.display_max == 0 || d < self.display_max));
+        let Some(displayed) = displayed else {
+            self.unreadable += 1;
+            return counted;
+        };
+        if displayed == counted {
+            self.matched += 1;
+        } else {
+            self.mismatched += 1;
+            if self.mismatched <= MAX_MISMATCH_LOGS {
+                println!("[FrameCount] ステップ#{}: 数えたフレーム数 {}F, 表示 {}F", step_index + 1, counted, displayed);
+            }
+        }
+        match self.mode {
+            FrameCountMode::CrossCheck => counted,
+            FrameCountMode::Recognized => displayed,
+        }
+    }
+}
+
+/// フレームごとの入力状態からステップ列（入力状態, 持続フレーム数）を組み立てる
+///
+/// 継続フレーム数の列を読み取る場合は、ステップ中に読み取った最大の値を表示された持続フレーム数とし、
+/// 設定に応じて数えたフレーム数との照合に使うか、持続フレーム数として使う。
+#[cfg(feature = "ml")]
+#[derive(Default)]
+struct StepBuilder {
+    steps: Vec<(InputState, u32)>,
+    previous_state: Option<InputState>,
+    duration: u32,
+    displayed: Option<u32>,
+    frame_count: Option<FrameCountCheck>,
+}
+
+#[cfg(feature = "ml")]
+impl StepBuilder {
+    fn new(frame_count: Option<&FrameCountColumn>) -> Self {
+        Self {
+            frame_count: frame_count.map(|column| FrameCountCheck {
+                mode: column.mode,
+                display_max: column.display_max,
+                matched: 0,
+                mismatched: 0,
+                unreadable: 0,
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// 次のフレームの分類結果を追加（状態が変化したら直前の状態をステップとして確定し、その持続フレーム数を返す）
+    fn push(&mut self, classification: FrameClassification) -> Option<u32> {
+        let current_state = classification.state;
+        let mut closed = None;
+        match self.previous_state.take() {
+            Some(prev) if prev == current_state => self.duration += 1,
+            Some(prev) => {
+                closed = Some(self.close_step(prev));
+                self.duration = 1;
+            }
+            None => self.duration = 1,
+        }
+
+        self.displayed = self.displayed.max(classification.displayed_frames);
+        self.previous_state = Some(current_state);
+        closed
+    }
+
+    /// 直前のフレームと同じ画像のフレームを追加（分類せず、現在のステップの持続フレーム数に加える）
+    fn repeat(&mut self) {
+        if self.previous_state.is_some() {
+            self.duration += 1;
+        }
+    }
+
+    /// 最後の状態を確定してステップ列を返す
+    fn finish(mut self) -> Vec<(InputState, u32)> {
+        if let Some(state) = self.previous_state.take() {
+            self.close_step(state);
+        }
+        if let Some(check) = &self.frame_count {
+            println!(
+                "[FrameCount] 継続フレーム数の照合: 一致 {} / 不一致 {} / 読み取れず {}",
+                check.matched, check.mismatched, check.unreadable
+            );
+        }
+        self.steps
+    }
+
+    fn close_step(&mut self, state: InputState) -> u32 {
+        let displayed = self.displayed.take();
+        let duration = match self.frame_count.as_mut() {
+            Some(check) => check.resolve(self.steps.len(), self.duration, displayed),
+            None => self.duration,
+        };
+        self.steps.push((state, duration));
+        duration
+    }
+}
+
+/// デコードしたフレームを分類に回す前の処理（進捗通知・重複フレームの検出）
+#[cfg(feature = "ml")]
+struct FrameFeed<'a, F> {
+    duplicate_filter: &'a mut Option<DuplicateFrameFilter>,
+    total_frames: &'a mut u32,
+    on_frame: &'a mut F,
+}
+
+#[cfg(feature = "ml")]
+impl<F> FrameFeed<'_, F>
+where
+    F: FnMut(u32) -> Result<()>,
+{
+    /// 分類するフレームなら true（false は直前と同じ画像で、分類結果も直前と同じとみなす）
+    fn accept(&mut self, frame_img: &image::RgbImage, frame_num: u32) -> Result<bool> {
+        *self.total_frames = frame_num + 1;
+        (self.on_frame)(frame_num)?;
+
+        // 直前と同じ画像のフレームは分類しない
+        if let Some(filter) = self.duplicate_filter.as_mut() {
+            if filter.is_duplicate(frame_img) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// 分類を並列化するワーカー数（0 は直列処理）
+///
+/// CPU バックエンドでのみ並列化し、デコード用に1コア残す。
+#[cfg(feature = "ml")]
+fn classification_workers(engine: &InferenceEngine) -> usize {
+    if !matches!(engine, InferenceEngine::NdArray { .. }) {
+        return 0;
+    }
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    if cores < 2 {
+        0
+    } else {
+        cores - 1
+    }
+}
+
+/// デコードと分類を別スレッドで行う
+///
+/// このスレッドで GStreamer からフレームを受け取り、上限付きのチャネルでワーカーに渡す。
+/// ワーカーはそれぞれ推論エンジンの複製で分類し、結果は送った順に並べ直してから record に渡す。
+/// 重複フレームはワーカーに送らず、同じ順番の中で None として record に渡す。
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+fn classify_frames_parallel<F, R>(
+    engine: &InferenceEngine,
+    workers: usize,
+    extractor: &FrameExtractor,
+    video_path: &Path,
+    region: &InputIndicatorRegion,
+    frame_count: Option<&FrameCountColumn>,
+    range: TimeRange,
+    frames: &mut FrameFeed<'_, F>,
+    record: &mut R,
+) -> Result<()>
+where
+    F: FnMut(u32) -> Result<()>,
+    R: FnMut(u32, Option<FrameClassification>),
+{
+    let replicas: Vec<InferenceEngine> = (0..workers).filter_map(|_| engine.try_clone_cpu()).collect();
+
+    let (job_tx, job_rx) = mpsc::sync_channel::<(u64, u32, image::RgbImage)>(workers * QUEUED_FRAMES_PER_WORKER);
+    let job_rx = Mutex::new(job_rx);
+    let (result_tx, result_rx) = mpsc::channel::<(u64, PendingResult)>();
+
+    std::thread::scope(|scope| {
+        for replica in replicas {
+            let job_rx = &job_rx;
+            let result_tx = result_tx.clone();
+            scope.spawn(move || loop {
+                // 受信の間だけロックし、分類は並列に行う
+                let job = job_rx.lock().unwrap().recv();
+                let Ok((seq, frame_num, frame_img)) = job else {
+                    break;
+                };
+                let result = classify_frame_with_count(&replica, &frame_img, region, frame_count);
+                if result_tx.send((seq, (frame_num, Some(result)))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut pending: BTreeMap<u64, PendingResult> = BTreeMap::new();
+        let mut next_seq = 0u64;
+        let mut sent = 0u64;
+
+        let crop_region = frame_count.map_or_else(|| region.clone(), |column| column.crop_region(region));
+        let decoded = extractor.process_frames_sync_in_range(video_path, Some(crop_region), range, |frame_img, frame_num| {
+            if frames.accept(frame_img, frame_num)? {
+                // ワーカーが追いつかない間はここで待つ（デコード済みフレームを溜め込まない）
+                job_tx
+                    .send((sent, frame_num, frame_img.clone()))
+                    .map_err(|_| anyhow::anyhow!("推論ワーカーが停止しました"))?;
+            } else {
+                pending.insert(sent, (frame_num, None));
+            }
+            sent += 1;
+
+            pending.extend(result_rx.try_iter());
+            apply_in_order(&mut pending, &mut next_seq, record)
+        });
+
+        // 残りの結果を受け取る（キューが空になるとワーカーが終了してチャネルが閉じる）
+        drop(job_tx);
+        pending.extend(result_rx.iter());
+        decoded?;
+        apply_in_order(&mut pending, &mut next_seq, record)
+    })
+}
+
+// ワーカーから届いた結果（フレーム番号, 分類結果。重複フレームは None）
+#[cfg(feature = "ml")]
+type PendingResult = (u32, Option<Result<FrameClassification>>);
+
+// 次に渡すべき番号から順に、届いている結果を record に渡す
+#[cfg(feature = "ml")]
+fn apply_in_order<R>(
+    pending: &mut BTreeMap<u64, PendingResult>,
+    next_seq: &mut u64,
+    record: &mut R,
+) -> Result<()>
+where
+    R: FnMut(u32, Option<FrameClassification>),
+{
+    while let Some((frame_num, result)) = pending.remove(next_seq) {
+        record(frame_num, result.transpose()?);
+        *next_seq += 1;
+    }
+    Ok(())
+}
+
+/// 動画から入力履歴を抽出してCSVに保存
+///
+/// 領域設定はモデルのメタデータ（engine.config()）を使用する。
+/// 抽出したステップ列には post_process（無入力区間の短縮・リリース挿入）を適用してから書き出す。
+/// ボタン列は columns（analyzer::output_columns_for で設定から決めたもの）の順・列名で書き出す。
+/// 設定で重複フレームの検出が有効な場合、直前と完全に同じ画像のフレームは分類せず、直前のステップの持続フレーム数に加える。
+/// CPU バックエンドではデコードと分類を別スレッドで並列に行う（結果はフレーム順に集計する）。
+/// 設定で数字のテンプレートが指定されている場合は継続フレーム数の列も読み取り、持続フレーム数と照合する。
+/// 設定で複数行解析が有効な場合は、間引いたキーフレームで全行を読み取る（history_rows モジュール）。
+/// range を指定した場合はその区間だけを解析する。
+/// on_frame は処理したフレーム番号（動画の先頭から数えた0始まりの番号）ごとに呼ばれ、
+/// エラーを返すと抽出を中断する（キャンセル用。CSVは書き出さない）。
+/// on_classified は分類したフレームごとにフレーム番号の順で呼ばれる（分類を省略した重複フレームを除く）。
+/// 戻り値は処理した最後のフレーム番号 + 1。
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+pub fn extract_input_history_to_csv<F, G>(
+    engine: &InferenceEngine,
+    video_path: &Path,
+    output_csv_path: &Path,
+    columns: &[OutputColumn],
+    post_process: &StepPostProcess,
+    range: TimeRange,
+    mut on_frame: F,
+    mut on_classified: G,
+) -> Result<u32>
+where
+    F: FnMut(u32) -> Result<()>,
+    G: FnMut(u32, &FrameClassification),
+{
+    let config = engine.config();
+
+    // メタデータの値をデバッグ出力
+    println!("[InputHistory] モデルメタデータ:");
+    println!("  tile_x: {}, tile_y: {}", config.tile_x, config.tile_y);
+    println!("  tile_width: {}, tile_height: {} (領域全体)", config.tile_width, config.tile_height);
+    println!("  image_width: {}, image_height: {} (個々のタイル)", config.image_width, config.image_height);
+    println!("  columns_per_row: {}", config.columns_per_row);
+    println!("  button_labels: {:?}", config.button_labels);
+
+    let region = indicator_region(config);
+    let app_config = crate::model::AppConfig::load_or_default();
+
+    println!("[InputHistory] InputIndicatorRegion: x={}, y={}, width={}, height={}, rows={}, cols={}",
+        region.x, region.y, region.width, region.height, region.rows, region.cols);
+
+    let frame_count = FrameCountColumn::from_config(&app_config.generation, &region);
+
+    // 複数行解析: キーフレームで全行を読み、毎フレームの分類はしない
+    if let Some(history_rows) = HistoryRowsSettings::from_config(&app_config.generation) {
+        let (steps, total_frames) = extract_steps_from_history_rows(
+            engine,
+            video_path,
+            app_config.button_tile.video_transform,
+            &region,
+            frame_count.as_ref(),
+            &history_rows,
+            range,
+            on_frame,
+            on_classified,
+        )
+        .context("フレーム処理エラー")?;
+        write_steps_csv(output_csv_path, columns, &post_process.apply(steps))?;
+        return Ok(total_frames);
+    }
+
+    // 一時ディレクトリ（システムのtempディレクトリを使用してViteの監視範囲外に配置）
+    let temp_dir = std::env::temp_dir().join("input_player_input_extraction");
+    fs::create_dir_all(&temp_dir).context("一時ディレクトリ作成エラー")?;
+    let tile_dir = temp_dir.join("tiles");
+    fs::create_dir_all(&tile_dir).ok();
+
+    let crop_region = frame_count.as_ref().map_or_else(|| region.clone(), |column| column.crop_region(&region));
+
+    // ステップはメモリ上でバッファしてから一括書き込みする
+    let mut builder = StepBuilder::new(frame_count.as_ref());
+    let mut total_frames = 0u32;
+
+    // フレーム抽出設定
+    let frame_config = FrameExtractorConfig {
+        frame_interval: 1, // 全フレーム
+        output_dir: temp_dir.clone(),
+        image_format: "png".to_string(),
+        jpeg_quality: 95,
+        scale_width: None,
+        scale_height: None,
+        transform: app_config.button_tile.video_transform,
+        image_sequence_fps: crate::video::DEFAULT_IMAGE_SEQUENCE_FPS,
+    };
+
+    let extractor = FrameExtractor::new(frame_config);
+    let mut duplicate_filter = DuplicateFrameFilter::from_config(&app_config);
+
+    let mut frames = FrameFeed {
+        duplicate_filter: &mut duplicate_filter,
+        total_frames: &mut total_frames,
+        on_frame: &mut on_frame,
+    };
+    let mut record = |frame_num: u32, classification: Option<FrameClassification>| match classification {
+        Some(classification) => {
+            on_classified(frame_num, &classification);
+            builder.push(classification);
+        }
+        None => builder.repeat(),
+    };
+    let workers = classification_workers(engine);
+    let result = if workers > 0 {
+        // デコードと推論を並列化: このスレッドでデコードし、ワーカーが分類する
+        println!("[InputHistory] 並列分類: ワーカー {}スレッド", workers);
+        classify_frames_parallel(
+            engine,
+            workers,
+            &extractor,
+            video_path,
+            &region,
+            frame_count.as_ref(),
+            range,
+            &mut frames,
+            &mut record,
+        )
+    } else {
+        // 同期処理: フレーム抽出とタイル推論を同じスレッド内で実行
+        // 事前に領域全体を videocrop で切り出してから AppSink で処理する
+        extractor.process_frames_sync_in_range(video_path, Some(crop_region), range, |frame_img, frame_num| {
+            let classification = if frames.accept(frame_img, frame_num)? {
+                Some(classify_frame_with_count(engine, frame_img, &region, frame_count.as_ref())?)
+            } else {
+                None
+            };
+            record(frame_num, classification);
+            Ok(())
+        })
+    };
+    if let Err(e) = result {
+        fs::remove_dir_all(&temp_dir).ok();
+        return Err(e.context("フレーム処理エラー"));
+    }
+
+    if let Some(filter) = &duplicate_filter {
+        println!("[InputHistory] 重複フレーム（分類を省略）: {}フレーム", filter.duplicates());
+    }
+
+    // 最後の状態をバッファに追加
+    let steps = builder.finish();
+    let steps = post_process.apply(steps);
+
+    // 一時ディレクトリを削除
+    fs::remove_dir_all(&temp_dir).ok();
+
+    write_steps_csv(output_csv_path, columns, &steps)?;
+
+    Ok(total_frames)
+}
+
+/// 領域ごとの出力CSVのパス（base の拡張子の前に _ラベル を付ける。例: out.csv → out_P1.csv）
+#[cfg(feature = "ml")]
+pub fn region_csv_path(base: &Path, label: &str) -> PathBuf {
+    let stem = base.file_stem().and_then(|s| s.to_str()).unwrap_or("input_history");
+    let extension = base.extension().and_then(|s| s.to_str()).unwrap_or("csv");
+    base.with_file_name(format!("{}_{}.{}", stem, label, extension))
+}
+
+/// 複数の領域（P1 / P2 など）の入力履歴を1回のデコードで抽出し、領域ごとにCSVを保存する
+///
+/// 全領域を囲む範囲を補正前の画像から切り出してデコードし、フレームごとに各領域を切り出して
+/// 領域ごとの回転・反転の補正を適用してから分類する（領域ごとに補正が異なっていてもよい）。
+/// 出力先は region_csv_path(output_csv_path, ラベル)。ボタン列は全領域で共通の columns で書き出す。
+/// 重複フレームの読み飛ばしは全領域で共通に判定する。
+/// 並列分類・継続フレーム数の読み取り・複数行解析には対応せず、各領域の最下行を毎フレーム解析する。
+/// 戻り値は（処理した最後のフレーム番号 + 1, 領域の順に並べた出力CSVのパス）。
+#[cfg(feature = "ml")]
+pub fn extract_regions_to_csv<F>(
+    engine: &InferenceEngine,
+    video_path: &Path,
+    regions: &[LabeledRegion],
+    output_csv_path: &Path,
+    columns: &[OutputColumn],
+    post_process: &StepPostProcess,
+    range: TimeRange,
+    mut on_frame: F,
+) -> Result<(u32, Vec<PathBuf>)>
+where
+    F: FnMut(u32) -> Result<()>,
+{
+    // 補正前の動画上の座標に変換してまとめて切り出す
+    let info = FrameExtractor::get_video_info(video_path, VideoTransform::None)?;
+    let source_regions: Vec<InputIndicatorRegion> = regions
+        .iter()
+        .map(|r| crate::video::untransformed_region(r.transform, info.width as u32, info.height as u32, &r.region))
+        .collect();
+    let bounds = InputIndicatorRegion::bounding(&source_regions)
+        .ok_or_else(|| anyhow::anyhow!("解析領域が指定されていません"))?;
+    for (i, labeled) in regions.iter().enumerate() {
+        if regions[..i].iter().any(|other| other.label == labeled.label) {
+            anyhow::bail!("解析領域のラベルが重複しています: {}", labeled.label);
+        }
+        let r = &labeled.region;
+        let (width, height) = if labeled.transform.swaps_dimensions() {
+            (info.height as u32, info.width as u32)
+        } else {
+            (info.width as u32, info.height as u32)
+        };
+        if r.x + r.width > width || r.y + r.height > height {
+            anyhow::bail!("領域 {} が動画の範囲外です（動画 {}x{}）", labeled.label, width, height);
+        }
+        println!("[InputHistory] 領域 {}: x={}, y={}, width={}, height={}, cols={}, 補正={:?}",
+            labeled.label, r.x, r.y, r.width, r.height, r.cols, labeled.transform);
+    }
+
+    let app_config = crate::model::AppConfig::load_or_default();
+    let extractor = FrameExtractor::new(FrameExtractorConfig::default());
+    let mut duplicate_filter = DuplicateFrameFilter::from_config(&app_config);
+    let mut builders: Vec<StepBuilder> = regions.iter().map(|_| StepBuilder::default()).collect();
+    let mut total_frames = 0u32;
+
+    extractor
+        .process_frames_sync_in_range(video_path, Some(bounds.clone()), range, |frame_img, frame_num| {
+            total_frames = frame_num + 1;
+            on_frame(frame_num)?;
+            if duplicate_filter.as_mut().is_some_and(|filter| filter.is_duplicate(frame_img)) {
+                builders.iter_mut().for_each(StepBuilder::repeat);
+                return Ok(());
+            }
+
+            let stride = frame_img.width() as usize * 3;
+            for ((labeled, source), builder) in regions.iter().zip(&source_regions).zip(builders.iter_mut()) {
+                let region_img = crate::video::crop_rgb_rows(frame_img.as_raw(), stride, source.x - bounds.x, source.y - bounds.y, source.width, source.height)
+                    .ok_or_else(|| anyhow::anyhow!("領域 {} を切り出せません", labeled.label))?;
+                let region_img = crate::video::transform_image(labeled.transform, region_img);
+                builder.push(classify_frame(engine, &region_img, &labeled.region)?);
+            }
+            Ok(())
+        })
+        .context("フレーム処理エラー")?;
+
+    if let Some(filter) = &duplicate_filter {
+        println!("[InputHistory] 重複フレーム（分類を省略）: {}フレーム", filter.duplicates());
+    }
+
+    let mut outputs = Vec::with_capacity(regions.len());
+    for (labeled, builder) in regions.iter().zip(builders) {
+        let steps = post_process.apply(builder.finish());
+        let path = region_csv_path(output_csv_path, &labeled.label);
+        println!("[InputHistory] 領域 {}: {}ステップ → {:?}", labeled.label, steps.len(), path);
+        write_steps_csv(&path, columns, &steps)?;
+        outputs.push(path);
+    }
+    Ok((total_frames, outputs))
+}
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+
+    fn state(direction: u8) -> InputState {
+        InputState { direction, ..InputState::new() }
+    }
+
+    #[test]
+    fn test_results_are_applied_in_frame_order() {
+        let classified =
+            |direction: u8| FrameClassification { state: state(direction), confidences: vec![1.0], displayed_frames: None };
+        let mut builder = StepBuilder::default();
+        let mut frames = Vec::new();
+        let mut record = |frame_num: u32, classification: FrameClassification| {
+            frames.push(frame_num);
+            builder.push(classification);
+        };
+        let mut pending = BTreeMap::new();
+        let mut next_seq = 0u64;
+
+        // ワーカーから順不同で届いた結果は、欠けている番号が届くまで保留する
+        pending.insert(1, (11, Ok(classified(6))));
+        pending.insert(2, (12, Ok(classified(6))));
+        apply_in_order(&mut pending, &mut next_seq, &mut record).unwrap();
+        assert_eq!(next_seq, 0);
+
+        pending.insert(0, (10, Ok(classified(5))));
+        pending.insert(3, (14, Ok(classified(2))));
+        apply_in_order(&mut pending, &mut next_seq, &mut record).unwrap();
+        assert_eq!(next_seq, 4);
+        assert_eq!(frames, vec![10, 11, 12, 14]);
+
+        let durations: Vec<(u8, u32)> = builder.finish().iter().map(|(s, d)| (s.direction, *d)).collect();
+        assert_eq!(durations, vec![(5, 1), (6, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn test_region_outputs_share_one_crop() {
+        let region = |x: u32, y: u32| InputIndicatorRegion { x, y, width: 288, height: 48, rows: 1, cols: 6 };
+        let bounds = InputIndicatorRegion::bounding(&[region(204, 902), region(1400, 890)]).unwrap();
+        assert_eq!((bounds.x, bounds.y, bounds.width, bounds.height), (204, 890, 1484, 60));
+
+        assert_eq!(region_csv_path(Path::new("out/match.csv"), "P2"), Path::new("out/match_P2.csv"));
+    }
+
+    #[test]
+    fn test_displayed_frame_count_replaces_counted_duration() {
+        let check = |mode: FrameCountMode| FrameCountCheck {
+            mode,
+            display_max: 99,
+            matched: 0,
+            mismatched: 0,
+            unreadable: 0,
+        };
+        let mut builder = StepBuilder { frame_count: Some(check(FrameCountMode::Recognized)), ..StepBuilder::default() };
+        let frame = |direction: u8, displayed: Option<u32>| FrameClassification {
+            state: state(direction),
+            confidences: vec![1.0],
+            displayed_frames: displayed,
+        };
+
+        // 録画で1フレーム落ちたステップ（表示は 1,2,4）
+        builder.push(frame(6, Some(1)));
+        builder.push(frame(6, Some(2)));
+        assert_eq!(builder.push(frame(6, Some(4))), None);
+        // 読み取れなかったステップは数えたフレーム数を使う
+        assert_eq!(builder.push(frame(2, None)), Some(4));
+        builder.push(frame(2, None));
+        // 上限に達した表示は使わない
+        builder.push(frame(5, Some(99)));
+
+        let durations: Vec<(u8, u32)> = builder.finish().iter().map(|(s, d)| (s.direction, *d)).collect();
+        assert_eq!(durations, vec![(6, 4), (2, 2), (5, 1)]);
+
+        // 照合のみの場合は数えたフレーム数を使い、不一致を数える
+        let mut cross_check = check(FrameCountMode::CrossCheck);
+        assert_eq!(cross_check.resolve(0, 3, Some(4)), 3);
+        assert_eq!(cross_check.resolve(1, 2, Some(2)), 2);
+        assert_eq!((cross_check.matched, cross_check.mismatched), (1, 1));
+    }
+}
@@ -0,0 +1,57 @@
+//! ライブ入力からの入力認識
+//!
+//! キャプチャした映像を動画と同じ手順（入力表示の切り出し→タイル分割→分類）で解析し、
+//! 入力状態が変化するたびに通知する。
+
+#[cfg(feature = "ml")]
+use anyhow::Result;
+
+#[cfg(feature = "ml")]
+use super::input_history::{classify_input_state, indicator_region};
+#[cfg(feature = "ml")]
+use crate::analyzer::InputState;
+#[cfg(feature = "ml")]
+use crate::jobs::CancellationToken;
+#[cfg(feature = "ml")]
+use crate::ml::InferenceEngine;
+#[cfg(feature = "ml")]
+use crate::video::{process_live_frames, LiveSource};
+
+/// ライブ入力をキャンセルされるまで解析する
+///
+/// 領域設定はモデルのメタデータ（engine.config()）を使用する。
+/// on_change は入力状態が変化したフレームごとに、フレーム番号・新しい入力状態・
+/// 直前の入力状態の持続フレーム数（最初のフレームは 0）を受け取る。
+/// フレーム番号はキャプチャした時刻から求めるため、解析が追いつかずに捨てたフレームも持続フレーム数に含まれる。
+/// 戻り値はキャプチャしたフレーム数。
+#[cfg(feature = "ml")]
+pub fn run_live_analysis<F>(
+    engine: &InferenceEngine,
+    source: &LiveSource,
+    token: &CancellationToken,
+    mut on_change: F,
+) -> Result<u32>
+where
+    F: FnMut(u32, &InputState, u32) -> Result<()>,
+{
+    let region = indicator_region(engine.config());
+    println!("[Live] InputIndicatorRegion: x={}, y={}, width={}, height={}",
+        region.x, region.y, region.width, region.height);
+
+    let mut previous_state: Option<InputState> = None;
+    let mut changed_at = 0u32;
+
+    process_live_frames(source, Some(&region), token, |frame_img, frame_num| {
+        let current_state = classify_input_state(engine, frame_img, &region)?;
+
+        if previous_state.as_ref() == Some(&current_state) {
+            return Ok(());
+        }
+
+        let duration = if previous_state.is_some() { frame_num - changed_at } else { 0 };
+        on_change(frame_num, &current_state, duration)?;
+        previous_state = Some(current_state);
+        changed_at = frame_num;
+        Ok(())
+    })
+}
@@ -12,9 +12,12 @@ use burn::{
         pool::{MaxPool2d, MaxPool2dConfig},
         Linear, LinearConfig, Relu,
     },
+    record::{DefaultFileRecorder, FullPrecisionSettings, HalfPrecisionSettings, Recorder},
     tensor::{backend::Backend, Int, Tensor},
     train::ClassificationOutput,
 };
+#[cfg(feature = "ml")]
+use crate::model::{ModelArchitecture, WeightPrecision};
 
 /// クラス数
 pub const NUM_CLASSES: usize = 14;
@@ -43,43 +46,59 @@ pub struct ModelConfig {
     /// ドロップアウト率
     #[config(default = 0.5)]
     pub dropout: f64,
-    /// 入力画像サイズ（正方形）
+    /// 入力画像の幅
     #[config(default = 48)]
     pub image_size: usize,
+    /// 入力画像の高さ（0 の場合は image_size と同じ正方形）
+    #[config(default = 0)]
+    pub image_height: usize,
+    /// モデルの構造
+    #[config(default = "ModelArchitecture::Standard")]
+    pub architecture: ModelArchitecture,
+}
+
+// 3層の畳み込み・2回のプーリング後の特徴マップの長さ（1辺ごと）
+//
+// Conv1 (3x3, no padding): size -> size - 2
+// Pool1 (2x2): (size - 2) -> (size - 2) / 2  (切り捨て)
+// Conv2 (3x3, no padding): ((size - 2) / 2) -> ((size - 2) / 2) - 2
+// Pool2 (2x2): (((size - 2) / 2) - 2) -> (((size - 2) / 2) - 2) / 2
+// Conv3 (3x3, no padding): final_size -> final_size - 2
+#[cfg(feature = "ml")]
+fn feature_map_len(size: usize) -> usize {
+    let after_pool1 = size.saturating_sub(2) / 2;
+    let after_pool2 = after_pool1.saturating_sub(2) / 2;
+    after_pool2.saturating_sub(2)
 }
 
 #[cfg(feature = "ml")]
 impl ModelConfig {
+    /// 入力画像の（幅, 高さ）
+    pub fn input_dims(&self) -> (usize, usize) {
+        match self.image_height {
+            0 => (self.image_size, self.image_size),
+            height => (self.image_size, height),
+        }
+    }
+
     /// モデルを初期化
     pub fn init<B: Backend>(&self, device: &B::Device) -> IconClassifier<B> {
-        // サイズ計算:
-        // Conv1 (3x3, no padding): size -> size - 2
-        // Pool1 (2x2): (size - 2) -> (size - 2) / 2  (切り捨て)
-        // Conv2 (3x3, no padding): ((size - 2) / 2) -> ((size - 2) / 2) - 2
-        // Pool2 (2x2): (((size - 2) / 2) - 2) -> (((size - 2) / 2) - 2) / 2
-        // Conv3 (3x3, no padding): final_size -> final_size - 2
-        
-        let after_conv1 = self.image_size.saturating_sub(2);
-        let after_pool1 = after_conv1 / 2;
-        let after_conv2 = after_pool1.saturating_sub(2);
-        let after_pool2 = after_conv2 / 2;
-        let feature_map_size = after_pool2.saturating_sub(2);
+        let (width, height) = self.input_dims();
+        let (feature_width, feature_height) = (feature_map_len(width), feature_map_len(height));
         
-        if feature_map_size == 0 {
-            panic!("入力サイズが小さすぎます: {} (最小14x14が必要)", self.image_size);
+        if feature_width == 0 || feature_height == 0 {
+            panic!("入力サイズが小さすぎます: {}x{} (幅・高さとも最小14が必要)", width, height);
         }
         
-        // 特徴次元 d = 128チャネル * feature_map_size * feature_map_size
-        let d = 128 * feature_map_size * feature_map_size;
+        // 特徴次元 d = 128チャネル * feature_height * feature_width（Compact はチャネルごとの平均なので 128）
+        let global_pool = self.architecture == ModelArchitecture::Compact;
+        let d = if global_pool { 128 } else { 128 * feature_width * feature_height };
         let d_half = d / 2;
         
-        println!("[Model] 入力サイズ: {}x{}", self.image_size, self.image_size);
-        println!("[Model] Conv1後: {}x{}", after_conv1, after_conv1);
-        println!("[Model] Pool1後: {}x{}", after_pool1, after_pool1);
-        println!("[Model] Conv2後: {}x{}", after_conv2, after_conv2);
-        println!("[Model] Pool2後: {}x{}", after_pool2, after_pool2);
-        println!("[Model] Conv3後: 128 x {}x{}", feature_map_size, feature_map_size);
-        println!("[Model] Flatten後の特徴次元 d: {}", d);
+        println!("[Model] 構造: {:?}", self.architecture);
+        println!("[Model] 入力サイズ: {}x{}", width, height);
+        println!("[Model] Conv3後: 128 x {}x{}", feature_width, feature_height);
+        println!("[Model] {}後の特徴次元 d: {}", if global_pool { "Global Average Pooling" } else { "Flatten" }, d);
         println!("[Model] FC1: {} -> {}", d, d_half);
         println!("[Model] FC2: {} -> {}", d_half, self.num_classes);
         
@@ -106,6 +125,7 @@ impl ModelConfig {
             fc2: LinearConfig::new(d_half, self.num_classes).init(device),
             
             activation: Relu::new(),
+            global_pool,
         }
     }
 }
@@ -116,15 +136,14 @@ impl ModelConfig {
 ///
 /// # アーキテクチャ
 /// - {Conv 3x3 (no padding, stride 1) + ReLU} x 3層
-/// - Flatten
+/// - Flatten（Compact はチャネルごとの平均 = Global Average Pooling）
 /// - FC: d -> d/2 + ReLU
 /// - FC: d/2 -> num_classes
 /// - Softmax (分類時)
 ///
 /// # サイズ計算
-/// - padding無し3x3カーネルで、1層でサイズが2減少
-/// - 3層後: (size - 6) x (size - 6)
-/// - 特徴次元 d = 128 * (size - 6) * (size - 6)
+/// - padding無し3x3カーネルで1層ごとに2減少し、2x2プーリングで半分になる（幅と高さを別々に計算する）
+/// - 特徴次元 d = 128 * (特徴マップの幅) * (特徴マップの高さ)、Compact は d = 128
 #[cfg(feature = "ml")]
 #[derive(Module, Debug)]
 pub struct IconClassifier<B: Backend> {
@@ -140,6 +159,9 @@ pub struct IconClassifier<B: Backend> {
     fc2: Linear<B>,    // d/2 -> num_classes
 
     activation: Relu,
+
+    // Global Average Pooling を使うか（ModelArchitecture::Compact。重みを持たないため従来のモデルもそのまま読み込める）
+    global_pool: bool,
 }
 
 #[cfg(feature = "ml")]
@@ -147,7 +169,7 @@ impl<B: Backend> IconClassifier<B> {
     /// 順伝播
     ///
     /// # 引数
-    /// - `images`: バッチ画像 [batch_size, 3, size, size]
+    /// - `images`: バッチ画像 [batch_size, 3, height, width]
     ///
     /// # 戻り値
     /// - クラスごとのロジット [batch_size, num_classes]
@@ -168,7 +190,12 @@ impl<B: Backend> IconClassifier<B> {
         let x = self.conv3.forward(x);
         let x = self.activation.forward(x);
 
-        // Flatten
+        // Flatten（Compact は特徴マップの平均を取ってから）
+        let x = if self.global_pool {
+            x.mean_dim(3).mean_dim(2)
+        } else {
+            x
+        };
         let [_, c, h, w] = x.dims();
         let x = x.reshape([batch_size, c * h * w]);
 
@@ -185,7 +212,7 @@ impl<B: Backend> IconClassifier<B> {
     /// 予測を実行
     ///
     /// # 引数
-    /// - `images`: バッチ画像 [batch_size, 3, size, size]
+    /// - `images`: バッチ画像 [batch_size, 3, height, width]
     ///
     /// # 戻り値
     /// - (予測クラスID, ロジット)
@@ -215,6 +242,34 @@ impl<B: Backend> IconClassifier<B> {
 
         ClassificationOutput::new(loss, output, targets)
     }
+
+    /// 保存済みの重み（model.bin の内容）を読み込む
+    ///
+    /// precision はメタデータの weight_precision。16bitで保存した重みも32bitに戻して読み込む。
+    pub fn load_weights(self, model_binary: &[u8], precision: WeightPrecision, device: &B::Device) -> anyhow::Result<Self> {
+        // 一時ファイルに書き出してDefaultFileRecorderで読み込む
+        static LOAD_COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let load_id = LOAD_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let temp_model_path = std::env::temp_dir().join(format!("model_{}_{}.mpk", std::process::id(), load_id));
+        std::fs::write(&temp_model_path, model_binary)?;
+
+        let record = match precision {
+            WeightPrecision::F32 => DefaultFileRecorder::<FullPrecisionSettings>::new().load(temp_model_path.clone(), device),
+            WeightPrecision::F16 => DefaultFileRecorder::<HalfPrecisionSettings>::new().load(temp_model_path.clone(), device),
+        };
+        let _ = std::fs::remove_file(&temp_model_path);
+        let record = record.map_err(|e| anyhow::anyhow!("モデル重みの読み込みエラー: {:?}", e))?;
+        Ok(self.load_record(record))
+    }
+
+    /// 重みを指定した精度で保存する（path に拡張子 .mpk が付いたファイルができる）
+    pub fn save_weights(self, path: &std::path::Path, precision: WeightPrecision) -> anyhow::Result<()> {
+        match precision {
+            WeightPrecision::F32 => self.save_file(path, &DefaultFileRecorder::<FullPrecisionSettings>::new())?,
+            WeightPrecision::F16 => self.save_file(path, &DefaultFileRecorder::<HalfPrecisionSettings>::new())?,
+        }
+        Ok(())
+    }
 }
 
 /// 画像を読み込んで正規化（サイズ指定版）
@@ -229,21 +284,31 @@ impl<B: Backend> IconClassifier<B> {
 /// - 正規化されたRGB画像データ (C, H, W) の順で平坦化
 #[cfg(feature = "ml")]
 pub fn load_and_normalize_image_with_size(path: &std::path::Path, expected_size: usize) -> anyhow::Result<Vec<f32>> {
+    load_and_normalize_image_with_dims(path, expected_size, expected_size)
+}
+
+/// 画像を読み込んで正規化（幅・高さを別々に指定する版）
+#[cfg(feature = "ml")]
+pub fn load_and_normalize_image_with_dims(
+    path: &std::path::Path,
+    expected_width: usize,
+    expected_height: usize,
+) -> anyhow::Result<Vec<f32>> {
     let img = image::open(path)?;
     let img = img.to_rgb8();
     let (width, height) = img.dimensions();
 
-    if width != expected_size as u32 || height != expected_size as u32 {
+    if width != expected_width as u32 || height != expected_height as u32 {
         anyhow::bail!(
             "画像サイズが不正です: {}x{} (期待: {}x{})",
             width,
             height,
-            expected_size,
-            expected_size
+            expected_width,
+            expected_height
         );
     }
 
-    let mut data = Vec::with_capacity(3 * expected_size * expected_size);
+    let mut data = Vec::with_capacity(3 * expected_width * expected_height);
 
     // ImageNetの平均と標準偏差で正規化
     let mean = [0.485, 0.456, 0.406];
@@ -270,3 +335,46 @@ pub fn load_and_normalize_image_with_size(path: &std::path::Path, expected_size:
 pub fn load_and_normalize_image(path: &std::path::Path) -> anyhow::Result<Vec<f32>> {
     load_and_normalize_image_with_size(path, IMAGE_SIZE)
 }
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+    use burn_ndarray::{NdArray, NdArrayDevice};
+
+    #[test]
+    fn test_rectangular_input() {
+        let config = ModelConfig {
+            num_classes: 5,
+            dropout: 0.0,
+            image_size: 64,
+            image_height: 32,
+            architecture: ModelArchitecture::Standard,
+        };
+        assert_eq!(config.input_dims(), (64, 32));
+        // 64 → 62 → 31 → 29 → 14 → 12, 32 → 30 → 15 → 13 → 6 → 4
+        assert_eq!((feature_map_len(64), feature_map_len(32)), (12, 4));
+
+        let device = NdArrayDevice::Cpu;
+        let model = config.init::<NdArray>(&device);
+        let images = Tensor::<NdArray, 4>::zeros([2, 3, 32, 64], &device);
+        assert_eq!(model.forward(images).dims(), [2, 5]);
+    }
+
+    #[test]
+    fn test_compact_architecture() {
+        let device = NdArrayDevice::Cpu;
+        let config = |architecture| ModelConfig {
+            num_classes: 14,
+            dropout: 0.0,
+            image_size: 48,
+            image_height: 48,
+            architecture,
+        };
+        let standard = config(ModelArchitecture::Standard).init::<NdArray>(&device);
+        let compact = config(ModelArchitecture::Compact).init::<NdArray>(&device);
+        assert!(compact.num_params() * 100 < standard.num_params());
+
+        let images = Tensor::<NdArray, 4>::zeros([3, 3, 48, 48], &device);
+        assert_eq!(compact.forward(images).dims(), [3, 14]);
+    }
+}
@@ -1,7 +1,18 @@
 pub mod ml_model;
 pub mod training;
 pub mod inference;
+pub mod engine_cache;
+pub mod input_history;
+pub mod history_rows;
+pub mod overlay_render;
+pub mod evaluation;
+pub mod live_analysis;
 
-pub use ml_model::{IconClassifier, ModelConfig, NUM_CLASSES, IMAGE_SIZE, CLASS_NAMES, BUTTON_LABELS, load_and_normalize_image, load_and_normalize_image_with_size};
-pub use training::{TileDataset, train_model, classify_tiles};
+pub use ml_model::{IconClassifier, ModelConfig, NUM_CLASSES, IMAGE_SIZE, CLASS_NAMES, BUTTON_LABELS, load_and_normalize_image, load_and_normalize_image_with_size, load_and_normalize_image_with_dims};
+pub use training::{TileDataset, TileTransfer, train_model, classify_tiles};
 pub use inference::InferenceEngine;
+pub use engine_cache::{CachedEngine, EngineCache};
+pub use input_history::{extract_input_history_to_csv, extract_regions_to_csv, FrameClassification, FrameCountColumn};
+pub use live_analysis::run_live_analysis;
+pub use overlay_render::render_overlay;
+pub use evaluation::{evaluate_model, EvaluationReport};
@@ -0,0 +1,504 @@
+//! 抽出結果を確認するための動画の書き出し
+//!
+//! 解析した動画の各フレームに、タイルごとの分類結果と復元した入力（例: `6A+B`）を描き込んで書き出す。
+//! どのフレーム・どのタイルで分類を誤っているかを目で追うためのもの。
+//! 出力先の拡張子が .mp4 の場合は GStreamer（x264enc）で動画に、それ以外はフォルダに連番PNGで保存する。
+//! MP4 の書き出しには gstreamer feature が必要。
+
+#[cfg(feature = "ml")]
+use anyhow::{Context, Result};
+#[cfg(all(feature = "ml", feature = "gstreamer"))]
+use gstreamer as gst;
+#[cfg(all(feature = "ml", feature = "gstreamer"))]
+use gstreamer::prelude::*;
+#[cfg(feature = "ml")]
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "ml")]
+use crate::analyzer::{extract_tiles_from_image, update_input_state, InputIndicatorRegion, InputState};
+#[cfg(feature = "ml")]
+use crate::ml::input_history::indicator_region;
+#[cfg(feature = "ml")]
+use crate::ml::InferenceEngine;
+#[cfg(feature = "ml")]
+use crate::video::{FrameExtractor, FrameExtractorConfig, TimeRange};
+
+/// 確信度がこれ未満のタイルは赤枠で表示する
+#[cfg(feature = "ml")]
+const LOW_CONFIDENCE: f32 = 0.6;
+
+/// 文字の拡大率（3x5 のドット絵を何倍で描くか）
+#[cfg(feature = "ml")]
+const TEXT_SCALE: u32 = 2;
+
+#[cfg(feature = "ml")]
+const GLYPH_WIDTH: u32 = 3;
+#[cfg(feature = "ml")]
+const GLYPH_HEIGHT: u32 = 5;
+
+/// 文字の周りの余白
+#[cfg(feature = "ml")]
+const PADDING: u32 = 2;
+
+#[cfg(feature = "ml")]
+const DIRECTION_COLOR: image::Rgb<u8> = image::Rgb([80, 160, 255]);
+#[cfg(feature = "ml")]
+const BUTTON_COLOR: image::Rgb<u8> = image::Rgb([80, 220, 80]);
+#[cfg(feature = "ml")]
+const EMPTY_COLOR: image::Rgb<u8> = image::Rgb([128, 128, 128]);
+#[cfg(feature = "ml")]
+const LOW_CONFIDENCE_COLOR: image::Rgb<u8> = image::Rgb([255, 64, 64]);
+#[cfg(feature = "ml")]
+const TEXT_COLOR: image::Rgb<u8> = image::Rgb([255, 255, 255]);
+#[cfg(feature = "ml")]
+const BACKGROUND_COLOR: image::Rgb<u8> = image::Rgb([0, 0, 0]);
+
+/// 解析した動画に分類結果を描き込んで書き出す
+///
+/// crop_only を指定すると入力表示の領域だけを切り出し、上に復元した入力を表示する帯を付ける。
+/// 指定しない場合はフレーム全体に描き込み、左上に復元した入力を表示する。
+/// 戻り値は書き出したフレーム数。
+#[cfg(feature = "ml")]
+pub fn render_overlay<F>(
+    engine: &InferenceEngine,
+    video_path: &Path,
+    output_path: &Path,
+    crop_only: bool,
+    range: TimeRange,
+    mut on_frame: F,
+) -> Result<u32>
+where
+    F: FnMut(u32) -> Result<()>,
+{
+    let region = indicator_region(engine.config());
+    let button_labels = &engine.config().button_labels;
+    let transform = crate::model::AppConfig::load_or_default().button_tile.video_transform;
+    let fps = FrameExtractor::get_video_info(video_path, transform)?.fps;
+    println!("[Overlay] 領域: x={}, y={}, width={}, height={}, cols={}",
+        region.x, region.y, region.width, region.height, region.cols);
+
+    let extractor = FrameExtractor::new(FrameExtractorConfig {
+        transform,
+        ..FrameExtractorConfig::default()
+    });
+    let mut writer: Option<OverlayWriter> = None;
+    let mut written = 0u32;
+
+    extractor
+        .process_frames_sync_in_range(video_path, crop_only.then(|| region.clone()), range, |frame_img, frame_num| {
+            on_frame(frame_num)?;
+
+            let (mut canvas, tile_origin) = if crop_only {
+                let mut canvas = new_canvas(frame_img.width().max(caption_width(button_labels)), frame_img.height() + caption_height());
+                image::imageops::replace(&mut canvas, frame_img, 0, caption_height() as i64);
+                (canvas, (0, caption_height()))
+            } else {
+                let mut canvas = new_canvas(frame_img.width(), frame_img.height());
+                image::imageops::replace(&mut canvas, frame_img, 0, 0);
+                (canvas, (region.x, region.y))
+            };
+
+            let strip = if crop_only {
+                frame_img.clone()
+            } else {
+                image::imageops::crop_imm(frame_img, region.x, region.y, region.width, region.height).to_image()
+            };
+            let tiles = classify_tiles(engine, &strip, &region)?;
+            draw_tiles(&mut canvas, tile_origin, &region, &tiles);
+
+            let mut state = InputState::new();
+            for (class_name, _) in &tiles {
+                update_input_state(&mut state, class_name);
+            }
+            let caption = format!("{} {}", frame_num, state_label(&state, button_labels));
+            draw_label(&mut canvas, PADDING, PADDING, &caption, TEXT_COLOR);
+
+            if writer.is_none() {
+                writer = Some(OverlayWriter::open(output_path, canvas.width(), canvas.height(), fps)?);
+            }
+            writer.as_mut().expect("writer").write(&canvas)?;
+            written += 1;
+            Ok(())
+        })
+        .context("フレーム処理エラー")?;
+
+    match writer {
+        Some(writer) => writer.finish()?,
+        None => anyhow::bail!("書き出すフレームがありません"),
+    }
+    println!("[Overlay] {}フレームを書き出しました: {:?}", written, output_path);
+    Ok(written)
+}
+
+// 領域の画像をタイルに分けて分類する（タイルごとのクラス名と確信度、左の列から順）
+#[cfg(feature = "ml")]
+fn classify_tiles(
+    engine: &InferenceEngine,
+    strip: &image::RgbImage,
+    region: &InputIndicatorRegion,
+) -> Result<Vec<(String, f32)>> {
+    let cropped_region = InputIndicatorRegion { x: 0, y: 0, ..region.clone() };
+    let tiles = extract_tiles_from_image(strip, &cropped_region)
+        .map_err(|e| anyhow::anyhow!("タイル抽出エラー: {}", e))?;
+    engine.classify_batch_with_confidence(&tiles)
+}
+
+// 各タイルに分類結果の色の枠とラベルを描く
+#[cfg(feature = "ml")]
+fn draw_tiles(canvas: &mut image::RgbImage, origin: (u32, u32), region: &InputIndicatorRegion, tiles: &[(String, f32)]) {
+    let tile_width = region.width / region.cols.max(1);
+    for (col, (class_name, confidence)) in tiles.iter().enumerate() {
+        let x = origin.0 + col as u32 * tile_width;
+        let color = if *confidence < LOW_CONFIDENCE {
+            LOW_CONFIDENCE_COLOR
+        } else if class_name.starts_with("dir_") {
+            DIRECTION_COLOR
+        } else if class_name == "others" || class_name == "empty" {
+            EMPTY_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        draw_outline(canvas, x, origin.1, tile_width, region.height, color);
+        draw_label(canvas, x + 1, origin.1 + 1, tile_label(class_name), color);
+    }
+}
+
+/// タイルに表示する短いラベル（方向は数字のみ、ボタン以外は "-"）
+#[cfg(feature = "ml")]
+fn tile_label(class_name: &str) -> &str {
+    match class_name.strip_prefix("dir_") {
+        Some(direction) => direction,
+        None if class_name == "others" || class_name == "empty" => "-",
+        None => class_name,
+    }
+}
+
+/// 入力状態のテンキー表記（例: `6A+B`、ボタンは button_labels の順）
+#[cfg(feature = "ml")]
+fn state_label(state: &InputState, button_labels: &[String]) -> String {
+    let pressed: Vec<&str> = button_labels
+        .iter()
+        .filter(|label| state.buttons.get(*label).copied().unwrap_or(0) != 0)
+        .map(String::as_str)
+        .collect();
+    format!("{}{}", state.direction, pressed.join("+"))
+}
+
+// 帯の高さ（文字1行分）
+#[cfg(feature = "ml")]
+fn caption_height() -> u32 {
+    GLYPH_HEIGHT * TEXT_SCALE + PADDING * 2
+}
+
+// 全ボタンを押したときの表示が収まる幅
+#[cfg(feature = "ml")]
+fn caption_width(button_labels: &[String]) -> u32 {
+    let longest = format!("000000 5{}", button_labels.join("+"));
+    text_width(&longest) + PADDING * 2
+}
+
+// 動画のエンコード（4:2:0）のため幅・高さを偶数に揃えた黒い画像
+#[cfg(feature = "ml")]
+fn new_canvas(width: u32, height: u32) -> image::RgbImage {
+    image::RgbImage::from_pixel(width.max(2).next_multiple_of(2), height.max(2).next_multiple_of(2), BACKGROUND_COLOR)
+}
+
+#[cfg(feature = "ml")]
+fn fill_rect(img: &mut image::RgbImage, x: u32, y: u32, width: u32, height: u32, color: image::Rgb<u8>) {
+    for py in y..(y + height).min(img.height()) {
+        for px in x..(x + width).min(img.width()) {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+#[cfg(feature = "ml")]
+fn draw_outline(img: &mut image::RgbImage, x: u32, y: u32, width: u32, height: u32, color: image::Rgb<u8>) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    fill_rect(img, x, y, width, 1, color);
+    fill_rect(img, x, y + height - 1, width, 1, color);
+    fill_rect(img, x, y, 1, height, color);
+    fill_rect(img, x + width - 1, y, 1, height, color);
+}
+
+#[cfg(feature = "ml")]
+fn text_width(text: &str) -> u32 {
+    (text.chars().count() as u32 * (GLYPH_WIDTH + 1)).saturating_sub(1) * TEXT_SCALE
+}
+
+// 黒い背景の上に文字を描く
+#[cfg(feature = "ml")]
+fn draw_label(img: &mut image::RgbImage, x: u32, y: u32, text: &str, color: image::Rgb<u8>) {
+    fill_rect(img, x, y, text_width(text) + PADDING * 2, GLYPH_HEIGHT * TEXT_SCALE + PADDING * 2, BACKGROUND_COLOR);
+    draw_text(img, x + PADDING, y + PADDING, text, color);
+}
+
+#[cfg(feature = "ml")]
+fn draw_text(img: &mut image::RgbImage, x: u32, y: u32, text: &str, color: image::Rgb<u8>) {
+    for (i, c) in text.chars().enumerate() {
+        let left = x + i as u32 * (GLYPH_WIDTH + 1) * TEXT_SCALE;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (0b100 >> col) != 0 {
+                    fill_rect(img, left + col * TEXT_SCALE, y + row as u32 * TEXT_SCALE, TEXT_SCALE, TEXT_SCALE, color);
+                }
+            }
+        }
+    }
+}
+
+/// 3x5 のドット絵（各行の下位3ビットが左から右の画素。英字は大文字で描き、未対応の文字は `?`）
+#[cfg(feature = "ml")]
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ' ' => [0b000; 5],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// 書き出し先（MP4 または連番PNGのフォルダ）
+#[cfg(feature = "ml")]
+enum OverlayWriter {
+    Png { dir: PathBuf, frames: u32 },
+    #[cfg(feature = "gstreamer")]
+    Mp4(Mp4Writer),
+}
+
+#[cfg(feature = "ml")]
+impl OverlayWriter {
+    #[cfg_attr(not(feature = "gstreamer"), allow(unused_variables))]
+    fn open(output_path: &Path, width: u32, height: u32, fps: f64) -> Result<Self> {
+        let is_mp4 = output_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"));
+        if is_mp4 {
+            #[cfg(feature = "gstreamer")]
+            return Ok(OverlayWriter::Mp4(Mp4Writer::open(output_path, width, height, fps)?));
+            #[cfg(not(feature = "gstreamer"))]
+            anyhow::bail!("MP4 の書き出しには GStreamer が必要です（出力先にフォルダを指定すると連番PNGで保存します）");
+        }
+        std::fs::create_dir_all(output_path)
+            .with_context(|| format!("出力フォルダを作成できません: {:?}", output_path))?;
+        Ok(OverlayWriter::Png { dir: output_path.to_path_buf(), frames: 0 })
+    }
+
+    fn write(&mut self, img: &image::RgbImage) -> Result<()> {
+        match self {
+            OverlayWriter::Png { dir, frames } => {
+                let path = dir.join(format!("frame_{:06}.png", frames));
+                img.save(&path).with_context(|| format!("画像の保存に失敗: {:?}", path))?;
+                *frames += 1;
+                Ok(())
+            }
+            #[cfg(feature = "gstreamer")]
+            OverlayWriter::Mp4(writer) => writer.write(img),
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            OverlayWriter::Png { .. } => Ok(()),
+            #[cfg(feature = "gstreamer")]
+            OverlayWriter::Mp4(writer) => writer.finish(),
+        }
+    }
+}
+
+/// エンコード待ちのフレームとして appsrc に溜めるバイト数の上限（超えると push_buffer が待つ）
+#[cfg(all(feature = "ml", feature = "gstreamer"))]
+const MAX_QUEUED_FRAMES: u64 = 8;
+
+/// appsrc から x264enc でエンコードして MP4 に書き出す
+///
+/// finish で書き終える前にドロップした場合（キャンセル・エラー）は、
+/// 再生できない途中までのファイルを残さないよう出力ファイルを削除する。
+#[cfg(all(feature = "ml", feature = "gstreamer"))]
+struct Mp4Writer {
+    pipeline: gst::Pipeline,
+    appsrc: gstreamer_app::AppSrc,
+    output_path: PathBuf,
+    frame_duration: gst::ClockTime,
+    frames: u64,
+    // GStreamer の RGB は行を4バイト境界に揃える
+    stride: usize,
+    finished: bool,
+}
+
+#[cfg(all(feature = "ml", feature = "gstreamer"))]
+impl Mp4Writer {
+    fn open(output_path: &Path, width: u32, height: u32, fps: f64) -> Result<Self> {
+        gst::init().context("GStreamer初期化失敗")?;
+        let pipeline = gst::parse::launch("appsrc name=src ! videoconvert ! x264enc speed-preset=ultrafast ! mp4mux ! filesink name=sink")
+            .context("パイプライン構築失敗（x264enc / mp4mux が必要です）")?
+            .dynamic_cast::<gst::Pipeline>()
+            .map_err(|_| anyhow::anyhow!("Pipeline型への変換失敗"))?;
+        let appsrc = pipeline
+            .by_name("src")
+            .context("AppSrcが見つかりません")?
+            .dynamic_cast::<gstreamer_app::AppSrc>()
+            .map_err(|_| anyhow::anyhow!("AppSrc型への変換失敗"))?;
+        // パスに空白や引用符が含まれてもよいよう、起動文字列に埋め込まずプロパティで指定する
+        pipeline
+            .by_name("sink")
+            .context("filesinkが見つかりません")?
+            .set_property("location", output_path.to_string_lossy().as_ref());
+
+        let framerate = gst::Fraction::approximate_f64(fps).unwrap_or_else(|| gst::Fraction::new(60, 1));
+        appsrc.set_caps(Some(
+            &gst::Caps::builder("video/x-raw")
+                .field("format", "RGB")
+                .field("width", width as i32)
+                .field("height", height as i32)
+                .field("framerate", framerate)
+                .build(),
+        ));
+        appsrc.set_format(gst::Format::Time);
+        // エンコードが追いつくまで push_buffer を待たせ、フレームをメモリに溜め込まない
+        let stride = (width as usize * 3).next_multiple_of(4);
+        appsrc.set_property("block", true);
+        appsrc.set_property("max-bytes", stride as u64 * height as u64 * MAX_QUEUED_FRAMES);
+
+        pipeline
+            .set_state(gst::State::Playing)
+            .context("パイプライン開始失敗")?;
+        Ok(Self {
+            pipeline,
+            appsrc,
+            output_path: output_path.to_path_buf(),
+            frame_duration: gst::ClockTime::from_nseconds((1_000_000_000.0 / fps.max(1.0)) as u64),
+            frames: 0,
+            stride,
+            finished: false,
+        })
+    }
+
+    fn write(&mut self, img: &image::RgbImage) -> Result<()> {
+        // エンコーダーが止まっていると push_buffer が待ち続けるため、先にエラーを確認する
+        if let Some(msg) = self.pipeline.bus().and_then(|bus| bus.pop_filtered(&[gst::MessageType::Error])) {
+            if let gst::MessageView::Error(err) = msg.view() {
+                anyhow::bail!("エンコードエラー: {} (デバッグ情報: {:?})", err.error(), err.debug());
+            }
+        }
+
+        let row_bytes = img.width() as usize * 3;
+        let mut data = vec![0u8; self.stride * img.height() as usize];
+        for (row, dst) in img.as_raw().chunks_exact(row_bytes).zip(data.chunks_exact_mut(self.stride)) {
+            dst[..row_bytes].copy_from_slice(row);
+        }
+
+        let mut buffer = gst::Buffer::from_mut_slice(data);
+        {
+            let buffer = buffer.get_mut().expect("buffer");
+            buffer.set_pts(self.frame_duration * self.frames);
+            buffer.set_duration(self.frame_duration);
+        }
+        self.appsrc
+            .push_buffer(buffer)
+            .map_err(|e| anyhow::anyhow!("フレームの書き込みに失敗: {:?}", e))?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.appsrc
+            .end_of_stream()
+            .map_err(|e| anyhow::anyhow!("書き込みの終了に失敗: {:?}", e))?;
+
+        // mp4mux が書き終えるまで待つ
+        let bus = self.pipeline.bus().context("バスの取得に失敗")?;
+        let result = match bus.timed_pop_filtered(gst::ClockTime::NONE, &[gst::MessageType::Eos, gst::MessageType::Error]) {
+            Some(msg) => match msg.view() {
+                gst::MessageView::Error(err) => Err(anyhow::anyhow!(
+                    "エンコードエラー: {} (デバッグ情報: {:?})",
+                    err.error(),
+                    err.debug()
+                )),
+                _ => Ok(()),
+            },
+            None => Ok(()),
+        };
+        self.finished = result.is_ok();
+        result
+    }
+}
+
+#[cfg(all(feature = "ml", feature = "gstreamer"))]
+impl Drop for Mp4Writer {
+    fn drop(&mut self) {
+        self.pipeline.set_state(gst::State::Null).ok();
+        if !self.finished {
+            println!("[Overlay] 書き出しを中断したため途中のファイルを削除します: {:?}", self.output_path);
+            std::fs::remove_file(&self.output_path).ok();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(tile_label("dir_6"), "6");
+        assert_eq!(tile_label("others"), "-");
+        assert_eq!(tile_label("A1"), "A1");
+
+        let labels: Vec<String> = ["A", "B", "C"].iter().map(|s| s.to_string()).collect();
+        let mut state = InputState { direction: 2, ..InputState::new() };
+        state.buttons.insert("C".to_string(), 1);
+        state.buttons.insert("A".to_string(), 1);
+        assert_eq!(state_label(&state, &labels), "2A+C");
+    }
+
+    #[test]
+    fn test_draw_text_stays_inside_image() {
+        let mut img = new_canvas(7, 5);
+        assert_eq!(img.dimensions(), (8, 6));
+        draw_label(&mut img, 0, 0, "dir_9+X", TEXT_COLOR);
+        // 左上の余白の内側に "D" の1行目の左端が描かれる
+        assert_eq!(img.get_pixel(PADDING, PADDING), &TEXT_COLOR);
+    }
+}
@@ -16,11 +16,13 @@ use burn::{
 };
 #[cfg(feature = "ml")]
 use burn_wgpu::WgpuDevice;
+#[cfg(feature = "ml")]
+use rayon::prelude::*;
 
 #[cfg(feature = "ml")]
 use crate::ml::{ModelConfig, IconClassifier};
 #[cfg(feature = "ml")]
-use crate::model::{ModelMetadata, save_model_with_metadata};
+use crate::model::{LearningCurve, ModelArchitecture, ModelMetadata, TrainingMetrics, WeightPrecision, load_metadata, load_model_binary, save_model_with_metadata};
 #[cfg(feature = "ml")]
 use crate::model::config::AppConfig;
 
@@ -212,13 +214,14 @@ impl Dataset<TileItem> for TileDataset {
 #[derive(Clone)]
 pub struct TileBatcher<B: Backend> {
     device: B::Device,
-    tile_size: usize,
+    tile_width: usize,
+    tile_height: usize,
 }
 
 #[cfg(feature = "ml")]
 impl<B: Backend> TileBatcher<B> {
-    pub fn new(device: B::Device, tile_size: usize) -> Self {
-        Self { device, tile_size }
+    pub fn new(device: B::Device, tile_width: usize, tile_height: usize) -> Self {
+        Self { device, tile_width, tile_height }
     }
 }
 
@@ -231,13 +234,13 @@ impl<B: Backend> Batcher<B, TileItem, TileBatch<B>> for TileBatcher<B> {
         use burn::tensor::Tensor;
         
         let batch_size = items.len();
-        let tile_size = self.tile_size;
-        let mut all_pixels = Vec::with_capacity(batch_size * 3 * tile_size * tile_size);
+        let (tile_width, tile_height) = (self.tile_width, self.tile_height);
+        let mut all_pixels = Vec::with_capacity(batch_size * 3 * tile_width * tile_height);
         let mut targets_vec = Vec::with_capacity(batch_size);
         
         for item in items {
             // 画像をロードして正規化（CPUメモリ上）
-            match crate::ml::load_and_normalize_image_with_size(&item.path, tile_size) {
+            match crate::ml::load_and_normalize_image_with_dims(&item.path, tile_width, tile_height) {
                 Ok(image_data) => {
                     all_pixels.extend_from_slice(&image_data);
                     targets_vec.push(item.label as i64);
@@ -246,7 +249,7 @@ impl<B: Backend> Batcher<B, TileItem, TileBatch<B>> for TileBatcher<B> {
                 Err(e) => {
                     eprintln!("警告: 画像読み込み失敗 {}: {}", item.path.display(), e);
                     // エラーの場合はゼロで埋める
-                    all_pixels.extend(vec![0.0f32; 3 * tile_size * tile_size]);
+                    all_pixels.extend(vec![0.0f32; 3 * tile_width * tile_height]);
                     targets_vec.push(item.label as i64);
                 }
             }
@@ -254,7 +257,7 @@ impl<B: Backend> Batcher<B, TileItem, TileBatch<B>> for TileBatcher<B> {
         
         // 1回の転送でバッチ全体をGPUメモリへ
         let images = Tensor::<B, 1>::from_floats(all_pixels.as_slice(), &self.device)
-            .reshape([batch_size, 3, tile_size, tile_size]);
+            .reshape([batch_size, 3, tile_height, tile_width]);
         let targets = Tensor::<B, 1, Int>::from_ints(targets_vec.as_slice(), &self.device);
         
         // CPUメモリを明示的に解放
@@ -291,10 +294,67 @@ impl<B: Backend> burn::train::ValidStep<TileBatch<B>, burn::train::Classificatio
     }
 }
 
+/// データセットディレクトリの内容からハッシュを算出
+///
+/// 画像の中身は読まず、相対パス・ファイルサイズ・更新日時の一覧をSHA-256でハッシュ化する。
+/// 同じハッシュであれば同一のデータセットで学習したとみなせる。
+#[cfg(feature = "ml")]
+pub fn compute_dataset_hash(data_dir: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    fn collect_entries(root: &Path, dir: &Path, entries: &mut Vec<String>) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                collect_entries(root, &path, entries)?;
+                continue;
+            }
+
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            entries.push(format!("{}\t{}\t{}", relative, metadata.len(), modified));
+        }
+        Ok(())
+    }
+
+    let mut entries = Vec::new();
+    collect_entries(data_dir, data_dir, &mut entries)?;
+    // OSによる列挙順の違いを吸収するためソート
+    entries.sort();
+
+    let mut hasher = Sha256::new();
+    for entry in &entries {
+        hasher.update(entry.as_bytes());
+        hasher.update(b"\n");
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 既存モデルから学習する場合の学習率の倍率（学習済みの重みを大きく崩さないよう小さくする）
+#[cfg(feature = "ml")]
+const FINE_TUNE_LEARNING_RATE_SCALE: f64 = 0.1;
+
 /// モデル学習を実行
 /// 
 /// button_labelsは以下の順序で構成される:
 /// [dir_1～dir_9(方向キー)], [ユーザー定義ボタン], [others]
+///
+/// base_model_path を指定すると、ランダムな初期値ではなくそのモデルの重みから学習を始める（ファインチューニング）。
+/// 学習率は FINE_TUNE_LEARNING_RATE_SCALE 倍にする。クラス構成とタイルサイズが元のモデルと同じである必要がある。
+///
+/// weight_precision が F16 の場合は重みを16bitで保存する（ファイルサイズが約半分になる）。
+/// architecture はモデルの構造（既存モデルから学習する場合は元のモデルの構造を使う）。
 #[cfg(feature = "ml")]
 pub fn train_model<F>(
     data_dir: PathBuf,
@@ -304,7 +364,10 @@ pub fn train_model<F>(
     learning_rate: f64,
     button_labels: Vec<String>,
     use_gpu: bool,
-    _cancel_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    base_model_path: Option<PathBuf>,
+    weight_precision: WeightPrecision,
+    architecture: ModelArchitecture,
+    cancel_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
     mut progress_callback: F,
     log_callback: impl Fn(String) + Send + 'static,
 ) -> Result<String>
@@ -318,7 +381,16 @@ where
             metric::{AccuracyMetric, LossMetric},
             LearnerBuilder, LearningStrategy,
         },
-        record::{DefaultFileRecorder, FullPrecisionSettings},
+    };
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    // キャンセルされていれば中断する（学習データの準備中は段階ごとに確認する）
+    let check_cancelled = || -> Result<()> {
+        if cancel_flag.load(Ordering::SeqCst) {
+            anyhow::bail!(crate::jobs::CANCELLED_MESSAGE);
+        }
+        Ok(())
     };
     
     // button_labelsはユーザーボタンのみ（方向キーとothersは含まない）
@@ -351,18 +423,23 @@ where
     log_callback(format!("ユーザーボタン: {}", user_buttons.join(", ")));
     log_callback(format!("全クラス順序 ({}個): {}", all_class_labels.len(), all_class_labels.join(", ")));
     
+    // データセットのスナップショットハッシュを記録（再現性確認用）
+    let dataset_hash = compute_dataset_hash(&data_dir)?;
+    log_callback(format!("データセットハッシュ: {}", dataset_hash));
+    check_cancelled()?;
+    
     // === バリデーション: タイルサイズチェック ===
     log_callback("学習データの検証を開始します...".to_string());
     
     // 設定ファイルからタイルサイズを取得
     let config = AppConfig::load_or_default();
-    let expected_tile_size = config.button_tile.tile_size;
+    let (expected_tile_width, expected_tile_height) = config.button_tile.tile_dims();
     
-    log_callback(format!("現在のタイルサイズ設定: {}x{}", expected_tile_size, expected_tile_size));
+    log_callback(format!("現在のタイルサイズ設定: {}x{}", expected_tile_width, expected_tile_height));
     
-    // 各クラスディレクトリを検証
+    // 各クラスディレクトリの画像を収集
     let mut empty_classes = Vec::new();
-    let mut invalid_size_images = Vec::new();
+    let mut image_paths: Vec<(String, PathBuf)> = Vec::new();
     
     for class_name in &all_class_labels {
         let class_dir = data_dir.join(class_name);
@@ -372,10 +449,7 @@ where
             continue;
         }
         
-        // クラス内の画像を収集
         let mut image_count = 0;
-        let mut checked_size = false;
-        
         for entry in std::fs::read_dir(&class_dir)? {
             let entry = entry?;
             let path = entry.path();
@@ -385,28 +459,7 @@ where
                     let ext_str = ext.to_string_lossy().to_lowercase();
                     if ext_str == "png" || ext_str == "jpg" || ext_str == "jpeg" {
                         image_count += 1;
-                        
-                        // 最初の画像のサイズをチェック（効率化のため1枚のみ）
-                        if !checked_size {
-                            match image::open(&path) {
-                                Ok(img) => {
-                                    let (width, height) = (img.width(), img.height());
-                                    if width != expected_tile_size || height != expected_tile_size {
-                                        invalid_size_images.push(format!(
-                                            "クラス '{}' の画像 '{}': {}x{} (期待: {}x{})",
-                                            class_name,
-                                            path.file_name().unwrap().to_string_lossy(),
-                                            width, height,
-                                            expected_tile_size, expected_tile_size
-                                        ));
-                                    }
-                                    checked_size = true;
-                                }
-                                Err(e) => {
-                                    log_callback(format!("警告: 画像読み込み失敗: {} - {}", path.display(), e));
-                                }
-                            }
-                        }
+                        image_paths.push((class_name.clone(), path));
                     }
                 }
             }
@@ -419,6 +472,34 @@ where
         log_callback(format!("  クラス '{}': {} 枚", class_name, image_count));
     }
     
+    // 全画像のサイズを並列にチェック（ヘッダーのみ読み込む）
+    log_callback(format!("{} 枚の画像サイズを検証中...", image_paths.len()));
+    let size_check_started = std::time::Instant::now();
+    let mut invalid_size_images: Vec<String> = image_paths
+        .par_iter()
+        .filter_map(|(class_name, path)| {
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            match image::image_dimensions(path) {
+                Ok((width, height)) if width == expected_tile_width && height == expected_tile_height => None,
+                Ok((width, height)) => Some(format!(
+                    "クラス '{}' の画像 '{}': {}x{} (期待: {}x{})",
+                    class_name, file_name, width, height,
+                    expected_tile_width, expected_tile_height
+                )),
+                Err(e) => Some(format!(
+                    "クラス '{}' の画像 '{}': 読み込み失敗 ({})",
+                    class_name, file_name, e
+                )),
+            }
+        })
+        .collect();
+    invalid_size_images.sort();
+    log_callback(format!(
+        "画像サイズの検証完了: {:.1}秒 (不正 {} 枚)",
+        size_check_started.elapsed().as_secs_f64(),
+        invalid_size_images.len()
+    ));
+    
     // エラーチェック
     if !empty_classes.is_empty() {
         let error_msg = format!(
@@ -431,8 +512,9 @@ where
     
     if !invalid_size_images.is_empty() {
         let error_msg = format!(
-            "エラー: 以下の画像のサイズが現在のタイルサイズ設定({}x{})と一致しません:\n{}",
-            expected_tile_size, expected_tile_size,
+            "エラー: 以下の{}枚の画像のサイズが現在のタイルサイズ設定({}x{})と一致しないか、読み込めません:\n{}",
+            invalid_size_images.len(),
+            expected_tile_width, expected_tile_height,
             invalid_size_images.join("\n")
         );
         log_callback(error_msg.clone());
@@ -440,6 +522,7 @@ where
     }
     
     log_callback("検証完了: すべてのクラスディレクトリは有効です".to_string());
+    check_cancelled()?;
     
     let dataset = TileDataset::from_directory_with_order(&data_dir, &all_class_labels)?;
     
@@ -455,18 +538,49 @@ where
     log_callback(format!("検証データ: {} 枚", dataset_val.len()));
     
     // タイルサイズを取得（検証時に既に取得済み）
-    let tile_size = config.button_tile.tile_size as usize;
+    let (tile_width, tile_height) = (expected_tile_width as usize, expected_tile_height as usize);
     
-    // モデル設定（全クラス数とタイルサイズを使用）
+    // 既存モデルから学習する場合は、出力層と入力層の形が一致するか確認する
+    let base_metadata = match &base_model_path {
+        Some(base_model_path) => {
+            let base_metadata = load_metadata(base_model_path)
+                .map_err(|e| anyhow::anyhow!("元のモデルのメタデータを読み込めません: {:?} ({})", base_model_path, e))?;
+            if base_metadata.all_class_labels != all_class_labels {
+                let error_msg = format!(
+                    "エラー: 元のモデルとクラス構成が異なるため、続きから学習できません\n  元のモデル: {}\n  学習データ: {}",
+                    base_metadata.all_class_labels.join(", "),
+                    all_class_labels.join(", ")
+                );
+                log_callback(error_msg.clone());
+                return Err(anyhow::anyhow!(error_msg));
+            }
+            if (base_metadata.image_width, base_metadata.image_height) != (expected_tile_width, expected_tile_height) {
+                let error_msg = format!(
+                    "エラー: 元のモデルの入力サイズ({}x{})が現在のタイルサイズ設定({}x{})と一致しないため、続きから学習できません",
+                    base_metadata.image_width, base_metadata.image_height,
+                    expected_tile_width, expected_tile_height
+                );
+                log_callback(error_msg.clone());
+                return Err(anyhow::anyhow!(error_msg));
+            }
+            Some(base_metadata)
+        }
+        None => None,
+    };
+    
+    // モデル設定（全クラス数とタイルサイズを使用。既存モデルから学習する場合は構造も元のモデルに合わせる）
+    let architecture = base_metadata.as_ref().map_or(architecture, |base_metadata| base_metadata.architecture);
     let num_classes = all_class_labels.len();
     let model_config = ModelConfig {
         num_classes,
         dropout: 0.5,
-        image_size: tile_size,
+        image_size: tile_width,
+        image_height: tile_height,
+        architecture,
     };
     
-    log_callback(format!("モデル設定: {} クラス, 入力サイズ: {}x{}, dropout={}", 
-        num_classes, tile_size, tile_size, model_config.dropout));
+    log_callback(format!("モデル設定: {} クラス, 入力サイズ: {}x{}, dropout={}, 構造: {:?}", 
+        num_classes, tile_width, tile_height, model_config.dropout, architecture));
     
     // デバイス設定（WGPUはGPU専用のため、use_gpuに関わらずGPUを使用）
     if !use_gpu {
@@ -477,8 +591,8 @@ where
     log_callback(format!("使用デバイス: {:?}", device));
     
     // バッチャー作成
-    let batcher_train = TileBatcher::<burn::backend::Autodiff<Wgpu>>::new(device.clone(), tile_size);
-    let batcher_val = TileBatcher::<Wgpu>::new(device.clone(), tile_size);
+    let batcher_train = TileBatcher::<burn::backend::Autodiff<Wgpu>>::new(device.clone(), tile_width, tile_height);
+    let batcher_val = TileBatcher::<Wgpu>::new(device.clone(), tile_width, tile_height);
     
     // データローダー作成（num_workers=0でオンデマンド読み込み）
     // データセット分割時に既にシャッフル済みなのでここではシャッフル不要
@@ -492,11 +606,24 @@ where
         .num_workers(0)
         .build(dataset_val);
     
-    // モデル初期化
+    // モデル初期化（既存モデルから学習する場合はその重みを読み込み、学習率を下げる）
     let model = model_config.init::<burn::backend::Autodiff<Wgpu>>(&device);
+    let (model, learning_rate) = match (&base_model_path, &base_metadata) {
+        (Some(base_model_path), Some(base_metadata)) => {
+            log_callback(format!("既存モデルの重みから学習します: {:?}", base_model_path));
+            let model_binary = load_model_binary(base_model_path)?;
+            let model = model.load_weights(&model_binary, base_metadata.weight_precision, &device)?;
+            (model, learning_rate * FINE_TUNE_LEARNING_RATE_SCALE)
+        }
+        _ => (model, learning_rate),
+    };
     
-    // アーティファクトディレクトリ作成（Viteの監視対象外）
-    let artifact_dir = std::env::temp_dir().join("input_player_training");
+    // アーティファクトディレクトリ作成（Viteの監視対象外。学習ごとに別のフォルダを使う）
+    let artifact_dir = std::env::temp_dir().join("input_player_training").join(format!(
+        "{}-{}",
+        std::process::id(),
+        chrono::Local::now().format("%Y%m%d%H%M%S%f")
+    ));
     std::fs::create_dir_all(&artifact_dir)?;
     let artifact_dir_str = artifact_dir.to_string_lossy().to_string();
     let artifact_dir_for_cleanup = artifact_dir.clone();
@@ -505,33 +632,82 @@ where
     log_callback("学習を開始します...".to_string());
     log_callback(format!("エポック数: {}, バッチサイズ: {}, 学習率: {}", num_epochs, batch_size, learning_rate));
     
-    let learner = LearnerBuilder::new(&artifact_dir_str)
+    // エポックごとのメトリクスは renderer から受け取る
+    let (metrics_sender, metrics_receiver) = std::sync::mpsc::channel();
+    let learner_builder = LearnerBuilder::new(&artifact_dir_str)
+        .renderer(EpochMetricsRenderer::new(metrics_sender))
         .metric_train_numeric(AccuracyMetric::new())
         .metric_valid_numeric(AccuracyMetric::new())
         .metric_train_numeric(LossMetric::new())
         .metric_valid_numeric(LossMetric::new())
         .learning_strategy(LearningStrategy::SingleDevice(device.clone()))
         .num_epochs(num_epochs)
-        .summary()
-        .build(
-            model,
-            AdamConfig::new().init(),
-            learning_rate,
-        );
+        .summary();
+    let interrupter = learner_builder.interrupter();
+    let learner = learner_builder.build(
+        model,
+        AdamConfig::new().init(),
+        learning_rate,
+    );
     
     log_callback("データローダーとモデルの準備が完了しました".to_string());
+    check_cancelled()?;
     
-    // TODO: キャンセルフラグと進捗コールバックの統合
-    // 現在のburn frameworkではカスタムコールバックが難しいため、
-    // 学習完了後にのみ報告
+    // 学習中はキャンセルフラグとエポックごとのメトリクスを監視する
+    // （キャンセルされたら burn の学習を現在のバッチの後で止め、エポックが終わるたびに進捗を通知する）
+    let fit_done = Arc::new(AtomicBool::new(false));
+    let watcher = {
+        let cancel_flag = cancel_flag.clone();
+        let fit_done = fit_done.clone();
+        std::thread::spawn(move || {
+            use std::sync::mpsc::RecvTimeoutError;
+
+            let mut stop_requested = false;
+            let mut curve: Vec<TrainingMetrics> = Vec::new();
+            loop {
+                if !stop_requested && cancel_flag.load(Ordering::SeqCst) {
+                    interrupter.stop();
+                    stop_requested = true;
+                }
+                match metrics_receiver.recv_timeout(WATCH_INTERVAL) {
+                    Ok(epoch) => {
+                        progress_callback(epoch.epoch, epoch.train_loss, epoch.train_accuracy, epoch.val_loss, epoch.val_accuracy);
+                        curve.push(TrainingMetrics {
+                            epochs_completed: epoch.epoch,
+                            train_loss: epoch.train_loss,
+                            train_accuracy: epoch.train_accuracy,
+                            val_loss: epoch.val_loss,
+                            val_accuracy: epoch.val_accuracy,
+                        });
+                    }
+                    // 学習が終わると renderer とともに送信側が破棄される
+                    Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) if fit_done.load(Ordering::SeqCst) => break,
+                    Err(RecvTimeoutError::Timeout) => {}
+                }
+            }
+            (progress_callback, curve)
+        })
+    };
     
     // 学習実行
     log_callback("===learner.fit()を開始します===".to_string());
     eprintln!("[DEBUG] learner.fit()を開始します");
     
     let model_trained = learner.fit(dataloader_train, dataloader_val);
+    fit_done.store(true, Ordering::SeqCst);
+    let (mut progress_callback, curve) = watcher
+        .join()
+        .map_err(|_| anyhow::anyhow!("学習の監視スレッドが異常終了しました"))?;
     
     eprintln!("[DEBUG] learner.fit()が完了しました");
+    
+    // 中断した場合は途中のモデルを保存しない
+    if cancel_flag.load(Ordering::SeqCst) {
+        log_callback("学習を中断しました（モデルは保存しません）".to_string());
+        std::fs::remove_dir_all(&artifact_dir_for_cleanup).ok();
+        anyhow::bail!(crate::jobs::CANCELLED_MESSAGE);
+    }
     log_callback("===learner.fit()が正常に完了しました===".to_string());
     
     // artifact_dirの内容をデバッグ出力
@@ -544,8 +720,10 @@ where
         }
     }
     
-    // 最終進捗を報告
-    progress_callback(num_epochs, 0.0, 0.0, 0.0, 0.0);
+    // メトリクスを受け取れなかった場合も完了は通知する
+    if curve.is_empty() {
+        progress_callback(num_epochs, 0.0, 0.0, 0.0, 0.0);
+    }
     
     log_callback("===進捗報告完了===".to_string());
     
@@ -578,13 +756,16 @@ where
     let total_params = trained_model.num_params();
     eprintln!("[DEBUG] 学習済みモデルの総パラメータ数: {} ({:.2}M)", total_params, total_params as f64 / 1_000_000.0);
     
-    if total_params < 15_000_000 {
+    let expected_model_size = total_params * weight_precision.bytes_per_param();
+    
+    if architecture == ModelArchitecture::Standard && total_params < 15_000_000 {
         eprintln!("[ERROR] 学習済みモデルのパラメータ数が少なすぎます！期待: 19.8M, 実際: {:.2}M", total_params as f64 / 1_000_000.0);
         eprintln!("[ERROR] これは古いアーキテクチャのモデルです。何らかの理由で学習されたモデルが保持されていません。");
     }
     
-    // DefaultFileRecorderを使用してf32精度で保存（CompactRecorderはf16で保存してしまう）
-    trained_model.save_file(&temp_model_path, &DefaultFileRecorder::<FullPrecisionSettings>::new())?;
+    // DefaultFileRecorderを使用して指定した精度で保存（CompactRecorderは常にf16で保存してしまう）
+    log_callback(format!("重みの保存精度: {:?}", weight_precision));
+    trained_model.save_weights(&temp_model_path, weight_precision)?;
     
     eprintln!("[DEBUG] save_file()完了");
     log_callback(format!("save_file完了"));
@@ -593,16 +774,19 @@ where
     if std::path::Path::new(&model_mpk_path).exists() {
         let saved_size = std::fs::metadata(&model_mpk_path)?.len();
         eprintln!("[DEBUG] 保存されたmodel.mpkサイズ: {} bytes ({:.2} MB)", saved_size, saved_size as f64 / 1024.0 / 1024.0);
-        eprintln!("[DEBUG] 期待サイズ: 79,287,368 bytes (79.29 MB)");
+        eprintln!("[DEBUG] 期待サイズ: 約{} bytes ({:.2} MB)", expected_model_size, expected_model_size as f64 / 1024.0 / 1024.0);
         
-        if saved_size < 40_000_000 {
+        if (saved_size as usize) < expected_model_size / 2 {
             eprintln!("[ERROR] モデルサイズが小さすぎます！古いアーキテクチャが保存されている可能性があります");
         }
         
         log_callback(format!("保存されたモデルサイズ: {} バイト ({:.2} MB)", 
             saved_size, 
             saved_size as f64 / 1024.0 / 1024.0));
-        log_callback(format!("期待サイズ: 79.29 MB (19,821,842 params × 4 bytes)"));
+        log_callback(format!("期待サイズ: {:.2} MB ({} params × {} bytes)",
+            expected_model_size as f64 / 1024.0 / 1024.0,
+            total_params,
+            weight_precision.bytes_per_param()));
     } else {
         eprintln!("[ERROR] model.mpkファイルが存在しません！");
         log_callback(format!("エラー: モデルファイルが保存されていません！"));
@@ -621,25 +805,31 @@ where
     let config = AppConfig::load_or_default();
     
     // メタデータ作成（button_labelsにはユーザーボタンのみ、all_class_labelsに全クラス）
-    let tile_size_u32 = config.button_tile.tile_size;
-    let metadata = ModelMetadata::new(
+    let (tile_width_u32, tile_height_u32) = config.button_tile.tile_dims();
+    let mut metadata = ModelMetadata::new(
         user_buttons,  // ユーザーボタンのみ
         all_class_labels.clone(),  // 全クラス（8方向 + ユーザーボタン + others）
-        tile_size_u32,  // 実際のタイルサイズ
-        tile_size_u32,  // 実際のタイルサイズ
+        tile_width_u32,  // 実際のタイルの幅
+        tile_height_u32,  // 実際のタイルの高さ
         config.button_tile.source_video_width,
         config.button_tile.source_video_height,
         config.button_tile.x,
         config.button_tile.y,
-        config.button_tile.tile_size,
-        config.button_tile.tile_size,
+        tile_width_u32,
+        tile_height_u32,
         config.button_tile.columns_per_row,
-        tile_size_u32,  // 実際のタイルサイズ
+        tile_width_u32,  // モデル入力の幅（高さは image_height）
         num_epochs as u32,
     );
+    metadata.dataset_hash = Some(dataset_hash);
+    metadata.base_model = base_model_path.map(|path| path.to_string_lossy().to_string());
+    metadata.weight_precision = weight_precision;
+    metadata.architecture = architecture;
     
     // モデルとメタデータを保存
-    save_model_with_metadata(&output_model_path, &metadata, &model_binary)?;
+    // 学習曲線はメトリクスを受け取れた場合のみ同梱する
+    let learning_curve = (!curve.is_empty()).then(|| LearningCurve { epochs: curve });
+    save_model_with_metadata(&output_model_path, &metadata, &model_binary, learning_curve.as_ref())?;
     
     // アーティファクトディレクトリをクリーンアップ
     std::fs::remove_dir_all(&artifact_dir_for_cleanup).ok();
@@ -647,43 +837,265 @@ where
     Ok(format!("学習完了: {:?} に保存しました", output_model_path))
 }
 
+/// 分類したタイルを出力先へ移すか複製するか
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TileTransfer {
+    /// 元のタイルを残して複製する
+    #[default]
+    Copy,
+    /// 元のフォルダから移動する（分類済みのタイルが元のフォルダに残らない）
+    Move,
+}
+
+/// 学習中にキャンセルフラグを確認する間隔
+#[cfg(feature = "ml")]
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// 1エポック分の学習・検証のメトリクス（正解率は 0〜1）
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, PartialEq)]
+struct EpochMetrics {
+    epoch: usize,
+    train_loss: f64,
+    train_accuracy: f64,
+    val_loss: f64,
+    val_accuracy: f64,
+}
+
+/// エポックごとのメトリクスの集計
+///
+/// burn の数値メトリクスはそのエポックのそれまでのバッチの平均として渡されるため、
+/// エポックの最後に受け取った値がエポック全体の平均になる。正解率は百分率で渡される。
+#[cfg(feature = "ml")]
+#[derive(Debug, Default)]
+struct EpochMetricsCollector {
+    train_loss: f64,
+    train_accuracy: f64,
+    val_loss: f64,
+    val_accuracy: f64,
+    /// 通知済みのエポック数
+    reported: usize,
+}
+
+#[cfg(feature = "ml")]
+impl EpochMetricsCollector {
+    fn record(&mut self, valid: bool, name: &str, value: f64) {
+        match (valid, name) {
+            (false, "Loss") => self.train_loss = value,
+            (false, "Accuracy") => self.train_accuracy = value / 100.0,
+            (true, "Loss") => self.val_loss = value,
+            (true, "Accuracy") => self.val_accuracy = value / 100.0,
+            _ => {}
+        }
+    }
+
+    /// エポックの検証まで終わったときのメトリクス（通知済みのエポックなら None）
+    fn complete(&mut self, epoch: usize) -> Option<EpochMetrics> {
+        if epoch <= self.reported {
+            return None;
+        }
+        self.reported = epoch;
+        let metrics = EpochMetrics {
+            epoch,
+            train_loss: self.train_loss,
+            train_accuracy: self.train_accuracy,
+            val_loss: self.val_loss,
+            val_accuracy: self.val_accuracy,
+        };
+        // 次のエポックに前のエポックの値が残らないようにする
+        *self = Self { reported: epoch, ..Self::default() };
+        Some(metrics)
+    }
+}
+
+/// 学習のメトリクスをエポックごとにチャンネルへ送る renderer
+///
+/// 検証の最後のバッチを処理した時点でそのエポックを通知する（検証データが無い場合は学習の終了時に通知する）。
+#[cfg(feature = "ml")]
+struct EpochMetricsRenderer {
+    collector: EpochMetricsCollector,
+    sender: std::sync::mpsc::Sender<EpochMetrics>,
+    epoch: usize,
+}
+
+#[cfg(feature = "ml")]
+impl EpochMetricsRenderer {
+    fn new(sender: std::sync::mpsc::Sender<EpochMetrics>) -> Self {
+        Self { collector: EpochMetricsCollector::default(), sender, epoch: 0 }
+    }
+
+    fn record(&mut self, valid: bool, state: burn::train::renderer::MetricState) {
+        if let burn::train::renderer::MetricState::Numeric(entry, value) = state {
+            self.collector.record(valid, entry.name.as_str(), value.current());
+        }
+    }
+
+    fn send_completed(&mut self) {
+        if let Some(metrics) = self.collector.complete(self.epoch) {
+            // 受信側が先に終了していても学習は続ける
+            self.sender.send(metrics).ok();
+        }
+    }
+}
+
+#[cfg(feature = "ml")]
+impl burn::train::renderer::MetricsRendererTraining for EpochMetricsRenderer {
+    fn update_train(&mut self, state: burn::train::renderer::MetricState) {
+        self.record(false, state);
+    }
+
+    fn update_valid(&mut self, state: burn::train::renderer::MetricState) {
+        self.record(true, state);
+    }
+
+    fn render_train(&mut self, item: burn::train::renderer::TrainingProgress) {
+        self.epoch = item.epoch;
+    }
+
+    fn render_valid(&mut self, item: burn::train::renderer::TrainingProgress) {
+        self.epoch = item.epoch;
+        if item.progress.items_processed >= item.progress.items_total {
+            self.send_completed();
+        }
+    }
+
+    fn on_train_end(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        self.send_completed();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ml")]
+impl burn::train::renderer::MetricsRendererEvaluation for EpochMetricsRenderer {
+    fn update_test(&mut self, _name: burn::train::renderer::EvaluationName, _state: burn::train::renderer::MetricState) {}
+
+    fn render_test(&mut self, _item: burn::train::renderer::EvaluationProgress) {}
+}
+
+#[cfg(feature = "ml")]
+impl burn::train::renderer::MetricsRenderer for EpochMetricsRenderer {
+    fn manual_close(&mut self) {}
+}
+
+/// 1回の推論で分類するタイル数
+#[cfg(feature = "ml")]
+const CLASSIFY_BATCH_SIZE: usize = 64;
+
 /// タイル分類を実行（学習データフィードバック用）
+///
+/// tiles_dir 直下の画像（png / jpg）をバッチ推論で分類し、output_dir/クラス名/ に複製または移動する。
+/// 読み込めない画像は分類せず警告して読み飛ばす。on_progress には（処理済み枚数, 総枚数）を渡す。
 #[cfg(feature = "ml")]
-pub fn classify_tiles(
-    model_path: PathBuf,
-    tiles_dir: PathBuf,
-    output_dir: PathBuf,
-    use_gpu: bool,
-) -> Result<HashMap<String, Vec<PathBuf>>> {
-    use crate::ml::InferenceEngine;
-    
-    // モデル読み込み
-    let engine = InferenceEngine::load_with_backend(&model_path, use_gpu)?;
-    
+pub fn classify_tiles<F>(
+    engine: &crate::ml::InferenceEngine,
+    tiles_dir: &Path,
+    output_dir: &Path,
+    transfer: TileTransfer,
+    mut on_progress: F,
+) -> Result<HashMap<String, Vec<PathBuf>>>
+where
+    F: FnMut(usize, usize) -> Result<()>,
+{
+    use anyhow::Context;
+
+    let mut tile_paths: Vec<PathBuf> = std::fs::read_dir(tiles_dir)
+        .with_context(|| format!("タイルのフォルダを開けません: {:?}", tiles_dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+        })
+        .collect();
+    tile_paths.sort();
+    println!("[タイル分類] {}枚のタイルを分類します: {:?}", tile_paths.len(), tiles_dir);
+
     let mut classified: HashMap<String, Vec<PathBuf>> = HashMap::new();
-    
-    // タイルを分類
-    for entry in std::fs::read_dir(&tiles_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.is_file() {
-            // 実際の分類処理
-            let class_name = engine.classify_image(&path)
-                .unwrap_or_else(|_| "others".to_string());
-            
-            classified.entry(class_name.clone())
-                .or_insert_with(Vec::new)
-                .push(path.clone());
-            
-            // 分類結果ディレクトリにコピー
+    let mut skipped = 0usize;
+
+    for (chunk_index, chunk) in tile_paths.chunks(CLASSIFY_BATCH_SIZE).enumerate() {
+        // 読み込めた画像だけをまとめて推論する
+        let (paths, images): (Vec<&PathBuf>, Vec<image::RgbImage>) = chunk
+            .iter()
+            .filter_map(|path| match image::open(path) {
+                Ok(img) => Some((path, img.to_rgb8())),
+                Err(e) => {
+                    println!("[タイル分類] 警告: 画像を読み込めません（スキップ）: {:?} ({})", path, e);
+                    skipped += 1;
+                    None
+                }
+            })
+            .unzip();
+        let labels = engine.classify_batch_from_images(&images)
+            .context("バッチ分類エラー")?;
+
+        for (path, class_name) in paths.into_iter().zip(labels) {
             let class_dir = output_dir.join(&class_name);
-            std::fs::create_dir_all(&class_dir)?;
-            
-            let dest = class_dir.join(path.file_name().unwrap());
-            std::fs::copy(&path, &dest)?;
+            std::fs::create_dir_all(&class_dir)
+                .with_context(|| format!("ディレクトリ作成エラー: {:?}", class_dir))?;
+            let dest = class_dir.join(path.file_name().expect("file name"));
+            transfer_tile(path, &dest, transfer)?;
+            classified.entry(class_name).or_default().push(dest);
         }
+
+        on_progress(((chunk_index + 1) * CLASSIFY_BATCH_SIZE).min(tile_paths.len()), tile_paths.len())?;
+    }
+
+    if skipped > 0 {
+        println!("[タイル分類] 読み込めなかった画像: {}枚", skipped);
     }
-    
     Ok(classified)
 }
+
+// タイルを出力先へ複製または移動する（別ドライブへの移動は複製してから削除する）
+#[cfg(feature = "ml")]
+fn transfer_tile(src: &Path, dest: &Path, transfer: TileTransfer) -> Result<()> {
+    use anyhow::Context;
+
+    match transfer {
+        TileTransfer::Copy => {
+            std::fs::copy(src, dest).with_context(|| format!("タイルの複製に失敗: {:?}", src))?;
+        }
+        TileTransfer::Move => {
+            if std::fs::rename(src, dest).is_err() {
+                std::fs::copy(src, dest).with_context(|| format!("タイルの移動に失敗: {:?}", src))?;
+                std::fs::remove_file(src).with_context(|| format!("移動元のタイルを削除できません: {:?}", src))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "ml"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_metrics_are_reported_once_per_epoch() {
+        let mut collector = EpochMetricsCollector::default();
+        collector.record(false, "Loss", 2.0);
+        collector.record(false, "Accuracy", 72.5);
+        collector.record(true, "Loss", 0.5);
+        collector.record(true, "Accuracy", 90.0);
+
+        assert_eq!(collector.complete(1), Some(EpochMetrics {
+            epoch: 1,
+            train_loss: 2.0,
+            train_accuracy: 0.725,
+            val_loss: 0.5,
+            val_accuracy: 0.9,
+        }));
+        // 学習の終了時に同じエポックをもう一度通知しない
+        assert_eq!(collector.complete(1), None);
+
+        // 前のエポックの値は持ち越さない
+        collector.record(false, "Loss", 1.0);
+        let second = collector.complete(2).unwrap();
+        assert_eq!(second.train_loss, 1.0);
+        assert_eq!(second.val_accuracy, 0.0);
+    }
+}
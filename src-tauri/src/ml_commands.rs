@@ -8,15 +8,48 @@ use std::path::PathBuf;
 use tauri::Manager;
 
 #[cfg(feature = "ml")]
-use crate::video::{FrameExtractor, FrameExtractorConfig};
+use crate::video::FrameExtractor;
 #[cfg(feature = "ml")]
-use crate::analyzer::{InputState, InputIndicatorRegion};
+use crate::analyzer::InputState;
 #[cfg(feature = "ml")]
-use crate::model::load_metadata;
+use crate::model::{load_metadata, AppConfig, TrainingMetrics};
+use crate::model::{TrainingHistory, TrainingRun};
 #[cfg(feature = "ml")]
 use std::fs;
 #[cfg(feature = "ml")]
-use crate::ml::InferenceEngine;
+use crate::gpu_scheduler::GpuJobKind;
+#[cfg(feature = "ml")]
+use crate::jobs::{JobGuard, JobKind, CANCELLED_MESSAGE};
+
+/// キャンセルされた場合はエラーメッセージを CANCELLED_MESSAGE に揃える（フロントエンドが判別できるように）
+#[cfg(feature = "ml")]
+fn job_error(job: &JobGuard, message: String) -> String {
+    if job.token().is_cancelled() {
+        CANCELLED_MESSAGE.to_string()
+    } else {
+        message
+    }
+}
+
+/// ジョブの処理を spawn_blocking で実行し、終わったら結果をジョブに記録して返す
+///
+/// 解析・分類はフレーム数に比例して時間がかかり、GPUの使用権の待機もブロックする。
+/// コマンドのスレッド（メインスレッド・非同期ランタイムのワーカー）で実行すると、その間は
+/// UI が固まり cancel_job も受け付けられないため、必ずこの関数を通して実行する。
+#[cfg(feature = "ml")]
+async fn run_job_blocking<T, F>(app: tauri::AppHandle, job: JobGuard, body: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&tauri::AppHandle, &crate::AppState, &JobGuard) -> Result<T, String> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<crate::AppState>();
+        let result = body(&app, &state, &job);
+        job.finish(result)
+    })
+    .await
+    .map_err(|e| format!("処理スレッドエラー: {}", e))?
+}
 
 /// 非圧縮PNGとして画像を保存するヘルパー関数
 #[cfg(feature = "ml")]
@@ -49,199 +82,510 @@ fn save_as_uncompressed_png<P: AsRef<std::path::Path>>(
 }
 
 /// 進捗情報のペイロード
-#[derive(Clone, serde::Serialize)]
+#[derive(Clone, Default, serde::Serialize)]
 pub struct ExtractionProgress {
     pub current_frame: u32,
     pub total_frames: u32,
     pub message: String,
+    /// 進捗率（0〜100）。総フレーム数が不明な場合は None
+    pub percent: Option<f64>,
+    /// 処理速度（フレーム/秒）
+    pub frames_per_sec: f64,
+    /// 処理開始からの経過時間（秒）
+    pub elapsed_sec: f64,
+    /// 残り時間の推定（秒）。推定できない場合は None
+    pub eta_sec: Option<f64>,
+}
+
+impl ExtractionProgress {
+    /// 処理開始時刻から処理速度・経過時間・残り時間を算出して設定
+    pub fn with_timing(mut self, started: std::time::Instant) -> Self {
+        self.elapsed_sec = started.elapsed().as_secs_f64();
+        if self.elapsed_sec > 0.0 {
+            self.frames_per_sec = self.current_frame as f64 / self.elapsed_sec;
+        }
+        self.eta_sec = if self.frames_per_sec > 0.0 && self.total_frames > self.current_frame {
+            Some((self.total_frames - self.current_frame) as f64 / self.frames_per_sec)
+        } else if self.total_frames > 0 && self.current_frame >= self.total_frames {
+            Some(0.0)
+        } else {
+            None
+        };
+        self.percent = (self.total_frames > 0)
+            .then(|| (self.current_frame as f64 / self.total_frames as f64 * 100.0).min(100.0));
+        self
+    }
+}
+
+/// 抽出コマンド共通の進捗通知（フレームごとの通知を設定の間隔に間引く）
+///
+/// 状態の変化（モデル読み込み・完了など）は `message` / `finish` で常に送り、
+/// フレーム処理中の進捗は `frame` で間引いて送る。ログも同じ間隔で出力する。
+#[cfg(feature = "ml")]
+pub struct ProgressReporter<'a> {
+    channel: &'a tauri::ipc::Channel<ExtractionProgress>,
+    job: &'a JobGuard,
+    tag: &'static str,
+    started: std::time::Instant,
+    total_frames: u32,
+    interval: std::time::Duration,
+    every_frames: u32,
+    last_sent: Option<(std::time::Instant, u32)>,
+}
+
+#[cfg(feature = "ml")]
+impl<'a> ProgressReporter<'a> {
+    /// total_frames が不明な場合は 0 を指定する（進捗はジョブの状態にも記録する）
+    pub fn new(
+        channel: &'a tauri::ipc::Channel<ExtractionProgress>,
+        job: &'a JobGuard,
+        tag: &'static str,
+        total_frames: u32,
+    ) -> Self {
+        let settings = crate::model::AppConfig::load_or_default().progress;
+        Self {
+            channel,
+            job,
+            tag,
+            started: std::time::Instant::now(),
+            total_frames,
+            interval: std::time::Duration::from_millis(settings.interval_ms),
+            every_frames: settings.every_frames,
+            last_sent: None,
+        }
+    }
+
+    fn send(&mut self, current_frame: u32, total_frames: u32, message: String) {
+        self.job.set_progress(current_frame as u64, total_frames as u64, &message);
+        self.channel.send(ExtractionProgress {
+            current_frame,
+            total_frames,
+            message,
+            ..Default::default()
+        }.with_timing(self.started)).ok();
+        self.last_sent = Some((std::time::Instant::now(), current_frame));
+    }
+
+    /// 状態の通知（間引かない）
+    pub fn message(&mut self, message: impl Into<String>) {
+        let current_frame = self.last_sent.map_or(0, |(_, frame)| frame);
+        self.send(current_frame, self.total_frames, message.into());
+    }
+
+    /// フレーム処理の進捗（前回の通知から間隔が空いた場合のみ送る。detail はメッセージの補足）
+    pub fn frame(&mut self, current_frame: u32, detail: impl FnOnce() -> String) {
+        if let Some((at, frame)) = self.last_sent {
+            let frames_due = self.every_frames > 0 && current_frame >= frame + self.every_frames;
+            if !frames_due && at.elapsed() < self.interval {
+                return;
+            }
+        }
+        let percent = (self.total_frames > 0)
+            .then(|| format!(" ({}%)", (current_frame as f64 / self.total_frames as f64 * 100.0).min(100.0) as u32))
+            .unwrap_or_default();
+        let detail = detail();
+        let message = if detail.is_empty() {
+            format!("{}フレーム処理中...{}", current_frame, percent)
+        } else {
+            format!("{}フレーム処理中...{} {}", current_frame, percent, detail)
+        };
+        println!("[{}] {}", self.tag, message);
+        self.send(current_frame, self.total_frames, message);
+    }
+
+    /// 完了の通知（処理したフレーム数を総数として送る）
+    pub fn finish(&mut self, total_frames: u32, message: impl Into<String>) {
+        self.total_frames = total_frames;
+        self.send(total_frames, total_frames, message.into());
+    }
+}
+
+/// 抽出中の分類結果を通知するイベント名
+#[cfg(feature = "ml")]
+pub const EXTRACTION_FRAME_EVENT: &str = "extraction-frame";
+
+/// 入力状態が変わらない間に分類結果を通知する間隔
+#[cfg(feature = "ml")]
+const EXTRACTION_FRAME_EVENT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// 抽出中に分類したフレーム（認識した入力のライブプレビュー用）
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractionFrameEvent {
+    pub job_id: String,
+    /// 動画の先頭から数えたフレーム番号
+    pub frame: u32,
+    pub direction: u8,
+    /// ボタン名 -> 状態 (0 or 1)
+    pub buttons: std::collections::HashMap<String, u8>,
+    /// タイルごとの確信度（0〜1、左の列から順）
+    pub confidences: Vec<f32>,
+}
+
+/// 抽出中の分類結果をイベントで通知する
+///
+/// 入力状態が変わったフレームは必ず送り、変わらない間は一定間隔に間引く。
+#[cfg(feature = "ml")]
+struct FrameEventEmitter<'a> {
+    app: &'a tauri::AppHandle,
+    job: &'a JobGuard,
+    last_state: Option<InputState>,
+    last_sent: Option<std::time::Instant>,
+}
+
+#[cfg(feature = "ml")]
+impl<'a> FrameEventEmitter<'a> {
+    fn new(app: &'a tauri::AppHandle, job: &'a JobGuard) -> Self {
+        Self { app, job, last_state: None, last_sent: None }
+    }
+
+    fn emit(&mut self, frame: u32, classification: &crate::ml::FrameClassification) {
+        use tauri::Emitter;
+
+        let changed = self.last_state.as_ref() != Some(&classification.state);
+        let due = self.last_sent.is_none_or(|at| at.elapsed() >= EXTRACTION_FRAME_EVENT_INTERVAL);
+        if !changed && !due {
+            return;
+        }
+        self.app.emit(EXTRACTION_FRAME_EVENT, ExtractionFrameEvent {
+            job_id: self.job.id().to_string(),
+            frame,
+            direction: classification.state.direction,
+            buttons: classification.state.buttons.clone(),
+            confidences: classification.confidences.clone(),
+        }).ok();
+        self.last_state = Some(classification.state.clone());
+        self.last_sent = Some(std::time::Instant::now());
+    }
 }
 
 /// 動画から入力履歴を抽出してCSV生成（同期処理版 + 進捗通知）
 /// 
 /// バックエンドスレッド内で完結するため、wgpuをSend制約なしで使用可能
 /// Channelを使ってフロントエンドに進捗を通知
+/// job_id を指定すると cancel_job で中断できる（中断時はCSVを書き出さない）
+/// start_sec / end_sec を指定するとその区間だけを解析する
+/// 分類結果は "extraction-frame" イベントで随時通知する
 #[cfg(feature = "ml")]
 #[tauri::command]
-pub fn extract_input_history(
+#[allow(clippy::too_many_arguments)]
+pub async fn extract_input_history(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
     video_path: String,
     model_path: String,
     output_csv_path: String,
     use_gpu: bool,
+    start_sec: Option<f64>,
+    end_sec: Option<f64>,
+    job_id: Option<String>,
     on_progress: tauri::ipc::Channel<ExtractionProgress>,
 ) -> Result<String, String> {
-    // このスレッド内で推論エンジンを初期化（Sendとして渡す必要なし）
-    let engine = InferenceEngine::load_with_backend(&PathBuf::from(&model_path), use_gpu)
-        .map_err(|e| format!("推論エンジンの初期化エラー: {}", e))?;
-    
-    // メタデータから領域設定を取得
-    let metadata = load_metadata(&PathBuf::from(&model_path))
-        .map_err(|e| format!("メタデータ読み込みエラー: {}", e))?;
-    
-    let button_labels = metadata.button_labels.clone();
-    
-    // メタデータの値をデバッグ出力
-    println!("[MP4→CSV] モデルメタデータ:");
-    println!("  tile_x: {}, tile_y: {}", metadata.tile_x, metadata.tile_y);
-    println!("  tile_width: {}, tile_height: {} (領域全体)", metadata.tile_width, metadata.tile_height);
-    println!("  image_width: {}, image_height: {} (個々のタイル)", metadata.image_width, metadata.image_height);
-    println!("  columns_per_row: {}", metadata.columns_per_row);
-    println!("  button_labels: {:?}", metadata.button_labels);
-    
-    // 領域全体のサイズを計算（個々のタイルサイズ × 列数）
-    // 注意: tile_widthは領域全体の幅、image_widthが個々のタイルサイズ
-    let tile_size = metadata.image_width; // 個々のタイルサイズ（48x48）
-    let total_width = tile_size * metadata.columns_per_row;
-    let total_height = tile_size; // 1行のみ
-    
-    println!("[MP4→CSV] 計算された領域:");
-    println!("  tile_size: {}", tile_size);
-    println!("  total_width: {} ({}x{})", total_width, tile_size, metadata.columns_per_row);
-    println!("  total_height: {}", total_height);
-    
-    let region = InputIndicatorRegion {
-        x: metadata.tile_x,
-        y: metadata.tile_y,
-        width: total_width,
-        height: total_height,
-        rows: 1, // 最下行のみ解析
-        cols: metadata.columns_per_row,
-    };
-    
-    println!("[MP4→CSV] InputIndicatorRegion: x={}, y={}, width={}, height={}, rows={}, cols={}",
-        region.x, region.y, region.width, region.height, region.rows, region.cols);
-    
-    // 一時ディレクトリ（システムのtempディレクトリを使用してViteの監視範囲外に配置）
-    let temp_dir = std::env::temp_dir().join("input_player_input_extraction");
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("一時ディレクトリ作成エラー: {}", e))?;
-    let tile_dir = temp_dir.join("tiles");
-    fs::create_dir_all(&tile_dir).ok();
-    
-    // CSV出力はメモリ上でバッファしてから一括書き込みする
-    let mut csv_lines: Vec<String> = Vec::new();
-    let mut header = vec!["duration".to_string(), "direction".to_string()];
-    header.extend(button_labels.clone());
-    
-    // 入力状態の履歴
-    let mut previous_state: Option<InputState> = None;
-    let mut duration = 0u32;
-    let mut total_frames = 0u32;
-    
-    // フレーム抽出設定
-    let frame_config = FrameExtractorConfig {
-        frame_interval: 1, // 全フレーム
-        output_dir: temp_dir.clone(),
-        image_format: "png".to_string(),
-        jpeg_quality: 95,
-    };
-    
-    let extractor = FrameExtractor::new(frame_config);
-    
-    // 同期処理: フレーム抽出とタイル推論を同じスレッド内で実行
-    // 事前に領域全体を videocrop で切り出してから AppSink で処理する
-    extractor.process_frames_sync_with_crop(&video_path, Some(region.clone()), |frame_img, frame_num| {
-        total_frames = frame_num + 1;
-        
-        // 30フレームごとに進捗通知
-        if frame_num % 30 == 0 {
-            on_progress.send(ExtractionProgress {
-                current_frame: frame_num,
-                total_frames: 0, // 総フレーム数は不明（動画の最後まで処理しないと分からない）
-                message: format!("{}フレーム処理中...", frame_num),
-            }).ok();
-        }
-        
-        // AppSinkに渡される画像は既に領域全体でクロップ済みなので、
-        // 切り出し後の画像上で列ごとにタイルを抽出する（x=0,y=0開始）
-        let cropped_region = crate::analyzer::InputIndicatorRegion {
-            x: 0,
-            y: 0,
-            width: region.width,
-            height: region.height,
-            rows: region.rows,
-            cols: region.cols,
-        };
-
-        let tiles = crate::analyzer::extract_tiles_from_image(frame_img, &cropped_region)
-            .map_err(|e| anyhow::anyhow!("タイル抽出エラー: {}", e))?;
+    let job = state.jobs.register(JobKind::Extraction, job_id, video_path.clone())?;
+    run_job_blocking(app, job, move |app, state, job| {
+        let range = crate::video::TimeRange::new(start_sec, end_sec).map_err(|e| e.to_string())?;
+        run_extract_input_history(app, state, video_path, model_path, output_csv_path, use_gpu, range, job, on_progress)
+    })
+    .await
+}
 
-        // 入力状態を初期化
-        let mut current_state = InputState::new();
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+fn run_extract_input_history(
+    app: &tauri::AppHandle,
+    state: &crate::AppState,
+    video_path: String,
+    model_path: String,
+    output_csv_path: String,
+    use_gpu: bool,
+    range: crate::video::TimeRange,
+    job: &JobGuard,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<String, String> {
+    // 総フレーム数は不明（動画の最後まで処理しないと分からない）
+    let mut progress = ProgressReporter::new(&on_progress, job, "抽出", 0);
 
-        // バッチサイズはモデルメタデータの列数を使用
-        let batch_size = engine.config().columns_per_row as usize;
+    // レジストリの登録名が指定された場合はパスに解決
+    let config = AppConfig::load_or_default();
+    let model_path = config.resolve_model_path(&model_path);
 
-        if batch_size == 0 {
-            // フォールバック: 個別分類
-            for tile in tiles.into_iter() {
-                let class_name = engine.classify_image_direct(&tile)
-                    .map_err(|e| anyhow::anyhow!("推論エラー: {}", e))?;
-                crate::analyzer::update_input_state(&mut current_state, &class_name);
-            }
-        } else {
-            // チャンク毎にバッチ分類を行う
-            for chunk in tiles.chunks(batch_size) {
-                // chunk は &[image::RgbImage]
-                let labels = engine.classify_batch_from_images(chunk)
-                    .map_err(|e| anyhow::anyhow!("バッチ推論エラー: {}", e))?;
+    // GPU使用時は他のGPUジョブの終了を待つ（VRAMの奪い合いを防ぐ）
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| progress.message(message))
+    }).transpose().map_err(|e| e.to_string())?;
 
-                for class_name in labels.into_iter() {
-                    crate::analyzer::update_input_state(&mut current_state, &class_name);
-                }
-            }
-        }
-        
-        // 状態が変化したらCSVに書き込み
-        if let Some(ref prev) = previous_state {
-            if prev != &current_state {
-                let line = prev.to_csv_line(duration, &button_labels);
-                csv_lines.push(line);
-                duration = 1;
-            } else {
-                duration += 1;
-            }
-        } else {
-            duration = 1;
-        }
-        
-        previous_state = Some(current_state);
-        
-        Ok(())
-    }).map_err(|e| format!("フレーム処理エラー: {}", e))?;
-    
-    // 最後の状態をバッファに追加
-    if let Some(ref state) = previous_state {
-        let line: String = state.to_csv_line(duration, &button_labels);
-        csv_lines.push(line);
-    }
+    // 推論エンジンを取得（同じモデル・バックエンドなら読み込み済みのものを使う）
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
+        .map_err(|e| format!("推論エンジンの初期化エラー: {}", e))?;
+    job.token().check().map_err(|e| e.to_string())?;
 
-    // バッファを書き出す（ヘッダー含む）
-    let mut csv_writer = csv::Writer::from_path(&output_csv_path)
-        .map_err(|e| format!("CSV作成エラー: {}", e))?;
-    csv_writer.write_record(&header)
-        .map_err(|e| format!("CSVヘッダー書き込みエラー: {}", e))?;
-    for line in csv_lines.into_iter() {
-        csv_writer.write_record(line.split(','))
-            .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
-    }
-    csv_writer.flush()
-        .map_err(|e| format!("CSVフラッシュエラー: {}", e))?;
+    // ボタン列は設定に応じてボタンマッピングの順序・列名に合わせる
+    let columns = crate::analyzer::output_columns_for(
+        &engine.config().button_labels,
+        &config.generation,
+        &state.button_order.lock().unwrap(),
+    );
     
-    // 一時ディレクトリを削除
-    fs::remove_dir_all(&temp_dir).ok();
+    let mut frame_events = FrameEventEmitter::new(app, job);
+    let total_frames = crate::ml::extract_input_history_to_csv(
+        &engine,
+        std::path::Path::new(&video_path),
+        std::path::Path::new(&output_csv_path),
+        &columns,
+        &crate::analyzer::StepPostProcess::from_config(&config),
+        range,
+        |frame_num| {
+            job.token().check()?;
+            progress.frame(frame_num, String::new);
+            Ok(())
+        },
+        |frame_num, classification| frame_events.emit(frame_num, classification),
+    ).map_err(|e| job_error(job, format!("入力履歴の抽出に失敗: {:#}", e)))?;
     
     // 完了通知
-    on_progress.send(ExtractionProgress {
-        current_frame: total_frames,
-        total_frames: total_frames,
-        message: format!("完了: {}フレーム処理しました", total_frames),
-    }).ok();
+    progress.finish(total_frames, format!("完了: {}フレーム処理しました", total_frames));
     
     Ok(format!("入力履歴を抽出しました: {} ({}フレーム処理)", output_csv_path, total_frames))
 }
 
+/// 複数の領域（P1 / P2 など）の入力履歴を1回のデコードで抽出し、領域ごとにCSVを保存する
+///
+/// 出力先は output_csv_path の拡張子の前に _ラベル を付けたパス（ラベル未指定の領域は P1, P2, ... の順）。
+/// 各領域の回転・反転の補正は領域ごとに適用し、設定時と解像度が異なる動画では座標を比率で補正する。
+#[cfg(feature = "ml")]
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn extract_input_history_regions(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    video_path: String,
+    model_path: String,
+    regions: Vec<crate::analysis_commands::AnalysisRegion>,
+    output_csv_path: String,
+    use_gpu: bool,
+    start_sec: Option<f64>,
+    end_sec: Option<f64>,
+    job_id: Option<String>,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<Vec<String>, String> {
+    let job = state.jobs.register(JobKind::Extraction, job_id, video_path.clone())?;
+    run_job_blocking(app, job, move |_, state, job| {
+        let range = crate::video::TimeRange::new(start_sec, end_sec).map_err(|e| e.to_string())?;
+        run_extract_input_history_regions(state, video_path, model_path, regions, output_csv_path, use_gpu, range, job, on_progress)
+    })
+    .await
+}
+
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+fn run_extract_input_history_regions(
+    state: &crate::AppState,
+    video_path: String,
+    model_path: String,
+    regions: Vec<crate::analysis_commands::AnalysisRegion>,
+    output_csv_path: String,
+    use_gpu: bool,
+    range: crate::video::TimeRange,
+    job: &JobGuard,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<Vec<String>, String> {
+    // 補正前の解像度（領域ごとの補正後の解像度に合わせて座標を補正する）
+    let info = FrameExtractor::get_video_info(&video_path, crate::model::VideoTransform::None)
+        .map_err(|e| format!("動画情報取得エラー: {}", e))?;
+    let labeled: Vec<crate::analyzer::LabeledRegion> = regions
+        .iter()
+        .enumerate()
+        .map(|(i, region)| {
+            let (width, height) = if region.video_transform.swaps_dimensions() {
+                (info.height as u32, info.width as u32)
+            } else {
+                (info.width as u32, info.height as u32)
+            };
+            crate::analyzer::LabeledRegion {
+                label: region.label.clone().filter(|l| !l.is_empty()).unwrap_or_else(|| format!("P{}", i + 1)),
+                region: region.scaled_to(width, height).to_indicator_region(),
+                transform: region.video_transform,
+            }
+        })
+        .collect();
+
+    let mut progress = ProgressReporter::new(&on_progress, job, "抽出", 0);
+    let config = AppConfig::load_or_default();
+    let model_path = config.resolve_model_path(&model_path);
+
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| progress.message(message))
+    }).transpose().map_err(|e| e.to_string())?;
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
+        .map_err(|e| format!("推論エンジンの初期化エラー: {}", e))?;
+    job.token().check().map_err(|e| e.to_string())?;
+
+    let columns = crate::analyzer::output_columns_for(
+        &engine.config().button_labels,
+        &config.generation,
+        &state.button_order.lock().unwrap(),
+    );
+    let (total_frames, outputs) = crate::ml::extract_regions_to_csv(
+        &engine,
+        std::path::Path::new(&video_path),
+        &labeled,
+        std::path::Path::new(&output_csv_path),
+        &columns,
+        &crate::analyzer::StepPostProcess::from_config(&config),
+        range,
+        |frame_num| {
+            job.token().check()?;
+            progress.frame(frame_num, String::new);
+            Ok(())
+        },
+    ).map_err(|e| job_error(job, format!("入力履歴の抽出に失敗: {:#}", e)))?;
+
+    progress.finish(total_frames, format!("完了: {}フレーム・{}領域を処理しました", total_frames, outputs.len()));
+    Ok(outputs.iter().map(|path| path.to_string_lossy().to_string()).collect())
+}
+
+/// 解析した動画に分類結果（タイルごとのクラスと復元した入力）を描き込んで書き出す
+///
+/// output_path の拡張子が .mp4 なら動画、それ以外はフォルダに連番PNGで保存する。
+/// crop_only を指定すると入力表示の領域だけを書き出す。戻り値は書き出したフレーム数。
+#[cfg(feature = "ml")]
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn render_overlay_video(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    video_path: String,
+    model_path: String,
+    output_path: String,
+    crop_only: bool,
+    use_gpu: bool,
+    start_sec: Option<f64>,
+    end_sec: Option<f64>,
+    job_id: Option<String>,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<u32, String> {
+    let job = state.jobs.register(JobKind::Extraction, job_id, video_path.clone())?;
+    run_job_blocking(app, job, move |_, state, job| {
+        let range = crate::video::TimeRange::new(start_sec, end_sec).map_err(|e| e.to_string())?;
+        run_render_overlay_video(state, video_path, model_path, output_path, crop_only, use_gpu, range, job, on_progress)
+    })
+    .await
+}
+
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+fn run_render_overlay_video(
+    state: &crate::AppState,
+    video_path: String,
+    model_path: String,
+    output_path: String,
+    crop_only: bool,
+    use_gpu: bool,
+    range: crate::video::TimeRange,
+    job: &JobGuard,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<u32, String> {
+    let mut progress = ProgressReporter::new(&on_progress, job, "書き出し", 0);
+    let model_path = AppConfig::load_or_default().resolve_model_path(&model_path);
+
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| progress.message(message))
+    }).transpose().map_err(|e| e.to_string())?;
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
+        .map_err(|e| format!("推論エンジンの初期化エラー: {}", e))?;
+    job.token().check().map_err(|e| e.to_string())?;
+
+    let written = crate::ml::render_overlay(
+        &engine,
+        std::path::Path::new(&video_path),
+        std::path::Path::new(&output_path),
+        crop_only,
+        range,
+        |frame_num| {
+            job.token().check()?;
+            progress.frame(frame_num, String::new);
+            Ok(())
+        },
+    ).map_err(|e| job_error(job, format!("確認用動画の書き出しに失敗: {:#}", e)))?;
+
+    progress.finish(written, format!("完了: {}フレームを書き出しました", written));
+    Ok(written)
+}
+
+/// 録画とシーケンスの比較: シーケンスCSVの再生を録画した動画から入力を再抽出し、元のCSVとステップ単位で比較
+///
+/// 往復テスト（再生 → 録画 → 解析）のうち解析と比較だけを行う。再生と録画はこのコマンドでは行わないため、
+/// video_path には事前に csv_path を再生してアプリ外で録画した動画を指定する。
+/// 比較前に両側のボタン名を現在のボタンマッピングでコントローラーのボタン名に揃える。
+/// 再抽出したCSVは動画と同じディレクトリに _roundtrip.csv として保存する。
+#[cfg(feature = "ml")]
+#[tauri::command]
+pub async fn compare_recording_with_sequence(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    csv_path: String,
+    video_path: String,
+    model_path: String,
+    use_gpu: bool,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<crate::roundtrip::RoundtripReport, String> {
+    let job = state.jobs.register(JobKind::Extraction, None, video_path.clone())?;
+    run_job_blocking(app, job, move |app, state, job| {
+        run_compare_recording_with_sequence(app, state, csv_path, video_path, model_path, use_gpu, job, on_progress)
+    })
+    .await
+}
+
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+fn run_compare_recording_with_sequence(
+    app: &tauri::AppHandle,
+    state: &crate::AppState,
+    csv_path: String,
+    video_path: String,
+    model_path: String,
+    use_gpu: bool,
+    job: &JobGuard,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<crate::roundtrip::RoundtripReport, String> {
+    use crate::csv_loader::load_csv;
+
+    let expected = load_csv(std::path::Path::new(&csv_path))
+        .map_err(|e| format!("CSV読み込みに失敗: {}", e))?;
+
+    let video = PathBuf::from(&video_path);
+    let stem = video.file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| "動画ファイル名が無効です".to_string())?;
+    let extracted_csv = video.with_file_name(format!("{}_roundtrip.csv", stem));
+    let extracted_csv_str = extracted_csv.to_string_lossy().to_string();
+
+    println!("[Roundtrip] 元CSV: {}", csv_path);
+    println!("[Roundtrip] 録画: {}", video_path);
+
+    run_extract_input_history(
+        app, state, video_path, model_path, extracted_csv_str.clone(), use_gpu, crate::video::TimeRange::default(), job, on_progress,
+    )?;
+
+    let actual = load_csv(&extracted_csv)
+        .map_err(|e| format!("再抽出CSVの読み込みに失敗: {}", e))?;
+    let mapping = state.player.lock().unwrap().get_button_mapping().clone();
+    let report = crate::roundtrip::build_report(
+        extracted_csv_str,
+        &crate::roundtrip::normalize_buttons(&expected, &mapping),
+        &crate::roundtrip::normalize_buttons(&actual, &mapping),
+    );
+
+    println!(
+        "[Roundtrip] 一致: {}/{} ステップ（フレーム一致率 {:.1}%）",
+        report.equal_steps, report.expected_steps, report.accuracy.frame_accuracy * 100.0
+    );
+    Ok(report)
+}
+
 /// 学習進捗データ
 #[cfg(feature = "ml")]
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct TrainingProgress {
     pub current_epoch: usize,
     pub total_epochs: usize,
@@ -251,6 +595,26 @@ pub struct TrainingProgress {
     pub val_accuracy: f64,
     pub message: String,
     pub log_lines: Vec<String>,
+    /// 1エポックあたりの平均所要時間（秒）
+    pub sec_per_epoch: f64,
+    /// 学習開始からの経過時間（秒）
+    pub elapsed_sec: f64,
+    /// 残り時間の推定（秒）。推定できない場合は None
+    pub eta_sec: Option<f64>,
+}
+
+#[cfg(feature = "ml")]
+impl TrainingProgress {
+    /// 学習開始時刻からエポック速度・経過時間・残り時間を算出して設定
+    pub fn with_timing(mut self, started: std::time::Instant) -> Self {
+        self.elapsed_sec = started.elapsed().as_secs_f64();
+        if self.current_epoch > 0 {
+            self.sec_per_epoch = self.elapsed_sec / self.current_epoch as f64;
+            let remaining = self.total_epochs.saturating_sub(self.current_epoch);
+            self.eta_sec = Some(remaining as f64 * self.sec_per_epoch);
+        }
+        self
+    }
 }
 
 /// モデル学習コマンド（非同期）
@@ -266,26 +630,69 @@ pub async fn train_classification_model(
     learning_rate: f64,
     button_labels: Vec<String>,
     use_gpu: bool,
+    base_model_path: Option<String>,
+    weight_precision: Option<crate::model::WeightPrecision>,
+    architecture: Option<crate::model::ModelArchitecture>,
+    job_id: Option<String>,
     on_progress: tauri::ipc::Channel<TrainingProgress>,
 ) -> Result<String, String> {
     use crate::ml::train_model;
     use std::sync::Arc;
-    use std::sync::atomic::AtomicBool;
     use tokio::task;
     
-    // 学習開始フラグを立てる
-    *state.is_training.lock().unwrap() = true;
-    
+    // 学習は同時に1つまで（GPU を取り合わないよう、実行中は新しい学習を受け付けない）
+    {
+        let mut is_training = state.is_training.lock().unwrap();
+        if *is_training {
+            return Err("別のモデルの学習を実行中です。完了してから開始してください".to_string());
+        }
+        *is_training = true;
+    }
+    let job = match state.jobs.register(JobKind::Training, job_id, data_dir.clone()) {
+        Ok(job) => job,
+        Err(e) => {
+            *state.is_training.lock().unwrap() = false;
+            return Err(e);
+        }
+    };
+    // 既存モデルから学習する場合（ファインチューニング）はモデル名も受け付ける
+    let base_model_path = base_model_path.map(|path| AppConfig::load_or_default().resolve_model_path(&path));
+    // 進捗は学習スレッドから更新する
+    let job_registry = job.registry().clone();
+    let job_id = job.id().to_string();
+
     // ウィンドウのクローズを防止
     if let Some(window) = app_handle.get_webview_window("main") {
         window.set_closable(false).ok();
     }
     
-    // キャンセルフラグ
-    let cancel_flag = Arc::new(AtomicBool::new(false));
-    let cancel_flag_clone = cancel_flag.clone();
-    
-    // TODO: キャンセルイベントリスナーを実装
+    // キャンセルフラグ（cancel_job で立つ。学習は現在のバッチの後で止まる）
+    let cancel_flag = job.token().as_flag();
+    let cancel_token = job.token().clone();
+    let cancelled_channel = on_progress.clone();
+    let gpu_scheduler = state.gpu_scheduler.clone();
+
+    // 学習履歴用の記録
+    let started_at = chrono::Local::now();
+    let started = std::time::Instant::now();
+    let mut run_record = TrainingRun {
+        id: started_at.format("%Y%m%d_%H%M%S").to_string(),
+        started_at: started_at.to_rfc3339(),
+        duration_sec: 0.0,
+        data_dir: data_dir.clone(),
+        output_model_path: output_path.clone(),
+        num_epochs,
+        batch_size,
+        learning_rate,
+        button_labels: button_labels.clone(),
+        use_gpu,
+        base_model_path: base_model_path.clone(),
+        success: false,
+        error: None,
+        metrics: TrainingMetrics::default(),
+    };
+    let final_metrics = Arc::new(std::sync::Mutex::new(TrainingMetrics::default()));
+    let final_metrics_clone = final_metrics.clone();
     
     // 別スレッドで学習実行
     let result = task::spawn_blocking(move || {
@@ -296,6 +703,23 @@ pub async fn train_classification_model(
         let log_buffer_clone = log_buffer.clone();
         let log_buffer_clone2 = log_buffer.clone();
         
+        // GPU使用時は他のGPUジョブ（推論など）の終了を待つ
+        let _gpu_guard = use_gpu.then(|| {
+            gpu_scheduler.acquire(GpuJobKind::Training, &cancel_token, |message| {
+                on_progress.send(TrainingProgress {
+                    current_epoch: 0,
+                    total_epochs: num_epochs,
+                    train_loss: 0.0,
+                    train_accuracy: 0.0,
+                    val_loss: 0.0,
+                    val_accuracy: 0.0,
+                    message,
+                    log_lines: vec![],
+                    ..Default::default()
+                }.with_timing(started)).ok();
+            })
+        }).transpose()?;
+
         // 初期メッセージを送信
         on_progress.send(TrainingProgress {
             current_epoch: 0,
@@ -306,11 +730,21 @@ pub async fn train_classification_model(
             val_accuracy: 0.0,
             message: "学習を初期化しています...".to_string(),
             log_lines: vec![],
-        }).ok();
+            ..Default::default()
+        }.with_timing(started)).ok();
         
         // 進捗コールバック
         let progress_callback = move |epoch: usize, train_loss: f64, train_acc: f64, val_loss: f64, val_acc: f64| {
+            *final_metrics_clone.lock().unwrap() = TrainingMetrics {
+                epochs_completed: epoch,
+                train_loss,
+                train_accuracy: train_acc,
+                val_loss,
+                val_accuracy: val_acc,
+            };
             let logs = log_buffer_clone.lock().unwrap().clone();
+            let message = format!("Epoch {}/{}", epoch, num_epochs);
+            job_registry.set_progress(&job_id, epoch as u64, num_epochs as u64, &message);
             on_progress.send(TrainingProgress {
                 current_epoch: epoch,
                 total_epochs: num_epochs,
@@ -318,9 +752,10 @@ pub async fn train_classification_model(
                 train_accuracy: train_acc,
                 val_loss,
                 val_accuracy: val_acc,
-                message: format!("Epoch {}/{}", epoch, num_epochs),
+                message,
                 log_lines: logs,
-            }).ok();
+                ..Default::default()
+            }.with_timing(started)).ok();
         };
         
         // ログコールバック
@@ -336,88 +771,302 @@ pub async fn train_classification_model(
             learning_rate,
             button_labels,
             use_gpu,
-            cancel_flag_clone,
+            base_model_path.map(PathBuf::from),
+            weight_precision.unwrap_or_default(),
+            architecture.unwrap_or_default(),
+            cancel_flag,
             progress_callback,
             log_callback,
         )
     })
-    .await
-    .map_err(|e| format!("学習スレッドエラー: {}", e))?;
-    
-    // 学習終了後、フラグをクリアしてウィンドウを閉じられるようにする
-    *state.is_training.lock().unwrap() = false;
-    if let Some(window) = app_handle.get_webview_window("main") {
-        window.set_closable(true).ok();
+    .await;
+    
+    // 学習終了後（中断・失敗時も）、フラグをクリアしてウィンドウを閉じられるようにする
+    *state.is_training.lock().unwrap() = false;
+    if let Some(window) = app_handle.get_webview_window("main") {
+        window.set_closable(true).ok();
+    }
+    let result = result.map_err(|e| format!("学習スレッドエラー: {}", e))?;
+
+    if job.token().is_cancelled() {
+        cancelled_channel.send(TrainingProgress {
+            current_epoch: final_metrics.lock().unwrap().epochs_completed,
+            total_epochs: num_epochs,
+            message: "学習を中断しました".to_string(),
+            ..Default::default()
+        }.with_timing(started)).ok();
+    }
+
+    // 学習履歴に記録（失敗しても学習結果には影響させない）
+    run_record.duration_sec = started.elapsed().as_secs_f64();
+    run_record.metrics = final_metrics.lock().unwrap().clone();
+    match &result {
+        Ok(_) => run_record.success = true,
+        Err(e) => run_record.error = Some(e.to_string()),
+    }
+    if let Err(e) = TrainingHistory::append_run(run_record) {
+        eprintln!("[Training] 学習履歴の保存に失敗: {}", e);
+    }
+    
+    job.finish(result.map_err(|e| e.to_string()))
+}
+
+/// 学習履歴の一覧を取得（新しい順）
+#[tauri::command]
+pub fn list_training_runs() -> Result<Vec<TrainingRun>, String> {
+    let mut runs = TrainingHistory::load_or_default().runs;
+    runs.reverse();
+    Ok(runs)
+}
+
+/// 保存済みモデルに同梱された学習曲線（エポックごとの損失・正解率）を取得
+///
+/// 学習曲線を記録する前に作成したモデルは None
+#[cfg(feature = "ml")]
+#[tauri::command]
+pub fn get_model_training_history(model_path: String) -> Result<Option<crate::model::LearningCurve>, String> {
+    let model_path = AppConfig::load_or_default().resolve_model_path(&model_path);
+    crate::model::load_learning_curve(std::path::Path::new(&model_path))
+        .map_err(|e| format!("学習曲線の読み込みに失敗: {}", e))
+}
+
+/// タイル分類コマンド（既存タイルの分類）
+///
+/// transfer を省略した場合はタイルを複製する（"move" で移動）
+#[cfg(feature = "ml")]
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn classify_video_tiles(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    model_path: String,
+    tiles_dir: String,
+    output_dir: String,
+    use_gpu: bool,
+    transfer: Option<crate::ml::TileTransfer>,
+    job_id: Option<String>,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<ClassificationResult, String> {
+    let job = state.jobs.register(JobKind::Classification, job_id, tiles_dir.clone())?;
+    run_job_blocking(app, job, move |_, state, job| {
+        run_classify_video_tiles(state, model_path, tiles_dir, output_dir, use_gpu, transfer.unwrap_or_default(), job, on_progress)
+    })
+    .await
+}
+
+#[cfg(feature = "ml")]
+#[allow(clippy::too_many_arguments)]
+fn run_classify_video_tiles(
+    state: &crate::AppState,
+    model_path: String,
+    tiles_dir: String,
+    output_dir: String,
+    use_gpu: bool,
+    transfer: crate::ml::TileTransfer,
+    job: &JobGuard,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<ClassificationResult, String> {
+    use crate::ml::classify_tiles;
+
+    let mut progress = ProgressReporter::new(&on_progress, job, "タイル分類", 0);
+    let model_path = AppConfig::load_or_default().resolve_model_path(&model_path);
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| progress.message(message))
+    }).transpose().map_err(|e| e.to_string())?;
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
+        .map_err(|e| format!("モデル読み込みエラー: {}", e))?;
+
+    let mut total = 0usize;
+    let classified = classify_tiles(
+        &engine,
+        std::path::Path::new(&tiles_dir),
+        std::path::Path::new(&output_dir),
+        transfer,
+        |done, tiles| {
+            job.token().check()?;
+            total = tiles;
+            progress.frame(done as u32, || format!("/ {} タイル", tiles));
+            Ok(())
+        },
+    )
+    .map_err(|e| job_error(job, format!("タイル分類に失敗: {:#}", e)))?;
+
+    // モデルのクラス順に並べる（分類されなかったクラスは含めない）
+    let class_labels = &engine.config().all_class_labels;
+    let mut summary: Vec<ClassSummary> = classified
+        .into_iter()
+        .map(|(class_name, paths)| ClassSummary { class_name, count: paths.len() })
+        .collect();
+    summary.sort_by_key(|s| class_labels.iter().position(|label| *label == s.class_name).unwrap_or(usize::MAX));
+
+    progress.finish(total as u32, format!("完了: {}タイルを分類しました", total));
+    Ok(ClassificationResult {
+        summary,
+        message: "タイル分類が完了しました".to_string(),
+    })
+}
+
+/// 学習済みモデルをクラス名のフォルダに分けた画像で評価する（学習し直さずにモデルを比較する）
+#[cfg(feature = "ml")]
+#[tauri::command]
+pub async fn evaluate_model(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    model_path: String,
+    labeled_dir: String,
+    use_gpu: bool,
+    job_id: Option<String>,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<crate::ml::EvaluationReport, String> {
+    let job = state.jobs.register(JobKind::Classification, job_id, labeled_dir.clone())?;
+    run_job_blocking(app, job, move |_, state, job| {
+        run_evaluate_model(state, model_path, labeled_dir, use_gpu, job, on_progress)
+    })
+    .await
+}
+
+#[cfg(feature = "ml")]
+fn run_evaluate_model(
+    state: &crate::AppState,
+    model_path: String,
+    labeled_dir: String,
+    use_gpu: bool,
+    job: &JobGuard,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<crate::ml::EvaluationReport, String> {
+    let mut progress = ProgressReporter::new(&on_progress, job, "評価", 0);
+    let model_path = AppConfig::load_or_default().resolve_model_path(&model_path);
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| progress.message(message))
+    }).transpose().map_err(|e| e.to_string())?;
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
+        .map_err(|e| format!("モデル読み込みエラー: {}", e))?;
+
+    let report = crate::ml::evaluate_model(&engine, std::path::Path::new(&labeled_dir), |done, total| {
+        job.token().check()?;
+        progress.frame(done as u32, || format!("/ {} 枚", total));
+        Ok(())
+    })
+    .map_err(|e| job_error(job, format!("モデルの評価に失敗: {:#}", e)))?;
+
+    progress.finish(report.total as u32, format!("完了: 正解率 {:.2}%", report.accuracy * 100.0));
+    Ok(report)
+}
+
+/// 1フレームから切り出すタイルの位置と大きさ（動画の解像度に合わせて補正したもの）
+#[cfg(feature = "ml")]
+struct TileLayout {
+    /// 各列のタイルの左端
+    columns: Vec<u32>,
+    y: u32,
+    width: u32,
+    height: u32,
+    /// 学習時のタイルサイズ
+    model_size: (u32, u32),
+    /// 学習時の動画サイズと異なり、比率で補正したか
+    scaled: bool,
+}
+
+#[cfg(feature = "ml")]
+impl TileLayout {
+    fn new(metadata: &crate::model::ModelMetadata, video_width: u32, video_height: u32) -> Self {
+        let scale_x = video_width as f64 / metadata.video_width.max(1) as f64;
+        let scale_y = video_height as f64 / metadata.video_height.max(1) as f64;
+        let scale = |value: u32, ratio: f64| (value as f64 * ratio).round() as u32;
+        Self {
+            // 列ごとに丸めると誤差が溜まるため、学習時の座標を列ごとに変換する
+            columns: (0..metadata.columns_per_row)
+                .map(|col| scale(metadata.tile_x + col * metadata.tile_width, scale_x))
+                .collect(),
+            y: scale(metadata.tile_y, scale_y),
+            width: scale(metadata.tile_width, scale_x).max(1),
+            height: scale(metadata.tile_height, scale_y).max(1),
+            model_size: (metadata.tile_width, metadata.tile_height),
+            scaled: video_width != metadata.video_width || video_height != metadata.video_height,
+        }
+    }
+
+    /// 補正した場合は保存・分類するタイルを学習時のタイルサイズに揃える
+    fn to_model_size(&self, tile: image::RgbImage) -> image::RgbImage {
+        if tile.dimensions() == self.model_size {
+            return tile;
+        }
+        let (width, height) = self.model_size;
+        image::imageops::resize(&tile, width, height, image::imageops::FilterType::Triangle)
     }
-    
-    result.map_err(|e| e.to_string())
 }
 
-/// タイル分類コマンド（既存タイルの分類）
+/// 動画からタイルを抽出して分類するコマンド（進捗付き）
+/// start_sec / end_sec を指定するとその区間だけを処理する
+/// scale_to_video を有効にすると、学習時と解像度が異なる動画でもタイルの位置・大きさを比率で補正して処理する
+/// video_path には連番画像のフォルダも指定できる（image_sequence_fps で撮影されたものとして扱う。省略時は 60fps）
 #[cfg(feature = "ml")]
 #[tauri::command]
-pub fn classify_video_tiles(
+#[allow(clippy::too_many_arguments)]
+pub async fn extract_and_classify_tiles(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    video_path: String,
     model_path: String,
-    tiles_dir: String,
     output_dir: String,
+    frame_skip: u32,
+    scale_to_video: Option<bool>,
     use_gpu: bool,
+    start_sec: Option<f64>,
+    end_sec: Option<f64>,
+    image_sequence_fps: Option<f64>,
+    job_id: Option<String>,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
 ) -> Result<ClassificationResult, String> {
-    use crate::ml::classify_tiles;
-    
-    let classified = classify_tiles(
-        PathBuf::from(model_path),
-        PathBuf::from(tiles_dir),
-        PathBuf::from(output_dir),
-        use_gpu,
-    )
-    .map_err(|e| e.to_string())?;
-    
-    let mut summary = Vec::new();
-    for (class_name, paths) in classified {
-        summary.push(ClassSummary {
-            class_name,
-            count: paths.len(),
-        });
-    }
-    
-    Ok(ClassificationResult {
-        summary,
-        message: "タイル分類が完了しました".to_string(),
+    let job = state.jobs.register(JobKind::Classification, job_id, video_path.clone())?;
+    run_job_blocking(app, job, move |_, state, job| {
+        let range = crate::video::TimeRange::new(start_sec, end_sec).map_err(|e| e.to_string())?;
+        let image_sequence_fps = image_sequence_fps.unwrap_or(crate::video::DEFAULT_IMAGE_SEQUENCE_FPS);
+        run_extract_and_classify_tiles(
+            state, video_path, model_path, output_dir, frame_skip, scale_to_video.unwrap_or(false), use_gpu, range, image_sequence_fps, job, on_progress,
+        )
     })
+    .await
 }
 
-/// 動画からタイルを抽出して分類するコマンド（進捗付き）
 #[cfg(feature = "ml")]
-#[tauri::command]
-pub fn extract_and_classify_tiles(
+#[allow(clippy::too_many_arguments)]
+fn run_extract_and_classify_tiles(
+    state: &crate::AppState,
     video_path: String,
     model_path: String,
     output_dir: String,
     frame_skip: u32,
+    scale_to_video: bool,
     use_gpu: bool,
+    range: crate::video::TimeRange,
+    image_sequence_fps: f64,
+    job: &JobGuard,
     on_progress: tauri::ipc::Channel<ExtractionProgress>,
 ) -> Result<ClassificationResult, String> {
     use crate::model::load_metadata;
-    use crate::ml::InferenceEngine;
     use std::fs;
     use std::collections::HashMap;
-    use gstreamer as gst;
-    use gstreamer::prelude::*;
-    use gstreamer_app as gst_app;
-    use gstreamer_video as gst_video;
-    use image::{ImageBuffer, Rgb};
     
-    // モデル読み込み（バックエンド設定を使用）
-    let engine = InferenceEngine::load_with_backend(&PathBuf::from(&model_path), use_gpu)
+    let app_config = AppConfig::load_or_default();
+    let model_path = app_config.resolve_model_path(&model_path);
+    let transform = app_config.button_tile.video_transform;
+    // 総フレーム数は不明
+    let mut progress = ProgressReporter::new(&on_progress, job, "タイル分類", 0);
+
+    // GPU使用時は他のGPUジョブの終了を待つ
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| progress.message(message))
+    }).transpose().map_err(|e| e.to_string())?;
+
+    // モデル読み込み（バックエンド設定を使用、読み込み済みならキャッシュから取得）
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
         .map_err(|e| format!("モデル読み込みエラー: {}", e))?;
     
     // メタデータ取得
     let metadata = load_metadata(&PathBuf::from(&model_path))
         .map_err(|e| format!("メタデータ読み込みエラー: {}", e))?;
     
-    // GStreamer初期化
-    gst::init().map_err(|e| format!("GStreamer初期化失敗: {}", e))?;
-    
     // 出力ディレクトリ作成（動画名のフォルダ）
     let video_pathbuf = PathBuf::from(&video_path);
     let video_stem = video_pathbuf
@@ -425,6 +1074,9 @@ pub fn extract_and_classify_tiles(
         .and_then(|s| s.to_str())
         .ok_or("動画ファイル名の取得エラー")?;
     let video_output_dir = PathBuf::from(&output_dir).join(video_stem);
+    // キャンセル時に途中まで保存したタイルを削除するため、作成前に存在したかを記録する
+    let output_dir_existed = video_output_dir.exists();
+    let mut written_tiles: Vec<PathBuf> = Vec::new();
     
     // クラス毎のディレクトリ作成（all_class_labelsがあればそれを使用）
     let class_labels = if !metadata.all_class_labels.is_empty() {
@@ -439,132 +1091,71 @@ pub fn extract_and_classify_tiles(
             .map_err(|e| format!("ディレクトリ作成エラー: {}", e))?;
     }
     
-    // パイプラインを構築
-    let pipeline_str = format!(
-        "filesrc location=\"{}\" ! decodebin ! videoconvert ! video/x-raw,format=RGB ! appsink name=sink",
-        video_path.replace("\\", "/")
-    );
-    
-    let pipeline = gst::parse::launch(&pipeline_str)
-        .map_err(|e| format!("パイプライン構築失敗: {}", e))?;
-    let pipeline = pipeline
-        .dynamic_cast::<gst::Pipeline>()
-        .map_err(|_| "Pipeline型への変換失敗")?;
-    
-    // AppSinkを取得
-    let appsink = pipeline
-        .by_name("sink")
-        .ok_or("AppSinkが見つかりません")?
-        .dynamic_cast::<gst_app::AppSink>()
-        .map_err(|_| "AppSink型への変換失敗")?;
-    
-    // パイプラインを開始
-    pipeline
-        .set_state(gst::State::Playing)
-        .map_err(|e| format!("パイプライン開始失敗: {:?}", e))?;
-    
-    let mut frame_count = 0u32;
     let mut tile_count: HashMap<String, usize> = HashMap::new();
     let mut total_tiles = 0usize;
+    let mut frame_count = 0u32;
     
     // メタデータから動画サイズをチェック
     let expected_width = metadata.video_width as u32;
     let expected_height = metadata.video_height as u32;
-    let mut size_checked = false;
-    
-    // フレームを処理
-    loop {
-        let sample = match appsink.pull_sample() {
-            Ok(sample) => sample,
-            Err(_) => break, // EOSまたはエラーで終了
-        };
+    let mut tile_layout: Option<TileLayout> = None;
+    
+    // フレームを処理（区間が指定されていれば開始位置へシークする。フレーム番号は動画の先頭から数える）
+    let extractor = crate::video::FrameExtractor::new(crate::video::FrameExtractorConfig {
+        frame_interval: frame_skip + 1,
+        transform,
+        image_sequence_fps,
+        ..crate::video::FrameExtractorConfig::default()
+    });
+    let result = extractor.process_frames_sync_in_range(&video_path, None, range, |frame_img, frame_num| {
+        job.token().check()?;
+        frame_count = frame_num + 1;
         
-        frame_count += 1;
-        
-        // 間引き処理
-        let frame_interval = frame_skip + 1;
-        if (frame_count - 1) % frame_interval != 0 {
-            continue;
-        }
-        
-        let buffer = sample.buffer().ok_or("バッファ取得失敗")?;
-        let caps = sample.caps().ok_or("Caps取得失敗")?;
-        
-        let video_info = gst_video::VideoInfo::from_caps(caps)
-            .map_err(|e| format!("VideoInfo取得失敗: {:?}", e))?;
-        
-        let width = video_info.width() as u32;
-        let height = video_info.height() as u32;
+        let width = frame_img.width();
+        let height = frame_img.height();
         
         // 動画サイズチェック（初回のみ）
-        if !size_checked {
-            if width != expected_width || height != expected_height {
-                pipeline.set_state(gst::State::Null).ok();
-                return Err(format!(
-                    "動画サイズが不一致: 動画={}x{}, モデル={}x{}",
+        if tile_layout.is_none() {
+            if (width != expected_width || height != expected_height) && !scale_to_video {
+                return Err(anyhow::anyhow!(
+                    "動画サイズが不一致: 動画={}x{}, モデル={}x{}（解像度の補正を有効にすると領域を拡大縮小して処理します）",
                     width, height, expected_width, expected_height
                 ));
             }
-            size_checked = true;
-        }
-        
-        // 進捗報告（30フレーム毎）
-        if frame_count % 30 == 0 {
-            on_progress.send(ExtractionProgress {
-                current_frame: frame_count,
-                total_frames: 0, // 総フレーム数は不明
-                message: format!("フレーム {} 処理中 ({} タイル分類済み)...", frame_count, total_tiles),
-            }).ok();
+            let layout = TileLayout::new(&metadata, width, height);
+            if layout.scaled {
+                println!(
+                    "[タイル分類] 動画サイズ {}x{} に合わせてタイル領域を補正します（モデル: {}x{}, タイル: {}x{}）",
+                    width, height, expected_width, expected_height, layout.width, layout.height
+                );
+            }
+            tile_layout = Some(layout);
         }
-        
-        // バッファをマップ
-        let map = buffer.map_readable()
-            .map_err(|_| "バッファマップ失敗")?;
-        let data = map.as_slice();
-        // 行のバイト幅（stride）を取得して、パディングを考慮したオフセット計算を行う
-        let stride_vals = video_info.stride(); // returns &[i32]
-        let stride = if let Some(&s) = stride_vals.get(0) {
-            let s = if s < 0 { -s } else { s };
-            s as usize
-        } else {
-            // フォールバック: 幅 * 3
-            (width as usize) * 3
-        };
-        
+        let layout = tile_layout.as_ref().expect("tile layout");
+    
+        // 進捗報告（設定の間隔で間引く）
+        progress.frame(frame_count, || format!("({} タイル分類済み)", total_tiles));
+    
+        let data = frame_img.as_raw();
+        let stride = width as usize * 3;
+    
         // 各タイルを切り出して分類（バッチ化）
         // 1行分のタイルをまずメモリ上で収集
         let mut frame_tiles: Vec<image::RgbImage> = Vec::with_capacity(metadata.columns_per_row as usize);
-        for col in 0..metadata.columns_per_row {
-            let tile_x = metadata.tile_x as u32 + (col as u32 * metadata.tile_width as u32);
-            let tile_y = metadata.tile_y as u32; // row == 0
+        for &tile_x in &layout.columns {
+            let tile_y = layout.y; // row == 0
 
-            if tile_x + metadata.tile_width as u32 > width || tile_y + metadata.tile_height as u32 > height {
+            if tile_x + layout.width > width || tile_y + layout.height > height {
                 // 範囲外はダミータイルを入れずスキップ
                 continue;
             }
 
-            let mut tile_img = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(
-                metadata.tile_width as u32,
-                metadata.tile_height as u32,
-            );
-
-            for ty in 0..metadata.tile_height as u32 {
-                for tx in 0..metadata.tile_width as u32 {
-                    let src_x = tile_x + tx;
-                    let src_y = tile_y + ty;
-                    let src_idx = (src_y as usize * stride) + (src_x as usize * 3);
-
-                    if src_idx + 2 < data.len() {
-                        tile_img.put_pixel(tx, ty, Rgb([
-                            data[src_idx],
-                            data[src_idx + 1],
-                            data[src_idx + 2],
-                        ]));
-                    }
-                }
-            }
+            // 行単位でコピー
+            let Some(tile_img) = crate::video::crop_rgb_rows(data, stride, tile_x, tile_y, layout.width, layout.height) else {
+                continue;
+            };
 
-            frame_tiles.push(tile_img);
+            frame_tiles.push(layout.to_model_size(tile_img));
         }
 
         // バッチサイズはモデルの列数
@@ -581,81 +1172,67 @@ pub fn extract_and_classify_tiles(
             // フォールバック: 個別分類
             for (i, tile) in frame_tiles.into_iter().enumerate() {
                 let class_idx = engine.predict_from_rgb_image(&tile)
-                    .map_err(|e| format!("分類エラー: {}", e))?;
+                    .map_err(|e| anyhow::anyhow!("分類エラー: {}", e))?;
                 let class_name = class_labels.get(class_idx)
-                    .ok_or(format!("クラスインデックス {} が範囲外（クラス数: {}）", class_idx, class_labels.len()))?;
+                    .ok_or_else(|| anyhow::anyhow!("クラスインデックス {} が範囲外（クラス数: {}）", class_idx, class_labels.len()))?;
 
                 let tile_id = i + 1;
                 let tile_filename = format!("{}_frame={}_tile={}.png", video_stem, frame_count, tile_id);
                 let tile_path = video_output_dir.join(class_name).join(&tile_filename);
                 let dynamic_img = image::DynamicImage::ImageRgb8(tile);
                 save_as_uncompressed_png(&dynamic_img, &tile_path)
-                    .map_err(|e| format!("タイル保存エラー: {}", e))?;
+                    .map_err(|e| anyhow::anyhow!("タイル保存エラー: {}", e))?;
+                written_tiles.push(tile_path);
                 drop(dynamic_img);
 
                 *tile_count.entry(class_name.clone()).or_insert(0) += 1;
                 total_tiles += 1;
             }
         } else {
-            // チャンク毎にバッチ分類（WGPUなら真のバッチ、NdArrayはチャンク内個別分類にフォールバック）
+            // チャンク（1行分）ごとに1回の forward でバッチ分類
             for (chunk_idx, chunk) in frame_tiles.chunks(batch_size).enumerate() {
-                match &engine {
-                    InferenceEngine::Wgpu { .. } => {
-                        let labels = engine.classify_batch_from_images(chunk)
-                            .map_err(|e| format!("バッチ分類エラー: {}", e))?;
-
-                        for (j, class_name) in labels.into_iter().enumerate() {
-                            let tile_index = chunk_idx * batch_size + j;
-                            let tile_id = tile_index + 1;
-                            // 範囲チェック
-                            if tile_index >= frame_tiles.len() { continue; }
-
-                            let tile = &frame_tiles[tile_index];
-                            let tile_filename = format!("{}_frame={}_tile={}.png", video_stem, frame_count, tile_id);
-                            let tile_path = video_output_dir.join(&class_name).join(&tile_filename);
-                            let dynamic_img = image::DynamicImage::ImageRgb8(tile.clone());
-                            save_as_uncompressed_png(&dynamic_img, &tile_path)
-                                .map_err(|e| format!("タイル保存エラー: {}", e))?;
-                            drop(dynamic_img);
-
-                            *tile_count.entry(class_name.clone()).or_insert(0) += 1;
-                            total_tiles += 1;
-                        }
-                    }
-                    InferenceEngine::NdArray { .. } => {
-                        // CPUでは既存の個別推論をチャンク単位で実行
-                        for (j, tile) in chunk.iter().enumerate() {
-                            let tile_index = chunk_idx * batch_size + j;
-                            let class_name = engine.classify_image_direct(tile)
-                                .map_err(|e| format!("分類エラー: {}", e))?;
-
-                            let tile_id = tile_index + 1;
-                            let tile_filename = format!("{}_frame={}_tile={}.png", video_stem, frame_count, tile_id);
-                            let tile_path = video_output_dir.join(&class_name).join(&tile_filename);
-                            let dynamic_img = image::DynamicImage::ImageRgb8(tile.clone());
-                            save_as_uncompressed_png(&dynamic_img, &tile_path)
-                                .map_err(|e| format!("タイル保存エラー: {}", e))?;
-                            drop(dynamic_img);
-
-                            *tile_count.entry(class_name.clone()).or_insert(0) += 1;
-                            total_tiles += 1;
-                        }
-                    }
+                let labels = engine.classify_batch_from_images(chunk)
+                    .map_err(|e| anyhow::anyhow!("バッチ分類エラー: {}", e))?;
+
+                for (j, class_name) in labels.into_iter().enumerate() {
+                    let tile_index = chunk_idx * batch_size + j;
+                    let tile_id = tile_index + 1;
+                    // 範囲チェック
+                    if tile_index >= frame_tiles.len() { continue; }
+
+                    let tile = &frame_tiles[tile_index];
+                    let tile_filename = format!("{}_frame={}_tile={}.png", video_stem, frame_count, tile_id);
+                    let tile_path = video_output_dir.join(&class_name).join(&tile_filename);
+                    let dynamic_img = image::DynamicImage::ImageRgb8(tile.clone());
+                    save_as_uncompressed_png(&dynamic_img, &tile_path)
+                        .map_err(|e| anyhow::anyhow!("タイル保存エラー: {}", e))?;
+                    written_tiles.push(tile_path);
+                    drop(dynamic_img);
+
+                    *tile_count.entry(class_name.clone()).or_insert(0) += 1;
+                    total_tiles += 1;
                 }
             }
         }
-    }
+        Ok(())
+    });
     
-    // パイプラインを停止
-    pipeline.set_state(gst::State::Null)
-        .map_err(|e| format!("パイプライン停止失敗: {:?}", e))?;
+    // キャンセルされたら、このジョブで保存したタイルを削除する
+    if job.token().is_cancelled() {
+        if output_dir_existed {
+            for tile_path in &written_tiles {
+                fs::remove_file(tile_path).ok();
+            }
+        } else {
+            fs::remove_dir_all(&video_output_dir).ok();
+        }
+        println!("[タイル分類] キャンセルされました（{} タイルを削除）", written_tiles.len());
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+    result.map_err(|e| format!("フレーム処理エラー: {:#}", e))?;
     
     // 最終進捗報告
-    on_progress.send(ExtractionProgress {
-        current_frame: frame_count,
-        total_frames: frame_count,
-        message: "分類完了".to_string(),
-    }).ok();
+    progress.finish(frame_count, "分類完了");
     
     // 結果サマリー作成（メタデータの順序でソート、0枚のクラスも含む）
     // 正しい順序: dir_1, dir_2, dir_3, dir_4, dir_6, dir_7, dir_8, dir_9, <ボタンリスト>, others
@@ -704,6 +1281,36 @@ pub fn extract_input_history(
     Err("機械学習機能が有効化されていません".to_string())
 }
 
+#[cfg(not(feature = "ml"))]
+#[tauri::command]
+pub fn extract_input_history_regions(
+    _video_path: String,
+    _model_path: String,
+    _output_csv_path: String,
+) -> Result<Vec<String>, String> {
+    Err("機械学習機能が有効化されていません".to_string())
+}
+
+#[cfg(not(feature = "ml"))]
+#[tauri::command]
+pub fn render_overlay_video(
+    _video_path: String,
+    _model_path: String,
+    _output_path: String,
+) -> Result<u32, String> {
+    Err("機械学習機能が有効化されていません".to_string())
+}
+
+#[cfg(not(feature = "ml"))]
+#[tauri::command]
+pub fn compare_recording_with_sequence(
+    _csv_path: String,
+    _video_path: String,
+    _model_path: String,
+) -> Result<crate::roundtrip::RoundtripReport, String> {
+    Err("機械学習機能が有効化されていません".to_string())
+}
+
 #[cfg(not(feature = "ml"))]
 #[derive(Debug, Clone, Serialize)]
 pub struct TrainingProgress {
@@ -715,6 +1322,9 @@ pub struct TrainingProgress {
     pub val_accuracy: f64,
     pub message: String,
     pub log_lines: Vec<String>,
+    pub sec_per_epoch: f64,
+    pub elapsed_sec: f64,
+    pub eta_sec: Option<f64>,
 }
 
 #[cfg(not(feature = "ml"))]
@@ -732,6 +1342,18 @@ pub async fn train_classification_model(
     Err("機械学習機能が有効化されていません".to_string())
 }
 
+#[cfg(not(feature = "ml"))]
+#[tauri::command]
+pub fn get_model_training_history(_model_path: String) -> Result<Option<crate::model::LearningCurve>, String> {
+    Err("機械学習機能が有効化されていません".to_string())
+}
+
+#[cfg(not(feature = "ml"))]
+#[tauri::command]
+pub fn evaluate_model(_model_path: String, _labeled_dir: String, _use_gpu: bool) -> Result<String, String> {
+    Err("機械学習機能が有効化されていません".to_string())
+}
+
 #[cfg(not(feature = "ml"))]
 #[tauri::command]
 pub fn classify_video_tiles(
@@ -876,16 +1498,46 @@ pub fn get_button_labels_from_data_dir(_data_dir: String) -> Result<Vec<String>,
 /// MP4動画からシーケンスCSVを生成（進捗通知付き）
 /// 
 /// extract_input_historyと同じ処理だが、出力パスを自動生成
+/// model_path を省略（または空文字）した場合は、動画の解像度に一致するモデルをレジストリから自動選択する
+/// job_id を指定すると cancel_job で中断できる（中断時は途中まで書いたCSVを削除する）
+/// 分類結果は extract_input_history と同じく "extraction-frame" イベントで通知する
 #[cfg(feature = "ml")]
 #[tauri::command]
 pub async fn mp4_to_sequence(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
     video_path: String,
-    model_path: String,
+    model_path: Option<String>,
+    backend: String,
+    job_id: Option<String>,
+    on_progress: tauri::ipc::Channel<ExtractionProgress>,
+) -> Result<String, String> {
+    let job = state.jobs.register(JobKind::Extraction, job_id, video_path.clone())?;
+    run_job_blocking(app, job, move |app, state, job| {
+        run_mp4_to_sequence(app, state, video_path, model_path, backend, job, on_progress)
+    })
+    .await
+}
+
+#[cfg(feature = "ml")]
+fn run_mp4_to_sequence(
+    app: &tauri::AppHandle,
+    state: &crate::AppState,
+    video_path: String,
+    model_path: Option<String>,
     backend: String,
+    job: &JobGuard,
     on_progress: tauri::ipc::Channel<ExtractionProgress>,
 ) -> Result<String, String> {
     use std::path::Path;
-    
+
+    let config = AppConfig::load_or_default();
+    let model_path = match model_path.filter(|p| !p.is_empty()) {
+        // レジストリの登録名が指定された場合はパスに解決
+        Some(name_or_path) => config.resolve_model_path(&name_or_path),
+        None => select_model_for_video(&config, &video_path)?,
+    };
+
     // 出力CSVパスを生成（動画と同じディレクトリに_input_history.csvを追加）
     let video_path_obj = Path::new(&video_path);
     let stem = video_path_obj.file_stem()
@@ -908,7 +1560,7 @@ pub async fn mp4_to_sequence(
     
     // 動画情報を取得して総フレーム数を計算
     use crate::video::FrameExtractor;
-    let video_info = FrameExtractor::get_video_info(&video_path)
+    let video_info = FrameExtractor::get_video_info(&video_path, config.button_tile.video_transform)
         .map_err(|e| format!("動画情報取得エラー: {}", e))?;
     let estimated_total_frames = (video_info.duration_sec * video_info.fps) as u32;
     
@@ -916,223 +1568,107 @@ pub async fn mp4_to_sequence(
         estimated_total_frames, video_info.duration_sec, video_info.fps);
     
     // 初期進捗を送信
+    let mut progress = ProgressReporter::new(&on_progress, job, "MP4→CSV", estimated_total_frames);
     println!("[MP4→CSV] 進捗通知: 推論エンジンを初期化中...");
-    on_progress.send(ExtractionProgress {
-        current_frame: 0,
-        total_frames: estimated_total_frames,
-        message: "推論エンジンを初期化中...".to_string(),
-    }).ok();
+    progress.message("推論エンジンを初期化中...");
     
     // バックエンド設定
     let use_gpu = backend == "wgpu";
     println!("[MP4→CSV] バックエンド設定: use_gpu={}", use_gpu);
+
+    // GPU使用時は学習など他のGPUジョブの終了を待つ
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| progress.message(message))
+    }).transpose().map_err(|e| e.to_string())?;
     
     // 推論エンジンを初期化（バックエンド指定）
-    println!("[MP4→CSV] InferenceEngine::load_with_backend 呼び出し開始");
-    let engine = InferenceEngine::load_with_backend(&PathBuf::from(&model_path), use_gpu)
+    println!("[MP4→CSV] 推論エンジンの取得開始");
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
         .map_err(|e| format!("推論エンジンの初期化エラー: {}", e))?;
-    println!("[MP4→CSV] InferenceEngine::load_with_backend 呼び出し完了");
+    println!("[MP4→CSV] 推論エンジンの取得完了");
     
     // エンジン初期化完了の通知
-    on_progress.send(ExtractionProgress {
-        current_frame: 0,
-        total_frames: estimated_total_frames,
-        message: "モデル読み込み完了。フレーム処理を準備中...".to_string(),
-    }).ok();
-    
-    // メタデータから領域設定を取得
-    println!("[MP4→CSV] メタデータ読み込み開始");
-    let metadata = load_metadata(&PathBuf::from(&model_path))
-        .map_err(|e| format!("メタデータ読み込みエラー: {}", e))?;
-    println!("[MP4→CSV] メタデータ読み込み完了");
-    
-    let button_labels = metadata.button_labels.clone();
-    
-    // メタデータの値をデバッグ出力
-    println!("[MP4→CSV] モデルメタデータ:");
-    println!("  tile_x: {}, tile_y: {}", metadata.tile_x, metadata.tile_y);
-    println!("  tile_width: {}, tile_height: {} (領域全体)", metadata.tile_width, metadata.tile_height);
-    println!("  image_width: {}, image_height: {} (個々のタイル)", metadata.image_width, metadata.image_height);
-    println!("  columns_per_row: {}", metadata.columns_per_row);
-    println!("  button_labels: {:?}", metadata.button_labels);
-    
-    // 領域全体のサイズを計算（個々のタイルサイズ × 列数）
-    // 注意: tile_widthは領域全体の幅、image_widthが個々のタイルサイズ
-    let tile_size = metadata.image_width; // 個々のタイルサイズ（48x48）
-    let total_width = tile_size * metadata.columns_per_row;
-    let total_height = tile_size; // 1行のみ
+    progress.message("モデル読み込み完了。フレーム処理を準備中...");
     
-    println!("[MP4→CSV] 計算された領域:");
-    println!("  tile_size: {}", tile_size);
-    println!("  total_width: {} ({}x{})", total_width, tile_size, metadata.columns_per_row);
-    println!("  total_height: {}", total_height);
-    
-    let region = InputIndicatorRegion {
-        x: metadata.tile_x,
-        y: metadata.tile_y,
-        width: total_width,
-        height: total_height,
-        rows: 1,
-        cols: metadata.columns_per_row,
-    };
-    
-    println!("[MP4→CSV] InputIndicatorRegion: x={}, y={}, width={}, height={}, rows={}, cols={}",
-        region.x, region.y, region.width, region.height, region.rows, region.cols);
-    
-    // CSV出力準備
-    let mut csv_writer = csv::Writer::from_path(&output_csv_path)
-        .map_err(|e| format!("CSV作成エラー: {}", e))?;
-    
-    // ヘッダー行を書き込み
-    let mut header = vec!["duration".to_string(), "direction".to_string()];
-    header.extend(button_labels.clone());
-    csv_writer.write_record(&header)
-        .map_err(|e| format!("CSVヘッダー書き込みエラー: {}", e))?;
-    
-    // 入力状態の履歴
-    let mut previous_state: Option<InputState> = None;
-    let mut duration = 0u32;
-    let mut total_frames = 0u32;
-    let mut sequence_steps = 0u32; // シーケンスステップ数
-    
-    // フレーム抽出設定（output_dirは使用しない）
-    let frame_config = FrameExtractorConfig {
-        frame_interval: 1, // 全フレーム
-        output_dir: PathBuf::from("."), // ダミー（使用しない）
-        image_format: "png".to_string(),
-        jpeg_quality: 95,
-    };
-    
-    let extractor = FrameExtractor::new(frame_config);
-    
-    println!("[MP4→CSV] フレーム処理開始");
-    
-    // フレーム処理開始の進捗を送信
-    on_progress.send(ExtractionProgress {
-        current_frame: 0,
-        total_frames: estimated_total_frames,
-        message: "フレーム処理を開始...".to_string(),
-    }).ok();
-    println!("[MP4→CSV] 進捗通知: フレーム処理を開始...");
-    
-    // 同期処理: フレーム抽出とタイル推論を同じスレッド内で実行
-    println!("[MP4→CSV] process_frames_sync 呼び出し開始");
-    extractor.process_frames_sync_with_crop(&video_path, Some(region.clone()), |frame_img, frame_num| {
-        total_frames = frame_num + 1;
-        
-        // 最初のフレームで確認ログ
-        if frame_num == 0 {
-            println!("[MP4→CSV] 最初のフレームを受信");
-        }
-        
-        // 進捗通知
-        println!("[MP4→CSV] フレーム {} 処理中 ({}%)", 
-            frame_num, 
-            (frame_num as f32 / estimated_total_frames as f32 * 100.0) as u32);
-        on_progress.send(ExtractionProgress {
-            current_frame: frame_num,
-            total_frames: estimated_total_frames,
-            message: format!("{}フレーム処理中... ({}%)", 
-                frame_num, 
-                (frame_num as f32 / estimated_total_frames as f32 * 100.0) as u32),
-        }).ok();
-        
-        // AppSinkに渡される画像は既に領域全体でクロップ済み
-        let cropped_region = crate::analyzer::InputIndicatorRegion {
-            x: 0,
-            y: 0,
-            width: region.width,
-            height: region.height,
-            rows: region.rows,
-            cols: region.cols,
-        };
+    // ボタン列の並び（設定に応じてボタンマッピングの順序・列名に合わせる）
+    let columns = crate::analyzer::output_columns_for(
+        &engine.config().button_labels,
+        &config.generation,
+        &state.button_order.lock().unwrap(),
+    );
+    println!("[MP4→CSV] 出力列: {:?}", columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>());
 
-        if frame_num == 0 {
-            println!("[MP4→CSV] フレーム0: タイル抽出開始 (クロップ済み画像)");
-        }
-        let tiles = crate::analyzer::extract_tiles_from_image(frame_img, &cropped_region)
-            .map_err(|e| anyhow::anyhow!("タイル抽出エラー: {}", e))?;
-        if frame_num == 0 {
-            println!("[MP4→CSV] フレーム0: タイル抽出完了 ({}個)", tiles.len());
-        }
-        
-        // 各タイルを推論（メモリ上で直接処理）
-        let mut current_state = InputState::new();
-        
-        for (i, tile) in tiles.into_iter().enumerate() {
-            if frame_num == 0 && i == 0 {
-                println!("[MP4→CSV] フレーム0: 最初のタイル処理開始（直接推論）");
-            }
-            
-            // ファイルI/Oなしで直接推論
-            let class_name = engine.classify_image_direct(&tile)
-                .map_err(|e| anyhow::anyhow!("推論エラー: {}", e))?;
-            
-            if frame_num == 0 && i == 0 {
-                println!("[MP4→CSV] フレーム0: 最初のタイル推論完了 (クラス: {})", class_name);
-            }
-            
-            // 入力状態に反映
-            crate::analyzer::update_input_state(&mut current_state, &class_name);
-        }
-        
-        if frame_num == 0 {
-            println!("[MP4→CSV] フレーム0: 全タイル処理完了");
-        }
-        
-        // 状態が変化したらCSVに書き込み
-        if let Some(ref prev) = previous_state {
-            if prev != &current_state {
-                let line = prev.to_csv_line(duration, &button_labels);
-                csv_writer.write_record(line.split(','))
-                    .map_err(|e| anyhow::anyhow!("CSV書き込みエラー: {}", e))?;
-                sequence_steps += 1;
-                println!("[MP4→CSV] シーケンス#{}: duration={}F ({:.2}秒)", 
-                    sequence_steps, duration, duration as f32 / 60.0);
-                duration = 1;
-            } else {
-                duration += 1;
-            }
-        } else {
-            duration = 1;
-        }
-        
-        previous_state = Some(current_state);
-        
-        Ok(())
-    }).map_err(|e| format!("フレーム処理エラー: {}", e))?;
-    
-    // 最後の状態を書き込み
-    if let Some(ref state) = previous_state {
-        let line: String = state.to_csv_line(duration, &button_labels);
-        csv_writer.write_record(line.split(','))
-            .map_err(|e| format!("CSV書き込みエラー: {}", e))?;
-        sequence_steps += 1;
-        println!("[MP4→CSV] シーケンス#{}: duration={}F ({:.2}秒) - 最終ステップ", 
-            sequence_steps, duration, duration as f32 / 60.0);
-    }
-    
-    csv_writer.flush()
-        .map_err(|e| format!("CSVフラッシュエラー: {}", e))?;
-    
+    let mut frame_events = FrameEventEmitter::new(app, job);
+    progress.message("フレーム処理を開始...");
+
+    // extract_input_history と同じ抽出処理（CPU バックエンドではデコードと分類を並列に行う）
+    let total_frames = crate::ml::extract_input_history_to_csv(
+        &engine,
+        video_path_obj,
+        &output_csv_path,
+        &columns,
+        &crate::analyzer::StepPostProcess::from_config(&config),
+        crate::video::TimeRange::default(),
+        |frame_num| {
+            job.token().check()?;
+            progress.frame(frame_num, String::new);
+            Ok(())
+        },
+        |frame_num, classification| frame_events.emit(frame_num, classification),
+    ).map_err(|e| job_error(job, format!("フレーム処理エラー: {:#}", e)))?;
+
+    let sequence_steps = crate::csv_loader::load_csv(&output_csv_path)
+        .map(|frames| frames.len() as u32)
+        .map_err(|e| format!("生成したCSVの読み込みに失敗: {}", e))?;
     println!("[MP4→CSV] 完了: {}フレーム → {}シーケンスステップ (平均: {:.1}F/ステップ)", 
         total_frames, sequence_steps, total_frames as f32 / sequence_steps.max(1) as f32);
     
     // 完了通知
-    on_progress.send(ExtractionProgress {
-        current_frame: total_frames,
-        total_frames: total_frames,
-        message: format!("完了: {}シーケンスステップを生成", sequence_steps),
-    }).ok();
+    progress.finish(total_frames, format!("完了: {}シーケンスステップを生成", sequence_steps));
     
     Ok(output_csv_str)
 }
 
+/// 動画の解像度と学習時の動画サイズが一致するモデルをレジストリから選択
+///
+/// 一致するモデルが複数ある場合は最初に登録されたものを使用する
+#[cfg(feature = "ml")]
+fn select_model_for_video(config: &AppConfig, video_path: &str) -> Result<String, String> {
+    let video_info = FrameExtractor::get_video_info(video_path, config.button_tile.video_transform)
+        .map_err(|e| format!("動画情報取得エラー: {}", e))?;
+    let (width, height) = (video_info.width as u32, video_info.height as u32);
+
+    let mut candidates = config.model_registry.iter().filter(|entry| {
+        match load_metadata(&PathBuf::from(&entry.path)) {
+            Ok(metadata) => metadata.video_width == width && metadata.video_height == height,
+            Err(e) => {
+                eprintln!("[MP4→CSV] 登録モデル {} のメタデータ読み込みに失敗: {}", entry.name, e);
+                false
+            }
+        }
+    });
+
+    let selected = candidates.next().ok_or_else(|| {
+        format!(
+            "動画サイズ {}x{} に一致するモデルがレジストリに登録されていません。モデルを指定してください",
+            width, height
+        )
+    })?;
+    if let Some(other) = candidates.next() {
+        println!(
+            "[MP4→CSV] {}x{} に一致するモデルが複数あります。{} を使用します（{} など）",
+            width, height, selected.name, other.name
+        );
+    }
+    println!("[MP4→CSV] 動画サイズ {}x{} からモデルを自動選択: {}", width, height, selected.name);
+    Ok(selected.path.clone())
+}
+
 #[cfg(not(feature = "ml"))]
 #[tauri::command]
 pub fn mp4_to_sequence(
     _video_path: String,
-    _model_path: String,
+    _model_path: Option<String>,
     _backend: String,
 ) -> Result<String, String> {
     Err("機械学習機能が有効化されていません".to_string())
@@ -1238,3 +1774,145 @@ pub fn validate_mapping_and_training_data(
 ) -> Result<(), String> {
     Err("機械学習機能が有効化されていません".to_string())
 }
+
+/// ライブ解析（キャプチャデバイス・画面キャプチャ）で入力状態が変化したときに送るイベント名（ペイロードは [`LiveInputEvent`]）
+pub const LIVE_INPUT_EVENT: &str = "live-input";
+
+/// ライブ解析で認識した入力
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveInputEvent {
+    pub job_id: String,
+    /// 解析開始からのフレーム番号
+    pub frame: u32,
+    pub direction: u8,
+    /// ボタン名 -> 状態 (0 or 1)
+    pub buttons: std::collections::HashMap<String, u8>,
+    /// 直前の入力状態が続いたフレーム数（最初の入力は 0）
+    pub previous_duration: u32,
+}
+
+/// キャプチャデバイス（Webカメラ・キャプチャボード）の映像をリアルタイムに解析する
+///
+/// 入力状態が変化するたびに live-input イベントを送る。
+/// 解析は別スレッドで続け、このコマンドはジョブIDをすぐに返す。
+/// cancel_job(job_id) で停止する（停止した場合も正常終了として扱う）。終了・失敗は job-finished イベントで通知する。
+#[cfg(feature = "ml")]
+#[tauri::command]
+pub async fn start_live_analysis(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    device_index: u32,
+    model_path: String,
+    use_gpu: bool,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    let job = state.jobs.register(JobKind::LiveAnalysis, job_id, format!("キャプチャデバイス {}", device_index))?;
+    let source = crate::video::LiveSource::CaptureDevice { device_index };
+    Ok(spawn_live_analysis(app, job, source, model_path, use_gpu))
+}
+
+/// 画面キャプチャ（同じPCで動かしているゲームの画面）をリアルタイムに解析する
+///
+/// 入力表示の領域はモデルのメタデータを使用する（画面上の座標として扱う）。
+/// イベントと停止方法は start_live_analysis と同じ（ジョブIDをすぐに返す）。
+#[cfg(feature = "ml")]
+#[tauri::command]
+pub async fn start_screen_analysis(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    monitor_index: u32,
+    model_path: String,
+    use_gpu: bool,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    let job = state.jobs.register(JobKind::LiveAnalysis, job_id, format!("画面キャプチャ（モニター {}）", monitor_index))?;
+    let source = crate::video::LiveSource::Screen { monitor_index };
+    Ok(spawn_live_analysis(app, job, source, model_path, use_gpu))
+}
+
+/// ライブ解析を別スレッドで開始してジョブIDを返す（結果はジョブに記録する）
+#[cfg(feature = "ml")]
+fn spawn_live_analysis(
+    app: tauri::AppHandle,
+    job: JobGuard,
+    source: crate::video::LiveSource,
+    model_path: String,
+    use_gpu: bool,
+) -> String {
+    let job_id = job.id().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<crate::AppState>();
+        let result = run_live_analysis(&app, &state, &source, model_path, use_gpu, &job);
+        if let Err(e) = &result {
+            println!("[Live] {}", e);
+        }
+        job.finish(result).ok();
+    });
+    job_id
+}
+
+#[cfg(feature = "ml")]
+fn run_live_analysis(
+    app: &tauri::AppHandle,
+    state: &crate::AppState,
+    source: &crate::video::LiveSource,
+    model_path: String,
+    use_gpu: bool,
+    job: &JobGuard,
+) -> Result<String, String> {
+    use tauri::Emitter;
+
+    let config = AppConfig::load_or_default();
+    let model_path = config.resolve_model_path(&model_path);
+
+    // GPU使用時は他のGPUジョブの終了を待つ（解析中は GPU を占有する）
+    let _gpu_guard = use_gpu.then(|| {
+        state.gpu_scheduler.acquire(GpuJobKind::Inference, job.token(), |message| {
+            job.set_progress(0, 0, &message);
+        })
+    }).transpose().map_err(|e| e.to_string())?;
+
+    let engine = state.engine_cache.checkout(&model_path, use_gpu)
+        .map_err(|e| format!("推論エンジンの初期化エラー: {}", e))?;
+    job.token().check().map_err(|e| e.to_string())?;
+    job.set_progress(0, 0, "解析中...");
+
+    let mut changes = 0u64;
+    let total_frames = crate::ml::run_live_analysis(&engine, source, job.token(), |frame, input, previous_duration| {
+        changes += 1;
+        job.set_progress(changes, 0, &format!("{}回目の入力変化（フレーム {}）", changes, frame));
+        app.emit(LIVE_INPUT_EVENT, LiveInputEvent {
+            job_id: job.id().to_string(),
+            frame,
+            direction: input.direction,
+            buttons: input.buttons.clone(),
+            previous_duration,
+        }).ok();
+        Ok(())
+    }).map_err(|e| format!("ライブ解析に失敗: {:#}", e))?;
+
+    Ok(format!("ライブ解析を停止しました（{}フレーム処理）", total_frames))
+}
+
+#[cfg(not(feature = "ml"))]
+#[tauri::command]
+pub fn start_live_analysis(
+    _device_index: u32,
+    _model_path: String,
+    _use_gpu: bool,
+    _job_id: Option<String>,
+) -> Result<String, String> {
+    Err("機械学習機能が有効化されていません".to_string())
+}
+
+#[cfg(not(feature = "ml"))]
+#[tauri::command]
+pub fn start_screen_analysis(
+    _monitor_index: u32,
+    _model_path: String,
+    _use_gpu: bool,
+    _job_id: Option<String>,
+) -> Result<String, String> {
+    Err("機械学習機能が有効化されていません".to_string())
+}
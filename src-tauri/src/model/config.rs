@@ -3,8 +3,19 @@
 //! 計算デバイスやモデル設定などをJSON形式で保存・読み込みします。
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 設定ファイルを置くディレクトリ（アプリ起動時に init_config_dir で設定）
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 以前のバージョンで設定ファイルなどを置いていたディレクトリ（作業ディレクトリからの相対パス）
+const LEGACY_CONFIG_DIR: &str = "..";
+
+/// 設定ファイルのディレクトリに置くファイル（init_config_dir で以前の場所から移行する）
+const CONFIG_DIR_FILES: [&str; 2] = ["config.json", "training_history.json"];
 
 /// 計算デバイスの種類
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -21,6 +32,52 @@ impl Default for DeviceType {
     }
 }
 
+/// 動画のフレーム抽出に使うバックエンド
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoBackend {
+    /// GStreamer（標準）
+    #[default]
+    Gstreamer,
+    /// ffmpeg / ffprobe コマンド（ffmpeg feature が必要）
+    Ffmpeg,
+}
+
+/// 録画した動画の回転・反転の補正（スマートフォンでモニターを撮影した動画など）
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoTransform {
+    /// 補正しない
+    #[default]
+    None,
+    /// 時計回りに90度回転
+    Rotate90,
+    /// 180度回転
+    Rotate180,
+    /// 時計回りに270度回転（反時計回りに90度）
+    Rotate270,
+    /// 左右反転
+    FlipHorizontal,
+}
+
+impl VideoTransform {
+    /// 補正後に幅と高さが入れ替わるか
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(self, VideoTransform::Rotate90 | VideoTransform::Rotate270)
+    }
+}
+
+/// 入力表示から読み取った継続フレーム数の使い方
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameCountMode {
+    /// 数えたフレーム数を使い、読み取った値との不一致をログに出す
+    #[default]
+    CrossCheck,
+    /// 読み取れたステップは読み取った値を持続フレーム数に使う（録画でフレームが落ちた動画向け）
+    Recognized,
+}
+
 impl std::fmt::Display for DeviceType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -91,9 +148,13 @@ pub struct ButtonTileSettings {
     pub x: u32,
     /// 切り出し画像内での相対Y座標（メタデータ用参考値）
     pub y: u32,
-    /// タイルサイズ（正方形、ピクセル）
-    /// CNNの入力が正方形であるため、幅と高さは同一である必要がある
+    /// タイルの幅（ピクセル）
     pub tile_size: u32,
+    /// タイルの高さ（ピクセル、0 の場合は tile_size と同じ正方形）
+    ///
+    /// 横長のアイコンを使うゲーム向け。CNNの入力も同じ幅・高さになる。
+    #[serde(default)]
+    pub tile_height: u32,
     /// 解析対象列数: 継続フレーム数を除く列数 (方向キー1 + ボタン5 = 6列)
     /// 実際の列構成: [継続フレーム数] [方向またはボタン×6] = 計7列
     pub columns_per_row: u32,
@@ -103,6 +164,9 @@ pub struct ButtonTileSettings {
     /// 学習データ生成時の動画解像度（高さ）
     #[serde(default)]
     pub source_video_height: u32,
+    /// 動画の回転・反転の補正（解析範囲の座標は補正後の画像上の座標）
+    #[serde(default)]
+    pub video_transform: VideoTransform,
 }
 
 impl Default for ButtonTileSettings {
@@ -110,14 +174,332 @@ impl Default for ButtonTileSettings {
         Self {
             x: 80,      // メタデータ用参考値: 切り出し画像内での相対X座標
             y: 400,     // メタデータ用参考値: 切り出し画像内での相対Y座標
-            tile_size: 48, // タイルサイズ（正方形、48x48ピクセル）
+            tile_size: 48, // タイルの幅（48ピクセル）
+            tile_height: 0, // 0 は正方形（48x48ピクセル）
             columns_per_row: 6, // 解析対象の列数: 方向キー + ボタン5種
             source_video_width: 1920,
             source_video_height: 1080,
+            video_transform: VideoTransform::None,
+        }
+    }
+}
+
+impl ButtonTileSettings {
+    /// タイルの（幅, 高さ）
+    pub fn tile_dims(&self) -> (u32, u32) {
+        match self.tile_height {
+            0 => (self.tile_size, self.tile_size),
+            height => (self.tile_size, height),
+        }
+    }
+}
+
+/// グローバルホットキー設定
+///
+/// キー表記は "F9" や "Ctrl+Shift+P" の形式で指定します。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeySettings {
+    /// ホットキーを有効にするか
+    pub enabled: bool,
+    /// 再生開始のホットキー
+    pub start_playback: Option<String>,
+    /// 再生停止のホットキー
+    pub stop_playback: Option<String>,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_playback: Some("F9".to_string()),
+            stop_playback: Some("F10".to_string()),
+        }
+    }
+}
+
+/// 同期マーカー設定
+///
+/// 再生開始時にシーケンスより先に特徴的な入力を送信し、
+/// 録画した映像から生成したCSVと元のシーケンスの位置合わせに使えるようにする。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncMarkerSettings {
+    /// マーカーを送信するか
+    pub enabled: bool,
+    /// 押すボタン（コントローラーのボタン名、"button10" = START）
+    pub button: String,
+    /// ボタンを押し続けるフレーム数（その後同じフレーム数だけ離してからシーケンスを開始）
+    pub frames: u32,
+}
+
+impl Default for SyncMarkerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            button: "button10".to_string(),
+            frames: 3,
+        }
+    }
+}
+
+/// エディタの保存設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EditorSettings {
+    /// 上書き保存時に残すバックアップ（`<ファイル名>.1.bak` 〜）の数（0でバックアップしない）
+    #[serde(default)]
+    pub backup_count: u32,
+}
+
+/// 前回終了時の状態（起動時に restore_last_session で復元する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSettings {
+    /// 最後に読み込んだシーケンスファイル
+    pub last_sequence_path: Option<String>,
+    /// 使用中のボタンマッピングファイル
+    pub mapping_path: Option<String>,
+    pub fps: u32,
+    pub loop_playback: bool,
+    pub invert_horizontal: bool,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            last_sequence_path: None,
+            mapping_path: None,
+            fps: 60,
+            loop_playback: false,
+            invert_horizontal: false,
         }
     }
 }
 
+/// リモート操作サーバーの設定（`remote` feature）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSettings {
+    /// 起動時にサーバーを開始するか
+    pub enabled: bool,
+    /// 待ち受けアドレス（別のPCから操作する場合は "0.0.0.0" など）
+    pub bind_address: String,
+    pub port: u16,
+    /// 必須の認証トークン（"Authorization: Bearer <token>" か "?token=<token>"）。有効にしたときに未設定なら生成する
+    pub token: Option<String>,
+}
+
+/// 生成するトークンの長さ
+const REMOTE_TOKEN_LENGTH: usize = 32;
+
+impl RemoteSettings {
+    /// 有効なのにトークンが未設定ならランダムに生成する（生成した場合は true）
+    pub fn ensure_token(&mut self) -> bool {
+        if !self.enabled || self.token.as_deref().is_some_and(|token| !token.is_empty()) {
+            return false;
+        }
+        use rand::Rng;
+        let token: String = rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(REMOTE_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        self.token = Some(token);
+        true
+    }
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 17890,
+            token: None,
+        }
+    }
+}
+
+/// OBS の録画連携の設定（obs-websocket v5）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObsSettings {
+    /// 再生の開始・停止に合わせて OBS の録画を開始・停止するか
+    pub enabled: bool,
+    /// obs-websocket のURL
+    pub url: String,
+    /// obs-websocket の認証パスワード（認証を無効にしている場合は None）
+    pub password: Option<String>,
+    /// 録画ファイル名をシーケンス名にする
+    pub name_after_sequence: bool,
+}
+
+impl Default for ObsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "ws://127.0.0.1:4455".to_string(),
+            password: None,
+            name_after_sequence: true,
+        }
+    }
+}
+
+/// 読み込んだシーケンスのキャッシュ設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// キャッシュの推定メモリ使用量の上限（MB）。超えたら最後に使われたのが古いものから破棄する
+    pub max_megabytes: u32,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self { max_megabytes: crate::frame_cache::DEFAULT_MAX_MEGABYTES }
+    }
+}
+
+/// 動画解析（入力履歴の抽出・タイル分類）の進捗通知の間隔
+///
+/// 1フレームごとに通知するとIPCが詰まり抽出自体が遅くなるため間引く。
+/// 前回の通知から interval_ms 経過するか、every_frames フレーム進んだら通知する。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressSettings {
+    /// 通知の最小間隔（ミリ秒）
+    pub interval_ms: u64,
+    /// このフレーム数ごとにも通知する（0で時間のみ）
+    pub every_frames: u32,
+}
+
+impl Default for ProgressSettings {
+    fn default() -> Self {
+        Self { interval_ms: 200, every_frames: 0 }
+    }
+}
+
+/// テンキー表記（"236A 5B" など）からシーケンスを作成する際のフレーム数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotationSettings {
+    /// コマンドの途中の方向（"236A" の 2 と 3）1つあたりのフレーム数
+    pub motion_frames: u32,
+    /// 最後の方向とボタンを押すステップのフレーム数
+    pub press_frames: u32,
+    /// 技と技の間に入れる無入力のフレーム数（0で挿入しない）
+    pub gap_frames: u32,
+    /// コマンドごとの途中の方向のフレーム数（"623" → 3 など。motion_frames より優先）
+    pub motion_overrides: HashMap<String, u32>,
+}
+
+impl Default for NotationSettings {
+    fn default() -> Self {
+        Self {
+            motion_frames: 2,
+            press_frames: 4,
+            gap_frames: 0,
+            motion_overrides: HashMap::new(),
+        }
+    }
+}
+
+/// 動画から生成するシーケンスの後処理設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationSettings {
+    /// 先頭・末尾の無入力ステップを取り除く
+    #[serde(default)]
+    pub trim_dead_time: bool,
+    /// 途中の無入力ステップの最大フレーム数（0で制限なし）
+    #[serde(default)]
+    pub max_neutral_gap_frames: u32,
+    /// ボタン列を読み込み中のボタンマッピングのシーケンス順に並べ替える
+    ///
+    /// マッピングにあってモデルに無いボタンは常に0の列として追加する。
+    #[serde(default)]
+    pub use_mapping_column_order: bool,
+    /// 出力時の列名の変更（モデルのボタンラベル → 出力する列名）
+    #[serde(default)]
+    pub column_renames: HashMap<String, String>,
+    /// 入力表示領域が直前のフレームと完全に同じ画像のフレームは分類を省略し、直前のステップの持続フレーム数に加える
+    /// （60fps でキャプチャした 30fps のゲームなど、重複フレームの多い動画の解析を速くする）
+    #[serde(default)]
+    pub skip_duplicate_frames: bool,
+    /// 誤認識の平滑化: 各フレームを中心としたこのフレーム数（前後合わせた窓の大きさ。偶数は1つ増やす）の多数決で入力を決める（1以下で無効）
+    #[serde(default)]
+    pub smoothing_window_frames: u32,
+    /// 誤認識の平滑化: これより短いステップを直前のステップに統合する（0で無効）
+    #[serde(default)]
+    pub min_step_frames: u32,
+    /// 継続フレーム数の列を読み取る数字のテンプレート（0.png〜9.png）のフォルダ（None で読み取らない）
+    ///
+    /// 継続フレーム数の列は解析する列のすぐ左にある、タイルと同じ幅の列とみなす。
+    #[serde(default)]
+    pub frame_count_digits_dir: Option<String>,
+    /// 読み取った継続フレーム数の使い方
+    #[serde(default)]
+    pub frame_count_mode: FrameCountMode,
+    /// 継続フレーム数の表示の上限（この値以上の表示は数えたフレーム数を使う。0で上限なし）
+    #[serde(default)]
+    pub frame_count_display_max: u32,
+    /// 複数行解析で読む入力表示の行数（最下行を含む。0・1 は最下行だけを毎フレーム解析する）
+    ///
+    /// 各行の持続フレーム数は継続フレーム数の列から読み取るため、frame_count_digits_dir の設定が必要。
+    #[serde(default)]
+    pub history_rows: u32,
+    /// 複数行解析で全行を読むキーフレームの間隔（0 の場合は 行数 - 1）
+    #[serde(default)]
+    pub history_keyframe_interval: u32,
+}
+
+/// 再生設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaybackSettings {
+    /// 入力遅延の補正（フレーム数、符号付き）
+    ///
+    /// 全ステップの送信予定時刻をこのフレーム数だけずらす。
+    /// キャプチャーボードやBluetoothの遅延を打ち消す場合は負の値を指定する。
+    /// 負の値は時刻を指定した予約再生（schedule_playback）でのみ適用され、即時開始では無視される。
+    #[serde(default)]
+    pub latency_offset_frames: i32,
+    /// 再生開始時の同期マーカー
+    #[serde(default)]
+    pub sync_marker: SyncMarkerSettings,
+    /// 同じボタンが連続するステップ間に挿入するリリースのフレーム数（0で無効）
+    ///
+    /// 再生時に加え、動画から生成するCSVにも適用する。
+    #[serde(default)]
+    pub release_gap_frames: u32,
+    /// 予定時刻よりこのミリ秒以上遅れて送信したステップをログとイベントで通知する（0で無効）
+    pub late_step_warning_ms: u32,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            latency_offset_frames: 0,
+            sync_marker: SyncMarkerSettings::default(),
+            release_gap_frames: 0,
+            late_step_warning_ms: 8,
+        }
+    }
+}
+
+/// モデルレジストリのエントリ
+///
+/// 名前でモデルファイルを参照できるようにするための登録情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRegistryEntry {
+    /// 登録名（一意）
+    pub name: String,
+    /// モデルファイルのパス
+    pub path: String,
+    /// 対象ゲーム名
+    #[serde(default)]
+    pub game: Option<String>,
+    /// メモ
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
 /// アプリケーション設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -136,6 +518,42 @@ pub struct AppConfig {
     /// 学習データ生成の出力フォルダ（前回値を保存）
     #[serde(default)]
     pub training_output_dir: Option<String>,
+    /// グローバルホットキー設定
+    #[serde(default)]
+    pub hotkeys: HotkeySettings,
+    /// モデルレジストリ（登録名 → モデルファイル）
+    #[serde(default)]
+    pub model_registry: Vec<ModelRegistryEntry>,
+    /// 再生設定
+    #[serde(default)]
+    pub playback: PlaybackSettings,
+    /// シーケンス生成の後処理設定
+    #[serde(default)]
+    pub generation: GenerationSettings,
+    /// テンキー表記の読み込み設定
+    #[serde(default)]
+    pub notation: NotationSettings,
+    /// エディタの保存設定
+    #[serde(default)]
+    pub editor: EditorSettings,
+    /// シーケンスのキャッシュ設定
+    #[serde(default)]
+    pub cache: CacheSettings,
+    /// 前回終了時の状態
+    #[serde(default)]
+    pub session: SessionSettings,
+    /// リモート操作サーバーの設定
+    #[serde(default)]
+    pub remote: RemoteSettings,
+    /// OBS の録画連携の設定
+    #[serde(default)]
+    pub obs: ObsSettings,
+    /// 動画解析の進捗通知の間隔
+    #[serde(default)]
+    pub progress: ProgressSettings,
+    /// 動画のフレーム抽出に使うバックエンド
+    #[serde(default)]
+    pub video_backend: VideoBackend,
 }
 
 impl Default for AppConfig {
@@ -148,15 +566,57 @@ impl Default for AppConfig {
             last_video_path: None,
             last_output_dir: None,
             training_output_dir: None,
+            hotkeys: HotkeySettings::default(),
+            model_registry: Vec::new(),
+            playback: PlaybackSettings::default(),
+            generation: GenerationSettings::default(),
+            notation: NotationSettings::default(),
+            editor: EditorSettings::default(),
+            cache: CacheSettings::default(),
+            session: SessionSettings::default(),
+            remote: RemoteSettings::default(),
+            obs: ObsSettings::default(),
+            progress: ProgressSettings::default(),
+            video_backend: VideoBackend::default(),
         }
     }
 }
 
 impl AppConfig {
     /// 設定ファイルのデフォルトパス
+    ///
+    /// アプリではプラットフォームのアプリ設定ディレクトリ（init_config_dir で指定）に保存する。
+    /// 未設定の場合（CLIツールなど）は以前と同じ作業ディレクトリの親の config.json を使う。
     pub fn default_path() -> PathBuf {
-        // src-tauriディレクトリの監視を避けるため、親ディレクトリに保存
-        PathBuf::from("../config.json")
+        Self::config_file_path("config.json")
+    }
+
+    /// 設定ファイルのディレクトリに置くファイルのパス（学習履歴など。未設定の場合は以前の場所）
+    pub fn config_file_path(file_name: &str) -> PathBuf {
+        match CONFIG_DIR.get() {
+            Some(dir) => dir.join(file_name),
+            None => Path::new(LEGACY_CONFIG_DIR).join(file_name),
+        }
+    }
+
+    /// 設定ファイルのディレクトリを指定（起動時に1回だけ）
+    ///
+    /// 新しい場所に設定ファイルや学習履歴が無く以前の場所にある場合はコピーして移行する。
+    pub fn init_config_dir(dir: PathBuf) -> anyhow::Result<PathBuf> {
+        fs::create_dir_all(&dir)?;
+        for file_name in CONFIG_DIR_FILES {
+            let path = dir.join(file_name);
+            let legacy = Path::new(LEGACY_CONFIG_DIR).join(file_name);
+            if !path.exists() && legacy.exists() {
+                fs::copy(&legacy, &path)?;
+                println!("{} を移行しました: {} → {}", file_name, legacy.display(), path.display());
+            }
+        }
+        let path = dir.join("config.json");
+        CONFIG_DIR
+            .set(dir)
+            .map_err(|_| anyhow::anyhow!("設定ファイルのディレクトリは既に設定されています"))?;
+        Ok(path)
     }
 
     /// 設定を読み込む
@@ -216,6 +676,37 @@ impl AppConfig {
         self.model.model_path = path;
     }
 
+    /// 登録名からモデルを検索
+    pub fn find_registered_model(&self, name: &str) -> Option<&ModelRegistryEntry> {
+        self.model_registry.iter().find(|entry| entry.name == name)
+    }
+
+    /// モデルを登録（同名のエントリがあれば置き換える）
+    pub fn register_model(&mut self, entry: ModelRegistryEntry) {
+        if let Some(existing) = self.model_registry.iter_mut().find(|e| e.name == entry.name) {
+            *existing = entry;
+        } else {
+            self.model_registry.push(entry);
+        }
+    }
+
+    /// 登録名のモデルを削除（削除した場合は true）
+    pub fn unregister_model(&mut self, name: &str) -> bool {
+        let before = self.model_registry.len();
+        self.model_registry.retain(|entry| entry.name != name);
+        self.model_registry.len() != before
+    }
+
+    /// 登録名またはパスからモデルファイルのパスを解決
+    ///
+    /// レジストリに一致する登録名があればそのパスを、なければ入力をそのままパスとして返す
+    pub fn resolve_model_path(&self, name_or_path: &str) -> String {
+        match self.find_registered_model(name_or_path) {
+            Some(entry) => entry.path.clone(),
+            None => name_or_path.to_string(),
+        }
+    }
+
     /// 最後に使用したビデオファイルのパスを更新
     pub fn update_last_video_path<P: AsRef<Path>>(&mut self, path: P) {
         self.last_video_path = Some(path.as_ref().to_string_lossy().to_string());
@@ -240,7 +731,8 @@ impl AppConfig {
         println!("シード: {}", self.training.seed);
         println!("\n--- ボタンタイル設定 ---");
         println!("切り出し開始: ({}, {})", self.button_tile.x, self.button_tile.y);
-        println!("タイルサイズ: {}x{}", self.button_tile.tile_size, self.button_tile.tile_size);
+        let (tile_width, tile_height) = self.button_tile.tile_dims();
+        println!("タイルサイズ: {}x{}", tile_width, tile_height);
         println!("列数: {}", self.button_tile.columns_per_row);
 
         if let Some(ref video) = self.last_video_path {
@@ -280,4 +772,27 @@ mod tests {
         assert_eq!(format!("{}", DeviceType::Wgpu), "WGPU (GPU)");
         assert_eq!(format!("{}", DeviceType::Cpu), "CPU (NdArray)");
     }
+
+    #[test]
+    fn test_model_registry() {
+        let mut config = AppConfig::default();
+        config.register_model(ModelRegistryEntry {
+            name: "sf6".to_string(),
+            path: "models/sf6.tar.gz".to_string(),
+            game: None,
+            notes: None,
+        });
+        config.register_model(ModelRegistryEntry {
+            name: "sf6".to_string(),
+            path: "models/sf6_v2.tar.gz".to_string(),
+            game: None,
+            notes: None,
+        });
+
+        assert_eq!(config.model_registry.len(), 1);
+        assert_eq!(config.resolve_model_path("sf6"), "models/sf6_v2.tar.gz");
+        assert_eq!(config.resolve_model_path("models/other.tar.gz"), "models/other.tar.gz");
+        assert!(config.unregister_model("sf6"));
+        assert!(!config.unregister_model("sf6"));
+    }
 }
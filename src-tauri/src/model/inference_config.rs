@@ -14,6 +14,7 @@ use crate::model::model_storage;
 
 /// モデルメタデータから推論用情報を取得
 #[cfg(feature = "ml")]
+#[derive(Clone)]
 pub struct InferenceConfig {
     /// ボタンラベル一覧
     pub button_labels: Vec<String>,
@@ -63,6 +64,16 @@ impl InferenceConfig {
         Ok(Self::from_metadata(&metadata))
     }
 
+    /// モデル入力の（幅, 高さ）
+    ///
+    /// 幅は model_input_size。学習時のタイルがモデル入力と同じ幅の場合は、高さもタイルの高さに合わせる
+    /// （長方形のタイルで学習したモデル）。それ以外は正方形とみなす。
+    pub fn input_dims(&self) -> (usize, usize) {
+        let width = self.model_input_size;
+        let height = if self.image_width == width && self.image_height > 0 { self.image_height } else { width };
+        (width as usize, height as usize)
+    }
+
     /// ボタンラベルの数を取得
     pub fn num_button_labels(&self) -> usize {
         self.button_labels.len()
@@ -100,7 +111,8 @@ impl InferenceConfig {
         println!("入力画像解像度: {}x{}", self.image_width, self.image_height);
         println!("タイル範囲: ({}, {}) - {}x{}", self.tile_x, self.tile_y, self.tile_width, self.tile_height);
         println!("列数: {}", self.columns_per_row);
-        println!("モデル入力サイズ: {}x{}", self.model_input_size, self.model_input_size);
+        let (input_width, input_height) = self.input_dims();
+        println!("モデル入力サイズ: {}x{}", input_width, input_height);
         println!("総クラス数: {}", self.num_total_classes());
         println!("==================");
     }
@@ -2,8 +2,12 @@ pub mod model_metadata;
 pub mod model_storage;
 pub mod inference_config;
 pub mod config;
+pub mod region_preset;
+pub mod training_history;
 
-pub use model_metadata::ModelMetadata;
-pub use model_storage::{save_model_with_metadata, load_metadata, load_model_binary, load_model_with_metadata, print_metadata_info};
+pub use model_metadata::{ModelArchitecture, ModelMetadata, WeightPrecision};
+pub use model_storage::{save_model_with_metadata, load_metadata, load_model_binary, load_model_with_metadata, load_learning_curve, print_metadata_info};
 pub use inference_config::InferenceConfig;
-pub use config::{AppConfig, DeviceType, ModelSettings, TrainingSettings, ButtonTileSettings};
+pub use config::{AppConfig, DeviceType, ModelSettings, TrainingSettings, ButtonTileSettings, HotkeySettings, ModelRegistryEntry, PlaybackSettings, SyncMarkerSettings, GenerationSettings, NotationSettings, EditorSettings, CacheSettings, SessionSettings, RemoteSettings, ObsSettings, ProgressSettings, VideoBackend, VideoTransform, FrameCountMode};
+pub use region_preset::RegionPreset;
+pub use training_history::{LearningCurve, TrainingHistory, TrainingMetrics, TrainingRun};
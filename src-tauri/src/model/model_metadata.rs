@@ -13,6 +13,42 @@ use anyhow::{Context, Result};
 #[cfg(feature = "ml")]
 use serde::{Deserialize, Serialize};
 
+/// モデルの重みの保存精度
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightPrecision {
+    /// 32bit浮動小数点（従来の形式）
+    #[default]
+    F32,
+    /// 16bit浮動小数点（ファイルサイズは約半分。読み込み時に32bitへ戻すため推論はそのまま）
+    F16,
+}
+
+#[cfg(feature = "ml")]
+impl WeightPrecision {
+    /// パラメータ1個あたりのバイト数
+    pub fn bytes_per_param(self) -> usize {
+        match self {
+            Self::F32 => 4,
+            Self::F16 => 2,
+        }
+    }
+}
+
+/// モデルの構造
+#[cfg(feature = "ml")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelArchitecture {
+    /// 畳み込み後の特徴マップをそのまま全結合層へ渡す（従来の構造。48x48で約2000万パラメータ）
+    #[default]
+    Standard,
+    /// 畳み込み後にチャネルごとの平均を取り（Global Average Pooling）、小さな全結合層で分類する
+    /// （パラメータ数が入力サイズに依存せず約10万。CPU推論が速い）
+    Compact,
+}
+
 /// モデルメタデータ
 ///
 /// tar.gz形式で保存される情報：
@@ -66,7 +102,7 @@ pub struct ModelMetadata {
     /// config.jsonの button_tile.columns_per_row から取得（デフォルト: 6）
     pub columns_per_row: u32,
 
-    /// モデル入力サイズ（CNNへの入力解像度、通常48x48）
+    /// モデル入力の幅（CNNへの入力解像度、通常48x48。高さは image_height に合わせる）
     pub model_input_size: u32,
 
     /// 学習エポック数
@@ -74,6 +110,23 @@ pub struct ModelMetadata {
 
     /// モデルの学習時刻（ISO8601形式）
     pub trained_at: String,
+
+    /// 学習データセットのハッシュ（ファイル一覧・サイズ・更新日時から算出）
+    /// 2つのモデルが同一データで学習されたかの確認に使用する
+    #[serde(default)]
+    pub dataset_hash: Option<String>,
+
+    /// 重みの初期値にした既存モデルのパス（ファインチューニングした場合のみ）
+    #[serde(default)]
+    pub base_model: Option<String>,
+
+    /// 重みの保存精度（この項目が無い従来のモデルは F32）
+    #[serde(default)]
+    pub weight_precision: WeightPrecision,
+
+    /// モデルの構造（この項目が無い従来のモデルは Standard）
+    #[serde(default)]
+    pub architecture: ModelArchitecture,
 }
 
 #[cfg(feature = "ml")]
@@ -111,6 +164,10 @@ impl ModelMetadata {
             model_input_size,
             num_epochs,
             trained_at,
+            dataset_hash: None,
+            base_model: None,
+            weight_precision: WeightPrecision::F32,
+            architecture: ModelArchitecture::Standard,
         }
     }
 
@@ -5,6 +5,7 @@
 //! ファイル構成（tar.gz内部）:
 //! - metadata.json   - メタデータ（ボタン情報、タイル設定など）
 //! - model.bin       - モデルの重み（バイナリ）
+//! - history.json    - エポックごとの学習曲線（学習時に記録したモデルのみ）
 
 #[cfg(feature = "ml")]
 use anyhow::{Context, Result};
@@ -25,17 +26,21 @@ use flate2::read::GzDecoder;
 
 #[cfg(feature = "ml")]
 use crate::model::model_metadata::ModelMetadata;
+#[cfg(feature = "ml")]
+use crate::model::training_history::LearningCurve;
 
 /// メタデータと共にモデルをTar.gz形式で保存
 ///
 /// 1つのtar.gzファイルに以下を含む：
 /// - metadata.json : メタデータ
 /// - model.bin : モデルの重み
+/// - history.json : 学習曲線（learning_curve を指定した場合）
 #[cfg(feature = "ml")]
 pub fn save_model_with_metadata(
     output_path: &Path,
     metadata: &ModelMetadata,
     model_binary: &[u8],
+    learning_curve: Option<&LearningCurve>,
 ) -> Result<()> {
     // output_pathがすでに.tar.gzで終わっている場合はそのまま、そうでなければ拡張子を追加
     let tar_gz_path = if output_path.extension().and_then(|s| s.to_str()) == Some("gz") {
@@ -78,6 +83,18 @@ pub fn save_model_with_metadata(
     tar_builder.append(&header, model_binary)
         .context("Failed to add model.bin to tar")?;
 
+    // 学習曲線を追加
+    if let Some(curve) = learning_curve {
+        let history_json = serde_json::to_string_pretty(curve)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_path("history.json")?;
+        header.set_size(history_json.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar_builder.append(&header, history_json.as_bytes())
+            .context("Failed to add history.json to tar")?;
+    }
+
     // tarアーカイブを完成させる
     tar_builder.finish()
         .context("Failed to finalize tar.gz archive")?;
@@ -172,6 +189,29 @@ pub fn load_model_with_metadata(tar_gz_path: &Path) -> Result<(ModelMetadata, Ve
     }
 }
 
+/// Tar.gzから学習曲線を読み込む（記録されていない古いモデルは None）
+#[cfg(feature = "ml")]
+pub fn load_learning_curve(tar_gz_path: &Path) -> Result<Option<LearningCurve>> {
+    let tar_gz_file = File::open(tar_gz_path)
+        .context(format!("Failed to open tar.gz file: {:?}", tar_gz_path))?;
+
+    let decoder = GzDecoder::new(tar_gz_file);
+    let mut archive = Archive::new(decoder);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?;
+
+        if path.to_str() == Some("history.json") {
+            let mut json_str = String::new();
+            entry.read_to_string(&mut json_str)?;
+            return Ok(Some(serde_json::from_str(&json_str).context("Failed to parse history.json")?));
+        }
+    }
+
+    Ok(None)
+}
+
 /// メタデータをコンソールに表示
 #[cfg(feature = "ml")]
 pub fn print_metadata_info(metadata: &ModelMetadata) {
@@ -185,5 +225,13 @@ pub fn print_metadata_info(metadata: &ModelMetadata) {
     println!("モデル入力サイズ: {}x{}", metadata.model_input_size, metadata.model_input_size);
     println!("学習エポック数: {}", metadata.num_epochs);
     println!("学習日時: {}", metadata.trained_at);
+    println!("重みの精度: {:?}", metadata.weight_precision);
+    println!("モデル構造: {:?}", metadata.architecture);
+    if let Some(hash) = &metadata.dataset_hash {
+        println!("データセットハッシュ: {}", hash);
+    }
+    if let Some(base_model) = &metadata.base_model {
+        println!("元のモデル: {}", base_model);
+    }
     println!("========================");
 }
@@ -0,0 +1,81 @@
+//! 解析範囲プリセット
+//!
+//! ボタンタイル設定とボタンラベルを、アプリ全体の設定ファイルとは独立した
+//! JSONファイルとして保存・読み込みします。ゲーム・解像度ごとの設定共有用。
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::model::config::ButtonTileSettings;
+
+/// プリセットファイルのフォーマットバージョン
+pub const REGION_PRESET_VERSION: u32 = 1;
+
+/// 解析範囲プリセット
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegionPreset {
+    /// フォーマットバージョン
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// プリセット名
+    pub name: String,
+    /// 対象ゲーム名
+    #[serde(default)]
+    pub game: Option<String>,
+    /// 補足説明
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// ボタンタイル設定
+    pub button_tile: ButtonTileSettings,
+    /// ボタンラベル（方向キーとothersを除くユーザー定義ボタン）
+    #[serde(default)]
+    pub button_labels: Vec<String>,
+    /// 対応するモデルファイル名（任意）
+    #[serde(default)]
+    pub model_file: Option<String>,
+    /// 作成日時（ISO8601形式）
+    #[serde(default)]
+    pub created_at: String,
+}
+
+fn default_version() -> u32 {
+    REGION_PRESET_VERSION
+}
+
+impl RegionPreset {
+    /// 新しいプリセットを作成
+    pub fn new(name: String, button_tile: ButtonTileSettings, button_labels: Vec<String>) -> Self {
+        Self {
+            version: REGION_PRESET_VERSION,
+            name,
+            game: None,
+            notes: None,
+            button_tile,
+            button_labels,
+            model_file: None,
+            created_at: chrono::Local::now().to_rfc3339(),
+        }
+    }
+
+    /// プリセットを読み込む
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let preset: RegionPreset = serde_json::from_str(&content)?;
+        if preset.version > REGION_PRESET_VERSION {
+            anyhow::bail!(
+                "未対応のプリセットバージョンです: {} (対応: {}以下)",
+                preset.version,
+                REGION_PRESET_VERSION
+            );
+        }
+        Ok(preset)
+    }
+
+    /// プリセットを保存する
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! 学習履歴管理モジュール
+//!
+//! 学習の実行ごとに設定・データセット・所要時間・最終メトリクス・出力モデルを記録し、
+//! モデルの由来を後から辿れるようにします。
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config::AppConfig;
+
+/// 学習の最終メトリクス（最終エポックの値）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrainingMetrics {
+    /// 完了したエポック数
+    pub epochs_completed: usize,
+    pub train_loss: f64,
+    pub train_accuracy: f64,
+    pub val_loss: f64,
+    pub val_accuracy: f64,
+}
+
+/// モデルに同梱する学習曲線（エポックごとのメトリクス。tar.gz 内の history.json）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LearningCurve {
+    /// エポック順（epochs_completed がそのエポックの番号）
+    pub epochs: Vec<TrainingMetrics>,
+}
+
+/// 1回分の学習記録
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingRun {
+    /// 記録ID（開始日時ベース）
+    pub id: String,
+    /// 開始日時（ISO8601形式）
+    pub started_at: String,
+    /// 所要時間（秒）
+    pub duration_sec: f64,
+    /// 学習データディレクトリ
+    pub data_dir: String,
+    /// 出力モデルのパス
+    pub output_model_path: String,
+    /// エポック数（設定値）
+    pub num_epochs: usize,
+    /// バッチサイズ
+    pub batch_size: usize,
+    /// 学習率
+    pub learning_rate: f64,
+    /// ボタンラベル
+    pub button_labels: Vec<String>,
+    /// GPUを使用したか
+    pub use_gpu: bool,
+    /// 重みの初期値にした既存モデル（ファインチューニングした場合のみ）
+    #[serde(default)]
+    pub base_model_path: Option<String>,
+    /// 成功したか
+    pub success: bool,
+    /// 失敗時のエラーメッセージ
+    #[serde(default)]
+    pub error: Option<String>,
+    /// 最終メトリクス
+    #[serde(default)]
+    pub metrics: TrainingMetrics,
+}
+
+/// 学習履歴
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrainingHistory {
+    pub runs: Vec<TrainingRun>,
+}
+
+impl TrainingHistory {
+    /// 履歴ファイルのデフォルトパス（設定ファイルと同じディレクトリ）
+    pub fn default_path() -> PathBuf {
+        AppConfig::config_file_path("training_history.json")
+    }
+
+    /// 履歴を読み込む
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let history: TrainingHistory = serde_json::from_str(&content)?;
+        Ok(history)
+    }
+
+    /// デフォルトパスから履歴を読み込む、存在しない場合は空の履歴を返す
+    pub fn load_or_default() -> Self {
+        let path = Self::default_path();
+        if !path.exists() {
+            return Self::default();
+        }
+        match Self::load(&path) {
+            Ok(history) => history,
+            Err(e) => {
+                eprintln!(
+                    "警告: 学習履歴の読み込みに失敗しました ({}): {}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// 履歴を保存する
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 学習記録を追加してデフォルトパスに保存する
+    ///
+    /// 既存の履歴ファイルを読めない場合は、上書きで以前の記録を失わないよう
+    /// 別名（training_history.corrupt-日時.json）に退避してから新しい履歴を作る。
+    pub fn append_run(run: TrainingRun) -> anyhow::Result<()> {
+        let path = Self::default_path();
+        let mut history = match path.exists().then(|| Self::load(&path)) {
+            None => Self::default(),
+            Some(Ok(history)) => history,
+            Some(Err(e)) => {
+                let backup = path.with_extension(format!(
+                    "corrupt-{}.json",
+                    chrono::Local::now().format("%Y%m%d%H%M%S")
+                ));
+                fs::rename(&path, &backup).with_context(|| {
+                    format!("読み込めない学習履歴を退避できません: {}", path.display())
+                })?;
+                eprintln!(
+                    "警告: 学習履歴を読み込めないため退避しました ({}): {}",
+                    backup.display(),
+                    e
+                );
+                Self::default()
+            }
+        };
+        history.runs.push(run);
+        history.save(&path)
+    }
+}
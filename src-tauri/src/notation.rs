@@ -0,0 +1,244 @@
+//! テンキー表記の読み込み
+//!
+//! "236A 5B 2C" のような格闘ゲームのテンキー表記をシーケンス（InputFrame の列）に変換する。
+//!
+//! 表記の規則:
+//! - 技は空白・`,`・`>` で区切る
+//! - 先頭の数字列が方向（1〜9）、残りがボタン名（同時押しは `+` で連結、例: `5A+B`）
+//! - 方向を省略した場合はニュートラル（5）、ボタンを省略した場合は方向入力のみ
+//! - 末尾の `(N)` で最後のステップのフレーム数を指定（例: `2C(10)`）
+//!
+//! "236A" は 2 → 3 → 6+A の3ステップになる。途中の方向は motion_frames、
+//! 最後のステップは press_frames のフレーム数を使う（NotationSettings）。
+//!
+//! 逆方向の変換（frames_to_notation）は同じ設定で読み込むと元のステップ列に戻る表記を作る。
+//! ただしコマンドごとのフレーム数（motion_overrides）は考慮しない。
+
+use crate::model::NotationSettings;
+use crate::types::InputFrame;
+use anyhow::Result;
+use std::path::Path;
+
+fn step(duration: u32, direction: u8, buttons: &[&str]) -> InputFrame {
+    InputFrame {
+        direction,
+        buttons: buttons.iter().map(|b| (b.to_string(), 1)).collect(),
+        ..InputFrame::neutral(duration)
+    }
+}
+
+// 末尾の "(N)" を取り除き、指定されたフレーム数を返す
+fn split_hold_frames(token: &str) -> Result<(&str, Option<u32>)> {
+    let Some(body) = token.strip_suffix(')') else {
+        return Ok((token, None));
+    };
+    let open = body
+        .rfind('(')
+        .ok_or_else(|| anyhow::anyhow!("'{}': 対応する '(' がありません", token))?;
+    let frames: u32 = body[open + 1..]
+        .parse()
+        .map_err(|_| anyhow::anyhow!("'{}': フレーム数が不正です", token))?;
+    if frames == 0 {
+        anyhow::bail!("'{}': フレーム数は1以上を指定してください", token);
+    }
+    Ok((&body[..open], Some(frames)))
+}
+
+// 1つの技をステップ列に変換
+fn parse_token(token: &str, settings: &NotationSettings) -> Result<Vec<InputFrame>> {
+    let (body, hold_frames) = split_hold_frames(token)?;
+    let digits_end = body.find(|c: char| !c.is_ascii_digit()).unwrap_or(body.len());
+    let (motion, rest) = body.split_at(digits_end);
+
+    let directions: Vec<u8> = motion.bytes().map(|b| b - b'0').collect();
+    if directions.contains(&0) {
+        anyhow::bail!("'{}': 方向は1〜9で指定してください", token);
+    }
+    let buttons: Vec<&str> = if rest.is_empty() {
+        Vec::new()
+    } else {
+        rest.split('+').collect()
+    };
+    if buttons.iter().any(|b| b.is_empty()) {
+        anyhow::bail!("'{}': ボタン名が空です", token);
+    }
+    if directions.is_empty() && buttons.is_empty() {
+        anyhow::bail!("'{}': 方向もボタンも指定されていません", token);
+    }
+
+    let motion_frames = settings
+        .motion_overrides
+        .get(motion)
+        .copied()
+        .unwrap_or(settings.motion_frames)
+        .max(1);
+    let press_frames = hold_frames.unwrap_or(settings.press_frames).max(1);
+
+    let (last_direction, path) = match directions.split_last() {
+        Some((&last, path)) => (last, path),
+        None => (5, &[][..]),
+    };
+    let mut frames: Vec<InputFrame> = path.iter().map(|&d| step(motion_frames, d, &[])).collect();
+    frames.push(step(press_frames, last_direction, &buttons));
+    Ok(frames)
+}
+
+/// テンキー表記をシーケンスに変換（設定を指定）
+pub fn parse_notation_with(text: &str, settings: &NotationSettings) -> Result<Vec<InputFrame>> {
+    let tokens: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '>')
+        .filter(|t| !t.is_empty())
+        .collect();
+    if tokens.is_empty() {
+        anyhow::bail!("テンキー表記が空です");
+    }
+
+    let mut frames = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 && settings.gap_frames > 0 {
+            frames.push(step(settings.gap_frames, 5, &[]));
+        }
+        frames.extend(parse_token(token, settings)?);
+    }
+    Ok(frames)
+}
+
+/// テンキー表記をシーケンスに変換（デフォルトのフレーム数を使用）
+pub fn parse_notation_to_frames(text: &str) -> Result<Vec<InputFrame>> {
+    parse_notation_with(text, &NotationSettings::default())
+}
+
+// 押されているボタン名（ソート済み）
+fn pressed_buttons(frame: &InputFrame) -> Vec<&str> {
+    let mut pressed: Vec<&str> = frame
+        .buttons
+        .iter()
+        .filter(|(_, &v)| v == 1)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    pressed.sort_unstable();
+    pressed
+}
+
+/// シーケンスをテンキー表記に変換
+///
+/// ボタンを押していない短いステップ（motion_frames と同じ長さ）は次の技のコマンドとしてまとめ、
+/// press_frames と異なる長さのステップには `(N)` を付ける。
+/// 技の間の gap_frames の無入力ステップは区切りとして省略する。
+pub fn frames_to_notation(frames: &[InputFrame], settings: &NotationSettings) -> String {
+    let motion_frames = settings.motion_frames.max(1);
+    let press_frames = settings.press_frames.max(1);
+    let mut tokens: Vec<String> = Vec::new();
+    let mut motion = String::new();
+    let mut gap_skipped = false;
+
+    for (i, frame) in frames.iter().enumerate() {
+        let buttons = pressed_buttons(frame);
+        let is_last = i + 1 == frames.len();
+
+        // 技の間の無入力（技1つにつき1回だけ）
+        if settings.gap_frames > 0
+            && !gap_skipped
+            && motion.is_empty()
+            && !tokens.is_empty()
+            && !is_last
+            && frame.direction == 5
+            && buttons.is_empty()
+            && frame.duration == settings.gap_frames
+        {
+            gap_skipped = true;
+            continue;
+        }
+
+        // コマンドの途中の方向
+        if !is_last && frame.direction != 5 && buttons.is_empty() && frame.duration == motion_frames {
+            motion.push_str(&frame.direction.to_string());
+            continue;
+        }
+
+        let mut token = std::mem::take(&mut motion);
+        // ボタンのみの技（5A など）は方向を省略
+        if !(frame.direction == 5 && token.is_empty() && !buttons.is_empty()) {
+            token.push_str(&frame.direction.to_string());
+        }
+        token.push_str(&buttons.join("+"));
+        if frame.duration != press_frames {
+            token.push_str(&format!("({})", frame.duration));
+        }
+        tokens.push(token);
+        gap_skipped = false;
+    }
+    tokens.join(" ")
+}
+
+/// シーケンスCSVをテンキー表記に変換
+pub fn sequence_to_notation(path: &Path, settings: &NotationSettings) -> Result<String> {
+    let frames = crate::csv_loader::load_csv(path)?;
+    Ok(frames_to_notation(&frames, settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn summary(frames: &[InputFrame]) -> Vec<(u32, u8, Vec<String>)> {
+        frames
+            .iter()
+            .map(|f| {
+                let mut buttons: Vec<String> = f.buttons.keys().cloned().collect();
+                buttons.sort();
+                (f.duration, f.direction, buttons)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_motion_and_buttons() {
+        let frames = parse_notation_to_frames("236A 5B+C > 2C(10)").unwrap();
+        assert_eq!(
+            summary(&frames),
+            vec![
+                (2, 2, vec![]),
+                (2, 3, vec![]),
+                (4, 6, vec!["A".to_string()]),
+                (4, 5, vec!["B".to_string(), "C".to_string()]),
+                (10, 2, vec!["C".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_notation_round_trip() {
+        let settings = NotationSettings::default();
+        let text = "236A B+C 2C(10) 6(30) 5(12) 41236D";
+        let frames = parse_notation_with(text, &settings).unwrap();
+        let exported = frames_to_notation(&frames, &settings);
+        assert_eq!(exported, text);
+        assert_eq!(summary(&parse_notation_with(&exported, &settings).unwrap()), summary(&frames));
+
+        // 技の間の無入力は区切りとして省略される
+        let settings = NotationSettings { gap_frames: 6, ..NotationSettings::default() };
+        let frames = parse_notation_with("2A 5(6) 236B", &settings).unwrap();
+        assert_eq!(frames_to_notation(&frames, &settings), "2A 5(6) 236B");
+    }
+
+    #[test]
+    fn test_parse_uses_settings() {
+        let settings = NotationSettings {
+            motion_frames: 1,
+            press_frames: 3,
+            gap_frames: 5,
+            motion_overrides: HashMap::from([("623".to_string(), 2)]),
+        };
+        let frames = parse_notation_with("623A1, A2", &settings).unwrap();
+        let durations: Vec<u32> = frames.iter().map(|f| f.duration).collect();
+        assert_eq!(durations, vec![2, 2, 3, 5, 3]);
+        assert_eq!(frames[2].buttons.get("A1"), Some(&1));
+        assert_eq!(frames[4].direction, 5);
+
+
+        assert!(parse_notation_to_frames("206A").is_err());
+        assert!(parse_notation_to_frames("5A+").is_err());
+    }
+}
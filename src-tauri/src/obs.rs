@@ -0,0 +1,246 @@
+//! OBS の録画連携（obs-websocket v5）
+//!
+//! 再生の開始・停止（"playback-state-changed" イベント）に合わせて OBS の録画を開始・停止し、
+//! 練習の試行を1回ずつ録画ファイルに残す。録画ファイル名はシーケンス名にできる
+//! （プロファイルのファイル名書式を録画開始の間だけ書き換えて元に戻す）。
+//! 通信は専用スレッドで行い、接続が切れた場合は次の録画開始時に接続し直す。
+//! 設定は毎回 config.json から読むので、変更は次の再生から反映される。
+
+use crate::model::{AppConfig, ObsSettings};
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use tauri::{Listener, Manager};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+enum ObsCommand {
+    StartRecording { sequence_name: Option<String> },
+    StopRecording,
+}
+
+struct ObsClient {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+    url: String,
+    password: Option<String>,
+    next_request_id: u64,
+}
+
+// obs-websocket の認証文字列: base64(sha256(base64(sha256(password + salt)) + challenge))
+fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = general_purpose::STANDARD.encode(Sha256::digest(format!("{}{}", password, salt)));
+    general_purpose::STANDARD.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+impl ObsClient {
+    fn connect(settings: &ObsSettings) -> Result<Self> {
+        let (socket, _) = tungstenite::connect(settings.url.as_str())
+            .map_err(|e| anyhow::anyhow!("{} に接続できません: {}", settings.url, e))?;
+        let mut client = Self {
+            socket,
+            url: settings.url.clone(),
+            password: settings.password.clone(),
+            next_request_id: 0,
+        };
+
+        // Hello (op 0) → Identify (op 1) → Identified (op 2)
+        let hello = client.read_message()?;
+        let mut identify = json!({ "rpcVersion": 1, "eventSubscriptions": 0 });
+        if let Some(auth) = hello["d"].get("authentication") {
+            let password = settings.password.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("OBS 側で認証が有効になっています。パスワードを設定してください"))?;
+            let salt = auth["salt"].as_str().unwrap_or_default();
+            let challenge = auth["challenge"].as_str().unwrap_or_default();
+            identify["authentication"] = json!(authentication(password, salt, challenge));
+        }
+        client.send(json!({ "op": 1, "d": identify }))?;
+        let identified = client.read_message()?;
+        if identified["op"] != 2 {
+            anyhow::bail!("OBS との接続の確立に失敗しました");
+        }
+        Ok(client)
+    }
+
+    fn send(&mut self, message: Value) -> Result<()> {
+        self.socket.send(Message::text(message.to_string()))?;
+        Ok(())
+    }
+
+    fn read_message(&mut self) -> Result<Value> {
+        loop {
+            match self.socket.read()? {
+                Message::Text(text) => return Ok(serde_json::from_str(&text)?),
+                // 認証失敗などは切断の理由として通知される
+                Message::Close(frame) => anyhow::bail!(
+                    "OBS との接続が切断されました{}",
+                    frame.map(|f| format!(": {}", f.reason)).unwrap_or_default()
+                ),
+                _ => continue,
+            }
+        }
+    }
+
+    /// リクエスト (op 6) を送信して応答 (op 7) の responseData を返す
+    fn request(&mut self, request_type: &str, data: Value) -> Result<Value> {
+        self.next_request_id += 1;
+        let request_id = self.next_request_id.to_string();
+        self.send(json!({
+            "op": 6,
+            "d": { "requestType": request_type, "requestId": request_id, "requestData": data },
+        }))?;
+        loop {
+            let message = self.read_message()?;
+            if message["op"] != 7 || message["d"]["requestId"] != request_id.as_str() {
+                continue;
+            }
+            let status = &message["d"]["requestStatus"];
+            if status["result"] != true {
+                anyhow::bail!("{} に失敗: {}", request_type, status["comment"].as_str().unwrap_or("不明なエラー"));
+            }
+            return Ok(message["d"]["responseData"].clone());
+        }
+    }
+
+    fn is_recording(&mut self) -> Result<bool> {
+        Ok(self.request("GetRecordStatus", json!({}))?["outputActive"] == true)
+    }
+
+    // ファイル名書式を一時的にシーケンス名にして録画を開始
+    fn start_recording(&mut self, sequence_name: Option<&str>) -> Result<()> {
+        let Some(name) = sequence_name else {
+            self.request("StartRecord", json!({}))?;
+            return Ok(());
+        };
+        let parameter = json!({ "parameterCategory": "Output", "parameterName": "FilenameFormatting" });
+        let original = self.request("GetProfileParameter", parameter.clone())?["parameterValue"].clone();
+
+        // '%' は OBS の書式指定と解釈されるため取り除く
+        let format = format!("{} %CCYY-%MM-%DD %hh-%mm-%ss", name.replace('%', ""));
+        let mut set = parameter.clone();
+        set["parameterValue"] = json!(format);
+        self.request("SetProfileParameter", set)?;
+
+        let result = self.request("StartRecord", json!({}));
+        if let Some(original) = original.as_str() {
+            let mut restore = parameter;
+            restore["parameterValue"] = json!(original);
+            if let Err(e) = self.request("SetProfileParameter", restore) {
+                println!("[OBS] ファイル名書式を元に戻せませんでした: {}", e);
+            }
+        }
+        result.map(|_| ())
+    }
+}
+
+/// OBS に接続できるか確認（OBS のバージョンを返す）
+pub fn test_connection(settings: &ObsSettings) -> Result<String> {
+    let mut client = ObsClient::connect(settings)?;
+    let version = client.request("GetVersion", json!({}))?;
+    Ok(version["obsVersion"].as_str().unwrap_or("不明").to_string())
+}
+
+// 録画中の状態（自分で開始した録画だけを停止し、手動で開始した録画は止めない）
+#[derive(Default)]
+struct Recorder {
+    client: Option<ObsClient>,
+    recording: bool,
+}
+
+impl Recorder {
+    fn handle(&mut self, command: ObsCommand, settings: &ObsSettings) -> Result<()> {
+        // 接続先が変わった場合は接続し直す
+        if self.client.as_ref().is_some_and(|c| c.url != settings.url || c.password != settings.password) {
+            self.client = None;
+        }
+
+        match command {
+            ObsCommand::StartRecording { sequence_name } => {
+                if self.client.is_none() {
+                    self.client = Some(ObsClient::connect(settings)?);
+                    println!("[OBS] {} に接続しました", settings.url);
+                }
+                let obs = self.client.as_mut().expect("接続済み");
+                if obs.is_recording()? {
+                    println!("[OBS] すでに録画中のため録画を開始しません");
+                    return Ok(());
+                }
+                let name = sequence_name.filter(|_| settings.name_after_sequence);
+                obs.start_recording(name.as_deref())?;
+                self.recording = true;
+                println!("[OBS] 録画を開始しました");
+            }
+            ObsCommand::StopRecording => self.stop()?,
+        }
+        Ok(())
+    }
+
+    // 開始した録画があれば、その接続で停止する
+    fn stop(&mut self) -> Result<()> {
+        if !self.recording {
+            return Ok(());
+        }
+        self.recording = false;
+        let Some(obs) = self.client.as_mut() else { return Ok(()) };
+        let output = obs.request("StopRecord", json!({}))?;
+        println!("[OBS] 録画を停止しました: {}", output["outputPath"].as_str().unwrap_or(""));
+        Ok(())
+    }
+}
+
+// 通信用スレッド
+fn run_worker(rx: mpsc::Receiver<ObsCommand>, app: tauri::AppHandle) {
+    let mut recorder = Recorder::default();
+    while let Ok(command) = rx.recv() {
+        let settings = AppConfig::load_or_default().obs;
+        if !settings.enabled {
+            // 連携を無効にする前に開始した録画は止めておく（止める手段がなくなるため）
+            if let Err(e) = recorder.stop() {
+                println!("[OBS] {}", e);
+            }
+            recorder = Recorder::default();
+            continue;
+        }
+        // シーケンス名は再生スレッドのロックと競合しないよう通信用スレッドで解決する
+        let command = match command {
+            ObsCommand::StartRecording { sequence_name: None } if settings.name_after_sequence => {
+                ObsCommand::StartRecording { sequence_name: current_sequence_name(&app) }
+            }
+            command => command,
+        };
+        if let Err(e) = recorder.handle(command, &settings) {
+            println!("[OBS] {}", e);
+            // 接続が切れている可能性があるため次回は接続し直す
+            recorder.client = None;
+        }
+    }
+}
+
+/// 再生状態の変化に合わせて録画を開始・停止するよう登録する
+pub fn attach(app: &tauri::AppHandle) {
+    let (tx, rx) = mpsc::channel::<ObsCommand>();
+    let handle = app.clone();
+    std::thread::spawn(move || run_worker(rx, handle));
+
+    // リスナーはイベント送信元のスレッドで同期的に呼ばれるため、ここではプレイヤーをロックしない
+    app.listen_any("playback-state-changed", move |event| {
+        let state: String = serde_json::from_str(event.payload()).unwrap_or_default();
+        let command = match state.as_str() {
+            "playing" => ObsCommand::StartRecording { sequence_name: None },
+            "stopped" | "no_sequence" => ObsCommand::StopRecording,
+            _ => return,
+        };
+        let _ = tx.send(command);
+    });
+}
+
+// 再生中のシーケンスのファイル名（拡張子なし）
+fn current_sequence_name(app: &tauri::AppHandle) -> Option<String> {
+    let app_state = app.state::<crate::AppState>();
+    let path = app_state.player.lock().unwrap().get_current_path();
+    path.as_deref()
+        .and_then(|p| std::path::Path::new(p).file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+}
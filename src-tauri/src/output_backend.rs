@@ -0,0 +1,172 @@
+//! 入力の出力先（バックエンド）
+//!
+//! [`Controller`](crate::Controller) は送信ログやマッピングを扱い、実際の送信は
+//! [`OutputBackend`] に任せる。標準は ViGEm の仮想 Xbox360 コントローラー（"vigem"）。
+//! ドライバの無い環境（開発機・CI）向けに、送信内容を記録するだけの [`SimulatedBackend`]
+//! （"simulation"）も用意している。`simulation` feature でビルドすると標準の出力先になる。
+//! ネットワーク送信やシリアル接続のパッドなどは、このトレイトを実装して
+//! [`register_backend`] で登録すれば controller.rs を変更せずに追加できる。
+//!
+//! ```no_run
+//! use input_player_lib::output_backend::{register_backend, OutputBackend};
+//! use input_player_lib::{ControllerType, InputFrame};
+//!
+//! struct SerialPad { connected: bool }
+//!
+//! impl OutputBackend for SerialPad {
+//!     fn connect(&mut self, _controller_type: ControllerType) -> anyhow::Result<()> {
+//!         self.connected = true;
+//!         Ok(())
+//!     }
+//!     fn disconnect(&mut self) -> anyhow::Result<()> {
+//!         self.connected = false;
+//!         Ok(())
+//!     }
+//!     fn is_connected(&self) -> bool {
+//!         self.connected
+//!     }
+//!     fn update_input(&mut self, frame: &InputFrame, invert_horizontal: bool) -> anyhow::Result<()> {
+//!         // frame.direction / frame.buttons（button1〜button12）をパッドに書き込む
+//!         Ok(())
+//!     }
+//! }
+//!
+//! register_backend("serial", || Box::new(SerialPad { connected: false }));
+//! ```
+
+use crate::types::{ControllerType, InputFrame};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// ViGEm のバックエンド名
+pub const VIGEM_BACKEND: &str = "vigem";
+/// 送信内容を記録するだけのバックエンド名
+pub const SIMULATION_BACKEND: &str = "simulation";
+
+/// 標準のバックエンド名
+#[cfg(not(feature = "simulation"))]
+pub const DEFAULT_BACKEND: &str = VIGEM_BACKEND;
+#[cfg(feature = "simulation")]
+pub const DEFAULT_BACKEND: &str = SIMULATION_BACKEND;
+
+// シミュレーションで保持する記録の件数（60FPSで約10分）
+const SIMULATION_RECORD_CAPACITY: usize = 36_000;
+
+/// 入力の出力先
+pub trait OutputBackend: Send {
+    fn connect(&mut self, controller_type: ControllerType) -> Result<()>;
+    fn disconnect(&mut self) -> Result<()>;
+    fn is_connected(&self) -> bool;
+    /// 1ステップ分の入力を送信（ボタン名はマッピング適用後の button1〜button12）
+    fn update_input(&mut self, frame: &InputFrame, invert_horizontal: bool) -> Result<()>;
+}
+
+type BackendFactory = Box<dyn Fn() -> Box<dyn OutputBackend> + Send + Sync>;
+
+// 登録順を保つため Vec で保持する
+fn registry() -> &'static Mutex<Vec<(String, BackendFactory)>> {
+    static REGISTRY: OnceLock<Mutex<Vec<(String, BackendFactory)>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let vigem: BackendFactory = Box::new(|| Box::new(crate::controller::ViGEmBackend::new()));
+        let simulation: BackendFactory = Box::new(|| Box::new(SimulatedBackend::new()));
+        Mutex::new(vec![
+            (VIGEM_BACKEND.to_string(), vigem),
+            (SIMULATION_BACKEND.to_string(), simulation),
+        ])
+    })
+}
+
+/// バックエンドを登録（同じ名前の登録は置き換える）
+pub fn register_backend<F>(name: &str, factory: F)
+where
+    F: Fn() -> Box<dyn OutputBackend> + Send + Sync + 'static,
+{
+    let mut registry = registry().lock().unwrap();
+    registry.retain(|(registered, _)| registered != name);
+    registry.push((name.to_string(), Box::new(factory)));
+}
+
+/// 登録されているバックエンド名（登録順）
+pub fn backend_names() -> Vec<String> {
+    registry().lock().unwrap().iter().map(|(name, _)| name.clone()).collect()
+}
+
+/// 名前を指定してバックエンドを作成
+pub fn create_backend(name: &str) -> Result<Box<dyn OutputBackend>> {
+    let registry = registry().lock().unwrap();
+    let (_, factory) = registry
+        .iter()
+        .find(|(registered, _)| registered == name)
+        .ok_or_else(|| anyhow::anyhow!("出力先が登録されていません: {}", name))?;
+    Ok(factory())
+}
+
+/// シミュレーションで記録した送信内容（1回分）
+#[derive(Debug, Clone)]
+pub struct SimulatedInput {
+    pub elapsed: Duration,  // 接続してからの経過時間
+    pub frame: InputFrame,
+    pub invert_horizontal: bool,
+}
+
+/// ドライバを使わず、送信内容を時刻付きで記録するだけのバックエンド
+pub struct SimulatedBackend {
+    connected_at: Option<Instant>,
+    records: Arc<Mutex<VecDeque<SimulatedInput>>>,
+}
+
+impl Default for SimulatedBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedBackend {
+    pub fn new() -> Self {
+        Self {
+            connected_at: None,
+            records: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// 記録への参照（Controller に渡した後でも読めるよう共有する）
+    pub fn records(&self) -> Arc<Mutex<VecDeque<SimulatedInput>>> {
+        self.records.clone()
+    }
+}
+
+impl OutputBackend for SimulatedBackend {
+    fn connect(&mut self, controller_type: ControllerType) -> Result<()> {
+        if self.connected_at.is_none() {
+            self.connected_at = Some(Instant::now());
+            self.records.lock().unwrap().clear();
+            println!("[Simulation] {} コントローラーとして接続（送信内容は記録のみ）", controller_type);
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self) -> Result<()> {
+        self.connected_at = None;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected_at.is_some()
+    }
+
+    fn update_input(&mut self, frame: &InputFrame, invert_horizontal: bool) -> Result<()> {
+        let connected_at = self.connected_at.ok_or_else(|| anyhow::anyhow!("Controller not connected"))?;
+        let mut records = self.records.lock().unwrap();
+        while records.len() >= SIMULATION_RECORD_CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(SimulatedInput {
+            elapsed: connected_at.elapsed(),
+            frame: frame.clone(),
+            invert_horizontal,
+        });
+        Ok(())
+    }
+}
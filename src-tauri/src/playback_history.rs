@@ -0,0 +1,99 @@
+//! 再生履歴（セッションログ）
+//!
+//! アプリ起動中の再生1回ごとに、ファイル・開始時刻・ループ回数・途中停止の有無を記録する。
+//! 長時間の練習セッションを後から振り返れるよう、CSV/JSONに書き出せる。
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// 1回分の再生記録
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackRun {
+    pub file: Option<String>,      // 再生したシーケンスファイル
+    pub started_at: String,        // 開始日時（ISO8601形式）
+    pub duration_sec: f64,         // 再生時間（秒）
+    pub loops_completed: u32,      // 最後まで再生し終えた回数（ループ再生では周回数）
+    pub last_step: usize,          // 終了時点の再生済みステップ数（最後の周回）
+    pub total_steps: usize,        // シーケンスのステップ数
+    pub reversed: bool,            // 逆再生だったか
+    pub stopped_early: bool,       // 最後まで再生する前に停止したか
+}
+
+/// 再生中の記録
+#[derive(Debug, Clone)]
+pub struct ActiveRun {
+    file: Option<String>,
+    started_at: chrono::DateTime<chrono::Local>,
+    started: Instant,
+    reversed: bool,
+    loops_completed: u32,
+}
+
+impl ActiveRun {
+    pub fn begin(file: Option<String>, reversed: bool) -> Self {
+        Self {
+            file,
+            started_at: chrono::Local::now(),
+            started: Instant::now(),
+            reversed,
+            loops_completed: 0,
+        }
+    }
+
+    /// シーケンスを最後まで再生し終えた
+    pub fn complete_loop(&mut self) {
+        self.loops_completed += 1;
+    }
+
+    pub fn loops_completed(&self) -> u32 {
+        self.loops_completed
+    }
+
+    pub fn finish(self, last_step: usize, total_steps: usize, stopped_early: bool) -> PlaybackRun {
+        PlaybackRun {
+            file: self.file,
+            started_at: self.started_at.to_rfc3339(),
+            duration_sec: self.started.elapsed().as_secs_f64(),
+            loops_completed: self.loops_completed,
+            last_step,
+            total_steps,
+            reversed: self.reversed,
+            stopped_early,
+        }
+    }
+}
+
+/// セッション中の再生履歴
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlaybackHistory {
+    pub runs: Vec<PlaybackRun>,
+}
+
+impl PlaybackHistory {
+    pub fn push(&mut self, run: PlaybackRun) {
+        self.runs.push(run);
+    }
+
+    pub fn clear(&mut self) {
+        self.runs.clear();
+    }
+
+    /// JSON形式で保存
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// CSV形式で保存（1行1再生）
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        for run in &self.runs {
+            writer.serialize(run)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -1,40 +1,128 @@
 use crate::controller::Controller;
-use crate::types::{InputFrame, SequenceState};
+use crate::csv_loader::scale_durations;
+use crate::model::SyncMarkerSettings;
+use crate::playback_history::{ActiveRun, PlaybackHistory};
+use crate::types::{
+    BreakpointHitEvent, InputFrame, LateStepEvent, PlaybackStepEvent, PlaybackTimingReport, SequenceLabel,
+    SequenceState, SequenceTimeline, TimelineStep,
+};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+// 予約再生（指定時刻または外部トリガーで開始する待機状態）
+#[derive(Debug, Clone, Copy)]
+pub struct ArmedStart {
+    pub deadline: Option<Instant>,       // 開始時刻（None の場合は外部トリガー待ち）
+    pub scheduled_unix_ms: Option<u64>,  // 予約時のOS時刻（UNIXエポックからのミリ秒）
+    pub reversed: bool,                  // 逆再生で開始するか
+}
+
+// 送信タイミングの計測値（予定時刻との差分を集計）
+#[derive(Debug, Clone, Copy, Default)]
+struct TimingStats {
+    samples: u32,
+    min_jitter: Option<Duration>,
+    max_jitter: Duration,
+    total_jitter: Duration,
+    late_steps: u32,
+    missed_frames: u32,
+}
+
 pub struct Player {
     // シーケンスデータ
-    pub frames: Vec<InputFrame>,
+    pub frames: Arc<Vec<InputFrame>>,  // キャッシュと共有するため Arc で保持
     
     // 状態管理
     state: SequenceState,
-    current_step: usize,  // 現在のステップ（行番号）
+    current_step: usize,  // 現在のステップ（再生順。逆再生時は行番号と異なる）
+    reversed: bool,       // 逆再生中（最終行から先頭行へ）
     
     // タイミング管理
     sequence_start_time: Option<Instant>,  // シーケンス開始時刻
+    armed: Option<ArmedStart>,  // 予約再生の待機状態
     next_step_time: Duration,  // 次のステップに進む累積時間
+    elapsed_frames: u32,  // 送信済みステップ（挿入したリリース含む）の累積フレーム数
+    release_inserted_step: Option<usize>,  // リリースを挿入済みのステップ
+    timing: TimingStats,  // 送信タイミングの計測値（再生開始ごとにリセット）
+    late_step_warning: Duration,  // これ以上遅れた送信を警告する（ゼロで無効）
+    late_step: Option<LateStepEvent>,  // 未通知の遅れた送信
+    preroll: Vec<InputFrame>,  // シーケンスの前に送信するフレーム（同期マーカー）
+    preroll_index: usize,      // 送信済みのプリロールフレーム数
+    paused_at: Option<Instant>,  // ブレークポイントで一時停止した時刻
+    resumed_step: Option<usize>, // 一時停止から再開したステップ（同じブレークポイントで再度止まらないため）
+    active_run: Option<ActiveRun>,  // 再生中の履歴記録
+    history: PlaybackHistory,       // このセッションの再生履歴
     
     // 設定
+    breakpoints: HashSet<usize>,  // 到達時に一時停止するステップ（行番号）
+    labels: Vec<SequenceLabel>,   // CSVの `label:` 行で付けたステップの名前
     invert_horizontal: bool,
     button_mapping: HashMap<String, String>, // CSVボタン名 -> Xboxボタン名
     loop_playback: bool,
+    sync_marker: SyncMarkerSettings,  // 再生開始時の同期マーカー
+    latency_offset_frames: i32,  // 送信予定時刻の補正（フレーム数、正で遅らせ負で早める）
+    duration_scale: f32,      // 各ステップのフレーム数に掛ける倍率（1.0で等倍）
+    scaled_durations: Vec<u32>,  // 倍率適用後の各ステップのフレーム数（行番号順）
+    duration_prefix: Vec<u32>,   // scaled_durations の累積和（先頭に0を含み、要素数はステップ数+1）
+    release_gap_frames: u32,  // 同じボタンが連続するステップ間に挿入するリリースのフレーム数（0で無効）
+    hold_last_input: bool,  // 終了時に最終ステップの入力を保持する（デフォルト設定）
+    hold_current: bool,     // 今回の再生で入力を保持するか
+    last_sent_frame: Option<InputFrame>,  // 最後に送信したステップ（マッピング適用済み）
+    held_input: bool,       // 再生終了後、最終ステップの入力を保持中
+    manual_overlay: bool,   // 再生中も手動入力を受け付け、シーケンスの入力に重ねる
+    manual_input: Option<InputFrame>,  // 重ねる手動入力（オーバーレイモード時）
+    current_output: Option<(InputFrame, bool)>,  // 最後に送信したフレーム（オーバーレイ適用前）と左右反転の有無
     current_path: Option<String>,
     fps: u32,  // FPS設定
 }
 
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Player {
     pub fn new() -> Self {
         Self {
-            frames: Vec::new(),
+            frames: Arc::new(Vec::new()),
             state: SequenceState::NoSequence,
             current_step: 0,
+            reversed: false,
             sequence_start_time: None,
+            armed: None,
             next_step_time: Duration::from_secs(0),
+            elapsed_frames: 0,
+            release_inserted_step: None,
+            timing: TimingStats::default(),
+            late_step_warning: Duration::ZERO,
+            late_step: None,
+            preroll: Vec::new(),
+            preroll_index: 0,
+            paused_at: None,
+            resumed_step: None,
+            active_run: None,
+            history: PlaybackHistory::default(),
+            breakpoints: HashSet::new(),
+            labels: Vec::new(),
             invert_horizontal: false,
             button_mapping: HashMap::new(),
             loop_playback: false,
+            sync_marker: SyncMarkerSettings::default(),
+            latency_offset_frames: 0,
+            duration_scale: 1.0,
+            scaled_durations: Vec::new(),
+            duration_prefix: vec![0],
+            release_gap_frames: 0,
+            hold_last_input: false,
+            hold_current: false,
+            last_sent_frame: None,
+            held_input: false,
+            manual_overlay: false,
+            manual_input: None,
+            current_output: None,
             current_path: None,
             fps: 60,
         }
@@ -42,7 +130,14 @@ impl Player {
 
     // シーケンスをロード（停止状態に遷移）
     pub fn load_frames(&mut self, frames: Vec<InputFrame>) {
+        self.load_shared_frames(Arc::new(frames));
+    }
+
+    // 共有のシーケンスをロード（巨大なシーケンスをキャッシュとコピーせずに共有する）
+    pub fn load_shared_frames(&mut self, frames: Arc<Vec<InputFrame>>) {
         self.frames = frames;
+        self.labels.clear();
+        self.update_scaled_durations();
         self.state = if self.frames.is_empty() {
             SequenceState::NoSequence
         } else {
@@ -51,35 +146,148 @@ impl Player {
         self.current_step = 0;
         self.sequence_start_time = None;
         self.next_step_time = Duration::from_secs(0);
+        self.elapsed_frames = 0;
+        self.release_inserted_step = None;
+    }
+
+    // シーケンスの末尾にステップを追加（再生中でも可。スクリプトのループ時のフック用）
+    pub fn append_frames(&mut self, frames: Vec<InputFrame>) {
+        Arc::make_mut(&mut self.frames).extend(frames);
+        self.update_scaled_durations();
     }
 
     // 再生開始
     pub fn start(&mut self) {
-        if self.state == SequenceState::Stopped || self.state == SequenceState::NoSequence {
+        if self.state != SequenceState::Playing {
             if !self.frames.is_empty() {
+                // 一時停止中から再開始した場合は前回の再生を途中停止として記録
+                self.finish_run(true);
                 // 1. シーケンスロード完了 (既に完了)
                 // 2. コントローラー状態を入力無しに (次のupdateで送信)
-                // 3. 再生開始時刻を取得
-                self.sequence_start_time = Some(Instant::now());
+                // 3. 再生開始時刻を取得（入力遅延の補正分ずらす）
+                self.sequence_start_time = Some(self.apply_latency_offset(Instant::now()));
+                // 負の補正は開始時刻より前に送ることになるため、時刻を指定した予約再生でしか適用できない
+                let scheduled = self.armed.is_some_and(|armed| armed.deadline.is_some());
+                if self.latency_offset_frames < 0 && !scheduled {
+                    println!(
+                        "[Player] 警告: 負の入力遅延補正（{}F）は時刻を指定した予約再生でのみ適用されます。即時開始では補正しません",
+                        self.latency_offset_frames
+                    );
+                }
                 // 4. 再生中に遷移
                 self.state = SequenceState::Playing;
                 // 5. コントローラの状態を最初のステップに設定 (次のupdateで送信)
                 self.current_step = 0;
                 // next_step_time は開始時刻からの絶対経過時間 (0ms = すぐに送信)
                 self.next_step_time = Duration::from_secs(0);
+                self.elapsed_frames = 0;
+                self.release_inserted_step = None;
+                self.reversed = false;
+                self.timing = TimingStats::default();
+                self.preroll = self.sync_marker_frames();
+                self.preroll_index = 0;
+                self.paused_at = None;
+                self.resumed_step = None;
+                self.active_run = Some(ActiveRun::begin(self.current_path.clone(), false));
+                // 終了時の入力保持はデフォルト設定に従う（start後に上書き可能）
+                self.hold_current = self.hold_last_input;
+                self.last_sent_frame = None;
+                self.held_input = false;
                 
                 println!("[Player] 再生開始: {} steps", self.frames.len());
             }
         }
     }
 
+    // 逆再生開始（最終行から先頭行へ、各ステップのdurationはそのまま）
+    pub fn start_reversed(&mut self) {
+        self.start();
+        if self.state == SequenceState::Playing {
+            self.reversed = true;
+            self.active_run = Some(ActiveRun::begin(self.current_path.clone(), true));
+            println!("[Player] 逆再生モード");
+        }
+    }
+
+    // 予約再生を設定（deadline が None の場合は fire_armed が呼ばれるまで待機）
+    pub fn arm(&mut self, armed: ArmedStart) {
+        self.armed = Some(armed);
+        println!("[Player] 予約再生を設定: {:?}", armed.scheduled_unix_ms);
+    }
+
+    // 予約再生を取り消す
+    pub fn disarm(&mut self) {
+        if self.armed.take().is_some() {
+            println!("[Player] 予約再生を取り消し");
+        }
+    }
+
+    pub fn get_armed(&self) -> Option<ArmedStart> {
+        self.armed
+    }
+
+    // 予約時刻に達していれば再生を開始する（開始した場合は予約内容を返す）
+    pub fn fire_if_due(&mut self) -> Option<ArmedStart> {
+        let deadline = self.armed_wake_time()?;
+        if Instant::now() < deadline {
+            return None;
+        }
+        self.fire_armed()
+    }
+
+    // 予約再生を即座に開始する（外部トリガー用。予約が無ければ None）
+    pub fn fire_armed(&mut self) -> Option<ArmedStart> {
+        // 開始処理の間は予約を残しておく（start が予約再生かどうかを判別できるように）
+        let armed = self.armed?;
+        if armed.reversed {
+            self.start_reversed();
+        } else {
+            self.start();
+        }
+        self.armed = None;
+        // 起床の遅れがステップのタイミングに影響しないよう、予約時刻を開始時刻とする
+        if let Some(deadline) = armed.deadline {
+            if self.state == SequenceState::Playing {
+                self.sequence_start_time = Some(self.apply_latency_offset(deadline));
+            }
+        }
+        Some(armed)
+    }
+
+    // 予約再生の開始処理を行う時刻（負の補正では最初のステップに間に合うよう早める）
+    fn armed_wake_time(&self) -> Option<Instant> {
+        let deadline = self.armed.and_then(|a| a.deadline)?;
+        Some(self.apply_latency_offset(deadline).min(deadline))
+    }
+
+    // 入力遅延の補正を開始時刻に適用
+    //
+    // 負の補正で開始時刻が現在より前になる場合は現在時刻に揃える
+    // （過ぎてしまった先頭のステップがまとめて送信されないように）。
+    // そのため負の補正は時刻を指定した予約再生でのみ効き、即時開始では補正しない（start で警告を出す）
+    fn apply_latency_offset(&self, origin: Instant) -> Instant {
+        let offset = self.frames_to_duration(self.latency_offset_frames.unsigned_abs());
+        if self.latency_offset_frames >= 0 {
+            origin + offset
+        } else {
+            origin.checked_sub(offset).unwrap_or(origin).max(Instant::now())
+        }
+    }
+
     // 停止
     pub fn stop(&mut self) {
-        if self.state == SequenceState::Playing {
+        self.armed = None;
+        self.current_output = None;
+        self.held_input = false;
+        self.paused_at = None;
+        if self.state == SequenceState::Playing || self.state == SequenceState::Paused {
+            self.finish_run(true);
             self.state = SequenceState::Stopped;
             self.current_step = 0;
             self.sequence_start_time = None;
             self.next_step_time = Duration::from_secs(0);
+            self.elapsed_frames = 0;
+            self.release_inserted_step = None;
             println!("[Player] 停止");
         }
     }
@@ -88,22 +296,265 @@ impl Player {
         self.stop();
     }
 
+    // ブレークポイントで一時停止中なら続きから再生、それ以外は先頭から再生
     pub fn resume(&mut self) {
-        self.start();
+        if self.state != SequenceState::Paused {
+            self.start();
+            return;
+        }
+        // 一時停止していた時間だけ開始時刻を後ろにずらし、以降のステップ間隔を保つ
+        if let (Some(start), Some(paused_at)) = (self.sequence_start_time, self.paused_at.take()) {
+            self.sequence_start_time = Some(start + paused_at.elapsed());
+        }
+        self.resumed_step = Some(self.current_step);
+        self.state = SequenceState::Playing;
+        println!("[Player] ステップ {} から再開", self.frame_index(self.current_step));
+    }
+
+    // 再生中の記録を履歴に追加する
+    fn finish_run(&mut self, stopped_early: bool) {
+        if let Some(run) = self.active_run.take() {
+            self.history.push(run.finish(self.current_step, self.frames.len(), stopped_early));
+        }
+    }
+
+    pub fn get_history(&self) -> &PlaybackHistory {
+        &self.history
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    // ラベルを設定（load_frames の後に呼ぶ。範囲外のステップを指すラベルは無視）
+    pub fn set_labels(&mut self, labels: Vec<SequenceLabel>) {
+        let len = self.frames.len();
+        self.labels = labels.into_iter().filter(|label| label.step < len).collect();
+    }
+
+    pub fn get_labels(&self) -> &[SequenceLabel] {
+        &self.labels
+    }
+
+    // ラベルを付けたステップへ移動する（戻り値はラベルのステップの行番号）
+    // 再生中・一時停止中はそのステップから続きを再生し、停止中はそのステップから再生を開始する。
+    pub fn jump_to_label(&mut self, name: &str) -> Result<usize> {
+        let row = self
+            .labels
+            .iter()
+            .find(|label| label.name == name)
+            .map(|label| label.step)
+            .ok_or_else(|| anyhow::anyhow!("ラベルが見つかりません: {}", name))?;
+        self.seek(row)?;
+        println!("[Player] ラベル '{}' (ステップ {}) へ移動", name, row);
+        Ok(row)
+    }
+
+    // 指定した行のステップへ移動する（再生中・停止中の扱いは jump_to_label と同じ）
+    pub fn seek(&mut self, row: usize) -> Result<usize> {
+        if row >= self.frames.len() {
+            anyhow::bail!("ステップが範囲外です: {} (全 {} ステップ)", row, self.frames.len());
+        }
+
+        if self.state != SequenceState::Playing && self.state != SequenceState::Paused {
+            self.start();
+        }
+        let step = self.frame_index(row);
+
+        // 移動先のステップがすぐに送信されるよう、開始時刻を移動先までのフレーム数だけ前にずらす
+        let frames_before = self.frames_before(step);
+        self.current_step = step;
+        self.elapsed_frames = frames_before;
+        self.next_step_time = self.frames_to_duration(frames_before);
+        self.release_inserted_step = Some(step);
+        self.resumed_step = Some(step);
+        self.preroll_index = self.preroll.len();
+        let anchor = self.paused_at.unwrap_or_else(Instant::now);
+        self.sequence_start_time = Some(anchor.checked_sub(self.next_step_time).unwrap_or(anchor));
+        Ok(row)
+    }
+
+    pub fn set_breakpoints(&mut self, steps: Vec<usize>) {
+        self.breakpoints = steps.into_iter().collect();
+    }
+
+    pub fn get_breakpoints(&self) -> Vec<usize> {
+        let mut steps: Vec<usize> = self.breakpoints.iter().copied().collect();
+        steps.sort_unstable();
+        steps
+    }
+
+    // 一時停止中のブレークポイント情報
+    pub fn breakpoint_event(&self) -> Option<BreakpointHitEvent> {
+        if self.state != SequenceState::Paused {
+            return None;
+        }
+        let cumulative_frame = self.frames_before(self.current_step);
+        Some(BreakpointHitEvent {
+            step: self.frame_index(self.current_step),
+            cumulative_frame,
+        })
     }
 
     pub fn set_invert_horizontal(&mut self, invert: bool) {
         self.invert_horizontal = invert;
     }
 
+    pub fn get_invert_horizontal(&self) -> bool {
+        self.invert_horizontal
+    }
+
+    // 逆再生中か
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
     pub fn set_button_mapping(&mut self, mapping: HashMap<String, String>) {
         self.button_mapping = mapping;
     }
 
+    // CSVボタン名 -> コントローラーボタン名
+    pub fn get_button_mapping(&self) -> &HashMap<String, String> {
+        &self.button_mapping
+    }
+
     pub fn set_loop_playback(&mut self, loop_enabled: bool) {
         self.loop_playback = loop_enabled;
     }
 
+    pub fn get_loop_playback(&self) -> bool {
+        self.loop_playback
+    }
+
+    // ステップのフレーム数の倍率を設定（例: 60FPS用のシーケンスを30FPSのゲームで使う場合は 0.5）
+    pub fn set_duration_scale(&mut self, scale: f32) {
+        self.duration_scale = scale;
+        self.update_scaled_durations();
+    }
+
+    pub fn get_duration_scale(&self) -> f32 {
+        self.duration_scale
+    }
+
+    // 倍率を適用した各ステップのフレーム数を計算する（端数は誤差拡散で次のステップへ持ち越す）
+    fn update_scaled_durations(&mut self) {
+        let durations: Vec<u32> = self.frames.iter().map(|f| f.duration).collect();
+        self.scaled_durations = scale_durations(&durations, self.duration_scale as f64);
+        // 再生中に毎ステップ合計し直さなくて済むよう累積和を持っておく
+        self.duration_prefix = std::iter::once(0)
+            .chain(self.scaled_durations.iter().scan(0u32, |sum, &d| {
+                *sum += d;
+                Some(*sum)
+            }))
+            .collect();
+    }
+
+    // 倍率適用後の全ステップのフレーム数
+    fn total_scaled_frames(&self) -> u32 {
+        self.duration_prefix.last().copied().unwrap_or(0)
+    }
+
+    // 再生順で先頭から step 個のステップのフレーム数の合計（逆再生時は末尾側から数える）
+    fn frames_before(&self, step: usize) -> u32 {
+        let step = step.min(self.frames.len());
+        if self.reversed {
+            let rest = self.frames.len() - step;
+            self.total_scaled_frames() - self.duration_prefix[rest]
+        } else {
+            self.duration_prefix[step]
+        }
+    }
+
+    // 倍率適用後のステップのフレーム数（行番号で指定）
+    fn step_duration(&self, index: usize) -> u32 {
+        self.scaled_durations
+            .get(index)
+            .copied()
+            .unwrap_or(self.frames[index].duration)
+    }
+
+    pub fn set_release_gap_frames(&mut self, frames: u32) {
+        self.release_gap_frames = frames;
+    }
+
+    pub fn get_release_gap_frames(&self) -> u32 {
+        self.release_gap_frames
+    }
+
+    pub fn set_sync_marker(&mut self, marker: SyncMarkerSettings) {
+        self.sync_marker = marker;
+    }
+
+    pub fn get_sync_marker(&self) -> SyncMarkerSettings {
+        self.sync_marker.clone()
+    }
+
+    // 同期マーカーのフレーム列（ボタンを押す → 同じフレーム数だけ離す）
+    // マーカーはコントローラーのボタン名で指定するため、ボタンマッピングは適用しない
+    fn sync_marker_frames(&self) -> Vec<InputFrame> {
+        if !self.sync_marker.enabled || self.sync_marker.frames == 0 {
+            return Vec::new();
+        }
+        let press = InputFrame {
+            buttons: HashMap::from([(self.sync_marker.button.clone(), 1)]),
+            ..InputFrame::neutral(self.sync_marker.frames)
+        };
+        let release = InputFrame {
+            buttons: HashMap::new(),
+            ..press.clone()
+        };
+        vec![press, release]
+    }
+
+    // 送信予定時刻の補正を設定（負の値は時刻を指定した予約再生でのみ適用される）
+    pub fn set_latency_offset_frames(&mut self, frames: i32) {
+        self.latency_offset_frames = frames;
+    }
+
+    pub fn get_latency_offset_frames(&self) -> i32 {
+        self.latency_offset_frames
+    }
+
+    // 送信の遅れを警告するしきい値（0で無効）
+    pub fn set_late_step_warning_ms(&mut self, ms: u32) {
+        self.late_step_warning = Duration::from_millis(ms as u64);
+    }
+
+    pub fn get_late_step_warning_ms(&self) -> u32 {
+        self.late_step_warning.as_millis() as u32
+    }
+
+    /// 直近の update で警告のしきい値を超えて遅れた送信があれば取り出す
+    pub fn take_late_step(&mut self) -> Option<LateStepEvent> {
+        self.late_step.take()
+    }
+
+    pub fn set_hold_last_input(&mut self, hold: bool) {
+        self.hold_last_input = hold;
+    }
+
+    pub fn get_hold_last_input(&self) -> bool {
+        self.hold_last_input
+    }
+
+    // 今回の再生のみ終了時の入力保持を切り替える（start の後に呼ぶ）
+    pub fn set_hold_for_current_playback(&mut self, hold: bool) {
+        self.hold_current = hold;
+    }
+
+    // 再生終了後に最終ステップの入力を保持中か
+    pub fn is_holding_input(&self) -> bool {
+        self.held_input
+    }
+
+    // 保持中の入力を解除（以降は手動入力に戻す）
+    pub fn release_held_input(&mut self) {
+        if self.held_input {
+            self.held_input = false;
+            println!("[Player] 最終ステップの入力保持を解除");
+        }
+    }
+
     pub fn set_fps(&mut self, fps: u32) {
         self.fps = fps;
     }
@@ -114,6 +565,176 @@ impl Player {
         self.state
     }
 
+    pub fn set_manual_overlay(&mut self, enabled: bool) {
+        self.manual_overlay = enabled;
+        if !enabled {
+            self.manual_input = None;
+        }
+    }
+
+    pub fn get_manual_overlay(&self) -> bool {
+        self.manual_overlay
+    }
+
+    // 重ねる手動入力を更新し、再生中であれば現在のステップに重ねたフレームを返す
+    // （戻り値はコントローラーへそのまま送信する: (フレーム, 左右反転)）
+    pub fn update_manual_overlay(&mut self, manual: InputFrame) -> Option<(InputFrame, bool)> {
+        self.manual_input = Some(manual);
+        if self.state != SequenceState::Playing {
+            return None;
+        }
+        let (frame, invert) = self.current_output.as_ref()?;
+        Some((self.with_manual_overlay(frame, *invert), *invert))
+    }
+
+    // シーケンスのフレームに手動入力を重ねる
+    // ボタンはOR結合し、方向は手動入力がニュートラル以外なら手動入力を優先する
+    fn with_manual_overlay(&self, frame: &InputFrame, invert_horizontal: bool) -> InputFrame {
+        let manual = match (&self.manual_input, self.manual_overlay) {
+            (Some(manual), true) => manual,
+            _ => return frame.clone(),
+        };
+
+        let mut merged = frame.clone();
+        for (button, &value) in &manual.buttons {
+            if value == 1 {
+                merged.buttons.insert(button.clone(), 1);
+            }
+        }
+        if manual.direction != 5 {
+            // 送信時に左右反転が適用されるため、手動入力の方向はあらかじめ反転を打ち消しておく
+            merged.direction = if invert_horizontal {
+                mirror_direction(manual.direction)
+            } else {
+                manual.direction
+            };
+        }
+        merged
+    }
+
+    // 再生順のステップ番号を行番号に変換
+    fn frame_index(&self, step: usize) -> usize {
+        if self.reversed {
+            self.frames.len() - 1 - step
+        } else {
+            step
+        }
+    }
+
+    // CSVボタン名をXboxボタン名に変換したフレームを作成
+    fn map_frame(&self, frame: &InputFrame) -> InputFrame {
+        let mut mapped_frame = frame.clone();
+        let mut mapped_buttons = HashMap::new();
+
+        for (csv_button, value) in &frame.buttons {
+            if let Some(xbox_button) = self.button_mapping.get(csv_button) {
+                let current_value = mapped_buttons.get(xbox_button).unwrap_or(&0);
+                let new_value = if *current_value == 1 || *value == 1 { 1 } else { 0 };
+                mapped_buttons.insert(xbox_button.clone(), new_value);
+            }
+        }
+        mapped_frame.buttons = mapped_buttons;
+        mapped_frame
+    }
+
+    // 前後のステップで押され続けるボタンがあれば、それらを離したリリース用フレームを返す
+    // （方向や他のボタンは前のステップの状態を維持する）
+    fn release_frame_between(&self, prev: usize, next: usize) -> Option<InputFrame> {
+        let prev_frame = self.map_frame(&self.frames[prev]);
+        let next_frame = self.map_frame(&self.frames[next]);
+        let buttons = release_buttons(&prev_frame.buttons, &next_frame.buttons)?;
+        Some(InputFrame { duration: self.release_gap_frames, buttons, ..prev_frame })
+    }
+
+    // フレーム数を経過時間に変換（ミリ秒に丸めず秒の浮動小数で計算。60FPSで1フレーム16.666ms）
+    fn frames_to_duration(&self, frames: u32) -> Duration {
+        Duration::from_secs_f64(frames as f64 / self.fps as f64)
+    }
+
+    // 予定時刻からの送信の遅れを記録する
+    fn record_send_timing(&mut self, elapsed: Duration) {
+        let jitter = elapsed.saturating_sub(self.next_step_time);
+        if !self.late_step_warning.is_zero() && jitter >= self.late_step_warning {
+            self.late_step = Some(LateStepEvent {
+                step: self.frame_index(self.current_step),
+                late_ms: jitter.as_secs_f64() * 1000.0,
+                threshold_ms: self.late_step_warning.as_millis() as u32,
+            });
+        }
+
+        let timing = &mut self.timing;
+        timing.samples += 1;
+        timing.min_jitter = Some(timing.min_jitter.map_or(jitter, |min| min.min(jitter)));
+        timing.max_jitter = timing.max_jitter.max(jitter);
+        timing.total_jitter += jitter;
+
+        let missed = (jitter.as_secs_f64() * self.fps as f64).floor() as u32;
+        if missed > 0 {
+            timing.late_steps += 1;
+            timing.missed_frames += missed;
+        }
+    }
+
+    // 直近の再生の送信タイミング集計
+    pub fn timing_report(&self) -> PlaybackTimingReport {
+        let timing = &self.timing;
+        let to_ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        PlaybackTimingReport {
+            samples: timing.samples,
+            min_jitter_ms: timing.min_jitter.map_or(0.0, to_ms),
+            max_jitter_ms: to_ms(timing.max_jitter),
+            mean_jitter_ms: if timing.samples > 0 {
+                to_ms(timing.total_jitter) / timing.samples as f64
+            } else {
+                0.0
+            },
+            late_steps: timing.late_steps,
+            missed_frames: timing.missed_frames,
+            fps: self.fps,
+        }
+    }
+
+    // コントローラーに送信せずに各ステップの開始時刻を計算する（fps 省略時は現在の設定）
+    // 同期マーカーと同じボタンが連続する箇所に挿入するリリースも再生時と同様に考慮する
+    pub fn timeline(&self, fps: Option<u32>) -> SequenceTimeline {
+        let fps = fps.unwrap_or(self.fps).max(1);
+        let to_ms = |frames: u32| frames as f64 * 1000.0 / fps as f64;
+
+        let mut elapsed_frames: u32 = self.sync_marker_frames().iter().map(|f| f.duration).sum();
+        let mut steps = Vec::with_capacity(self.frames.len());
+        for index in 0..self.frames.len() {
+            if self.release_gap_frames > 0 && index > 0 {
+                if let Some(release_frame) = self.release_frame_between(index - 1, index) {
+                    elapsed_frames += release_frame.duration;
+                }
+            }
+            steps.push(TimelineStep {
+                step: index,
+                start_frame: elapsed_frames,
+                start_ms: to_ms(elapsed_frames),
+                duration_frames: self.step_duration(index),
+            });
+            elapsed_frames += self.step_duration(index);
+        }
+
+        SequenceTimeline {
+            fps,
+            total_frames: elapsed_frames,
+            total_duration_ms: to_ms(elapsed_frames),
+            steps,
+        }
+    }
+
+    // 次のステップを送信すべき時刻（再生中、または予約再生の待機中）
+    // メインループはこの時刻に合わせて待機する
+    pub fn next_step_deadline(&self) -> Option<Instant> {
+        if self.state != SequenceState::Playing {
+            // 予約再生の待機中は予約時刻に起床する
+            return self.armed_wake_time();
+        }
+        self.sequence_start_time.map(|t| t + self.next_step_time)
+    }
+
     // メインループから呼ばれる更新関数
     // controller_opt が Some の場合はコントローラーへ入力を送信する。
     // None の場合はコントローラー送信をスキップするが、再生進行自体は行う。
@@ -129,53 +750,108 @@ impl Player {
         };
 
         // 8. 再生開始時間からの経過時間を取得
-        let elapsed = start_time.elapsed();
+        // 正の遅延補正で開始時刻が未来の場合はまだ何も送信しない
+        let elapsed = match Instant::now().checked_duration_since(start_time) {
+            Some(elapsed) => elapsed,
+            None => return Ok((false, false)),
+        };
         let mut state_changed = false;
 
         // 9. 開始時刻からの絶対経過時間で送信時刻を管理 (累積誤差を防ぐ)
         // 10. 現在時刻から次の送信時刻までの差分sleep (メインループが60FPSで呼ぶのでここではチェックのみ)
             if elapsed >= self.next_step_time {
+            // 同期マーカーをシーケンスより先に送信（マーカー分だけ以降のタイミングは後ろにずれる）
+            if self.preroll_index < self.preroll.len() {
+                let marker_frame = self.preroll[self.preroll_index].clone();
+                let mut sent = false;
+                if let Some(ctrl) = controller_opt {
+                    if ctrl.is_connected() {
+                        if ctrl.update_input(&self.with_manual_overlay(&marker_frame, false), false).is_ok() {
+                            sent = true;
+                        }
+                    }
+                }
+                self.current_output = Some((marker_frame.clone(), false));
+                self.preroll_index += 1;
+                self.elapsed_frames += marker_frame.duration;
+                self.next_step_time = self.frames_to_duration(self.elapsed_frames);
+                return Ok((sent, state_changed));
+            }
+
             // 5. コントローラの状態を現在のステップの入力状態に更新
             if self.current_step < self.frames.len() {
-                let frame = &self.frames[self.current_step];
-                
-                // ボタンマッピングを適用
-                let mut mapped_frame = frame.clone();
-                let mut mapped_buttons = HashMap::new();
-
-                for (csv_button, value) in &frame.buttons {
-                    if let Some(xbox_button) = self.button_mapping.get(csv_button) {
-                        let current_value = mapped_buttons.get(xbox_button).unwrap_or(&0);
-                        let new_value = if *current_value == 1 || *value == 1 { 1 } else { 0 };
-                        mapped_buttons.insert(xbox_button.clone(), new_value);
+                // 同じボタンを押し続けるステップが連続する場合、間にリリースを挿入して
+                // ゲーム側で押し直しとして認識させる（挿入分だけ以降のタイミングは後ろにずれる）
+                if self.release_gap_frames > 0
+                    && self.current_step > 0
+                    && self.release_inserted_step != Some(self.current_step)
+                {
+                    let prev_index = self.frame_index(self.current_step - 1);
+                    let next_index = self.frame_index(self.current_step);
+                    if let Some(release_frame) = self.release_frame_between(prev_index, next_index) {
+                        self.record_send_timing(elapsed);
+                        let mut sent = false;
+                        if let Some(ctrl) = controller_opt {
+                            if ctrl.is_connected() {
+                                let output = self.with_manual_overlay(&release_frame, self.invert_horizontal);
+                                if ctrl.update_input(&output, self.invert_horizontal).is_ok() {
+                                    sent = true;
+                                }
+                            }
+                        }
+                        self.current_output = Some((release_frame, self.invert_horizontal));
+                        self.release_inserted_step = Some(self.current_step);
+                        self.elapsed_frames += self.release_gap_frames;
+                        self.next_step_time = self.frames_to_duration(self.elapsed_frames);
+                        return Ok((sent, state_changed));
                     }
                 }
-                mapped_frame.buttons = mapped_buttons;
+
+                // ブレークポイント: このステップを送信する前に一時停止し、入力を離す
+                if self.breakpoints.contains(&self.frame_index(self.current_step))
+                    && self.resumed_step != Some(self.current_step)
+                {
+                    let mut sent = false;
+                    if let Some(ctrl) = controller_opt {
+                        if ctrl.is_connected() {
+                            if ctrl.update_input(&self.with_manual_overlay(&InputFrame::neutral(1), false), false).is_ok() {
+                                sent = true;
+                            }
+                        }
+                    }
+                    self.current_output = None;
+                    self.state = SequenceState::Paused;
+                    self.paused_at = Some(Instant::now());
+                    println!("[Player] ブレークポイント: ステップ {} で一時停止", self.frame_index(self.current_step));
+                    return Ok((sent, true));
+                }
+
+                // ボタンマッピングを適用
+                let frame_index = self.frame_index(self.current_step);
+                let mapped_frame = self.map_frame(&self.frames[frame_index]);
+                self.record_send_timing(elapsed);
 
                 // 6. コントローラの状態をドライバに送信
                 // コントローラーが渡されている場合のみ送信を行う
                 let mut sent = false;
                 if let Some(ctrl) = controller_opt {
                     if ctrl.is_connected() {
-                        if ctrl.update_input(&mapped_frame, self.invert_horizontal).is_ok() {
+                        let output = self.with_manual_overlay(&mapped_frame, self.invert_horizontal);
+                        if ctrl.update_input(&output, self.invert_horizontal).is_ok() {
                             sent = true;
                         }
                     }
                 }
+                self.current_output = Some((mapped_frame.clone(), self.invert_horizontal));
+                self.last_sent_frame = Some(mapped_frame);
 
                 // 次のステップの送信時刻を開始時刻からの絶対時間で計算（現在のステップをインクリメントする前）
                 // 例: step0(3F) 送信後 → next_step_time = 0 + 3*1000/60 = 50ms
                 //     step1(5F) 送信後 → next_step_time = 0 + (3+5)*1000/60 = 133ms
                 //     step2(4F) 送信後 → next_step_time = 0 + (3+5+4)*1000/60 = 200ms
-                // これにより各ステップの誤差が累積しない
-                let mut cumulative_duration = 0u32;
-                for i in 0..=self.current_step {
-                    if i < self.frames.len() {
-                        cumulative_duration += self.frames[i].duration;
-                    }
-                }
-                let cumulative_ms = cumulative_duration as f64 * 1000.0 / self.fps as f64;
-                self.next_step_time = Duration::from_millis(cumulative_ms as u64);
+                // 累積は整数フレーム数で保持するため各ステップの誤差が累積しない
+                self.elapsed_frames += self.step_duration(frame_index);
+                self.next_step_time = self.frames_to_duration(self.elapsed_frames);
 
                 // 7. コントローラの内部状態を次のステップの状態に更新
                 self.current_step += 1;
@@ -183,42 +859,57 @@ impl Player {
                 return Ok((sent, state_changed));
             } else if self.current_step >= self.frames.len() {
                 // 全てのステップを送信済みで、最後のステップのdurationも経過した
+                if let Some(run) = self.active_run.as_mut() {
+                    run.complete_loop();
+                }
                 if self.loop_playback {
                     // ループ再生: 先頭に戻る
                     self.current_step = 0;
                     // ループの先頭に戻るたびに開始時刻を更新（各ループサイクルが独立した正確なタイミングで再生）
                     self.sequence_start_time = Some(Instant::now());
                     self.next_step_time = Duration::from_secs(0);
+                    self.elapsed_frames = 0;
+                    self.release_inserted_step = None;
+                    self.resumed_step = None;
                     state_changed = true;
                     println!("[Player] ループ再生: 先頭に戻ります");
                     return Ok((false, state_changed));
+                } else if self.hold_current && self.last_sent_frame.is_some() {
+                    // 入力保持: 最終ステップの入力をコントローラーに残したまま停止
+                    // （次の手動入力があるまで保持し続ける）
+                    self.finish_run(false);
+                    self.state = SequenceState::Stopped;
+                    self.current_step = 0;
+                    self.sequence_start_time = None;
+                    self.next_step_time = Duration::from_secs(0);
+                    self.elapsed_frames = 0;
+                    self.release_inserted_step = None;
+                    self.held_input = true;
+                    state_changed = true;
+                    println!("[Player] 再生完了: 最終ステップの入力を保持したまま停止状態に遷移");
+                    return Ok((false, state_changed));
                 } else {
                     // 通常再生: 無入力を送信してから停止
-                    let neutral_frame = InputFrame {
-                        duration: 1,
-                        direction: 5, // 中立
-                        buttons: HashMap::new(), // 全ボタンOFF
-                        thumb_lx: 0,
-                        thumb_ly: 0,
-                        thumb_rx: 0,
-                        thumb_ry: 0,
-                        left_trigger: 0,
-                        right_trigger: 0,
-                    };
+                    let neutral_frame = InputFrame::neutral(1);
                     // コントローラがあれば中立入力を送信する
                     let mut sent = false;
+                    // オーバーレイモードでは手動入力だけが残る
                     if let Some(ctrl) = controller_opt {
                         if ctrl.is_connected() {
-                            if ctrl.update_input(&neutral_frame, false).is_ok() {
+                            if ctrl.update_input(&self.with_manual_overlay(&neutral_frame, false), false).is_ok() {
                                 sent = true;
                             }
                         }
                     }
+                    self.current_output = None;
 
+                    self.finish_run(false);
                     self.state = SequenceState::Stopped;
                     self.current_step = 0;
                     self.sequence_start_time = None;
                     self.next_step_time = Duration::from_secs(0);
+                    self.elapsed_frames = 0;
+                    self.release_inserted_step = None;
                     state_changed = true;
                     println!("[Player] 再生完了: 無入力送信後、停止状態に遷移");
                     return Ok((sent, state_changed));
@@ -233,10 +924,32 @@ impl Player {
         (self.current_step, self.frames.len())
     }
 
+    // 今回の再生で最後まで再生し終えた回数（ループ再生の周回数。停止中は0）
+    pub fn loops_completed(&self) -> u32 {
+        self.active_run.as_ref().map_or(0, |run| run.loops_completed())
+    }
+
     pub fn get_current_step(&self) -> usize {
         self.current_step
     }
 
+    // 現在出力中のステップ情報（まだ何も送信していなければ None）
+    pub fn step_event(&self) -> Option<PlaybackStepEvent> {
+        if self.current_step == 0 || self.current_step > self.frames.len() {
+            return None;
+        }
+        // 累積フレームは再生順で数え、step は行番号で返す（逆再生時もエディタの行と一致させる）
+        let played = self.current_step - 1;
+        let cumulative_frame = self.frames_before(played);
+        let total_frames = self.total_scaled_frames();
+        Some(PlaybackStepEvent {
+            step: self.frame_index(played),
+            cumulative_frame,
+            total_steps: self.frames.len(),
+            total_frames,
+        })
+    }
+
     pub fn set_current_path(&mut self, path: String) {
         self.current_path = Some(path);
     }
@@ -245,3 +958,33 @@ impl Player {
         self.current_path.clone()
     }
 }
+
+/// 同じボタンが連続するステップ間に挿入するリリースのボタンの状態
+///
+/// prev で押されていて next でも押されているボタンを離し、それ以外は prev の状態を維持する。
+/// 押され続けるボタンが無ければ None。再生時の挿入と、動画から生成するCSVへの挿入
+/// （analyzer::insert_release_gaps）で同じ規則を使う。
+pub fn release_buttons(prev: &HashMap<String, u8>, next: &HashMap<String, u8>) -> Option<HashMap<String, u8>> {
+    let mut released = prev.clone();
+    let mut has_repeat = false;
+    for (button, value) in prev {
+        if *value == 1 && next.get(button) == Some(&1) {
+            released.insert(button.clone(), 0);
+            has_repeat = true;
+        }
+    }
+    has_repeat.then_some(released)
+}
+
+/// テンキー表記の方向を左右反転
+pub(crate) fn mirror_direction(direction: u8) -> u8 {
+    match direction {
+        1 => 3,
+        3 => 1,
+        4 => 6,
+        6 => 4,
+        7 => 9,
+        9 => 7,
+        d => d,
+    }
+}
@@ -0,0 +1,250 @@
+//! リモート操作API（`remote` feature）
+//!
+//! ストリームデッキや OBS のスクリプト、別のPCから再生を操作するためのHTTPサーバー。
+//! レスポンスはすべてJSONで、エラー時は {"error": "..."} を返す。
+//!
+//! すべてのリクエストに設定のトークン（"Authorization: Bearer <token>" か "?token=<token>"）が必要。
+//! Web ページから localhost に送られたリクエストで操作されないよう、CORS のヘッダーは返さず、
+//! `Origin` ヘッダーの付いたリクエスト（ブラウザからのリクエスト）は拒否する。
+//!
+//! | メソッド | パス        | 内容                                                         |
+//! |----------|-------------|--------------------------------------------------------------|
+//! | GET      | `/status`   | アプリの状態（`get_app_snapshot` と同じ）                    |
+//! | GET      | `/progress` | 再生状態と現在のステップ                                     |
+//! | POST     | `/start`    | 再生開始（`?reversed=true` で逆再生）                        |
+//! | POST     | `/stop`     | 停止                                                         |
+//! | POST     | `/pause`    | 一時停止                                                     |
+//! | POST     | `/resume`   | 再開                                                         |
+//! | POST     | `/load`     | シーケンスを読み込み（`{"path": "..."}`）                    |
+//! | POST     | `/seek`     | 移動（`{"step": 10}` または `{"label": "..."}`）             |
+//! | GET      | `/events`   | WebSocket。Tauri イベントを `{"event", "payload"}` で配信   |
+
+use crate::model::RemoteSettings;
+use crate::AppState;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::sync::{mpsc, Arc, Mutex};
+use tauri::{Listener, Manager};
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+/// WebSocket に中継するイベント（フロントエンドが受け取るものと同じ）
+const FORWARDED_EVENTS: &[&str] = &[
+    "playback-state-changed",
+    "playback-step-changed",
+    "playback-breakpoint-hit",
+    "playback-step-late",
+    "scheduled-playback-fired",
+    "sequence-load-progress",
+    "file-reloaded",
+    "edit-session-changed",
+    "controller-selftest-progress",
+    "job-started",
+    "job-finished",
+    "live-input",
+    "extraction-frame",
+];
+
+// WebSocket クライアントごとの送信先
+type Subscribers = Arc<Mutex<Vec<mpsc::Sender<String>>>>;
+
+#[derive(Deserialize)]
+struct LoadRequest {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct SeekRequest {
+    step: Option<usize>,
+    label: Option<String>,
+}
+
+// エラー時のステータスコードとメッセージ
+type RouteError = (u16, String);
+
+/// サーバーを別スレッドで開始
+pub fn start(app: tauri::AppHandle, settings: &RemoteSettings) -> anyhow::Result<()> {
+    // トークンの確認と待ち受けが済むまではイベントのリスナーを登録しない（失敗時に残さないため）
+    let token = settings
+        .token
+        .clone()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("認証トークンが設定されていません"))?;
+    let address = format!("{}:{}", settings.bind_address, settings.port);
+    let server = Server::http(&address)
+        .map_err(|e| anyhow::anyhow!("{} で待ち受けできません: {}", address, e))?;
+
+    let subscribers: Subscribers = Arc::new(Mutex::new(Vec::new()));
+    for &name in FORWARDED_EVENTS {
+        let subscribers = subscribers.clone();
+        app.listen_any(name, move |event| {
+            let payload: Value = serde_json::from_str(event.payload()).unwrap_or(Value::Null);
+            let message = json!({ "event": name, "payload": payload }).to_string();
+            // 切断されたクライアントは送信に失敗するので取り除く
+            subscribers.lock().unwrap().retain(|tx| tx.send(message.clone()).is_ok());
+        });
+    }
+
+    println!("[Remote] http://{} で待ち受けを開始", address);
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle_request(&app, request, &token, &subscribers);
+        }
+    });
+    Ok(())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("ヘッダーは ASCII で指定する")
+}
+
+fn find_header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn is_authorized(request: &Request, query: &str, token: &str) -> bool {
+    let bearer = find_header(request, "Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    bearer == Some(token) || query_param(query, "token") == Some(token)
+}
+
+fn respond(request: Request, status: u16, body: Value) {
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json; charset=utf-8"));
+    if let Err(e) = request.respond(response) {
+        println!("[Remote] 応答の送信に失敗: {}", e);
+    }
+}
+
+fn handle_request(app: &tauri::AppHandle, mut request: Request, token: &str, subscribers: &Subscribers) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+
+    // ブラウザ（Web ページ）からのリクエストは受け付けない
+    if find_header(&request, "Origin").is_some() {
+        respond(request, 403, json!({ "error": "ブラウザからのリクエストは受け付けません" }));
+        return;
+    }
+    if !is_authorized(&request, query, token) {
+        respond(request, 401, json!({ "error": "認証に失敗しました" }));
+        return;
+    }
+    if request.method() == &Method::Get && path == "/events" {
+        accept_websocket(request, subscribers);
+        return;
+    }
+
+    let mut body = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut body) {
+        respond(request, 400, json!({ "error": format!("リクエストの読み込みに失敗: {}", e) }));
+        return;
+    }
+    let method = request.method().clone();
+    match route(app, &method, path, query, &body) {
+        Ok(value) => respond(request, 200, value),
+        Err((status, message)) => {
+            println!("[Remote] {} {} に失敗: {}", method, path, message);
+            respond(request, status, json!({ "error": message }));
+        }
+    }
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, RouteError> {
+    serde_json::from_str(body).map_err(|e| (400, format!("リクエストの解析に失敗: {}", e)))
+}
+
+fn route(app: &tauri::AppHandle, method: &Method, path: &str, query: &str, body: &str) -> Result<Value, RouteError> {
+    let state = app.state::<AppState>();
+    let failed = |e: String| (409, e);
+
+    match (method, path) {
+        (Method::Get, "/status") => Ok(json!(crate::app_snapshot(&state))),
+        (Method::Get, "/progress") => {
+            let player = state.player.lock().unwrap();
+            let (current_step, total_steps) = player.get_progress();
+            Ok(json!({
+                "state": player.get_state(),
+                "current_step": current_step,
+                "total_steps": total_steps,
+            }))
+        }
+        (Method::Post, "/start") => {
+            if query_param(query, "reversed") == Some("true") {
+                crate::start_playback_reversed(None, state).map_err(failed)?;
+            } else {
+                crate::start_playback(None, state).map_err(failed)?;
+            }
+            Ok(json!({}))
+        }
+        (Method::Post, "/stop") => {
+            crate::stop_playback(state).map_err(failed)?;
+            Ok(json!({}))
+        }
+        (Method::Post, "/pause") => {
+            crate::pause_playback(state).map_err(failed)?;
+            Ok(json!({}))
+        }
+        (Method::Post, "/resume") => {
+            crate::resume_playback(state).map_err(failed)?;
+            Ok(json!({}))
+        }
+        (Method::Post, "/load") => {
+            let request: LoadRequest = parse_body(body)?;
+            let steps = crate::load_sequence_file(&state, request.path).map_err(failed)?;
+            Ok(json!({ "steps": steps }))
+        }
+        (Method::Post, "/seek") => {
+            let request: SeekRequest = parse_body(body)?;
+            let mut player = state.player.lock().unwrap();
+            let step = match (request.step, request.label) {
+                (Some(step), _) => player.seek(step),
+                (None, Some(label)) => player.jump_to_label(&label),
+                (None, None) => return Err((400, "step か label を指定してください".to_string())),
+            };
+            let step = step.map_err(|e| failed(e.to_string()))?;
+            Ok(json!({ "step": step }))
+        }
+        _ => Err((404, format!("不明なエンドポイントです: {} {}", method, path))),
+    }
+}
+
+// WebSocket に切り替えてイベントの配信を始める
+fn accept_websocket(request: Request, subscribers: &Subscribers) {
+    let Some(key) = find_header(&request, "Sec-WebSocket-Key").map(str::to_string) else {
+        respond(request, 400, json!({ "error": "WebSocket の接続要求ではありません" }));
+        return;
+    };
+    let response = Response::empty(StatusCode(101))
+        .with_header(header("Upgrade", "websocket"))
+        .with_header(header("Connection", "Upgrade"))
+        .with_header(header("Sec-WebSocket-Accept", &tungstenite::handshake::derive_accept_key(key.as_bytes())));
+    let stream = request.upgrade("websocket", response);
+
+    let (tx, rx) = mpsc::channel::<String>();
+    subscribers.lock().unwrap().push(tx);
+    std::thread::spawn(move || {
+        let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        // 送信に失敗したら切断とみなして終了（次のイベントで購読者から外れる）
+        while let Ok(message) = rx.recv() {
+            if socket.send(Message::text(message)).is_err() {
+                break;
+            }
+        }
+    });
+}
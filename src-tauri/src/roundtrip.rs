@@ -0,0 +1,141 @@
+//! 往復テスト（再生 → 録画 → 解析）の結果比較
+//!
+//! 元のシーケンスCSVと、その再生を録画した動画から再抽出したCSVを比較する。
+//! 再生と録画はアプリ外で行い、`compare_recording_with_sequence` コマンドは再抽出と比較だけを行う。
+//! 比較は `accuracy::compare_sequences_accuracy` のフレーム単位の位置合わせをそのまま使い、
+//! 食い違ったフレームを元シーケンスのステップごとに集計する。
+//! 録画前後の無入力区間は位置合わせで両側とも読み飛ばされる。
+//! ボタン名は比較前にボタンマッピングでコントローラーのボタン名に揃える（CSVとモデルで名前が違っても比較できるように）。
+
+use crate::accuracy::{compare_sequences_accuracy, AccuracyReport};
+use crate::types::InputFrame;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// ステップ単位の比較結果
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundtripStepResult {
+    pub step: usize,            // 元シーケンスのステップ番号（0始まり）
+    pub start_frame: u32,       // 元シーケンスでのステップの開始フレーム
+    pub end_frame: u32,         // 元シーケンスでのステップの終了フレーム（含まない）
+    pub mismatched_frames: u32, // 再抽出側と入力が食い違ったフレーム数
+    pub equal: bool,            // ステップ内のすべてのフレームで方向・ボタンが一致
+}
+
+/// 往復テストの結果
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundtripReport {
+    pub extracted_csv_path: String, // 再抽出したCSVのパス
+    pub expected_steps: usize,
+    pub equal_steps: usize,
+    pub steps: Vec<RoundtripStepResult>,
+    pub accuracy: AccuracyReport, // フレーム単位の比較結果
+}
+
+/// 押されているボタンの名前をボタンマッピング（CSVボタン名 -> コントローラーボタン名）で揃える
+///
+/// マッピングに無い名前はそのまま使う（既にコントローラーのボタン名で書かれている場合）。
+pub fn normalize_buttons(frames: &[InputFrame], mapping: &HashMap<String, String>) -> Vec<InputFrame> {
+    frames
+        .iter()
+        .map(|frame| InputFrame {
+            buttons: frame
+                .buttons
+                .iter()
+                .filter(|(_, &v)| v == 1)
+                .map(|(name, _)| (mapping.get(name).unwrap_or(name).clone(), 1))
+                .collect(),
+            ..frame.clone()
+        })
+        .collect()
+}
+
+/// 比較結果をまとめたレポートを作成（expected と actual はボタン名を揃えたもの）
+pub fn build_report(
+    extracted_csv_path: String,
+    expected: &[InputFrame],
+    actual: &[InputFrame],
+) -> RoundtripReport {
+    let accuracy = compare_sequences_accuracy(actual, expected);
+
+    let mut start_frame = 0u32;
+    let steps: Vec<RoundtripStepResult> = expected
+        .iter()
+        .enumerate()
+        .map(|(step, frame)| {
+            let end_frame = start_frame + frame.duration;
+            let mismatched_frames = accuracy
+                .mismatches
+                .iter()
+                .map(|range| range.end_frame.min(end_frame).saturating_sub(range.start_frame.max(start_frame)))
+                .sum();
+            let result = RoundtripStepResult {
+                step,
+                start_frame,
+                end_frame,
+                mismatched_frames,
+                equal: mismatched_frames == 0,
+            };
+            start_frame = end_frame;
+            result
+        })
+        .collect();
+
+    RoundtripReport {
+        extracted_csv_path,
+        expected_steps: expected.len(),
+        equal_steps: steps.iter().filter(|s| s.equal).count(),
+        steps,
+        accuracy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_ignores_leading_and_trailing_idle() {
+        let expected = vec![
+            InputFrame::with_buttons(10, 5, &[]),
+            InputFrame::with_buttons(3, 6, &["A"]),
+            InputFrame::with_buttons(5, 2, &[]),
+            InputFrame::with_buttons(4, 5, &["B"]),
+        ];
+        let actual = vec![
+            InputFrame::with_buttons(120, 5, &[]),
+            InputFrame::with_buttons(3, 6, &["A"]),
+            InputFrame::with_buttons(5, 2, &[]),
+            InputFrame::with_buttons(4, 5, &["B"]),
+            InputFrame::with_buttons(300, 5, &[]),
+        ];
+        let report = build_report(String::new(), &expected, &actual);
+        assert_eq!(report.equal_steps, 4);
+        assert_eq!(report.accuracy.frame_accuracy, 1.0);
+    }
+
+    #[test]
+    fn test_compare_reports_mismatch() {
+        let expected = vec![
+            InputFrame::with_buttons(3, 6, &["A"]),
+            InputFrame::with_buttons(4, 5, &["B"]),
+            InputFrame::with_buttons(2, 5, &[]),
+        ];
+        let actual = vec![InputFrame::with_buttons(3, 6, &["A"]), InputFrame::with_buttons(5, 5, &["B"])];
+        let report = build_report(String::new(), &expected, &actual);
+        assert!(report.steps[0].equal);
+        assert!(report.steps[1].equal);
+        // B を1フレーム長く押している
+        assert!(!report.steps[2].equal);
+        assert_eq!(report.steps[2].mismatched_frames, 1);
+    }
+
+    #[test]
+    fn test_normalize_buttons_uses_mapping() {
+        let mapping = HashMap::from([("Punch".to_string(), "X".to_string())]);
+        let normalized = normalize_buttons(&[InputFrame::with_buttons(1, 5, &["Punch", "A"])], &mapping);
+        let mut pressed: Vec<&str> = normalized[0].buttons.keys().map(|b| b.as_str()).collect();
+        pressed.sort_unstable();
+        assert_eq!(pressed, vec!["A", "X"]);
+    }
+}
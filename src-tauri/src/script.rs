@@ -0,0 +1,368 @@
+//! スクリプト（Rhai）によるシーケンス生成
+//!
+//! `.rhai` ファイルを読み込み時に実行し、最後の式の配列をシーケンスとして使う。
+//! 配列の要素はステップを表すマップ（`#{duration: 3, direction: 6, buttons: ["LP"]}`）か、
+//! 下記の関数が返すステップの配列。
+//!
+//! - `step(duration, direction)` / `press(duration, direction, buttons)`: 1ステップ
+//! - `motion(name)` / `motion(name, button)`: コマンド入力（"qcf" など。[`crate::generator`]）
+//! - `notation(text)`: テンキー表記（"236A 5B"。[`crate::notation`]）
+//! - `FPS`: 読み込み時のFPS設定
+//!
+//! スクリプトに `fn on_loop(count) { ... }` を定義すると、ループ再生で先頭に戻るたびに
+//! 周回数（1始まり）を引数に呼ばれ、返したステップがシーケンスの末尾に追加される
+//! （例: `if count == 3 { notation("236A") } else { [] }`）。
+//!
+//! 無限ループや巨大な配列で止まらないよう、演算の回数・呼び出しの深さ・文字列と配列の大きさと
+//! 実行時間に上限を設け、超えたら実行を中断してエラーにする。
+
+use crate::model::NotationSettings;
+use crate::types::InputFrame;
+use anyhow::Result;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// ループ時に呼ぶ関数名
+const ON_LOOP_FN: &str = "on_loop";
+
+/// 1回の実行で行える演算の回数の上限
+const MAX_OPERATIONS: u64 = 50_000_000;
+/// 関数呼び出しの深さの上限
+const MAX_CALL_LEVELS: usize = 64;
+/// 文字列の長さの上限（バイト）
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+/// 配列・マップの要素数の上限
+const MAX_ARRAY_SIZE: usize = 1_000_000;
+const MAX_MAP_SIZE: usize = 100_000;
+
+/// 読み込み時の実行の制限時間
+const GENERATE_TIME_LIMIT: Duration = Duration::from_secs(10);
+/// on_loop の制限時間（再生スレッドで呼ぶため、次のステップの送信を遅らせないよう短くする）
+const ON_LOOP_TIME_LIMIT: Duration = Duration::from_millis(20);
+
+/// 制限時間を確認する間隔（演算の回数）
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+/// スクリプトファイルか（拡張子 .rhai）
+pub fn is_script(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rhai"))
+}
+
+fn frame_to_dynamic(frame: &InputFrame) -> Dynamic {
+    let buttons: Map = frame
+        .buttons
+        .iter()
+        .map(|(name, &value)| (name.as_str().into(), Dynamic::from(value as i64)))
+        .collect();
+    let mut map = Map::new();
+    map.insert("duration".into(), Dynamic::from(frame.duration as i64));
+    map.insert("direction".into(), Dynamic::from(frame.direction as i64));
+    map.insert("buttons".into(), Dynamic::from(buttons));
+    for (key, value) in [
+        ("thumb_lx", frame.thumb_lx as i64),
+        ("thumb_ly", frame.thumb_ly as i64),
+        ("thumb_rx", frame.thumb_rx as i64),
+        ("thumb_ry", frame.thumb_ry as i64),
+        ("left_trigger", frame.left_trigger as i64),
+        ("right_trigger", frame.right_trigger as i64),
+    ] {
+        if value != 0 {
+            map.insert(key.into(), Dynamic::from(value));
+        }
+    }
+    Dynamic::from(map)
+}
+
+fn frames_to_dynamic(frames: &[InputFrame]) -> Array {
+    frames.iter().map(frame_to_dynamic).collect()
+}
+
+// マップの整数項目（省略時は default、範囲外はエラー）
+fn int_field<T: TryFrom<i64>>(map: &Map, key: &str, default: T) -> Result<T> {
+    let Some(value) = map.get(key) else { return Ok(default) };
+    let value = value
+        .as_int()
+        .map_err(|_| anyhow::anyhow!("{} は整数で指定してください", key))?;
+    T::try_from(value).map_err(|_| anyhow::anyhow!("{} が範囲外です: {}", key, value))
+}
+
+// ボタンは名前の配列（押したボタン）か 名前→0/1 のマップで指定する
+fn buttons_from_dynamic(value: &Dynamic) -> Result<HashMap<String, u8>> {
+    if let Some(names) = value.read_lock::<Array>() {
+        return names
+            .iter()
+            .map(|name| {
+                name.clone()
+                    .into_string()
+                    .map(|name| (name, 1))
+                    .map_err(|_| anyhow::anyhow!("ボタン名は文字列で指定してください"))
+            })
+            .collect();
+    }
+    if let Some(map) = value.read_lock::<Map>() {
+        return map
+            .iter()
+            .map(|(name, state)| {
+                let state = state.as_int().ok().and_then(|v| u8::try_from(v).ok())
+                    .ok_or_else(|| anyhow::anyhow!("ボタン {} の値は0か1で指定してください", name))?;
+                Ok((name.to_string(), state))
+            })
+            .collect();
+    }
+    if let Ok(name) = value.clone().into_string() {
+        return Ok(HashMap::from([(name, 1)]));
+    }
+    anyhow::bail!("buttons は配列かマップで指定してください")
+}
+
+fn frame_from_dynamic(value: &Dynamic) -> Result<InputFrame> {
+    let map = value
+        .read_lock::<Map>()
+        .ok_or_else(|| anyhow::anyhow!("ステップはマップで指定してください: {}", value))?;
+    let duration: u32 = int_field(&map, "duration", 0)?;
+    if duration == 0 {
+        anyhow::bail!("duration は1以上を指定してください: {}", value);
+    }
+    let direction: u8 = int_field(&map, "direction", 5)?;
+    if !(1..=9).contains(&direction) {
+        anyhow::bail!("direction は1〜9で指定してください: {}", direction);
+    }
+    Ok(InputFrame {
+        duration,
+        direction,
+        buttons: match map.get("buttons") {
+            Some(buttons) => buttons_from_dynamic(buttons)?,
+            None => HashMap::new(),
+        },
+        thumb_lx: int_field(&map, "thumb_lx", 0)?,
+        thumb_ly: int_field(&map, "thumb_ly", 0)?,
+        thumb_rx: int_field(&map, "thumb_rx", 0)?,
+        thumb_ry: int_field(&map, "thumb_ry", 0)?,
+        left_trigger: int_field(&map, "left_trigger", 0)?,
+        right_trigger: int_field(&map, "right_trigger", 0)?,
+        extra_columns: Vec::new(),
+    })
+}
+
+// スクリプトの結果（ステップかステップの配列を要素とする配列、入れ子可）をフレーム列に変換
+fn collect_frames(value: &Dynamic, frames: &mut Vec<InputFrame>) -> Result<()> {
+    match value.read_lock::<Array>() {
+        Some(items) => {
+            for item in items.iter() {
+                collect_frames(item, frames)?;
+            }
+        }
+        None => frames.push(frame_from_dynamic(value)?),
+    }
+    Ok(())
+}
+
+fn to_script_error(e: anyhow::Error) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+// 実行中のスクリプトの中断期限（実行していない間は None）
+type Deadline = Arc<Mutex<Option<Instant>>>;
+
+fn create_engine(fps: u32, notation: NotationSettings, deadline: Deadline) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_ARRAY_SIZE);
+    engine.set_max_map_size(MAX_MAP_SIZE);
+    engine.on_progress(move |operations| {
+        if operations % DEADLINE_CHECK_INTERVAL != 0 {
+            return None;
+        }
+        let expired = deadline.lock().unwrap().is_some_and(|deadline| Instant::now() >= deadline);
+        expired.then(|| "制限時間を超えました".into())
+    });
+
+    engine.register_fn("step", |duration: i64, direction: i64| -> Dynamic {
+        let mut map = Map::new();
+        map.insert("duration".into(), Dynamic::from(duration));
+        map.insert("direction".into(), Dynamic::from(direction));
+        Dynamic::from(map)
+    });
+    engine.register_fn("press", |duration: i64, direction: i64, buttons: Dynamic| -> Dynamic {
+        let mut map = Map::new();
+        map.insert("duration".into(), Dynamic::from(duration));
+        map.insert("direction".into(), Dynamic::from(direction));
+        map.insert("buttons".into(), buttons);
+        Dynamic::from(map)
+    });
+    let motion_notation = notation.clone();
+    engine.register_fn("motion", move |name: &str| -> ScriptResult<Array> {
+        crate::generator::generate_motion(name, None, fps, &motion_notation)
+            .map(|frames| frames_to_dynamic(&frames))
+            .map_err(to_script_error)
+    });
+    let motion_notation = notation.clone();
+    engine.register_fn("motion", move |name: &str, button: &str| -> ScriptResult<Array> {
+        crate::generator::generate_motion(name, Some(button), fps, &motion_notation)
+            .map(|frames| frames_to_dynamic(&frames))
+            .map_err(to_script_error)
+    });
+    engine.register_fn("notation", move |text: &str| -> ScriptResult<Array> {
+        crate::notation::parse_notation_with(text, &notation)
+            .map(|frames| frames_to_dynamic(&frames))
+            .map_err(to_script_error)
+    });
+    engine.on_print(|text| println!("[スクリプト] {}", text));
+    engine
+}
+
+/// 読み込んだスクリプト
+pub struct SequenceScript {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    has_on_loop: bool,
+    deadline: Deadline,
+}
+
+impl SequenceScript {
+    /// スクリプトを読み込んでコンパイル（まだ実行しない）
+    pub fn load(path: &Path, fps: u32, notation: NotationSettings) -> Result<Self> {
+        let deadline = Deadline::default();
+        let engine = create_engine(fps, notation, deadline.clone());
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| anyhow::anyhow!("スクリプトの読み込みに失敗: {}", e))?;
+        let has_on_loop = ast.iter_functions().any(|f| f.name == ON_LOOP_FN && f.params.len() == 1);
+        let mut scope = Scope::new();
+        scope.push_constant("FPS", fps as i64);
+        Ok(Self { engine, ast, scope, has_on_loop, deadline })
+    }
+
+    // 制限時間を設けて実行する（超えた場合は on_progress で中断される）
+    fn run_with_time_limit<T>(
+        &mut self,
+        limit: Duration,
+        run: impl FnOnce(&Engine, &mut Scope<'static>, &AST) -> ScriptResult<T>,
+    ) -> ScriptResult<T> {
+        *self.deadline.lock().unwrap() = Some(Instant::now() + limit);
+        let result = run(&self.engine, &mut self.scope, &self.ast);
+        *self.deadline.lock().unwrap() = None;
+        result
+    }
+
+    /// スクリプトを実行してシーケンスを生成
+    pub fn generate(&mut self) -> Result<Vec<InputFrame>> {
+        let result: Dynamic = self
+            .run_with_time_limit(GENERATE_TIME_LIMIT, |engine, scope, ast| engine.eval_ast_with_scope(scope, ast))
+            .map_err(|e| anyhow::anyhow!("スクリプトの実行に失敗: {}", e))?;
+        let mut frames = Vec::new();
+        collect_frames(&result, &mut frames)?;
+        if frames.is_empty() {
+            anyhow::bail!("スクリプトがステップを返しませんでした");
+        }
+        Ok(frames)
+    }
+
+    /// ループで先頭に戻ったときに on_loop を呼び、末尾に追加するステップを返す
+    pub fn on_loop(&mut self, count: u32) -> Result<Vec<InputFrame>> {
+        if !self.has_on_loop {
+            return Ok(Vec::new());
+        }
+        let result: Dynamic = self
+            .run_with_time_limit(ON_LOOP_TIME_LIMIT, |engine, scope, ast| {
+                // トップレベルは読み込み時に実行済みなので、関数だけを呼ぶ
+                let options = CallFnOptions::new().eval_ast(false);
+                engine.call_fn_with_options(options, scope, ast, ON_LOOP_FN, (count as i64,))
+            })
+            .map_err(|e| anyhow::anyhow!("{} の実行に失敗: {}", ON_LOOP_FN, e))?;
+        let mut frames = Vec::new();
+        if !result.is_unit() {
+            collect_frames(&result, &mut frames)?;
+        }
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // テストごとのディレクトリにスクリプトを書き込む（ディレクトリはドロップ時に削除される）
+    fn write_script(name: &str, source: &str) -> (crate::test_support::TempDir, std::path::PathBuf) {
+        let dir = crate::test_support::TempDir::new(name);
+        let path = dir.join("script.rhai");
+        std::fs::write(&path, source).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_generate_and_on_loop() {
+        let (_dir, path) = write_script(
+            "script_test",
+            r#"
+                fn on_loop(count) {
+                    if count == 3 { [step(10, 5)] } else { [] }
+                }
+                let frames = [step(FPS / 6, 5), press(2, 6, ["LP", "MP"])];
+                frames += motion("qcf", "HP");
+                frames.push(#{ duration: 4, direction: 2, buttons: #{ "LK": 1 } });
+                frames
+            "#,
+        );
+        let mut script = SequenceScript::load(&path, 60, NotationSettings::default()).unwrap();
+        let frames = script.generate().unwrap();
+        let summary: Vec<(u32, u8, usize)> = frames.iter().map(|f| (f.duration, f.direction, f.buttons.len())).collect();
+        assert_eq!(summary, vec![(10, 5, 0), (2, 6, 2), (2, 2, 0), (2, 3, 0), (4, 6, 1), (4, 2, 1)]);
+
+        assert!(script.on_loop(1).unwrap().is_empty());
+        assert_eq!(script.on_loop(3).unwrap().len(), 1);
+
+        let (_dir, path) = write_script("script_test_invalid", "[step(0, 5)]");
+        let mut script = SequenceScript::load(&path, 60, NotationSettings::default()).unwrap();
+        assert!(script.generate().is_err());
+    }
+
+    #[test]
+    fn test_on_loop_is_interrupted_after_time_limit() {
+        let (_dir, path) = write_script(
+            "script_test_endless",
+            r#"
+                fn on_loop(count) { loop { count += 1; } }
+                [step(1, 5)]
+            "#,
+        );
+        let mut script = SequenceScript::load(&path, 60, NotationSettings::default()).unwrap();
+        assert_eq!(script.generate().unwrap().len(), 1);
+
+        let started = Instant::now();
+        assert!(script.on_loop(1).is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_on_loop_does_not_rerun_top_level() {
+        // トップレベルの処理は on_loop の制限時間より長くかかる
+        let (_dir, path) = write_script(
+            "script_test_slow_top_level",
+            r#"
+                fn on_loop(count) { [step(count, 5)] }
+                let total = 0;
+                for i in 0..1000000 { total += i; }
+                [step(1, 5)]
+            "#,
+        );
+        let mut script = SequenceScript::load(&path, 60, NotationSettings::default()).unwrap();
+        let started = Instant::now();
+        assert_eq!(script.generate().unwrap().len(), 1);
+        assert!(started.elapsed() > ON_LOOP_TIME_LIMIT);
+
+        let frames = script.on_loop(2).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].duration, 2);
+    }
+}
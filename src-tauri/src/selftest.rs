@@ -0,0 +1,105 @@
+//! 仮想コントローラーのセルフテスト
+//!
+//! 長いシーケンスを流す前に、ゲーム側が仮想パッドを認識しているかを確かめるため、
+//! マッピングされたボタン・十字キーの8方向・アナログトリガー・スティックの端を
+//! 1つずつ短く押す入力列を作る。送信とタイミングは `run_controller_selftest` コマンドが行う。
+
+use crate::controller::CONTROLLER_BUTTONS;
+use crate::types::InputFrame;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// セルフテストの1項目
+#[derive(Debug, Clone)]
+pub struct SelfTestStep {
+    pub label: String,  // 表示名（"button1 (LP)"、"方向 6" など）
+    pub frame: InputFrame,
+}
+
+/// 進捗（"controller-selftest-progress" のペイロード）
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestProgressEvent {
+    pub index: usize,  // 実行中の項目（0始まり。index == total で完了）
+    pub total: usize,
+    pub label: String,
+}
+
+/// 中立入力（各項目の間とテスト終了時に送る）
+pub fn neutral_frame() -> InputFrame {
+    InputFrame::neutral(1)
+}
+
+/// テスト項目を作成（mapping は CSVボタン名 → コントローラーボタン名。空なら全ボタンを試す）
+pub fn selftest_steps(mapping: &HashMap<String, String>) -> Vec<SelfTestStep> {
+    let mut steps = Vec::new();
+
+    for &button in CONTROLLER_BUTTONS {
+        let mut names: Vec<&str> = mapping
+            .iter()
+            .filter(|(_, mapped)| mapped.as_str() == button)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if names.is_empty() && !mapping.is_empty() {
+            continue;
+        }
+        names.sort();
+        let mut frame = neutral_frame();
+        frame.buttons.insert(button.to_string(), 1);
+        let label = if names.is_empty() {
+            button.to_string()
+        } else {
+            format!("{} ({})", button, names.join(", "))
+        };
+        steps.push(SelfTestStep { label, frame });
+    }
+
+    for direction in [8, 9, 6, 3, 2, 1, 4, 7] {
+        let mut frame = neutral_frame();
+        frame.direction = direction;
+        steps.push(SelfTestStep { label: format!("方向 {}", direction), frame });
+    }
+
+    let mut frame = neutral_frame();
+    frame.left_trigger = u8::MAX;
+    steps.push(SelfTestStep { label: "左トリガー".to_string(), frame });
+    let mut frame = neutral_frame();
+    frame.right_trigger = u8::MAX;
+    steps.push(SelfTestStep { label: "右トリガー".to_string(), frame });
+
+    for (stick, is_left) in [("左スティック", true), ("右スティック", false)] {
+        for (name, x, y) in [("上", 0, i16::MAX), ("右", i16::MAX, 0), ("下", 0, i16::MIN), ("左", i16::MIN, 0)] {
+            let mut frame = neutral_frame();
+            if is_left {
+                frame.thumb_lx = x;
+                frame.thumb_ly = y;
+            } else {
+                frame.thumb_rx = x;
+                frame.thumb_ry = y;
+            }
+            steps.push(SelfTestStep { label: format!("{} {}", stick, name), frame });
+        }
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selftest_steps_cover_mapped_buttons() {
+        // マッピングが無ければ全ボタン + 8方向 + トリガー2 + スティック8
+        assert_eq!(selftest_steps(&HashMap::new()).len(), 12 + 8 + 2 + 8);
+
+        let mapping = HashMap::from([
+            ("LP".to_string(), "button3".to_string()),
+            ("HP".to_string(), "button6".to_string()),
+            ("MP".to_string(), "button3".to_string()),
+        ]);
+        let steps = selftest_steps(&mapping);
+        assert_eq!(steps.len(), 2 + 8 + 2 + 8);
+        assert_eq!(steps[0].label, "button3 (LP, MP)");
+        assert_eq!(steps[0].frame.buttons.get("button3"), Some(&1));
+        assert_eq!(steps[1].label, "button6 (HP)");
+    }
+}
@@ -0,0 +1,379 @@
+//! バイナリ形式のシーケンスファイル
+//!
+//! 数時間分の録画から生成したシーケンスはCSVだと読み込みが遅くメモリも多く使うため、
+//! 固定長に近いコンパクトなバイナリ形式で保存・ストリーム読み込みできるようにする。
+//!
+//! ファイル構成（数値はすべてリトルエンディアン）:
+//! - マジック `IPSQ`（4バイト）、バージョン（u16）
+//! - ボタン数（u16）と各ボタン名（u16 長さ + UTF-8）
+//! - ラベル数（u32）と各ラベル（ステップ u32、u16 長さ + UTF-8 の名前）
+//! - ステップ数（u32）
+//! - 各ステップ: フレーム数（u32）、方向（u8）、ボタンのビットマスク（ボタン数/8 切り上げ）、
+//!   アナログ値の有無（u8）。有りの場合は LX, LY, RX, RY（i16）と LT, RT（u8）が続く
+//!
+//! CSVの数値以外の列（InputFrame::extra_columns）は保存しない。
+
+use crate::atomic_write;
+use crate::csv_loader::{self, ANALOG_COLUMNS};
+use crate::types::{InputFrame, SequenceLabel};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+pub const MAGIC: [u8; 4] = *b"IPSQ";
+pub const VERSION: u16 = 1;
+
+/// バイナリ形式のファイルの拡張子（読み込み時の判定はマジックで行う）
+pub const EXTENSION: &str = "ipsq";
+
+/// ファイル先頭のマジックでバイナリ形式か判定
+pub fn is_binary_sequence(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map(|_| magic == MAGIC)
+        .unwrap_or(false)
+}
+
+fn write_str<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+    let len = u16::try_from(value.len()).context("文字列が長すぎます")?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_str<R: Read>(reader: &mut R) -> Result<String> {
+    let len = read_u16(reader)? as usize;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf).context("文字列がUTF-8ではありません")
+}
+
+/// シーケンスをバイナリ形式で保存
+///
+/// button_names の順にビットマスクを割り当てる。値が0以外のボタンは押下として保存する。
+/// 内容をメモリ上に組み立ててから一時ファイル経由で書き込む（途中で中断しても壊れたファイルを残さない）。
+pub fn write_binary(
+    path: &Path,
+    frames: &[InputFrame],
+    labels: &[SequenceLabel],
+    button_names: &[String],
+) -> Result<()> {
+    let mut writer: Vec<u8> = Vec::new();
+
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    let button_count = u16::try_from(button_names.len()).context("ボタン数が多すぎます")?;
+    writer.write_all(&button_count.to_le_bytes())?;
+    for name in button_names {
+        write_str(&mut writer, name)?;
+    }
+    writer.write_all(&(labels.len() as u32).to_le_bytes())?;
+    for label in labels {
+        writer.write_all(&(label.step as u32).to_le_bytes())?;
+        write_str(&mut writer, &label.name)?;
+    }
+    let frame_count = u32::try_from(frames.len()).context("ステップ数が多すぎます")?;
+    writer.write_all(&frame_count.to_le_bytes())?;
+
+    let mask_len = button_names.len().div_ceil(8);
+    let mut mask = vec![0u8; mask_len];
+    for frame in frames {
+        writer.write_all(&frame.duration.to_le_bytes())?;
+        writer.write_all(&[frame.direction])?;
+        mask.iter_mut().for_each(|b| *b = 0);
+        for (i, name) in button_names.iter().enumerate() {
+            if frame.buttons.get(name).copied().unwrap_or(0) != 0 {
+                mask[i / 8] |= 1 << (i % 8);
+            }
+        }
+        writer.write_all(&mask)?;
+
+        let analog = csv_loader::analog_values(frame);
+        if analog.iter().all(|&v| v == 0) {
+            writer.write_all(&[0])?;
+        } else {
+            writer.write_all(&[1])?;
+            for value in [frame.thumb_lx, frame.thumb_ly, frame.thumb_rx, frame.thumb_ry] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&[frame.left_trigger, frame.right_trigger])?;
+        }
+    }
+    atomic_write::write_atomic(path, &writer, 0).context(format!("ファイルを保存できませんでした: {:?}", path))?;
+    Ok(())
+}
+
+/// バイナリ形式のシーケンスをステップ単位で読み込む
+///
+/// ヘッダー（ボタン名・ラベル）は open 時に読み込み、ステップはイテレーターで1つずつ返す。
+pub struct BinarySequenceReader<R: Read> {
+    reader: R,
+    button_names: Vec<String>,
+    labels: Vec<SequenceLabel>,
+    frame_count: u32,
+    remaining: u32,
+    bytes_read: u64,
+}
+
+impl BinarySequenceReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).context(format!("ファイルを開けませんでした: {:?}", path))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> BinarySequenceReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).context("ヘッダーを読み込めませんでした")?;
+        if magic != MAGIC {
+            anyhow::bail!("バイナリ形式のシーケンスファイルではありません");
+        }
+        let version = read_u16(&mut reader)?;
+        if version != VERSION {
+            anyhow::bail!("未対応のバージョンです: {}", version);
+        }
+
+        let button_count = read_u16(&mut reader)?;
+        let button_names = (0..button_count)
+            .map(|_| read_str(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+        let label_count = read_u32(&mut reader)?;
+        let labels = (0..label_count)
+            .map(|_| -> Result<SequenceLabel> {
+                let step = read_u32(&mut reader)? as usize;
+                let name = read_str(&mut reader)?;
+                Ok(SequenceLabel { name, step })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let frame_count = read_u32(&mut reader)?;
+
+        // マジック・バージョン・ボタン数・ラベル数・ステップ数と、各文字列の長さ・ラベルのステップ
+        let header_bytes = 4 + 2 + 2 + 4 + 4
+            + button_names.iter().map(|name| 2 + name.len() as u64).sum::<u64>()
+            + labels.iter().map(|label| 4 + 2 + label.name.len() as u64).sum::<u64>();
+        Ok(Self {
+            reader,
+            button_names,
+            labels,
+            frame_count,
+            remaining: frame_count,
+            bytes_read: header_bytes,
+        })
+    }
+
+    pub fn button_names(&self) -> &[String] {
+        &self.button_names
+    }
+
+    pub fn labels(&self) -> &[SequenceLabel] {
+        &self.labels
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    /// ファイル先頭から読み込んだバイト数（読み込みの進捗表示用）
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    fn read_frame(&mut self) -> Result<InputFrame> {
+        let duration = read_u32(&mut self.reader)?;
+        let mut byte = [0u8; 1];
+        self.reader.read_exact(&mut byte)?;
+        let direction = byte[0];
+        if !(1..=9).contains(&direction) {
+            anyhow::bail!("方向の値が不正です: {}", direction);
+        }
+        let mut mask = vec![0u8; self.button_names.len().div_ceil(8)];
+        self.reader.read_exact(&mut mask)?;
+        let buttons: HashMap<String, u8> = self
+            .button_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), (mask[i / 8] >> (i % 8)) & 1))
+            .collect();
+
+        self.bytes_read += 4 + 1 + mask.len() as u64 + 1;
+        let mut frame = InputFrame { direction, buttons, ..InputFrame::neutral(duration) };
+        self.reader.read_exact(&mut byte)?;
+        if byte[0] != 0 {
+            let mut analog = [0u8; 10];
+            self.reader.read_exact(&mut analog)?;
+            frame.thumb_lx = i16::from_le_bytes([analog[0], analog[1]]);
+            frame.thumb_ly = i16::from_le_bytes([analog[2], analog[3]]);
+            frame.thumb_rx = i16::from_le_bytes([analog[4], analog[5]]);
+            frame.thumb_ry = i16::from_le_bytes([analog[6], analog[7]]);
+            frame.left_trigger = analog[8];
+            frame.right_trigger = analog[9];
+            self.bytes_read += analog.len() as u64;
+        }
+        Ok(frame)
+    }
+}
+
+impl<R: Read> Iterator for BinarySequenceReader<R> {
+    type Item = Result<InputFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let frame = self.read_frame();
+        if frame.is_err() {
+            // 壊れたファイルでは以降を読まない
+            self.remaining = 0;
+        }
+        Some(frame.context("ステップの読み込みに失敗しました"))
+    }
+}
+
+/// 読み込みの進捗を通知するステップ数の間隔
+const PROGRESS_INTERVAL_STEPS: usize = 4096;
+
+/// 読み込み前に確保するステップ数の上限（それ以上は読みながら伸ばす）
+const MAX_PREALLOCATED_STEPS: usize = 1 << 20;
+
+/// バイナリ形式のシーケンスを読み込む
+pub fn load_binary(path: &Path) -> Result<(Vec<InputFrame>, Vec<SequenceLabel>)> {
+    load_binary_with_progress(path, |_| {})
+}
+
+/// バイナリ形式のシーケンスを1ステップずつ読み込む（on_progress には読み込んだバイト数を渡す）
+///
+/// ファイル全体をメモリに読み込まず、プレイヤーに渡すステップだけを保持する。
+pub fn load_binary_with_progress<F>(path: &Path, mut on_progress: F) -> Result<(Vec<InputFrame>, Vec<SequenceLabel>)>
+where
+    F: FnMut(u64),
+{
+    let mut reader = BinarySequenceReader::open(path)?;
+    let labels = reader.labels().to_vec();
+    // ステップ数はヘッダーの値なので、壊れたファイルで巨大な領域を確保しないよう上限を設ける
+    let mut frames = Vec::with_capacity((reader.frame_count() as usize).min(MAX_PREALLOCATED_STEPS));
+    while let Some(frame) = reader.next() {
+        frames.push(frame?);
+        if frames.len() % PROGRESS_INTERVAL_STEPS == 0 {
+            on_progress(reader.bytes_read());
+        }
+    }
+    Ok((frames, labels))
+}
+
+/// CSVをバイナリ形式に変換（戻り値はステップ数）
+pub fn convert_csv_to_binary(csv_path: &Path, out_path: &Path) -> Result<usize> {
+    let button_names = csv_loader::get_csv_button_names(csv_path)?;
+    let (frames, labels) = csv_loader::load_csv_with_labels(csv_path)?;
+    write_binary(out_path, &frames, &labels, &button_names)?;
+    Ok(frames.len())
+}
+
+/// バイナリ形式をCSVに変換（戻り値はステップ数）
+///
+/// アナログ値を持つステップがある場合のみアナログ列を出力する。
+/// ステップは1つずつ読み書きする（アナログ列の有無を決めるため、ファイルは2回読む）。
+pub fn convert_binary_to_csv(binary_path: &Path, out_path: &Path) -> Result<usize> {
+    let mut write_analog = false;
+    for frame in BinarySequenceReader::open(binary_path)? {
+        if csv_loader::analog_values(&frame?).iter().any(|&v| v != 0) {
+            write_analog = true;
+            break;
+        }
+    }
+    let frames = BinarySequenceReader::open(binary_path)?;
+    let button_names = frames.button_names().to_vec();
+    let labels = frames.labels().to_vec();
+    let steps = frames.frame_count() as usize;
+
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_path(out_path)
+        .context(format!("CSVファイルを作成できませんでした: {:?}", out_path))?;
+    let mut header = vec!["duration".to_string(), "direction".to_string()];
+    header.extend(button_names.iter().cloned());
+    if write_analog {
+        header.extend(ANALOG_COLUMNS.iter().map(|c| c.to_string()));
+    }
+    writer.write_record(&header)?;
+
+    let mut labels = labels.iter().peekable();
+    for (step, frame) in frames.enumerate() {
+        let frame = frame?;
+        while let Some(label) = labels.next_if(|label| label.step == step) {
+            writer.write_record([format!("{}{}", csv_loader::LABEL_PREFIX, label.name)])?;
+        }
+        let mut record = vec![frame.duration.to_string(), frame.direction.to_string()];
+        record.extend(button_names.iter().map(|name| frame.buttons.get(name).unwrap_or(&0).to_string()));
+        if write_analog {
+            record.extend(csv_loader::analog_values(&frame).iter().map(|v| v.to_string()));
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_round_trip() {
+        let dir = crate::test_support::TempDir::new("binary_round_trip_test");
+        let csv_path = dir.join("source.csv");
+        let bin_path = dir.join("sequence.ipsq");
+        let out_path = dir.join("output.csv");
+        let csv = "duration,direction,A,B,C,D,E,F,G,H,I\n\
+                   3,5,0,0,0,0,0,0,0,0,0\n\
+                   label:combo\n\
+                   2,2,1,0,0,0,0,0,0,0,1\n";
+        std::fs::write(&csv_path, csv).unwrap();
+
+        assert_eq!(convert_csv_to_binary(&csv_path, &bin_path).unwrap(), 2);
+        assert!(is_binary_sequence(&bin_path));
+        assert!(!is_binary_sequence(&csv_path));
+        assert_eq!(csv_loader::get_csv_button_names(&bin_path).unwrap(), csv_loader::get_csv_button_names(&csv_path).unwrap());
+        let (frames, labels) = load_binary(&bin_path).unwrap();
+        assert_eq!(frames[1].direction, 2);
+        assert_eq!(frames[1].buttons.get("A"), Some(&1));
+        assert_eq!(frames[1].buttons.get("I"), Some(&1));
+        assert_eq!(frames[1].buttons.get("B"), Some(&0));
+        assert_eq!(labels, vec![SequenceLabel { name: "combo".to_string(), step: 1 }]);
+
+        convert_binary_to_csv(&bin_path, &out_path).unwrap();
+        let written = std::fs::read_to_string(&out_path).unwrap();
+        assert_eq!(written.replace("\r\n", "\n"), csv);
+    }
+
+    #[test]
+    fn test_rejects_invalid_direction() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes()); // ボタン数
+        bytes.extend(0u32.to_le_bytes()); // ラベル数
+        bytes.extend(1u32.to_le_bytes()); // ステップ数
+        bytes.extend(3u32.to_le_bytes());
+        bytes.extend([0u8, 0u8]); // 方向0、アナログ値なし
+
+        let mut reader = BinarySequenceReader::new(std::io::Cursor::new(bytes)).unwrap();
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
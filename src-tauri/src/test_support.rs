@@ -0,0 +1,31 @@
+//! 単体テスト用の共通処理
+
+use std::path::{Path, PathBuf};
+
+/// テストごとの一時ディレクトリ（ドロップ時に中身ごと削除するため、テストが失敗しても残らない）
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// name はテストごとに変える（並列に実行されるテスト同士でファイルが衝突しないように）
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("input_player_{}_{}", name, std::process::id()));
+        // 異常終了した前回の実行の残りがあれば消してから作る
+        std::fs::remove_dir_all(&path).ok();
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).ok();
+    }
+}
@@ -0,0 +1,243 @@
+//! 高精度タイマー
+//!
+//! OSのスリープは粒度が粗い（Windows標準で約15.6ms）ため、
+//! 期限の少し手前まではスリープし、残りはスピン待機するハイブリッド方式で
+//! サブミリ秒精度の待機を行う。
+
+use std::time::{Duration, Instant};
+
+/// スリープを打ち切ってスピン待機に切り替える残り時間
+/// timeBeginPeriod(1) 適用下でもスリープは1ms前後ずれるため余裕を持たせる
+const SPIN_THRESHOLD: Duration = Duration::from_micros(2000);
+
+/// 指定時刻まで待機する（スリープ + スピンのハイブリッド）
+pub fn sleep_until(deadline: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+        let remaining = deadline - now;
+        if remaining > SPIN_THRESHOLD {
+            std::thread::sleep(remaining - SPIN_THRESHOLD);
+        } else if remaining > Duration::from_micros(200) {
+            std::thread::yield_now();
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// 指定時刻まで待機する（スリープのみ。精度より CPU 使用率を優先する待機用）
+pub fn sleep_until_coarse(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        std::thread::sleep(deadline - now);
+    }
+}
+
+/// 1フレームあたりの時間（整数ミリ秒に切り捨てない）
+pub fn frame_period(fps: u32) -> Duration {
+    Duration::from_secs_f64(1.0 / fps.max(1) as f64)
+}
+
+/// タイマー分解能の引き上げ
+///
+/// Windowsでは生存中 timeBeginPeriod(1) を適用し、スリープ精度を約1msにする。
+/// 他のOSでは何もしない。
+pub struct TimerResolutionGuard {
+    #[cfg(windows)]
+    active: bool,
+}
+
+#[cfg(windows)]
+#[link(name = "winmm")]
+extern "system" {
+    fn timeBeginPeriod(u_period: u32) -> u32;
+    fn timeEndPeriod(u_period: u32) -> u32;
+}
+
+impl TimerResolutionGuard {
+    #[cfg(windows)]
+    pub fn new() -> Self {
+        // TIMERR_NOERROR = 0
+        let active = unsafe { timeBeginPeriod(1) } == 0;
+        if !active {
+            eprintln!("[Timer] timeBeginPeriod(1) に失敗しました。タイマー精度が低下します");
+        }
+        Self { active }
+    }
+
+    #[cfg(not(windows))]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Drop for TimerResolutionGuard {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        if self.active {
+            unsafe {
+                timeEndPeriod(1);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod ffi {
+    use std::ffi::c_void;
+
+    pub const THREAD_PRIORITY_NORMAL: i32 = 0;
+    pub const THREAD_PRIORITY_HIGHEST: i32 = 2;
+    pub const PROCESS_POWER_THROTTLING: i32 = 4; // PROCESS_INFORMATION_CLASS::ProcessPowerThrottling
+    pub const PROCESS_POWER_THROTTLING_CURRENT_VERSION: u32 = 1;
+    pub const PROCESS_POWER_THROTTLING_EXECUTION_SPEED: u32 = 0x1;
+    pub const PROCESS_POWER_THROTTLING_IGNORE_TIMER_RESOLUTION: u32 = 0x4;
+
+    #[repr(C)]
+    pub struct ProcessPowerThrottlingState {
+        pub version: u32,
+        pub control_mask: u32,
+        pub state_mask: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn GetCurrentThread() -> *mut c_void;
+        pub fn SetThreadPriority(thread: *mut c_void, priority: i32) -> i32;
+        pub fn GetCurrentProcess() -> *mut c_void;
+        pub fn SetProcessInformation(process: *mut c_void, class: i32, info: *const c_void, size: u32) -> i32;
+    }
+
+    #[link(name = "avrt")]
+    extern "system" {
+        pub fn AvSetMmThreadCharacteristicsW(task_name: *const u16, task_index: *mut u32) -> *mut c_void;
+        pub fn AvRevertMmThreadCharacteristics(handle: *mut c_void) -> i32;
+    }
+}
+
+/// 再生スレッドの優先度の引き上げ
+///
+/// 再生中だけ MMCSS の "Games" タスクに登録し（登録できなければ THREAD_PRIORITY_HIGHEST にする）、
+/// 他のプロセスの負荷が高いときでも送信時刻に起床できるようにする。停止中は元の優先度に戻す。
+/// 作成したスレッドでのみ使う（スレッドをまたいで使わない）。
+pub struct PlaybackPriority {
+    raised: bool,
+    #[cfg(windows)]
+    mmcss: Option<*mut std::ffi::c_void>,
+}
+
+impl PlaybackPriority {
+    pub fn new() -> Self {
+        Self {
+            raised: false,
+            #[cfg(windows)]
+            mmcss: None,
+        }
+    }
+
+    /// 再生中かどうかに合わせて優先度を切り替える（状態が変わったときだけ OS を呼ぶ）
+    pub fn set_raised(&mut self, raised: bool) {
+        if self.raised == raised {
+            return;
+        }
+        self.raised = raised;
+        #[cfg(windows)]
+        if raised {
+            self.raise();
+        } else {
+            self.restore();
+        }
+    }
+
+    #[cfg(windows)]
+    fn raise(&mut self) {
+        let task: Vec<u16> = "Games".encode_utf16().chain(std::iter::once(0)).collect();
+        let mut task_index = 0u32;
+        let handle = unsafe { ffi::AvSetMmThreadCharacteristicsW(task.as_ptr(), &mut task_index) };
+        if !handle.is_null() {
+            self.mmcss = Some(handle);
+            return;
+        }
+        let ok = unsafe { ffi::SetThreadPriority(ffi::GetCurrentThread(), ffi::THREAD_PRIORITY_HIGHEST) } != 0;
+        if !ok {
+            eprintln!("[Timer] スレッド優先度の変更に失敗しました");
+        }
+    }
+
+    #[cfg(windows)]
+    fn restore(&mut self) {
+        match self.mmcss.take() {
+            Some(handle) => unsafe {
+                ffi::AvRevertMmThreadCharacteristics(handle);
+            },
+            None => unsafe {
+                ffi::SetThreadPriority(ffi::GetCurrentThread(), ffi::THREAD_PRIORITY_NORMAL);
+            },
+        }
+    }
+}
+
+impl Default for PlaybackPriority {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for PlaybackPriority {
+    fn drop(&mut self) {
+        self.set_raised(false);
+    }
+}
+
+/// プロセスの省電力スロットリングを無効にする
+///
+/// Windows 11 はウィンドウが最小化・隠れているプロセスのタイマー要求
+/// （timeBeginPeriod）を無視し、タイマーをまとめて遅らせる（EcoQoS）。
+/// 最小化したまま再生しても送信タイミングが崩れないよう、明示的に除外を要求する。
+pub fn disable_power_throttling() {
+    // まとめて指定すると未対応のフラグを含む古い Windows では全体が失敗するため、1つずつ指定する。
+    // 未対応の場合は何もしない（スロットリングの除外は最善努力）
+    #[cfg(windows)]
+    for flag in [
+        ffi::PROCESS_POWER_THROTTLING_EXECUTION_SPEED,
+        ffi::PROCESS_POWER_THROTTLING_IGNORE_TIMER_RESOLUTION,
+    ] {
+        let state = ffi::ProcessPowerThrottlingState {
+            version: ffi::PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+            control_mask: flag,
+            state_mask: 0, // 「スロットリングしない」にする
+        };
+        unsafe {
+            ffi::SetProcessInformation(
+                ffi::GetCurrentProcess(),
+                ffi::PROCESS_POWER_THROTTLING,
+                &state as *const _ as *const std::ffi::c_void,
+                std::mem::size_of::<ffi::ProcessPowerThrottlingState>() as u32,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_period_not_truncated() {
+        let period = frame_period(60);
+        // 1000/60 = 16ms に切り捨てられず 16.666ms になること
+        assert!(period > Duration::from_micros(16_600));
+        assert!(period < Duration::from_micros(16_700));
+    }
+
+    #[test]
+    fn test_sleep_until_does_not_return_early() {
+        let _guard = TimerResolutionGuard::new();
+        let deadline = Instant::now() + Duration::from_millis(5);
+        sleep_until(deadline);
+        assert!(Instant::now() >= deadline);
+    }
+}
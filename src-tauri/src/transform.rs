@@ -0,0 +1,143 @@
+//! シーケンスの一括変換
+//!
+//! 全体のタイミングのずらし、フレーム数の倍率変換、左右反転、ボタン列のクリアを
+//! バックエンドでまとめて適用する。UI 上で1行ずつ編集しなくても一括で修正できる。
+
+use crate::csv_loader::scale_durations;
+use crate::player::mirror_direction;
+use crate::types::InputFrame;
+use anyhow::Result;
+use serde::Deserialize;
+
+/// 変換操作（JSON では {"op": "shift", "frames": 3} のように指定する）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SequenceTransform {
+    /// 全体を N フレーム遅らせる（負の値なら先頭の N フレームを削って早める）
+    Shift { frames: i64 },
+    /// 各ステップのフレーム数を倍率で変換（端数は次のステップへ持ち越す）
+    Scale { factor: f64 },
+    /// 左右反転（4↔6, 1↔3, 7↔9、スティックのX軸も反転）
+    Mirror,
+    /// 指定したボタンを全ステップで離した状態にする
+    ClearButton { button: String },
+}
+
+fn is_neutral(frame: &InputFrame) -> bool {
+    frame.direction == 5
+        && frame.buttons.values().all(|&v| v == 0)
+        && crate::csv_loader::analog_values(frame).iter().all(|&v| v == 0)
+}
+
+fn shift(frames: &mut Vec<InputFrame>, amount: i64) -> Result<()> {
+    if amount > 0 {
+        let amount = u32::try_from(amount).map_err(|_| anyhow::anyhow!("ずらすフレーム数が大きすぎます: {}", amount))?;
+        // 先頭が無入力ならそのステップを延ばす
+        match frames.first_mut() {
+            Some(first) if is_neutral(first) => first.duration = first.duration.saturating_add(amount),
+            _ => frames.insert(0, InputFrame::neutral(amount)),
+        }
+        return Ok(());
+    }
+
+    let mut remaining = amount.unsigned_abs();
+    let total: u64 = frames.iter().map(|f| f.duration as u64).sum();
+    if remaining >= total {
+        anyhow::bail!("シーケンス全体（{}F）以上を削ることはできません: {}F", total, remaining);
+    }
+    let mut removed = 0;
+    for frame in frames.iter_mut() {
+        if remaining == 0 {
+            break;
+        }
+        let cut = remaining.min(frame.duration as u64);
+        frame.duration -= cut as u32;
+        remaining -= cut;
+        if frame.duration == 0 {
+            removed += 1;
+        }
+    }
+    frames.drain(..removed);
+    Ok(())
+}
+
+fn mirror_axis(value: i16) -> i16 {
+    value.checked_neg().unwrap_or(i16::MAX)
+}
+
+/// 1つの変換を適用
+pub fn apply_transform(frames: &mut Vec<InputFrame>, transform: &SequenceTransform) -> Result<()> {
+    match transform {
+        SequenceTransform::Shift { frames: amount } => shift(frames, *amount)?,
+        SequenceTransform::Scale { factor } => {
+            if !factor.is_finite() || *factor <= 0.0 {
+                anyhow::bail!("倍率は正の値を指定してください: {}", factor);
+            }
+            let durations: Vec<u32> = frames.iter().map(|f| f.duration).collect();
+            for (frame, duration) in frames.iter_mut().zip(scale_durations(&durations, *factor)) {
+                frame.duration = duration;
+            }
+        }
+        SequenceTransform::Mirror => {
+            for frame in frames.iter_mut() {
+                frame.direction = mirror_direction(frame.direction);
+                frame.thumb_lx = mirror_axis(frame.thumb_lx);
+                frame.thumb_rx = mirror_axis(frame.thumb_rx);
+            }
+        }
+        SequenceTransform::ClearButton { button } => {
+            if !frames.iter().any(|f| f.buttons.contains_key(button)) {
+                anyhow::bail!("ボタン列が見つかりません: {}", button);
+            }
+            for frame in frames.iter_mut() {
+                if let Some(value) = frame.buttons.get_mut(button) {
+                    *value = 0;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 変換を順番に適用
+pub fn apply_transforms(frames: &mut Vec<InputFrame>, transforms: &[SequenceTransform]) -> Result<()> {
+    for transform in transforms {
+        apply_transform(frames, transform)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(duration: u32, direction: u8, button: u8) -> InputFrame {
+        let mut frame = InputFrame::neutral(duration);
+        frame.direction = direction;
+        frame.buttons.insert("A".to_string(), button);
+        frame
+    }
+
+    fn summary(frames: &[InputFrame]) -> Vec<(u32, u8, u8)> {
+        frames.iter().map(|f| (f.duration, f.direction, f.buttons["A"])).collect()
+    }
+
+    #[test]
+    fn test_apply_transforms() {
+        let mut frames = vec![step(4, 5, 0), step(3, 6, 1), step(2, 1, 0)];
+
+        apply_transform(&mut frames, &SequenceTransform::Shift { frames: 2 }).unwrap();
+        assert_eq!(summary(&frames), vec![(6, 5, 0), (3, 6, 1), (2, 1, 0)]);
+        apply_transform(&mut frames, &SequenceTransform::Shift { frames: -7 }).unwrap();
+        assert_eq!(summary(&frames), vec![(2, 6, 1), (2, 1, 0)]);
+        assert!(apply_transform(&mut frames, &SequenceTransform::Shift { frames: -4 }).is_err());
+
+        let transforms: Vec<SequenceTransform> = serde_json::from_str(
+            r#"[{"op": "scale", "factor": 1.5}, {"op": "mirror"}, {"op": "clear_button", "button": "A"}]"#,
+        )
+        .unwrap();
+        apply_transforms(&mut frames, &transforms).unwrap();
+        assert_eq!(summary(&frames), vec![(3, 4, 0), (3, 3, 0)]);
+        assert!(apply_transform(&mut frames, &SequenceTransform::ClearButton { button: "B".to_string() }).is_err());
+    }
+}
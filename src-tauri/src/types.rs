@@ -7,7 +7,7 @@ pub struct InputFrame {
     pub direction: u8,
     pub buttons: HashMap<String, u8>,
     
-    // アナログ軸 (オプション。CSVからの再生時は使用しない)
+    // アナログ軸 (オプション。CSVでは予約列 LX,LY,RX,RY,LT,RT に対応)
     #[serde(default)]
     pub thumb_lx: i16,  // 左スティック X (-32768 to 32767)
     #[serde(default)]
@@ -20,6 +20,41 @@ pub struct InputFrame {
     pub left_trigger: u8,  // 左トリガー (0-255)
     #[serde(default)]
     pub right_trigger: u8, // 右トリガー (0-255)
+
+    // CSVの数値以外の値を持つ列（他のツールが追加したメモなど）。保存時にそのまま書き戻す
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_columns: Vec<(String, String)>,
+}
+
+impl InputFrame {
+    /// 無入力のフレーム（方向は中立、全ボタンOFF、アナログ軸は0）
+    ///
+    /// 一部の項目だけを指定する場合は `InputFrame { direction, ..InputFrame::neutral(duration) }` のように使う。
+    pub fn neutral(duration: u32) -> Self {
+        Self {
+            duration,
+            direction: 5,
+            buttons: HashMap::new(),
+            thumb_lx: 0,
+            thumb_ly: 0,
+            thumb_rx: 0,
+            thumb_ry: 0,
+            left_trigger: 0,
+            right_trigger: 0,
+            extra_columns: Vec::new(),
+        }
+    }
+
+    /// 方向と押下中のボタン（値は1）を指定したフレーム（テスト・サンプルのシーケンス作成用）
+    ///
+    /// テストクレート（tests/）からも使えるよう `#[cfg(test)]` にはしない。
+    pub fn with_buttons(duration: u32, direction: u8, pressed: &[&str]) -> Self {
+        Self {
+            direction,
+            buttons: pressed.iter().map(|button| (button.to_string(), 1)).collect(),
+            ..Self::neutral(duration)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,16 +70,134 @@ pub struct ButtonMapping {
     pub mapping: Vec<UserButton>,
 }
 
+impl ButtonMapping {
+    /// コントローラーごとの標準的なマッピング
+    /// （フェイスボタン・ショルダー・トリガーはシーケンスで使用、Start/Back は使用しない）
+    /// トリガーの名前は CSV のアナログ列（LT / RT）と重ならないようにする。
+    pub fn default_for(controller_type: ControllerType) -> Self {
+        let names: [&str; 10] = match controller_type {
+            ControllerType::Xbox => ["A", "B", "X", "Y", "LB", "RB", "LTButton", "RTButton", "Back", "Start"],
+            ControllerType::DualShock4 => [
+                "Cross", "Circle", "Square", "Triangle", "L1", "R1", "L2", "R2", "Share", "Options",
+            ],
+        };
+        let mapping = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| UserButton {
+                user_button: name.to_string(),
+                controller_button: vec![format!("button{}", i + 1)],
+                use_in_sequence: i < 8,
+            })
+            .collect();
+        Self { controller_type, mapping }
+    }
+}
+
+// CSVの `label:` 行で付けたステップの名前（ジャンプ先のマーカー）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceLabel {
+    pub name: String,
+    pub step: usize,  // ラベルの直後のステップ（行番号、0始まり）
+}
+
 // シーケンスの状態
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SequenceState {
     NoSequence,  // シーケンス無し
     Stopped,     // 停止状態（シーケンスはロード済み）
     Playing,     // 再生中
+    Paused,      // ブレークポイントで一時停止中（resumeで続きから再生）
 }
 
 // シーケンスイベントは現在未使用のため削除（Player#get_event も削除）
 
+// 予約再生の発火イベント（"scheduled-playback-fired" のペイロード）
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledPlaybackFiredEvent {
+    pub scheduled_unix_ms: Option<u64>,  // 予約時刻（外部トリガー待ちの場合は None）
+    pub fired_unix_ms: u64,              // 実際に再生を開始した時刻
+}
+
+// アプリ全体の状態（後から開いたウィンドウが1回の呼び出しで同期するため）
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSnapshot {
+    pub controller_connected: bool,
+    pub controller_type: Option<ControllerType>,
+    pub sequence_path: Option<String>,  // 読み込み中のシーケンス
+    pub sequence_state: SequenceState,
+    pub current_step: usize,
+    pub total_steps: usize,
+    pub fps: u32,
+    pub loop_playback: bool,
+    pub invert_horizontal: bool,
+    pub reversed: bool,                 // 逆再生中
+    pub is_training: bool,
+}
+
+// 大きなシーケンスの読み込み進捗（"sequence-load-progress" のペイロード）
+#[derive(Debug, Clone, Serialize)]
+pub struct SequenceLoadProgressEvent {
+    pub path: String,
+    pub bytes_read: u64,
+    pub total_bytes: u64,  // bytes_read == total_bytes で読み込み完了
+}
+
+// 送信の遅れの警告（"playback-step-late" のペイロード）
+#[derive(Debug, Clone, Serialize)]
+pub struct LateStepEvent {
+    pub step: usize,          // 遅れて送信したステップ（行番号、0始まり）
+    pub late_ms: f64,         // 予定時刻からの遅れ
+    pub threshold_ms: u32,    // 警告のしきい値
+}
+
+// ブレークポイント到達イベント（"playback-breakpoint-hit" のペイロード）
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakpointHitEvent {
+    pub step: usize,            // 一時停止したステップ（行番号、0始まり。このステップはまだ送信していない）
+    pub cumulative_frame: u32,  // このステップ開始時点の累積フレーム数
+}
+
+// 再生ステップ遷移イベント（"playback-step-changed" のペイロード）
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackStepEvent {
+    pub step: usize,            // 現在出力中のステップ（行番号、0始まり）
+    pub cumulative_frame: u32,  // このステップ開始時点の累積フレーム数
+    pub total_steps: usize,     // 総ステップ数
+    pub total_frames: u32,      // シーケンス全体のフレーム数
+}
+
+// 再生タイミングの計測結果（直近の再生開始からの集計）
+// ジッタ = 実際の送信時刻 - 予定時刻（next_step_time）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlaybackTimingReport {
+    pub samples: u32,         // 計測した送信回数
+    pub min_jitter_ms: f64,   // 最小ジッタ
+    pub max_jitter_ms: f64,   // 最大ジッタ
+    pub mean_jitter_ms: f64,  // 平均ジッタ
+    pub late_steps: u32,      // 1フレーム以上遅れて送信したステップ数
+    pub missed_frames: u32,   // 遅延により取りこぼしたフレーム数の合計
+    pub fps: u32,             // 計測時のFPS設定
+}
+
+// タイムライン上の1ステップ（再生を行わずに計算した予定時刻）
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineStep {
+    pub step: usize,            // 行番号（0始まり）
+    pub start_frame: u32,       // 再生開始からの累積フレーム数（このステップの開始時点）
+    pub start_ms: f64,          // 再生開始からの時刻（ミリ秒）
+    pub duration_frames: u32,   // このステップのフレーム数
+}
+
+// シーケンスのタイムライン（ドライラン結果）
+#[derive(Debug, Clone, Serialize)]
+pub struct SequenceTimeline {
+    pub fps: u32,
+    pub total_frames: u32,      // 同期マーカー・挿入リリースを含む総フレーム数
+    pub total_duration_ms: f64, // 再生にかかる時間（ミリ秒）
+    pub steps: Vec<TimelineStep>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ControllerType {
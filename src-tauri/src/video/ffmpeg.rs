@@ -0,0 +1,337 @@
+//! ffmpeg / ffprobe コマンドによるフレーム抽出
+//!
+//! GStreamer のインストールが難しい環境向けの代替バックエンド。
+//! ffmpeg / ffprobe は実行ファイルと同じフォルダに同梱したものを優先し、無ければ PATH から探す。
+//! デコードしたフレームは rawvideo（RGB24）としてパイプで受け取る。
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::frame_extractor::{save_frame_image, CustomVideoInfo, FrameExtractorConfig, TimeRange};
+use crate::analyzer::InputIndicatorRegion;
+use crate::model::VideoTransform;
+
+/// 同梱の ffmpeg / ffprobe があればそのパス、無ければコマンド名（PATH から探す）
+fn tool_path(name: &str) -> PathBuf {
+    let file_name = if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    };
+    let bundled = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(&file_name)));
+    match bundled {
+        Some(path) if path.exists() => path,
+        _ => PathBuf::from(file_name),
+    }
+}
+
+fn command(name: &str) -> Command {
+    let mut command = Command::new(tool_path(name));
+    // コンソールウィンドウを表示しない
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+/// ffmpeg / ffprobe コマンドを実行できるか確認する
+pub(super) fn check_available() -> Result<()> {
+    for name in ["ffmpeg", "ffprobe"] {
+        let status = command(name)
+            .arg("-version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .with_context(|| format!("{}が見つかりません", name))?;
+        if !status.success() {
+            anyhow::bail!("{}の実行に失敗しました", name);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    width: i32,
+    height: i32,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+}
+
+/// "60000/1001" 形式のフレームレートを数値に変換（0/0 などは None）
+fn parse_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.split_once('/')?;
+    let num: f64 = num.parse().ok()?;
+    let den: f64 = den.parse().ok()?;
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+// 回転・反転の補正に対応する ffmpeg のフィルタ（補正なしは None）
+fn transform_filter(transform: VideoTransform) -> Option<&'static str> {
+    match transform {
+        VideoTransform::None => None,
+        VideoTransform::Rotate90 => Some("transpose=1"),
+        VideoTransform::Rotate180 => Some("hflip,vflip"),
+        VideoTransform::Rotate270 => Some("transpose=2"),
+        VideoTransform::FlipHorizontal => Some("hflip"),
+    }
+}
+
+/// 動画ファイルの情報を取得（解像度は回転・反転の補正前の値）
+pub(super) fn get_video_info(video_path: &Path) -> Result<CustomVideoInfo> {
+    if !video_path.exists() {
+        anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
+    }
+
+    let output = command("ffprobe")
+        .args([
+            "-v", "error",
+            "-select_streams", "v:0",
+            "-show_entries", "stream=width,height,avg_frame_rate,r_frame_rate:format=duration",
+            "-of", "json",
+        ])
+        .arg(video_path)
+        .output()
+        .context("ffprobeの実行に失敗しました（ffprobeが見つかりません）")?;
+    if !output.status.success() {
+        anyhow::bail!("動画の解析に失敗しました: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let probe: ProbeOutput = serde_json::from_slice(&output.stdout)
+        .context("ffprobeの出力の解析に失敗しました")?;
+    let stream = probe
+        .streams
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("動画ストリームが見つかりません"))?;
+    let fps = stream
+        .avg_frame_rate
+        .as_deref()
+        .and_then(parse_rate)
+        .or_else(|| stream.r_frame_rate.as_deref().and_then(parse_rate))
+        .ok_or_else(|| anyhow::anyhow!("フレームレートを取得できません"))?;
+    let duration_sec = probe
+        .format
+        .and_then(|format| format.duration)
+        .and_then(|duration| duration.parse().ok())
+        .unwrap_or(0.0);
+
+    Ok(CustomVideoInfo {
+        width: stream.width,
+        height: stream.height,
+        fps,
+        duration_sec,
+    })
+}
+
+/// 動画をデコードし、frame_interval ごとのフレームをコールバックで処理する
+///
+/// crop_region が指定された場合は ffmpeg の crop フィルタで切り出した画像を渡す。
+/// range を指定した場合はその区間だけをデコードする（フレーム番号は動画の先頭から数える）。
+/// 戻り値は処理した区間の終わりのフレーム番号。
+pub(super) fn process_frames<F>(
+    video_path: &Path,
+    transform: VideoTransform,
+    crop_region: Option<&InputIndicatorRegion>,
+    range: TimeRange,
+    frame_interval: u32,
+    mut callback: F,
+) -> Result<u32>
+where
+    F: FnMut(&image::RgbImage, u32) -> Result<()>,
+{
+    let info = get_video_info(video_path)?;
+    let (width, height) = match crop_region {
+        Some(region) => (region.width, region.height),
+        None => super::transformed_size(transform, info.width as u32, info.height as u32),
+    };
+
+    let mut command = command("ffmpeg");
+    command.args(["-v", "error"]);
+    // 入力側の -ss でキーフレームへシークしてから開始位置までデコードする
+    if let Some(start) = range.start_sec {
+        command.args(["-ss".to_string(), format!("{:.6}", start)]);
+    }
+    command.arg("-i").arg(video_path);
+    if let Some(end) = range.end_sec {
+        let duration = end - range.start_sec.unwrap_or(0.0);
+        command.args(["-t".to_string(), format!("{:.6}", duration)]);
+    }
+    // 回転・反転を補正してから切り出す（解析範囲の座標は補正後の画像上の座標）
+    let filters: Vec<String> = transform_filter(transform)
+        .map(str::to_string)
+        .into_iter()
+        .chain(crop_region.map(|region| format!("crop={}:{}:{}:{}", region.width, region.height, region.x, region.y)))
+        .collect();
+    if !filters.is_empty() {
+        command.args(["-vf".to_string(), filters.join(",")]);
+    }
+    // 可変フレームレートの動画も、解析用の固定フレームレートに合わせて複製・間引きして出力する
+    command.args(["-vsync".to_string(), "cfr".to_string(), "-r".to_string(), format!("{}", info.fps)]);
+    command.args(["-f", "rawvideo", "-pix_fmt", "rgb24", "pipe:1"]);
+
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("ffmpegの実行に失敗しました（ffmpegが見つかりません）")?;
+
+    // 標準エラーが詰まらないよう別スレッドで読み切る
+    let mut stderr = child.stderr.take().expect("stderr");
+    let stderr_reader = std::thread::spawn(move || {
+        let mut message = String::new();
+        stderr.read_to_string(&mut message).ok();
+        message
+    });
+
+    let mut stdout = child.stdout.take().expect("stdout");
+    let frame_size = (width * height * 3) as usize;
+    let mut buffer = vec![0u8; frame_size];
+    let mut frame_count = range.start_frame(info.fps);
+
+    loop {
+        match stdout.read_exact(&mut buffer) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(e).context("ffmpegの出力の読み込みに失敗しました");
+            }
+        }
+
+        let current_frame = frame_count;
+        frame_count += 1;
+        if current_frame % frame_interval.max(1) != 0 {
+            continue;
+        }
+
+        let img = image::RgbImage::from_raw(width, height, buffer.clone())
+            .context("RgbImageの作成に失敗しました")?;
+        // エラー（キャンセルを含む）の場合は ffmpeg を止めてから抜ける
+        if let Err(e) = callback(&img, current_frame) {
+            child.kill().ok();
+            child.wait().ok();
+            return Err(e);
+        }
+    }
+
+    let status = child.wait().context("ffmpegの終了待ちに失敗しました")?;
+    let message = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        anyhow::bail!("ffmpegがエラーで終了しました: {}", message.trim());
+    }
+
+    Ok(frame_count)
+}
+
+/// 動画から frame_interval ごとのフレームを保存し、保存したパスをコールバックに渡す
+pub(super) fn save_frames<F>(
+    config: &FrameExtractorConfig,
+    video_path: &Path,
+    crop_region: Option<&InputIndicatorRegion>,
+    mut on_saved: F,
+) -> Result<()>
+where
+    F: FnMut(PathBuf) -> Result<()>,
+{
+    std::fs::create_dir_all(&config.output_dir)
+        .context("出力ディレクトリの作成に失敗しました")?;
+
+    process_frames(video_path, config.transform, crop_region, TimeRange::default(), config.frame_interval, |img, frame_number| {
+        let output_path = config
+            .output_dir
+            .join(format!("frame_{:06}.{}", frame_number, config.image_format));
+        save_frame_image(config, img, &output_path)?;
+        on_saved(output_path)
+    })?;
+    Ok(())
+}
+
+/// 動画からフレームを抽出して保存する（進捗コールバックには保存済みの枚数を渡す）
+pub(super) fn extract_frames<F>(
+    config: &FrameExtractorConfig,
+    video_path: &Path,
+    progress_callback: Option<F>,
+    crop_region: Option<&InputIndicatorRegion>,
+) -> Result<Vec<PathBuf>>
+where
+    F: Fn(usize),
+{
+    let mut paths = Vec::new();
+    save_frames(config, video_path, crop_region, |path| {
+        paths.push(path);
+        if let Some(ref callback) = progress_callback {
+            callback(paths.len());
+        }
+        Ok(())
+    })?;
+
+    println!("\n抽出完了!");
+    println!("  抽出フレーム数: {}", paths.len());
+
+    Ok(paths)
+}
+
+/// 指定フレームの画像を取得する
+///
+/// 入力側の -ss 指定により、直前のキーフレームへシークしてから目的の時刻までデコードする
+pub(super) fn extract_frame(video_path: &Path, transform: VideoTransform, frame_number: u32) -> Result<image::RgbImage> {
+    let info = get_video_info(video_path)?;
+    let time_sec = frame_number as f64 / info.fps;
+
+    let mut command = command("ffmpeg");
+    command
+        .args(["-v", "error", "-ss", &format!("{:.6}", time_sec), "-i"])
+        .arg(video_path);
+    if let Some(filter) = transform_filter(transform) {
+        command.args(["-vf", filter]);
+    }
+    let output = command
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgb24", "pipe:1"])
+        .output()
+        .context("ffmpegの実行に失敗しました（ffmpegが見つかりません）")?;
+    if !output.status.success() {
+        anyhow::bail!("フレーム抽出に失敗しました: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let (width, height) = super::transformed_size(transform, info.width as u32, info.height as u32);
+    let frame_size = (width * height * 3) as usize;
+    if output.stdout.len() < frame_size {
+        anyhow::bail!("指定されたフレームが見つかりませんでした");
+    }
+    image::RgbImage::from_raw(width, height, output.stdout[..frame_size].to_vec())
+        .ok_or_else(|| anyhow::anyhow!("フレーム画像の作成に失敗しました"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("60/1"), Some(60.0));
+        assert!((parse_rate("60000/1001").unwrap() - 59.94).abs() < 0.01);
+        assert_eq!(parse_rate("0/0"), None);
+        assert_eq!(parse_rate("abc"), None);
+    }
+}
@@ -1,42 +1,57 @@
 use anyhow::{Context, Result};
-use gstreamer::prelude::*;
-use gstreamer::{self as gst, ElementFactory};
-use gstreamer_app::AppSink;
 use image::{ImageBuffer, Rgb};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
 
-// 指定された VideoInfo と元データ（stride を含む可能性あり）から
-// 連続した RGB バイト列を作成して返す。
-fn plane_to_contiguous_rgb(video_info: &gstreamer_video::VideoInfo, src: &[u8]) -> Vec<u8> {
-    let width = video_info.width() as usize;
-    let height = video_info.height() as usize;
-    // stride() は行バイト幅のスライスを返す（通常は1要素）
-    let stride = video_info.stride().get(0).cloned().unwrap_or((width * 3) as i32) as usize;
+use super::frame_source::{FrameSource, DEFAULT_IMAGE_SEQUENCE_FPS};
+use super::Decoder;
+use crate::model::VideoTransform;
+
+/// 行バイト幅 stride の RGB バッファから矩形を切り出す（範囲外は None）
+///
+/// タイルは行ごとのスライスをそのままコピーするため、画素ごとに put_pixel するより大幅に速い。
+pub fn crop_rgb_rows(src: &[u8], stride: usize, x: u32, y: u32, width: u32, height: u32) -> Option<image::RgbImage> {
+    let row_bytes = width as usize * 3;
+    let start_x = x as usize * 3;
+    if start_x + row_bytes > stride {
+        return None;
+    }
 
-    // stride が期待どおりならそのままコピー
-    if stride == width * 3 {
-        return src.to_vec();
+    let mut out = Vec::with_capacity(row_bytes * height as usize);
+    for row in y as usize..(y + height) as usize {
+        let start = row * stride + start_x;
+        out.extend_from_slice(src.get(start..start + row_bytes)?);
     }
+    image::RgbImage::from_raw(width, height, out)
+}
 
-    let mut out = Vec::with_capacity(width * 3 * height);
-    for row in 0..height {
-        let start = row * stride;
-        let end = start + width * 3;
-        if end <= src.len() {
-            out.extend_from_slice(&src[start..end]);
-        } else if start < src.len() {
-            // 不足している場合は残りをコピーしてゼロ埋め
-            out.extend_from_slice(&src[start..src.len()]);
-            out.extend(std::iter::repeat(0).take(end - src.len()));
-        } else {
-            out.extend(std::iter::repeat(0).take(width * 3));
+// 設定された形式（png / jpg）でフレーム画像を保存する
+pub(super) fn save_frame_image(
+    config: &FrameExtractorConfig,
+    img: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    path: &Path,
+) -> Result<()> {
+    // パイプラインで縮小していない場合（ffmpeg・単一フレームの抽出）はここで縮小する
+    let resized;
+    let img = match config.scaled_size(img.width(), img.height()) {
+        Some((width, height)) if (width, height) != img.dimensions() => {
+            resized = image::imageops::resize(img, width, height, image::imageops::FilterType::Triangle);
+            &resized
         }
+        _ => img,
+    };
+
+    if config.image_format == "jpg" || config.image_format == "jpeg" {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("ファイルの作成に失敗しました: {}", path.display()))?;
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(file, config.jpeg_quality);
+        img.write_with_encoder(encoder)?;
+    } else {
+        img.save(path)?;
     }
-
-    out
+    Ok(())
 }
 
+
 /// フレーム抽出の設定
 #[derive(Debug, Clone)]
 pub struct FrameExtractorConfig {
@@ -48,6 +63,14 @@ pub struct FrameExtractorConfig {
     pub image_format: String,
     /// JPEGの品質（0-100、jpgの場合のみ有効）
     pub jpeg_quality: u8,
+    /// 保存する画像の幅（None なら元のサイズ。高さだけ指定した場合は縦横比を保つ）
+    pub scale_width: Option<u32>,
+    /// 保存する画像の高さ（None なら元のサイズ。幅だけ指定した場合は縦横比を保つ）
+    pub scale_height: Option<u32>,
+    /// 回転・反転の補正（切り出し範囲の座標は補正後の画像上の座標）
+    pub transform: VideoTransform,
+    /// 連番画像のフォルダを読むときのフレームレート
+    pub image_sequence_fps: f64,
 }
 
 impl Default for FrameExtractorConfig {
@@ -57,6 +80,25 @@ impl Default for FrameExtractorConfig {
             output_dir: PathBuf::from("output/frames"),
             image_format: "png".to_string(),
             jpeg_quality: 95,
+            scale_width: None,
+            scale_height: None,
+            transform: VideoTransform::None,
+            image_sequence_fps: DEFAULT_IMAGE_SEQUENCE_FPS,
+        }
+    }
+}
+
+impl FrameExtractorConfig {
+    /// 元の画像サイズに scale_width / scale_height を適用した保存サイズ（指定なしは None）
+    pub fn scaled_size(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let keep_aspect = |value: u32, numerator: u32, denominator: u32| {
+            ((value as u64 * numerator as u64 / denominator.max(1) as u64) as u32).max(1)
+        };
+        match (self.scale_width, self.scale_height) {
+            (None, None) => None,
+            (Some(w), Some(h)) => Some((w, h)),
+            (Some(w), None) => Some((w, keep_aspect(height, w, width))),
+            (None, Some(h)) => Some((keep_aspect(width, h, height), h)),
         }
     }
 }
@@ -70,7 +112,46 @@ pub struct CustomVideoInfo {
     pub duration_sec: f64,
 }
 
+/// 処理する動画の区間（秒）。None の場合は先頭・末尾まで
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimeRange {
+    pub start_sec: Option<f64>,
+    pub end_sec: Option<f64>,
+}
+
+impl TimeRange {
+    /// 区間を作成する（開始が終了以降の場合はエラー）
+    pub fn new(start_sec: Option<f64>, end_sec: Option<f64>) -> Result<Self> {
+        if let (Some(start), Some(end)) = (start_sec, end_sec) {
+            if start >= end {
+                anyhow::bail!("開始時間({}秒)は終了時間({}秒)より前にしてください", start, end);
+            }
+        }
+        if start_sec.is_some_and(|start| start < 0.0) || end_sec.is_some_and(|end| end < 0.0) {
+            anyhow::bail!("区間の時間に負の値は指定できません");
+        }
+        Ok(Self { start_sec, end_sec })
+    }
+
+    /// 動画全体か
+    pub fn is_full(&self) -> bool {
+        self.start_sec.is_none() && self.end_sec.is_none()
+    }
+
+    /// 区間の最初のフレーム番号
+    pub fn start_frame(&self, fps: f64) -> u32 {
+        (self.start_sec.unwrap_or(0.0) * fps).round() as u32
+    }
+
+    /// 区間の終わりのフレーム番号（このフレームは含まない。末尾までの場合は None）
+    pub fn end_frame(&self, fps: f64) -> Option<u32> {
+        self.end_sec.map(|end| (end * fps).round() as u32)
+    }
+}
+
 /// フレーム抽出器
+///
+/// デコードは設定で選んだバックエンド（GStreamer / ffmpeg）で行う。連番画像のフォルダはバックエンドを使わずに読み込む。
 pub struct FrameExtractor {
     config: FrameExtractorConfig,
 }
@@ -88,71 +169,40 @@ impl FrameExtractor {
         }
     }
 
-    /// GStreamerを初期化
-    fn init_gstreamer() -> Result<()> {
-        gst::init().context("GStreamerの初期化に失敗しました")?;
-        Ok(())
+    /// 動画ファイルの情報を取得（解像度は transform の補正後の値）
+    ///
+    /// 連番画像のフォルダは DEFAULT_IMAGE_SEQUENCE_FPS で撮影されたものとして扱う。
+    /// フレームレートを指定する場合は設定を持った FrameExtractor の video_info を使う。
+    pub fn get_video_info<P: AsRef<Path>>(video_path: P, transform: VideoTransform) -> Result<CustomVideoInfo> {
+        Self::new(FrameExtractorConfig { transform, ..FrameExtractorConfig::default() }).video_info(video_path)
     }
 
-    /// 動画ファイルの情報を取得
-    pub fn get_video_info<P: AsRef<Path>>(video_path: P) -> Result<CustomVideoInfo> {
-        Self::init_gstreamer()?;
+    /// 動画ファイルの情報を取得（解像度は config.transform の補正後の値、連番画像は config.image_sequence_fps）
+    pub fn video_info<P: AsRef<Path>>(&self, video_path: P) -> Result<CustomVideoInfo> {
+        let mut info = self.probe_video_info(video_path.as_ref())?;
+        let (width, height) = super::transformed_size(self.config.transform, info.width as u32, info.height as u32);
+        info.width = width as i32;
+        info.height = height as i32;
+        Ok(info)
+    }
 
-        let video_path = video_path.as_ref();
-        
-        // ファイルの存在チェック
-        if !video_path.exists() {
-            anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
-        }
-        
-        // ファイルが読み取り可能かチェック
-        if let Err(e) = std::fs::metadata(video_path) {
-            anyhow::bail!("動画ファイルにアクセスできません: {:?} ({})", video_path, e);
+    // 補正前の動画情報
+    fn probe_video_info(&self, video_path: &Path) -> Result<CustomVideoInfo> {
+        if let FrameSource::ImageSequence(sequence) = FrameSource::open(video_path, self.config.image_sequence_fps)? {
+            return sequence.info();
         }
-        
-        let canonical = video_path
-            .canonicalize()
-            .context("動画ファイルのパスを解決できませんでした")?;
-        let uri = url::Url::from_file_path(&canonical)
-            .map_err(|_| anyhow::anyhow!("ファイルパスからURIへの変換に失敗しました"))?
-            .to_string();
-
-        // Discovererを使って動画情報を取得
-        let discoverer = gstreamer_pbutils::Discoverer::new(gst::ClockTime::from_seconds(10))
-            .context("Discovererの作成に失敗しました")?;
-
-        let info = discoverer
-            .discover_uri(&uri)
-            .context("動画の解析に失敗しました")?;
 
-        let video_streams = info.video_streams();
-        if video_streams.is_empty() {
-            anyhow::bail!("動画ストリームが見つかりません");
+        match super::decoder()? {
+            #[cfg(feature = "gstreamer")]
+            Decoder::Gstreamer => super::gstreamer_backend::get_video_info(video_path),
+            #[cfg(feature = "ffmpeg")]
+            Decoder::Ffmpeg => super::ffmpeg::get_video_info(video_path),
         }
-
-        let video_stream = &video_streams[0];
-        let width = video_stream.width() as i32;
-        let height = video_stream.height() as i32;
-        let fps_num = video_stream.framerate().numer() as f64;
-        let fps_den = video_stream.framerate().denom() as f64;
-        let fps = fps_num / fps_den;
-
-        let duration = info.duration();
-        let duration_sec = if let Some(dur) = duration {
-            dur.seconds() as f64
-        } else {
-            0.0
-        };
-
-        Ok(CustomVideoInfo {
-            width,
-            height,
-            fps,
-            duration_sec,
-        })
     }
 
     /// 動画からフレームを抽出（進捗コールバック付き）
+    ///
+    /// GStreamer バックエンドでは、長い動画を時間区間に分割して並列にデコードする。
     pub fn extract_frames_with_progress<P, F>(
         &self,
         video_path: P,
@@ -163,302 +213,12 @@ impl FrameExtractor {
         P: AsRef<Path>,
         F: Fn(usize) + Send + Sync + 'static,
     {
-        Self::init_gstreamer()?;
-
-        let video_path = video_path.as_ref();
-        
-        // ファイルの存在チェック
-        if !video_path.exists() {
-            anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
-        }
-        
-        // ファイルが読み取り可能かチェック
-        if let Err(e) = std::fs::metadata(video_path) {
-            anyhow::bail!("動画ファイルにアクセスできません: {:?} ({})", video_path, e);
-        }
-        
-        println!("動画ファイルを開いています: {}", video_path.display());
-
-        // 出力ディレクトリを作成
-        std::fs::create_dir_all(&self.config.output_dir)
-            .context("出力ディレクトリの作成に失敗しました")?;
-
-        // 動画情報を取得
-        let info = Self::get_video_info(video_path)?;
-        println!("動画情報:");
-        println!("  解像度: {}x{}", info.width, info.height);
-        println!("  FPS: {:.2}", info.fps);
-        println!("  再生時間: {:.2}秒", info.duration_sec);
-
-        let _canonical = video_path.canonicalize()?;
-        let _uri = url::Url::from_file_path(&_canonical)
-            .map_err(|_| anyhow::anyhow!("ファイルパスからURIへの変換に失敗しました"))?
-            .to_string();
-
-        // GStreamerパイプラインを構築
-        let pipeline = gst::Pipeline::new();
-
-        // エレメントを作成
-        let source = ElementFactory::make("filesrc")
-            .name("source")
-            .build()
-            .context("filesrcの作成に失敗しました")?;
-
-        let decodebin = ElementFactory::make("decodebin")
-            .name("decoder")
-            .build()
-            .context("decodebinの作成に失敗しました")?;
-
-        let videoconvert = ElementFactory::make("videoconvert")
-            .name("converter")
-            .build()
-            .context("videoconvertの作成に失敗しました")?;
-
-        let appsink = ElementFactory::make("appsink")
-            .name("sink")
-            .build()
-            .context("appsinkの作成に失敗しました")?;
-
-        let appsink = appsink
-            .dynamic_cast::<AppSink>()
-            .map_err(|_| anyhow::anyhow!("appsinkへのキャストに失敗しました"))?;
-
-        // AppSinkの設定
-        appsink.set_caps(Some(
-            &gst::Caps::builder("video/x-raw")
-                .field("format", "RGB")
-                .build(),
-        ));
-        appsink.set_property("emit-signals", false);
-        appsink.set_property("sync", false);
-
-        // ファイルパスを設定（正規化した絶対パスを使用）
-        let source_path = video_path.canonicalize()?;
-        source.set_property("location", source_path.to_str().unwrap());
-
-        // パイプラインにエレメントを追加
-        // source と decodebin の追加は共通
-        // videocrop を使う場合は videocrop をパイプラインに挿入して
-        // videoconvert -> videocrop -> appsink の形にする
-        if let Some(region) = &crop_region {
-            let videocrop = ElementFactory::make("videocrop")
-                .name("crop")
-                .build()
-                .context("videocrop の作成に失敗しました")?;
-
-            // crop の値を計算
-            let video_w = info.width as i32;
-            let video_h = info.height as i32;
-            let left = region.x as i32;
-            let top = region.y as i32;
-            let right = (video_w - (region.x as i32 + region.width as i32)).max(0);
-            let bottom = (video_h - (region.y as i32 + region.height as i32)).max(0);
-
-            videocrop.set_property("left", left);
-            videocrop.set_property("top", top);
-            videocrop.set_property("right", right);
-            videocrop.set_property("bottom", bottom);
-
-            pipeline.add_many(&[
-                &source,
-                &decodebin,
-                &videoconvert,
-                videocrop.upcast_ref::<gst::Element>(),
-                appsink.upcast_ref::<gst::Element>(),
-            ])
-            .context("エレメントの追加に失敗しました")?;
-
-            // source と decodebin をリンク
-            source
-                .link(&decodebin)
-                .context("sourceとdecoderのリンクに失敗しました")?;
-
-            // videoconvert -> videocrop -> appsink をリンク
-            videoconvert
-                .link(videocrop.upcast_ref::<gst::Element>())
-                .context("converterとvideocropのリンクに失敗しました")?;
-            videocrop
-                .link(appsink.upcast_ref::<gst::Element>())
-                .context("videocropとsinkのリンクに失敗しました")?;
-        } else {
-            pipeline
-                .add_many(&[
-                    &source,
-                    &decodebin,
-                    &videoconvert,
-                    appsink.upcast_ref::<gst::Element>(),
-                ])
-                .context("エレメントの追加に失敗しました")?;
-
-            // sourceとdecodebinをリンク
-            source
-                .link(&decodebin)
-                .context("sourceとdecoderのリンクに失敗しました")?;
-
-            // videoconvertとappsinkをリンク
-            videoconvert
-                .link(appsink.upcast_ref::<gst::Element>())
-                .context("converterとsinkのリンクに失敗しました")?;
-        }
-
-        // decodebinの動的パッドをリンク
-        let videoconvert_clone = videoconvert.clone();
-        decodebin.connect_pad_added(move |_src, src_pad| {
-            let sink_pad = videoconvert_clone
-                .static_pad("sink")
-                .expect("videoconvertのsinkパッドが見つかりません");
-
-            if !sink_pad.is_linked() {
-                if let Err(e) = src_pad.link(&sink_pad) {
-                    eprintln!("パッドのリンクに失敗: {:?}", e);
-                }
-            }
-        });
-
-        println!("\nフレーム抽出中...");
-        println!("  抽出間隔: {}フレームごと", self.config.frame_interval);
-        println!("  出力先: {}", self.config.output_dir.display());
-
-        let output_paths = Arc::new(Mutex::new(Vec::new()));
-        let frame_count = Arc::new(Mutex::new(0u32));
-        let extracted_count = Arc::new(Mutex::new(0u32));
-
-        // 必要なフレーム数に達したら停止するためのフラグ
-        // frame_intervalが非常に大きい場合（frame 0のみ）は、1フレーム抽出後に停止
-        let should_stop = Arc::new(Mutex::new(false));
-        let target_extracts = if self.config.frame_interval == u32::MAX { 1 } else { u32::MAX };
-
-        let progress_callback = Arc::new(progress_callback);
-        let output_paths_clone = output_paths.clone();
-        let frame_count_clone = frame_count.clone();
-        let extracted_count_clone = extracted_count.clone();
-        let should_stop_clone = should_stop.clone();
-        let progress_callback_clone = progress_callback.clone();
-        let config = self.config.clone();
-
-        // サンプルコールバックを設定
-        appsink.set_callbacks(
-            gstreamer_app::AppSinkCallbacks::builder()
-                .new_sample(move |appsink| {
-                    let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Error)?;
-                    let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
-                    let caps = sample.caps().ok_or(gst::FlowError::Error)?;
-
-                    let video_info = gstreamer_video::VideoInfo::from_caps(caps)
-                        .map_err(|_| gst::FlowError::Error)?;
-
-                    let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
-
-                    let mut frame_num = frame_count_clone.lock().unwrap();
-                    let current_frame = *frame_num;
-                    *frame_num += 1;
-
-                    // 指定された間隔でフレームを保存
-                    if current_frame % config.frame_interval == 0 {
-                        let width = video_info.width() as u32;
-                        let height = video_info.height() as u32;
-
-                        // RGB画像として保存（stride に対応して連続バッファを作成）
-                        let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
-                        if let Some(img_buffer) =
-                            ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, contiguous)
-                        {
-                            let filename = format!("frame_{:06}.{}", current_frame, config.image_format);
-                            let output_path = config.output_dir.join(&filename);
-
-                            if let Err(e) = if config.image_format == "jpg" || config.image_format == "jpeg" {
-                                let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
-                                    std::fs::File::create(&output_path).unwrap(),
-                                    config.jpeg_quality,
-                                );
-                                img_buffer.write_with_encoder(encoder)
-                            } else {
-                                img_buffer.save(&output_path)
-                            } {
-                                eprintln!("フレームの保存に失敗: {}", e);
-                            } else {
-                                let mut paths = output_paths_clone.lock().unwrap();
-                                paths.push(output_path);
-
-                                let mut extracted = extracted_count_clone.lock().unwrap();
-                                *extracted += 1;
-
-                                // 進捗コールバック呼び出し
-                                if let Some(ref callback) = *progress_callback_clone {
-                                    callback(*extracted as usize);
-                                }
-
-                                if *extracted % 10 == 0 {
-                                    println!("  {}フレーム抽出完了", *extracted);
-                                }
-
-                                // 必要なフレーム数に達したら停止フラグを立てる
-                                if *extracted >= target_extracts {
-                                    let mut stop = should_stop_clone.lock().unwrap();
-                                    *stop = true;
-                                }
-                            }
-                        }
-                    }
-
-                    Ok(gst::FlowSuccess::Ok)
-                })
-                .build(),
-        );
-
-        // パイプラインを開始
-        pipeline
-            .set_state(gst::State::Playing)
-            .context("パイプラインの開始に失敗しました")?;
-
-        // バスメッセージを処理
-        let bus = pipeline
-            .bus()
-            .expect("パイプラインにバスがありません");
-
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-
-            match msg.view() {
-                MessageView::Eos(..) => {
-                    println!("\n動画の終わりに到達しました");
-                    break;
-                }
-                MessageView::Error(err) => {
-                    pipeline.set_state(gst::State::Null).ok();
-                    anyhow::bail!(
-                        "エラーが発生しました: {} (デバッグ情報: {:?})",
-                        err.error(),
-                        err.debug()
-                    );
-                }
-                _ => (),
-            }
-
-            // 必要なフレーム数に達したら停止
-            if *should_stop.lock().unwrap() {
-                println!("\n必要なフレーム数に達しました。処理を停止します。");
-                break;
-            }
+        match super::decoder()? {
+            #[cfg(feature = "gstreamer")]
+            Decoder::Gstreamer => super::gstreamer_backend::extract_frames(&self.config, video_path.as_ref(), progress_callback, crop_region),
+            #[cfg(feature = "ffmpeg")]
+            Decoder::Ffmpeg => super::ffmpeg::extract_frames(&self.config, video_path.as_ref(), progress_callback, crop_region.as_ref()),
         }
-
-        // パイプラインを停止
-        pipeline
-            .set_state(gst::State::Null)
-            .context("パイプラインの停止に失敗しました")?;
-
-        let final_frame_count = *frame_count.lock().unwrap();
-        let final_extracted_count = *extracted_count.lock().unwrap();
-
-        println!("\n抽出完了!");
-        println!("  処理フレーム数: {}", final_frame_count);
-        println!("  抽出フレーム数: {}", final_extracted_count);
-
-        let paths = Arc::try_unwrap(output_paths)
-            .map(|m| m.into_inner().unwrap())
-            .unwrap_or_else(|arc| arc.lock().unwrap().clone());
-
-        Ok(paths)
     }
 
     /// 動画からフレームを抽出
@@ -480,384 +240,33 @@ impl FrameExtractor {
         P: AsRef<Path>,
         F: FnMut(PathBuf) -> Result<()> + Send + 'static,
     {
-        Self::init_gstreamer()?;
-
-        let video_path = video_path.as_ref();
-        
-        // ファイルの存在チェック
-        if !video_path.exists() {
-            anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
-        }
-        
-        // ファイルが読み取り可能かチェック
-        if let Err(e) = std::fs::metadata(video_path) {
-            anyhow::bail!("動画ファイルにアクセスできません: {:?} ({})", video_path, e);
-        }
-        
-        println!("動画ファイルを開いています: {}", video_path.display());
-
-        // 出力ディレクトリを作成
-        std::fs::create_dir_all(&self.config.output_dir)
-            .context("出力ディレクトリの作成に失敗しました")?;
-
-        // 動画情報を取得
-        let info = Self::get_video_info(video_path)?;
-        println!("動画情報:");
-        println!("  解像度: {}x{}", info.width, info.height);
-        println!("  FPS: {:.2}", info.fps);
-        println!("  再生時間: {:.2}秒", info.duration_sec);
-
-        // GStreamerパイプラインを構築
-        let pipeline = gst::Pipeline::new();
-
-        let source = ElementFactory::make("filesrc")
-            .name("source")
-            .build()
-            .context("filesrcの作成に失敗しました")?;
-
-        let decodebin = ElementFactory::make("decodebin")
-            .name("decoder")
-            .build()
-            .context("decodebinの作成に失敗しました")?;
-
-        let videoconvert = ElementFactory::make("videoconvert")
-            .name("converter")
-            .build()
-            .context("videoconvertの作成に失敗しました")?;
-
-        let appsink = ElementFactory::make("appsink")
-            .name("sink")
-            .build()
-            .context("appsinkの作成に失敗しました")?;
-
-        let appsink = appsink
-            .dynamic_cast::<AppSink>()
-            .map_err(|_| anyhow::anyhow!("appsinkへのキャストに失敗しました"))?;
-
-        appsink.set_caps(Some(
-            &gst::Caps::builder("video/x-raw")
-                .field("format", "RGB")
-                .build(),
-        ));
-        appsink.set_property("emit-signals", false);
-        appsink.set_property("sync", false);
-
-        let source_path = video_path.canonicalize()?;
-        source.set_property("location", source_path.to_str().unwrap());
-
-        pipeline
-            .add_many(&[&source, &decodebin, &videoconvert, appsink.upcast_ref::<gst::Element>()])
-            .context("エレメントの追加に失敗しました")?;
-
-        source.link(&decodebin).context("sourceとdecoderのリンクに失敗しました")?;
-        videoconvert.link(appsink.upcast_ref::<gst::Element>())
-            .context("converterとsinkのリンクに失敗しました")?;
-
-        let videoconvert_clone = videoconvert.clone();
-        decodebin.connect_pad_added(move |_src, src_pad| {
-            let sink_pad = videoconvert_clone
-                .static_pad("sink")
-                .expect("videoconvertのsinkパッドが見つかりません");
-
-            if !sink_pad.is_linked() {
-                if let Err(e) = src_pad.link(&sink_pad) {
-                    eprintln!("パッドのリンクに失敗: {:?}", e);
-                }
-            }
-        });
-
-        let frame_count = Arc::new(Mutex::new(0u32));
-        let extracted_count = Arc::new(Mutex::new(0u32));
-        let callback_error = Arc::new(Mutex::new(None::<String>));
-        let callback = Arc::new(Mutex::new(callback));
-
-        let frame_count_clone = frame_count.clone();
-        let extracted_count_clone = extracted_count.clone();
-        let callback_error_clone = callback_error.clone();
-        let callback_clone = callback.clone();
-        let config = self.config.clone();
-
-        appsink.set_callbacks(
-            gstreamer_app::AppSinkCallbacks::builder()
-                .new_sample(move |appsink| {
-                    // エラーが既に発生していたら処理を中断
-                    if callback_error_clone.lock().unwrap().is_some() {
-                        return Err(gst::FlowError::Error);
-                }
-
-                    let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Error)?;
-                    let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
-                    let caps = sample.caps().ok_or(gst::FlowError::Error)?;
-
-                    let video_info = gstreamer_video::VideoInfo::from_caps(caps)
-                        .map_err(|_| gst::FlowError::Error)?;
-
-                    let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
-
-                    let mut frame_num = frame_count_clone.lock().unwrap();
-                    let current_frame = *frame_num;
-                    *frame_num += 1;
-
-                    if current_frame % config.frame_interval == 0 {
-                        let width = video_info.width() as u32;
-                        let height = video_info.height() as u32;
-
-                        let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
-                        let img = image::RgbImage::from_raw(width, height, contiguous)
-                            .ok_or(gst::FlowError::Error)?;
-
-                        let output_filename = format!("frame_{:08}.{}", current_frame, config.image_format);
-                        let output_path = config.output_dir.join(&output_filename);
-
-                        if let Err(e) = img.save(&output_path) {
-                            eprintln!("画像保存エラー: {}", e);
-                            return Err(gst::FlowError::Error);
-                        }
-
-                        let mut extracted = extracted_count_clone.lock().unwrap();
-                        *extracted += 1;
-
-                        // コールバックを呼び出し
-                        let result = {
-                            let mut cb = callback_clone.lock().unwrap();
-                            cb(output_path)
-                        };
-
-                        if let Err(e) = result {
-                            *callback_error_clone.lock().unwrap() = Some(format!("コールバックエラー: {}", e));
-                            return Err(gst::FlowError::Error);
-                        }
-                    }
-
-                    Ok(gst::FlowSuccess::Ok)
-                })
-                .build(),
-        );
-
-        pipeline.set_state(gst::State::Playing)
-            .context("パイプラインの開始に失敗しました")?;
-
-        let bus = pipeline.bus().expect("パイプラインにバスがありません");
-
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-
-            match msg.view() {
-                MessageView::Eos(..) => {
-                    break;
-                }
-                MessageView::Error(err) => {
-                    pipeline.set_state(gst::State::Null).ok();
-                    anyhow::bail!(
-                        "エラーが発生しました: {} (デバッグ情報: {:?})",
-                        err.error(),
-                        err.debug()
-                    );
-                }
-                _ => (),
-            }
+        match super::decoder()? {
+            #[cfg(feature = "gstreamer")]
+            Decoder::Gstreamer => super::gstreamer_backend::save_frames(&self.config, video_path.as_ref(), callback),
+            #[cfg(feature = "ffmpeg")]
+            Decoder::Ffmpeg => super::ffmpeg::save_frames(&self.config, video_path.as_ref(), None, callback),
         }
-
-        pipeline.set_state(gst::State::Null)
-            .context("パイプラインの停止に失敗しました")?;
-
-        // コールバックでエラーが発生していたら返す
-        if let Some(error) = callback_error.lock().unwrap().take() {
-            anyhow::bail!(error);
-        }
-
-        let final_frame_count = *frame_count.lock().unwrap();
-        let final_extracted_count = *extracted_count.lock().unwrap();
-
-        println!("\n抽出完了!");
-        println!("  処理フレーム数: {}", final_frame_count);
-        println!("  抽出フレーム数: {}", final_extracted_count);
-
-        Ok(())
-    }
-
-    /// シーク後、指定フレーム位置の単一フレームをデコード
-    pub fn extract_frame_at_seek<P: AsRef<Path>>(
-        &self,
-        video_path: P,
-        frame_number: u32,
-    ) -> Result<PathBuf> {
-        Self::init_gstreamer()?;
-
-        let video_path = video_path.as_ref();
-        let info = Self::get_video_info(video_path)?;
-
-        // フレーム番号から時間（秒）を計算
-        let time_sec = (frame_number as f64) / info.fps;
-        let time_ns = gst::ClockTime::from_seconds(time_sec as u64);
-
-        // 出力ディレクトリを作成
-        std::fs::create_dir_all(&self.config.output_dir)
-            .context("出力ディレクトリの作成に失敗しました")?;
-
-        // GStreamerパイプラインを構築
-        let pipeline = gst::Pipeline::new();
-
-        let canonical = video_path.canonicalize()?;
-        let source = ElementFactory::make("filesrc")
-            .property("location", canonical.to_str().unwrap())
-            .build()
-            .context("filesrcの作成に失敗しました")?;
-
-        let decodebin = ElementFactory::make("decodebin")
-            .build()
-            .context("decodebinの作成に失敗しました")?;
-
-        let videoconvert = ElementFactory::make("videoconvert")
-            .build()
-            .context("videoconvertの作成に失敗しました")?;
-
-        let appsink = ElementFactory::make("appsink")
-            .build()
-            .context("appsinkの作成に失敗しました")?;
-
-        let appsink = appsink
-            .dynamic_cast::<AppSink>()
-            .map_err(|_| anyhow::anyhow!("appsinkへのキャストに失敗しました"))?;
-
-        appsink.set_caps(Some(
-            &gst::Caps::builder("video/x-raw")
-                .field("format", "RGB")
-                .build(),
-        ));
-        appsink.set_property("emit-signals", false);
-        appsink.set_property("sync", false);
-
-        pipeline
-            .add_many(&[&source, &decodebin, &videoconvert, appsink.upcast_ref::<gst::Element>()])
-            .context("エレメントの追加に失敗しました")?;
-
-        source
-            .link(&decodebin)
-            .context("sourceとdecoderのリンクに失敗しました")?;
-
-        videoconvert
-            .link(appsink.upcast_ref::<gst::Element>())
-            .context("converterとsinkのリンクに失敗しました")?;
-
-        // decodebinの動的パッドをリンク
-        let videoconvert_clone = videoconvert.clone();
-        decodebin.connect_pad_added(move |_dbin, pad| {
-            if pad.name().starts_with("video") {
-                let videoconvert_sink = videoconvert_clone.static_pad("sink").unwrap();
-                let _ = pad.link(&videoconvert_sink);
-            }
-        });
-
-        // パイプラインを再生状態に
-        pipeline
-            .set_state(gst::State::Playing)
-            .context("パイプラインの開始に失敗しました")?;
-
-        // シーク処理
-        pipeline.seek_simple(gst::SeekFlags::FLUSH, time_ns)?;
-
-        // AppSinkからサンプルを取得
-        let _appsink_element = appsink.upcast_ref::<gst::Element>();
-
-        // パイプラインを停止するまでサンプルを待機
-        std::thread::sleep(std::time::Duration::from_millis(100));
-
-        // AppSinkからサンプルを取得
-        let output_paths = Arc::new(Mutex::new(Vec::new()));
-        let output_paths_clone = output_paths.clone();
-
-        if let Some(sample) = appsink.try_pull_sample(gst::ClockTime::NONE) {
-            if let Some(buffer) = sample.buffer() {
-                if let Ok(map) = buffer.map_readable() {
-                    let caps = sample.caps().unwrap();
-                    if let Some(structure) = caps.structure(0) {
-                        if let (Ok(width), Ok(height)) = (
-                            structure.get::<i32>("width"),
-                            structure.get::<i32>("height"),
-                        ) {
-                            // 画像を保存
-                            let frame_data = map.as_slice();
-                            // caps から VideoInfo を作成して stride を考慮してコピー
-                            if let Ok(video_info2) = gstreamer_video::VideoInfo::from_caps(&caps) {
-                                let contiguous = plane_to_contiguous_rgb(&video_info2, frame_data);
-                                if let Some(img) = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(
-                                    width as u32,
-                                    height as u32,
-                                    contiguous,
-                                ) {
-                                    let output_path = self.config.output_dir.join(format!("frame_{:06}.png", frame_number));
-                                    if let Ok(_) = img.save(&output_path) {
-                                        output_paths_clone.lock().unwrap().push(output_path);
-                                    }
-                                }
-                            } else {
-                                // VideoInfo 作成失敗時は従来どおり直接保存
-                                if let Some(img) = ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(
-                                    width as u32,
-                                    height as u32,
-                                    frame_data.to_vec(),
-                                ) {
-                                    let output_path = self.config.output_dir.join(format!("frame_{:06}.png", frame_number));
-                                    if let Ok(_) = img.save(&output_path) {
-                                        output_paths_clone.lock().unwrap().push(output_path);
-                                    }
-                                }
-                            }
-                            
-                        }
-                    }
-                }
-            }
-        }
-
-        pipeline
-            .set_state(gst::State::Null)
-            .context("パイプラインの停止に失敗しました")?;
-
-        let paths = output_paths.lock().unwrap().clone();
-        paths
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("フレームの抽出に失敗しました"))
     }
 
     /// 特定のフレーム番号のフレームを抽出
+    ///
+    /// 目的のフレームの直前のキーフレームにシークしてからデコードするため、
+    /// 動画の後半のフレームでも先頭からデコードし直す必要はない
     pub fn extract_frame_at<P: AsRef<Path>>(
         &self,
         video_path: P,
         frame_number: u32,
     ) -> Result<PathBuf> {
-        // frame 0の場合は最初のフレームだけを抽出
-        if frame_number == 0 {
-            // 最初のフレームのみ抽出するため、frame_intervalを非常に大きく設定
-            let mut temp_config = self.config.clone();
-            // frame_intervalを最初のフレームより大きく設定することで、
-            // 最初のフレーム（frame 0）のみが抽出される
-            temp_config.frame_interval = u32::MAX; // 最初のフレームのみを抽出
-
-            let temp_extractor = FrameExtractor::new(temp_config);
-            let paths = temp_extractor.extract_frames(&video_path)?;
-
-            // 最初に抽出されたフレームを返す
-            paths
-                .into_iter()
-                .next()
-                .ok_or_else(|| anyhow::anyhow!("フレームの抽出に失敗しました"))
-        } else {
-            // その他のフレームは従来の方法で抽出
-            let mut temp_config = self.config.clone();
-            temp_config.frame_interval = (frame_number + 1).max(1);
-
-            let temp_extractor = FrameExtractor::new(temp_config);
-            let paths = temp_extractor.extract_frames(&video_path)?;
+        let img = self.extract_frame_to_memory(&video_path, frame_number)?;
 
-            // 最後に抽出されたフレームが目的のフレーム
-            paths
-                .into_iter()
-                .last()
-                .ok_or_else(|| anyhow::anyhow!("フレームの抽出に失敗しました"))
-        }
+        std::fs::create_dir_all(&self.config.output_dir)
+            .context("出力ディレクトリの作成に失敗しました")?;
+        let output_path = self
+            .config
+            .output_dir
+            .join(format!("frame_{:06}.{}", frame_number, self.config.image_format));
+        save_frame_image(&self.config, &img, &output_path)?;
+        Ok(output_path)
     }
 
     /// 時間指定でフレームを抽出（秒単位）
@@ -866,15 +275,14 @@ impl FrameExtractor {
         video_path: P,
         time_sec: f64,
     ) -> Result<PathBuf> {
-        let info = Self::get_video_info(&video_path)?;
+        let info = self.video_info(&video_path)?;
         let frame_number = (time_sec * info.fps) as u32;
         self.extract_frame_at(video_path, frame_number)
     }
 
     /// 動画からフレームを抽出し、各フレームをメモリ上で同期的にコールバックで処理
     /// 
-    /// GStreamerのSend制約を回避するため、AppSinkから取得したバッファを
-    /// 同じスレッド内でコールバックに渡す。これによりWgpuなどのnon-Send型も使用可能。
+    /// デコードしたフレームを同じスレッド内でコールバックに渡す。これによりWgpuなどのnon-Send型も使用可能。
     ///
     /// # Arguments
     /// * `video_path` - 動画ファイルパス
@@ -882,482 +290,79 @@ impl FrameExtractor {
     pub fn process_frames_sync<P, F>(
         &self,
         video_path: P,
-        mut callback: F,
+        callback: F,
     ) -> Result<()>
     where
         P: AsRef<Path>,
         F: FnMut(&image::RgbImage, u32) -> Result<()>,
     {
-        Self::init_gstreamer()?;
-
-        let video_path = video_path.as_ref();
-        println!("動画ファイルを開いています: {}", video_path.display());
-
-        // 動画情報を取得
-        let info = Self::get_video_info(video_path)?;
-        println!("動画情報:");
-        println!("  解像度: {}x{}", info.width, info.height);
-        println!("  FPS: {:.2}", info.fps);
-        println!("  再生時間: {:.2}秒", info.duration_sec);
-
-        // GStreamerパイプラインを構築
-        let pipeline = gst::Pipeline::new();
-
-        let source = ElementFactory::make("filesrc")
-            .name("source")
-            .build()
-            .context("filesrcの作成に失敗しました")?;
-
-        let decodebin = ElementFactory::make("decodebin")
-            .name("decoder")
-            .build()
-            .context("decodebinの作成に失敗しました")?;
-
-        let videoconvert = ElementFactory::make("videoconvert")
-            .name("converter")
-            .build()
-            .context("videoconvertの作成に失敗しました")?;
-
-        let appsink = ElementFactory::make("appsink")
-            .name("sink")
-            .build()
-            .context("appsinkの作成に失敗しました")?;
-
-        let appsink = appsink
-            .dynamic_cast::<AppSink>()
-            .map_err(|_| anyhow::anyhow!("appsinkへのキャストに失敗しました"))?;
-
-        appsink.set_caps(Some(
-            &gst::Caps::builder("video/x-raw")
-                .field("format", "RGB")
-                .build(),
-        ));
-        appsink.set_property("emit-signals", false);
-        appsink.set_property("sync", false);
-        appsink.set_property("max-buffers", 1u32);  // バッファを最小化
-
-        source.set_property("location", video_path.to_str().unwrap());
-
-        pipeline
-            .add_many(&[&source, &decodebin, &videoconvert, appsink.upcast_ref::<gst::Element>()])
-            .context("エレメントの追加に失敗しました")?;
-
-        source.link(&decodebin).context("sourceとdecoderのリンクに失敗しました")?;
-        videoconvert.link(appsink.upcast_ref::<gst::Element>())
-            .context("converterとsinkのリンクに失敗しました")?;
-
-        let videoconvert_clone = videoconvert.clone();
-        decodebin.connect_pad_added(move |_src, src_pad| {
-            let sink_pad = videoconvert_clone
-                .static_pad("sink")
-                .expect("videoconvertのsinkパッドが見つかりません");
-
-            if !sink_pad.is_linked() {
-                if let Err(e) = src_pad.link(&sink_pad) {
-                    eprintln!("パッドのリンクに失敗: {:?}", e);
-                }
-            }
-        });
-
-        pipeline.set_state(gst::State::Playing)
-            .context("パイプラインの開始に失敗しました")?;
-
-        let bus = pipeline.bus().expect("パイプラインにバスがありません");
-        let mut frame_count = 0u32;
-        let mut processed_count = 0u32;
-
-        // フレームを同期的に処理
-        loop {
-            // バスメッセージを確認
-            if let Some(msg) = bus.pop() {
-                use gst::MessageView;
-                match msg.view() {
-                    MessageView::Eos(..) => {
-                        break;
-                    }
-                    MessageView::Error(err) => {
-                        pipeline.set_state(gst::State::Null).ok();
-                        anyhow::bail!(
-                            "エラーが発生しました: {} (デバッグ情報: {:?})",
-                            err.error(),
-                            err.debug()
-                        );
-                    }
-                    _ => (),
-                }
-            }
-
-            // フレームを取得（非ブロッキング）
-            if let Some(sample) = appsink.try_pull_sample(gst::ClockTime::from_mseconds(100)) {
-                let buffer = sample.buffer().context("バッファの取得に失敗しました")?;
-                let caps = sample.caps().context("capsの取得に失敗しました")?;
-
-                let video_info = gstreamer_video::VideoInfo::from_caps(caps)
-                    .context("VideoInfoの作成に失敗しました")?;
-
-                let map = buffer.map_readable().context("バッファのマップに失敗しました")?;
-
-                let current_frame = frame_count;
-                frame_count += 1;
-
-                if current_frame % self.config.frame_interval == 0 {
-                    let width = video_info.width() as u32;
-                    let height = video_info.height() as u32;
-
-                    let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
-                    let img = image::RgbImage::from_raw(width, height, contiguous)
-                        .context("RgbImageの作成に失敗しました")?;
-
-                    // コールバックを同期的に呼び出し（同じスレッド内）
-                    callback(&img, current_frame)?;
-
-                    processed_count += 1;
-
-                    if processed_count % 30 == 0 {
-                        println!("処理済み: {}フレーム", processed_count);
-                    }
-                }
-            }
-        }
-
-        pipeline.set_state(gst::State::Null)
-            .context("パイプラインの停止に失敗しました")?;
-
-        println!("\n処理完了!");
-        println!("  総フレーム数: {}", frame_count);
-        println!("  処理フレーム数: {}", processed_count);
-
-        Ok(())
+        self.process_frames_sync_in_range(video_path, None, TimeRange::default(), callback)
     }
 
     /// 動画をクロップしてからフレームを同期的に処理する
     ///
-    /// `crop_region` が Some の場合、GStreamer パイプラインに `videocrop` を挿入し、
-    /// 指定領域を先に切り出してから AppSink に渡します。AppSink に渡される画像は
+    /// `crop_region` が Some の場合、デコード時に指定領域を先に切り出してから
+    /// コールバックに渡します。コールバックに渡される画像は
     /// 切り出し後の領域（幅 = crop_region.width, 高さ = crop_region.height）になります。
     pub fn process_frames_sync_with_crop<P, F>(
         &self,
         video_path: P,
         crop_region: Option<crate::analyzer::InputIndicatorRegion>,
-        mut callback: F,
+        callback: F,
     ) -> Result<()>
     where
         P: AsRef<Path>,
         F: FnMut(&image::RgbImage, u32) -> Result<()>,
     {
-        Self::init_gstreamer()?;
-
-        let video_path = video_path.as_ref();
-        println!("動画ファイルを開いています: {}", video_path.display());
-
-        // 動画情報を取得
-        let info = Self::get_video_info(video_path)?;
-        println!("動画情報:");
-        println!("  解像度: {}x{}", info.width, info.height);
-        println!("  FPS: {:.2}", info.fps);
-        println!("  再生時間: {:.2}秒", info.duration_sec);
-
-        // GStreamerパイプラインを構築
-        let pipeline = gst::Pipeline::new();
-
-        let source = ElementFactory::make("filesrc")
-            .name("source")
-            .build()
-            .context("filesrcの作成に失敗しました")?;
-
-        let decodebin = ElementFactory::make("decodebin")
-            .name("decoder")
-            .build()
-            .context("decodebinの作成に失敗しました")?;
-
-        let videoconvert = ElementFactory::make("videoconvert")
-            .name("converter")
-            .build()
-            .context("videoconvertの作成に失敗しました")?;
-
-        // videocrop はオプションで追加
-        let videocrop = if crop_region.is_some() {
-            Some(
-                ElementFactory::make("videocrop")
-                    .name("crop")
-                    .build()
-                    .context("videocropの作成に失敗しました")?,
-            )
-        } else {
-            None
-        };
-
-        let appsink = ElementFactory::make("appsink")
-            .name("sink")
-            .build()
-            .context("appsinkの作成に失敗しました")?;
-
-        let appsink = appsink
-            .dynamic_cast::<AppSink>()
-            .map_err(|_| anyhow::anyhow!("appsinkへのキャストに失敗しました"))?;
-
-        appsink.set_caps(Some(
-            &gst::Caps::builder("video/x-raw").field("format", "RGB").build(),
-        ));
-        appsink.set_property("emit-signals", false);
-        appsink.set_property("sync", false);
-        appsink.set_property("max-buffers", 1u32);
-
-        source.set_property("location", video_path.to_str().unwrap());
-
-        // パイプラインにエレメントを追加
-        if let Some(ref crop) = videocrop {
-            pipeline
-                .add_many(&[&source, &decodebin, &videoconvert, crop, appsink.upcast_ref::<gst::Element>()])
-                .context("エレメントの追加に失敗しました")?;
-        } else {
-            pipeline
-                .add_many(&[&source, &decodebin, &videoconvert, appsink.upcast_ref::<gst::Element>()])
-                .context("エレメントの追加に失敗しました")?;
-        }
-
-        source.link(&decodebin).context("sourceとdecoderのリンクに失敗しました")?;
-
-        // パス: decodebin -> videoconvert -> (videocrop?) -> appsink
-        if let Some(ref crop) = videocrop {
-            videoconvert
-                .link(crop)
-                .context("converterとvideocropのリンクに失敗しました")?;
-            crop.link(appsink.upcast_ref::<gst::Element>())
-                .context("videocropとsinkのリンクに失敗しました")?;
-        } else {
-            videoconvert
-                .link(appsink.upcast_ref::<gst::Element>())
-                .context("converterとsinkのリンクに失敗しました")?;
-        }
-
-        let videoconvert_clone = videoconvert.clone();
-        decodebin.connect_pad_added(move |_src, src_pad| {
-            let sink_pad = videoconvert_clone
-                .static_pad("sink")
-                .expect("videoconvertのsinkパッドが見つかりません");
-
-            if !sink_pad.is_linked() {
-                if let Err(e) = src_pad.link(&sink_pad) {
-                    eprintln!("パッドのリンクに失敗: {:?}", e);
-                }
-            }
-        });
-
-        // videocrop プロパティ設定（必要なら）
-        if let (Some(crop_elem), Some(region)) = (videocrop.as_ref(), crop_region) {
-            let left = region.x as i32;
-            let top = region.y as i32;
-            let crop_w = region.width as i32;
-            let crop_h = region.height as i32;
-            let right = (info.width as i32) - (left + crop_w);
-            let bottom = (info.height as i32) - (top + crop_h);
-            let right = if right < 0 { 0 } else { right };
-            let bottom = if bottom < 0 { 0 } else { bottom };
+        self.process_frames_sync_in_range(video_path, crop_region, TimeRange::default(), callback)
+    }
 
-            crop_elem.set_property("left", &left);
-            crop_elem.set_property("right", &right);
-            crop_elem.set_property("top", &top);
-            crop_elem.set_property("bottom", &bottom);
+    /// 動画の指定区間だけをクロップしてフレームを同期的に処理する
+    ///
+    /// 区間の開始位置へシークし、終了位置で EOS になるようにしてからデコードする。
+    /// コールバックに渡すフレーム番号は動画の先頭から数えた番号になる。
+    pub fn process_frames_sync_in_range<P, F>(
+        &self,
+        video_path: P,
+        crop_region: Option<crate::analyzer::InputIndicatorRegion>,
+        range: TimeRange,
+        callback: F,
+    ) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&image::RgbImage, u32) -> Result<()>,
+    {
+        if let FrameSource::ImageSequence(sequence) = FrameSource::open(video_path.as_ref(), self.config.image_sequence_fps)? {
+            return sequence.process_frames(self.config.transform, crop_region.as_ref(), range, self.config.frame_interval, callback);
         }
 
-        pipeline.set_state(gst::State::Playing)
-            .context("パイプラインの開始に失敗しました")?;
-
-        let bus = pipeline.bus().expect("パイプラインにバスがありません");
-        let mut frame_count = 0u32;
-        let mut processed_count = 0u32;
-
-        // フレームを同期的に処理
-        loop {
-            // バスメッセージを確認
-            if let Some(msg) = bus.pop() {
-                use gst::MessageView;
-                match msg.view() {
-                    MessageView::Eos(..) => {
-                        break;
-                    }
-                    MessageView::Error(err) => {
-                        pipeline.set_state(gst::State::Null).ok();
-                        anyhow::bail!(
-                            "エラーが発生しました: {} (デバッグ情報: {:?})",
-                            err.error(),
-                            err.debug()
-                        );
-                    }
-                    _ => (),
-                }
-            }
-
-            // フレームを取得（非ブロッキング）
-            if let Some(sample) = appsink.try_pull_sample(gst::ClockTime::from_mseconds(100)) {
-                let buffer = sample.buffer().context("バッファの取得に失敗しました")?;
-                let caps = sample.caps().context("capsの取得に失敗しました")?;
-
-                let video_info = gstreamer_video::VideoInfo::from_caps(caps)
-                    .context("VideoInfoの作成に失敗しました")?;
-
-                let map = buffer.map_readable().context("バッファのマップに失敗しました")?;
-
-                let current_frame = frame_count;
-                frame_count += 1;
-
-                if current_frame % self.config.frame_interval == 0 {
-                    let width = video_info.width() as u32;
-                    let height = video_info.height() as u32;
-
-                    let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
-                    let img = image::RgbImage::from_raw(width, height, contiguous)
-                        .context("RgbImageの作成に失敗しました")?;
-
-                    // コールバックを同期的に呼び出し（同じスレッド内）
-                    callback(&img, current_frame)?;
-
-                    processed_count += 1;
-
-                    if processed_count % 30 == 0 {
-                        println!("処理済み: {}フレーム", processed_count);
-                    }
-                }
-            }
+        match super::decoder()? {
+            #[cfg(feature = "gstreamer")]
+            Decoder::Gstreamer => super::gstreamer_backend::process_frames_sync_in_range(&self.config, video_path.as_ref(), crop_region, range, callback),
+            #[cfg(feature = "ffmpeg")]
+            Decoder::Ffmpeg => super::ffmpeg::process_frames(video_path.as_ref(), self.config.transform, crop_region.as_ref(), range, self.config.frame_interval, callback).map(|_| ()),
         }
-
-        pipeline.set_state(gst::State::Null)
-            .context("パイプラインの停止に失敗しました")?;
-
-        println!("\n処理完了!");
-        println!("  総フレーム数: {}", frame_count);
-        println!("  処理フレーム数: {}", processed_count);
-
-        Ok(())
     }
 
     /// 特定のフレーム番号のフレームをメモリ上で抽出（ファイル保存なし）
+    ///
+    /// 目的のフレームの直前のキーフレームにシークし、そこから PTS を見ながら
+    /// 目的のフレームまでデコードを進める
     pub fn extract_frame_to_memory<P: AsRef<Path>>(
         &self,
         video_path: P,
         frame_number: u32,
     ) -> Result<image::RgbImage> {
-        gst::init()?;
-
-        let pipeline = gst::Pipeline::default();
-
-        let src = ElementFactory::make("filesrc")
-            .name("src")
-            .property("location", video_path.as_ref().to_str().unwrap())
-            .build()?;
-
-        let decodebin = ElementFactory::make("decodebin")
-            .name("decoder")
-            .build()?;
-        
-        let videoconvert = ElementFactory::make("videoconvert")
-            .name("converter")
-            .build()?;
-        
-        let videoscale = ElementFactory::make("videoscale")
-            .name("scaler")
-            .build()?;
-
-        let appsink = AppSink::builder()
-            .name("sink")
-            .caps(
-                &gst::Caps::builder("video/x-raw")
-                    .field("format", "RGB")
-                    .build(),
-            )
-            .build();
-
-        pipeline.add_many([&src, &decodebin, &videoconvert, &videoscale, appsink.upcast_ref()])?;
-        src.link(&decodebin)?;
-        videoconvert.link(&videoscale)?;
-        videoscale.link(&appsink)?;
-
-        let videoconvert_weak = videoconvert.downgrade();
-        decodebin.connect_pad_added(move |_, src_pad| {
-            let Some(videoconvert) = videoconvert_weak.upgrade() else {
-                return;
-            };
-
-            let sink_pad = videoconvert.static_pad("sink").expect("sink pad");
-            if sink_pad.is_linked() {
-                return;
-            }
-
-            if let Err(e) = src_pad.link(&sink_pad) {
-                eprintln!("Failed to link pads: {}", e);
-            }
-        });
-
-        pipeline.set_state(gst::State::Playing)?;
-
-        let bus = pipeline.bus().unwrap();
-        let mut frame_count = 0u32;
-        let mut result_image: Option<image::RgbImage> = None;
-        
-        // タイムアウトを設定（10秒）
-        let timeout = std::time::Duration::from_secs(10);
-        let start_time = std::time::Instant::now();
-
-        'outer: loop {
-            // タイムアウトチェック
-            if start_time.elapsed() > timeout {
-                pipeline.set_state(gst::State::Null)?;
-                return Err(anyhow::anyhow!("フレーム抽出がタイムアウトしました"));
-            }
-
-            // バスメッセージを処理
-            while let Some(msg) = bus.pop() {
-                use gst::MessageView;
-
-                match msg.view() {
-                    MessageView::Eos(..) => {
-                        break 'outer;
-                    }
-                    MessageView::Error(err) => {
-                        pipeline.set_state(gst::State::Null)?;
-                        return Err(anyhow::anyhow!(
-                            "エラー: {} (デバッグ: {:?})",
-                            err.error(),
-                            err.debug()
-                        ));
-                    }
-                    _ => {}
-                }
-            }
-
-            // フレームを取得
-            if let Some(sample) = appsink.try_pull_sample(gst::ClockTime::from_mseconds(100)) {
-                if frame_count == frame_number {
-                    // 目的のフレームを取得
-                    let buffer = sample.buffer().ok_or_else(|| anyhow::anyhow!("バッファなし"))?;
-                    let caps = sample.caps().ok_or_else(|| anyhow::anyhow!("キャプスなし"))?;
-                    let video_info = gstreamer_video::VideoInfo::from_caps(caps)?;
-
-                    let map = buffer.map_readable().map_err(|_| anyhow::anyhow!("マップ失敗"))?;
-                    let width = video_info.width();
-                    let height = video_info.height();
-
-                    let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
-                    if let Some(img) = image::RgbImage::from_raw(width, height, contiguous) {
-                        result_image = Some(img);
-                        break 'outer;
-                    }
-                }
-                frame_count += 1;
-            }
-
-            // CPU使用率を下げるため少し待機
-            std::thread::sleep(std::time::Duration::from_millis(10));
+        if let FrameSource::ImageSequence(sequence) = FrameSource::open(video_path.as_ref(), self.config.image_sequence_fps)? {
+            return sequence.frame(frame_number, self.config.transform);
         }
 
-        // パイプラインを確実に停止・解放
-        pipeline.set_state(gst::State::Null)?;
-        
-        // 少し待機してリソースを解放
-        std::thread::sleep(std::time::Duration::from_millis(100));
-
-        result_image.ok_or_else(|| anyhow::anyhow!("指定されたフレームが見つかりませんでした"))
+        match super::decoder()? {
+            #[cfg(feature = "gstreamer")]
+            Decoder::Gstreamer => super::gstreamer_backend::extract_frame(&self.config, video_path.as_ref(), frame_number),
+            #[cfg(feature = "ffmpeg")]
+            Decoder::Ffmpeg => super::ffmpeg::extract_frame(video_path.as_ref(), self.config.transform, frame_number),
+        }
     }
 }
 
@@ -1372,4 +377,47 @@ mod tests {
         assert_eq!(config.image_format, "png");
         assert_eq!(config.jpeg_quality, 95);
     }
+
+    #[test]
+    fn test_crop_rgb_rows_with_padding() {
+        // 幅 4px、1行 16 バイト（4バイトのパディング付き）の 3 行
+        let stride = 16;
+        let src: Vec<u8> = (0..stride * 3).map(|i| i as u8).collect();
+        let tile = crop_rgb_rows(&src, stride, 1, 1, 2, 2).unwrap();
+        assert_eq!(tile.get_pixel(0, 0).0, [19, 20, 21]);
+        assert_eq!(tile.get_pixel(1, 1).0, [38, 39, 40]);
+
+        // 範囲外
+        assert!(crop_rgb_rows(&src, stride, 3, 0, 2, 1).is_none());
+        assert!(crop_rgb_rows(&src, stride, 0, 2, 1, 2).is_none());
+    }
+
+    #[test]
+    fn test_scaled_size_keeps_aspect() {
+        let mut config = FrameExtractorConfig::default();
+        assert_eq!(config.scaled_size(1920, 1080), None);
+
+        config.scale_width = Some(960);
+        assert_eq!(config.scaled_size(1920, 1080), Some((960, 540)));
+
+        config.scale_width = None;
+        config.scale_height = Some(360);
+        assert_eq!(config.scaled_size(1920, 1080), Some((640, 360)));
+
+        config.scale_width = Some(100);
+        assert_eq!(config.scaled_size(1920, 1080), Some((100, 360)));
+    }
+
+    #[test]
+    fn test_time_range_frames() {
+        let range = TimeRange::new(Some(10.0), Some(20.5)).unwrap();
+        assert!(!range.is_full());
+        assert_eq!(range.start_frame(60.0), 600);
+        assert_eq!(range.end_frame(60.0), Some(1230));
+
+        assert!(TimeRange::default().is_full());
+        assert_eq!(TimeRange::default().start_frame(60.0), 0);
+        assert!(TimeRange::new(Some(20.0), Some(10.0)).is_err());
+        assert!(TimeRange::new(Some(-1.0), None).is_err());
+    }
 }
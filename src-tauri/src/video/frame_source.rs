@@ -0,0 +1,193 @@
+//! フレームの供給元（動画ファイルまたは連番画像のフォルダ）
+//!
+//! 他のツールから書き出した連番の PNG / JPG フォルダも動画と同じように解析できるよう、
+//! `process_frames_sync_with_crop` と同じコールバックでフレームを渡す。
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::frame_extractor::{CustomVideoInfo, TimeRange};
+use crate::analyzer::InputIndicatorRegion;
+use crate::model::VideoTransform;
+
+/// 連番画像にはフレームレートの情報が無いため、指定が無ければこのフレームレートで撮影されたものとして扱う
+pub const DEFAULT_IMAGE_SEQUENCE_FPS: f64 = 60.0;
+
+/// フレームの供給元
+pub enum FrameSource {
+    /// 動画ファイル（GStreamer / ffmpeg でデコードする）
+    Video(PathBuf),
+    /// 連番画像のフォルダ
+    ImageSequence(ImageSequence),
+}
+
+impl FrameSource {
+    /// フォルダの場合は連番画像（image_sequence_fps で撮影されたものとして扱う）、それ以外は動画ファイルとして開く
+    pub fn open<P: AsRef<Path>>(path: P, image_sequence_fps: f64) -> Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Ok(FrameSource::ImageSequence(ImageSequence::open(path, image_sequence_fps)?))
+        } else {
+            Ok(FrameSource::Video(path.to_path_buf()))
+        }
+    }
+}
+
+/// 連番画像のフォルダ（ファイル名の末尾の数字の順に並べる）
+pub struct ImageSequence {
+    files: Vec<PathBuf>,
+    fps: f64,
+}
+
+impl ImageSequence {
+    pub fn open(dir: &Path, fps: f64) -> Result<Self> {
+        if !(fps.is_finite() && fps > 0.0) {
+            anyhow::bail!("連番画像のフレームレートが不正です: {}", fps);
+        }
+        let mut files: Vec<(u64, PathBuf)> = std::fs::read_dir(dir)
+            .with_context(|| format!("フォルダを開けません: {:?}", dir))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+            })
+            .map(|path| (trailing_number(&path), path))
+            .collect();
+        if files.is_empty() {
+            anyhow::bail!("フォルダに画像（png / jpg）がありません: {:?}", dir);
+        }
+        // frame_2.png が frame_10.png より前に来るよう数値で並べる
+        files.sort();
+
+        Ok(Self { files: files.into_iter().map(|(_, path)| path).collect(), fps })
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// 動画情報（解像度は最初の画像から取得する。回転・反転の補正前の値）
+    pub fn info(&self) -> Result<CustomVideoInfo> {
+        let (width, height) = image::image_dimensions(&self.files[0])
+            .with_context(|| format!("画像を読み込めません: {:?}", self.files[0]))?;
+        Ok(CustomVideoInfo {
+            width: width as i32,
+            height: height as i32,
+            fps: self.fps,
+            duration_sec: self.files.len() as f64 / self.fps,
+        })
+    }
+
+    /// 指定フレームの画像（transform の補正を適用する）
+    pub fn frame(&self, frame_number: u32, transform: VideoTransform) -> Result<image::RgbImage> {
+        let path = self
+            .files
+            .get(frame_number as usize)
+            .ok_or_else(|| anyhow::anyhow!("指定されたフレームが見つかりませんでした"))?;
+        let img = image::open(path)
+            .with_context(|| format!("画像を読み込めません: {:?}", path))?
+            .to_rgb8();
+        Ok(super::transform_image(transform, img))
+    }
+
+    /// 画像を順に読み込み、frame_interval ごとにコールバックで処理する
+    ///
+    /// crop_region を指定した場合は切り出した画像を渡す（動画の videocrop と同じ）。
+    /// 切り出し範囲が画像からはみ出す場合はエラーを返す。
+    pub fn process_frames<F>(
+        &self,
+        transform: VideoTransform,
+        crop_region: Option<&InputIndicatorRegion>,
+        range: TimeRange,
+        frame_interval: u32,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&image::RgbImage, u32) -> Result<()>,
+    {
+        let start = range.start_frame(self.fps) as usize;
+        let end = range
+            .end_frame(self.fps)
+            .map_or(self.files.len(), |end| (end as usize).min(self.files.len()));
+        println!("連番画像を処理します: {}枚 (フレーム {}〜{})", self.files.len(), start, end);
+
+        for frame_number in (start..end).filter(|n| *n as u32 % frame_interval.max(1) == 0) {
+            let img = self.frame(frame_number as u32, transform)?;
+            let img = match crop_region {
+                Some(region) => {
+                    if region.x + region.width > img.width() || region.y + region.height > img.height() {
+                        anyhow::bail!(
+                            "入力表示の領域が画像（{}x{}）の範囲外です: {:?}",
+                            img.width(),
+                            img.height(),
+                            self.files[frame_number]
+                        );
+                    }
+                    image::imageops::crop_imm(&img, region.x, region.y, region.width, region.height).to_image()
+                }
+                None => img,
+            };
+            callback(&img, frame_number as u32)?;
+        }
+        Ok(())
+    }
+}
+
+// ファイル名（拡張子なし）の末尾の数字（無ければ 0）
+fn trailing_number(path: &Path) -> u64 {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let digits: String = stem
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    digits.parse().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_sequence_order_and_crop() {
+        let dir = crate::test_support::TempDir::new("sequence_test");
+        for (name, value) in [("frame_10.png", 30u8), ("frame_2.png", 20), ("frame_1.png", 10)] {
+            image::RgbImage::from_pixel(8, 4, image::Rgb([value, value, value]))
+                .save(dir.join(name))
+                .unwrap();
+        }
+
+        let sequence = match FrameSource::open(dir.path(), 30.0).unwrap() {
+            FrameSource::ImageSequence(sequence) => sequence,
+            FrameSource::Video(_) => panic!("フォルダは連番画像として開く"),
+        };
+        assert_eq!(sequence.len(), 3);
+        let info = sequence.info().unwrap();
+        assert_eq!((info.width, info.fps, info.duration_sec), (8, 30.0, 0.1));
+
+        let region = InputIndicatorRegion { x: 2, y: 1, width: 4, height: 2, rows: 1, cols: 1 };
+        let mut seen = Vec::new();
+        sequence
+            .process_frames(VideoTransform::None, Some(&region), TimeRange::default(), 1, |img, frame| {
+                assert_eq!(img.dimensions(), (4, 2));
+                seen.push((frame, img.get_pixel(0, 0)[0]));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen, vec![(0, 10), (1, 20), (2, 30)]);
+
+        // 画像からはみ出す範囲は切り詰めずにエラーにする
+        let outside = InputIndicatorRegion { x: 6, y: 0, width: 4, height: 2, rows: 1, cols: 1 };
+        assert!(sequence
+            .process_frames(VideoTransform::None, Some(&outside), TimeRange::default(), 1, |_, _| Ok(()))
+            .is_err());
+    }
+}
@@ -0,0 +1,1332 @@
+//! GStreamer によるフレーム抽出（gstreamer feature）
+//!
+//! 標準のバックエンド。ffmpeg バックエンドと同じく、FrameExtractor の各メソッドから呼び出される。
+
+use anyhow::{Context, Result};
+use gstreamer::prelude::*;
+use gstreamer::{self as gst, ElementFactory};
+use gstreamer_app::AppSink;
+use image::{ImageBuffer, Rgb};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::frame_extractor::{save_frame_image, CustomVideoInfo, FrameExtractor, FrameExtractorConfig, TimeRange};
+use crate::model::VideoTransform;
+
+/// GStreamerを初期化
+pub(super) fn init() -> Result<()> {
+    gst::init().context("GStreamerの初期化に失敗しました")?;
+    Ok(())
+}
+
+// 指定された VideoInfo と元データ（stride を含む可能性あり）から
+// 連続した RGB バイト列を作成して返す。
+pub(super) fn plane_to_contiguous_rgb(video_info: &gstreamer_video::VideoInfo, src: &[u8]) -> Vec<u8> {
+    let width = video_info.width() as usize;
+    let height = video_info.height() as usize;
+    // stride() は行バイト幅のスライスを返す（通常は1要素）
+    let stride = video_info.stride().get(0).cloned().unwrap_or((width * 3) as i32) as usize;
+
+    // stride が期待どおりならそのままコピー
+    if stride == width * 3 {
+        return src.to_vec();
+    }
+
+    let mut out = Vec::with_capacity(width * 3 * height);
+    for row in 0..height {
+        let start = row * stride;
+        let end = start + width * 3;
+        if end <= src.len() {
+            out.extend_from_slice(&src[start..end]);
+        } else if start < src.len() {
+            // 不足している場合は残りをコピーしてゼロ埋め
+            out.extend_from_slice(&src[start..src.len()]);
+            out.extend(std::iter::repeat(0).take(end - src.len()));
+        } else {
+            out.extend(std::iter::repeat(0).take(width * 3));
+        }
+    }
+
+    out
+}
+
+// videoconvert を作成する（回転・反転の補正がある場合は videoconvert ! videoflip のビン）
+// ビンのパッド名も "sink" / "src" になるため、videoconvert と同じようにリンクできる
+pub(super) fn make_converter(transform: VideoTransform) -> Result<gst::Element> {
+    match super::videoflip_method(transform) {
+        None => ElementFactory::make("videoconvert")
+            .build()
+            .context("videoconvertの作成に失敗しました"),
+        Some(method) => {
+            let bin = gst::parse::bin_from_description(&format!("videoconvert ! videoflip method={}", method), true)
+                .context("videoflipの作成に失敗しました")?;
+            Ok(bin.upcast())
+        }
+    }
+}
+
+/// パイプラインを区間の開始位置へシークし、終了位置で EOS になるようにする
+///
+/// PLAYING にする前に呼ぶ（プリロールのため PAUSED にする）。動画全体の場合は何もしない。
+pub(super) fn seek_to_range(pipeline: &gst::Pipeline, range: TimeRange) -> Result<()> {
+    if range.is_full() {
+        return Ok(());
+    }
+
+    // プリロールしてからシークする（シークは PAUSED 以上でないと効かない）
+    pipeline.set_state(gst::State::Paused)
+        .context("パイプラインの開始に失敗しました")?;
+    let (preroll_result, _, _) = pipeline.state(gst::ClockTime::from_seconds(10));
+    preroll_result.context("パイプラインのプリロールに失敗しました")?;
+
+    let to_time = |sec: f64| gst::ClockTime::from_nseconds((sec * 1_000_000_000.0) as u64);
+    let (stop_type, stop) = match range.end_sec {
+        Some(end) => (gst::SeekType::Set, to_time(end)),
+        None => (gst::SeekType::None, gst::ClockTime::ZERO),
+    };
+    pipeline
+        .seek(
+            1.0,
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+            gst::SeekType::Set,
+            to_time(range.start_sec.unwrap_or(0.0)),
+            stop_type,
+            stop,
+        )
+        .context("区間開始位置へのシークに失敗しました")?;
+    println!("  区間: {:?}秒 〜 {:?}秒", range.start_sec, range.end_sec);
+    Ok(())
+}
+
+/// バッファの PTS を解析用の固定フレームレートのフレーム番号に対応付ける
+///
+/// 可変フレームレート（VFR）の録画ではサンプルの間隔が一定でないため、
+/// サンプルを数えるだけではフレーム番号（＝入力の持続フレーム数）がずれていく。
+/// PTS から求めた番号までの欠けた分は複製し、処理済みの番号と重なるサンプルは捨てる。
+/// 最初のサンプルの PTS を開始フレームとするため、先頭の PTS が 0 でない動画でも先頭に複製は入らない。
+/// ライブ入力（[`super::live`]）でもキャプチャ開始を 0 としたフレーム番号に使う。
+pub(crate) struct FrameClock {
+    fps: f64,
+    start_frame: u32,
+    first_pts: Option<gst::ClockTime>,
+    next_frame: u32,
+}
+
+impl FrameClock {
+    /// コンテナにフレームレートが記録されていない VFR 動画で使う解析用のフレームレート
+    const FALLBACK_FPS: f64 = 60.0;
+
+    pub(crate) fn new(fps: f64, start_frame: u32) -> Self {
+        let fps = if fps > 0.0 { fps } else { Self::FALLBACK_FPS };
+        Self { fps, start_frame, first_pts: None, next_frame: start_frame }
+    }
+
+    /// このサンプルで処理するフレーム番号の範囲（空の場合はサンプルを捨てる）
+    ///
+    /// 範囲の最後がこのサンプル自身の番号で、それより前は欠けていた番号。
+    /// PTS が無い場合は連番として扱う。
+    pub(crate) fn frames_for(&mut self, pts: Option<gst::ClockTime>) -> std::ops::Range<u32> {
+        let start = self.next_frame;
+        let frame = match pts {
+            Some(pts) => {
+                let first = *self.first_pts.get_or_insert(pts);
+                let elapsed = pts.saturating_sub(first).nseconds() as f64 / 1_000_000_000.0;
+                self.start_frame + (elapsed * self.fps).round() as u32
+            }
+            None => start,
+        };
+        if frame < start {
+            return start..start;
+        }
+        self.next_frame = frame + 1;
+        start..frame + 1
+    }
+}
+
+/// scale_width / scale_height が指定されている場合は videoscale を作成し、appsink の出力サイズを設定する
+fn scale_element(config: &FrameExtractorConfig, appsink: &AppSink, source_width: u32, source_height: u32) -> Result<Option<gst::Element>> {
+    let Some((width, height)) = config.scaled_size(source_width, source_height) else {
+        return Ok(None);
+    };
+    println!("  保存サイズ: {}x{}", width, height);
+
+    appsink.set_caps(Some(
+        &gst::Caps::builder("video/x-raw")
+            .field("format", "RGB")
+            .field("width", width as i32)
+            .field("height", height as i32)
+            .build(),
+    ));
+    let videoscale = ElementFactory::make("videoscale")
+        .build()
+        .context("videoscaleの作成に失敗しました")?;
+    Ok(Some(videoscale))
+}
+
+/// last -> (videoscale ->) appsink をリンク
+fn link_to_sink(
+    pipeline: &gst::Pipeline,
+    last: &gst::Element,
+    appsink: &AppSink,
+    videoscale: Option<gst::Element>,
+) -> Result<()> {
+    match videoscale {
+        Some(videoscale) => {
+            pipeline
+                .add(&videoscale)
+                .context("videoscaleの追加に失敗しました")?;
+            last.link(&videoscale)
+                .context("videoscaleのリンクに失敗しました")?;
+            videoscale
+                .link(appsink.upcast_ref::<gst::Element>())
+                .context("videoscaleとsinkのリンクに失敗しました")?;
+        }
+        None => {
+            last.link(appsink.upcast_ref::<gst::Element>())
+                .context("sinkのリンクに失敗しました")?;
+        }
+    }
+    Ok(())
+}
+
+/// 動画ファイルの情報を取得（解像度は回転・反転の補正前の値）
+pub(super) fn get_video_info(video_path: &Path) -> Result<CustomVideoInfo> {
+    init()?;
+    
+    // ファイルの存在チェック
+    if !video_path.exists() {
+        anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
+    }
+    
+    // ファイルが読み取り可能かチェック
+    if let Err(e) = std::fs::metadata(video_path) {
+        anyhow::bail!("動画ファイルにアクセスできません: {:?} ({})", video_path, e);
+    }
+    
+    let canonical = video_path
+        .canonicalize()
+        .context("動画ファイルのパスを解決できませんでした")?;
+    let uri = url::Url::from_file_path(&canonical)
+        .map_err(|_| anyhow::anyhow!("ファイルパスからURIへの変換に失敗しました"))?
+        .to_string();
+
+    // Discovererを使って動画情報を取得
+    let discoverer = gstreamer_pbutils::Discoverer::new(gst::ClockTime::from_seconds(10))
+        .context("Discovererの作成に失敗しました")?;
+
+    let info = discoverer
+        .discover_uri(&uri)
+        .context("動画の解析に失敗しました")?;
+
+    let video_streams = info.video_streams();
+    if video_streams.is_empty() {
+        anyhow::bail!("動画ストリームが見つかりません");
+    }
+
+    let video_stream = &video_streams[0];
+    let width = video_stream.width() as i32;
+    let height = video_stream.height() as i32;
+    let fps_num = video_stream.framerate().numer() as f64;
+    let fps_den = video_stream.framerate().denom() as f64;
+    let fps = fps_num / fps_den;
+
+    let duration = info.duration();
+    let duration_sec = if let Some(dur) = duration {
+        dur.seconds() as f64
+    } else {
+        0.0
+    };
+
+    Ok(CustomVideoInfo {
+        width,
+        height,
+        fps,
+        duration_sec,
+    })
+}
+
+/// filesrc -> decodebin -> videoconvert -> (videocrop ->) (videoscale ->) appsink のパイプラインを作成する
+///
+/// crop_region を指定すると領域を切り出し、scale_width / scale_height が指定されていれば縮小してから appsink に渡す。
+fn build_extract_pipeline(
+    config: &FrameExtractorConfig,
+    source_path: &Path,
+    info: &CustomVideoInfo,
+    crop_region: Option<&crate::analyzer::InputIndicatorRegion>,
+) -> Result<(gst::Pipeline, AppSink)> {
+    let pipeline = gst::Pipeline::new();
+
+    let source = ElementFactory::make("filesrc")
+        .property("location", source_path.to_str().unwrap())
+        .build()
+        .context("filesrcの作成に失敗しました")?;
+
+    let decodebin = ElementFactory::make("decodebin")
+        .build()
+        .context("decodebinの作成に失敗しました")?;
+
+    let videoconvert = make_converter(config.transform)?;
+
+    let appsink = ElementFactory::make("appsink")
+        .build()
+        .context("appsinkの作成に失敗しました")?
+        .dynamic_cast::<AppSink>()
+        .map_err(|_| anyhow::anyhow!("appsinkへのキャストに失敗しました"))?;
+
+    appsink.set_caps(Some(
+        &gst::Caps::builder("video/x-raw")
+            .field("format", "RGB")
+            .build(),
+    ));
+    appsink.set_property("emit-signals", false);
+    appsink.set_property("sync", false);
+
+    pipeline
+        .add_many(&[&source, &decodebin, &videoconvert, appsink.upcast_ref::<gst::Element>()])
+        .context("エレメントの追加に失敗しました")?;
+
+    source
+        .link(&decodebin)
+        .context("sourceとdecoderのリンクに失敗しました")?;
+
+    // クロップ指定時は videoconvert -> videocrop -> (videoscale ->) appsink とする
+    if let Some(region) = crop_region {
+        let videocrop = ElementFactory::make("videocrop")
+            .build()
+            .context("videocrop の作成に失敗しました")?;
+
+        let right = (info.width - (region.x as i32 + region.width as i32)).max(0);
+        let bottom = (info.height - (region.y as i32 + region.height as i32)).max(0);
+        videocrop.set_property("left", region.x as i32);
+        videocrop.set_property("top", region.y as i32);
+        videocrop.set_property("right", right);
+        videocrop.set_property("bottom", bottom);
+
+        pipeline
+            .add(&videocrop)
+            .context("videocropの追加に失敗しました")?;
+        videoconvert
+            .link(&videocrop)
+            .context("converterとvideocropのリンクに失敗しました")?;
+        let videoscale = scale_element(config, &appsink, region.width, region.height)?;
+        link_to_sink(&pipeline, &videocrop, &appsink, videoscale)?;
+    } else {
+        let videoscale = scale_element(config, &appsink, info.width as u32, info.height as u32)?;
+        link_to_sink(&pipeline, &videoconvert, &appsink, videoscale)?;
+    }
+
+    // decodebinの動的パッドをリンク
+    let videoconvert_clone = videoconvert.clone();
+    decodebin.connect_pad_added(move |_dbin, pad| {
+        let sink_pad = videoconvert_clone
+            .static_pad("sink")
+            .expect("videoconvertのsinkパッドが見つかりません");
+        if !sink_pad.is_linked() {
+            if let Err(e) = pad.link(&sink_pad) {
+                eprintln!("パッドのリンクに失敗: {:?}", e);
+            }
+        }
+    });
+
+    Ok((pipeline, appsink))
+}
+
+/// 動画からフレームを抽出して保存する（進捗コールバックには保存済みの枚数を渡す）
+///
+/// 長い動画は時間区間に分割し、CPU コア数分のパイプラインで並列にデコードする。
+pub(super) fn extract_frames<F>(
+    config: &FrameExtractorConfig,
+    video_path: &Path,
+    progress_callback: Option<F>,
+    crop_region: Option<crate::analyzer::InputIndicatorRegion>,
+) -> Result<Vec<PathBuf>>
+where
+    F: Fn(usize) + Send + Sync + 'static,
+{
+    let num_segments = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    extract_frames_parallel(config, video_path, num_segments, progress_callback, crop_region)
+}
+
+// 1本のパイプラインで先頭から順にフレームを抽出する
+fn extract_frames_sequential<F>(
+    config: &FrameExtractorConfig,
+    video_path: &Path,
+    progress_callback: Option<F>,
+    crop_region: Option<crate::analyzer::InputIndicatorRegion>,
+) -> Result<Vec<PathBuf>>
+where
+    F: Fn(usize) + Send + Sync + 'static,
+{
+    init()?;
+
+    // ファイルの存在チェック
+    if !video_path.exists() {
+        anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
+    }
+    
+    // ファイルが読み取り可能かチェック
+    if let Err(e) = std::fs::metadata(video_path) {
+        anyhow::bail!("動画ファイルにアクセスできません: {:?} ({})", video_path, e);
+    }
+    
+    println!("動画ファイルを開いています: {}", video_path.display());
+
+    // 出力ディレクトリを作成
+    std::fs::create_dir_all(&config.output_dir)
+        .context("出力ディレクトリの作成に失敗しました")?;
+
+    // 動画情報を取得
+    let info = FrameExtractor::get_video_info(video_path, config.transform)?;
+    println!("動画情報:");
+    println!("  解像度: {}x{}", info.width, info.height);
+    println!("  FPS: {:.2}", info.fps);
+    println!("  再生時間: {:.2}秒", info.duration_sec);
+
+    // ファイルパスは正規化した絶対パスを使用
+    let source_path = video_path.canonicalize()?;
+    let (pipeline, appsink) = build_extract_pipeline(config, &source_path, &info, crop_region.as_ref())?;
+
+    println!("\nフレーム抽出中...");
+    println!("  抽出間隔: {}フレームごと", config.frame_interval);
+    println!("  出力先: {}", config.output_dir.display());
+
+    let output_paths = Arc::new(Mutex::new(Vec::new()));
+    let frame_count = Arc::new(Mutex::new(0u32));
+    let extracted_count = Arc::new(Mutex::new(0u32));
+
+    // 必要なフレーム数に達したら停止するためのフラグ
+    // frame_intervalが非常に大きい場合（frame 0のみ）は、1フレーム抽出後に停止
+    let should_stop = Arc::new(Mutex::new(false));
+    let target_extracts = if config.frame_interval == u32::MAX { 1 } else { u32::MAX };
+
+    let progress_callback = Arc::new(progress_callback);
+    let output_paths_clone = output_paths.clone();
+    let frame_count_clone = frame_count.clone();
+    let extracted_count_clone = extracted_count.clone();
+    let should_stop_clone = should_stop.clone();
+    let progress_callback_clone = progress_callback.clone();
+    let config = config.clone();
+    // 並列抽出の区間と同じく、ファイル名のフレーム番号は PTS から求める
+    let mut frame_clock = FrameClock::new(info.fps, 0);
+
+    // サンプルコールバックを設定
+    appsink.set_callbacks(
+        gstreamer_app::AppSinkCallbacks::builder()
+            .new_sample(move |appsink| {
+                let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Error)?;
+                let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
+                let caps = sample.caps().ok_or(gst::FlowError::Error)?;
+
+                let video_info = gstreamer_video::VideoInfo::from_caps(caps)
+                    .map_err(|_| gst::FlowError::Error)?;
+
+                let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+
+                *frame_count_clone.lock().unwrap() += 1;
+                // 処理済みの番号と重なるサンプルは捨てる（同じファイル名に上書きしない）
+                let frames = frame_clock.frames_for(buffer.pts());
+                if frames.is_empty() {
+                    return Ok(gst::FlowSuccess::Ok);
+                }
+                let current_frame = frames.end - 1;
+
+                // 指定された間隔でフレームを保存
+                if current_frame % config.frame_interval == 0 {
+                    let width = video_info.width() as u32;
+                    let height = video_info.height() as u32;
+
+                    // RGB画像として保存（stride に対応して連続バッファを作成）
+                    let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
+                    if let Some(img_buffer) =
+                        ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, contiguous)
+                    {
+                        let filename = format!("frame_{:06}.{}", current_frame, config.image_format);
+                        let output_path = config.output_dir.join(&filename);
+
+                        if let Err(e) = if config.image_format == "jpg" || config.image_format == "jpeg" {
+                            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                                std::fs::File::create(&output_path).unwrap(),
+                                config.jpeg_quality,
+                            );
+                            img_buffer.write_with_encoder(encoder)
+                        } else {
+                            img_buffer.save(&output_path)
+                        } {
+                            eprintln!("フレームの保存に失敗: {}", e);
+                        } else {
+                            let mut paths = output_paths_clone.lock().unwrap();
+                            paths.push(output_path);
+
+                            let mut extracted = extracted_count_clone.lock().unwrap();
+                            *extracted += 1;
+
+                            // 進捗コールバック呼び出し
+                            if let Some(ref callback) = *progress_callback_clone {
+                                callback(*extracted as usize);
+                            }
+
+                            if *extracted % 10 == 0 {
+                                println!("  {}フレーム抽出完了", *extracted);
+                            }
+
+                            // 必要なフレーム数に達したら停止フラグを立てる
+                            if *extracted >= target_extracts {
+                                let mut stop = should_stop_clone.lock().unwrap();
+                                *stop = true;
+                            }
+                        }
+                    }
+                }
+
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+
+    // パイプラインを開始
+    pipeline
+        .set_state(gst::State::Playing)
+        .context("パイプラインの開始に失敗しました")?;
+
+    // バスメッセージを処理
+    let bus = pipeline
+        .bus()
+        .expect("パイプラインにバスがありません");
+
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => {
+                println!("\n動画の終わりに到達しました");
+                break;
+            }
+            MessageView::Error(err) => {
+                pipeline.set_state(gst::State::Null).ok();
+                anyhow::bail!(
+                    "エラーが発生しました: {} (デバッグ情報: {:?})",
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+
+        // 必要なフレーム数に達したら停止
+        if *should_stop.lock().unwrap() {
+            println!("\n必要なフレーム数に達しました。処理を停止します。");
+            break;
+        }
+    }
+
+    // パイプラインを停止
+    pipeline
+        .set_state(gst::State::Null)
+        .context("パイプラインの停止に失敗しました")?;
+
+    let final_frame_count = *frame_count.lock().unwrap();
+    let final_extracted_count = *extracted_count.lock().unwrap();
+
+    println!("\n抽出完了!");
+    println!("  処理フレーム数: {}", final_frame_count);
+    println!("  抽出フレーム数: {}", final_extracted_count);
+
+    let paths = Arc::try_unwrap(output_paths)
+        .map(|m| m.into_inner().unwrap())
+        .unwrap_or_else(|arc| arc.lock().unwrap().clone());
+
+    Ok(paths)
+}
+
+/// 動画を時間区間に分割し、区間ごとのパイプラインで並列にフレームを抽出する
+///
+/// 各区間はシークで開始位置へ移動してからデコードし、ファイル名のフレーム番号は区間の開始フレームを起点に
+/// バッファの PTS から算出する（[`FrameClock`]。1本のパイプラインで処理した場合と同じ番号になる）。
+/// `num_segments` が1以下、または動画が短い場合は1本のパイプラインで処理する。
+fn extract_frames_parallel<F>(
+    config: &FrameExtractorConfig,
+    video_path: &Path,
+    num_segments: usize,
+    progress_callback: Option<F>,
+    crop_region: Option<crate::analyzer::InputIndicatorRegion>,
+) -> Result<Vec<PathBuf>>
+where
+    F: Fn(usize) + Send + Sync + 'static,
+{
+    init()?;
+
+    // ファイルの存在チェック
+    if !video_path.exists() {
+        anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
+    }
+
+    let info = FrameExtractor::get_video_info(video_path, config.transform)?;
+    let estimated_frames = (info.duration_sec * info.fps) as u32;
+
+    // 1区間あたり最低でも数秒分のフレームがないと分割の効果がない
+    // （抽出間隔が長く、抽出するフレームが区間数より少ない場合も分割しない）
+    let min_frames_per_segment = (info.fps * 5.0).max(1.0) as u32;
+    let num_segments = num_segments
+        .min((estimated_frames / min_frames_per_segment).max(1) as usize)
+        .min((estimated_frames / config.frame_interval).max(1) as usize);
+
+    if num_segments <= 1 || info.fps <= 0.0 {
+        return extract_frames_sequential(config, video_path, progress_callback, crop_region);
+    }
+
+    std::fs::create_dir_all(&config.output_dir)
+        .context("出力ディレクトリの作成に失敗しました")?;
+
+    let source_path = video_path.canonicalize()?;
+    let segment_len = estimated_frames / num_segments as u32;
+
+    println!("\n並列フレーム抽出中...");
+    println!("  区間数: {} (1区間あたり約{}フレーム)", num_segments, segment_len);
+    println!("  抽出間隔: {}フレームごと", config.frame_interval);
+    println!("  出力先: {}", config.output_dir.display());
+
+    // 全区間合計の抽出数で進捗を通知
+    let extracted_count = Arc::new(AtomicUsize::new(0));
+    let progress_callback = Arc::new(progress_callback);
+    let on_saved: Arc<dyn Fn() + Send + Sync> = {
+        let extracted_count = extracted_count.clone();
+        Arc::new(move || {
+            let extracted = extracted_count.fetch_add(1, Ordering::SeqCst) + 1;
+            if let Some(ref callback) = *progress_callback {
+                callback(extracted);
+            }
+        })
+    };
+
+    let segment_results: Vec<Result<Vec<PathBuf>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_segments)
+            .map(|i| {
+                let start_frame = i as u32 * segment_len;
+                // 再生時間は秒単位で切り捨てられているため、最終区間は終端まで読む
+                let end_frame = if i + 1 == num_segments {
+                    None
+                } else {
+                    Some((i as u32 + 1) * segment_len)
+                };
+                let source_path = &source_path;
+                let info = &info;
+                let crop_region = crop_region.as_ref();
+                let on_saved = on_saved.clone();
+                scope.spawn(move || {
+                    extract_segment(config, source_path, info, start_frame, end_frame, crop_region, on_saved)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .enumerate()
+            .map(|(i, handle)| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("区間{}の処理スレッドが異常終了しました", i)))
+            })
+            .collect()
+    });
+
+    // 区間順に連結（各区間内はPTS順に並んでいる）
+    let mut paths = Vec::new();
+    for (i, result) in segment_results.into_iter().enumerate() {
+        let segment_paths = result.with_context(|| format!("区間{}のフレーム抽出に失敗しました", i))?;
+        paths.extend(segment_paths);
+    }
+
+    println!("\n抽出完了!");
+    println!("  抽出フレーム数: {}", paths.len());
+
+    Ok(paths)
+}
+
+/// 指定したフレーム区間 [start_frame, end_frame) をデコードして保存
+///
+/// end_frame が None の場合は動画の終端まで処理する
+fn extract_segment(
+    config: &FrameExtractorConfig,
+    source_path: &Path,
+    info: &CustomVideoInfo,
+    start_frame: u32,
+    end_frame: Option<u32>,
+    crop_region: Option<&crate::analyzer::InputIndicatorRegion>,
+    on_saved: Arc<dyn Fn() + Send + Sync>,
+) -> Result<Vec<PathBuf>> {
+    let (pipeline, appsink) = build_extract_pipeline(config, source_path, info, crop_region)?;
+
+    let output_paths = Arc::new(Mutex::new(Vec::new()));
+    let should_stop = Arc::new(Mutex::new(false));
+
+    let output_paths_clone = output_paths.clone();
+    let should_stop_clone = should_stop.clone();
+    let config = config.clone();
+    // シーク後の最初のサンプルを区間の開始フレームとして PTS から番号を求める
+    let mut frame_clock = FrameClock::new(info.fps, start_frame);
+
+    appsink.set_callbacks(
+        gstreamer_app::AppSinkCallbacks::builder()
+            .new_sample(move |appsink| {
+                let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Error)?;
+                let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
+                let caps = sample.caps().ok_or(gst::FlowError::Error)?;
+
+                // PTSからフレーム番号を算出（処理済みの番号と重なるサンプルは捨てる）
+                let frames = frame_clock.frames_for(buffer.pts());
+                if frames.is_empty() {
+                    return Ok(gst::FlowSuccess::Ok);
+                }
+                let current_frame = frames.end - 1;
+                // 区間の終端に達したら停止
+                if let Some(end) = end_frame {
+                    if current_frame >= end {
+                        *should_stop_clone.lock().unwrap() = true;
+                        return Err(gst::FlowError::Eos);
+                    }
+                }
+                if current_frame % config.frame_interval != 0 {
+                    return Ok(gst::FlowSuccess::Ok);
+                }
+
+                let video_info = gstreamer_video::VideoInfo::from_caps(caps)
+                    .map_err(|_| gst::FlowError::Error)?;
+                let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+
+                let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
+                if let Some(img_buffer) = ImageBuffer::<Rgb<u8>, _>::from_raw(
+                    video_info.width(),
+                    video_info.height(),
+                    contiguous,
+                ) {
+                    let filename = format!("frame_{:06}.{}", current_frame, config.image_format);
+                    let output_path = config.output_dir.join(&filename);
+
+                    if let Err(e) = save_frame_image(&config, &img_buffer, &output_path) {
+                        eprintln!("フレームの保存に失敗: {}", e);
+                    } else {
+                        output_paths_clone.lock().unwrap().push(output_path);
+                        on_saved();
+                    }
+                }
+
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+
+    // プリロールしてからシークする（シークは PAUSED 以上でないと効かない）
+    pipeline
+        .set_state(gst::State::Paused)
+        .context("パイプラインの開始に失敗しました")?;
+    let (preroll_result, _, _) = pipeline.state(gst::ClockTime::from_seconds(10));
+    preroll_result.context("パイプラインのプリロールに失敗しました")?;
+
+    // 半フレーム手前から開始し、区間終端の半フレーム手前で停止する
+    let frame_to_time = |frame: f64| {
+        gst::ClockTime::from_nseconds((frame.max(0.0) / info.fps * 1_000_000_000.0) as u64)
+    };
+    let start_time = frame_to_time(start_frame as f64 - 0.5);
+    let seek_flags = gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE;
+    match end_frame {
+        Some(end) => pipeline.seek(
+            1.0,
+            seek_flags,
+            gst::SeekType::Set,
+            start_time,
+            gst::SeekType::Set,
+            frame_to_time(end as f64 - 0.5),
+        ),
+        None => pipeline.seek_simple(seek_flags, start_time),
+    }
+    .context("区間開始位置へのシークに失敗しました")?;
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .context("パイプラインの開始に失敗しました")?;
+
+    let bus = pipeline
+        .bus()
+        .expect("パイプラインにバスがありません");
+
+    // 停止フラグも確認できるようタイムアウト付きでメッセージを待つ
+    while !*should_stop.lock().unwrap() {
+        let msg = match bus.timed_pop(gst::ClockTime::from_mseconds(100)) {
+            Some(msg) => msg,
+            None => continue,
+        };
+
+        use gst::MessageView;
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => {
+                pipeline.set_state(gst::State::Null).ok();
+                anyhow::bail!(
+                    "エラーが発生しました: {} (デバッグ情報: {:?})",
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+
+    pipeline
+        .set_state(gst::State::Null)
+        .context("パイプラインの停止に失敗しました")?;
+
+    let paths = output_paths.lock().unwrap().clone();
+    Ok(paths)
+}
+
+/// 動画から frame_interval ごとのフレームを保存し、保存したパスをコールバックに渡す
+pub(super) fn save_frames<F>(config: &FrameExtractorConfig, video_path: &Path, callback: F) -> Result<()>
+where
+    F: FnMut(PathBuf) -> Result<()> + Send + 'static,
+{
+    init()?;
+
+    // ファイルの存在チェック
+    if !video_path.exists() {
+        anyhow::bail!("動画ファイルが見つかりません: {:?}", video_path);
+    }
+    
+    // ファイルが読み取り可能かチェック
+    if let Err(e) = std::fs::metadata(video_path) {
+        anyhow::bail!("動画ファイルにアクセスできません: {:?} ({})", video_path, e);
+    }
+    
+    println!("動画ファイルを開いています: {}", video_path.display());
+
+    // 出力ディレクトリを作成
+    std::fs::create_dir_all(&config.output_dir)
+        .context("出力ディレクトリの作成に失敗しました")?;
+
+    // 動画情報を取得
+    let info = FrameExtractor::get_video_info(video_path, config.transform)?;
+    println!("動画情報:");
+    println!("  解像度: {}x{}", info.width, info.height);
+    println!("  FPS: {:.2}", info.fps);
+    println!("  再生時間: {:.2}秒", info.duration_sec);
+
+    // GStreamerパイプラインを構築
+    let source_path = video_path.canonicalize()?;
+    let (pipeline, appsink) = build_extract_pipeline(config, &source_path, &info, None)?;
+
+    let frame_count = Arc::new(Mutex::new(0u32));
+    let extracted_count = Arc::new(Mutex::new(0u32));
+    let callback_error = Arc::new(Mutex::new(None::<String>));
+    let callback = Arc::new(Mutex::new(callback));
+
+    let frame_count_clone = frame_count.clone();
+    let extracted_count_clone = extracted_count.clone();
+    let callback_error_clone = callback_error.clone();
+    let callback_clone = callback.clone();
+    let config = config.clone();
+
+    appsink.set_callbacks(
+        gstreamer_app::AppSinkCallbacks::builder()
+            .new_sample(move |appsink| {
+                // エラーが既に発生していたら処理を中断
+                if callback_error_clone.lock().unwrap().is_some() {
+                    return Err(gst::FlowError::Error);
+            }
+
+                let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Error)?;
+                let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
+                let caps = sample.caps().ok_or(gst::FlowError::Error)?;
+
+                let video_info = gstreamer_video::VideoInfo::from_caps(caps)
+                    .map_err(|_| gst::FlowError::Error)?;
+
+                let map = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
+
+                let mut frame_num = frame_count_clone.lock().unwrap();
+                let current_frame = *frame_num;
+                *frame_num += 1;
+
+                if current_frame % config.frame_interval == 0 {
+                    let width = video_info.width() as u32;
+                    let height = video_info.height() as u32;
+
+                    let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
+                    let img = image::RgbImage::from_raw(width, height, contiguous)
+                        .ok_or(gst::FlowError::Error)?;
+
+                    let output_filename = format!("frame_{:08}.{}", current_frame, config.image_format);
+                    let output_path = config.output_dir.join(&output_filename);
+
+                    if let Err(e) = img.save(&output_path) {
+                        eprintln!("画像保存エラー: {}", e);
+                        return Err(gst::FlowError::Error);
+                    }
+
+                    let mut extracted = extracted_count_clone.lock().unwrap();
+                    *extracted += 1;
+
+                    // コールバックを呼び出し
+                    let result = {
+                        let mut cb = callback_clone.lock().unwrap();
+                        cb(output_path)
+                    };
+
+                    if let Err(e) = result {
+                        *callback_error_clone.lock().unwrap() = Some(format!("コールバックエラー: {}", e));
+                        return Err(gst::FlowError::Error);
+                    }
+                }
+
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+
+    pipeline.set_state(gst::State::Playing)
+        .context("パイプラインの開始に失敗しました")?;
+
+    let bus = pipeline.bus().expect("パイプラインにバスがありません");
+
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => {
+                break;
+            }
+            MessageView::Error(err) => {
+                pipeline.set_state(gst::State::Null).ok();
+                anyhow::bail!(
+                    "エラーが発生しました: {} (デバッグ情報: {:?})",
+                    err.error(),
+                    err.debug()
+                );
+            }
+            _ => (),
+        }
+    }
+
+    pipeline.set_state(gst::State::Null)
+        .context("パイプラインの停止に失敗しました")?;
+
+    // コールバックでエラーが発生していたら返す
+    if let Some(error) = callback_error.lock().unwrap().take() {
+        anyhow::bail!(error);
+    }
+
+    let final_frame_count = *frame_count.lock().unwrap();
+    let final_extracted_count = *extracted_count.lock().unwrap();
+
+    println!("\n抽出完了!");
+    println!("  処理フレーム数: {}", final_frame_count);
+    println!("  抽出フレーム数: {}", final_extracted_count);
+
+    Ok(())
+}
+
+/// 動画の指定区間をクロップしてフレームを同期的に処理する
+pub(super) fn process_frames_sync_in_range<F>(
+    config: &FrameExtractorConfig,
+    video_path: &Path,
+    crop_region: Option<crate::analyzer::InputIndicatorRegion>,
+    range: TimeRange,
+    mut callback: F,
+) -> Result<()>
+where
+    F: FnMut(&image::RgbImage, u32) -> Result<()>,
+{
+    init()?;
+
+    println!("動画ファイルを開いています: {}", video_path.display());
+
+    // 動画情報を取得
+    let info = FrameExtractor::get_video_info(video_path, config.transform)?;
+    println!("動画情報:");
+    println!("  解像度: {}x{}", info.width, info.height);
+    println!("  FPS: {:.2}", info.fps);
+    println!("  再生時間: {:.2}秒", info.duration_sec);
+
+    // GStreamerパイプラインを構築（コールバックが処理するまで次のフレームを溜めない）
+    let (pipeline, appsink) = build_extract_pipeline(config, video_path, &info, crop_region.as_ref())?;
+    appsink.set_property("max-buffers", 1u32);
+
+    seek_to_range(&pipeline, range)?;
+
+    pipeline.set_state(gst::State::Playing)
+        .context("パイプラインの開始に失敗しました")?;
+
+    let bus = pipeline.bus().expect("パイプラインにバスがありません");
+    let mut frame_count = range.start_frame(info.fps);
+    let mut frame_clock = FrameClock::new(info.fps, frame_count);
+    let mut previous_img: Option<image::RgbImage> = None;
+    let mut processed_count = 0u32;
+
+    // フレームを同期的に処理
+    loop {
+        // バスメッセージを確認
+        if let Some(msg) = bus.pop() {
+            use gst::MessageView;
+            match msg.view() {
+                MessageView::Eos(..) => {
+                    break;
+                }
+                MessageView::Error(err) => {
+                    pipeline.set_state(gst::State::Null).ok();
+                    anyhow::bail!(
+                        "エラーが発生しました: {} (デバッグ情報: {:?})",
+                        err.error(),
+                        err.debug()
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        // フレームを取得（非ブロッキング）
+        if let Some(sample) = appsink.try_pull_sample(gst::ClockTime::from_mseconds(100)) {
+            let buffer = sample.buffer().context("バッファの取得に失敗しました")?;
+            let caps = sample.caps().context("capsの取得に失敗しました")?;
+
+            let video_info = gstreamer_video::VideoInfo::from_caps(caps)
+                .context("VideoInfoの作成に失敗しました")?;
+
+            let map = buffer.map_readable().context("バッファのマップに失敗しました")?;
+
+            // PTS から固定フレームレート上のフレーム番号を求める（VFR 対応）
+            let frames = frame_clock.frames_for(buffer.pts());
+            if frames.is_empty() {
+                continue;
+            }
+            frame_count = frames.end;
+
+            let width = video_info.width() as u32;
+            let height = video_info.height() as u32;
+
+            let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
+            let img = image::RgbImage::from_raw(width, height, contiguous)
+                .context("RgbImageの作成に失敗しました")?;
+
+            let last_frame = frames.end - 1;
+            for current_frame in frames {
+                if current_frame % config.frame_interval != 0 {
+                    continue;
+                }
+                // 欠けていたフレーム番号は直前のサンプルの画像で埋める
+                let frame_img = match &previous_img {
+                    Some(previous) if current_frame != last_frame => previous,
+                    _ => &img,
+                };
+
+                // コールバックを同期的に呼び出し（同じスレッド内）
+                // エラー（キャンセルを含む）の場合もパイプラインを停止してから抜ける
+                if let Err(e) = callback(frame_img, current_frame) {
+                    pipeline.set_state(gst::State::Null).ok();
+                    return Err(e);
+                }
+
+                processed_count += 1;
+
+                if processed_count % 30 == 0 {
+                    println!("処理済み: {}フレーム", processed_count);
+                }
+            }
+            previous_img = Some(img);
+        }
+    }
+
+    pipeline.set_state(gst::State::Null)
+        .context("パイプラインの停止に失敗しました")?;
+
+    println!("\n処理完了!");
+    println!("  総フレーム数: {}", frame_count);
+    println!("  処理フレーム数: {}", processed_count);
+
+    Ok(())
+}
+
+/// 指定フレームの画像を取得する
+///
+/// 目的のフレームの直前のキーフレームにシークし、そこから PTS を見ながら
+/// 目的のフレームまでデコードを進める
+pub(super) fn extract_frame(config: &FrameExtractorConfig, video_path: &Path, frame_number: u32) -> Result<image::RgbImage> {
+    init()?;
+
+    let info = FrameExtractor::get_video_info(video_path, config.transform)?;
+
+    let pipeline = gst::Pipeline::default();
+
+    let src = ElementFactory::make("filesrc")
+        .name("src")
+        .property("location", video_path.to_str().unwrap())
+        .build()?;
+
+    let decodebin = ElementFactory::make("decodebin")
+        .name("decoder")
+        .build()?;
+
+    let videoconvert = make_converter(config.transform)?;
+
+    let appsink = AppSink::builder()
+        .name("sink")
+        .caps(
+            &gst::Caps::builder("video/x-raw")
+                .field("format", "RGB")
+                .build(),
+        )
+        .build();
+    appsink.set_property("sync", false);
+
+    pipeline.add_many([&src, &decodebin, &videoconvert, appsink.upcast_ref()])?;
+    src.link(&decodebin)?;
+    videoconvert.link(&appsink)?;
+
+    let videoconvert_weak = videoconvert.downgrade();
+    decodebin.connect_pad_added(move |_, src_pad| {
+        let Some(videoconvert) = videoconvert_weak.upgrade() else {
+            return;
+        };
+
+        let sink_pad = videoconvert.static_pad("sink").expect("sink pad");
+        if sink_pad.is_linked() {
+            return;
+        }
+
+        if let Err(e) = src_pad.link(&sink_pad) {
+            eprintln!("Failed to link pads: {}", e);
+        }
+    });
+
+    let result = seek_and_decode_frame(&pipeline, &appsink, info.fps, frame_number);
+
+    // パイプラインを確実に停止・解放
+    pipeline.set_state(gst::State::Null)?;
+
+    result
+}
+
+/// プリロール済みのパイプラインを frame_number 直前のキーフレームにシークし、
+/// 目的のフレームに到達するまでデコードを進める
+fn seek_and_decode_frame(
+    pipeline: &gst::Pipeline,
+    appsink: &AppSink,
+    fps: f64,
+    frame_number: u32,
+) -> Result<image::RgbImage> {
+    // プリロールしてからシークする（シークは PAUSED 以上でないと効かない）
+    pipeline
+        .set_state(gst::State::Paused)
+        .context("パイプラインの開始に失敗しました")?;
+    let (preroll_result, _, _) = pipeline.state(gst::ClockTime::from_seconds(10));
+    preroll_result.context("パイプラインのプリロールに失敗しました")?;
+
+    // 半フレーム手前の時刻を指定し、それより前のキーフレームから再生する
+    let target_time = gst::ClockTime::from_nseconds(
+        ((frame_number as f64 - 0.5).max(0.0) / fps * 1_000_000_000.0) as u64,
+    );
+    pipeline
+        .seek_simple(
+            gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT | gst::SeekFlags::SNAP_BEFORE,
+            target_time,
+        )
+        .context("キーフレームへのシークに失敗しました")?;
+    pipeline
+        .set_state(gst::State::Playing)
+        .context("パイプラインの開始に失敗しました")?;
+
+    let bus = pipeline.bus().expect("パイプラインにバスがありません");
+
+    // タイムアウトを設定（10秒）
+    let timeout = std::time::Duration::from_secs(10);
+    let start_time = std::time::Instant::now();
+
+    loop {
+        if start_time.elapsed() > timeout {
+            anyhow::bail!("フレーム抽出がタイムアウトしました");
+        }
+
+        // バスメッセージを処理
+        while let Some(msg) = bus.pop() {
+            use gst::MessageView;
+
+            if let MessageView::Error(err) = msg.view() {
+                anyhow::bail!(
+                    "エラー: {} (デバッグ: {:?})",
+                    err.error(),
+                    err.debug()
+                );
+            }
+        }
+
+        let Some(sample) = appsink.try_pull_sample(gst::ClockTime::from_mseconds(100)) else {
+            if appsink.is_eos() {
+                anyhow::bail!("指定されたフレームが見つかりませんでした");
+            }
+            continue;
+        };
+
+        let buffer = sample.buffer().ok_or_else(|| anyhow::anyhow!("バッファなし"))?;
+
+        // PTSからフレーム番号を算出し、キーフレームから目的のフレームまでは捨てる
+        let current_frame = match buffer.pts() {
+            Some(pts) => (pts.nseconds() as f64 * fps / 1_000_000_000.0).round() as u32,
+            None => continue,
+        };
+        if current_frame < frame_number {
+            continue;
+        }
+
+        let caps = sample.caps().ok_or_else(|| anyhow::anyhow!("キャプスなし"))?;
+        let video_info = gstreamer_video::VideoInfo::from_caps(caps)?;
+
+        let map = buffer.map_readable().map_err(|_| anyhow::anyhow!("マップ失敗"))?;
+        let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
+        return image::RgbImage::from_raw(video_info.width(), video_info.height(), contiguous)
+            .ok_or_else(|| anyhow::anyhow!("フレーム画像の作成に失敗しました"));
+    }
+}
+
+
+/// 一時停止状態のまま任意の位置へシークしてフレームを取り出すパイプライン（プレビュー・サムネイル用）
+///
+/// scale を指定すると videoscale でその大きさに縮小したフレームを返す。ドロップすると解放する。
+pub(super) struct PausedPipeline {
+    pipeline: gst::Pipeline,
+    appsink: AppSink,
+}
+
+impl PausedPipeline {
+    /// シーク・プリロールの待ち時間の上限
+    const PREROLL_TIMEOUT_SECS: u64 = 10;
+
+    pub(super) fn open(video_path: &Path, transform: VideoTransform, scale: Option<(u32, u32)>) -> Result<Self> {
+        init()?;
+        let pipeline = gst::Pipeline::default();
+
+        let src = ElementFactory::make("filesrc")
+            .property("location", video_path.to_str().unwrap())
+            .build()
+            .context("filesrcの作成に失敗しました")?;
+
+        let decodebin = ElementFactory::make("decodebin")
+            .build()
+            .context("decodebinの作成に失敗しました")?;
+
+        let videoconvert = make_converter(transform)?;
+
+        let mut caps = gst::Caps::builder("video/x-raw").field("format", "RGB");
+        if let Some((width, height)) = scale {
+            caps = caps.field("width", width as i32).field("height", height as i32);
+        }
+        let appsink = AppSink::builder().caps(&caps.build()).build();
+        appsink.set_property("sync", false);
+
+        pipeline.add_many([&src, &decodebin, &videoconvert, appsink.upcast_ref()])?;
+        src.link(&decodebin)?;
+        if scale.is_some() {
+            let videoscale = ElementFactory::make("videoscale")
+                .build()
+                .context("videoscaleの作成に失敗しました")?;
+            pipeline.add(&videoscale)?;
+            gst::Element::link_many([&videoconvert, &videoscale, appsink.upcast_ref()])?;
+        } else {
+            videoconvert.link(&appsink)?;
+        }
+
+        let videoconvert_weak = videoconvert.downgrade();
+        decodebin.connect_pad_added(move |_, src_pad| {
+            let Some(videoconvert) = videoconvert_weak.upgrade() else {
+                return;
+            };
+
+            let sink_pad = videoconvert.static_pad("sink").expect("sink pad");
+            if sink_pad.is_linked() {
+                return;
+            }
+
+            if let Err(e) = src_pad.link(&sink_pad) {
+                eprintln!("パッドのリンクに失敗: {}", e);
+            }
+        });
+
+        let paused = Self { pipeline, appsink };
+        paused
+            .pipeline
+            .set_state(gst::State::Paused)
+            .context("パイプラインの開始に失敗しました")?;
+        paused.wait_preroll()?;
+        Ok(paused)
+    }
+
+    /// frame_number の位置にシークし、取り出したフレームの番号と画像を返す
+    ///
+    /// accurate が false の場合は正確な位置までデコードせず、最寄りのキーフレームを返す。
+    pub(super) fn frame(&self, frame_number: u32, fps: f64, accurate: bool) -> Result<(u32, image::RgbImage)> {
+        let time = gst::ClockTime::from_nseconds((frame_number as f64 / fps * 1_000_000_000.0) as u64);
+        let flags = if accurate {
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE
+        } else {
+            gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT | gst::SeekFlags::SNAP_NEAREST
+        };
+        self.pipeline.seek_simple(flags, time).context("シークに失敗しました")?;
+        self.wait_preroll()?;
+
+        let sample = self
+            .appsink
+            .try_pull_preroll(gst::ClockTime::from_seconds(Self::PREROLL_TIMEOUT_SECS))
+            .ok_or_else(|| anyhow::anyhow!("フレームを取得できませんでした"))?;
+        let buffer = sample.buffer().ok_or_else(|| anyhow::anyhow!("バッファなし"))?;
+        let caps = sample.caps().ok_or_else(|| anyhow::anyhow!("キャプスなし"))?;
+        let video_info = gstreamer_video::VideoInfo::from_caps(caps)?;
+
+        let pulled_frame = buffer
+            .pts()
+            .map(|pts| (pts.nseconds() as f64 * fps / 1_000_000_000.0).round() as u32)
+            .unwrap_or(frame_number);
+        let map = buffer.map_readable().map_err(|_| anyhow::anyhow!("マップ失敗"))?;
+        let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
+        let image = image::RgbImage::from_raw(video_info.width(), video_info.height(), contiguous)
+            .ok_or_else(|| anyhow::anyhow!("フレーム画像の作成に失敗しました"))?;
+        Ok((pulled_frame, image))
+    }
+
+    fn wait_preroll(&self) -> Result<()> {
+        let (result, _, _) = self
+            .pipeline
+            .state(gst::ClockTime::from_seconds(Self::PREROLL_TIMEOUT_SECS));
+        result.context("パイプラインのプリロールに失敗しました")?;
+        Ok(())
+    }
+}
+
+impl Drop for PausedPipeline {
+    fn drop(&mut self) {
+        self.pipeline.set_state(gst::State::Null).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_clock_maps_vfr_timestamps() {
+        let ms = gst::ClockTime::from_mseconds;
+        let mut clock = FrameClock::new(60.0, 0);
+        assert_eq!(clock.frames_for(Some(ms(0))), 0..1);
+        assert_eq!(clock.frames_for(Some(ms(17))), 1..2);
+        // 3フレーム分空いたサンプルは欠けた番号を含む
+        assert_eq!(clock.frames_for(Some(ms(67))), 2..5);
+        // 同じ番号に重なるサンプルは捨てる
+        assert!(clock.frames_for(Some(ms(70))).is_empty());
+        assert_eq!(clock.frames_for(Some(ms(83))), 5..6);
+        // PTS が無い場合は連番
+        assert_eq!(clock.frames_for(None), 6..7);
+
+        // 最初のサンプルの PTS が開始フレームになる（先頭に複製を入れない）
+        let mut clock = FrameClock::new(60.0, 600);
+        assert_eq!(clock.frames_for(Some(ms(10_033))), 600..601);
+        assert_eq!(clock.frames_for(Some(ms(10_050))), 601..602);
+    }
+}
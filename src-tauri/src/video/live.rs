@@ -0,0 +1,282 @@
+//! キャプチャデバイス（Webカメラ・キャプチャボード）や画面キャプチャからのライブ入力
+//!
+//! filesrc の代わりにキャプチャデバイス・画面キャプチャのソース要素からパイプラインを作り、
+//! 届いたフレームを順にコールバックへ渡す。ゲーム機の映像や同じPCで動かしているゲームの画面を
+//! 録画せずにその場で解析するために使う。
+
+use anyhow::Result;
+use serde::Serialize;
+#[cfg(feature = "gstreamer")]
+use anyhow::Context;
+#[cfg(feature = "gstreamer")]
+use gstreamer::prelude::*;
+#[cfg(feature = "gstreamer")]
+use gstreamer::{self as gst, ElementFactory};
+#[cfg(feature = "gstreamer")]
+use gstreamer_app::AppSink;
+
+#[cfg(feature = "gstreamer")]
+use super::gstreamer_backend::{plane_to_contiguous_rgb, FrameClock};
+use crate::analyzer::InputIndicatorRegion;
+use crate::jobs::CancellationToken;
+
+/// フレームを待つ時間の上限（この間隔でキャンセルとエラーを確認する）
+#[cfg(feature = "gstreamer")]
+const PULL_TIMEOUT_MS: u64 = 200;
+
+// ライブ入力は GStreamer のソース要素を使うため、gstreamer feature が無効なビルドでは使えない
+#[cfg(not(feature = "gstreamer"))]
+const UNAVAILABLE_MESSAGE: &str = "ライブ入力には GStreamer が必要です（gstreamer feature を有効にしてビルドしてください）";
+
+/// ライブ入力の取得元
+#[derive(Debug, Clone)]
+pub enum LiveSource {
+    /// キャプチャデバイス（`list_capture_devices` の番号）
+    CaptureDevice { device_index: u32 },
+    /// 画面キャプチャ（モニター番号は 0 始まり）
+    Screen { monitor_index: u32 },
+}
+
+/// キャプチャデバイスの情報
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureDeviceInfo {
+    pub index: u32,
+    pub name: String,
+}
+
+#[cfg(feature = "gstreamer")]
+fn video_source_devices() -> Result<Vec<gst::Device>> {
+    gst::init().context("GStreamerの初期化に失敗しました")?;
+    let monitor = gst::DeviceMonitor::new();
+    monitor.add_filter(Some("Video/Source"), None);
+    monitor.start().context("キャプチャデバイスの列挙に失敗しました")?;
+    let devices = monitor.devices().into_iter().collect();
+    monitor.stop();
+    Ok(devices)
+}
+
+/// 接続されているキャプチャデバイスの一覧
+#[cfg(feature = "gstreamer")]
+pub fn list_capture_devices() -> Result<Vec<CaptureDeviceInfo>> {
+    Ok(video_source_devices()?
+        .iter()
+        .enumerate()
+        .map(|(index, device)| CaptureDeviceInfo {
+            index: index as u32,
+            name: device.display_name().to_string(),
+        })
+        .collect())
+}
+
+// デバイスのソース要素を作成する
+// デバイスモニタで見つからない場合は、プラットフォームのソース要素をデバイス番号で開く
+#[cfg(feature = "gstreamer")]
+fn capture_element(device_index: u32) -> Result<gst::Element> {
+    if let Some(device) = video_source_devices()?.get(device_index as usize) {
+        println!("[Live] キャプチャデバイス: {}", device.display_name());
+        return device
+            .create_element(None)
+            .context("キャプチャデバイスのソース要素の作成に失敗しました");
+    }
+
+    #[cfg(windows)]
+    let candidates = ["mfvideosrc", "ksvideosrc"];
+    #[cfg(target_os = "macos")]
+    let candidates = ["avfvideosrc"];
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let candidates = ["v4l2src"];
+
+    for name in candidates {
+        let Ok(element) = ElementFactory::make(name).build() else {
+            continue;
+        };
+        if name == "v4l2src" {
+            element.set_property("device", format!("/dev/video{}", device_index));
+        } else {
+            element.set_property("device-index", device_index as i32);
+        }
+        println!("[Live] キャプチャデバイス: {} (device-index={})", name, device_index);
+        return Ok(element);
+    }
+    anyhow::bail!("キャプチャデバイスが見つかりません: {}", device_index)
+}
+
+// 画面キャプチャのソース要素を作成する
+// Windows では d3d11screencapturesrc（Desktop Duplication API）を優先し、無ければ dxgiscreencapsrc を使う
+#[cfg(feature = "gstreamer")]
+fn screen_element(monitor_index: u32) -> Result<gst::Element> {
+    #[cfg(windows)]
+    let candidates = ["d3d11screencapturesrc", "dxgiscreencapsrc"];
+    #[cfg(target_os = "macos")]
+    let candidates = ["avfvideosrc"];
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let candidates = ["ximagesrc"];
+
+    for name in candidates {
+        let Ok(element) = ElementFactory::make(name).build() else {
+            continue;
+        };
+        match name {
+            "d3d11screencapturesrc" => element.set_property("monitor-index", monitor_index as i32),
+            "dxgiscreencapsrc" => element.set_property("monitor", monitor_index as i32),
+            "avfvideosrc" => {
+                element.set_property("capture-screen", true);
+                element.set_property("device-index", monitor_index as i32);
+            }
+            // X11 では画面番号ではなくディスプレイ全体を取り込む
+            _ => element.set_property("use-damage", false),
+        }
+        println!("[Live] 画面キャプチャ: {} (モニター {})", name, monitor_index);
+        return Ok(element);
+    }
+    anyhow::bail!("画面キャプチャの要素が見つかりません（GStreamer のプラグインを確認してください）")
+}
+
+#[cfg(feature = "gstreamer")]
+fn source_element(source: &LiveSource) -> Result<gst::Element> {
+    match source {
+        LiveSource::CaptureDevice { device_index } => capture_element(*device_index),
+        LiveSource::Screen { monitor_index } => screen_element(*monitor_index),
+    }
+}
+
+/// ライブ入力のフレームをキャンセルされるまでコールバックで処理する
+///
+/// crop_region を指定した場合は切り出した画像を渡す。
+/// 解析が追いつかない場合は古いフレームを捨てて最新のフレームを処理する。フレーム番号はバッファの PTS から
+/// 求めたキャプチャ開始からの番号のため、捨てたフレームも数に含まれる（持続フレーム数が実時間と合う）。
+/// 動画ファイルと違い、回転・反転の補正は適用しない。
+/// キャンセルされた場合は正常終了として扱い、処理したフレーム数を返す。
+#[cfg(feature = "gstreamer")]
+pub fn process_live_frames<F>(
+    source: &LiveSource,
+    crop_region: Option<&InputIndicatorRegion>,
+    token: &CancellationToken,
+    mut callback: F,
+) -> Result<u32>
+where
+    F: FnMut(&image::RgbImage, u32) -> Result<()>,
+{
+    let src = source_element(source)?;
+
+    let pipeline = gst::Pipeline::default();
+
+    let videoconvert = ElementFactory::make("videoconvert")
+        .build()
+        .context("videoconvertの作成に失敗しました")?;
+
+    let appsink = AppSink::builder()
+        .caps(
+            &gst::Caps::builder("video/x-raw")
+                .field("format", "RGB")
+                .build(),
+        )
+        .max_buffers(2)
+        .drop(true)
+        .build();
+    appsink.set_property("sync", false);
+
+    pipeline.add_many([&src, &videoconvert, appsink.upcast_ref()])?;
+    gst::Element::link_many([&src, &videoconvert, appsink.upcast_ref()])?;
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .context("キャプチャの開始に失敗しました")?;
+    println!("[Live] キャプチャを開始しました");
+
+    let result = pull_frames(&pipeline, &appsink, crop_region, token, &mut callback);
+    pipeline.set_state(gst::State::Null).ok();
+    println!("[Live] キャプチャを終了しました");
+    result
+}
+
+/// 接続されているキャプチャデバイスの一覧
+#[cfg(not(feature = "gstreamer"))]
+pub fn list_capture_devices() -> Result<Vec<CaptureDeviceInfo>> {
+    anyhow::bail!(UNAVAILABLE_MESSAGE)
+}
+
+/// ライブ入力のフレームをキャンセルされるまでコールバックで処理する
+#[cfg(not(feature = "gstreamer"))]
+pub fn process_live_frames<F>(
+    _source: &LiveSource,
+    _crop_region: Option<&InputIndicatorRegion>,
+    _token: &CancellationToken,
+    _callback: F,
+) -> Result<u32>
+where
+    F: FnMut(&image::RgbImage, u32) -> Result<()>,
+{
+    anyhow::bail!(UNAVAILABLE_MESSAGE)
+}
+
+#[cfg(feature = "gstreamer")]
+fn pull_frames<F>(
+    pipeline: &gst::Pipeline,
+    appsink: &AppSink,
+    crop_region: Option<&InputIndicatorRegion>,
+    token: &CancellationToken,
+    callback: &mut F,
+) -> Result<u32>
+where
+    F: FnMut(&image::RgbImage, u32) -> Result<()>,
+{
+    let bus = pipeline.bus().expect("パイプラインのバスがありません");
+    let mut frame_count = 0u32;
+    // 最初のバッファのフレームレートで作る（報告しないデバイスでは FrameClock の既定値）
+    let mut clock: Option<FrameClock> = None;
+
+    while !token.is_cancelled() {
+        // デバイスの切断などのエラーを確認
+        if let Some(msg) = bus.pop_filtered(&[gst::MessageType::Error, gst::MessageType::Eos]) {
+            match msg.view() {
+                gst::MessageView::Error(err) => {
+                    anyhow::bail!("キャプチャエラー: {} ({:?})", err.error(), err.debug())
+                }
+                _ => anyhow::bail!("キャプチャデバイスからの入力が終了しました"),
+            }
+        }
+
+        let Some(sample) = appsink.try_pull_sample(gst::ClockTime::from_mseconds(PULL_TIMEOUT_MS)) else {
+            continue;
+        };
+        let buffer = sample.buffer().ok_or_else(|| anyhow::anyhow!("バッファなし"))?;
+        let caps = sample.caps().ok_or_else(|| anyhow::anyhow!("キャプスなし"))?;
+        let video_info = gstreamer_video::VideoInfo::from_caps(caps)?;
+
+        let map = buffer.map_readable().map_err(|_| anyhow::anyhow!("マップ失敗"))?;
+        let contiguous = plane_to_contiguous_rgb(&video_info, map.as_slice());
+        let img = image::RgbImage::from_raw(video_info.width(), video_info.height(), contiguous)
+            .ok_or_else(|| anyhow::anyhow!("フレーム画像の作成に失敗しました"))?;
+
+        let img = match crop_region {
+            Some(region) => {
+                if region.x + region.width > img.width() || region.y + region.height > img.height() {
+                    anyhow::bail!(
+                        "入力表示の領域がキャプチャ映像（{}x{}）の範囲外です",
+                        img.width(),
+                        img.height()
+                    );
+                }
+                image::imageops::crop_imm(&img, region.x, region.y, region.width, region.height).to_image()
+            }
+            None => img,
+        };
+
+        let clock = clock.get_or_insert_with(|| {
+            let fps = video_info.fps();
+            let fps = if fps.denom() > 0 { fps.numer() as f64 / fps.denom() as f64 } else { 0.0 };
+            FrameClock::new(fps, 0)
+        });
+        // 処理済みのフレーム番号と重なるバッファは捨てる（間のフレームが落ちた分は番号を飛ばす）
+        let frames = clock.frames_for(buffer.pts());
+        if frames.is_empty() {
+            continue;
+        }
+        let frame_num = frames.end - 1;
+        callback(&img, frame_num)?;
+        frame_count = frame_num + 1;
+    }
+
+    Ok(frame_count)
+}
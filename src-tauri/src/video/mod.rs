@@ -1,3 +1,177 @@
 pub mod frame_extractor;
+pub mod preview;
+pub mod live;
+pub mod frame_source;
+pub mod thumbnails;
+#[cfg(feature = "gstreamer")]
+mod gstreamer_backend;
+#[cfg(feature = "ffmpeg")]
+mod ffmpeg;
 
-pub use frame_extractor::{FrameExtractor, FrameExtractorConfig, CustomVideoInfo};
+pub use frame_extractor::{crop_rgb_rows, FrameExtractor, FrameExtractorConfig, CustomVideoInfo, TimeRange};
+pub use preview::PreviewSession;
+pub use live::{list_capture_devices, process_live_frames, CaptureDeviceInfo, LiveSource};
+pub use frame_source::{FrameSource, ImageSequence, DEFAULT_IMAGE_SEQUENCE_FPS};
+pub use thumbnails::{generate_thumbnails, Thumbnail};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::model::{VideoBackend, VideoTransform};
+
+// ffmpeg バックエンドを使うか（起動時と設定変更時に設定ファイルの値を反映する）
+// gstreamer feature が無効なビルドでは ffmpeg だけを使う
+static USE_FFMPEG: AtomicBool = AtomicBool::new(!cfg!(feature = "gstreamer"));
+
+/// フレーム抽出に使うバックエンドを切り替える（feature が無効なバックエンドは選べない）
+pub fn set_backend(backend: VideoBackend) -> anyhow::Result<()> {
+    if backend == VideoBackend::Ffmpeg && !cfg!(feature = "ffmpeg") {
+        anyhow::bail!("ffmpegバックエンドが有効化されていません（ffmpeg feature を有効にしてビルドしてください）");
+    }
+    if backend == VideoBackend::Gstreamer && !cfg!(feature = "gstreamer") {
+        anyhow::bail!("GStreamerバックエンドが有効化されていません（gstreamer feature を有効にしてビルドしてください）");
+    }
+    USE_FFMPEG.store(backend == VideoBackend::Ffmpeg, Ordering::SeqCst);
+    println!("[Video] バックエンド: {:?}", backend);
+    Ok(())
+}
+
+/// 現在のフレーム抽出バックエンド
+pub fn backend() -> VideoBackend {
+    if use_ffmpeg() {
+        VideoBackend::Ffmpeg
+    } else {
+        VideoBackend::Gstreamer
+    }
+}
+
+fn use_ffmpeg() -> bool {
+    USE_FFMPEG.load(Ordering::SeqCst)
+}
+
+/// 動画のデコードに使うバックエンド（ビルドで有効なものだけ）
+pub(crate) enum Decoder {
+    #[cfg(feature = "gstreamer")]
+    Gstreamer,
+    #[cfg(feature = "ffmpeg")]
+    Ffmpeg,
+}
+
+/// 現在の設定で使うデコーダー（どちらの feature も無効なビルドではエラー）
+pub(crate) fn decoder() -> anyhow::Result<Decoder> {
+    #[cfg(feature = "ffmpeg")]
+    if use_ffmpeg() {
+        return Ok(Decoder::Ffmpeg);
+    }
+    #[cfg(feature = "gstreamer")]
+    {
+        Ok(Decoder::Gstreamer)
+    }
+    #[cfg(not(feature = "gstreamer"))]
+    {
+        Err(anyhow::anyhow!("動画を読み込めません（gstreamer か ffmpeg の feature を有効にしてビルドしてください）"))
+    }
+}
+
+/// 選択中のバックエンドが使えるか確認する（GStreamer の初期化・ffmpeg コマンドの実行）
+pub fn check_backend() -> anyhow::Result<()> {
+    match decoder()? {
+        #[cfg(feature = "gstreamer")]
+        Decoder::Gstreamer => gstreamer_backend::init(),
+        #[cfg(feature = "ffmpeg")]
+        Decoder::Ffmpeg => ffmpeg::check_available(),
+    }
+}
+
+/// 補正後の解像度
+fn transformed_size(transform: VideoTransform, width: u32, height: u32) -> (u32, u32) {
+    if transform.swaps_dimensions() {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+/// 補正を GStreamer の videoflip の method 名で返す（補正なしは None）
+#[cfg(feature = "gstreamer")]
+fn videoflip_method(transform: VideoTransform) -> Option<&'static str> {
+    match transform {
+        VideoTransform::None => None,
+        VideoTransform::Rotate90 => Some("clockwise"),
+        VideoTransform::Rotate180 => Some("rotate-180"),
+        VideoTransform::Rotate270 => Some("counterclockwise"),
+        VideoTransform::FlipHorizontal => Some("horizontal-flip"),
+    }
+}
+
+/// 補正後の画像上の領域を、補正前の画像上の領域に変換する（width / height は補正前の解像度）
+///
+/// 補正前の画像から切り出して transform_image を適用すると、補正後の画像から切り出した領域と一致する。
+/// rows・cols はそのまま引き継ぐ。
+pub fn untransformed_region(
+    transform: VideoTransform,
+    width: u32,
+    height: u32,
+    region: &crate::analyzer::InputIndicatorRegion,
+) -> crate::analyzer::InputIndicatorRegion {
+    let (x, y, region_width, region_height) = match transform {
+        VideoTransform::None => (region.x, region.y, region.width, region.height),
+        VideoTransform::FlipHorizontal => (width.saturating_sub(region.x + region.width), region.y, region.width, region.height),
+        VideoTransform::Rotate180 => (
+            width.saturating_sub(region.x + region.width),
+            height.saturating_sub(region.y + region.height),
+            region.width,
+            region.height,
+        ),
+        // 時計回りの回転: 補正後の (x, y) は補正前の (y, height - 1 - x)
+        VideoTransform::Rotate90 => (region.y, height.saturating_sub(region.x + region.width), region.height, region.width),
+        // 反時計回りの回転: 補正後の (x, y) は補正前の (width - 1 - y, x)
+        VideoTransform::Rotate270 => (width.saturating_sub(region.y + region.height), region.x, region.height, region.width),
+    };
+    crate::analyzer::InputIndicatorRegion {
+        x,
+        y,
+        width: region_width,
+        height: region_height,
+        rows: region.rows,
+        cols: region.cols,
+    }
+}
+
+/// 画像に補正を適用する（連番画像や、補正前の画像から切り出した領域用）
+pub fn transform_image(transform: VideoTransform, img: image::RgbImage) -> image::RgbImage {
+    use image::imageops;
+    match transform {
+        VideoTransform::None => img,
+        VideoTransform::Rotate90 => imageops::rotate90(&img),
+        VideoTransform::Rotate180 => imageops::rotate180(&img),
+        VideoTransform::Rotate270 => imageops::rotate270(&img),
+        VideoTransform::FlipHorizontal => imageops::flip_horizontal(&img),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::InputIndicatorRegion;
+
+    #[test]
+    fn test_untransformed_region_matches_transformed_crop() {
+        let (width, height) = (7u32, 5u32);
+        let source = image::RgbImage::from_fn(width, height, |x, y| image::Rgb([x as u8, y as u8, 0]));
+        let region = InputIndicatorRegion { x: 1, y: 2, width: 3, height: 2, rows: 1, cols: 1 };
+        for transform in [
+            VideoTransform::None,
+            VideoTransform::Rotate90,
+            VideoTransform::Rotate180,
+            VideoTransform::Rotate270,
+            VideoTransform::FlipHorizontal,
+        ] {
+            let transformed = transform_image(transform, source.clone());
+            let expected = image::imageops::crop_imm(&transformed, region.x, region.y, region.width, region.height).to_image();
+
+            let raw = untransformed_region(transform, width, height, &region);
+            let cropped = image::imageops::crop_imm(&source, raw.x, raw.y, raw.width, raw.height).to_image();
+            assert_eq!(transform_image(transform, cropped), expected, "{:?}", transform);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! タイムラインのスクラブ用プレビュー
+//!
+//! GStreamer バックエンドでは一時停止状態のパイプラインを保持したままシークするため、
+//! フレームごとにパイプラインを作り直す `extract_frame_to_memory` より高速に応答できる。
+//! 連番画像と ffmpeg バックエンドは1フレームずつ `extract_frame_to_memory` で取り出す。
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use super::frame_extractor::{CustomVideoInfo, FrameExtractor};
+use super::frame_source::FrameSource;
+#[cfg(feature = "gstreamer")]
+use super::gstreamer_backend::PausedPipeline;
+use super::FrameExtractorConfig;
+use crate::model::VideoTransform;
+
+/// 開いている動画のプレビュー（ドロップするとパイプラインを解放する）
+pub struct PreviewSession {
+    source: PreviewSource,
+    info: CustomVideoInfo,
+}
+
+enum PreviewSource {
+    /// 一時停止状態のパイプライン
+    #[cfg(feature = "gstreamer")]
+    Pipeline(PausedPipeline),
+    /// 1フレームずつ取り出す（連番画像・ffmpeg バックエンド）
+    Frames { path: PathBuf, extractor: FrameExtractor },
+}
+
+impl PreviewSession {
+    /// 動画を開く（transform の補正を適用したフレームを返す）
+    pub fn open<P: AsRef<Path>>(video_path: P, transform: VideoTransform) -> Result<Self> {
+        let video_path = video_path.as_ref();
+        // GStreamerの初期化も行われる
+        let info = FrameExtractor::get_video_info(video_path, transform)?;
+
+        let source = match (FrameSource::open(video_path, super::DEFAULT_IMAGE_SEQUENCE_FPS)?, super::decoder()) {
+            #[cfg(feature = "gstreamer")]
+            (FrameSource::Video(_), Ok(super::Decoder::Gstreamer)) => {
+                PreviewSource::Pipeline(PausedPipeline::open(video_path, transform, None)?)
+            }
+            _ => PreviewSource::Frames {
+                path: video_path.to_path_buf(),
+                extractor: FrameExtractor::new(FrameExtractorConfig {
+                    transform,
+                    ..FrameExtractorConfig::default()
+                }),
+            },
+        };
+        Ok(Self { source, info })
+    }
+
+    pub fn info(&self) -> &CustomVideoInfo {
+        &self.info
+    }
+
+    /// 総フレーム数（動画の長さから算出した概算）
+    pub fn total_frames(&self) -> u32 {
+        (self.info.duration_sec * self.info.fps) as u32
+    }
+
+    /// 指定フレームにシークして画像を取得する
+    pub fn frame(&mut self, frame_number: u32) -> Result<image::RgbImage> {
+        let frame_number = match self.total_frames() {
+            0 => frame_number,
+            total => frame_number.min(total - 1),
+        };
+        match &mut self.source {
+            #[cfg(feature = "gstreamer")]
+            PreviewSource::Pipeline(pipeline) => pipeline.frame(frame_number, self.info.fps, true).map(|(_, image)| image),
+            PreviewSource::Frames { path, extractor } => extractor.extract_frame_to_memory(&*path, frame_number),
+        }
+    }
+}
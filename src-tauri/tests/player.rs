@@ -0,0 +1,85 @@
+//! Player をライブラリとして利用する統合テスト（コントローラー未接続で再生進行のみ確認）
+
+use input_player_lib::{InputFrame, Player, SequenceState};
+use std::time::Instant;
+
+#[test]
+fn test_playback_runs_to_completion_without_controller() {
+    let mut player = Player::new();
+    // 1フレーム = 1ms として短時間で再生を終える
+    player.set_fps(1000);
+    player.load_frames(vec![
+        InputFrame::with_buttons(3, 6, &[]),
+        InputFrame::with_buttons(2, 2, &[]),
+        InputFrame::with_buttons(4, 5, &[]),
+    ]);
+    assert_eq!(player.get_state(), SequenceState::Stopped);
+
+    player.start();
+    let started = Instant::now();
+    let mut visited_steps = Vec::new();
+    while player.get_state() == SequenceState::Playing {
+        if let Some(deadline) = player.next_step_deadline() {
+            std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+        }
+        player.update(None).unwrap();
+        if let Some(event) = player.step_event() {
+            if visited_steps.last() != Some(&event.step) {
+                visited_steps.push(event.step);
+            }
+        }
+    }
+
+    assert_eq!(visited_steps, vec![0, 1, 2]);
+    // 合計9フレーム（9ms）は待機してから停止すること
+    assert!(started.elapsed().as_millis() >= 9);
+    assert_eq!(player.timing_report().samples, 3);
+}
+
+#[test]
+fn test_late_step_is_reported_once() {
+    let mut player = Player::new();
+    player.set_fps(1000);
+    player.set_late_step_warning_ms(5);
+    player.load_frames(vec![InputFrame::with_buttons(3, 6, &[]), InputFrame::with_buttons(2, 2, &[])]);
+
+    player.start();
+    // 最初のステップの送信をわざと遅らせる
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    player.update(None).unwrap();
+
+    let late = player.take_late_step().expect("遅れた送信が記録されること");
+    assert_eq!(late.step, 0);
+    assert!(late.late_ms >= 5.0);
+    assert_eq!(late.threshold_ms, 5);
+    assert!(player.take_late_step().is_none());
+}
+
+#[test]
+fn test_step_event_counts_cumulative_frames_in_play_order() {
+    let mut player = Player::new();
+    player.set_fps(1000);
+    player.load_frames(vec![
+        InputFrame::with_buttons(3, 6, &[]),
+        InputFrame::with_buttons(2, 2, &[]),
+        InputFrame::with_buttons(4, 5, &[]),
+    ]);
+
+    // 逆再生では末尾の行から累積フレームを数える
+    player.start_reversed();
+    let mut events = Vec::new();
+    while player.get_state() == SequenceState::Playing {
+        if let Some(deadline) = player.next_step_deadline() {
+            std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+        }
+        player.update(None).unwrap();
+        if let Some(event) = player.step_event() {
+            assert_eq!(event.total_frames, 9);
+            if events.last().map(|&(step, _)| step) != Some(event.step) {
+                events.push((event.step, event.cumulative_frame));
+            }
+        }
+    }
+
+    assert_eq!(events, vec![(2, 0), (1, 4), (0, 6)]);
+}
@@ -0,0 +1,44 @@
+//! シミュレーション用の出力先で、ドライバ無しにコントローラーへの送信内容を確認する統合テスト
+
+use input_player_lib::output_backend::{SimulatedBackend, SIMULATION_BACKEND};
+use input_player_lib::{Controller, ControllerType, InputFrame, Player, SequenceState};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_playback_sends_each_step_to_simulated_backend() {
+    let backend = SimulatedBackend::new();
+    let records = backend.records();
+    let mut controller = Controller::with_backend(SIMULATION_BACKEND, Box::new(backend));
+    controller.connect(ControllerType::Xbox).unwrap();
+    assert!(controller.is_connected());
+
+    let mut player = Player::new();
+    // 1フレーム = 1ms として短時間で再生を終える
+    player.set_fps(1000);
+    player.load_frames(vec![
+        InputFrame::with_buttons(3, 6, &[]),
+        InputFrame::with_buttons(2, 2, &[]),
+        InputFrame::with_buttons(4, 5, &[]),
+    ]);
+    player.start();
+    while player.get_state() == SequenceState::Playing {
+        if let Some(deadline) = player.next_step_deadline() {
+            std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+        }
+        player.update(Some(&mut controller)).unwrap();
+    }
+
+    // 各ステップと、終了時の中立入力
+    let records = records.lock().unwrap();
+    let directions: Vec<u8> = records.iter().map(|r| r.frame.direction).collect();
+    assert_eq!(directions, vec![6, 2, 5, 5]);
+    // 各ステップは前のステップのフレーム数だけ待ってから送信される
+    assert!(records[1].elapsed - records[0].elapsed >= Duration::from_millis(3));
+    assert!(records[2].elapsed - records[1].elapsed >= Duration::from_millis(2));
+
+    // 最後に送った中立入力が現在の送信状態になる
+    assert_eq!(controller.current_output().direction, 5);
+
+    // 送信ログにも送信成功として残る
+    assert!(controller.log_entries().iter().all(|entry| entry.sent));
+}
@@ -11,16 +11,18 @@ import BackendSettings from "./BackendSettings";
 import TrainingDialog from "./TrainingDialog";
 import TileClassificationDialog from "./TileClassificationDialog";
 import ModelConfigDialog from "./ModelConfigDialog";
-import type { SequenceSlot, InputFrame } from "./types";
+import type { SequenceSlot, InputFrame, PlaybackStepEvent, FileReloadedEvent, SelfTestProgressEvent } from "./types";
 
 function App() {
   // Controller state
   const [isConnected, setIsConnected] = useState(false);
+  const [selfTestProgress, setSelfTestProgress] = useState<SelfTestProgressEvent | null>(null);
 
 
 
   // Playback state
   const [isPlaying, setIsPlaying] = useState(false);
+  const [isPaused, setIsPaused] = useState(false); // ブレークポイントで一時停止中（再生中扱いのまま）
   const [invertHorizontal, setInvertHorizontal] = useState(false);
   const [currentStep, setCurrentStep] = useState(0);
   const [totalSteps, setTotalSteps] = useState(0);
@@ -145,14 +147,20 @@ function App() {
       if (event.payload === "stopped" || event.payload === "no_sequence") {
         // 停止状態に遷移
         setIsPlaying(false);
+        setIsPaused(false);
         setPlayingSlot(null);
         if (isPlayingChain) {
           console.log("[Event] チェーン再生終了");
           setIsPlayingChain(false);
         }
       } else if (event.payload === "playing") {
-        // 再生開始（通常はフロントエンドから開始するので不要だが念のため）
+        // 再生開始・ブレークポイントからの再開
         setIsPlaying(true);
+        setIsPaused(false);
+      } else if (event.payload === "paused") {
+        // ブレークポイントで一時停止（スロットは再生中のまま保持し、チェーンも終了させない）
+        setIsPlaying(true);
+        setIsPaused(true);
       }
     });
 
@@ -175,30 +183,39 @@ function App() {
   }, [activeTestButton]);
 
   // Update playback progress
-  // バックエンド優先: 更新頻度を下げてバックエンドのCPU負荷を軽減
+  // バックエンドからのステップ遷移イベントで更新（ポーリング不要）
+  // 再生停止時はハイライトをリセットしない（停止位置を保持）
   useEffect(() => {
-    const interval = setInterval(async () => {
-      if (isPlaying) {
-        const [current, total] = await api.getPlaybackProgress();
-        setCurrentStep(current);
-        setTotalSteps(total);
-
-        // エディタ表示中は再生中のフレーム番号も取得
-        if (showSequenceEditor) {
-          try {
-            const playingFrame = await api.getCurrentPlayingFrame();
-            setCurrentPlayingRow(playingFrame);
-          } catch (error) {
-            // エラーは無視（再生中でない場合など）
-          }
-        }
+    const unlisten = listen<PlaybackStepEvent>(
+      "playback-step-changed",
+      (event) => {
+        const { step, total_steps } = event.payload;
+        setCurrentStep(step + 1);
+        setTotalSteps(total_steps);
+        setCurrentPlayingRow(step);
+      },
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 外部のエディタで保存されたマッピング・シーケンスはバックエンドが自動で読み込み直す
+  useEffect(() => {
+    const unlisten = listen<FileReloadedEvent>("file-reloaded", (event) => {
+      const { kind, path, error } = event.payload;
+      if (error) {
+        console.warn(`[Event] 再読み込みに失敗 (${kind}): ${path}`, error);
       } else {
-        // 再生停止時はハイライトをリセットしない（停止位置を保持）
+        console.log(`[Event] 再読み込み (${kind}): ${path}`);
       }
-    }, 200); // 100ms → 200ms (5FPS表示更新、バックエンドは60FPSで動作)
+    });
 
-    return () => clearInterval(interval);
-  }, [isPlaying, loopPlayback, isPlayingChain, showSequenceEditor]);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   // Send manual input continuously while connected
   useEffect(() => {
@@ -238,6 +255,7 @@ function App() {
       setIsConnected(true);
     } catch (error) {
       console.error("接続エラー:", error);
+      alert(`コントローラーの接続に失敗しました: ${error}`);
     }
   };
 
@@ -250,6 +268,22 @@ function App() {
     }
   };
 
+  // ゲーム側が仮想パッドを認識しているか、全ボタン・方向を順に押して確認する
+  const handleSelfTest = async () => {
+    const unlisten = await listen<SelfTestProgressEvent>("controller-selftest-progress", (event) => {
+      setSelfTestProgress(event.payload);
+    });
+    try {
+      await api.runControllerSelftest();
+    } catch (error) {
+      console.error("セルフテストエラー:", error);
+      alert(`セルフテストに失敗しました: ${error}`);
+    } finally {
+      unlisten();
+      setSelfTestProgress(null);
+    }
+  };
+
   // Note: handleLoadMappingFile is used through the button mapping editor
   // which calls loadMapping directly
 
@@ -405,6 +439,7 @@ function App() {
     try {
       await api.stopPlayback();
       setIsPlaying(false);
+      setIsPaused(false);
       setCurrentStep(0);
       setPlayingSlot(null);
       setIsPlayingChain(false);
@@ -413,6 +448,15 @@ function App() {
     }
   };
 
+  const resumeSequence = async () => {
+    try {
+      await api.resumePlayback();
+      setIsPaused(false);
+    } catch (error) {
+      console.error(`再開エラー:`, error);
+    }
+  };
+
   // シーケンスチェーン: スロットをチェーンに追加（同一スロット複数回OK）
   const addToChain = (slotIndex: number) => {
     if (sequenceChain.length < 20) {
@@ -678,6 +722,32 @@ function App() {
     loadMapping(currentMappingPath, false, true);
   }, []);
 
+  // 多重起動していると仮想パッドへの入力を送り合うため、起動時に警告する
+  useEffect(() => {
+    api
+      .checkInstanceConflict()
+      .then((conflict) => {
+        if (conflict.another_instance || conflict.target_in_use) {
+          alert(
+            "別の 無人入力機 じんむくん が起動しています。\n" +
+              "仮想コントローラーは片方のインスタンスからのみ接続できます。",
+          );
+        }
+      })
+      .catch((error) => console.error("多重起動の確認エラー:", error));
+  }, []);
+
+  // バックエンドが起動時に復元した前回の再生設定（ループ・左右反転）を表示に反映
+  useEffect(() => {
+    api
+      .getAppSnapshot()
+      .then((snapshot) => {
+        setLoopPlayback(snapshot.loop_playback);
+        setInvertHorizontal(snapshot.invert_horizontal);
+      })
+      .catch((error) => console.error("状態の取得エラー:", error));
+  }, []);
+
   return (
     <main className="container">
       <h1>無人入力機 じんむくん</h1>
@@ -714,6 +784,25 @@ function App() {
                 Xbox 360: {isConnected ? "接続中" : "未接続"}
               </span>
             </button>
+            <button
+              onClick={handleSelfTest}
+              className="btn-overlay"
+              title="ボタン・方向・トリガー・スティックを順に押して、ゲームが仮想パッドを認識しているか確認"
+              disabled={!isConnected || isPlaying || selfTestProgress !== null}
+            >
+              {selfTestProgress
+                ? `🩺 ${selfTestProgress.label} (${Math.min(selfTestProgress.index + 1, selfTestProgress.total)}/${selfTestProgress.total})`
+                : "🩺 セルフテスト"}
+            </button>
+            <button
+              onClick={() =>
+                api.openOverlayWindow().catch((error) => console.error("オーバーレイの表示エラー:", error))
+              }
+              className="btn-overlay"
+              title="送信中の入力を最前面の透過ウィンドウに表示（配信用）"
+            >
+              📺 入力オーバーレイ
+            </button>
           </div>
           <div className="ml-buttons">
             <button
@@ -871,8 +960,13 @@ function App() {
             <h3>シーケンス再生</h3>
             <span className="frame-counter">
               {isPlaying ? `${currentStep} / ${totalSteps}` : "0 / 0"}{" "}
-              ステップ
+              ステップ{isPaused && "（一時停止中）"}
             </span>
+            {isPaused && (
+              <button onClick={resumeSequence} className="btn-chain-play">
+                ▶ 再開
+              </button>
+            )}
           </div>
           <div className="sequence-controls">
             <div className="sequence-buttons">
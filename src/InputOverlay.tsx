@@ -0,0 +1,105 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { api } from "./api";
+import type { ControllerOutput } from "./types";
+import "./InputOverlay.css";
+
+// button1〜button12 に対応する Xbox のボタン（controller.rs の割り当てと同じ）
+const PAD_BUTTONS: [string, string][] = [
+  ["button1", "A"],
+  ["button2", "B"],
+  ["button3", "X"],
+  ["button4", "Y"],
+  ["button5", "LB"],
+  ["button6", "RB"],
+  ["button7", "LT"],
+  ["button8", "RT"],
+  ["button9", "BACK"],
+  ["button10", "START"],
+  ["button11", "LS"],
+  ["button12", "RS"],
+];
+
+// テンキー配置（上段が 7 8 9）
+const DIRECTION_GRID = [7, 8, 9, 4, 5, 6, 1, 2, 3];
+
+const NEUTRAL: ControllerOutput = {
+  direction: 5,
+  buttons: [],
+  thumb_lx: 0,
+  thumb_ly: 0,
+  thumb_rx: 0,
+  thumb_ry: 0,
+  left_trigger: 0,
+  right_trigger: 0,
+  invert_horizontal: false,
+};
+
+// スティックの値（-32768〜32767）を表示位置（%）に変換
+const stickPosition = (x: number, y: number) => ({
+  left: `${50 + (x / 32768) * 40}%`,
+  top: `${50 - (y / 32768) * 40}%`,
+});
+
+function InputOverlay() {
+  const [output, setOutput] = useState<ControllerOutput>(NEUTRAL);
+
+  // 送信中の状態はバックエンドからフレームごと（変化時のみ）に送られる
+  useEffect(() => {
+    api
+      .getControllerOutput()
+      .then(setOutput)
+      .catch((error) => console.error("送信状態の取得エラー:", error));
+
+    const unlisten = listen<ControllerOutput>("controller-output", (event) => {
+      setOutput(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return (
+    <div className="input-overlay" data-tauri-drag-region>
+      <div className="overlay-direction" data-tauri-drag-region>
+        {DIRECTION_GRID.map((direction) => (
+          <div
+            key={direction}
+            className={`overlay-direction-cell ${output.direction === direction ? "active" : ""}`}
+          />
+        ))}
+      </div>
+
+      <div className="overlay-sticks">
+        {[
+          ["L", output.thumb_lx, output.thumb_ly],
+          ["R", output.thumb_rx, output.thumb_ry],
+        ].map(([name, x, y]) => (
+          <div key={name} className="overlay-stick" title={`${name}スティック`}>
+            <div className="overlay-stick-dot" style={stickPosition(x as number, y as number)} />
+          </div>
+        ))}
+      </div>
+
+      <div className="overlay-buttons">
+        {PAD_BUTTONS.map(([name, label]) => {
+          const trigger =
+            name === "button7" ? output.left_trigger : name === "button8" ? output.right_trigger : 0;
+          const pressed = output.buttons.includes(name) || trigger > 0;
+          return (
+            <span key={name} className={`overlay-button ${pressed ? "pressed" : ""}`}>
+              {label}
+            </span>
+          );
+        })}
+      </div>
+
+      <button className="overlay-close" onClick={() => getCurrentWindow().close()} title="閉じる">
+        ✕
+      </button>
+    </div>
+  );
+}
+
+export default InputOverlay;
@@ -1,5 +1,7 @@
 import { useState, useEffect, useRef } from "react";
+import { listen } from "@tauri-apps/api/event";
 import "./SequenceEditor.css";
+import type { PlaybackStepEvent } from "./types";
 
 interface InputFrame {
   duration: number;
@@ -39,6 +41,7 @@ function SequenceEditor({
   const [currentFilePath, setCurrentFilePath] = useState<string>(csvPath); // 現在編集中のファイルパス
   const scrollRef = useRef<HTMLDivElement>(null);
   const [localIsPlaying, setLocalIsPlaying] = useState(false);
+  const [isPaused, setIsPaused] = useState(false); // ブレークポイントで一時停止中
   const [internalPlayingRow, setInternalPlayingRow] = useState<number>(-1);
 
   // 再生中かどうかを判定（親コンポーネントからのプロップ優先）
@@ -83,55 +86,43 @@ function SequenceEditor({
     }
   }, [displayPlayingRow]);
 
-  // 再生中は再生状態をポーリング
+  // 再生中はバックエンドのイベントで再生行と終了を検知
   useEffect(() => {
-    console.log(
-      "[SequenceEditor] ポーリング useEffect - localIsPlaying:",
-      localIsPlaying,
-    );
     if (!localIsPlaying) return;
 
-    console.log("[SequenceEditor] ポーリング開始");
-    const interval = setInterval(async () => {
-      try {
-        const { api } = await import("./api");
-        const playing = await api.isPlaying();
-        if (!playing) {
-          // 再生が終了した
-          // 最終フレームのハイライトを保持
-          const finalFrame = frames.length - 1;
-          console.log(
-            "[SequenceEditor] 再生終了検知 - 最終フレームに設定:",
-            finalFrame,
-          );
-          setInternalPlayingRow(finalFrame);
-          setLocalIsPlaying(false);
-          setMessage("再生が終了しました");
-        } else {
-          // 現在のフレームを取得
-          try {
-            const frame = await api.getCurrentPlayingFrame();
-            if (frame !== internalPlayingRow) {
-              console.log(
-                "[SequenceEditor] フレーム更新:",
-                internalPlayingRow,
-                "→",
-                frame,
-              );
-            }
-            setInternalPlayingRow(frame);
-          } catch (e) {
-            console.error("[SequenceEditor] フレーム取得エラー:", e);
-          }
-        }
-      } catch (error) {
-        console.error("[SequenceEditor] 再生状態の確認エラー:", error);
+    console.log("[SequenceEditor] 再生イベント購読開始");
+    const unlistenStep = listen<PlaybackStepEvent>(
+      "playback-step-changed",
+      (event) => {
+        setInternalPlayingRow(event.payload.step);
+      },
+    );
+    const unlistenState = listen<string>("playback-state-changed", (event) => {
+      if (event.payload === "stopped" || event.payload === "no_sequence") {
+        // 再生が終了した
+        // 最終フレームのハイライトを保持
+        const finalFrame = frames.length - 1;
+        console.log(
+          "[SequenceEditor] 再生終了検知 - 最終フレームに設定:",
+          finalFrame,
+        );
+        setInternalPlayingRow(finalFrame);
+        setLocalIsPlaying(false);
+        setIsPaused(false);
+        setMessage("再生が終了しました");
+      } else if (event.payload === "paused") {
+        // ブレークポイントで一時停止（再生行のハイライトはそのまま）
+        setIsPaused(true);
+        setMessage("ブレークポイントで一時停止しました");
+      } else if (event.payload === "playing") {
+        setIsPaused(false);
       }
-    }, 16); // 約60FPS (16ms)ごとにチェック
+    });
 
     return () => {
-      console.log("[SequenceEditor] ポーリング停止");
-      clearInterval(interval);
+      console.log("[SequenceEditor] 再生イベント購読停止");
+      unlistenStep.then((fn) => fn());
+      unlistenState.then((fn) => fn());
     };
   }, [localIsPlaying]);
 
@@ -149,12 +140,16 @@ function SequenceEditor({
         : [...sequenceButtons].sort();
       console.log("✓ Button names (sequence buttons with order):", buttonNamesArray);
 
-      // 各フレームのボタンをsequenceButtonsのみに制限
+      // 表示するボタン（sequenceButtons）の値を揃える
+      // マッピングに無い列も保存時に書き戻すため残しておく
       const filteredFrames = loadedFrames.map((frame: InputFrame) => ({
         ...frame,
-        buttons: Object.fromEntries(
-          buttonNamesArray.map((btn) => [btn, frame.buttons[btn] ?? 0]),
-        ),
+        buttons: {
+          ...frame.buttons,
+          ...Object.fromEntries(
+            buttonNamesArray.map((btn) => [btn, frame.buttons[btn] ?? 0]),
+          ),
+        },
       }));
 
       setFrames(filteredFrames);
@@ -321,6 +316,7 @@ function SequenceEditor({
         );
         await api.stopPlayback();
         setLocalIsPlaying(false);
+        setIsPaused(false);
         console.log(
           "[SequenceEditor] 停止後 - internalPlayingRowを保持:",
           internalPlayingRow,
@@ -345,6 +341,7 @@ function SequenceEditor({
         await api.startPlayback();
 
         setLocalIsPlaying(true);
+        setIsPaused(false);
         setInternalPlayingRow(0);
         setMessage(`再生を開始しました (${frameCount}ステップ)`);
       } catch (error) {
@@ -354,6 +351,18 @@ function SequenceEditor({
     }
   };
 
+  const handleResume = async () => {
+    try {
+      const { api } = await import("./api");
+      await api.resumePlayback();
+      setIsPaused(false);
+      setMessage("再生を再開しました");
+    } catch (error) {
+      console.error("再開エラー:", error);
+      setMessage(`再開エラー: ${error}`);
+    }
+  };
+
   const handleRowClick = (index: number, event: React.MouseEvent) => {
     if (event.ctrlKey) {
       const newSelected = new Set(selectedRows);
@@ -484,6 +493,11 @@ function SequenceEditor({
             >
               {isPlaying ? "■ 停止" : "▶ 再生"}
             </button>
+            {isPaused && (
+              <button onClick={handleResume} className="btn-toolbar btn-play">
+                ▶ 再開
+              </button>
+            )}
             <div className="toolbar-divider"></div>
             <button
               onClick={() => addRow(selectedRow)}
@@ -504,7 +518,7 @@ function SequenceEditor({
             <span className="editor-message">{message}</span>
             <span className="editor-status">
               総行数: {frames.length} {hasChanges && "(未保存)"}{" "}
-              {isPlaying && "🔴 再生中"}
+              {isPlaying && (isPaused ? "⏸ 一時停止中" : "🔴 再生中")}
             </span>
           </div>
         </div>
@@ -1,8 +1,10 @@
 import { useState, useEffect } from "react";
 import { getCurrentWindow } from "@tauri-apps/api/window";
+import { listen } from "@tauri-apps/api/event";
 import SequenceEditor from "./SequenceEditor";
 import { api } from "./api";
 import "./SequenceEditor.css";
+import type { PlaybackStepEvent } from "./types";
 
 function SequenceEditorWindow() {
   const [csvPath, setCsvPath] = useState<string | null>(null);
@@ -54,31 +56,23 @@ function SequenceEditorWindow() {
     loadMapping();
   }, []);
 
-  // 再生中のフレーム番号を定期的に取得
+  // 再生中の行番号をバックエンドのステップ遷移イベントで受け取る
   useEffect(() => {
-    if (csvPath) {
-      console.log("Starting playback frame polling for:", csvPath);
-    }
-    
-    const interval = setInterval(async () => {
-      if (csvPath) {
-        try {
-          const playingFrame = await api.getCurrentPlayingFrame();
-          if (playingFrame !== currentPlayingRow) {
-            console.log("Current playing frame:", playingFrame);
-          }
-          setCurrentPlayingRow(playingFrame);
-        } catch (error) {
-          // エラーは無視（再生中でない場合など）
-        }
-      }
-    }, 100);
+    if (!csvPath) return;
+
+    console.log("Listening playback step events for:", csvPath);
+    const unlisten = listen<PlaybackStepEvent>(
+      "playback-step-changed",
+      (event) => {
+        setCurrentPlayingRow(event.payload.step);
+      },
+    );
 
     return () => {
-      console.log("Stopping playback frame polling");
-      clearInterval(interval);
+      console.log("Stop listening playback step events");
+      unlisten.then((fn) => fn());
     };
-  }, [csvPath, currentPlayingRow]);
+  }, [csvPath]);
 
   const handleClose = () => {
     console.log("Close button clicked");
@@ -1,15 +1,24 @@
-import { useState } from "react";
+import { useState, useRef } from "react";
 import { invoke, Channel } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
-import { api } from "./api";
+import { api, createJobId, JOB_CANCELLED_MESSAGE } from "./api";
+import type { ExtractionFrameEvent } from "./types";
 import "./SequenceSelector.css";
 
 interface ExtractionProgress {
   current_frame: number;
   total_frames: number;
   message: string;
+  percent: number | null; // 進捗率（0〜100、総フレーム数が不明な場合は null）
+  frames_per_sec: number; // 処理速度（フレーム/秒）
+  elapsed_sec: number; // 経過時間（秒）
+  eta_sec: number | null; // 残り時間の推定（秒）
 }
 
+// 変換中のプレビューで確信度が低いと警告表示するしきい値
+const LOW_CONFIDENCE = 0.6;
+
 interface SequenceSelectorProps {
   onClose: () => void;
   onSelect: (
@@ -39,6 +48,8 @@ function SequenceSelector({
   const [message, setMessage] = useState("");
   const [isConverting, setIsConverting] = useState(false);
   const [progress, setProgress] = useState({ current: 0, total: 0 });
+  const jobIdRef = useRef<string | null>(null); // 実行中のMP4変換ジョブ（中断用）
+  const [livePreview, setLivePreview] = useState<ExtractionFrameEvent | null>(null); // 変換中に認識した入力
 
   const handleLoad = () => {
     if (csvPath) {
@@ -77,8 +88,9 @@ function SequenceSelector({
                     multiple: false,
                     directory: false,
                     filters: [
-                      { name: "Input Files", extensions: ["csv", "mp4"] },
+                      { name: "Input Files", extensions: ["csv", "ipsq", "mp4"] },
                       { name: "CSV", extensions: ["csv"] },
+                      { name: "Binary Sequence", extensions: ["ipsq"] },
                       { name: "MP4", extensions: ["mp4"] },
                     ],
                   });
@@ -86,12 +98,7 @@ function SequenceSelector({
                     const isMP4 = file.toLowerCase().endsWith(".mp4");
                     
                     if (isMP4) {
-                      // MP4ファイルの場合
-                      if (!classificationModelPath) {
-                        setMessage("MP4ファイルを読み込むには、先にモデル設定が必要です");
-                        setIsCompatible(false);
-                        return;
-                      }
+                      // MP4ファイルの場合（モデル未設定なら動画の解像度から自動選択）
                       
                       // MP4からCSVに変換
                       setIsConverting(true);
@@ -100,6 +107,14 @@ function SequenceSelector({
                       setProgress({ current: 0, total: 0 });
                       setMessage("MP4を解析中...");
                       
+                      // 認識した入力を変換中に表示する（領域やモデルの誤りに早く気付けるように）
+                      const jobId = createJobId("mp4");
+                      const unlistenFrames = await listen<ExtractionFrameEvent>("extraction-frame", (event) => {
+                        if (event.payload.job_id === jobId) {
+                          setLivePreview(event.payload);
+                        }
+                      });
+                      
                       try {
                         console.log("[MP4変換] 開始:", file);
                         
@@ -113,10 +128,12 @@ function SequenceSelector({
                           setMessage(progressData.message);
                         };
                         
+                        jobIdRef.current = jobId;
                         const generatedCsvPath = await invoke<string>("mp4_to_sequence", {
                           videoPath: file,
-                          modelPath: classificationModelPath,
+                          modelPath: classificationModelPath || null,
                           backend: mlBackend,
+                          jobId,
                           onProgress,
                         });
                         
@@ -147,12 +164,19 @@ function SequenceSelector({
                           );
                         }
                       } catch (error) {
-                        console.error("[MP4変換] エラー:", error);
-                        setMessage(`エラー: ${error}`);
+                        if (error === JOB_CANCELLED_MESSAGE) {
+                          setMessage("MP4の変換を中断しました");
+                        } else {
+                          console.error("[MP4変換] エラー:", error);
+                          setMessage(`エラー: ${error}`);
+                        }
                         setIsCompatible(false);
                         setCsvPath("");
                         setCsvButtons([]);
                       } finally {
+                        unlistenFrames();
+                        jobIdRef.current = null;
+                        setLivePreview(null);
                         setIsConverting(false);
                       }
                     } else {
@@ -212,6 +236,33 @@ function SequenceSelector({
                 {progress.current} / {progress.total} フレーム (
                 {Math.round((progress.current / progress.total) * 100)}%)
               </div>
+              {livePreview && (
+                <div className="live-preview">
+                  <span>フレーム {livePreview.frame}</span>
+                  <span>方向 {livePreview.direction}</span>
+                  <span>
+                    {Object.entries(livePreview.buttons)
+                      .filter(([, state]) => state === 1)
+                      .map(([name]) => name)
+                      .join("+") || "ボタンなし"}
+                  </span>
+                  {livePreview.confidences.length > 0 && (
+                    <span
+                      className={
+                        Math.min(...livePreview.confidences) < LOW_CONFIDENCE ? "low-confidence" : undefined
+                      }
+                    >
+                      確信度 {Math.round(Math.min(...livePreview.confidences) * 100)}%
+                    </span>
+                  )}
+                </div>
+              )}
+              <button
+                className="cancel-button"
+                onClick={() => jobIdRef.current && api.cancelJob(jobIdRef.current)}
+              >
+                中断
+              </button>
             </div>
           )}
 
@@ -1,7 +1,8 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke, Channel } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
 import { path } from "@tauri-apps/api";
+import { api, createJobId, JOB_CANCELLED_MESSAGE } from "./api";
 import "./TrainingDialog.css"; // 同じスタイルを使用
 
 interface TileClassificationDialogProps {
@@ -14,6 +15,10 @@ interface ClassificationConfig {
   videoPath: string;
   outputDir: string;
   frameSkip: number;
+  scaleToVideo: boolean; // 学習時と解像度が異なる動画はタイル領域を比率で補正する
+  startSec: number | null; // 処理する区間（null は先頭・末尾まで）
+  endSec: number | null;
+  imageSequenceFps: number | null; // 連番画像のフォルダを選んだ場合のフレームレート（動画は null）
 }
 
 interface ModelMetadata {
@@ -37,6 +42,10 @@ interface ExtractionProgress {
   current_frame: number;
   total_frames: number;
   message: string;
+  percent: number | null; // 進捗率（0〜100、総フレーム数が不明な場合は null）
+  frames_per_sec: number; // 処理速度（フレーム/秒）
+  elapsed_sec: number; // 経過時間（秒）
+  eta_sec: number | null; // 残り時間の推定（秒）
 }
 
 interface ClassSummary {
@@ -55,10 +64,15 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
     videoPath: "",
     outputDir: "",
     frameSkip: 0,
+    scaleToVideo: false,
+    startSec: null,
+    endSec: null,
+    imageSequenceFps: null,
   });
 
   const [metadata, setMetadata] = useState<ModelMetadata | null>(null);
   const [isClassifying, setIsClassifying] = useState(false);
+  const jobIdRef = useRef<string | null>(null); // 実行中の分類ジョブ（中断用）
   const [progress, setProgress] = useState<ExtractionProgress | null>(null);
   const [result, setResult] = useState<ClassificationResult | null>(null);
   const [errorMessage, setErrorMessage] = useState<string>("");
@@ -144,18 +158,26 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
   };
 
   // 動画選択
-  const handleSelectVideo = async () => {
+  // directory が true なら連番画像（png / jpg）のフォルダを選択する
+  const handleSelectVideo = async (directory: boolean) => {
     try {
-      const selected = await open({
-        multiple: false,
-        filters: [
-          { name: "Video", extensions: ["mp4", "avi", "mkv", "mov", "webm"] },
-        ],
-        title: "分類対象の動画を選択",
-      });
+      const selected = directory
+        ? await open({
+            directory: true,
+            multiple: false,
+            title: "分類対象の連番画像フォルダを選択",
+          })
+        : await open({
+            multiple: false,
+            filters: [
+              { name: "Video", extensions: ["mp4", "avi", "mkv", "mov", "webm"] },
+            ],
+            title: "分類対象の動画を選択",
+          });
 
       if (selected) {
         const videoPath = selected as string;
+        const imageSequenceFps = directory ? config.imageSequenceFps ?? 60 : null;
 
         // 動画サイズチェック
         if (metadata) {
@@ -166,16 +188,17 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
             );
 
             if (
-              videoInfo.width !== metadata.video_width ||
-              videoInfo.height !== metadata.video_height
+              !config.scaleToVideo &&
+              (videoInfo.width !== metadata.video_width ||
+                videoInfo.height !== metadata.video_height)
             ) {
               setErrorMessage(
-                `動画サイズが不一致: 動画=${videoInfo.width}x${videoInfo.height}, モデル=${metadata.video_width}x${metadata.video_height}`
+                `動画サイズが不一致: 動画=${videoInfo.width}x${videoInfo.height}, モデル=${metadata.video_width}x${metadata.video_height}（「解像度の違いを補正」を有効にすると処理できます）`
               );
               return;
             }
 
-            setConfig({ ...config, videoPath });
+            setConfig({ ...config, videoPath, imageSequenceFps });
             setErrorMessage("");
             
             // 動画名ベースの出力ディレクトリを生成
@@ -185,7 +208,7 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
             setErrorMessage(`動画情報取得エラー: ${error}`);
           }
         } else {
-          setConfig({ ...config, videoPath });
+          setConfig({ ...config, videoPath, imageSequenceFps });
           await updateOutputDirFromVideo(videoPath);
         }
       }
@@ -248,6 +271,8 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
     setIsClassifying(true);
     setResult(null);
     setErrorMessage("");
+    const jobId = createJobId("classify");
+    jobIdRef.current = jobId;
 
     try {
       // 進捗チャンネル作成
@@ -263,7 +288,12 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
           modelPath: config.modelPath,
           outputDir: config.outputDir,
           frameSkip: config.frameSkip,
+          scaleToVideo: config.scaleToVideo,
           useGpu: mlBackend === "wgpu",
+          startSec: config.startSec,
+          endSec: config.endSec,
+          imageSequenceFps: config.imageSequenceFps,
+          jobId,
           onProgress: progressChannel,
         }
       );
@@ -271,9 +301,15 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
       setResult(classificationResult);
       setProgress(null);
     } catch (error) {
-      console.error("分類エラー:", error);
-      setErrorMessage(`分類エラー: ${error}`);
+      if (error === JOB_CANCELLED_MESSAGE) {
+        setErrorMessage("分類を中断しました（保存途中のタイルは削除しました）");
+      } else {
+        console.error("分類エラー:", error);
+        setErrorMessage(`分類エラー: ${error}`);
+      }
+      setProgress(null);
     } finally {
+      jobIdRef.current = null;
       setIsClassifying(false);
     }
   };
@@ -348,17 +384,39 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
                 type="text"
                 value={config.videoPath}
                 readOnly
-                placeholder="動画ファイルを選択"
+                placeholder="動画ファイルまたは連番画像のフォルダを選択"
               />
               <button
-                onClick={handleSelectVideo}
+                onClick={() => handleSelectVideo(false)}
                 disabled={isClassifying || !metadata}
               >
                 参照
               </button>
+              <button
+                onClick={() => handleSelectVideo(true)}
+                disabled={isClassifying || !metadata}
+              >
+                フォルダ
+              </button>
             </div>
           </div>
 
+          {/* 連番画像のフレームレート（区間指定の秒数をフレーム番号に換算する） */}
+          {config.imageSequenceFps !== null && (
+            <div className="config-item">
+              <label>連番画像のフレームレート (fps)</label>
+              <input
+                type="number"
+                value={config.imageSequenceFps}
+                onChange={(e) =>
+                  setConfig({ ...config, imageSequenceFps: Number(e.target.value) || 60 })
+                }
+                min={1}
+                disabled={isClassifying}
+              />
+            </div>
+          )}
+
           {/* 出力先選択 */}
           <div className="config-item">
             <label>出力先ディレクトリ</label>
@@ -394,6 +452,50 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
             />
           </div>
 
+          {/* 解像度補正 */}
+          <div className="config-item">
+            <label>
+              <input
+                type="checkbox"
+                checked={config.scaleToVideo}
+                onChange={(e) => setConfig({ ...config, scaleToVideo: e.target.checked })}
+                disabled={isClassifying}
+              />
+              解像度の違いを補正
+              <span className="hint-text">
+                (720p に再エンコードした動画など、学習時と解像度が異なる場合にタイル領域を比率で拡大縮小)
+              </span>
+            </label>
+          </div>
+
+          {/* 区間設定 */}
+          <div className="config-item">
+            <label>
+              処理する区間（秒）
+              <span className="hint-text">(空欄=先頭から/末尾まで)</span>
+            </label>
+            <input
+              type="number"
+              value={config.startSec ?? ""}
+              placeholder="開始"
+              onChange={(e) =>
+                setConfig({ ...config, startSec: e.target.value === "" ? null : Number(e.target.value) })
+              }
+              min={0}
+              disabled={isClassifying}
+            />
+            <input
+              type="number"
+              value={config.endSec ?? ""}
+              placeholder="終了"
+              onChange={(e) =>
+                setConfig({ ...config, endSec: e.target.value === "" ? null : Number(e.target.value) })
+              }
+              min={0}
+              disabled={isClassifying}
+            />
+          </div>
+
           {/* エラーメッセージ */}
           {errorMessage && (
             <div className="error-banner">{errorMessage}</div>
@@ -461,6 +563,14 @@ function TileClassificationDialog({ mlBackend, onClose }: TileClassificationDial
           >
             {isClassifying ? "分類中..." : "分類開始"}
           </button>
+          {isClassifying && (
+            <button
+              className="secondary-button"
+              onClick={() => jobIdRef.current && api.cancelJob(jobIdRef.current)}
+            >
+              中断
+            </button>
+          )}
           <button onClick={onClose} disabled={isClassifying}>
             閉じる
           </button>
@@ -1,7 +1,8 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke, Channel } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
 import { path } from "@tauri-apps/api";
+import { api, createJobId, JOB_CANCELLED_MESSAGE } from "./api";
 import "./TrainingDialog.css";
 
 interface TrainingDialogProps {
@@ -16,6 +17,9 @@ interface TrainingConfig {
   numEpochs: number;
   batchSize: number;
   learningRate: number;
+  baseModelPath: string; // 空文字: ランダムな初期値から学習
+  halfPrecision: boolean; // 重みを16bitで保存（ファイルサイズ約半分）
+  architecture: "standard" | "compact"; // compact: Global Average Pooling + 小さな全結合層
 }
 
 interface TrainingProgress {
@@ -27,6 +31,9 @@ interface TrainingProgress {
   val_accuracy: number;
   message: string;
   log_lines: string[];
+  sec_per_epoch: number; // 1エポックあたりの平均所要時間（秒）
+  elapsed_sec: number; // 経過時間（秒）
+  eta_sec: number | null; // 残り時間の推定（秒）
 }
 
 function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDialogProps) {
@@ -36,6 +43,9 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
     numEpochs: 50,
     batchSize: 8,  // input_analyzerと同じデフォルト値（GPUメモリ効率的）
     learningRate: 0.001,
+    baseModelPath: "",
+    halfPrecision: false,
+    architecture: "standard",
   });
 
   const [buttonLabels, setButtonLabels] = useState<string[]>([]);
@@ -45,6 +55,7 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
   const [trainingProgress, setTrainingProgress] = useState<TrainingProgress | null>(null);
   const [trainingMessage, setTrainingMessage] = useState<string>("");
   const [trainingComplete, setTrainingComplete] = useState(false);
+  const jobIdRef = useRef<string | null>(null); // 実行中の学習ジョブ（中断用）
 
   // 初回起動時にmodelsディレクトリをデフォルト出力先に設定
   useEffect(() => {
@@ -182,6 +193,23 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
     }
   };
 
+  const handleSelectBaseModel = async () => {
+    try {
+      const selected = await open({
+        directory: false,
+        multiple: false,
+        title: "続きから学習するモデルを選択",
+        filters: [{ name: "Model", extensions: ["gz"] }],
+      });
+
+      if (selected) {
+        setConfig({ ...config, baseModelPath: selected as string });
+      }
+    } catch (error) {
+      console.error("ファイル選択エラー:", error);
+    }
+  };
+
   const handleStartTraining = async () => {
     if (!config.dataDir || !config.outputDir) {
       alert("学習データディレクトリとモデル保存先を指定してください。");
@@ -241,6 +269,8 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
         setTrainingMessage(progress.message);
       };
 
+      const jobId = createJobId("training");
+      jobIdRef.current = jobId;
       const result = await invoke<string>("train_classification_model", {
         dataDir: config.dataDir,
         outputPath: outputPath,
@@ -249,15 +279,24 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
         learningRate: config.learningRate,
         buttonLabels: orderedLabels,
         useGpu: mlBackend === "wgpu",
+        baseModelPath: config.baseModelPath || null,
+        weightPrecision: config.halfPrecision ? "f16" : "f32",
+        architecture: config.architecture,
+        jobId,
         onProgress: progressChannel,
       });
 
       setTrainingMessage(result);
       setTrainingComplete(true);
     } catch (error) {
-      console.error("学習エラー:", error);
-      setTrainingMessage(`エラー: ${error}`);
+      if (error === JOB_CANCELLED_MESSAGE) {
+        setTrainingMessage("学習を中断しました（モデルは保存していません）");
+      } else {
+        console.error("学習エラー:", error);
+        setTrainingMessage(`エラー: ${error}`);
+      }
     } finally {
+      jobIdRef.current = null;
       setIsTraining(false);
     }
   };
@@ -315,6 +354,32 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
               )}
             </div>
 
+            <div className="config-item">
+              <label>既存モデルから学習（任意）</label>
+              <div className="input-with-button">
+                <input
+                  type="text"
+                  value={config.baseModelPath}
+                  readOnly
+                  placeholder="未指定（ランダムな初期値から学習）"
+                  disabled={isTraining}
+                />
+                <button onClick={handleSelectBaseModel} disabled={isTraining}>
+                  参照
+                </button>
+                {config.baseModelPath && (
+                  <button onClick={() => setConfig({ ...config, baseModelPath: "" })} disabled={isTraining}>
+                    解除
+                  </button>
+                )}
+              </div>
+              {config.baseModelPath && (
+                <div className="output-filename-hint">
+                  モデルの重みから学習を続けます（学習率は1/10になります。少ないエポック数で十分です）
+                </div>
+              )}
+            </div>
+
             <div className="config-row">
               <div className="config-item">
                 <label>エポック数</label>
@@ -354,6 +419,33 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
               </div>
             </div>
 
+            <div className="config-item">
+              <label>モデル構造</label>
+              <select
+                value={config.architecture}
+                onChange={(e) => setConfig({ ...config, architecture: e.target.value as TrainingConfig["architecture"] })}
+                disabled={isTraining || !!config.baseModelPath}
+              >
+                <option value="standard">標準</option>
+                <option value="compact">軽量（CPU推論が高速）</option>
+              </select>
+              {config.baseModelPath && (
+                <div className="output-filename-hint">既存モデルから学習する場合は元のモデルの構造を使います</div>
+              )}
+            </div>
+
+            <div className="config-item">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={config.halfPrecision}
+                  onChange={(e) => setConfig({ ...config, halfPrecision: e.target.checked })}
+                  disabled={isTraining}
+                />
+                重みを16bitで保存（モデルファイルが約半分のサイズになります）
+              </label>
+            </div>
+
             {buttonLabels.length > 0 && (
               <div className="config-item">
                 <label>
@@ -427,6 +519,14 @@ function TrainingDialog({ mlBackend, currentMappingPath, onClose }: TrainingDial
         </div>
 
         <div className="training-footer">
+          {isTraining && (
+            <button
+              className="btn-cancel"
+              onClick={() => jobIdRef.current && api.cancelJob(jobIdRef.current)}
+            >
+              中断
+            </button>
+          )}
           <button className="btn-cancel" onClick={onClose} disabled={isTraining}>
             {trainingComplete ? "閉じる" : "キャンセル"}
           </button>
@@ -2,6 +2,7 @@ import { useState, useRef, useEffect } from "react";
 import "./VideoAnalyzer.css";
 import { invoke } from "@tauri-apps/api/core";
 import { open, ask } from "@tauri-apps/plugin-dialog";
+import type { Thumbnail, VideoTransform } from "./types";
 
 interface VideoInfo {
   width: number;
@@ -13,7 +14,8 @@ interface VideoInfo {
 interface AnalysisRegion {
   x: number;
   y: number;
-  tile_size: number;
+  tile_size: number; // タイルの幅
+  tile_height: number;
   cols: number;
 }
 
@@ -40,9 +42,14 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
     x: 80,
     y: 400,
     tile_size: 80,
+    tile_height: 80,
     cols: 6,
   });
+  const [videoTransform, setVideoTransform] = useState<VideoTransform>("none"); // 回転・反転の補正
+  const [thumbnails, setThumbnails] = useState<Thumbnail[]>([]); // フレーム選択用のフィルムストリップ
   const [frameInterval, setFrameInterval] = useState<number>(10); // タイル抽出時の間引き間隔
+  const [rangeStartSec, setRangeStartSec] = useState<number | null>(null); // 学習データ収集の区間（null は先頭から）
+  const [rangeEndSec, setRangeEndSec] = useState<number | null>(null); // null は末尾まで
   const [tileOutputDir, setTileOutputDir] = useState<string>("");
   const [hasNeutralImage, setHasNeutralImage] = useState<boolean>(false); // ニュートラル画像ありフラグ
   const [isProcessing, setIsProcessing] = useState(false);
@@ -150,6 +157,7 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
           tile_height: number;
           columns: number;
           rows: number;
+          video_transform: VideoTransform;
         }>("load_analysis_region");
         
         // バックエンド形式からフロントエンド形式に変換
@@ -157,8 +165,10 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
           x: savedRegion.x,
           y: savedRegion.y,
           tile_size: savedRegion.tile_width,
+          tile_height: savedRegion.tile_height,
           cols: savedRegion.columns,
         });
+        setVideoTransform(savedRegion.video_transform);
         console.log("保存された解析範囲を読み込みました:", savedRegion);
       } catch (error) {
         console.log("保存された解析範囲がありません（初回起動）");
@@ -318,6 +328,12 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
         const info = await invoke<VideoInfo>("get_video_info", { videoPath: selected });
         console.log("動画情報取得完了:", info);
         setVideoInfo(info);
+
+        // フィルムストリップは表示を待たせないよう非同期で作成する
+        setThumbnails([]);
+        invoke<Thumbnail[]>("generate_thumbnails", { videoPath: selected, count: 10 })
+          .then(setThumbnails)
+          .catch((error) => console.warn("サムネイルの作成に失敗:", error));
         
         // 新しい動画を読み込む際は初回フラグをリセット
         isFirstLoadRef.current = true;
@@ -351,11 +367,12 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
         x: region.x,
         y: region.y,
         tile_width: region.tile_size,
-        tile_height: region.tile_size,
+        tile_height: region.tile_height,
         columns: region.cols,
         rows: 1,
         video_width: videoInfo?.width || 1920,
         video_height: videoInfo?.height || 1080,
+        video_transform: videoTransform,
       };
       await invoke("save_analysis_region", { region: regionToSave });
       alert("入力解析範囲を保存しました");
@@ -389,11 +406,12 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
         x: region.x,
         y: region.y,
         tile_width: region.tile_size,
-        tile_height: region.tile_size,
+        tile_height: region.tile_height,
         columns: region.cols,
         rows: 1,
         video_width: videoInfo?.width || 1920,
         video_height: videoInfo?.height || 1080,
+        video_transform: videoTransform,
       };
 
       console.log("[handleCollectTrainingData] collect_training_data呼び出し");
@@ -402,6 +420,8 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
         outputDir: tileOutputDir,
         frameInterval,
         region: regionToSend,
+        startSec: rangeStartSec,
+        endSec: rangeEndSec,
       });
 
       console.log("[handleCollectTrainingData] 収集完了:", result);
@@ -521,19 +541,19 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
 
     // 領域の矩形を描画（計算された幅・高さを使用）
     const regionWidth = region.tile_size * region.cols;
-    const regionHeight = region.tile_size;
+    const regionHeight = region.tile_height;
     ctx.strokeStyle = "#00ff00";
     ctx.lineWidth = 3 / currentZoom; // ズームレベルに応じて線の太さを調整
     ctx.strokeRect(region.x, region.y, regionWidth, regionHeight);
 
-    // タイルのグリッドを描画（1行のみ、正方形タイル）
+    // タイルのグリッドを描画（1行のみ）
     ctx.strokeStyle = "#ffff00";
     ctx.lineWidth = 1 / currentZoom; // ズームレベルに応じて線の太さを調整
 
     for (let col = 0; col < region.cols; col++) {
       const x = region.x + col * region.tile_size;
       const y = region.y;
-      ctx.strokeRect(x, y, region.tile_size, region.tile_size);
+      ctx.strokeRect(x, y, region.tile_size, region.tile_height);
     }
     
     ctx.restore();
@@ -802,6 +822,19 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
                     フレーム: {currentFrame} / {Math.floor(videoInfo.duration_sec * videoInfo.fps)}
                   </span>
                 </label>
+                {thumbnails.length > 0 && (
+                  <div style={{ gridColumn: "1 / -1", display: "flex", gap: "4px", overflowX: "auto" }}>
+                    {thumbnails.map((thumbnail) => (
+                      <img
+                        key={thumbnail.frame_number}
+                        src={thumbnail.data_url}
+                        title={`フレーム ${thumbnail.frame_number}`}
+                        onClick={() => handleSeekVideo(thumbnail.frame_number)}
+                        style={{ height: "60px", cursor: "pointer", border: "1px solid #555" }}
+                      />
+                    ))}
+                  </div>
+                )}
                 <label>
                   X座標: <input type="number" value={region.x} onChange={(e) => setRegion({...region, x: Number(e.target.value)})} />
                 </label>
@@ -809,12 +842,26 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
                   Y座標: <input type="number" value={region.y} onChange={(e) => setRegion({...region, y: Number(e.target.value)})} />
                 </label>
                 <label>
-                  タイルサイズ（正方形）: <input type="number" value={region.tile_size} onChange={(e) => setRegion({...region, tile_size: Number(e.target.value)})} />
-                  <span style={{ fontSize: "12px", color: "#ccc", marginLeft: "10px" }}>（幅 = {region.tile_size * region.cols}, 高さ = {region.tile_size}）</span>
+                  タイルの幅: <input type="number" value={region.tile_size} onChange={(e) => setRegion({...region, tile_size: Number(e.target.value)})} />
+                </label>
+                <label>
+                  タイルの高さ: <input type="number" value={region.tile_height} onChange={(e) => setRegion({...region, tile_height: Number(e.target.value)})} />
+                  <span style={{ fontSize: "12px", color: "#ccc", marginLeft: "10px" }}>（領域の幅 = {region.tile_size * region.cols}, 高さ = {region.tile_height}）</span>
                 </label>
                 <label>
                   列数（ボタン数）: <input type="number" value={region.cols} min={1} onChange={(e) => setRegion({...region, cols: Number(e.target.value)})} />
                 </label>
+                <label>
+                  回転・反転の補正:{" "}
+                  <select value={videoTransform} onChange={(e) => setVideoTransform(e.target.value as VideoTransform)}>
+                    <option value="none">なし</option>
+                    <option value="rotate90">時計回りに90度</option>
+                    <option value="rotate180">180度</option>
+                    <option value="rotate270">反時計回りに90度</option>
+                    <option value="flip_horizontal">左右反転</option>
+                  </select>
+                  <span style={{ fontSize: "12px", color: "#ccc", marginLeft: "10px" }}>（解析時に適用。座標は補正後の画像上の値）</span>
+                </label>
               </div>
 
               <div style={{ marginTop: "20px", padding: "10px", backgroundColor: "#2a2a2a", border: "1px solid #4a9eff", borderRadius: "5px" }}>
@@ -871,6 +918,26 @@ export default function VideoAnalyzer({ onClose, initialStep = "region-setup", c
                   <span style={{ fontSize: "12px", color: "#ccc", marginLeft: "10px" }}>（例: 30 = 30フレームごとに1枚抽出）</span>
                 </label>
 
+                <label>
+                  処理する区間（秒）:
+                  <input
+                    type="number"
+                    value={rangeStartSec ?? ""}
+                    placeholder="開始"
+                    onChange={(e) => setRangeStartSec(e.target.value === "" ? null : Number(e.target.value))}
+                    min={0}
+                  />
+                  〜
+                  <input
+                    type="number"
+                    value={rangeEndSec ?? ""}
+                    placeholder="終了"
+                    onChange={(e) => setRangeEndSec(e.target.value === "" ? null : Number(e.target.value))}
+                    min={0}
+                  />
+                  <span style={{ fontSize: "12px", color: "#ccc", marginLeft: "10px" }}>（空欄 = 先頭から／末尾まで）</span>
+                </label>
+
                 <label style={{ display: "flex", alignItems: "center", gap: "10px" }}>
                   <input 
                     type="checkbox" 
@@ -1,10 +1,72 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { ButtonMapping, ControllerType } from "./types";
+import type {
+  AccuracyReport,
+  AppSnapshot,
+  ButtonMapping,
+  CacheStats,
+  CaptureDeviceInfo,
+  ControllerLogEntry,
+  ControllerOutput,
+  ControllerType,
+  EditorSettings,
+  EditSessionChange,
+  EditSessionState,
+  FpsConversionReport,
+  GenerationSettings,
+  InputFrame,
+  InstanceConflict,
+  JobInfo,
+  MappingDiagnostic,
+  NotationSettings,
+  ObsSettings,
+  PlaybackRun,
+  PlaybackTimingReport,
+  PreviewInfo,
+  RemoteSettings,
+  SequenceLabel,
+  SequenceTimeline,
+  SequenceTransform,
+  StickMotion,
+  SyncMarkerSettings,
+  Thumbnail,
+  VideoBackend,
+} from "./types";
+
+// キャンセルされたジョブのエラーメッセージ（jobs.rs の CANCELLED_MESSAGE と同じ）
+export const JOB_CANCELLED_MESSAGE = "キャンセルされました";
+
+// cancelJob で指定するジョブIDを作成
+export const createJobId = (kind: string): string =>
+  `${kind}-${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
 
 export const api = {
   // Controller operations
-  async connectController(controllerType: ControllerType): Promise<string> {
-    return await invoke("connect_controller", { controllerType });
+  // backend を省略した場合は現在の出力先（デフォルトは "vigem"）に接続する
+  async connectController(controllerType: ControllerType, backend?: string): Promise<string> {
+    return await invoke("connect_controller", { controllerType, backend: backend ?? null });
+  },
+
+  async listOutputBackends(): Promise<string[]> {
+    return await invoke("list_output_backends");
+  },
+
+  // 実行中のジョブ（動画からの抽出など）を中断する。中断されたコマンドは JOB_CANCELLED_MESSAGE で失敗する
+  async cancelJob(jobId: string): Promise<boolean> {
+    return await invoke("cancel_job", { jobId });
+  },
+
+  // ジョブの一覧（新しい順、終了済みも含む）。ウィンドウを再読み込みした後の状態復元に使う
+  async listJobs(): Promise<JobInfo[]> {
+    return await invoke("list_jobs");
+  },
+
+  async getJobStatus(jobId: string): Promise<JobInfo> {
+    return await invoke("get_job_status", { jobId });
+  },
+
+  // 他の input_player が起動中・仮想コントローラーを使用中か
+  async checkInstanceConflict(): Promise<InstanceConflict> {
+    return await invoke("check_instance_conflict");
   },
 
   async disconnectController(): Promise<string> {
@@ -15,17 +77,56 @@ export const api = {
     return await invoke("is_controller_connected");
   },
 
+  // 設定ファイル（config.json）のパス（アプリ設定ディレクトリ内）
+  async getConfigPath(): Promise<string> {
+    return await invoke("get_config_path");
+  },
+
+  // コントローラー・再生・学習の状態をまとめて取得（後から開いたウィンドウの同期用）
+  async getAppSnapshot(): Promise<AppSnapshot> {
+    return await invoke("get_app_snapshot");
+  },
+
+  // 前回終了時の状態（シーケンス・マッピング・FPS・ループ・左右反転）を復元（起動時にも自動で実行される）
+  async restoreLastSession(): Promise<AppSnapshot> {
+    return await invoke("restore_last_session");
+  },
+
   // Playback operations
   async loadInputFile(path: string): Promise<number> {
     return await invoke("load_input_file", { path });
   },
 
+  // .rhai スクリプトを実行して生成したシーケンスを読み込む（on_loop はループ再生のたびに呼ばれる）
+  async loadScriptSequence(path: string): Promise<number> {
+    return await invoke("load_script_sequence", { path });
+  },
+
   async loadInputSequence(frames: any[]): Promise<number> {
     return await invoke("load_input_sequence", { frames });
   },
 
-  async startPlayback(): Promise<void> {
-    return await invoke("start_playback");
+  // holdLastInput: 指定時は今回の再生のみ終了時の入力保持設定を上書き
+  async startPlayback(holdLastInput?: boolean): Promise<void> {
+    return await invoke("start_playback", { holdLastInput });
+  },
+
+  // 最終行から先頭行へ逆順に再生
+  async startPlaybackReversed(holdLastInput?: boolean): Promise<void> {
+    return await invoke("start_playback_reversed", { holdLastInput });
+  },
+
+  // 指定時刻（UNIXエポックからのミリ秒）に再生を予約。省略時は外部トリガー待ち
+  async schedulePlayback(startAtUnixMs?: number, reversed?: boolean): Promise<void> {
+    return await invoke("schedule_playback", { startAtUnixMs, reversed });
+  },
+
+  async triggerArmedPlayback(): Promise<void> {
+    return await invoke("trigger_armed_playback");
+  },
+
+  async cancelScheduledPlayback(): Promise<void> {
+    return await invoke("cancel_scheduled_playback");
   },
 
   async stopPlayback(): Promise<void> {
@@ -48,6 +149,237 @@ export const api = {
     return await invoke("set_loop_playback", { loopEnabled: loop });
   },
 
+  // 同じボタンが連続するステップ間に挿入するリリースのフレーム数（0で無効）
+  async setReleaseGapFrames(frames: number): Promise<void> {
+    return await invoke("set_release_gap_frames", { frames });
+  },
+
+  async getReleaseGapFrames(): Promise<number> {
+    return await invoke("get_release_gap_frames");
+  },
+
+  // 各ステップのフレーム数の倍率（60FPS用シーケンスを30FPSのゲームで使う場合は 0.5）
+  async setDurationScale(scale: number): Promise<void> {
+    return await invoke("set_duration_scale", { scale });
+  },
+
+  async getDurationScale(): Promise<number> {
+    return await invoke("get_duration_scale");
+  },
+
+  // 入力遅延の補正（符号付きフレーム数、設定ファイルに保存される）
+  async setLatencyOffsetFrames(frames: number): Promise<void> {
+    return await invoke("set_latency_offset_frames", { frames });
+  },
+
+  async getLatencyOffsetFrames(): Promise<number> {
+    return await invoke("get_latency_offset_frames");
+  },
+
+  // 送信が予定よりこのミリ秒以上遅れたら "playback-step-late" イベントで通知（0で無効）
+  async setLateStepWarningMs(ms: number): Promise<void> {
+    return await invoke("set_late_step_warning_ms", { ms });
+  },
+
+  async getLateStepWarningMs(): Promise<number> {
+    return await invoke("get_late_step_warning_ms");
+  },
+
+  async getGenerationSettings(): Promise<GenerationSettings> {
+    return await invoke("get_generation_settings");
+  },
+
+  async setGenerationSettings(settings: GenerationSettings): Promise<void> {
+    return await invoke("set_generation_settings", { settings });
+  },
+
+  async getEditorSettings(): Promise<EditorSettings> {
+    return await invoke("get_editor_settings");
+  },
+
+  async setEditorSettings(settings: EditorSettings): Promise<void> {
+    return await invoke("set_editor_settings", { settings });
+  },
+
+  async getRemoteSettings(): Promise<RemoteSettings> {
+    return await invoke("get_remote_settings");
+  },
+
+  // 有効にしてトークンが未設定の場合は生成したトークンを含む設定を返す
+  async setRemoteSettings(settings: RemoteSettings): Promise<RemoteSettings> {
+    return await invoke("set_remote_settings", { settings });
+  },
+
+  async getObsSettings(): Promise<ObsSettings> {
+    return await invoke("get_obs_settings");
+  },
+
+  async setObsSettings(settings: ObsSettings): Promise<void> {
+    return await invoke("set_obs_settings", { settings });
+  },
+
+  async testObsConnection(settings: ObsSettings): Promise<string> {
+    return await invoke("test_obs_connection", { settings });
+  },
+
+  // テンキー表記（"236A 5B 2C" など）をシーケンスに変換
+  async parseNotation(text: string): Promise<InputFrame[]> {
+    return await invoke("parse_notation", { text });
+  },
+
+  // よく使うコマンド入力を生成（motion: qcf, qcb, dp, rdp, hcf, hcb, 360, charge_back, charge_down）
+  async generateMotion(motion: string, button: string | null, fps: number): Promise<InputFrame[]> {
+    return await invoke("generate_motion", { motion, button, fps });
+  },
+
+  // 左スティックの軌道を生成（radius: 倒す量 0.0〜1.0、省略時は1.0）
+  async generateStickMotion(motion: StickMotion, radius?: number): Promise<InputFrame[]> {
+    return await invoke("generate_stick_motion", { motion, radius });
+  },
+
+  // シーケンスCSVをテンキー表記に変換（共有用）
+  async sequenceToNotation(path: string): Promise<string> {
+    return await invoke("sequence_to_notation", { path });
+  },
+
+  // 抽出したシーケンスCSVを正解のCSVとフレーム単位で比較（抽出精度の確認用）
+  async compareSequencesAccuracy(extractedCsvPath: string, referenceCsvPath: string): Promise<AccuracyReport> {
+    return await invoke("compare_sequences_accuracy", { extractedCsvPath, referenceCsvPath });
+  },
+
+  async getNotationSettings(): Promise<NotationSettings> {
+    return await invoke("get_notation_settings");
+  },
+
+  async setNotationSettings(settings: NotationSettings): Promise<void> {
+    return await invoke("set_notation_settings", { settings });
+  },
+
+  // 直近のコントローラー送信ログ（path指定時はJSONファイルにも書き出す）
+  async dumpControllerLog(path?: string): Promise<ControllerLogEntry[]> {
+    return await invoke("dump_controller_log", { path });
+  },
+
+  async setControllerLogCapacity(capacity: number): Promise<void> {
+    return await invoke("set_controller_log_capacity", { capacity });
+  },
+
+  // 現在コントローラーに送信している状態
+  async getControllerOutput(): Promise<ControllerOutput> {
+    return await invoke("get_controller_output");
+  },
+
+  // 仮想コントローラーのセルフテスト（進捗は "controller-selftest-progress" イベント、戻り値は項目数）
+  async runControllerSelftest(holdMs?: number): Promise<number> {
+    return await invoke("run_controller_selftest", { holdMs: holdMs ?? null });
+  },
+
+  // 配信用の入力オーバーレイ（最前面・透過ウィンドウ）を開く
+  async openOverlayWindow(): Promise<void> {
+    return await invoke("open_overlay_window");
+  },
+
+  // シーケンスCSVを別のFPS用に変換して outPath に保存
+  async convertSequenceFps(
+    path: string,
+    fromFps: number,
+    toFps: number,
+    outPath: string,
+  ): Promise<FpsConversionReport> {
+    return await invoke("convert_sequence_fps", { path, fromFps, toFps, outPath });
+  },
+
+  // 一括変換（ずらし・倍率・左右反転・ボタン列のクリア）。outPath 省略時は 元の名前_transformed.csv に保存（戻り値はステップ数）
+  async transformSequence(
+    path: string,
+    operations: SequenceTransform[],
+    outPath?: string,
+  ): Promise<number> {
+    return await invoke("transform_sequence", { path, operations, outPath });
+  },
+
+  // 長時間の録画向けのバイナリ形式との相互変換（戻り値はステップ数）
+  // loadInputFile はバイナリ形式のファイルも自動判別して読み込む
+  async convertSequenceToBinary(path: string, outPath: string): Promise<number> {
+    return await invoke("convert_sequence_to_binary", { path, outPath });
+  },
+
+  async convertBinaryToCsv(path: string, outPath: string): Promise<number> {
+    return await invoke("convert_binary_to_csv", { path, outPath });
+  },
+
+  async getPlaybackHistory(): Promise<PlaybackRun[]> {
+    return await invoke("get_playback_history");
+  },
+
+  async clearPlaybackHistory(): Promise<void> {
+    return await invoke("clear_playback_history");
+  },
+
+  async exportPlaybackHistoryCsv(path: string): Promise<void> {
+    return await invoke("export_playback_history_csv", { path });
+  },
+
+  async exportPlaybackHistoryJson(path: string): Promise<void> {
+    return await invoke("export_playback_history_json", { path });
+  },
+
+  async setSyncMarkerSettings(settings: SyncMarkerSettings): Promise<void> {
+    return await invoke("set_sync_marker_settings", { settings });
+  },
+
+  async getSyncMarkerSettings(): Promise<SyncMarkerSettings> {
+    return await invoke("get_sync_marker_settings");
+  },
+
+  // オーバーレイモード: 再生中も手動入力をシーケンスの入力に重ねて送信
+  async setManualOverlay(enabled: boolean): Promise<void> {
+    return await invoke("set_manual_overlay", { enabled });
+  },
+
+  async getManualOverlay(): Promise<boolean> {
+    return await invoke("get_manual_overlay");
+  },
+
+  // 指定したステップ（行番号）に到達すると一時停止する。resumePlayback で続きから再生
+  async setStepBreakpoints(steps: number[]): Promise<void> {
+    return await invoke("set_step_breakpoints", { steps });
+  },
+
+  async getStepBreakpoints(): Promise<number[]> {
+    return await invoke("get_step_breakpoints");
+  },
+
+  async getSequenceLabels(): Promise<SequenceLabel[]> {
+    return await invoke("get_sequence_labels");
+  },
+
+  // ラベルのステップへ移動（停止中はそこから再生開始）。戻り値は行番号
+  async jumpToLabel(name: string): Promise<number> {
+    return await invoke("jump_to_label", { name });
+  },
+
+  async seekPlayback(step: number): Promise<number> {
+    return await invoke("seek_playback", { step });
+  },
+
+  // 再生せずに各ステップの開始時刻を計算（fps省略時は現在の設定）
+  async getSequenceTimeline(fps?: number): Promise<SequenceTimeline> {
+    return await invoke("get_sequence_timeline", { fps });
+  },
+
+  async getPlaybackTimingReport(): Promise<PlaybackTimingReport> {
+    return await invoke("get_playback_timing_report");
+  },
+
+  async setHoldLastInput(hold: boolean): Promise<void> {
+    return await invoke("set_hold_last_input", { hold });
+  },
+
+  async getHoldLastInput(): Promise<boolean> {
+    return await invoke("get_hold_last_input");
+  },
+
   async isPlaying(): Promise<boolean> {
     return await invoke("is_playing");
   },
@@ -65,6 +397,20 @@ export const api = {
     return await invoke("save_button_mapping", { path, mapping });
   },
 
+  // コントローラーの標準マッピングを生成して保存（既存ファイルは overwrite 指定時のみ上書き）
+  async generateDefaultMapping(
+    controllerType: ControllerType,
+    path: string,
+    overwrite?: boolean,
+  ): Promise<ButtonMapping> {
+    return await invoke("generate_default_mapping", { controllerType, path, overwrite });
+  },
+
+  // マッピングファイルを検証（行ごとの診断結果、問題が無ければ空）
+  async validateButtonMapping(path: string): Promise<MappingDiagnostic[]> {
+    return await invoke("validate_button_mapping", { path });
+  },
+
   // Manual input
   async updateManualInput(
     direction: number,
@@ -96,6 +442,43 @@ export const api = {
     return await invoke("save_frames_for_edit", { path, frames });
   },
 
+  // Edit session operations
+  async openEditSession(path: string): Promise<EditSessionState> {
+    return await invoke("open_edit_session", { path });
+  },
+
+  async insertFrames(path: string, index: number, frames: InputFrame[]): Promise<EditSessionChange> {
+    return await invoke("insert_frames", { path, index, frames });
+  },
+
+  async deleteFrames(path: string, start: number, end: number): Promise<EditSessionChange> {
+    return await invoke("delete_frames", { path, start, end });
+  },
+
+  async duplicateFrames(path: string, start: number, end: number): Promise<EditSessionChange> {
+    return await invoke("duplicate_frames", { path, start, end });
+  },
+
+  async updateFrame(path: string, index: number, frame: InputFrame): Promise<EditSessionChange> {
+    return await invoke("update_frame", { path, index, frame });
+  },
+
+  async undoEdit(path: string): Promise<EditSessionChange> {
+    return await invoke("undo_edit", { path });
+  },
+
+  async redoEdit(path: string): Promise<EditSessionChange> {
+    return await invoke("redo_edit", { path });
+  },
+
+  async saveEditSession(path: string): Promise<EditSessionChange> {
+    return await invoke("save_edit_session", { path });
+  },
+
+  async closeEditSession(path: string): Promise<void> {
+    return await invoke("close_edit_session", { path });
+  },
+
   async getCurrentPlayingFrame(): Promise<number> {
     return await invoke("get_current_playing_frame");
   },
@@ -108,4 +491,60 @@ export const api = {
   async reloadCurrentSequence(): Promise<void> {
     return await invoke("reload_current_sequence");
   },
+
+  // シーケンスキャッシュ（LRU、上限は設定ファイルに保存）
+  async getCacheStats(): Promise<CacheStats> {
+    return await invoke("get_cache_stats");
+  },
+
+  async clearCache(): Promise<void> {
+    return await invoke("clear_cache");
+  },
+
+  async setCacheMaxMegabytes(megabytes: number): Promise<void> {
+    return await invoke("set_cache_max_megabytes", { megabytes });
+  },
+
+  // フレーム抽出のバックエンド（設定ファイルに保存）
+  async getVideoBackend(): Promise<VideoBackend> {
+    return await invoke("get_video_backend");
+  },
+
+  async setVideoBackend(backend: VideoBackend): Promise<void> {
+    return await invoke("set_video_backend", { backend });
+  },
+
+  // ライブ解析（キャプチャデバイスの映像から入力を認識し "live-input" イベントで通知する）
+  async listCaptureDevices(): Promise<CaptureDeviceInfo[]> {
+    return await invoke("list_capture_devices");
+  },
+
+  // 解析を開始してジョブIDを返す（cancelJob(jobId) で停止、終了・失敗は "job-finished" イベント）
+  async startLiveAnalysis(deviceIndex: number, modelPath: string, useGpu: boolean, jobId?: string): Promise<string> {
+    return await invoke("start_live_analysis", { deviceIndex, modelPath, useGpu, jobId });
+  },
+
+  // 同じPCの画面をキャプチャして解析する（入力表示の領域はモデルのメタデータの座標。startLiveAnalysis と同じくジョブIDをすぐに返す）
+  async startScreenAnalysis(monitorIndex: number, modelPath: string, useGpu: boolean, jobId?: string): Promise<string> {
+    return await invoke("start_screen_analysis", { monitorIndex, modelPath, useGpu, jobId });
+  },
+
+  // タイムラインのスクラブ用プレビュー（一時停止中のパイプラインを保持してシークする。開いたウィンドウを閉じると解放される）
+  async openPreview(videoPath: string): Promise<PreviewInfo> {
+    return await invoke("open_preview", { videoPath });
+  },
+
+  // 動画全体から等間隔に count 枚のサムネイル（width の既定値は 160）
+  async generateThumbnails(videoPath: string, count: number, width?: number): Promise<Thumbnail[]> {
+    return await invoke("generate_thumbnails", { videoPath, count, width });
+  },
+
+  // 指定フレームの画像（PNGのdata URL）
+  async previewFrame(sessionId: string, frameNumber: number): Promise<string> {
+    return await invoke("preview_frame", { sessionId, frameNumber });
+  },
+
+  async closePreview(sessionId: string): Promise<void> {
+    return await invoke("close_preview", { sessionId });
+  },
 };
@@ -0,0 +1,9 @@
+import React from "react";
+import ReactDOM from "react-dom/client";
+import InputOverlay from "./InputOverlay";
+
+ReactDOM.createRoot(document.getElementById("overlay-root") as HTMLElement).render(
+  <React.StrictMode>
+    <InputOverlay />
+  </React.StrictMode>,
+);
@@ -10,6 +10,21 @@ export interface ButtonMapping {
   mapping: UserButton[]; // ボタンマッピング配列（この順番で画面表示される）
 }
 
+// ボタンマッピングの検証結果（1件）
+export interface MappingDiagnostic {
+  severity: "error" | "warning";
+  kind:
+    | "empty_user_button"
+    | "duplicate_user_button"
+    | "unknown_controller_button"
+    | "empty_mapping"
+    | "unused_controller_button"
+    | "shared_controller_button";
+  index: number; // mapping 内の行番号（0始まり）
+  user_button: string;
+  message: string;
+}
+
 export interface InputFrame {
   duration: number;
   direction: number;
@@ -20,6 +35,7 @@ export interface InputFrame {
   thumb_ry?: number; // 右スティック Y (-32768 to 32767)
   left_trigger?: number; // 左トリガー (0-255)
   right_trigger?: number; // 右トリガー (0-255)
+  extra_columns?: [string, string][]; // CSVの数値以外の値を持つ列（保存時にそのまま書き戻す）
 }
 
 export type ControllerType = "xbox" | "dualshock4";
@@ -29,9 +45,369 @@ export interface PlaybackProgress {
   total: number;
 }
 
+// "playback-step-changed" イベントのペイロード
+export interface PlaybackStepEvent {
+  step: number; // 現在出力中のステップ（行番号、0始まり）
+  cumulative_frame: number; // このステップ開始時点の累積フレーム数
+  total_steps: number; // 総ステップ数
+  total_frames: number; // シーケンス全体のフレーム数
+}
+
+// アプリ全体の状態（get_app_snapshot）
+export interface AppSnapshot {
+  controller_connected: boolean;
+  controller_type: ControllerType | null;
+  sequence_path: string | null; // 読み込み中のシーケンス
+  sequence_state: "NoSequence" | "Stopped" | "Playing" | "Paused";
+  current_step: number;
+  total_steps: number;
+  fps: number;
+  loop_playback: boolean;
+  invert_horizontal: boolean;
+  reversed: boolean; // 逆再生中
+  is_training: boolean;
+}
+
+// 大きなシーケンスの読み込み進捗（"sequence-load-progress"）
+export interface SequenceLoadProgressEvent {
+  path: string;
+  bytes_read: number;
+  total_bytes: number; // bytes_read === total_bytes で読み込み完了
+}
+
+// 監視中のファイルを再読み込みしたときのイベント（"file-reloaded"）
+export interface FileReloadedEvent {
+  kind: "mapping" | "sequence";
+  path: string; // 読み込み時に指定されたパス
+  error: string | null; // 再読み込みに失敗した場合のエラー
+}
+
+// 往復テスト（録画済みの再生から再抽出）のステップ単位の比較結果
+export interface RoundtripStepResult {
+  step: number;
+  start_frame: number; // 元シーケンスでの開始フレーム
+  end_frame: number; // 元シーケンスでの終了フレーム（含まない）
+  mismatched_frames: number; // 入力が食い違ったフレーム数
+  equal: boolean;
+}
+
+export interface RoundtripReport {
+  extracted_csv_path: string;
+  expected_steps: number;
+  equal_steps: number;
+  steps: RoundtripStepResult[];
+  accuracy: AccuracyReport; // フレーム単位の比較結果
+}
+
+// 抽出したシーケンスと正解のシーケンスのフレーム単位の精度比較
+export interface ButtonAccuracy {
+  button: string;
+  true_positive: number;
+  false_positive: number; // 抽出側だけ押されていたフレーム数
+  false_negative: number; // 正解側だけ押されていたフレーム数
+  precision: number | null; // 抽出側で一度も押されていなければ null
+  recall: number | null; // 正解側で一度も押されていなければ null
+}
+
+export interface MismatchRange {
+  start_frame: number; // 正解側のフレーム番号
+  end_frame: number; // この値は含まない
+  expected: string; // 区間の先頭での正解側の入力（例: "6A+B"）
+  actual: string;
+}
+
+export interface AccuracyReport {
+  compared_frames: number;
+  offset: number; // 抽出側のフレーム番号 = 正解側のフレーム番号 + offset
+  direction_accuracy: number;
+  frame_accuracy: number; // 方向・ボタンがすべて一致したフレームの割合
+  buttons: ButtonAccuracy[];
+  mismatches: MismatchRange[];
+}
+
+// 再生開始時の同期マーカー設定
+export interface SyncMarkerSettings {
+  enabled: boolean;
+  button: string; // コントローラーのボタン名（"button10" = START）
+  frames: number; // 押し続けるフレーム数（同じフレーム数だけ離してからシーケンス開始）
+}
+
+// 再生タイミングの計測結果（ジッタ = 実際の送信時刻 - 予定時刻）
+export interface PlaybackTimingReport {
+  samples: number; // 計測した送信回数
+  min_jitter_ms: number;
+  max_jitter_ms: number;
+  mean_jitter_ms: number;
+  late_steps: number; // 1フレーム以上遅れて送信したステップ数
+  missed_frames: number; // 遅延により取りこぼしたフレーム数の合計
+  fps: number;
+}
+
+// 送信の遅れの警告（"playback-step-late"）
+export interface LateStepEvent {
+  step: number; // 遅れて送信したステップ（行番号）
+  late_ms: number; // 予定時刻からの遅れ
+  threshold_ms: number; // 警告のしきい値
+}
+
+// ブレークポイント到達イベント（"playback-breakpoint-hit"）
+export interface BreakpointHitEvent {
+  step: number; // 一時停止したステップ（行番号、まだ送信していない）
+  cumulative_frame: number;
+}
+
+// CSVの `label:` 行で付けたステップの名前
+export interface SequenceLabel {
+  name: string;
+  step: number; // ラベルの直後のステップ（行番号、0始まり）
+}
+
+// 再生履歴（1回分の再生記録）
+export interface PlaybackRun {
+  file: string | null;
+  started_at: string; // ISO8601
+  duration_sec: number;
+  loops_completed: number; // 最後まで再生し終えた回数
+  last_step: number; // 終了時点の再生済みステップ数
+  total_steps: number;
+  reversed: boolean;
+  stopped_early: boolean; // 最後まで再生する前に停止したか
+}
+
+// シーケンスのタイムライン（再生せずに計算した各ステップの開始時刻）
+export interface TimelineStep {
+  step: number; // 行番号（0始まり）
+  start_frame: number; // 再生開始からの累積フレーム数
+  start_ms: number;
+  duration_frames: number;
+}
+
+export interface SequenceTimeline {
+  fps: number;
+  total_frames: number; // 同期マーカー・挿入リリースを含む総フレーム数
+  total_duration_ms: number;
+  steps: TimelineStep[];
+}
+
+// 動画から生成するシーケンスの後処理設定
+export interface GenerationSettings {
+  trim_dead_time: boolean; // 先頭・末尾の無入力ステップを取り除く
+  max_neutral_gap_frames: number; // 途中の無入力ステップの最大フレーム数（0で制限なし）
+  use_mapping_column_order: boolean; // ボタン列をボタンマッピングのシーケンス順に並べ替える
+  column_renames: Record<string, string>; // 出力時の列名の変更（モデルのボタンラベル → 列名）
+  skip_duplicate_frames: boolean; // 入力表示が直前と完全に同じ画像のフレームは分類を省略し、直前のステップの持続フレーム数に加える
+  smoothing_window_frames: number; // 各フレームを中心としたこのフレーム数（前後合わせた窓の大きさ）の多数決で誤認識を平滑化する（1以下で無効）
+  min_step_frames: number; // これより短いステップを直前のステップに統合する（0で無効）
+  frame_count_digits_dir: string | null; // 継続フレーム数の列を読み取る数字のテンプレート（0.png〜9.png）のフォルダ
+  frame_count_mode: FrameCountMode; // 読み取った継続フレーム数の使い方
+  frame_count_display_max: number; // 継続フレーム数の表示の上限（この値以上は数えたフレーム数を使う。0で上限なし）
+  history_rows: number; // 複数行解析で読む行数（最下行を含む。0・1 は最下行だけを毎フレーム解析）
+  history_keyframe_interval: number; // 複数行解析で全行を読むキーフレームの間隔（0 の場合は 行数 - 1）
+}
+
+// シーケンスキャッシュの使用状況
+export interface CacheStats {
+  entries: number;
+  total_bytes: number; // 推定メモリ使用量
+  max_bytes: number;
+  hits: number;
+  misses: number;
+  evictions: number; // 上限を超えたため破棄した数
+}
+
+// エディタの保存設定
+export interface EditorSettings {
+  backup_count: number; // 上書き保存時に残すバックアップ（<ファイル名>.1.bak 〜）の数（0でバックアップしない）
+}
+
+// リモート操作サーバーの設定（remote feature でビルドした場合のみ有効、次回起動時に反映）
+export interface RemoteSettings {
+  enabled: boolean;
+  bind_address: string; // 別のPCから操作する場合は "0.0.0.0" など
+  port: number;
+  token: string | null; // 必須の認証トークン（"Authorization: Bearer <token>" か "?token=<token>"）。有効にしたときに未設定なら生成される
+}
+
+// OBS の録画連携の設定（obs-websocket v5）
+export interface ObsSettings {
+  enabled: boolean; // 再生の開始・停止に合わせて録画を開始・停止する
+  url: string;
+  password: string | null;
+  name_after_sequence: boolean; // 録画ファイル名をシーケンス名にする
+}
+
+// 左スティックの軌道（角度は右が0°、反時計回りが正）
+export type StickMotion =
+  | { kind: "rotation"; start_angle: number; degrees: number; frames: number } // 1フレームずつ回転
+  | { kind: "wiggle"; angle: number; count: number; hold_frames: number }; // angle と反対方向を交互に倒す
+
+// シーケンスの一括変換の操作
+export type SequenceTransform =
+  | { op: "shift"; frames: number } // 全体を N フレーム遅らせる（負の値なら先頭を削って早める）
+  | { op: "scale"; factor: number } // 各ステップのフレーム数を倍率で変換
+  | { op: "mirror" } // 左右反転（4↔6, 1↔3, 7↔9）
+  | { op: "clear_button"; button: string }; // ボタン列を全ステップで離した状態にする
+
+// バックエンドの編集セッションを開いたときの状態
+export interface EditSessionState {
+  path: string;
+  frames: InputFrame[];
+  revision: number; // 変更のたびに増える
+  can_undo: boolean;
+  can_redo: boolean;
+  dirty: boolean; // 保存した内容から変更があるか
+}
+
+// 行の置き換え（index から removed 行を取り除き、その位置に inserted を挿入する）
+export interface FrameSplice {
+  index: number;
+  removed: number;
+  inserted: InputFrame[];
+}
+
+// 編集セッションの操作による変更（各操作の戻り値、"edit-session-changed" イベントのペイロード）
+export interface EditSessionChange {
+  path: string;
+  splice: FrameSplice | null; // 内容が変わらなかった操作では null
+  revision: number;
+  can_undo: boolean;
+  can_redo: boolean;
+  dirty: boolean;
+}
+
+// テンキー表記の読み込み設定
+export interface NotationSettings {
+  motion_frames: number; // コマンドの途中の方向1つあたりのフレーム数
+  press_frames: number; // 最後の方向とボタンを押すステップのフレーム数
+  gap_frames: number; // 技と技の間に入れる無入力のフレーム数（0で挿入しない）
+  motion_overrides: Record<string, number>; // コマンドごとの途中の方向のフレーム数（"623" → 3 など）
+}
+
+// コントローラーへの送信ログ（1回分）
+export interface ControllerLogEntry {
+  unix_ms: number;
+  direction: number; // テンキー表記（左右反転適用前）
+  invert_horizontal: boolean;
+  buttons: string[]; // 押されているボタン名
+  buttons_raw: number; // 送信したXInputボタンのビット列
+  left_trigger: number;
+  right_trigger: number;
+  sent: boolean; // ドライバへの送信に成功したか
+}
+
+// 他の input_player との競合状態（check_instance_conflict）
+export interface InstanceConflict {
+  another_instance: boolean; // このアプリが他にも起動している
+  target_in_use: boolean; // 他のプロセスが仮想コントローラーを接続中
+}
+
+// 学習・抽出・分類・ライブ解析のジョブ（list_jobs / get_job_status と "job-started" / "job-finished" のペイロード）
+export type JobKind = "training" | "extraction" | "classification" | "live_analysis";
+export type JobStatus = "running" | "completed" | "failed" | "cancelled";
+
+export interface JobInfo {
+  id: string;
+  kind: JobKind;
+  label: string; // 処理対象（動画パスなど）
+  status: JobStatus;
+  started_at: string; // RFC3339
+  finished_at: string | null;
+  current: number; // 進捗（フレーム数・エポック数など）
+  total: number; // 不明な場合は 0
+  message: string; // 最新の進捗メッセージ
+  error: string | null; // 失敗時のエラーメッセージ
+}
+
+// セルフテストの進捗（"controller-selftest-progress" のペイロード）
+export interface SelfTestProgressEvent {
+  index: number; // 実行中の項目（0始まり。index === total で完了）
+  total: number;
+  label: string; // "button1 (LP)"、"方向 6" など
+}
+
+// 現在コントローラーに送信している状態（"controller-output" イベント / get_controller_output）
+export interface ControllerOutput {
+  direction: number; // テンキー表記（左右反転適用後）
+  buttons: string[]; // 押されているボタン名（マッピング適用後の button1〜button12）
+  thumb_lx: number;
+  thumb_ly: number;
+  thumb_rx: number;
+  thumb_ry: number;
+  left_trigger: number;
+  right_trigger: number;
+  invert_horizontal: boolean;
+}
+
+// シーケンスのFPS変換結果
+export interface FpsConversionStep {
+  step: number;
+  original_frames: number;
+  converted_frames: number;
+  exact_frames: number; // 丸める前のフレーム数
+  rounding_error: number; // このステップの丸め誤差（フレーム）
+  cumulative_error: number; // このステップ終了時点の累積誤差（フレーム）
+}
+
+export interface FpsConversionReport {
+  from_fps: number;
+  to_fps: number;
+  original_total_frames: number;
+  converted_total_frames: number;
+  max_abs_cumulative_error: number;
+  steps: FpsConversionStep[];
+}
+
 // シーケンススロットのデータ構造
 export interface SequenceSlot {
   path: string; // ファイルパス
   frames: InputFrame[]; // メモリに展開されたフレームデータ
   compatible: boolean; // マッピングとの互換性
 }
+
+// 動画プレビューのセッション（open_preview の戻り値）
+export interface PreviewInfo {
+  session_id: string;
+  width: number;
+  height: number;
+  fps: number;
+  total_frames: number; // 動画の長さから算出した概算
+}
+
+// キャプチャデバイス（list_capture_devices）
+export interface CaptureDeviceInfo {
+  index: number;
+  name: string;
+}
+
+// ライブ解析で認識した入力（"live-input" のペイロード）
+export interface LiveInputEvent {
+  job_id: string;
+  frame: number; // 解析開始からのフレーム番号
+  direction: number;
+  buttons: Record<string, number>; // ボタン名 -> 状態 (0 or 1)
+  previous_duration: number; // 直前の入力状態が続いたフレーム数（最初の入力は 0）
+}
+
+// 入力履歴の抽出中に分類したフレーム（"extraction-frame" のペイロード）
+export interface ExtractionFrameEvent {
+  job_id: string;
+  frame: number; // 動画の先頭から数えたフレーム番号
+  direction: number;
+  buttons: Record<string, number>; // ボタン名 -> 状態 (0 or 1)
+  confidences: number[]; // タイルごとの確信度（0〜1、左の列から順）
+}
+
+// 解析範囲の設定画面のサムネイル（generate_thumbnails）
+export interface Thumbnail {
+  frame_number: number;
+  data_url: string; // PNGのdata URL
+}
+
+// 動画のフレーム抽出に使うバックエンド（"ffmpeg" は ffmpeg feature でビルドした場合のみ）
+export type VideoBackend = "gstreamer" | "ffmpeg";
+
+// 録画した動画の回転・反転の補正（解析範囲と一緒に保存する。rotate90 は時計回り）
+export type VideoTransform = "none" | "rotate90" | "rotate180" | "rotate270" | "flip_horizontal";
+
+// 継続フレーム数の使い方（cross_check: 数えたフレーム数を使い不一致をログに出す / recognized: 読み取った値を使う）
+export type FrameCountMode = "cross_check" | "recognized";
@@ -9,12 +9,13 @@ const host = process.env.TAURI_DEV_HOST;
 export default defineConfig(async () => ({
   plugins: [react()],
 
-  // Multiple entry points for main window and editor window
+  // Multiple entry points for main window, editor window and input overlay
   build: {
     rollupOptions: {
       input: {
         main: resolve(__dirname, "index.html"),
         editor: resolve(__dirname, "editor.html"),
+        overlay: resolve(__dirname, "overlay.html"),
       },
     },
   },